@@ -46,9 +46,15 @@ pub fn run<I, T, E>(args: I, exit: E, version: VersionInfo) -> error::Result<()>
 			Ok(new_full_start!(config).0), load_spec, exit),
 		ParseAndPrepare::CheckBlock(cmd) => cmd.run_with_builder(|config: Config<_>|
 			Ok(new_full_start!(config).0), load_spec, exit),
+		ParseAndPrepare::ExportState(cmd) => cmd.run_with_builder(|config: Config<_>|
+			Ok(new_full_start!(config).0), load_spec),
+		ParseAndPrepare::DryRunUpgrade(cmd) => cmd.run_with_builder(|config: Config<_>|
+			Ok(new_full_start!(config).0), load_spec),
 		ParseAndPrepare::PurgeChain(cmd) => cmd.run(load_spec),
 		ParseAndPrepare::RevertChain(cmd) => cmd.run_with_builder(|config: Config<_>|
 			Ok(new_full_start!(config).0), load_spec),
+		ParseAndPrepare::Benchmark(_) =>
+			Err(error::Error::Other("Benchmarking is not supported by this node.".into())),
 		ParseAndPrepare::CustomCommand(_) => Ok(())
 	}?;
 
@@ -22,7 +22,7 @@ use serde::{Serialize, Deserialize};
 use node_runtime::{
 	AuthorityDiscoveryConfig, BabeConfig, BalancesConfig, ContractsConfig, CouncilConfig, DemocracyConfig,
 	GrandpaConfig, ImOnlineConfig, IndicesConfig, SessionConfig, SessionKeys, StakerStatus, StakingConfig,
-	SocietyConfig, SudoConfig, SystemConfig, TechnicalCommitteeConfig, WASM_BINARY,
+	SocietyConfig, SudoConfig, SystemConfig, TechnicalCommitteeConfig, VestingConfig, WASM_BINARY,
 };
 use node_runtime::Block;
 use node_runtime::constants::currency::*;
@@ -237,7 +237,6 @@ pub fn testnet_genesis(
 				.map(|k| (k, ENDOWMENT))
 				.chain(initial_authorities.iter().map(|x| (x.0.clone(), STASH)))
 				.collect(),
-			vesting: vec![],
 		}),
 		pallet_indices: Some(IndicesConfig {
 			ids: endowed_accounts.iter().cloned()
@@ -299,7 +298,10 @@ pub fn testnet_genesis(
 			members: endowed_accounts[0..3].to_vec(),
 			pot: 0,
 			max_members: 999,
-		})
+		}),
+		pallet_vesting: Some(VestingConfig {
+			vesting: vec![],
+		}),
 	}
 }
 
@@ -130,9 +130,21 @@ pub fn run<I, T, E>(args: I, exit: E, version: sc_cli::VersionInfo) -> error::Re
 			Ok(new_full_start!(config).0), load_spec, exit),
 		ParseAndPrepare::CheckBlock(cmd) => cmd.run_with_builder(|config: Config<_, _>|
 			Ok(new_full_start!(config).0), load_spec, exit),
+		ParseAndPrepare::ExportState(cmd) => cmd.run_with_builder(|config: Config<_, _>|
+			Ok(new_full_start!(config).0), load_spec),
+		ParseAndPrepare::DryRunUpgrade(cmd) => cmd.run_with_builder(|config: Config<_, _>|
+			Ok(new_full_start!(config).0), load_spec),
 		ParseAndPrepare::PurgeChain(cmd) => cmd.run(load_spec),
 		ParseAndPrepare::RevertChain(cmd) => cmd.run_with_builder(|config: Config<_, _>|
 			Ok(new_full_start!(config).0), load_spec),
+		ParseAndPrepare::Benchmark(cmd) => if cfg!(feature = "runtime-benchmarks") {
+			cmd.run::<_, _, _, node_primitives::Block, node_executor::Executor>(load_spec)
+		} else {
+			Err(error::Error::Other(
+				"Benchmarking wasn't enabled when building the node. \
+				You can enable it with `--features runtime-benchmarks`.".into()
+			))
+		},
 		ParseAndPrepare::CustomCommand(CustomSubcommands::Factory(cli_args)) => {
 			let mut config: Config<_, _> = sc_cli::create_config_with_db_path(
 				load_spec,
@@ -124,12 +124,14 @@ macro_rules! new_full {
 			name,
 			disable_grandpa,
 			sentry_nodes,
+			prometheus_registry,
 		) = (
 			$config.roles.is_authority(),
 			$config.force_authoring,
 			$config.name.clone(),
 			$config.disable_grandpa,
 			$config.network.sentry_nodes.clone(),
+			$config.prometheus_registry().cloned(),
 		);
 
 		// sentry nodes announce themselves as authorities to the network
@@ -235,6 +237,7 @@ macro_rules! new_full {
 					telemetry_on_connect: Some(service.telemetry_on_connect_stream()),
 					voting_rule: grandpa::VotingRulesBuilder::default().build(),
 					executor: service.spawn_task_handle(),
+					prometheus_registry,
 				};
 				// the GRANDPA voter task is considered infallible, i.e.
 				// if it fails we take down the service with it.
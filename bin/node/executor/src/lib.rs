@@ -22,8 +22,19 @@ use sc_executor::native_executor_instance;
 
 // Declare an instance of the native executor named `Executor`. Include the wasm binary as the
 // equivalent wasm code.
+#[cfg(not(feature = "runtime-benchmarks"))]
 native_executor_instance!(
 	pub Executor,
 	node_runtime::api::dispatch,
 	node_runtime::native_version
 );
+
+// The benchmarking host functions are only registered when the node is compiled for
+// benchmarking, a production node must never be able to wipe or commit its state.
+#[cfg(feature = "runtime-benchmarks")]
+native_executor_instance!(
+	pub Executor,
+	node_runtime::api::dispatch,
+	node_runtime::native_version,
+	frame_benchmarking::benchmarking::HostFunctions,
+);
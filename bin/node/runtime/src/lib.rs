@@ -20,34 +20,38 @@
 // `construct_runtime!` does a lot of recursion and requires us to increase the limit to 256.
 #![recursion_limit="256"]
 
-use sp_std::prelude::*;
+use sp_std::{prelude::*, convert::TryFrom};
 use frame_support::{
 	construct_runtime, parameter_types, debug,
 	weights::Weight,
-	traits::{SplitTwoWays, Currency, Randomness},
+	traits::{SplitTwoWays, Currency, Randomness, KeyOwnerProofSystem, InstanceFilter},
 };
+use codec::{Encode, Decode};
 use sp_core::u32_trait::{_1, _2, _3, _4};
 use node_primitives::{AccountId, AccountIndex, Balance, BlockNumber, Hash, Index, Moment, Signature};
 use sp_api::impl_runtime_apis;
 use sp_runtime::{
-	Permill, Perbill, Percent, ApplyExtrinsicResult, impl_opaque_keys, generic, create_runtime_str
+	Permill, Perbill, Percent, ApplyExtrinsicResult, KeyTypeId, impl_opaque_keys, generic,
+	create_runtime_str, RuntimeDebug,
 };
 use sp_runtime::curve::PiecewiseLinear;
 use sp_runtime::transaction_validity::TransactionValidity;
 use sp_runtime::traits::{
 	self, BlakeTwo256, Block as BlockT, StaticLookup, SaturatedConversion,
-	OpaqueKeys,
+	OpaqueKeys, NumberFor, ConvertInto,
 };
 use sp_version::RuntimeVersion;
 #[cfg(any(feature = "std", test))]
 use sp_version::NativeVersion;
 use sp_core::OpaqueMetadata;
-use pallet_grandpa::AuthorityList as GrandpaAuthorityList;
+use pallet_grandpa::{AuthorityId as GrandpaId, AuthorityList as GrandpaAuthorityList};
 use pallet_grandpa::fg_primitives;
 use pallet_im_online::sr25519::{AuthorityId as ImOnlineId};
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
 use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
-use pallet_contracts_rpc_runtime_api::ContractExecResult;
+use pallet_contracts_rpc_runtime_api::{
+	ContractCallResult, ContractExecResult, ContractInstantiateResult,
+};
 use frame_system::offchain::TransactionSubmitter;
 use sp_inherents::{InherentData, CheckInherentsResult};
 
@@ -80,8 +84,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to equal spec_version. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 204,
-	impl_version: 204,
+	spec_version: 220,
+	impl_version: 220,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -147,6 +151,133 @@ impl pallet_utility::Trait for Runtime {
 	type MaxSignatories = MaxSignatories;
 }
 
+parameter_types! {
+	// One storage item; value is size 4+4+16+32 bytes = 56 bytes.
+	pub const ProxyDepositBase: Balance = 30 * CENTS;
+	// Additional storage item size of 33 bytes.
+	pub const ProxyDepositFactor: Balance = 5 * CENTS;
+	pub const MaxProxies: u16 = 32;
+	// One storage item; value is size 16 bytes.
+	pub const AnnouncementDepositBase: Balance = 10 * CENTS;
+	// Additional storage item size of 68 bytes.
+	pub const AnnouncementDepositFactor: Balance = 10 * CENTS;
+	pub const MaxPending: u32 = 32;
+}
+
+/// The type used to represent the kinds of proxying allowed.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+pub enum ProxyType {
+	Any,
+	NonTransfer,
+	Governance,
+	Staking,
+}
+impl Default for ProxyType { fn default() -> Self { Self::Any } }
+impl InstanceFilter<Call> for ProxyType {
+	fn filter(&self, c: &Call) -> bool {
+		match self {
+			ProxyType::Any => true,
+			// Only calls which cannot move funds out of the account are allowed. Anything that
+			// transfers value or dispatches further calls (e.g. `Utility`, `Proxy`, `Sudo`,
+			// `Recovery`) is denied.
+			ProxyType::NonTransfer => match c {
+				Call::Staking(pallet_staking::Call::bond(..))
+					| Call::Staking(pallet_staking::Call::bond_extra(..))
+					| Call::Staking(pallet_staking::Call::unbond(..))
+					| Call::Staking(pallet_staking::Call::rebond(..))
+					| Call::Staking(pallet_staking::Call::withdraw_unbonded(..)) => false,
+				Call::System(..) | Call::Babe(..) | Call::Timestamp(..) | Call::Authorship(..)
+					| Call::Staking(..) | Call::Session(..) | Call::Democracy(..) | Call::Council(..)
+					| Call::TechnicalCommittee(..) | Call::Elections(..)
+					| Call::TechnicalMembership(..) | Call::FinalityTracker(..) | Call::Grandpa(..)
+					| Call::Treasury(..) | Call::ImOnline(..) | Call::Identity(..)
+					| Call::Vesting(pallet_vesting::Call::vest(..))
+					| Call::Vesting(pallet_vesting::Call::vest_other(..)) => true,
+				_ => false,
+			},
+			ProxyType::Governance => match c {
+				Call::Democracy(..) | Call::Council(..) | Call::TechnicalCommittee(..)
+					| Call::Elections(..) | Call::Treasury(..) => true,
+				_ => false,
+			},
+			ProxyType::Staking => match c {
+				Call::Staking(..) | Call::Session(..) => true,
+				_ => false,
+			},
+		}
+	}
+	fn is_superset(&self, o: &Self) -> bool {
+		match (self, o) {
+			(x, y) if x == y => true,
+			(ProxyType::Any, _) => true,
+			(_, ProxyType::Any) => false,
+			// `NonTransfer` allows all governance calls, but not the staking calls which bond funds.
+			(ProxyType::NonTransfer, ProxyType::Governance) => true,
+			_ => false,
+		}
+	}
+}
+
+impl pallet_proxy::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type Currency = Balances;
+	type ProxyType = ProxyType;
+	type ProxyDepositBase = ProxyDepositBase;
+	type ProxyDepositFactor = ProxyDepositFactor;
+	type MaxProxies = MaxProxies;
+	type MaxPending = MaxPending;
+	type AnnouncementDepositBase = AnnouncementDepositBase;
+	type AnnouncementDepositFactor = AnnouncementDepositFactor;
+}
+
+parameter_types! {
+	pub const MaximumSchedulerWeight: Weight = Perbill::from_percent(80) * MaximumBlockWeight::get();
+}
+
+/// The origins that calls can be scheduled by.
+///
+/// Unlike `Origin` this type is encodable, so that it can be stored with the scheduled call.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum PalletsOrigin {
+	System(frame_system::Origin<Runtime>),
+	Council(pallet_collective::Origin<Runtime, CouncilCollective>),
+	TechnicalCommittee(pallet_collective::Origin<Runtime, TechnicalCollective>),
+}
+impl From<frame_system::Origin<Runtime>> for PalletsOrigin {
+	fn from(origin: frame_system::Origin<Runtime>) -> Self {
+		PalletsOrigin::System(origin)
+	}
+}
+impl TryFrom<Origin> for PalletsOrigin {
+	type Error = Origin;
+	fn try_from(origin: Origin) -> Result<Self, Origin> {
+		match origin {
+			Origin::system(origin) => Ok(PalletsOrigin::System(origin)),
+			Origin::pallet_collective_Instance1(origin) => Ok(PalletsOrigin::Council(origin)),
+			Origin::pallet_collective_Instance2(origin) => Ok(PalletsOrigin::TechnicalCommittee(origin)),
+			origin => Err(origin),
+		}
+	}
+}
+impl From<PalletsOrigin> for Origin {
+	fn from(origin: PalletsOrigin) -> Self {
+		match origin {
+			PalletsOrigin::System(origin) => origin.into(),
+			PalletsOrigin::Council(origin) => origin.into(),
+			PalletsOrigin::TechnicalCommittee(origin) => origin.into(),
+		}
+	}
+}
+
+impl pallet_scheduler::Trait for Runtime {
+	type Event = Event;
+	type PalletsOrigin = PalletsOrigin;
+	type Call = Call;
+	type MaximumWeight = MaximumSchedulerWeight;
+	type ScheduleOrigin = frame_system::EnsureRoot<AccountId>;
+}
+
 parameter_types! {
 	pub const EpochDuration: u64 = EPOCH_DURATION_IN_SLOTS;
 	pub const ExpectedBlockTime: Moment = MILLISECS_PER_BLOCK;
@@ -156,6 +287,25 @@ impl pallet_babe::Trait for Runtime {
 	type EpochDuration = EpochDuration;
 	type ExpectedBlockTime = ExpectedBlockTime;
 	type EpochChangeTrigger = pallet_babe::ExternalTrigger;
+	type Call = Call;
+
+	type KeyOwnerProofSystem = pallet_session::historical::Module<Runtime>;
+
+	type KeyOwnerProof = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::Proof;
+
+	type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		pallet_babe::AuthorityId,
+	)>>::IdentificationTuple;
+
+	type HandleEquivocation = pallet_babe::EquivocationHandler<
+		Self::KeyOwnerIdentification,
+		Offences,
+		SubmitTransaction,
+	>;
 }
 
 impl pallet_indices::Trait for Runtime {
@@ -184,6 +334,19 @@ impl pallet_balances::Trait for Runtime {
 	type CreationFee = CreationFee;
 }
 
+parameter_types! {
+	pub const MinVestedTransfer: Balance = 100 * DOLLARS;
+	pub const MaxVestingSchedules: u32 = 28;
+}
+
+impl pallet_vesting::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BlockNumberToBalance = ConvertInto;
+	type MinVestedTransfer = MinVestedTransfer;
+	type MaxVestingSchedules = MaxVestingSchedules;
+}
+
 parameter_types! {
 	pub const TransactionBaseFee: Balance = 1 * CENTS;
 	pub const TransactionByteFee: Balance = 10 * MILLICENTS;
@@ -268,6 +431,8 @@ parameter_types! {
 	pub const BondingDuration: pallet_staking::EraIndex = 24 * 28;
 	pub const SlashDeferDuration: pallet_staking::EraIndex = 24 * 7; // 1/4 the bonding duration.
 	pub const RewardCurve: &'static PiecewiseLinear<'static> = &REWARD_CURVE;
+	pub const MaxIterations: u32 = 10;
+	pub const MaxSubmissions: u32 = 16;
 }
 
 impl pallet_staking::Trait for Runtime {
@@ -285,6 +450,11 @@ impl pallet_staking::Trait for Runtime {
 	type SlashCancelOrigin = pallet_collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
 	type SessionInterface = Self;
 	type RewardCurve = RewardCurve;
+	type Call = Call;
+	type SubmitTransaction = SubmitTransaction;
+	type MaxIterations = MaxIterations;
+	type SessionLength = SessionDuration;
+	type MaxSubmissions = MaxSubmissions;
 }
 
 parameter_types! {
@@ -441,6 +611,7 @@ impl pallet_contracts::Trait for Runtime {
 	type MaxDepth = pallet_contracts::DefaultMaxDepth;
 	type MaxValueSize = pallet_contracts::DefaultMaxValueSize;
 	type BlockGasLimit = pallet_contracts::DefaultBlockGasLimit;
+	type ChainExtension = ();
 }
 
 impl pallet_sudo::Trait for Runtime {
@@ -474,6 +645,23 @@ impl pallet_authority_discovery::Trait for Runtime {}
 
 impl pallet_grandpa::Trait for Runtime {
 	type Event = Event;
+	type Call = Call;
+
+	type KeyOwnerProofSystem = pallet_session::historical::Module<Runtime>;
+
+	type KeyOwnerProof =
+		<Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(KeyTypeId, GrandpaId)>>::Proof;
+
+	type KeyOwnerIdentification = <Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(
+		KeyTypeId,
+		GrandpaId,
+	)>>::IdentificationTuple;
+
+	type HandleEquivocation = pallet_grandpa::EquivocationHandler<
+		Self::KeyOwnerIdentification,
+		Offences,
+		SubmitTransaction,
+	>;
 }
 
 parameter_types! {
@@ -597,13 +785,13 @@ construct_runtime!(
 	{
 		System: frame_system::{Module, Call, Storage, Config, Event},
 		Utility: pallet_utility::{Module, Call, Storage, Event<T>},
-		Babe: pallet_babe::{Module, Call, Storage, Config, Inherent(Timestamp)},
+		Babe: pallet_babe::{Module, Call, Storage, Config, Inherent(Timestamp), ValidateUnsigned},
 		Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
 		Authorship: pallet_authorship::{Module, Call, Storage, Inherent},
 		Indices: pallet_indices,
 		Balances: pallet_balances,
 		TransactionPayment: pallet_transaction_payment::{Module, Storage},
-		Staking: pallet_staking,
+		Staking: pallet_staking::{Module, Call, Config<T>, Storage, Event<T>, ValidateUnsigned},
 		Session: pallet_session::{Module, Call, Storage, Event, Config<T>},
 		Democracy: pallet_democracy::{Module, Call, Storage, Config, Event<T>},
 		Council: pallet_collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
@@ -611,7 +799,7 @@ construct_runtime!(
 		Elections: pallet_elections_phragmen::{Module, Call, Storage, Event<T>},
 		TechnicalMembership: pallet_membership::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
 		FinalityTracker: pallet_finality_tracker::{Module, Call, Inherent},
-		Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event},
+		Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event, ValidateUnsigned},
 		Treasury: pallet_treasury::{Module, Call, Storage, Config, Event<T>},
 		Contracts: pallet_contracts,
 		Sudo: pallet_sudo,
@@ -622,6 +810,9 @@ construct_runtime!(
 		Identity: pallet_identity::{Module, Call, Storage, Event<T>},
 		Society: pallet_society::{Module, Call, Storage, Event<T>, Config<T>},
 		Recovery: pallet_recovery::{Module, Call, Storage, Event<T>},
+		Scheduler: pallet_scheduler::{Module, Call, Storage, Event<T>},
+		Proxy: pallet_proxy::{Module, Call, Storage, Event<T>},
+		Vesting: pallet_vesting::{Module, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -654,6 +845,59 @@ pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExt
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<Runtime, Block, frame_system::ChainContext<Runtime>, Runtime, AllModules>;
 
+/// Map an error of accessing a contract in the contracts module into the error of the runtime API.
+fn map_get_storage_error(
+	error: pallet_contracts::GetStorageError,
+) -> pallet_contracts_rpc_runtime_api::GetStorageError {
+	use pallet_contracts::GetStorageError;
+	use pallet_contracts_rpc_runtime_api::{GetStorageError as RpcGetStorageError};
+	match error {
+		GetStorageError::ContractDoesntExist => RpcGetStorageError::ContractDoesntExist,
+		GetStorageError::IsTombstone => RpcGetStorageError::IsTombstone,
+	}
+}
+
+/// Map a result of a call of the contracts module into the result of the runtime API.
+fn map_exec_result(exec_result: pallet_contracts::ExecResult) -> ContractExecResult {
+	match exec_result {
+		Ok(v) => ContractExecResult::Success {
+			status: v.status,
+			data: v.data,
+		},
+		Err(_) => ContractExecResult::Error,
+	}
+}
+
+/// Map an execution trace of the contracts module into the trace of the runtime API.
+fn map_call_trace(
+	trace: pallet_contracts::trace::CallTraceOf<Runtime>,
+) -> pallet_contracts_rpc_runtime_api::CallTrace<AccountId, Balance, Hash> {
+	use pallet_contracts::trace::{CallKind, FrameOutcome};
+	use pallet_contracts_rpc_runtime_api::{
+		CallKind as RpcCallKind, CallTrace, FrameOutcome as RpcFrameOutcome, TracedEvent,
+	};
+	CallTrace {
+		kind: match trace.kind {
+			CallKind::Call => RpcCallKind::Call,
+			CallKind::Instantiate => RpcCallKind::Instantiate,
+		},
+		caller: trace.caller,
+		dest: trace.dest,
+		value: trace.value,
+		gas_used: trace.gas_used,
+		events: trace.events
+			.into_iter()
+			.map(|event| TracedEvent { topics: event.topics, data: event.data })
+			.collect(),
+		debug_messages: trace.debug_messages,
+		outcome: match trace.outcome {
+			FrameOutcome::Returned { status, data } => RpcFrameOutcome::Returned { status, data },
+			FrameOutcome::Trapped { reason } => RpcFrameOutcome::Trapped { reason },
+		},
+		nested: trace.nested.into_iter().map(map_call_trace).collect(),
+	}
+}
+
 impl_runtime_apis! {
 	impl sp_api::Core<Block> for Runtime {
 		fn version() -> RuntimeVersion {
@@ -713,6 +957,32 @@ impl_runtime_apis! {
 		fn grandpa_authorities() -> GrandpaAuthorityList {
 			Grandpa::grandpa_authorities()
 		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: fg_primitives::EquivocationProof<
+				<Block as BlockT>::Hash,
+				NumberFor<Block>,
+			>,
+			key_owner_proof: fg_primitives::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			let key_owner_proof = key_owner_proof.decode()?;
+
+			Grandpa::submit_report_equivocation_extrinsic(
+				equivocation_proof,
+				key_owner_proof,
+			)
+		}
+
+		fn generate_key_ownership_proof(
+			_set_id: fg_primitives::SetId,
+			authority_id: GrandpaId,
+		) -> Option<fg_primitives::OpaqueKeyOwnershipProof> {
+			use codec::Encode;
+
+			pallet_session::historical::Module::<Runtime>::prove((fg_primitives::KEY_TYPE, authority_id))
+				.map(|p| p.encode())
+				.map(fg_primitives::OpaqueKeyOwnershipProof::new)
+		}
 	}
 
 	impl sp_consensus_babe::BabeApi<Block> for Runtime {
@@ -731,6 +1001,29 @@ impl_runtime_apis! {
 				secondary_slots: true,
 			}
 		}
+
+		fn generate_key_ownership_proof(
+			_slot_number: sp_consensus_babe::SlotNumber,
+			authority_id: sp_consensus_babe::AuthorityId,
+		) -> Option<sp_consensus_babe::OpaqueKeyOwnershipProof> {
+			use codec::Encode;
+
+			pallet_session::historical::Module::<Runtime>::prove((sp_consensus_babe::KEY_TYPE, authority_id))
+				.map(|p| p.encode())
+				.map(sp_consensus_babe::OpaqueKeyOwnershipProof::new)
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: sp_consensus_babe::EquivocationProof<<Block as BlockT>::Header>,
+			key_owner_proof: sp_consensus_babe::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			let key_owner_proof = key_owner_proof.decode()?;
+
+			Babe::submit_report_equivocation_extrinsic(
+				equivocation_proof,
+				key_owner_proof,
+			)
+		}
 	}
 
 	impl sp_authority_discovery::AuthorityDiscoveryApi<Block> for Runtime {
@@ -745,6 +1038,12 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl frame_system_rpc_runtime_api::BlockWeightApi<Block> for Runtime {
+		fn block_weight() -> u64 {
+			System::all_extrinsics_weight().into()
+		}
+	}
+
 	impl pallet_contracts_rpc_runtime_api::ContractsApi<Block, AccountId, Balance> for Runtime {
 		fn call(
 			origin: AccountId,
@@ -760,12 +1059,51 @@ impl_runtime_apis! {
 				gas_limit,
 				input_data,
 			);
+			map_exec_result(exec_result)
+		}
+
+		fn call_with_trace(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: u64,
+			input_data: Vec<u8>,
+		) -> ContractCallResult<AccountId, Balance, Hash> {
+			let (exec_result, trace) = Contracts::bare_call_with_trace(
+				origin,
+				dest.into(),
+				value,
+				gas_limit,
+				input_data,
+			);
+			ContractCallResult {
+				result: map_exec_result(exec_result),
+				trace: trace.map(map_call_trace),
+			}
+		}
+
+		fn instantiate(
+			origin: AccountId,
+			endowment: Balance,
+			gas_limit: u64,
+			code_hash: Hash,
+			data: Vec<u8>,
+		) -> ContractInstantiateResult<AccountId> {
+			let (exec_result, gas_consumed) = Contracts::bare_instantiate(
+				origin,
+				endowment,
+				gas_limit,
+				code_hash,
+				data,
+			);
 			match exec_result {
-				Ok(v) => ContractExecResult::Success {
+				Ok((address, v)) => ContractInstantiateResult::Success {
+					address,
 					status: v.status,
 					data: v.data,
+					gas_consumed,
 				},
-				Err(_) => ContractExecResult::Error,
+				Err(_) => ContractInstantiateResult::Error { gas_consumed },
 			}
 		}
 
@@ -773,15 +1111,20 @@ impl_runtime_apis! {
 			address: AccountId,
 			key: [u8; 32],
 		) -> pallet_contracts_rpc_runtime_api::GetStorageResult {
-			Contracts::get_storage(address, key).map_err(|rpc_err| {
-				use pallet_contracts::GetStorageError;
-				use pallet_contracts_rpc_runtime_api::{GetStorageError as RpcGetStorageError};
-				/// Map the contract error into the RPC layer error.
-				match rpc_err {
-					GetStorageError::ContractDoesntExist => RpcGetStorageError::ContractDoesntExist,
-					GetStorageError::IsTombstone => RpcGetStorageError::IsTombstone,
-				}
-			})
+			Contracts::get_storage(address, key).map_err(map_get_storage_error)
+		}
+
+		fn rent_projection(
+			address: AccountId,
+		) -> pallet_contracts_rpc_runtime_api::RentProjectionResult<BlockNumber> {
+			use pallet_contracts::RentProjection;
+			use pallet_contracts_rpc_runtime_api::{RentProjection as RpcRentProjection};
+			Contracts::rent_projection(address)
+				.map(|projection| match projection {
+					RentProjection::EvictionAt(block) => RpcRentProjection::EvictionAt(block),
+					RentProjection::NoEviction => RpcRentProjection::NoEviction,
+				})
+				.map_err(map_get_storage_error)
 		}
 	}
 
@@ -800,6 +1143,23 @@ impl_runtime_apis! {
 			SessionKeys::generate(seed)
 		}
 	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	impl frame_benchmarking::Benchmark<Block> for Runtime {
+		fn dispatch_benchmark(
+			module: Vec<u8>,
+			extrinsic: Vec<u8>,
+			steps: u32,
+			repeat: u32,
+		) -> Option<Vec<frame_benchmarking::BenchmarkResults>> {
+			use frame_benchmarking::Benchmarking;
+
+			match module.as_slice() {
+				b"pallet-identity" | b"identity" => Identity::run_benchmark(extrinsic, steps, repeat).ok(),
+				_ => None,
+			}
+		}
+	}
 }
 
 #[cfg(test)]
@@ -830,6 +1190,27 @@ mod tests {
 		is_sign_and_submit_transaction::<SubmitTransaction>();
 	}
 
+	#[test]
+	fn non_transfer_proxy_cannot_move_funds() {
+		let vested_transfer = Call::Vesting(pallet_vesting::Call::vested_transfer(
+			Default::default(),
+			pallet_vesting::VestingInfo { locked: 100, per_block: 1, starting_block: 0 },
+		));
+		assert!(!ProxyType::NonTransfer.filter(&vested_transfer));
+
+		let remark = Call::System(frame_system::Call::remark(vec![]));
+		let nested_proxy = Call::Proxy(pallet_proxy::Call::proxy(
+			Default::default(),
+			None,
+			Box::new(remark.clone()),
+		));
+		assert!(!ProxyType::NonTransfer.filter(&nested_proxy));
+		assert!(ProxyType::NonTransfer.filter(&remark));
+
+		assert!(ProxyType::NonTransfer.is_superset(&ProxyType::Governance));
+		assert!(!ProxyType::NonTransfer.is_superset(&ProxyType::Staking));
+	}
+
 	#[test]
 	fn block_hooks_weight_should_not_exceed_limits() {
 		use frame_support::weights::WeighBlock;
@@ -20,7 +20,7 @@ use crate::keyring::*;
 use sp_keyring::{Ed25519Keyring, Sr25519Keyring};
 use node_runtime::{
 	GenesisConfig, BalancesConfig, SessionConfig, StakingConfig, SystemConfig,
-	GrandpaConfig, IndicesConfig, ContractsConfig, SocietyConfig, WASM_BINARY,
+	GrandpaConfig, IndicesConfig, ContractsConfig, SocietyConfig, VestingConfig, WASM_BINARY,
 };
 use node_runtime::constants::currency::*;
 use sp_core::ChangesTrieConfiguration;
@@ -49,7 +49,6 @@ pub fn config(support_changes_trie: bool, code: Option<&[u8]>) -> GenesisConfig
 				(eve(), 101 * DOLLARS),
 				(ferdie(), 100 * DOLLARS),
 			],
-			vesting: vec![],
 		}),
 		pallet_session: Some(SessionConfig {
 			keys: vec![
@@ -101,5 +100,8 @@ pub fn config(support_changes_trie: bool, code: Option<&[u8]>) -> GenesisConfig
 			pot: 0,
 			max_members: 999,
 		}),
+		pallet_vesting: Some(VestingConfig {
+			vesting: vec![],
+		}),
 	}
 }
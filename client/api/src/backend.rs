@@ -139,6 +139,20 @@ pub trait BlockImportOperation<Block: BlockT> {
 	/// Inject storage data into the database replacing any existing data.
 	fn reset_storage(&mut self, storage: Storage) -> sp_blockchain::Result<Block::Hash>;
 
+	/// Inject the full state of an already imported block, which has been imported without
+	/// state, e.g. a state downloaded from the network. Returns the resulting state root.
+	///
+	/// `top` yields the top-level storage entries, which are consumed one at a time so that
+	/// the state doesn't have to be held in memory. Backends that can not store state for
+	/// existing blocks return an error.
+	fn import_state(
+		&mut self,
+		_block: Block::Hash,
+		_top: &mut dyn Iterator<Item = (Vec<u8>, Vec<u8>)>,
+	) -> sp_blockchain::Result<Block::Hash> {
+		Err(sp_blockchain::Error::Backend("Importing state is not supported".into()))
+	}
+
 	/// Set storage changes.
 	fn update_storage(
 		&mut self,
@@ -146,6 +160,16 @@ pub trait BlockImportOperation<Block: BlockT> {
 		child_update: ChildStorageCollection,
 	) -> sp_blockchain::Result<()>;
 
+	/// Set offchain storage changes, to be written along with the block.
+	///
+	/// Backends without an offchain storage may ignore them.
+	fn update_offchain_storage(
+		&mut self,
+		_offchain_update: StorageCollection,
+	) -> sp_blockchain::Result<()> {
+		Ok(())
+	}
+
 	/// Inject changes trie data into the database.
 	fn update_changes_trie(
 		&mut self,
@@ -155,6 +155,18 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 				self.strategies.other.get_manager(),
 		};
 
+		(manager, self.extensions(at, context))
+	}
+
+	/// Create `Extensions` for given offchain call.
+	///
+	/// Based on the execution context and capabilities it produces
+	/// the right extensions object to support desired set of APIs.
+	pub fn extensions(
+		&self,
+		at: &BlockId<Block>,
+		context: ExecutionContext,
+	) -> Extensions {
 		let capabilities = context.capabilities();
 
 		let mut extensions = self.extensions_factory.read().extensions_for(capabilities);
@@ -180,7 +192,7 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 			)
 		}
 
-		(manager, extensions)
+		extensions
 	}
 }
 
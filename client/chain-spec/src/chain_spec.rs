@@ -33,6 +33,7 @@ enum GenesisSource<G> {
 	File(PathBuf),
 	Binary(Cow<'static, [u8]>),
 	Factory(Rc<dyn Fn() -> G>),
+	Storage(Storage),
 }
 
 impl<G> Clone for GenesisSource<G> {
@@ -41,6 +42,7 @@ impl<G> Clone for GenesisSource<G> {
 			GenesisSource::File(ref path) => GenesisSource::File(path.clone()),
 			GenesisSource::Binary(ref d) => GenesisSource::Binary(d.clone()),
 			GenesisSource::Factory(ref f) => GenesisSource::Factory(f.clone()),
+			GenesisSource::Storage(ref s) => GenesisSource::Storage(s.clone()),
 		}
 	}
 }
@@ -66,6 +68,7 @@ impl<G: RuntimeGenesis> GenesisSource<G> {
 				Ok(genesis.genesis)
 			},
 			GenesisSource::Factory(f) => Ok(Genesis::Runtime(f())),
+			GenesisSource::Storage(storage) => Ok(Genesis::Raw(storage.clone().into())),
 		}
 	}
 }
@@ -121,6 +124,31 @@ struct RawGenesis {
 	pub children: HashMap<StorageKey, ChildRawStorage>,
 }
 
+impl From<Storage> for RawGenesis {
+	fn from(storage: Storage) -> Self {
+		let top = storage.top.into_iter()
+			.map(|(k, v)| (StorageKey(k), StorageData(v)))
+			.collect();
+		let children = storage.children.into_iter()
+			.map(|(sk, child)| {
+				let info = child.child_info.as_ref();
+				let (info, ci_type) = info.info();
+				(
+					StorageKey(sk),
+					ChildRawStorage {
+						data: child.data.into_iter()
+							.map(|(k, v)| (StorageKey(k), StorageData(v)))
+							.collect(),
+						child_info: info.to_vec(),
+						child_type: ci_type,
+					},
+			)})
+			.collect();
+
+		RawGenesis { top, children }
+	}
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
@@ -214,6 +242,13 @@ impl<G, E> ChainSpec<G, E> {
 		&self.client_spec.extensions
 	}
 
+	/// Replace the genesis of this spec with the given raw storage.
+	///
+	/// The spec will then always be dumped with a raw genesis.
+	pub fn set_storage(&mut self, storage: Storage) {
+		self.genesis = GenesisSource::Storage(storage);
+	}
+
 	/// Create hardcoded spec.
 	pub fn from_genesis<F: Fn() -> G + 'static>(
 		name: &str,
@@ -282,26 +317,7 @@ impl<G: RuntimeGenesis, E: serde::Serialize> ChainSpec<G, E> {
 		let genesis = match (raw, self.genesis.resolve()?) {
 			(true, Genesis::Runtime(g)) => {
 				let storage = g.build_storage()?;
-				let top = storage.top.into_iter()
-					.map(|(k, v)| (StorageKey(k), StorageData(v)))
-					.collect();
-				let children = storage.children.into_iter()
-					.map(|(sk, child)| {
-						let info = child.child_info.as_ref();
-						let (info, ci_type) = info.info();
-						(
-							StorageKey(sk),
-							ChildRawStorage {
-								data: child.data.into_iter()
-									.map(|(k, v)| (StorageKey(k), StorageData(v)))
-									.collect(),
-								child_info: info.to_vec(),
-								child_type: ci_type,
-							},
-					)})
-					.collect();
-
-				Genesis::Raw(RawGenesis { top, children })
+				Genesis::Raw(storage.into())
 			},
 			(_, genesis) => genesis,
 		};
@@ -363,4 +379,29 @@ mod tests {
 
 		assert_eq!(spec.extensions().my_property, "Test Extension");
 	}
+
+	#[test]
+	fn set_storage_is_dumped_as_raw_genesis() {
+		let mut spec = TestSpec::from_json_bytes(Cow::Owned(
+			include_bytes!("../res/chain_spec.json").to_vec()
+		)).unwrap();
+
+		let child_info = ChildInfo::new_default(b"unique_id").to_owned();
+		let mut storage = Storage::default();
+		storage.top.insert(b"key".to_vec(), b"value".to_vec());
+		storage.children.insert(
+			b":child_storage:default:unique_id".to_vec(),
+			StorageChild {
+				data: vec![(b"child_key".to_vec(), b"child_value".to_vec())].into_iter().collect(),
+				child_info,
+			},
+		);
+		spec.set_storage(storage.clone());
+
+		let json = spec.to_json(false).unwrap();
+		let restored = TestSpec::from_json_bytes(json.into_bytes()).unwrap().build_storage().unwrap();
+
+		assert_eq!(restored.top, storage.top);
+		assert_eq!(restored.children, storage.children);
+	}
 }
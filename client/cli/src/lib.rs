@@ -28,7 +28,9 @@ pub mod informant;
 
 use sc_client_api::execution_extensions::ExecutionStrategies;
 use sc_service::{
-	config::{Configuration, DatabaseConfig, KeystoreConfig},
+	config::{
+		Configuration, DatabaseConfig, KeystoreConfig, PrometheusConfig, TransactionPoolSenderLimit,
+	},
 	ServiceBuilderCommand,
 	RuntimeGenesis, ChainSpecExtension, PruningMode, ChainSpec,
 };
@@ -54,7 +56,8 @@ pub use structopt::clap::App;
 use params::{
 	RunCmd, PurgeChainCmd, RevertCmd, ImportBlocksCmd, ExportBlocksCmd, BuildSpecCmd,
 	NetworkConfigurationParams, MergeParameters, TransactionPoolParams,
-	NodeKeyParams, NodeKeyType, Cors, CheckBlockCmd,
+	NodeKeyParams, NodeKeyType, Cors, CheckBlockCmd, ExportStateCmd,
+	DryRunUpgradeCmd, BenchmarkCmd,
 };
 pub use params::{NoCustom, CoreParams, SharedParams, ImportParams, ExecutionStrategy};
 pub use traits::GetSharedParams;
@@ -64,7 +67,9 @@ use lazy_static::lazy_static;
 use futures::{Future, executor::block_on};
 use sc_telemetry::TelemetryEndpoints;
 use sp_runtime::generic::BlockId;
-use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, HasherFor, NumberFor};
+use sp_runtime::BuildStorage;
+use codec::{Encode, Decode};
 
 /// default sub directory to store network config
 const DEFAULT_NETWORK_CONFIG_PATH : &'static str = "network";
@@ -72,6 +77,8 @@ const DEFAULT_NETWORK_CONFIG_PATH : &'static str = "network";
 const DEFAULT_DB_CONFIG_PATH : &'static str = "db";
 /// default sub directory for the key store
 const DEFAULT_KEYSTORE_CONFIG_PATH : &'static str =  "keystore";
+/// default file to store the transaction pool journal
+const DEFAULT_TXPOOL_JOURNAL_PATH : &'static str = "txpool_journal";
 
 /// The maximum number of characters for a node name.
 const NODE_NAME_MAX_LENGTH: usize = 32;
@@ -240,12 +247,21 @@ where
 		params::CoreParams::CheckBlock(params) => ParseAndPrepare::CheckBlock(
 			CheckBlock { params, version }
 		),
+		params::CoreParams::ExportState(params) => ParseAndPrepare::ExportState(
+			ParseAndPrepareExportState { params, version }
+		),
+		params::CoreParams::DryRunUpgrade(params) => ParseAndPrepare::DryRunUpgrade(
+			ParseAndPrepareDryRunUpgrade { params, version }
+		),
 		params::CoreParams::PurgeChain(params) => ParseAndPrepare::PurgeChain(
 			ParseAndPreparePurge { params, version }
 		),
 		params::CoreParams::Revert(params) => ParseAndPrepare::RevertChain(
 			ParseAndPrepareRevert { params, version }
 		),
+		params::CoreParams::Benchmark(params) => ParseAndPrepare::Benchmark(
+			ParseAndPrepareBenchmark { params, version }
+		),
 		params::CoreParams::Custom(params) => ParseAndPrepare::CustomCommand(params),
 	};
 	init_logger(args.shared_params().and_then(|p| p.log.as_ref()).map(|v| v.as_ref()).unwrap_or(""));
@@ -276,10 +292,16 @@ pub enum ParseAndPrepare<'a, CC, RP> {
 	ImportBlocks(ParseAndPrepareImport<'a>),
 	/// Command to check a block.
 	CheckBlock(CheckBlock<'a>),
+	/// Command ready to export the state of a block.
+	ExportState(ParseAndPrepareExportState<'a>),
+	/// Command ready to dry-run a runtime upgrade.
+	DryRunUpgrade(ParseAndPrepareDryRunUpgrade<'a>),
 	/// Command ready to purge the chain.
 	PurgeChain(ParseAndPreparePurge<'a>),
 	/// Command ready to revert the chain.
 	RevertChain(ParseAndPrepareRevert<'a>),
+	/// Command ready to benchmark runtime pallets.
+	Benchmark(ParseAndPrepareBenchmark<'a>),
 	/// An additional custom command passed to `parse_and_prepare`.
 	CustomCommand(CC),
 }
@@ -293,8 +315,11 @@ impl<'a, CC, RP> ParseAndPrepare<'a, CC, RP> where CC: GetSharedParams {
 			ParseAndPrepare::ExportBlocks(c) => Some(&c.params.shared_params),
 			ParseAndPrepare::ImportBlocks(c) => Some(&c.params.shared_params),
 			ParseAndPrepare::CheckBlock(c) => Some(&c.params.shared_params),
+			ParseAndPrepare::ExportState(c) => Some(&c.params.shared_params),
+			ParseAndPrepare::DryRunUpgrade(c) => Some(&c.params.shared_params),
 			ParseAndPrepare::PurgeChain(c) => Some(&c.params.shared_params),
 			ParseAndPrepare::RevertChain(c) => Some(&c.params.shared_params),
+			ParseAndPrepare::Benchmark(c) => Some(&c.params.shared_params),
 			ParseAndPrepare::CustomCommand(c) => c.shared_params(),
 		}
 	}
@@ -353,6 +378,20 @@ impl<'a, CC, RP> ParseAndPrepare<'a, CC, RP> {
 					c.version,
 					default_base_path,
 				)).transpose(),
+			ParseAndPrepare::ExportState(c) =>
+				Some(create_config_with_db_path(
+					spec_factory,
+					&c.params.shared_params,
+					c.version,
+					default_base_path,
+				)).transpose(),
+			ParseAndPrepare::DryRunUpgrade(c) =>
+				Some(create_config_with_db_path(
+					spec_factory,
+					&c.params.shared_params,
+					c.version,
+					default_base_path,
+				)).transpose(),
 			ParseAndPrepare::PurgeChain(c) =>
 				Some(create_config_with_db_path(
 					spec_factory,
@@ -367,6 +406,13 @@ impl<'a, CC, RP> ParseAndPrepare<'a, CC, RP> {
 					c.version,
 					default_base_path,
 				)).transpose(),
+			ParseAndPrepare::Benchmark(c) =>
+				Some(create_config_with_db_path(
+					spec_factory,
+					&c.params.shared_params,
+					c.version,
+					default_base_path,
+				)).transpose(),
 			ParseAndPrepare::CustomCommand(_) => Ok(None),
 		}
 	}
@@ -623,14 +669,7 @@ impl<'a> CheckBlock<'a> {
 		fill_import_params(&mut config, &self.params.import_params, sc_service::Roles::FULL)?;
 		fill_config_keystore_in_memory(&mut config)?;
 
-		let input = if self.params.input.starts_with("0x") { &self.params.input[2..] } else { &self.params.input[..] };
-		let block_id = match FromStr::from_str(input) {
-			Ok(hash) => BlockId::hash(hash),
-			Err(_) => match self.params.input.parse::<u32>() {
-				Ok(n) => BlockId::number((n as u32).into()),
-				Err(_) => return Err(error::Error::Input("Invalid hash or number specified".into())),
-			}
-		};
+		let block_id = parse_block_id(&self.params.input)?;
 
 		let start = std::time::Instant::now();
 		let check = builder(config)?
@@ -642,6 +681,106 @@ impl<'a> CheckBlock<'a> {
 	}
 }
 
+/// Command ready to export the state of a block.
+pub struct ParseAndPrepareExportState<'a> {
+	params: ExportStateCmd,
+	version: &'a VersionInfo,
+}
+
+impl<'a> ParseAndPrepareExportState<'a> {
+	/// Runs the command and prints a chain spec with the exported state as raw genesis.
+	pub fn run_with_builder<C, G, E, F, B, S>(
+		self,
+		builder: F,
+		spec_factory: S,
+	) -> error::Result<()>
+		where S: FnOnce(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+			F: FnOnce(Configuration<C, G, E>) -> Result<B, error::Error>,
+			B: ServiceBuilderCommand,
+			<<B as ServiceBuilderCommand>::Block as BlockT>::Hash: FromStr,
+			C: Default,
+			G: RuntimeGenesis,
+			E: ChainSpecExtension,
+	{
+		let mut config = create_config_with_db_path(
+			spec_factory,
+			&self.params.shared_params,
+			self.version,
+			None,
+		)?;
+		fill_config_keystore_in_memory(&mut config)?;
+
+		let block_id = self.params.at.as_ref().map(|at| parse_block_id(at)).transpose()?;
+		let mut spec = config.chain_spec.clone();
+
+		info!("Exporting raw state...");
+		let raw_state = builder(config)?.export_raw_state(block_id)?;
+		spec.set_storage(raw_state);
+
+		info!("Generating new chain spec...");
+		let json = sc_service::chain_ops::build_spec(spec, true)?;
+
+		print!("{}", json);
+
+		Ok(())
+	}
+}
+
+/// Command ready to dry-run a runtime upgrade.
+pub struct ParseAndPrepareDryRunUpgrade<'a> {
+	params: DryRunUpgradeCmd,
+	version: &'a VersionInfo,
+}
+
+impl<'a> ParseAndPrepareDryRunUpgrade<'a> {
+	/// Runs the command and reports the outcome of the upgrade.
+	pub fn run_with_builder<C, G, E, F, B, S>(
+		self,
+		builder: F,
+		spec_factory: S,
+	) -> error::Result<()>
+		where S: FnOnce(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+			F: FnOnce(Configuration<C, G, E>) -> Result<B, error::Error>,
+			B: ServiceBuilderCommand,
+			<<B as ServiceBuilderCommand>::Block as BlockT>::Hash: FromStr,
+			C: Default,
+			G: RuntimeGenesis,
+			E: ChainSpecExtension,
+	{
+		let mut config = create_config_with_db_path(
+			spec_factory,
+			&self.params.shared_params,
+			self.version,
+			None,
+		)?;
+		fill_import_params(&mut config, &self.params.import_params, sc_service::Roles::FULL)?;
+		fill_config_keystore_in_memory(&mut config)?;
+		config.default_heap_pages = self.params.default_heap_pages.map(|p| p as u64);
+
+		let block_id = parse_block_id(&self.params.at)?;
+		let code = std::fs::read(&self.params.code)?;
+
+		let start = std::time::Instant::now();
+		builder(config)?.dry_run_upgrade(code, block_id, self.params.blocks)?;
+		println!("Completed in {} ms.", start.elapsed().as_millis());
+		Ok(())
+	}
+}
+
+/// Parse a block hash (optionally `0x` prefixed) or number given on the command line.
+fn parse_block_id<B: BlockT>(input: &str) -> error::Result<BlockId<B>> where
+	B::Hash: FromStr,
+{
+	let hash = if input.starts_with("0x") { &input[2..] } else { input };
+	match FromStr::from_str(hash) {
+		Ok(hash) => Ok(BlockId::hash(hash)),
+		Err(_) => match input.parse::<u32>() {
+			Ok(n) => Ok(BlockId::number(n.into())),
+			Err(_) => Err(error::Error::Input("Invalid hash or number specified".into())),
+		}
+	}
+}
+
 /// Command ready to purge the chain.
 pub struct ParseAndPreparePurge<'a> {
 	params: PurgeChainCmd,
@@ -740,6 +879,93 @@ impl<'a> ParseAndPrepareRevert<'a> {
 	}
 }
 
+/// Command ready to benchmark runtime pallets.
+pub struct ParseAndPrepareBenchmark<'a> {
+	params: BenchmarkCmd,
+	version: &'a VersionInfo,
+}
+
+impl<'a> ParseAndPrepareBenchmark<'a> {
+	/// Runs the command and benchmarks the chain.
+	///
+	/// The benchmark is executed against the genesis state of the selected chain, using a
+	/// temporary database and the runtime dispatch of the given `ExecDispatch`.
+	pub fn run<G, E, S, BB, ExecDispatch>(
+		self,
+		spec_factory: S,
+	) -> error::Result<()> where
+		S: FnOnce(&str) -> Result<Option<ChainSpec<G, E>>, String>,
+		G: RuntimeGenesis,
+		E: ChainSpecExtension,
+		BB: BlockT,
+		ExecDispatch: sc_executor::NativeExecutionDispatch + 'static,
+	{
+		let spec = load_spec(&self.params.shared_params, spec_factory)?;
+		let genesis_storage = spec.build_storage()?;
+		let state = sc_client_db::BenchmarkingState::<BB>::new(genesis_storage)?;
+		let executor = sc_executor::NativeExecutor::<ExecDispatch>::new(
+			self.params.wasm_method.into(),
+			None,
+			1,
+		);
+		let mut changes = Default::default();
+
+		let result = sp_state_machine::StateMachine::<_, HasherFor<BB>, NumberFor<BB>, _>::new(
+			&state,
+			None,
+			&mut changes,
+			&executor,
+			"Benchmark_dispatch_benchmark",
+			&(
+				&self.params.pallet,
+				&self.params.extrinsic,
+				self.params.steps,
+				self.params.repeat,
+			).encode(),
+			Default::default(),
+		)
+		.execute(self.params.execution.into())
+		.map_err(|e| format!("Error executing runtime benchmark: {:?}", e))?;
+
+		let results = <Option<Vec<frame_benchmarking::BenchmarkResults>> as Decode>::decode(
+			&mut &result[..],
+		).map_err(|e| format!("Failed to decode benchmark results: {:?}", e))?;
+
+		match results {
+			Some(results) => {
+				// Print benchmark metadata
+				println!(
+					"Pallet: {:?}, Extrinsic: {:?}, Steps: {:?}, Repeat: {:?}",
+					self.params.pallet,
+					self.params.extrinsic,
+					self.params.steps,
+					self.params.repeat,
+				);
+
+				if let Some((components, _, _)) = results.first() {
+					// Print the table header
+					components.iter().for_each(|(param, _)| print!("{:?},", param));
+					println!("extrinsic_time,storage_root_time");
+				}
+
+				// Print the values
+				results.iter().for_each(|(components, extrinsic_time, storage_root_time)| {
+					components.iter().for_each(|(_, value)| print!("{:?},", value));
+					println!("{:?},{:?}", extrinsic_time, storage_root_time);
+				});
+
+				// Print the per-component linear regressions
+				if let Some(analysis) = frame_benchmarking::Analysis::median_slopes(&results) {
+					println!("\nMedian Slopes Analysis\n========\n{}", analysis);
+				}
+			},
+			None => eprintln!("No Results."),
+		}
+
+		Ok(())
+	}
+}
+
 /// Create a `NodeKeyConfig` from the given `NodeKeyParams` in the context
 /// of an optional network config storage directory.
 fn node_key_config<P>(params: NodeKeyParams, net_config_dir: &Option<P>)
@@ -792,6 +1018,21 @@ fn fill_transaction_pool_configuration<C, G, E>(
 	options.transaction_pool.future.count = params.pool_limit / factor;
 	options.transaction_pool.future.total_bytes = params.pool_kbytes * 1024 / factor;
 
+	// per-sender limit, shared by both queues
+	options.transaction_pool.sender_limit = params.pool_limit_per_sender.map(|count|
+		TransactionPoolSenderLimit {
+			count,
+			tag_prefix_len: params.pool_sender_tag_len,
+		}
+	);
+
+	// journal of locally submitted transactions
+	if params.pool_journal {
+		options.transaction_pool.journal_path = Some(
+			options.in_chain_config_dir(DEFAULT_TXPOOL_JOURNAL_PATH).expect("We provided a base_path.")
+		);
+	}
+
 	Ok(())
 }
 
@@ -847,6 +1088,7 @@ fn fill_network_configuration(
 	};
 
 	config.max_parallel_downloads = cli.max_parallel_downloads;
+	config.sync_mode = cli.sync.into();
 
 	Ok(())
 }
@@ -941,6 +1183,7 @@ pub fn fill_import_params<C, G, E>(
 	};
 
 	config.wasm_method = cli.wasm_method.into();
+	config.max_runtime_instances = cli.max_runtime_instances;
 
 	let exec = &cli.execution_strategies;
 	let exec_all_or = |strat: ExecutionStrategy| exec.execution.unwrap_or(strat).into();
@@ -1045,6 +1288,7 @@ where
 	let rpc_interface: &str = interface_str(cli.rpc_external, cli.unsafe_rpc_external, cli.validator)?;
 	let ws_interface: &str = interface_str(cli.ws_external, cli.unsafe_ws_external, cli.validator)?;
 	let grafana_interface: &str = if cli.grafana_external { "0.0.0.0" } else { "127.0.0.1" };
+	let prometheus_interface: &str = if cli.prometheus_external { "0.0.0.0" } else { "127.0.0.1" };
 
 	config.rpc_http = Some(parse_address(&format!("{}:{}", rpc_interface, 9933), cli.rpc_port)?);
 	config.rpc_ws = Some(parse_address(&format!("{}:{}", ws_interface, 9944), cli.ws_port)?);
@@ -1052,6 +1296,15 @@ where
 		parse_address(&format!("{}:{}", grafana_interface, 9955), cli.grafana_port)?
 	);
 
+	// Override prometheus
+	if cli.no_prometheus {
+		config.prometheus_config = None;
+	} else if config.prometheus_config.is_none() {
+		config.prometheus_config = Some(PrometheusConfig::new_with_default_registry(
+			parse_address(&format!("{}:{}", prometheus_interface, 9615), cli.prometheus_port)?,
+		));
+	}
+
 	config.rpc_ws_max_connections = cli.ws_max_connections;
 	config.rpc_cors = cli.rpc_cors.unwrap_or_else(|| if is_dev {
 		log::warn!("Running in --dev mode, RPC CORS has been disabled.");
@@ -129,6 +129,15 @@ pub struct ImportParams {
 	)]
 	pub wasm_method: WasmExecutionMethod,
 
+	/// The maximal number of idle Wasm runtime instances kept for reuse.
+	///
+	/// Runtime calls from different threads (e.g. block import and RPC) use separate instances,
+	/// which are created when no idle instance is available. Only this many of them are kept
+	/// once the calls are finished. Creating an instance of a compiled runtime compiles its code
+	/// again.
+	#[structopt(long = "max-runtime-instances", value_name = "COUNT", default_value = "8")]
+	pub max_runtime_instances: usize,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub execution_strategies: ExecutionStrategies,
@@ -206,11 +215,45 @@ pub struct NetworkConfigurationParams {
 	#[structopt(long = "max-parallel-downloads", value_name = "COUNT", default_value = "5")]
 	pub max_parallel_downloads: u32,
 
+	/// How to catch up with the chain when starting from genesis.
+	///
+	/// `Fast` verifies all headers, downloads the state of the latest finalized
+	/// block and only executes the blocks after it. Chains whose state contains
+	/// child tries can only be synced in `Full` mode; a fast sync of such a chain
+	/// stops once they are found and imports all blocks instead.
+	#[structopt(
+		long = "sync",
+		value_name = "SYNC_MODE",
+		possible_values = &SyncMode::variants(),
+		case_insensitive = true,
+		default_value = "Full"
+	)]
+	pub sync: SyncMode,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub node_key_params: NodeKeyParams,
 }
 
+arg_enum! {
+	/// How to catch up with the chain.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum SyncMode {
+		Full,
+		Fast,
+	}
+}
+
+impl Into<sc_network::config::SyncMode> for SyncMode {
+	fn into(self) -> sc_network::config::SyncMode {
+		match self {
+			SyncMode::Full => sc_network::config::SyncMode::Full,
+			SyncMode::Fast => sc_network::config::SyncMode::Fast,
+		}
+	}
+}
+
 arg_enum! {
 	#[allow(missing_docs)]
 	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -290,6 +333,21 @@ pub struct TransactionPoolParams {
 	/// Maximum number of kilobytes of all transactions stored in the pool.
 	#[structopt(long = "pool-kbytes", value_name = "COUNT", default_value = "10240")]
 	pub pool_kbytes: usize,
+	/// Maximum number of transactions from a single sender in the transaction pool.
+	///
+	/// By default the number of transactions per sender is not limited.
+	#[structopt(long = "pool-limit-per-sender", value_name = "COUNT")]
+	pub pool_limit_per_sender: Option<usize>,
+	/// Number of leading bytes of the first `provides` tag identifying the sender of a transaction.
+	///
+	/// Only used together with `--pool-limit-per-sender`.
+	#[structopt(long = "pool-sender-tag-len", value_name = "BYTES", default_value = "32")]
+	pub pool_sender_tag_len: usize,
+	/// Keep a journal of locally submitted transactions and restore them after restart.
+	///
+	/// Restored transactions are re-validated before they enter the pool.
+	#[structopt(long = "pool-journal")]
+	pub pool_journal: bool,
 }
 
 arg_enum! {
@@ -453,6 +511,12 @@ pub struct RunCmd {
 	#[structopt(long = "grafana-external")]
 	pub grafana_external: bool,
 
+	/// Listen to all Prometheus data source interfaces.
+	///
+	/// Default is local.
+	#[structopt(long = "prometheus-external")]
+	pub prometheus_external: bool,
+
 	/// Specify HTTP RPC server TCP port.
 	#[structopt(long = "rpc-port", value_name = "PORT")]
 	pub rpc_port: Option<u16>,
@@ -479,6 +543,16 @@ pub struct RunCmd {
 	#[structopt(long = "grafana-port", value_name = "PORT")]
 	pub grafana_port: Option<u16>,
 
+	/// Specify Prometheus data source server TCP Port.
+	#[structopt(long = "prometheus-port", value_name = "PORT")]
+	pub prometheus_port: Option<u16>,
+
+	/// Do not expose a Prometheus metric endpoint.
+	///
+	/// Prometheus metric endpoint is enabled by default.
+	#[structopt(long = "no-prometheus")]
+	pub no_prometheus: bool,
+
 	/// The human-readable name for this node.
 	///
 	/// The node name will be reported to the telemetry server, if enabled.
@@ -829,6 +903,51 @@ pub struct CheckBlockCmd {
 	pub import_params: ImportParams,
 }
 
+/// The `export-state` command used to export the state of a given block into
+/// a chain spec.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportStateCmd {
+	/// Block hash or number.
+	///
+	/// Default is best block.
+	#[structopt(long = "at", value_name = "HASH or NUMBER")]
+	pub at: Option<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// The `dry-run-upgrade` command used to test a runtime upgrade against the state of a block.
+#[derive(Debug, StructOpt, Clone)]
+pub struct DryRunUpgradeCmd {
+	/// Wasm blob of the runtime to upgrade to.
+	#[structopt(parse(from_os_str), value_name = "WASM_FILE")]
+	pub code: PathBuf,
+
+	/// Hash or number of the block on top of which the upgrade is enacted.
+	#[structopt(long = "at", value_name = "HASH or NUMBER")]
+	pub at: String,
+
+	/// Number of following blocks to execute with the new runtime.
+	#[structopt(long = "blocks", value_name = "COUNT", default_value = "10")]
+	pub blocks: u32,
+
+	/// The default number of 64KB pages to ever allocate for Wasm execution.
+	///
+	/// Don't alter this unless you know what you're doing.
+	#[structopt(long = "default-heap-pages", value_name = "COUNT")]
+	pub default_heap_pages: Option<u32>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub import_params: ImportParams,
+}
+
 /// The `revert` command used revert the chain to a previous state.
 #[derive(Debug, StructOpt, Clone)]
 pub struct RevertCmd {
@@ -853,6 +972,53 @@ pub struct PurgeChainCmd {
 	pub shared_params: SharedParams,
 }
 
+/// The `benchmark` command used to benchmark FRAME Pallets.
+#[derive(Debug, StructOpt, Clone)]
+pub struct BenchmarkCmd {
+	/// Select a FRAME Pallet to benchmark.
+	#[structopt(short, long)]
+	pub pallet: String,
+
+	/// Select an extrinsic to benchmark.
+	#[structopt(short, long)]
+	pub extrinsic: String,
+
+	/// Select how many samples we should take across the variable components.
+	#[structopt(short, long, default_value = "1")]
+	pub steps: u32,
+
+	/// Select how many repetitions of this benchmark should run.
+	#[structopt(short, long, default_value = "1")]
+	pub repeat: u32,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	/// The execution strategy that should be used for benchmarks.
+	///
+	/// The benchmarks are only compiled into the native runtime of a node that was built with
+	/// the `runtime-benchmarks` feature.
+	#[structopt(
+		long = "execution",
+		value_name = "STRATEGY",
+		possible_values = &ExecutionStrategy::variants(),
+		case_insensitive = true,
+		default_value = "Native"
+	)]
+	pub execution: ExecutionStrategy,
+
+	/// Method for executing Wasm runtime code.
+	#[structopt(
+		long = "wasm-execution",
+		value_name = "METHOD",
+		possible_values = &WasmExecutionMethod::enabled_variants(),
+		case_insensitive = true,
+		default_value = "Interpreted"
+	)]
+	pub wasm_method: WasmExecutionMethod,
+}
+
 /// All core commands that are provided by default.
 ///
 /// The core commands are split into multiple subcommands and `Run` is the default subcommand. From
@@ -875,12 +1041,21 @@ pub enum CoreParams<CC, RP> {
 	/// Validte a single block.
 	CheckBlock(CheckBlockCmd),
 
+	/// Export state as raw chain spec.
+	ExportState(ExportStateCmd),
+
+	/// Dry-run a runtime upgrade against the state of a block.
+	DryRunUpgrade(DryRunUpgradeCmd),
+
 	/// Revert chain to the previous state.
 	Revert(RevertCmd),
 
 	/// Remove the whole chain data.
 	PurgeChain(PurgeChainCmd),
 
+	/// Benchmark runtime pallets.
+	Benchmark(BenchmarkCmd),
+
 	/// Further custom subcommands.
 	Custom(CC),
 }
@@ -912,6 +1087,16 @@ impl<CC, RP> StructOpt for CoreParams<CC, RP> where
 			CheckBlockCmd::augment_clap(SubCommand::with_name("check-block"))
 				.about("Re-validate a known block.")
 		)
+		.subcommand(
+			ExportStateCmd::augment_clap(SubCommand::with_name("export-state"))
+				.about("Export the state of a given block into a chain spec with a raw genesis, \
+						outputting to stdout.")
+		)
+		.subcommand(
+			DryRunUpgradeCmd::augment_clap(SubCommand::with_name("dry-run-upgrade"))
+				.about("Enact a runtime upgrade on top of a block and re-execute the following \
+						blocks with the new runtime, without writing anything to the database.")
+		)
 		.subcommand(
 			RevertCmd::augment_clap(SubCommand::with_name("revert"))
 				.about("Revert chain to the previous state.")
@@ -920,6 +1105,10 @@ impl<CC, RP> StructOpt for CoreParams<CC, RP> where
 			PurgeChainCmd::augment_clap(SubCommand::with_name("purge-chain"))
 				.about("Remove the whole chain data.")
 		)
+		.subcommand(
+			BenchmarkCmd::augment_clap(SubCommand::with_name("benchmark"))
+				.about("Benchmark runtime pallets.")
+		)
 	}
 
 	fn from_clap(matches: &::structopt::clap::ArgMatches) -> Self {
@@ -932,9 +1121,15 @@ impl<CC, RP> StructOpt for CoreParams<CC, RP> where
 				CoreParams::ImportBlocks(ImportBlocksCmd::from_clap(matches)),
 			("check-block", Some(matches)) =>
 				CoreParams::CheckBlock(CheckBlockCmd::from_clap(matches)),
+			("export-state", Some(matches)) =>
+				CoreParams::ExportState(ExportStateCmd::from_clap(matches)),
+			("dry-run-upgrade", Some(matches)) =>
+				CoreParams::DryRunUpgrade(DryRunUpgradeCmd::from_clap(matches)),
 			("revert", Some(matches)) => CoreParams::Revert(RevertCmd::from_clap(matches)),
 			("purge-chain", Some(matches)) =>
 				CoreParams::PurgeChain(PurgeChainCmd::from_clap(matches)),
+			("benchmark", Some(matches)) =>
+				CoreParams::Benchmark(BenchmarkCmd::from_clap(matches)),
 			(_, None) => CoreParams::Run(MergeParameters::from_clap(matches)),
 			_ => CoreParams::Custom(CC::from_clap(matches)),
 		}
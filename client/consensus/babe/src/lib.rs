@@ -639,6 +639,98 @@ impl<B, E, Block: BlockT, RA, PRA> BabeVerifier<B, E, Block, RA, PRA> {
 			Ok(())
 		}
 	}
+
+	fn check_and_report_equivocation(
+		&self,
+		slot_now: SlotNumber,
+		slot: SlotNumber,
+		header: &Block::Header,
+		author: &AuthorityId,
+		origin: &BlockOrigin,
+	) -> Result<(), Error<Block>>
+		where
+			B: Backend<Block>,
+			E: CallExecutor<Block>,
+			PRA: ProvideRuntimeApi<Block> + AuxStore,
+			PRA::Api: BabeApi<Block, Error = sp_blockchain::Error>,
+	{
+		// don't report any equivocations during initial sync
+		// as they are most likely stale.
+		if *origin == BlockOrigin::NetworkInitialSync {
+			return Ok(());
+		}
+
+		// check if authorship of this header is an equivocation and return a proof if so.
+		let equivocation_proof = match check_equivocation(
+			&*self.api,
+			slot_now,
+			slot,
+			header,
+			author,
+		).map_err(Error::Client)? {
+			Some(proof) => proof,
+			None => return Ok(()),
+		};
+
+		info!(
+			"Slot author {:?} is equivocating at slot {} with headers {:?} and {:?}",
+			author,
+			slot,
+			equivocation_proof.fst_header().hash(),
+			equivocation_proof.snd_header().hash(),
+		);
+
+		let equivocation_proof = sp_consensus_babe::EquivocationProof {
+			offender: author.clone(),
+			slot_number: slot,
+			first_header: equivocation_proof.fst_header().clone(),
+			second_header: equivocation_proof.snd_header().clone(),
+		};
+
+		// get the best block on which we will build and send the equivocation report.
+		let best_id = BlockId::Hash(self.client.chain_info().best_hash);
+
+		// generate a key ownership proof. we start by trying to generate the
+		// key ownership proof at the parent of the equivocating header, this
+		// will make sure that proof generation is successful since it happens
+		// during the on-going session (i.e. session keys are available in the
+		// state to be able to generate the proof). this might fail if the
+		// equivocation happens on the first block of the session, in which case
+		// its parent would be on the previous session. if generation on the
+		// parent header fails we try with the best block as well.
+		let generate_key_owner_proof = |block_id: &BlockId<Block>| {
+			self.api
+				.runtime_api()
+				.generate_key_ownership_proof(block_id, slot, author.clone())
+				.map_err(Error::Client)
+		};
+
+		let parent_id = BlockId::Hash(*header.parent_hash());
+		let key_owner_proof = match generate_key_owner_proof(&parent_id)? {
+			Some(proof) => proof,
+			None => match generate_key_owner_proof(&best_id)? {
+				Some(proof) => proof,
+				None => {
+					debug!(target: "babe", "Equivocation offender is not part of the authority set.");
+					return Ok(());
+				}
+			},
+		};
+
+		// submit equivocation report at best block.
+		self.api
+			.runtime_api()
+			.submit_report_equivocation_unsigned_extrinsic(
+				&best_id,
+				equivocation_proof,
+				key_owner_proof,
+			)
+			.map_err(Error::Client)?;
+
+		info!(target: "babe", "Submitted equivocation report for author {:?}", author);
+
+		Ok(())
+	}
 }
 
 #[allow(dead_code)]
@@ -758,21 +850,16 @@ impl<B, E, Block, RA, PRA> Verifier<Block> for BabeVerifier<B, E, Block, RA, PRA
 				let author = verified_info.author;
 
 				// the header is valid but let's check if there was something else already
-				// proposed at the same slot by the given author
-				if let Some(equivocation_proof) = check_equivocation(
-					&*self.api,
+				// proposed at the same slot by the given author. if there was, we will
+				// report the equivocation to the runtime.
+				if let Err(err) = self.check_and_report_equivocation(
 					slot_now,
-					babe_pre_digest.slot_number(),
+					slot_number,
 					&header,
 					&author,
-				).map_err(|e| e.to_string())? {
-					info!(
-						"Slot author {:?} is equivocating at slot {} with headers {:?} and {:?}",
-						author,
-						babe_pre_digest.slot_number(),
-						equivocation_proof.fst_header().hash(),
-						equivocation_proof.snd_header().hash(),
-					);
+					&origin,
+				) {
+					warn!(target: "babe", "Error checking/reporting BABE equivocation: {:?}", err);
 				}
 
 				// if the body is passed through, we need to use the runtime
@@ -916,8 +1003,13 @@ impl<B, E, Block, I, RA, PRA> BlockImport<Block> for BabeBlockImport<B, E, Block
 		let number = block.header.number().clone();
 
 		// early exit if block already in chain, otherwise the check for
-		// epoch changes will error when trying to re-import an epoch change
+		// epoch changes will error when trying to re-import an epoch change.
+		// blocks that are imported again to add their state only need to be executed.
 		match self.client.status(BlockId::Hash(hash)) {
+			Ok(sp_blockchain::BlockStatus::InChain) if block.import_existing => {
+				block.justification = None;
+				return self.inner.import_block(block, new_cache).map_err(Into::into)
+			},
 			Ok(sp_blockchain::BlockStatus::InChain) => return Ok(ImportResult::AlreadyInChain),
 			Ok(sp_blockchain::BlockStatus::Unknown) => {},
 			Err(e) => return Err(ConsensusError::ClientImport(e.to_string())),
@@ -0,0 +1,276 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State backend that's useful for benchmarking
+
+use std::sync::Arc;
+use std::path::PathBuf;
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+
+use hash_db::Prefix;
+use kvdb::{KeyValueDB, DBTransaction};
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use sp_trie::{MemoryDB, prefixed_key};
+use sp_core::storage::ChildInfo;
+use sp_runtime::traits::{Block as BlockT, HasherFor};
+use sp_runtime::Storage;
+use sp_state_machine::{DBValue, backend::Backend as StateBackend};
+
+use crate::utils::db_err;
+
+type DbState<B> = sp_state_machine::TrieBackend<
+	Arc<dyn sp_state_machine::Storage<HasherFor<B>>>, HasherFor<B>
+>;
+
+type DbTransaction<B> = <DbState<B> as StateBackend<HasherFor<B>>>::Transaction;
+
+/// The only column used by the benchmarking database.
+const STATE_COLUMN: u32 = 0;
+
+struct StorageDb<Block: BlockT> {
+	db: Arc<dyn KeyValueDB>,
+	_block: PhantomData<Block>,
+}
+
+impl<Block: BlockT> sp_state_machine::Storage<HasherFor<Block>> for StorageDb<Block> {
+	fn get(&self, key: &Block::Hash, prefix: Prefix) -> Result<Option<DBValue>, String> {
+		let key = prefixed_key::<HasherFor<Block>>(key, prefix);
+		self.db.get(STATE_COLUMN, &key)
+			.map_err(|e| format!("Database backend error: {:?}", e))
+	}
+}
+
+/// State that manages the backend database reference. Allows runtime to control the database.
+///
+/// The state is backed by a RocksDB database in a temporary directory, which is removed once
+/// the state is dropped. The database can be reset to the genesis state with `wipe` and
+/// changes made by the runtime can be persisted with `commit`.
+pub struct BenchmarkingState<B: BlockT> {
+	path: PathBuf,
+	db: Arc<dyn KeyValueDB>,
+	root: Cell<B::Hash>,
+	state: RefCell<DbState<B>>,
+	genesis: DbTransaction<B>,
+	genesis_root: B::Hash,
+}
+
+impl<B: BlockT> BenchmarkingState<B> {
+	/// Create a new instance that creates a database in a temporary dir and
+	/// populates it with the given genesis storage.
+	pub fn new(genesis: Storage) -> Result<Self, String> {
+		let path = std::env::temp_dir().join(format!("substrate-benchmark-{}", std::process::id()));
+		let path_str = path.to_str().ok_or_else(|| "Invalid database path".to_string())?;
+
+		let db_config = DatabaseConfig::with_columns(1);
+		let db: Arc<dyn KeyValueDB> = Arc::new(
+			Database::open(&db_config, path_str).map_err(|e| format!("{:?}", db_err(e)))?
+		);
+
+		let mut root = B::Hash::default();
+		let mut mdb = MemoryDB::<HasherFor<B>>::default();
+		sp_state_machine::TrieDBMut::<HasherFor<B>>::new(&mut mdb, &mut root);
+
+		let state = Self::open_state(&db, root);
+		let (genesis_root, genesis_transaction) = state.full_storage_root(
+			genesis.top.into_iter().map(|(k, v)| (k, Some(v))),
+			genesis.children.into_iter().map(|(storage_key, child)|
+				(storage_key, child.data.into_iter().map(|(k, v)| (k, Some(v))), child.child_info)
+			),
+		);
+
+		let state = BenchmarkingState {
+			path,
+			db,
+			root: Cell::new(root),
+			state: RefCell::new(state),
+			genesis: genesis_transaction.clone(),
+			genesis_root,
+		};
+
+		state.commit(genesis_root, genesis_transaction)?;
+
+		Ok(state)
+	}
+
+	fn open_state(db: &Arc<dyn KeyValueDB>, root: B::Hash) -> DbState<B> {
+		let storage: Arc<dyn sp_state_machine::Storage<HasherFor<B>>> = Arc::new(StorageDb::<B> {
+			db: db.clone(),
+			_block: Default::default(),
+		});
+
+		DbState::<B>::new(storage, root)
+	}
+
+	fn reopen(&self) {
+		*self.state.borrow_mut() = Self::open_state(&self.db, self.root.get());
+	}
+}
+
+impl<B: BlockT> StateBackend<HasherFor<B>> for BenchmarkingState<B> {
+	type Error = <DbState<B> as StateBackend<HasherFor<B>>>::Error;
+	type Transaction = <DbState<B> as StateBackend<HasherFor<B>>>::Transaction;
+	type TrieBackendStorage = <DbState<B> as StateBackend<HasherFor<B>>>::TrieBackendStorage;
+
+	fn storage(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.state.borrow().storage(key)
+	}
+
+	fn storage_hash(&self, key: &[u8]) -> Result<Option<B::Hash>, Self::Error> {
+		self.state.borrow().storage_hash(key)
+	}
+
+	fn child_storage(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		key: &[u8],
+	) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.state.borrow().child_storage(storage_key, child_info, key)
+	}
+
+	fn exists_storage(&self, key: &[u8]) -> Result<bool, Self::Error> {
+		self.state.borrow().exists_storage(key)
+	}
+
+	fn exists_child_storage(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		key: &[u8],
+	) -> Result<bool, Self::Error> {
+		self.state.borrow().exists_child_storage(storage_key, child_info, key)
+	}
+
+	fn next_storage_key(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.state.borrow().next_storage_key(key)
+	}
+
+	fn next_child_storage_key(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		key: &[u8],
+	) -> Result<Option<Vec<u8>>, Self::Error> {
+		self.state.borrow().next_child_storage_key(storage_key, child_info, key)
+	}
+
+	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], f: F) {
+		self.state.borrow().for_keys_with_prefix(prefix, f)
+	}
+
+	fn for_key_values_with_prefix<F: FnMut(&[u8], &[u8])>(&self, prefix: &[u8], f: F) {
+		self.state.borrow().for_key_values_with_prefix(prefix, f)
+	}
+
+	fn for_keys_in_child_storage<F: FnMut(&[u8])>(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		f: F,
+	) {
+		self.state.borrow().for_keys_in_child_storage(storage_key, child_info, f)
+	}
+
+	fn for_child_keys_with_prefix<F: FnMut(&[u8])>(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		prefix: &[u8],
+		f: F,
+	) {
+		self.state.borrow().for_child_keys_with_prefix(storage_key, child_info, prefix, f)
+	}
+
+	fn storage_root<I>(&self, delta: I) -> (B::Hash, Self::Transaction)
+		where
+			I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>
+	{
+		self.state.borrow().storage_root(delta)
+	}
+
+	fn child_storage_root<I>(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		delta: I,
+	) -> (B::Hash, bool, Self::Transaction)
+		where
+			I: IntoIterator<Item=(Vec<u8>, Option<Vec<u8>>)>,
+	{
+		self.state.borrow().child_storage_root(storage_key, child_info, delta)
+	}
+
+	fn pairs(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+		self.state.borrow().pairs()
+	}
+
+	fn keys(&self, prefix: &[u8]) -> Vec<Vec<u8>> {
+		self.state.borrow().keys(prefix)
+	}
+
+	fn child_keys(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		prefix: &[u8],
+	) -> Vec<Vec<u8>> {
+		self.state.borrow().child_keys(storage_key, child_info, prefix)
+	}
+
+	fn commit(&self, storage_root: B::Hash, mut transaction: Self::Transaction) -> Result<(), String> {
+		let mut db_transaction = DBTransaction::new();
+
+		for (key, (val, rc)) in transaction.drain() {
+			if rc > 0 {
+				db_transaction.put(STATE_COLUMN, &key, &val);
+			} else if rc < 0 {
+				db_transaction.delete(STATE_COLUMN, &key);
+			}
+		}
+
+		self.db.write(db_transaction).map_err(|e| format!("Error writing to database: {:?}", e))?;
+		self.root.set(storage_root);
+		self.reopen();
+
+		Ok(())
+	}
+
+	fn wipe(&self) -> Result<(), String> {
+		let mut db_transaction = DBTransaction::new();
+
+		for (key, _) in self.db.iter(STATE_COLUMN) {
+			db_transaction.delete(STATE_COLUMN, &key);
+		}
+
+		self.db.write(db_transaction).map_err(|e| format!("Error wiping database: {:?}", e))?;
+
+		// the trie is empty now, restore the genesis state.
+		self.commit(self.genesis_root, self.genesis.clone())
+	}
+}
+
+impl<B: BlockT> Drop for BenchmarkingState<B> {
+	fn drop(&mut self) {
+		let _ = std::fs::remove_dir_all(&self.path);
+	}
+}
+
+impl<Block: BlockT> std::fmt::Debug for BenchmarkingState<Block> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "Bench DB")
+	}
+}
@@ -37,6 +37,8 @@ mod storage_cache;
 mod upgrade;
 mod utils;
 mod stats;
+#[cfg(feature = "kvdb-rocksdb")]
+mod bench;
 
 use std::sync::Arc;
 use std::path::PathBuf;
@@ -56,7 +58,7 @@ use kvdb::{KeyValueDB, DBTransaction};
 use sp_trie::{MemoryDB, PrefixedMemoryDB, prefixed_key};
 use parking_lot::RwLock;
 use sp_core::{ChangesTrieConfiguration, traits::CodeExecutor};
-use sp_core::storage::{well_known_keys, ChildInfo};
+use sp_core::storage::{well_known_keys, ChildInfo, OwnedChildInfo};
 use sp_runtime::{
 	generic::BlockId, Justification, Storage,
 	BuildStorage,
@@ -80,6 +82,9 @@ use crate::stats::StateUsageStats;
 use log::{trace, debug, warn};
 pub use sc_state_db::PruningMode;
 
+#[cfg(feature = "kvdb-rocksdb")]
+pub use bench::BenchmarkingState;
+
 #[cfg(feature = "test-helpers")]
 use sc_client::in_mem::Backend as InMemoryBackend;
 
@@ -283,6 +288,7 @@ pub fn new_client<E, S, Block, RA>(
 	fork_blocks: ForkBlocks<Block>,
 	bad_blocks: BadBlocks<Block>,
 	execution_extensions: ExecutionExtensions<Block>,
+	prometheus_registry: Option<prometheus_endpoint::Registry>,
 ) -> Result<(
 		sc_client::Client<
 			Backend<Block>,
@@ -309,6 +315,7 @@ pub fn new_client<E, S, Block, RA>(
 			fork_blocks,
 			bad_blocks,
 			execution_extensions,
+			prometheus_registry,
 		)?,
 		backend,
 	))
@@ -516,6 +523,7 @@ pub struct BlockImportOperation<Block: BlockT> {
 	db_updates: PrefixedMemoryDB<HasherFor<Block>>,
 	storage_updates: StorageCollection,
 	child_storage_updates: ChildStorageCollection,
+	offchain_storage_updates: StorageCollection,
 	changes_trie_updates: MemoryDB<HasherFor<Block>>,
 	changes_trie_build_cache_update: Option<ChangesTrieCacheAction<Block::Hash, NumberFor<Block>>>,
 	changes_trie_config_update: Option<Option<ChangesTrieConfiguration>>,
@@ -524,6 +532,7 @@ pub struct BlockImportOperation<Block: BlockT> {
 	finalized_blocks: Vec<(BlockId<Block>, Option<Justification>)>,
 	set_head: Option<BlockId<Block>>,
 	commit_state: bool,
+	imported_state: Option<Block::Hash>,
 }
 
 impl<Block: BlockT> BlockImportOperation<Block> {
@@ -535,6 +544,16 @@ impl<Block: BlockT> BlockImportOperation<Block> {
 			}
 		}
 	}
+
+	fn apply_offchain(&mut self, transaction: &mut DBTransaction) {
+		for (key, maybe_val) in self.offchain_storage_updates.drain(..) {
+			let key: Vec<u8> = sp_offchain::STORAGE_PREFIX.iter().chain(&key).cloned().collect();
+			match maybe_val {
+				Some(val) => transaction.put_vec(columns::OFFCHAIN, &key, val),
+				None => transaction.delete(columns::OFFCHAIN, &key),
+			}
+		}
+	}
 }
 
 impl<Block: BlockT> sc_client_api::backend::BlockImportOperation<Block> for BlockImportOperation<Block> {
@@ -613,6 +632,33 @@ impl<Block: BlockT> sc_client_api::backend::BlockImportOperation<Block> for Bloc
 		Ok(root)
 	}
 
+	fn import_state(
+		&mut self,
+		block: Block::Hash,
+		top: &mut dyn Iterator<Item = (Vec<u8>, Vec<u8>)>,
+	) -> ClientResult<Block::Hash> {
+		// the child tries can't be imported, because their roots don't tell where their
+		// nodes are stored.
+		let mut has_child_tries = false;
+		let child_delta = std::iter::empty::<(Vec<u8>, std::vec::IntoIter<(Vec<u8>, Option<Vec<u8>>)>, OwnedChildInfo)>();
+		let (root, transaction) = self.old_state.full_storage_root(
+			top.map(|(k, v)| {
+				has_child_tries |= well_known_keys::is_child_storage_key(&k);
+				(k, Some(v))
+			}),
+			child_delta,
+		);
+		if has_child_tries {
+			return Err(sp_blockchain::Error::Backend(
+				"Importing state with child tries is not supported".into()
+			));
+		}
+
+		self.db_updates = transaction;
+		self.imported_state = Some(block);
+		Ok(root)
+	}
+
 	fn update_changes_trie(
 		&mut self,
 		update: ChangesTrieTransaction<HasherFor<Block>, NumberFor<Block>>,
@@ -639,6 +685,14 @@ impl<Block: BlockT> sc_client_api::backend::BlockImportOperation<Block> for Bloc
 		Ok(())
 	}
 
+	fn update_offchain_storage(
+		&mut self,
+		offchain_update: StorageCollection,
+	) -> ClientResult<()> {
+		self.offchain_storage_updates = offchain_update;
+		Ok(())
+	}
+
 	fn mark_finalized(
 		&mut self,
 		block: BlockId<Block>,
@@ -1019,6 +1073,12 @@ impl<Block: BlockT> Backend<Block> {
 						implies existence of blocks with all numbers before it; qed")
 			};
 
+			// blocks imported without state (e.g. headers downloaded before the state) are
+			// never inserted into the state db.
+			if self.storage.state_db.is_pruned(&hash, new_canonical) {
+				return Ok(())
+			}
+
 			trace!(target: "db", "Canonicalize block #{} ({:?})", new_canonical, hash);
 			let commit = self.storage.state_db.canonicalize_block(&hash)
 				.map_err(|e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
@@ -1028,6 +1088,31 @@ impl<Block: BlockT> Backend<Block> {
 		Ok(())
 	}
 
+	// canonicalizes all blocks with state up to the given one, which must be on the finalized chain.
+	fn canonicalize_up_to(
+		&self,
+		transaction: &mut DBTransaction,
+		hash: Block::Hash,
+		number: u64,
+	) -> ClientResult<()> {
+		let best_canonical = self.storage.state_db.best_canonical().unwrap_or(0);
+		for n in (best_canonical + 1)..=number {
+			let hash = if n == number {
+				hash
+			} else {
+				::sc_client::blockchain::HeaderBackend::hash(&self.blockchain, n.saturated_into())?
+					.expect("existence of block with number `number` \
+						implies existence of blocks with all numbers before it; qed")
+			};
+
+			trace!(target: "db", "Canonicalize block #{} ({:?})", n, hash);
+			let commit = self.storage.state_db.canonicalize_block(&hash)
+				.map_err(|e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
+			apply_state_commit(transaction, commit);
+		}
+		Ok(())
+	}
+
 	fn try_commit_operation(&self, mut operation: BlockImportOperation<Block>)
 		-> ClientResult<()>
 	{
@@ -1035,6 +1120,7 @@ impl<Block: BlockT> Backend<Block> {
 		let mut finalization_displaced_leaves = None;
 
 		operation.apply_aux(&mut transaction);
+		operation.apply_offchain(&mut transaction);
 
 		let mut meta_updates = Vec::with_capacity(operation.finalized_blocks.len());
 		let mut last_finalized_hash = self.blockchain.meta.read().finalized_hash;
@@ -1056,6 +1142,24 @@ impl<Block: BlockT> Backend<Block> {
 			last_finalized_hash = block_hash;
 		}
 
+		if let Some(hash) = operation.imported_state {
+			let number = self.blockchain.expect_header(BlockId::Hash(hash))?.number().saturated_into::<u64>();
+			let mut changeset: sc_state_db::ChangeSet<Vec<u8>> = sc_state_db::ChangeSet::default();
+			let mut bytes: u64 = 0;
+			for (key, (val, rc)) in operation.db_updates.drain() {
+				if rc > 0 {
+					bytes += key.len() as u64 + val.len() as u64;
+					changeset.inserted.push((key, val.to_vec()));
+				}
+			}
+			self.state_usage.tally_writes(changeset.inserted.len() as u64, bytes);
+
+			debug!(target: "db", "Importing state of #{} ({:?})", number, hash);
+			let commit = self.storage.state_db.import_canonical_state(&hash, number, changeset)
+				.map_err(|e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
+			apply_state_commit(&mut transaction, commit);
+		}
+
 		let imported = if let Some(pending_block) = operation.pending_block {
 			let hash = pending_block.header.hash();
 			let parent_hash = *pending_block.header.parent_hash();
@@ -1064,7 +1168,16 @@ impl<Block: BlockT> Backend<Block> {
 			// blocks are keyed by number + hash.
 			let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
 
-			let (enacted, retracted) = if pending_block.leaf_state.is_best() {
+			// a block that has been imported without state before, and is now imported again
+			// with its state.
+			let existing = read_db(
+				&*self.storage.db,
+				columns::KEY_LOOKUP,
+				columns::HEADER,
+				BlockId::<Block>::Hash(hash),
+			)?.is_some();
+
+			let (enacted, retracted) = if pending_block.leaf_state.is_best() && !existing {
 				self.set_head_with_transaction(&mut transaction, parent_hash, (number, hash))?
 			} else {
 				(Default::default(), Default::default())
@@ -1125,9 +1238,16 @@ impl<Block: BlockT> Backend<Block> {
 					.map_err(|e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
 				apply_state_commit(&mut transaction, commit);
 
-				// Check if need to finalize. Genesis is always finalized instantly.
-				let finalized = number_u64 == 0 || pending_block.leaf_state.is_final();
-				finalized
+				if existing {
+					// the block may have been finalized before its state was known.
+					if number <= self.blockchain.meta.read().finalized_number {
+						self.canonicalize_up_to(&mut transaction, hash, number_u64)?;
+					}
+					false
+				} else {
+					// Check if need to finalize. Genesis is always finalized instantly.
+					number_u64 == 0 || pending_block.leaf_state.is_final()
+				}
 			} else {
 				false
 			};
@@ -1170,19 +1290,26 @@ impl<Block: BlockT> Backend<Block> {
 
 			debug!(target: "db", "DB Commit {:?} ({}), best = {}", hash, number, is_best);
 
-			let displaced_leaf = {
-				let mut leaves = self.blockchain.leaves.write();
-				let displaced_leaf = leaves.import(hash, number, parent_hash);
-				leaves.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+			let displaced_leaf = if existing {
+				None
+			} else {
+				let displaced_leaf = {
+					let mut leaves = self.blockchain.leaves.write();
+					let displaced_leaf = leaves.import(hash, number, parent_hash);
+					leaves.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
 
-				displaced_leaf
-			};
+					displaced_leaf
+				};
+
+				let mut children = children::read_children(&*self.storage.db, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash)?;
+				children.push(hash);
+				children::write_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash, children);
 
-			let mut children = children::read_children(&*self.storage.db, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash)?;
-			children.push(hash);
-			children::write_children(&mut transaction, columns::META, meta_keys::CHILDREN_PREFIX, parent_hash, children);
+				meta_updates.push((hash, number, pending_block.leaf_state.is_best(), finalized));
 
-			meta_updates.push((hash, number, pending_block.leaf_state.is_best(), finalized));
+				displaced_leaf
+			};
+			let is_best = is_best && !existing;
 
 			Some((number, hash, enacted, retracted, displaced_leaf, is_best, cache))
 		} else {
@@ -1279,9 +1406,11 @@ impl<Block: BlockT> Backend<Block> {
 			let lookup_key = utils::number_and_hash_to_lookup_key(f_num, f_hash.clone())?;
 			transaction.put(columns::META, meta_keys::FINALIZED_BLOCK, &lookup_key);
 
-			let commit = self.storage.state_db.canonicalize_block(&f_hash)
-				.map_err(|e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
-			apply_state_commit(transaction, commit);
+			if !self.storage.state_db.is_pruned(&f_hash, f_num.saturated_into::<u64>()) {
+				let commit = self.storage.state_db.canonicalize_block(&f_hash)
+					.map_err(|e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
+				apply_state_commit(transaction, commit);
+			}
 
 			if !f_num.is_zero() {
 				let new_changes_trie_cache_ops = self.changes_tries_storage.finalize(
@@ -1359,6 +1488,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			db_updates: PrefixedMemoryDB::default(),
 			storage_updates: Default::default(),
 			child_storage_updates: Default::default(),
+			offchain_storage_updates: Default::default(),
 			changes_trie_config_update: None,
 			changes_trie_updates: MemoryDB::default(),
 			changes_trie_build_cache_update: None,
@@ -1366,6 +1496,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			finalized_blocks: Vec::new(),
 			set_head: None,
 			commit_state: false,
+			imported_state: None,
 		})
 	}
 
@@ -1845,6 +1976,91 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn offchain_storage_is_written_with_the_block() {
+		use sp_core::offchain::OffchainStorage;
+
+		let db = Backend::<Block>::new_test(2, 0);
+		let mut op = db.begin_operation().unwrap();
+		db.begin_state_operation(&mut op, BlockId::Hash(Default::default())).unwrap();
+		let mut header = Header {
+			number: 0,
+			parent_hash: Default::default(),
+			state_root: Default::default(),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		header.state_root = op.old_state.storage_root(std::iter::empty()).0.into();
+
+		op.reset_storage(Default::default()).unwrap();
+		op.set_block_data(header, Some(vec![]), None, NewBlockState::Best).unwrap();
+		op.update_offchain_storage(vec![
+			(b"indexed".to_vec(), Some(b"value".to_vec())),
+			(b"removed".to_vec(), None),
+		]).unwrap();
+
+		let mut offchain = db.offchain_storage().unwrap();
+		offchain.set(sp_offchain::STORAGE_PREFIX, b"removed", b"old");
+		assert_eq!(offchain.get(sp_offchain::STORAGE_PREFIX, b"indexed"), None);
+
+		db.commit_operation(op).unwrap();
+
+		assert_eq!(offchain.get(sp_offchain::STORAGE_PREFIX, b"indexed"), Some(b"value".to_vec()));
+		assert_eq!(offchain.get(sp_offchain::STORAGE_PREFIX, b"removed"), None);
+	}
+
+	#[test]
+	fn import_state_of_block_imported_without_state() {
+		let db = Backend::<Block>::new_test(2, 0);
+		let genesis = insert_header(&db, 0, Default::default(), None, Default::default());
+
+		let storage = vec![
+			(vec![1, 3, 5], vec![2, 4, 6]),
+			(vec![1, 2, 3], vec![9, 9, 9]),
+		];
+		let header = Header {
+			number: 1,
+			parent_hash: genesis,
+			state_root: BlakeTwo256::trie_root(storage.clone()),
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		let hash = header.hash();
+
+		let mut op = db.begin_operation().unwrap();
+		op.set_block_data(header.clone(), None, None, NewBlockState::Best).unwrap();
+		db.commit_operation(op).unwrap();
+		db.finalize_block(BlockId::Hash(hash), None).unwrap();
+		assert!(!db.have_state_at(&hash, 1));
+
+		let mut op = db.begin_operation().unwrap();
+		let root = op.import_state(hash, &mut storage.iter().cloned()).unwrap();
+		assert_eq!(root, header.state_root);
+		db.commit_operation(op).unwrap();
+
+		assert!(db.have_state_at(&hash, 1));
+		let state = db.state_at(BlockId::Number(1)).unwrap();
+		assert_eq!(state.storage(&[1, 3, 5]).unwrap(), Some(vec![2, 4, 6]));
+		assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![9, 9, 9]));
+
+		let hash2 = insert_header(&db, 2, hash, None, Default::default());
+		assert!(db.have_state_at(&hash2, 2));
+	}
+
+	#[test]
+	fn import_state_with_child_tries_is_rejected() {
+		let db = Backend::<Block>::new_test(2, 0);
+		let genesis = insert_header(&db, 0, Default::default(), None, Default::default());
+		let hash = insert_header(&db, 1, genesis, None, Default::default());
+
+		let mut child_root_key = well_known_keys::CHILD_STORAGE_KEY_PREFIX.to_vec();
+		child_root_key.extend_from_slice(b"default:child");
+		let storage = vec![(vec![1, 2, 3], vec![9, 9, 9]), (child_root_key, vec![0; 32])];
+
+		let mut op = db.begin_operation().unwrap();
+		assert!(op.import_state(hash, &mut storage.into_iter()).is_err());
+	}
+
 	#[test]
 	fn delete_only_when_negative_rc() {
 		let _ = ::env_logger::try_init();
@@ -16,13 +16,24 @@
 
 //! Definitions for a wasm runtime.
 
-use crate::error::Error;
+use crate::error::{Error, WasmError};
 use sp_wasm_interface::Function;
 
+/// A trait that defines an abstract wasm module, parsed or compiled once, that runtime instances
+/// can be created from.
+///
+/// Modules are shared by all the instances of the same code, possibly on different threads, so
+/// they need to be `Send` and `Sync`.
+pub trait WasmModule: Send + Sync {
+	/// Create a new runtime instance of the module.
+	fn new_instance(&self) -> Result<Box<dyn WasmRuntime>, WasmError>;
+}
+
 /// A trait that defines an abstract wasm runtime.
 ///
-/// This can be implemented by an execution engine.
-pub trait WasmRuntime {
+/// This can be implemented by an execution engine. Runtimes are pooled and handed to different
+/// threads, one at a time, so they need to be `Send`.
+pub trait WasmRuntime: Send {
 	/// Attempt to update the number of heap pages available during execution.
 	///
 	/// Returns false if the update cannot be applied. The function is guaranteed to return true if
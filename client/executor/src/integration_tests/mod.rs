@@ -188,6 +188,99 @@ fn panicking_should_work(wasm_method: WasmExecutionMethod) {
 	assert!(output.is_err());
 }
 
+#[test_case(WasmExecutionMethod::Interpreted)]
+#[cfg_attr(feature = "wasmtime", test_case(WasmExecutionMethod::Compiled))]
+fn runtime_instance_should_be_reusable(wasm_method: WasmExecutionMethod) {
+	let mut ext = TestExternalities::default();
+	let mut ext = ext.ext();
+	let mut runtime = crate::wasm_runtime::create_wasm_runtime_with_code(
+		wasm_method,
+		8,
+		&WASM_BINARY[..],
+		<HostFunctions as sp_wasm_interface::HostFunctions>::host_functions(),
+		true,
+	).unwrap();
+
+	let mut call = |method: &str, data: &[u8]| {
+		let mut runtime = std::panic::AssertUnwindSafe(&mut runtime);
+		crate::native_executor::with_externalities_safe(&mut ext, move || runtime.call(method, data))
+			.and_then(|r| r)
+	};
+
+	let expected = blake2_256(&b"Hello world!"[..]).to_vec().encode();
+	let input = b"Hello world!".to_vec().encode();
+	assert_eq!(call("test_blake2_256", &input).unwrap(), expected);
+	assert!(call("test_exhaust_heap", &[]).is_err());
+	assert_eq!(call("test_blake2_256", &input).unwrap(), expected);
+	assert!(call("test_panic", &[]).is_err());
+	assert_eq!(call("test_blake2_256", &input).unwrap(), expected);
+}
+
+#[test_case(WasmExecutionMethod::Interpreted)]
+#[cfg_attr(feature = "wasmtime", test_case(WasmExecutionMethod::Compiled))]
+fn instances_of_a_module_should_be_independent(wasm_method: WasmExecutionMethod) {
+	let mut ext = TestExternalities::default();
+	let mut ext = ext.ext();
+	let module = crate::wasm_runtime::create_wasm_module_with_code(
+		wasm_method,
+		8,
+		&WASM_BINARY[..],
+		<HostFunctions as sp_wasm_interface::HostFunctions>::host_functions(),
+		true,
+	).unwrap();
+	let mut first = module.new_instance().unwrap();
+	let mut second = module.new_instance().unwrap();
+
+	let mut call = |runtime: &mut Box<dyn crate::WasmRuntime>, method: &str, data: &[u8]| {
+		let mut runtime = std::panic::AssertUnwindSafe(runtime);
+		crate::native_executor::with_externalities_safe(&mut ext, move || runtime.call(method, data))
+			.and_then(|r| r)
+	};
+
+	let expected = blake2_256(&b"Hello world!"[..]).to_vec().encode();
+	let input = b"Hello world!".to_vec().encode();
+	assert!(call(&mut first, "test_exhaust_heap", &[]).is_err());
+	assert_eq!(call(&mut second, "test_blake2_256", &input).unwrap(), expected);
+	assert!(call(&mut second, "test_panic", &[]).is_err());
+	assert_eq!(call(&mut first, "test_blake2_256", &input).unwrap(), expected);
+	assert_eq!(call(&mut second, "test_blake2_256", &input).unwrap(), expected);
+}
+
+#[test_case(WasmExecutionMethod::Interpreted)]
+#[cfg_attr(feature = "wasmtime", test_case(WasmExecutionMethod::Compiled))]
+fn instances_of_a_module_should_run_on_different_threads(wasm_method: WasmExecutionMethod) {
+	let module = crate::wasm_runtime::create_wasm_module_with_code(
+		wasm_method,
+		8,
+		&WASM_BINARY[..],
+		<HostFunctions as sp_wasm_interface::HostFunctions>::host_functions(),
+		true,
+	).unwrap();
+
+	let threads = (0..4).map(|_| {
+		let mut runtime = module.new_instance().unwrap();
+		std::thread::spawn(move || {
+			let mut ext = TestExternalities::default();
+			let mut ext = ext.ext();
+			let expected = blake2_256(&b"Hello world!"[..]).to_vec().encode();
+			let input = b"Hello world!".to_vec().encode();
+			for _ in 0..10 {
+				let mut runtime = std::panic::AssertUnwindSafe(&mut runtime);
+				let input = &input;
+				let output = crate::native_executor::with_externalities_safe(
+					&mut ext,
+					move || runtime.call("test_blake2_256", input),
+				).and_then(|r| r);
+				assert_eq!(output.unwrap(), expected);
+			}
+		})
+	}).collect::<Vec<_>>();
+
+	for thread in threads {
+		thread.join().unwrap();
+	}
+}
+
 #[test_case(WasmExecutionMethod::Interpreted)]
 #[cfg_attr(feature = "wasmtime", test_case(WasmExecutionMethod::Compiled))]
 fn storage_should_work(wasm_method: WasmExecutionMethod) {
@@ -46,9 +46,9 @@ pub use codec::Codec;
 pub use sp_core::traits::Externalities;
 #[doc(hidden)]
 pub use sp_wasm_interface;
-pub use wasm_runtime::WasmExecutionMethod;
+pub use wasm_runtime::{WasmExecutionMethod, create_wasm_module_with_code, create_wasm_runtime_with_code};
 
-pub use sc_executor_common::{error, allocator, sandbox};
+pub use sc_executor_common::{error, allocator, sandbox, wasm_runtime::WasmRuntime};
 
 /// Call the given `function` in the given wasm `code`.
 ///
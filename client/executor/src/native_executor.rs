@@ -22,14 +22,10 @@ use sp_version::{NativeVersion, RuntimeVersion};
 use codec::{Decode, Encode};
 use sp_core::{NativeOrEncoded, traits::{CodeExecutor, Externalities}};
 use log::trace;
-use std::{result, cell::RefCell, panic::{UnwindSafe, AssertUnwindSafe}, sync::Arc};
+use std::{result, panic::{UnwindSafe, AssertUnwindSafe}, sync::Arc};
 use sp_wasm_interface::{HostFunctions, Function};
 use sc_executor_common::wasm_runtime::WasmRuntime;
 
-thread_local! {
-	static RUNTIMES_CACHE: RefCell<RuntimesCache> = RefCell::new(RuntimesCache::new());
-}
-
 /// Default num of pages for the heap
 const DEFAULT_HEAP_PAGES: u64 = 1024;
 
@@ -88,6 +84,8 @@ pub struct NativeExecutor<D> {
 	default_heap_pages: u64,
 	/// The host functions registered with this instance.
 	host_functions: Arc<Vec<&'static dyn Function>>,
+	/// Pool of runtime instances shared between all clones of this executor.
+	cache: Arc<RuntimesCache>,
 }
 
 impl<D: NativeExecutionDispatch> NativeExecutor<D> {
@@ -99,7 +97,13 @@ impl<D: NativeExecutionDispatch> NativeExecutor<D> {
 	///
 	/// `default_heap_pages` - Number of 64KB pages to allocate for Wasm execution.
 	/// 	Defaults to `DEFAULT_HEAP_PAGES` if `None` is provided.
-	pub fn new(fallback_method: WasmExecutionMethod, default_heap_pages: Option<u64>) -> Self {
+	///
+	/// `max_runtime_instances` - The maximal number of idle runtime instances kept in the cache.
+	pub fn new(
+		fallback_method: WasmExecutionMethod,
+		default_heap_pages: Option<u64>,
+		max_runtime_instances: usize,
+	) -> Self {
 		let mut host_functions = sp_io::SubstrateHostFunctions::host_functions();
 		// Add the old and deprecated host functions as well, so that we support old wasm runtimes.
 		host_functions.extend(
@@ -115,20 +119,21 @@ impl<D: NativeExecutionDispatch> NativeExecutor<D> {
 			native_version: D::native_version(),
 			default_heap_pages: default_heap_pages.unwrap_or(DEFAULT_HEAP_PAGES),
 			host_functions: Arc::new(host_functions),
+			cache: Arc::new(RuntimesCache::new(max_runtime_instances)),
 		}
 	}
 
 	/// Execute the given closure `f` with the latest runtime (based on the `CODE` key in `ext`).
 	///
 	/// The closure `f` is expected to return `Err(_)` when there happened a `panic!` in native code
-	/// while executing the runtime in Wasm. If a `panic!` occurred, the runtime is invalidated to
-	/// prevent any poisoned state. Native runtime execution does not need to report back
+	/// while executing the runtime in Wasm. If a `panic!` occurred, the runtime instance is dropped
+	/// to prevent any poisoned state. Native runtime execution does not need to report back
 	/// any `panic!`.
 	///
 	/// # Safety
 	///
 	/// `runtime` and `ext` are given as `AssertUnwindSafe` to the closure. As described above, the
-	/// runtime is invalidated on any `panic!` to prevent a poisoned state. `ext` is already
+	/// runtime instance is dropped on any `panic!` to prevent a poisoned state. `ext` is already
 	/// implicitly handled as unwind safe, as we store it in a global variable while executing the
 	/// native runtime.
 	fn with_runtime<E, R>(
@@ -140,26 +145,13 @@ impl<D: NativeExecutionDispatch> NativeExecutor<D> {
 			AssertUnwindSafe<&'a mut E>,
 		) -> Result<Result<R>>,
 	) -> Result<R> where E: Externalities {
-		RUNTIMES_CACHE.with(|cache| {
-			let mut cache = cache.borrow_mut();
-			let (runtime, version, code_hash) = cache.fetch_runtime(
-				ext,
-				self.fallback_method,
-				self.default_heap_pages,
-				&*self.host_functions,
-			)?;
-
-			let runtime = AssertUnwindSafe(runtime);
-			let ext = AssertUnwindSafe(ext);
-
-			match f(runtime, version, ext) {
-				Ok(res) => res,
-				Err(e) => {
-					cache.invalidate_runtime(self.fallback_method, code_hash);
-					Err(e)
-				}
-			}
-		})
+		self.cache.with_instance(
+			ext,
+			self.fallback_method,
+			self.default_heap_pages,
+			&*self.host_functions,
+			|runtime, version, ext| f(AssertUnwindSafe(runtime), version, AssertUnwindSafe(ext)),
+		)
 	}
 }
 
@@ -171,6 +163,7 @@ impl<D: NativeExecutionDispatch> Clone for NativeExecutor<D> {
 			native_version: D::native_version(),
 			default_heap_pages: self.default_heap_pages,
 			host_functions: self.host_functions.clone(),
+			cache: self.cache.clone(),
 		}
 	}
 }
@@ -380,7 +373,7 @@ mod tests {
 
 	#[test]
 	fn native_executor_registers_custom_interface() {
-		let executor = NativeExecutor::<MyExecutor>::new(WasmExecutionMethod::Interpreted, None);
+		let executor = NativeExecutor::<MyExecutor>::new(WasmExecutionMethod::Interpreted, None, 8);
 		my_interface::HostFunctions::host_functions().iter().for_each(|function| {
 			assert_eq!(
 				executor.host_functions.iter().filter(|f| f == &function).count(),
@@ -16,16 +16,17 @@
 
 //! Traits and accessor functions for calling into the Substrate Wasm runtime.
 //!
-//! The primary means of accessing the runtimes is through a cache which saves the reusable
-//! components of the runtime that are expensive to initialize.
+//! The primary means of accessing the runtimes is through a cache which pools the runtime
+//! instances that are expensive to initialize.
 
 use crate::error::{Error, WasmError};
 use log::{trace, warn};
 use codec::Decode;
 use sp_core::{storage::well_known_keys, traits::Externalities};
 use sp_version::RuntimeVersion;
-use std::{collections::hash_map::{Entry, HashMap}, panic::AssertUnwindSafe};
-use sc_executor_common::wasm_runtime::WasmRuntime;
+use std::{panic::AssertUnwindSafe, sync::Arc};
+use parking_lot::Mutex;
+use sc_executor_common::wasm_runtime::{WasmModule, WasmRuntime};
 
 use sp_wasm_interface::Function;
 
@@ -39,48 +40,79 @@ pub enum WasmExecutionMethod {
 	Compiled,
 }
 
-/// A Wasm runtime object along with its cached runtime version.
-struct VersionedRuntime {
-	runtime: Box<dyn WasmRuntime>,
+/// Maximal number of modules, or errors of invalid code, kept in the cache.
+const MAX_MODULES: usize = 4;
+
+/// A Wasm module along with the runtime version of its code.
+struct VersionedModule {
+	module: Arc<dyn WasmModule>,
 	/// Runtime version according to `Core_version`.
 	version: RuntimeVersion,
 }
 
+/// A Wasm runtime object along with the module it was created from.
+struct VersionedRuntime {
+	runtime: Box<dyn WasmRuntime>,
+	module: Arc<VersionedModule>,
+}
+
+/// Identifies runtime instances that can be used interchangeably.
+#[derive(PartialEq, Eq, Clone)]
+struct RuntimeKey {
+	wasm_method: WasmExecutionMethod,
+	code_hash: Vec<u8>,
+	heap_pages: u64,
+}
+
+/// A cached module, or the error its code failed to compile with, keyed by the runtime key and
+/// the host functions the module was created with.
+type CachedModule = (RuntimeKey, Vec<&'static dyn Function>, Result<Arc<VersionedModule>, String>);
+
 /// Cache for the runtimes.
 ///
-/// When an instance is requested for the first time it is added to this cache. Metadata is kept
-/// with the instance so that it can be efficiently reinitialized.
+/// Runtime instances are pooled and keyed by the Wasm execution method, the hash of their code
+/// and the number of heap pages. A call takes an idle instance out of the pool (or creates a new
+/// one if there is none) and puts it back once the call is finished, so concurrent calls from
+/// different threads are executed on different instances.
 ///
-/// When using the Wasmi interpreter execution method, the metadata includes the initial memory and
-/// values of mutable globals. Follow-up requests to fetch a runtime return this one instance with
-/// the memory reset to the initial memory. So, one runtime instance is reused for every fetch
-/// request.
+/// New instances are created from a module that is compiled once per code and kept in the cache,
+/// along with its runtime version. Code that fails to compile is cached as well, so that it is not
+/// compiled again on every call.
 ///
-/// For now the cache grows indefinitely, but that should be fine for now since runtimes can only be
-/// upgraded rarely and there are no other ways to make the node to execute some other runtime.
+/// Instances reset their state between calls: the memory is restored to its initial contents and
+/// the values of mutable globals are restored to their values right after instantiation.
+/// Every instance of a module compiled by Wasmtime has its own compiled code and host state, so
+/// creating one compiles the code again.
+///
+/// At most `max_instances` idle instances are kept. When the pool is full, the least recently
+/// used instance is dropped, so instances of the runtime code that was upgraded are eventually
+/// evicted.
 pub struct RuntimesCache {
-	/// A cache of runtime instances along with metadata, ready to be reused.
-	///
-	/// Instances are keyed by the Wasm execution method and the hash of their code.
-	instances: HashMap<(WasmExecutionMethod, Vec<u8>), Result<VersionedRuntime, WasmError>>,
+	/// Idle runtime instances ordered from the least to the most recently used.
+	idle: Mutex<Vec<(RuntimeKey, VersionedRuntime)>>,
+	/// Modules ordered from the least to the most recently used.
+	modules: Mutex<Vec<CachedModule>>,
+	/// Maximal number of idle instances kept in the cache.
+	max_instances: usize,
 }
 
 impl RuntimesCache {
-	/// Creates a new instance of a runtimes cache.
-	pub fn new() -> RuntimesCache {
+	/// Creates a new instance of a runtimes cache keeping up to `max_instances` idle instances.
+	pub fn new(max_instances: usize) -> RuntimesCache {
 		RuntimesCache {
-			instances: HashMap::new(),
+			idle: Mutex::new(Vec::new()),
+			modules: Mutex::new(Vec::new()),
+			max_instances,
 		}
 	}
 
-	/// Fetches an instance of the runtime.
-	///
-	/// On first use we create a new runtime instance, save it to the cache
-	/// and persist its initial memory.
+	/// Runs `f` with an instance of the runtime.
 	///
-	/// Each subsequent request will return this instance, with its memory restored
-	/// to the persisted initial memory. Thus, we reuse one single runtime instance
-	/// for every `fetch_runtime` invocation.
+	/// An idle instance with matching code, heap pages and host functions is taken from the cache,
+	/// or a new one is created from the cached module if there is none. After `f` returns the
+	/// instance is put back into the cache, unless `f` returned `Err(_)`, which means that a
+	/// `panic!` happened in native code while executing the runtime. In that case the instance
+	/// might be in a poisoned state and it is dropped.
 	///
 	/// # Parameters
 	///
@@ -93,22 +125,24 @@ impl RuntimesCache {
 	///
 	/// # Return value
 	///
-	/// If no error occurred a tuple `(&mut WasmRuntime, H256)` is
-	/// returned. `H256` is the hash of the runtime code.
-	///
-	/// In case of failure one of two errors can be returned:
+	/// In case of failure to create the runtime one of two errors can be returned:
 	///
 	/// `Err::InvalidCode` is returned for runtime code issues.
 	///
 	/// `Error::InvalidMemoryReference` is returned if no memory export with the
 	/// identifier `memory` can be found in the runtime.
-	pub fn fetch_runtime<E: Externalities>(
-		&mut self,
+	pub fn with_instance<E, R>(
+		&self,
 		ext: &mut E,
 		wasm_method: WasmExecutionMethod,
 		default_heap_pages: u64,
 		host_functions: &[&'static dyn Function],
-	) -> Result<(&mut (dyn WasmRuntime + 'static), &RuntimeVersion, Vec<u8>), Error> {
+		f: impl for<'a> FnOnce(
+			&'a mut (dyn WasmRuntime + 'static),
+			&'a RuntimeVersion,
+			&'a mut E,
+		) -> Result<Result<R, Error>, Error>,
+	) -> Result<R, Error> where E: Externalities {
 		let code_hash = ext
 			.original_storage_hash(well_known_keys::CODE)
 			.ok_or(Error::InvalidCode("`CODE` not found in storage.".into()))?;
@@ -118,70 +152,125 @@ impl RuntimesCache {
 			.and_then(|pages| u64::decode(&mut &pages[..]).ok())
 			.unwrap_or(default_heap_pages);
 
-		let result = match self.instances.entry((wasm_method, code_hash.clone())) {
-			Entry::Occupied(o) => {
-				let result = o.into_mut();
-				if let Ok(ref mut cached_runtime) = result {
-					let heap_pages_changed = !cached_runtime.runtime.update_heap_pages(heap_pages);
-					let host_functions_changed = cached_runtime.runtime.host_functions()
-						!= host_functions;
-					if heap_pages_changed || host_functions_changed {
-						let changed = if heap_pages_changed {
-							"heap_pages"
-						} else {
-							"host functions"
-						};
-
-						trace!(
-							target: "runtimes_cache",
-							"{} were changed. Reinstantiating the instance",
-							changed,
-						);
-						*result = create_versioned_wasm_runtime(
-							ext,
-							wasm_method,
-							heap_pages,
-							host_functions.into(),
-						);
-						if let Err(ref err) = result {
-							warn!(target: "runtimes_cache", "cannot create a runtime: {:?}", err);
-						}
-					}
-				}
-				result
+		let key = RuntimeKey { wasm_method, code_hash, heap_pages };
+		let mut instance = match self.take_idle(&key, host_functions) {
+			Some(instance) => instance,
+			None => {
+				trace!(target: "runtimes_cache", "no idle instance found in cache, creating now.");
+				self.create_instance(ext, &key, host_functions)?
 			},
-			Entry::Vacant(v) => {
-				trace!(target: "runtimes_cache", "no instance found in cache, creating now.");
-				let result = create_versioned_wasm_runtime(
-					ext,
-					wasm_method,
-					heap_pages,
-					host_functions.into(),
-				);
-				if let Err(ref err) = result {
-					warn!(target: "runtimes_cache", "cannot create a runtime: {:?}", err);
-				}
-				v.insert(result)
-			}
 		};
 
-		result.as_mut()
-			.map(|entry| (entry.runtime.as_mut(), &entry.version, code_hash))
-			.map_err(|ref e| Error::InvalidCode(format!("{:?}", e)))
+		let result = {
+			let VersionedRuntime { ref mut runtime, ref module } = instance;
+			f(runtime.as_mut(), &module.version, ext)
+		};
+
+		match result {
+			Ok(result) => {
+				self.put_idle(key, instance);
+				result
+			},
+			Err(e) => {
+				trace!(target: "runtimes_cache", "dropping a runtime instance after panic");
+				Err(e)
+			},
+		}
+	}
+
+	/// Takes the most recently used idle instance matching the given key and host functions.
+	fn take_idle(
+		&self,
+		key: &RuntimeKey,
+		host_functions: &[&'static dyn Function],
+	) -> Option<VersionedRuntime> {
+		let mut idle = self.idle.lock();
+		let position = idle.iter().rposition(|(idle_key, instance)|
+			idle_key == key && instance.runtime.host_functions() == host_functions
+		)?;
+		Some(idle.remove(position).1)
+	}
+
+	/// Puts the instance back into the pool, evicting the least recently used one if it's full.
+	fn put_idle(&self, key: RuntimeKey, instance: VersionedRuntime) {
+		let mut idle = self.idle.lock();
+		idle.push((key, instance));
+		while idle.len() > self.max_instances {
+			idle.remove(0);
+		}
 	}
 
-	/// Invalidate the runtime for the given `wasm_method` and `code_hash`.
+	/// Creates a new instance from the cached module matching the given key and host functions.
 	///
-	/// Invalidation of a runtime is useful when there was a `panic!` in native while executing it.
-	/// The `panic!` maybe have brought the runtime into a poisoned state and so, it is better to
-	/// invalidate this runtime instance.
-	pub fn invalidate_runtime(
-		&mut self,
-		wasm_method: WasmExecutionMethod,
-		code_hash: Vec<u8>,
-	) {
-		// Just remove the instance, it will be re-created the next time it is requested.
-		self.instances.remove(&(wasm_method, code_hash));
+	/// If there is no such module yet, the code is compiled and its runtime version is queried
+	/// with the new instance. The module, or the error if the code is invalid, is then cached.
+	fn create_instance<E: Externalities>(
+		&self,
+		ext: &mut E,
+		key: &RuntimeKey,
+		host_functions: &[&'static dyn Function],
+	) -> Result<VersionedRuntime, Error> {
+		if let Some(cached) = self.cached_module(key, host_functions) {
+			let module = cached.map_err(Error::InvalidCode)?;
+			let runtime = module.module.new_instance().map_err(|e| {
+				warn!(target: "runtimes_cache", "cannot instantiate a runtime: {:?}", e);
+				Error::InvalidCode(format!("{:?}", e))
+			})?;
+			return Ok(VersionedRuntime { runtime, module })
+		}
+
+		let created = create_versioned_wasm_runtime(ext, key.wasm_method, key.heap_pages, host_functions.into())
+			.map_err(|e| {
+				warn!(target: "runtimes_cache", "cannot create a runtime: {:?}", e);
+				format!("{:?}", e)
+			});
+
+		let mut modules = self.modules.lock();
+		modules.push((
+			key.clone(),
+			host_functions.into(),
+			created.as_ref().map(|instance| instance.module.clone()).map_err(Clone::clone),
+		));
+		while modules.len() > MAX_MODULES {
+			modules.remove(0);
+		}
+		created.map_err(Error::InvalidCode)
+	}
+
+	/// Returns the cached module matching the given key and host functions, marking it as the
+	/// most recently used one.
+	fn cached_module(
+		&self,
+		key: &RuntimeKey,
+		host_functions: &[&'static dyn Function],
+	) -> Option<Result<Arc<VersionedModule>, String>> {
+		let mut modules = self.modules.lock();
+		let position = modules.iter().rposition(|(module_key, module_host_functions, _)|
+			module_key == key && &module_host_functions[..] == host_functions
+		)?;
+		let cached = modules.remove(position);
+		let result = cached.2.clone();
+		modules.push(cached);
+		Some(result)
+	}
+}
+
+/// Create a wasm module with the given `code`, that runtime instances can be created from.
+pub fn create_wasm_module_with_code(
+	wasm_method: WasmExecutionMethod,
+	heap_pages: u64,
+	code: &[u8],
+	host_functions: Vec<&'static dyn Function>,
+	allow_missing_imports: bool,
+) -> Result<Arc<dyn WasmModule>, WasmError> {
+	match wasm_method {
+		WasmExecutionMethod::Interpreted =>
+			sc_executor_wasmi::create_module(code, heap_pages, host_functions, allow_missing_imports)
+				.map(|module| -> Arc<dyn WasmModule> { Arc::new(module) }),
+		#[cfg(feature = "wasmtime")]
+		WasmExecutionMethod::Compiled =>
+			sc_executor_wasmtime::create_module(code, heap_pages, host_functions)
+				.map(|module| -> Arc<dyn WasmModule> { Arc::new(module) }),
 	}
 }
 
@@ -213,7 +302,8 @@ fn create_versioned_wasm_runtime<E: Externalities>(
 	let code = ext
 		.original_storage(well_known_keys::CODE)
 		.ok_or(WasmError::CodeNotFound)?;
-	let mut runtime = create_wasm_runtime_with_code(wasm_method, heap_pages, &code, host_functions, false)?;
+	let module = create_wasm_module_with_code(wasm_method, heap_pages, &code, host_functions, false)?;
+	let mut runtime = module.new_instance()?;
 
 	// Call to determine runtime version.
 	let version_result = {
@@ -235,12 +325,15 @@ fn create_versioned_wasm_runtime<E: Externalities>(
 
 	Ok(VersionedRuntime {
 		runtime,
-		version,
+		module: Arc::new(VersionedModule { module, version }),
 	})
 }
 
 #[cfg(test)]
 mod tests {
+	use super::*;
+	use assert_matches::assert_matches;
+	use sp_core::Blake2Hasher;
 	use sp_wasm_interface::HostFunctions;
 
 	#[test]
@@ -250,4 +343,32 @@ mod tests {
 		let equal = &host_functions[..] == &host_functions[..];
 		assert!(equal, "Host functions are not equal");
 	}
+
+	#[test]
+	fn invalid_code_is_cached() {
+		let mut ext = sp_state_machine::TestExternalities::<Blake2Hasher, u64>::new_with_code(
+			b"invalid code",
+			Default::default(),
+		);
+		let mut ext = ext.ext();
+		let cache = RuntimesCache::new(2);
+		let host_functions = sp_io::SubstrateHostFunctions::host_functions();
+
+		for _ in 0..2 {
+			let result = cache.with_instance(
+				&mut ext,
+				WasmExecutionMethod::Interpreted,
+				8,
+				&host_functions,
+				|_, _, _| Ok(Ok(())),
+			);
+			assert_matches!(result, Err(Error::InvalidCode(_)));
+		}
+
+		// The code was only compiled once, the second call got the cached error.
+		let modules = cache.modules.lock();
+		assert_eq!(modules.len(), 1);
+		assert!(modules[0].2.is_err());
+		assert!(cache.idle.lock().is_empty());
+	}
 }
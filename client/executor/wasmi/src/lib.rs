@@ -34,7 +34,7 @@ use sp_wasm_interface::{
 	FunctionContext, Pointer, WordSize, Sandbox, MemoryId, Result as WResult, Function,
 };
 use sp_runtime_interface::unpack_ptr_and_len;
-use sc_executor_common::wasm_runtime::WasmRuntime;
+use sc_executor_common::wasm_runtime::{WasmModule, WasmRuntime};
 
 struct FunctionExecutor<'a> {
 	sandbox_store: sandbox::Store<wasmi::FuncRef>,
@@ -569,7 +569,6 @@ impl StateSnapshot {
 }
 
 /// A runtime along with its initial state snapshot.
-#[derive(Clone)]
 pub struct WasmiRuntime {
 	/// A wasm module instance.
 	instance: ModuleRef,
@@ -584,6 +583,14 @@ pub struct WasmiRuntime {
 	missing_functions: Vec<String>,
 }
 
+// `ModuleRef`, `MemoryRef`, `GlobalRef` and the other wasmi instance handles are `Rc`s to
+// `RefCell`s, which is all that makes this type `!Send`. `instantiate` creates a fresh graph of them
+// for every runtime and no handle is ever cloned out of the runtime: `call` only lends them to the
+// `FunctionExecutor` for the duration of the call. Moving the runtime therefore moves every owner of
+// those reference counts to the other thread at once. The parsed `Module` the instance was created
+// from is not referenced by the instance after instantiation.
+unsafe impl Send for WasmiRuntime {}
+
 impl WasmRuntime for WasmiRuntime {
 	fn update_heap_pages(&mut self, heap_pages: u64) -> bool {
 		self.state_snapshot.heap_pages == heap_pages
@@ -617,12 +624,62 @@ impl WasmRuntime for WasmiRuntime {
 	}
 }
 
-pub fn create_instance(
+/// A parsed and validated wasm module that runtime instances can be created from.
+pub struct WasmiModule {
+	/// The parsed module.
+	module: Module,
+	/// The data segments of the module, used to take the state snapshot of new instances.
+	data_segments: Vec<DataSegment>,
+	heap_pages: u64,
+	/// The host functions registered for the instances.
+	host_functions: Vec<&'static dyn Function>,
+	/// Enable stub generation for functions that are not available in `host_functions`.
+	allow_missing_imports: bool,
+}
+
+impl WasmiModule {
+	/// Instantiate the module and take the state snapshot of the new instance.
+	fn instantiate(&self) -> Result<WasmiRuntime, WasmError> {
+		let (instance, missing_functions) = instantiate_module(
+			self.heap_pages as usize,
+			&self.module,
+			&self.host_functions,
+			self.allow_missing_imports,
+		).map_err(|e| WasmError::Instantiation(e.to_string()))?;
+
+		// Take state snapshot before executing anything.
+		let state_snapshot = StateSnapshot::take(&instance, self.data_segments.clone(), self.heap_pages)
+			.expect(
+				"`take` returns `Err` if the module is not valid;
+					we already loaded module in `create_module`, thus the `Module` is proven to be
+					valid at this point;
+					qed
+					",
+			);
+
+		Ok(WasmiRuntime {
+			instance,
+			state_snapshot,
+			host_functions: self.host_functions.clone(),
+			allow_missing_imports: self.allow_missing_imports,
+			missing_functions,
+		})
+	}
+}
+
+impl WasmModule for WasmiModule {
+	fn new_instance(&self) -> Result<Box<dyn WasmRuntime>, WasmError> {
+		self.instantiate().map(|runtime| -> Box<dyn WasmRuntime> { Box::new(runtime) })
+	}
+}
+
+/// Parse and validate the given wasm code, so that it can be instantiated many times.
+pub fn create_module(
 	code: &[u8],
 	heap_pages: u64,
 	host_functions: Vec<&'static dyn Function>,
 	allow_missing_imports: bool,
-) -> Result<WasmiRuntime, WasmError> {
+) -> Result<WasmiModule, WasmError> {
 	let module = Module::from_buffer(&code).map_err(|_| WasmError::InvalidModule)?;
 
 	// Extract the data segments from the wasm code.
@@ -631,32 +688,24 @@ pub fn create_instance(
 	// we just loaded and validated the `module` above.
 	let data_segments = extract_data_segments(&code)?;
 
-	// Instantiate this module.
-	let (instance, missing_functions) = instantiate_module(
-		heap_pages as usize,
-		&module,
-		&host_functions,
-		allow_missing_imports,
-	).map_err(|e| WasmError::Instantiation(e.to_string()))?;
-
-	// Take state snapshot before executing anything.
-	let state_snapshot = StateSnapshot::take(&instance, data_segments, heap_pages)
-		.expect(
-			"`take` returns `Err` if the module is not valid;
-				we already loaded module above, thus the `Module` is proven to be valid at this point;
-				qed
-				",
-		);
-
-	Ok(WasmiRuntime {
-		instance,
-		state_snapshot,
+	Ok(WasmiModule {
+		module,
+		data_segments,
+		heap_pages,
 		host_functions,
 		allow_missing_imports,
-		missing_functions,
 	})
 }
 
+pub fn create_instance(
+	code: &[u8],
+	heap_pages: u64,
+	host_functions: Vec<&'static dyn Function>,
+	allow_missing_imports: bool,
+) -> Result<WasmiRuntime, WasmError> {
+	create_module(code, heap_pages, host_functions, allow_missing_imports)?.instantiate()
+}
+
 /// Extract the data segments from the given wasm code.
 ///
 /// Returns `Err` if the given wasm code cannot be deserialized.
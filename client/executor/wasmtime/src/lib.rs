@@ -21,5 +21,5 @@ mod runtime;
 mod trampoline;
 mod util;
 
-pub use runtime::create_instance;
+pub use runtime::{create_instance, create_module, WasmtimeModule};
 
@@ -22,7 +22,7 @@ use crate::util::{cranelift_ir_signature, read_memory_into, write_memory_from};
 
 use sc_executor_common::{
 	error::{Error, Result, WasmError},
-	wasm_runtime::WasmRuntime,
+	wasm_runtime::{WasmModule, WasmRuntime},
 };
 use sp_wasm_interface::{Pointer, WordSize, Function};
 use sp_runtime_interface::unpack_ptr_and_len;
@@ -31,33 +31,234 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::rc::Rc;
+use std::sync::Arc;
 
+use parking_lot::Mutex;
+
+use parity_wasm::elements::{deserialize_buffer, Instruction, Module as RawModule};
 use cranelift_codegen::ir;
 use cranelift_codegen::isa::TargetIsa;
 use cranelift_entity::{EntityRef, PrimaryMap};
 use cranelift_frontend::FunctionBuilderContext;
-use cranelift_wasm::DefinedFuncIndex;
+use cranelift_wasm::{DefinedFuncIndex, GlobalIndex};
 use wasmtime_environ::{Module, translate_signature};
 use wasmtime_jit::{
 	ActionOutcome, CodeMemory, CompilationStrategy, CompiledModule, Compiler, Context, RuntimeValue,
 };
-use wasmtime_runtime::{Export, Imports, InstanceHandle, VMFunctionBody};
+use wasmtime_runtime::{Export, Imports, InstanceHandle, VMFunctionBody, VMGlobalDefinition};
+
+/// A Wasm module compiled to machine code by the Wasmtime JIT, along with the context it was
+/// compiled in.
+struct CompiledUnit {
+	module: CompiledModule,
+	context: Context,
+}
+
+/// The contents of the memory of the instances of a module right after instantiation.
+struct InitialMemory {
+	/// The offsets and contents of the data segments.
+	data_segments: Vec<(u32, Vec<u8>)>,
+	/// The data segments written to a memory file that is mapped copy-on-write over the memory
+	/// of the instances, if it could be created.
+	#[cfg(target_os = "linux")]
+	image: Option<MemoryImage>,
+}
+
+impl InitialMemory {
+	fn new(data_segments: Vec<(u32, Vec<u8>)>) -> Self {
+		InitialMemory {
+			#[cfg(target_os = "linux")]
+			image: MemoryImage::new(&data_segments),
+			data_segments,
+		}
+	}
+
+	/// Restores the given linear memory to its initial contents.
+	fn restore(&self, memory: &mut [u8]) -> Result<()> {
+		#[cfg(target_os = "linux")]
+		{
+			if let Some(image) = &self.image {
+				if image.len <= memory.len() {
+					let (head, tail) = memory.split_at_mut(image.len);
+					if image.map_over(head) {
+						clear_memory(tail);
+						return Ok(());
+					}
+				}
+			}
+		}
+
+		clear_memory(memory);
+		for (offset, contents) in &self.data_segments {
+			write_memory_from(memory, Pointer::new(*offset), contents)?;
+		}
+		Ok(())
+	}
+}
+
+/// A memory file holding the data segments of a module at their offsets.
+///
+/// Restoring the memory of an instance maps the file over it, so only the pages that are written
+/// by a call are copied and the data segments don't need to be copied again on every call.
+#[cfg(target_os = "linux")]
+struct MemoryImage {
+	fd: libc::c_int,
+	/// The size of the file, rounded up to the page size.
+	len: usize,
+}
+
+#[cfg(target_os = "linux")]
+impl MemoryImage {
+	fn new(data_segments: &[(u32, Vec<u8>)]) -> Option<Self> {
+		let end = data_segments.iter()
+			.map(|(offset, contents)| *offset as usize + contents.len())
+			.max()
+			.unwrap_or(0);
+		// This is safe as `sysconf` has no preconditions.
+		let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+		let len = (end + page_size - 1) / page_size * page_size;
+
+		// This is safe as the name is a valid nul-terminated string.
+		let fd = unsafe {
+			libc::memfd_create(b"wasm-memory-image\0".as_ptr() as *const libc::c_char, libc::MFD_CLOEXEC)
+		};
+		if fd < 0 {
+			return None;
+		}
+		let image = MemoryImage { fd, len };
+
+		// This is safe as `fd` is a valid file descriptor owned by `image`.
+		if unsafe { libc::ftruncate(fd, len as libc::off_t) } != 0 {
+			return None;
+		}
+		for (offset, contents) in data_segments {
+			// This is safe as `contents` is a valid slice and `fd` is a valid file descriptor.
+			let written = unsafe {
+				libc::pwrite(
+					fd,
+					contents.as_ptr() as *const libc::c_void,
+					contents.len(),
+					*offset as libc::off_t,
+				)
+			};
+			if written != contents.len() as isize {
+				return None;
+			}
+		}
+		Some(image)
+	}
+
+	/// Maps the image copy-on-write over the given memory, which must be `self.len` bytes long.
+	///
+	/// Returns `false` if the memory could not be mapped, in which case it is left as it was.
+	fn map_over(&self, memory: &mut [u8]) -> bool {
+		if memory.is_empty() {
+			return true;
+		}
+		// This is safe as the range is a valid mutable slice of the instance memory, which starts
+		// at a page boundary, and is replaced by a private mapping with the same protection.
+		let result = unsafe {
+			libc::mmap(
+				memory.as_mut_ptr() as *mut libc::c_void,
+				self.len,
+				libc::PROT_READ | libc::PROT_WRITE,
+				libc::MAP_PRIVATE | libc::MAP_FIXED,
+				self.fd,
+				0,
+			)
+		};
+		result != libc::MAP_FAILED
+	}
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for MemoryImage {
+	fn drop(&mut self) {
+		// This is safe as `fd` is a valid file descriptor owned by the image.
+		unsafe { libc::close(self.fd); }
+	}
+}
+
+/// A Wasm module compiled by Wasmtime that runtime instances can be created from.
+///
+/// Every instance has its own compiled code and host state, so the code is compiled again for
+/// every instance but the first one.
+pub struct WasmtimeModule {
+	code: Vec<u8>,
+	/// The instance created along with the module, handed out by the first `new_instance` call.
+	first_instance: Mutex<Option<WasmtimeRuntime>>,
+	/// The initial contents of the memory, used to restore the memory of instances.
+	///
+	/// `None` if the data segments can't be restored in place. The module is instantiated anew
+	/// for every call in that case.
+	initial_memory: Option<Arc<InitialMemory>>,
+	max_heap_pages: Option<u32>,
+	heap_pages: u32,
+	/// The host functions registered for the module.
+	host_functions: Vec<&'static dyn Function>,
+}
+
+impl WasmtimeModule {
+	fn runtime(&self, unit: CompiledUnit) -> WasmtimeRuntime {
+		WasmtimeRuntime {
+			instance: None,
+			unit,
+			initial_memory: self.initial_memory.clone(),
+			max_heap_pages: self.max_heap_pages,
+			heap_pages: self.heap_pages,
+			host_functions: self.host_functions.clone(),
+		}
+	}
+}
+
+impl WasmModule for WasmtimeModule {
+	fn new_instance(&self) -> std::result::Result<Box<dyn WasmRuntime>, WasmError> {
+		if let Some(runtime) = self.first_instance.lock().take() {
+			return Ok(Box::new(runtime));
+		}
+		let unit = create_compiled_unit(&self.code, &self.host_functions)?;
+		Ok(Box::new(self.runtime(unit)))
+	}
+}
+
+/// An instance that is reused between calls, along with its state right after instantiation.
+struct ReusableInstance {
+	handle: InstanceHandle,
+	/// The size of the memory.
+	memory_size: usize,
+	/// The values of the mutable globals defined by the instance.
+	globals: Vec<(GlobalIndex, VMGlobalDefinition)>,
+}
 
 /// A `WasmRuntime` implementation using the Wasmtime JIT to compile the runtime module to native
 /// and execute the compiled code.
 pub struct WasmtimeRuntime {
-	module: CompiledModule,
-	context: Context,
+	/// The instance of the module that is reused between calls.
+	///
+	/// Declared before `unit`, so that it is dropped before the code it was instantiated from.
+	instance: Option<ReusableInstance>,
+	unit: CompiledUnit,
+	initial_memory: Option<Arc<InitialMemory>>,
 	max_heap_pages: Option<u32>,
 	heap_pages: u32,
 	/// The host functions registered for this instance.
 	host_functions: Vec<&'static dyn Function>,
 }
 
+// The compiled unit and the instance of the runtime share `Rc`s: the module metadata, the map of
+// global exports and the "env" instance that holds the host state of the calls. None of these is
+// shared with anything outside of the runtime, which is never cloned, so moving the runtime to
+// another thread moves all the references to them at once and they are never accessed from two
+// threads.
+unsafe impl Send for WasmtimeRuntime {}
+
 impl WasmRuntime for WasmtimeRuntime {
 	fn update_heap_pages(&mut self, heap_pages: u64) -> bool {
 		match heap_pages_valid(heap_pages, self.max_heap_pages) {
 			Some(heap_pages) => {
+				if heap_pages != self.heap_pages {
+					self.instance = None;
+				}
 				self.heap_pages = heap_pages;
 				true
 			}
@@ -70,24 +271,50 @@ impl WasmRuntime for WasmtimeRuntime {
 	}
 
 	fn call(&mut self, method: &str, data: &[u8]) -> Result<Vec<u8>> {
-		call_method(
-			&mut self.context,
-			&mut self.module,
-			method,
-			data,
-			self.heap_pages,
-		)
+		let CompiledUnit { ref mut module, ref mut context } = self.unit;
+
+		let reusable = match (self.instance.take(), &self.initial_memory) {
+			(Some(mut instance), Some(initial_memory)) =>
+				if reset_instance(&mut instance, initial_memory)? {
+					Some(instance)
+				} else {
+					None
+				},
+			_ => None,
+		};
+		let mut instance = match reusable {
+			Some(reusable) => reusable,
+			None => {
+				let mut handle = instantiate(context, module, self.heap_pages)?;
+				let memory_size = get_memory_mut(&mut handle)?.len();
+				let globals = mutable_globals(&mut handle)?;
+				ReusableInstance { handle, memory_size, globals }
+			},
+		};
+
+		let result = call_method(context, &mut instance.handle, method, data);
+
+		// The memory and the mutable globals are restored before the next call, so the instance
+		// can be reused even if this call trapped.
+		if self.initial_memory.is_some() {
+			self.instance = Some(instance);
+		}
+		result
 	}
 }
 
-/// Create a new `WasmtimeRuntime` given the code. This function performs translation from Wasm to
-/// machine code, which can be computationally heavy.
-pub fn create_instance(
+/// Compile the given code to a module that runtime instances can be created from. This function
+/// performs translation from Wasm to machine code, which can be computationally heavy.
+///
+/// The first instance of the module reuses the code compiled here, every further instance
+/// compiles the code again.
+pub fn create_module(
 	code: &[u8],
 	heap_pages: u64,
 	host_functions: Vec<&'static dyn Function>,
-) -> std::result::Result<WasmtimeRuntime, WasmError> {
-	let (compiled_module, context) = create_compiled_unit(code, &host_functions)?;
+) -> std::result::Result<WasmtimeModule, WasmError> {
+	let CompiledUnit { module: compiled_module, context } =
+		create_compiled_unit(code, &host_functions)?;
 
 	// Inspect the module for the min and max memory sizes.
 	let (min_memory_size, max_memory_size) = {
@@ -106,19 +333,39 @@ pub fn create_instance(
 	let heap_pages = heap_pages_valid(heap_pages, max_heap_pages)
 		.ok_or_else(|| WasmError::InvalidHeapPages)?;
 
-	Ok(WasmtimeRuntime {
-		module: compiled_module,
-		context,
+	let initial_memory = extract_data_segments(code)?.map(|data_segments| {
+		Arc::new(InitialMemory::new(data_segments))
+	});
+
+	let mut module = WasmtimeModule {
+		code: code.to_vec(),
+		first_instance: Mutex::new(None),
+		initial_memory,
 		max_heap_pages,
 		heap_pages,
 		host_functions,
-	})
+	};
+	let first_instance = module.runtime(CompiledUnit { module: compiled_module, context });
+	*module.first_instance.get_mut() = Some(first_instance);
+	Ok(module)
+}
+
+/// Create a new `WasmtimeRuntime` given the code. This function performs translation from Wasm to
+/// machine code, which can be computationally heavy.
+pub fn create_instance(
+	code: &[u8],
+	heap_pages: u64,
+	host_functions: Vec<&'static dyn Function>,
+) -> std::result::Result<WasmtimeRuntime, WasmError> {
+	let module = create_module(code, heap_pages, host_functions)?;
+	Ok(module.first_instance.into_inner()
+		.expect("the first instance is created along with the module; qed"))
 }
 
 fn create_compiled_unit(
 	code: &[u8],
 	host_functions: &[&'static dyn Function],
-) -> std::result::Result<(CompiledModule, Context), WasmError> {
+) -> std::result::Result<CompiledUnit, WasmError> {
 	let compilation_strategy = CompilationStrategy::Cranelift;
 
 	let compiler = new_compiler(compilation_strategy)?;
@@ -137,17 +384,41 @@ fn create_compiled_unit(
 	let module = context.compile_module(&code)
 		.map_err(|e| WasmError::Other(format!("module compile error: {}", e)))?;
 
-	Ok((module, context))
+	Ok(CompiledUnit { module, context })
 }
 
-/// Call a function inside a precompiled Wasm module.
-fn call_method(
+/// Extracts the offsets and contents of the data segments of the module.
+///
+/// Returns `None` if any of the segments is passive or its offset is not a constant.
+fn extract_data_segments(
+	wasm_code: &[u8],
+) -> std::result::Result<Option<Vec<(u32, Vec<u8>)>>, WasmError> {
+	let raw_module: RawModule = deserialize_buffer(wasm_code)
+		.map_err(|_| WasmError::CantDeserializeWasm)?;
+
+	let segments = raw_module
+		.data_section()
+		.map(|ds| ds.entries())
+		.unwrap_or(&[])
+		.iter()
+		.map(|segment| {
+			let init_expr = segment.offset().as_ref()?.code();
+			match init_expr {
+				[Instruction::I32Const(offset), Instruction::End] =>
+					Some((*offset as u32, segment.value().to_vec())),
+				_ => None,
+			}
+		})
+		.collect();
+	Ok(segments)
+}
+
+/// Instantiate a precompiled Wasm module with the given number of heap pages.
+fn instantiate(
 	context: &mut Context,
 	module: &mut CompiledModule,
-	method: &str,
-	data: &[u8],
 	heap_pages: u32,
-) -> Result<Vec<u8>> {
+) -> Result<InstanceHandle> {
 	// Old exports get clobbered in `InstanceHandle::new` if we don't explicitly remove them first.
 	//
 	// The global exports mechanism is temporary in Wasmtime and expected to be removed.
@@ -163,18 +434,104 @@ fn call_method(
 	// at this time.
 	grow_memory(&mut instance, heap_pages)?;
 
+	Ok(instance)
+}
+
+/// Restores the memory and the mutable globals of a previously used instance to their state right
+/// after instantiation.
+///
+/// Returns `false` if the memory was grown since the instantiation, in which case the instance
+/// can't be reused.
+fn reset_instance(
+	instance: &mut ReusableInstance,
+	initial_memory: &InitialMemory,
+) -> Result<bool> {
+	let memory = get_memory_mut(&mut instance.handle)?;
+	if memory.len() != instance.memory_size {
+		return Ok(false);
+	}
+
+	initial_memory.restore(memory)?;
+
+	for (index, value) in &instance.globals {
+		match instance.handle.lookup_by_declaration(&wasmtime_environ::Export::Global(*index)) {
+			// This is safe to wrap in an unsafe block as:
+			// - The definition pointer is returned by a lookup on a valid instance
+			// - The value was read from the same global, so it has the right type
+			Export::Global { definition, vmctx: _, global: _ } => unsafe { *definition = *value },
+			_ => return Err(Error::Other("cannot restore a mutable global".into())),
+		}
+	}
+	Ok(true)
+}
+
+/// Reads the values of the mutable globals defined by the instance, such as the stack pointer.
+fn mutable_globals(instance: &mut InstanceHandle) -> Result<Vec<(GlobalIndex, VMGlobalDefinition)>> {
+	let indices = {
+		let module = instance.module_ref();
+		module.globals
+			.iter()
+			.filter(|(index, global)| !module.is_imported_global(*index) && global.mutability)
+			.map(|(index, _)| index)
+			.collect::<Vec<_>>()
+	};
+
+	indices.into_iter().map(|index| {
+		match instance.lookup_by_declaration(&wasmtime_environ::Export::Global(index)) {
+			// This is safe to wrap in an unsafe block as:
+			// - The result of the lookup is not mutated
+			// - The definition pointer is returned by a lookup on a valid instance
+			Export::Global { definition, vmctx: _, global: _ } => Ok((index, unsafe { *definition })),
+			_ => Err(Error::Other("cannot read a mutable global".into())),
+		}
+	}).collect()
+}
+
+/// Zeroes the given linear memory.
+///
+/// The pages are released with `madvise`, so the kernel maps shared zero pages on the next
+/// access and copies them only when written, instead of zeroing the whole memory upfront.
+#[cfg(unix)]
+fn clear_memory(memory: &mut [u8]) {
+	// This is safe as the range is a valid mutable slice of the instance memory, which is an
+	// anonymous private mapping starting at a page boundary.
+	let result = unsafe {
+		libc::madvise(memory.as_mut_ptr() as *mut libc::c_void, memory.len(), libc::MADV_DONTNEED)
+	};
+	if result != 0 {
+		for byte in memory.iter_mut() {
+			*byte = 0;
+		}
+	}
+}
+
+/// Zeroes the given linear memory.
+#[cfg(not(unix))]
+fn clear_memory(memory: &mut [u8]) {
+	for byte in memory.iter_mut() {
+		*byte = 0;
+	}
+}
+
+/// Call a function inside an instantiated Wasm module.
+fn call_method(
+	context: &mut Context,
+	instance: &mut InstanceHandle,
+	method: &str,
+	data: &[u8],
+) -> Result<Vec<u8>> {
 	// Initialize the function executor state.
-	let heap_base = get_heap_base(&instance)?;
+	let heap_base = get_heap_base(instance)?;
 	let executor_state = FunctionExecutorState::new(heap_base);
 	reset_env_state_and_take_trap(context, Some(executor_state))?;
 
 	// Write the input data into guest memory.
-	let (data_ptr, data_len) = inject_input_data(context, &mut instance, data)?;
+	let (data_ptr, data_len) = inject_input_data(context, instance, data)?;
 	let args = [RuntimeValue::I32(u32::from(data_ptr) as i32), RuntimeValue::I32(data_len as i32)];
 
 	// Invoke the function in the runtime.
 	let outcome = context
-		.invoke(&mut instance, method, &args[..])
+		.invoke(instance, method, &args[..])
 		.map_err(|e| Error::Other(format!("error calling runtime: {}", e)))?;
 	let trap_error = reset_env_state_and_take_trap(context, None)?;
 	let (output_ptr, output_len) = match outcome {
@@ -189,7 +546,7 @@ fn call_method(
 
 	// Read the output data from guest memory.
 	let mut output = vec![0; output_len as usize];
-	let memory = get_memory_mut(&mut instance)?;
+	let memory = get_memory_mut(instance)?;
 	read_memory_into(memory, Pointer::new(output_ptr), &mut output)?;
 	Ok(output)
 }
@@ -366,3 +723,60 @@ fn heap_pages_valid(heap_pages: u64, max_heap_pages: Option<u32>)
 	}
 	Some(heap_pages)
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+	use super::*;
+
+	/// Page aligned anonymous memory, like the linear memory of an instance.
+	struct AnonymousMemory {
+		ptr: *mut u8,
+		len: usize,
+	}
+
+	impl AnonymousMemory {
+		fn new(len: usize) -> Self {
+			let ptr = unsafe {
+				libc::mmap(
+					std::ptr::null_mut(),
+					len,
+					libc::PROT_READ | libc::PROT_WRITE,
+					libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+					-1,
+					0,
+				)
+			};
+			assert_ne!(ptr, libc::MAP_FAILED);
+			AnonymousMemory { ptr: ptr as *mut u8, len }
+		}
+
+		fn as_mut_slice(&mut self) -> &mut [u8] {
+			unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+		}
+	}
+
+	impl Drop for AnonymousMemory {
+		fn drop(&mut self) {
+			unsafe { libc::munmap(self.ptr as *mut libc::c_void, self.len); }
+		}
+	}
+
+	#[test]
+	fn initial_memory_is_restored() {
+		let initial_memory = InitialMemory::new(vec![(16, vec![1, 2, 3]), (70_000, vec![4; 10])]);
+		#[cfg(target_os = "linux")]
+		assert!(initial_memory.image.is_some());
+
+		let mut expected = vec![0u8; 4 * 65536];
+		expected[16..19].copy_from_slice(&[1, 2, 3]);
+		expected[70_000..70_010].copy_from_slice(&[4; 10]);
+
+		let mut memory = AnonymousMemory::new(4 * 65536);
+		let memory = memory.as_mut_slice();
+		for _ in 0..2 {
+			memory.iter_mut().for_each(|byte| *byte = 0xff);
+			initial_memory.restore(memory).unwrap();
+			assert!(memory == &expected[..]);
+		}
+	}
+}
@@ -33,6 +33,7 @@ use sc_client_api::{
 	Finalizer,
 	call_executor::CallExecutor,
 	utils::is_descendent_of,
+	ExecutionStrategy,
 };
 use sc_client::{
 	apply_aux, Client,
@@ -41,12 +42,13 @@ use finality_grandpa::{
 	BlockNumberOps, Equivocation, Error as GrandpaError, round::State as RoundState,
 	voter, voter_set::VoterSet,
 };
-use sp_core::Pair;
+use sp_core::{ExecutionContext, Pair};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{
 	Block as BlockT, Header as HeaderT, NumberFor, One, Zero,
 };
 use sc_telemetry::{telemetry, CONSENSUS_INFO};
+use prometheus_endpoint::{register, IntGauge, PrometheusError, Registry};
 
 use crate::{
 	CommandOrError, Commit, Config, Error, Precommit, Prevote,
@@ -61,7 +63,10 @@ use crate::consensus_changes::SharedConsensusChanges;
 use crate::justification::GrandpaJustification;
 use crate::until_imported::UntilVoteTargetImported;
 use crate::voting_rule::VotingRule;
-use sp_finality_grandpa::{AuthorityId, AuthoritySignature, SetId, RoundNumber};
+use sp_finality_grandpa::{
+	AuthorityId, AuthoritySignature, EquivocationProof, OpaqueKeyOwnershipProof, SetId,
+	RoundNumber,
+};
 
 type HistoricalVotes<Block> = finality_grandpa::HistoricalVotes<
 	<Block as BlockT>::Hash,
@@ -376,6 +381,23 @@ impl<Block: BlockT> SharedVoterSetState<Block> {
 	}
 }
 
+/// Prometheus metrics for GRANDPA.
+#[derive(Clone)]
+pub(crate) struct Metrics {
+	finality_grandpa_round: IntGauge,
+}
+
+impl Metrics {
+	pub(crate) fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			finality_grandpa_round: register(
+				IntGauge::new("finality_grandpa_round", "Highest completed GRANDPA round.")?,
+				registry,
+			)?,
+		})
+	}
+}
+
 /// The environment we run GRANDPA in.
 pub(crate) struct Environment<B, E, Block: BlockT, N: NetworkT<Block>, RA, SC, VR> {
 	pub(crate) client: Arc<Client<B, E, Block, RA>>,
@@ -388,6 +410,7 @@ pub(crate) struct Environment<B, E, Block: BlockT, N: NetworkT<Block>, RA, SC, V
 	pub(crate) set_id: SetId,
 	pub(crate) voter_set_state: SharedVoterSetState<Block>,
 	pub(crate) voting_rule: VR,
+	pub(crate) metrics: Option<Metrics>,
 }
 
 impl<B, E, Block: BlockT, N: NetworkT<Block>, RA, SC, VR> Environment<B, E, Block, N, RA, SC, VR> {
@@ -406,6 +429,108 @@ impl<B, E, Block: BlockT, N: NetworkT<Block>, RA, SC, VR> Environment<B, E, Bloc
 	}
 }
 
+impl<B, E, Block, N, RA, SC, VR> Environment<B, E, Block, N, RA, SC, VR>
+where
+	Block: BlockT,
+	B: Backend<Block>,
+	E: CallExecutor<Block> + Send + Sync,
+	N: NetworkT<Block>,
+	SC: SelectChain<Block>,
+{
+	/// Report the given equivocation to the GRANDPA runtime module. This method
+	/// generates a session membership proof of the offender and then submits
+	/// an extrinsic to report the equivocation. In particular, the session
+	/// membership proof must be generated at a block at which the given set
+	/// was live on-chain.
+	fn report_equivocation(
+		&self,
+		equivocation: sp_finality_grandpa::Equivocation<Block::Hash, NumberFor<Block>>,
+	) -> Result<(), Error> {
+		if self.authority_set.set_id() != self.set_id {
+			// the set we are voting on is no longer the latest one, it won't be
+			// possible to prove key ownership for it at the latest state.
+			debug!(target: "afg", "Skipping report of equivocation in obsolete set {}", self.set_id);
+			return Ok(());
+		}
+
+		let best_header = self.select_chain
+			.best_chain()
+			.map_err(|e| Error::Blockchain(e.to_string()))?;
+
+		// the current set lasts at least until the block preceding the
+		// earliest signaled change, any block after that might already
+		// be tracking a new session on-chain.
+		let earliest_change = self.authority_set.inner().read()
+			.pending_changes()
+			.map(|change| change.canon_height)
+			.min();
+
+		let at = match earliest_change {
+			Some(canon_height) if canon_height <= *best_header.number() => {
+				if canon_height.is_zero() {
+					return Err(Error::Safety("Authority set change signaled at genesis.".into()));
+				}
+
+				BlockId::Number(canon_height - One::one())
+			},
+			_ => BlockId::Hash(best_header.hash()),
+		};
+
+		let equivocation_proof = EquivocationProof::new(self.set_id, equivocation);
+
+		// generate key ownership proof at that block
+		let key_owner_proof = self.client.executor()
+			.call(
+				&at,
+				"GrandpaApi_generate_key_ownership_proof",
+				&(self.set_id, equivocation_proof.offender().clone()).encode(),
+				ExecutionStrategy::NativeElseWasm,
+				None,
+			)
+			.and_then(|call_result| {
+				<Option<OpaqueKeyOwnershipProof>>::decode(&mut &call_result[..])
+					.map_err(|err| ClientError::CallResultDecode(
+						"failed to decode GRANDPA key ownership proof".into(), err
+					))
+			})?;
+
+		let key_owner_proof = match key_owner_proof {
+			Some(proof) => proof,
+			None => {
+				debug!(target: "afg", "Equivocation offender is not part of the authority set.");
+				return Ok(());
+			},
+		};
+
+		// submit equivocation report at best block, the transaction pool
+		// extension is required for the runtime to be able to submit it.
+		let best_block_id = BlockId::Hash(best_header.hash());
+		let extensions = self.client.execution_extensions()
+			.extensions(&best_block_id, ExecutionContext::OffchainCall(None));
+
+		let submitted = self.client.executor()
+			.call(
+				&best_block_id,
+				"GrandpaApi_submit_report_equivocation_unsigned_extrinsic",
+				&(equivocation_proof, key_owner_proof).encode(),
+				ExecutionStrategy::NativeElseWasm,
+				Some(extensions),
+			)
+			.and_then(|call_result| {
+				<Option<()>>::decode(&mut &call_result[..])
+					.map_err(|err| ClientError::CallResultDecode(
+						"failed to decode equivocation report submission result".into(), err
+					))
+			})?;
+
+		if submitted.is_none() {
+			debug!(target: "afg", "Equivocation report was not submitted by the runtime.");
+		}
+
+		Ok(())
+	}
+}
+
 impl<Block: BlockT, B, E, N, RA, SC, VR>
 	finality_grandpa::Chain<Block::Hash, NumberFor<Block>>
 for Environment<B, E, Block, N, RA, SC, VR>
@@ -816,6 +941,10 @@ where
 			Ok(Some(set_state))
 		})?;
 
+		if let Some(metrics) = self.metrics.as_ref() {
+			metrics.finality_grandpa_round.set(round as i64);
+		}
+
 		Ok(())
 	}
 
@@ -907,7 +1036,13 @@ where
 		equivocation: ::finality_grandpa::Equivocation<Self::Id, Prevote<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected prevote equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet; this could craft misbehavior reports of some kind.
+		let equivocation = sp_finality_grandpa::Equivocation::Prevote(
+			vote_equivocation(equivocation, |prevote| (prevote.target_hash, prevote.target_number)),
+		);
+
+		if let Err(err) = self.report_equivocation(equivocation) {
+			warn!(target: "afg", "Error reporting prevote equivocation: {:?}", err);
+		}
 	}
 
 	fn precommit_equivocation(
@@ -916,7 +1051,34 @@ where
 		equivocation: Equivocation<Self::Id, Precommit<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected precommit equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet
+		let equivocation = sp_finality_grandpa::Equivocation::Precommit(
+			vote_equivocation(equivocation, |precommit| (precommit.target_hash, precommit.target_number)),
+		);
+
+		if let Err(err) = self.report_equivocation(equivocation) {
+			warn!(target: "afg", "Error reporting precommit equivocation: {:?}", err);
+		}
+	}
+}
+
+/// Convert an equivocation detected by the voter into its runtime
+/// representation, using the given function to extract the vote target.
+fn vote_equivocation<H, N, V, F>(
+	equivocation: Equivocation<AuthorityId, V, AuthoritySignature>,
+	target: F,
+) -> sp_finality_grandpa::VoteEquivocation<H, N> where
+	F: Fn(V) -> (H, N),
+{
+	let vote = |(vote, signature): (V, AuthoritySignature)| {
+		let (target_hash, target_number) = target(vote);
+		(sp_finality_grandpa::Vote { target_hash, target_number }, signature)
+	};
+
+	sp_finality_grandpa::VoteEquivocation {
+		round_number: equivocation.round_number,
+		identity: equivocation.identity,
+		first: vote(equivocation.first),
+		second: vote(equivocation.second),
 	}
 }
 
@@ -402,8 +402,14 @@ impl<B, E, Block: BlockT, RA, SC> BlockImport<Block>
 		let number = block.header.number().clone();
 
 		// early exit if block already in chain, otherwise the check for
-		// authority changes will error when trying to re-import a change block
+		// authority changes will error when trying to re-import a change block.
+		// blocks that are imported again to add their state only need to be executed.
 		match self.inner.status(BlockId::Hash(hash)) {
+			Ok(BlockStatus::InChain) if block.import_existing => {
+				block.justification = None;
+				return (&*self.inner).import_block(block, new_cache)
+					.map_err(|e| ConsensusError::ClientImport(e.to_string()).into())
+			},
 			Ok(BlockStatus::InChain) => return Ok(ImportResult::AlreadyInChain),
 			Ok(BlockStatus::Unknown) => {},
 			Err(e) => return Err(ConsensusError::ClientImport(e.to_string()).into()),
@@ -43,7 +43,9 @@ use parity_scale_codec::Decode;
 use sp_runtime::traits::{Header as HeaderT, HasherFor};
 use sp_runtime::generic::{BlockId, DigestItem};
 use sp_core::{H256, NativeOrEncoded, ExecutionContext, crypto::Public};
-use sp_finality_grandpa::{GRANDPA_ENGINE_ID, AuthorityList, GrandpaApi};
+use sp_finality_grandpa::{
+	GRANDPA_ENGINE_ID, AuthorityList, EquivocationProof, GrandpaApi, OpaqueKeyOwnershipProof,
+};
 use sp_state_machine::{InMemoryBackend, prove_read, read_proof_check};
 use std::{pin::Pin, task};
 
@@ -309,6 +311,26 @@ impl GrandpaApi<Block> for RuntimeApi {
 	) -> Result<NativeOrEncoded<AuthorityList>> {
 		Ok(self.inner.genesis_authorities.clone()).map(NativeOrEncoded::Native)
 	}
+
+	fn GrandpaApi_submit_report_equivocation_unsigned_extrinsic_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<(EquivocationProof<Hash, BlockNumber>, OpaqueKeyOwnershipProof)>,
+		_: Vec<u8>,
+	) -> Result<NativeOrEncoded<Option<()>>> {
+		Ok(NativeOrEncoded::Native(None))
+	}
+
+	fn GrandpaApi_generate_key_ownership_proof_runtime_api_impl(
+		&self,
+		_: &BlockId<Block>,
+		_: ExecutionContext,
+		_: Option<(SetId, AuthorityId)>,
+		_: Vec<u8>,
+	) -> Result<NativeOrEncoded<Option<OpaqueKeyOwnershipProof>>> {
+		Ok(NativeOrEncoded::Native(None))
+	}
 }
 
 impl GenesisAuthoritySetProvider<Block> for TestApi {
@@ -442,6 +464,7 @@ fn run_to_completion_with<F>(
 			telemetry_on_connect: None,
 			voting_rule: (),
 			executor: threads_pool.clone(),
+			prometheus_registry: None,
 		};
 		let voter = run_grandpa_voter(grandpa_params).expect("all in order with client and network");
 
@@ -582,6 +605,7 @@ fn finalize_3_voters_1_full_observer() {
 			telemetry_on_connect: None,
 			voting_rule: (),
 			executor: threads_pool.clone(),
+			prometheus_registry: None,
 		};
 
 		voters.push(run_grandpa_voter(grandpa_params).expect("all in order with client and network"));
@@ -751,6 +775,7 @@ fn transition_3_voters_twice_1_full_observer() {
 			telemetry_on_connect: None,
 			voting_rule: (),
 			executor: threads_pool.clone(),
+			prometheus_registry: None,
 		};
 		let voter = run_grandpa_voter(grandpa_params).expect("all in order with client and network");
 
@@ -1202,6 +1227,7 @@ fn voter_persists_its_votes() {
 							telemetry_on_connect: None,
 							voting_rule: VotingRulesBuilder::default().build(),
 							executor: self.threads_pool.clone(),
+							prometheus_registry: None,
 						};
 
 						let voter = run_grandpa_voter(grandpa_params)
@@ -1554,6 +1580,7 @@ fn voter_catches_up_to_latest_round_when_behind() {
 			telemetry_on_connect: None,
 			voting_rule: (),
 			executor: threads_pool.clone(),
+			prometheus_registry: None,
 		};
 
 		Box::new(run_grandpa_voter(grandpa_params).expect("all in order with client and network"))
@@ -1687,6 +1714,7 @@ fn grandpa_environment_respects_voting_rules() {
 			voters: Arc::new(authority_set.current_authorities()),
 			network,
 			voting_rule,
+			metrics: None,
 		}
 	};
 
@@ -64,6 +64,31 @@ pub trait Client<Block: BlockT>: Send + Sync {
 	/// Get method execution proof.
 	fn execution_proof(&self, block: &Block::Hash, method: &str, data: &[u8]) -> Result<(Vec<u8>, StorageProof), Error>;
 
+	/// Get a read proof of the storage entries starting at `start`, covering at least
+	/// `max_size` bytes unless the end of the state is reached. Returns the number of
+	/// entries proven.
+	fn read_state_range_proof(
+		&self,
+		block: &Block::Hash,
+		start: &[u8],
+		max_size: usize,
+	) -> Result<(usize, StorageProof), Error>;
+
+	/// Store a chunk of the state of a block until the whole state has been downloaded.
+	fn store_state_chunk(
+		&self,
+		block: &Block::Hash,
+		index: u32,
+		entries: Vec<(Vec<u8>, Vec<u8>)>,
+	) -> Result<(), Error>;
+
+	/// Remove the first `chunks` stored chunks of the state of a block.
+	fn discard_state(&self, block: &Block::Hash, chunks: u32) -> Result<(), Error>;
+
+	/// Import the full state of a finalized block that has been imported without state, from
+	/// its first `chunks` stored chunks. The chunks are removed afterwards.
+	fn import_state(&self, block: &Block::Hash, chunks: u32) -> Result<(), Error>;
+
 	/// Get key changes proof.
 	fn key_changes_proof(
 		&self,
@@ -159,6 +184,33 @@ impl<B, E, Block, RA> Client<Block> for SubstrateClient<B, E, Block, RA> where
 		)
 	}
 
+	fn read_state_range_proof(
+		&self,
+		block: &Block::Hash,
+		start: &[u8],
+		max_size: usize,
+	) -> Result<(usize, StorageProof), Error> {
+		(self as &SubstrateClient<B, E, Block, RA>)
+			.read_state_range_proof(&BlockId::Hash(block.clone()), start, max_size)
+	}
+
+	fn store_state_chunk(
+		&self,
+		block: &Block::Hash,
+		index: u32,
+		entries: Vec<(Vec<u8>, Vec<u8>)>,
+	) -> Result<(), Error> {
+		(self as &SubstrateClient<B, E, Block, RA>).store_state_chunk(block.clone(), index, entries)
+	}
+
+	fn discard_state(&self, block: &Block::Hash, chunks: u32) -> Result<(), Error> {
+		(self as &SubstrateClient<B, E, Block, RA>).discard_state(block.clone(), chunks)
+	}
+
+	fn import_state(&self, block: &Block::Hash, chunks: u32) -> Result<(), Error> {
+		(self as &SubstrateClient<B, E, Block, RA>).import_state(block.clone(), chunks)
+	}
+
 	fn key_changes_proof(
 		&self,
 		first: Block::Hash,
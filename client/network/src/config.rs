@@ -265,6 +265,8 @@ pub struct NetworkConfiguration {
 	pub transport: TransportConfig,
 	/// Maximum number of peers to ask the same blocks in parallel.
 	pub max_parallel_downloads: u32,
+	/// How a node without any state other than genesis catches up with the chain.
+	pub sync_mode: SyncMode,
 }
 
 impl Default for NetworkConfiguration {
@@ -289,6 +291,7 @@ impl Default for NetworkConfiguration {
 				wasm_external_transport: None,
 			},
 			max_parallel_downloads: 5,
+			sync_mode: SyncMode::Full,
 		}
 	}
 }
@@ -322,6 +325,19 @@ impl NetworkConfiguration {
 	}
 }
 
+/// How a node catches up with the chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncMode {
+	/// Download and execute all blocks, starting from genesis.
+	Full,
+	/// Download and verify the headers, then download the state of the latest finalized block
+	/// and execute the blocks from there on.
+	///
+	/// Only used when starting from genesis, and only for chains without child tries. If the
+	/// state can't be downloaded or imported, all blocks are imported as with `Full`.
+	Fast,
+}
+
 /// Configuration for the transport layer.
 #[derive(Clone, Debug)]
 pub enum TransportConfig {
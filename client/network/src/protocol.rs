@@ -41,7 +41,7 @@ use light_dispatch::{LightDispatch, LightDispatchNetwork, RequestData};
 use specialization::NetworkSpecialization;
 use sync::{ChainSync, SyncState};
 use crate::service::{TransactionPool, ExHashT};
-use crate::config::{BoxFinalityProofRequestBuilder, Roles};
+use crate::config::{BoxFinalityProofRequestBuilder, Roles, SyncMode};
 use rustc_hex::ToHex;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
@@ -74,12 +74,16 @@ const MAX_KNOWN_BLOCKS: usize = 1024; // ~32kb per peer + LruHashSet overhead
 const MAX_KNOWN_EXTRINSICS: usize = 4096; // ~128kb per peer + overhead
 
 /// Current protocol version.
-pub(crate) const CURRENT_VERSION: u32 = 5;
+pub(crate) const CURRENT_VERSION: u32 = 6;
 /// Lowest version we support
 pub(crate) const MIN_VERSION: u32 = 3;
+/// Lowest version that supports state requests.
+const STATE_REQUEST_MIN_VERSION: u32 = 6;
 
 // Maximum allowed entries in `BlockResponse`
 const MAX_BLOCK_DATA_RESPONSE: u32 = 128;
+// Size of the storage entries in a `StateResponse` after which no more entries are added.
+const MAX_STATE_RESPONSE_SIZE: usize = 1024 * 1024;
 /// When light node connects to the full node and the full node is behind light node
 /// for at least `LIGHT_MAXIMAL_BLOCKS_DIFFERENCE` blocks, we consider it unuseful
 /// and disconnect to free connection slot.
@@ -380,6 +384,8 @@ pub struct ProtocolConfig {
 	pub roles: Roles,
 	/// Maximum number of peers to ask the same blocks in parallel.
 	pub max_parallel_downloads: u32,
+	/// How to catch up with the chain.
+	pub sync_mode: SyncMode,
 }
 
 impl Default for ProtocolConfig {
@@ -387,6 +393,7 @@ impl Default for ProtocolConfig {
 		ProtocolConfig {
 			roles: Roles::FULL,
 			max_parallel_downloads: 5,
+			sync_mode: SyncMode::Full,
 		}
 	}
 }
@@ -413,6 +420,7 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 			finality_proof_request_builder,
 			block_announce_validator,
 			config.max_parallel_downloads,
+			config.sync_mode,
 		);
 
 		let important_peers = {
@@ -629,6 +637,10 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 				return self.on_finality_proof_response(who, response),
 			GenericMessage::RemoteReadChildRequest(request) =>
 				self.on_remote_read_child_request(who, request),
+			GenericMessage::StateRequest(request) =>
+				self.on_state_request(who, request),
+			GenericMessage::StateResponse(response) =>
+				self.on_state_response(who, response),
 			GenericMessage::Consensus(msg) =>
 				return if self.registered_notif_protocols.contains(&msg.engine_id) {
 					CustomMessageOutcome::NotificationsReceived {
@@ -1705,6 +1717,94 @@ impl<B: BlockT, S: NetworkSpecialization<B>, H: ExHashT> Protocol<B, S, H> {
 		}, who, response);
 	}
 
+	fn on_state_request(
+		&mut self,
+		who: PeerId,
+		request: message::StateRequest<B::Hash>,
+	) {
+		trace!(target: "sync", "State request {} from {} at {}", request.id, who, request.block);
+
+		// sending state requests to the node that is unable to serve it is considered a bad behavior
+		if !self.config.roles.is_full() {
+			trace!(target: "sync", "Peer {} is trying to download state from the light node", who);
+			self.behaviour.disconnect_peer(&who);
+			self.peerset_handle.report_peer(who, rep::UNEXPECTED_REQUEST);
+			return;
+		}
+
+		let (count, proof) = match self.context_data.chain.read_state_range_proof(
+			&request.block,
+			&request.start,
+			MAX_STATE_RESPONSE_SIZE,
+		) {
+			Ok(result) => result,
+			Err(error) => {
+				trace!(target: "sync", "State request {} from {} at {} failed with: {}",
+					request.id,
+					who,
+					request.block,
+					error
+				);
+				(0, StorageProof::empty())
+			}
+		};
+		self.send_message(
+			&who,
+			GenericMessage::StateResponse(message::StateResponse {
+				id: request.id,
+				count: count as u32,
+				proof,
+			}),
+		);
+	}
+
+	fn on_state_response(
+		&mut self,
+		who: PeerId,
+		response: message::StateResponse,
+	) {
+		trace!(target: "sync", "State response {} from {}: {} entries", response.id, who, response.count);
+		match self.sync.on_state_data(who, response) {
+			Ok(sync::OnStateData::Nothing) => {},
+			Ok(sync::OnStateData::Store(hash, index, entries)) => {
+				if let Err(e) = self.context_data.chain.store_state_chunk(&hash, index, entries) {
+					warn!(target: "sync", "Error storing state of {}: {:?}", hash, e);
+					if let Err(e) = self.context_data.chain.discard_state(&hash, index) {
+						warn!(target: "sync", "Error removing state of {}: {:?}", hash, e);
+					}
+					self.sync.on_state_imported(hash, false);
+				}
+			},
+			Ok(sync::OnStateData::Import(hash, index, entries)) => {
+				// imported right away, the state is not imported through the import queue.
+				let chain = &self.context_data.chain;
+				let result = match chain.store_state_chunk(&hash, index, entries) {
+					// removes the stored chunks as well.
+					Ok(()) => chain.import_state(&hash, index + 1),
+					Err(e) => {
+						if let Err(e) = chain.discard_state(&hash, index) {
+							warn!(target: "sync", "Error removing state of {}: {:?}", hash, e);
+						}
+						Err(e)
+					},
+				};
+				if let Err(e) = &result {
+					warn!(target: "sync", "Error importing state of {}: {:?}", hash, e);
+				}
+				self.sync.on_state_imported(hash, result.is_ok());
+			},
+			Ok(sync::OnStateData::Discard(hash, chunks)) => {
+				if let Err(e) = self.context_data.chain.discard_state(&hash, chunks) {
+					warn!(target: "sync", "Error removing state of {}: {:?}", hash, e);
+				}
+			},
+			Err(sync::BadPeer(id, repu)) => {
+				self.behaviour.disconnect_peer(&id);
+				self.peerset_handle.report_peer(id, repu);
+			}
+		}
+	}
+
 	fn on_finality_proof_request(
 		&mut self,
 		who: PeerId,
@@ -1905,6 +2005,18 @@ Protocol<B, S, H> {
 				&id,
 				GenericMessage::FinalityProofRequest(r))
 		}
+		let peers = &self.context_data.peers;
+		let state_request = self.sync.state_request(|who| peers.get(who).map_or(false, |peer|
+			peer.info.roles.is_full() && peer.info.protocol_version >= STATE_REQUEST_MIN_VERSION
+		));
+		if let Some((id, r)) = state_request {
+			send_request(
+				&mut self.behaviour,
+				&mut self.context_data.stats,
+				&mut self.context_data.peers,
+				&id,
+				GenericMessage::StateRequest(r))
+		}
 
 		let event = match self.behaviour.poll(cx, params) {
 			Poll::Pending => return Poll::Pending,
@@ -24,7 +24,7 @@ pub use self::generic::{
 	RemoteHeaderRequest, RemoteHeaderResponse,
 	RemoteChangesRequest, RemoteChangesResponse,
 	FinalityProofRequest, FinalityProofResponse,
-	FromBlock, RemoteReadChildRequest,
+	FromBlock, RemoteReadChildRequest, StateRequest,
 };
 use sc_client_api::StorageProof;
 
@@ -135,6 +135,18 @@ pub struct RemoteReadResponse {
 	pub proof: StorageProof,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+/// State response.
+pub struct StateResponse {
+	/// Id of a request this response was made for.
+	pub id: RequestId,
+	/// Number of storage entries proven, starting at the requested key.
+	/// Zero if the state is not available.
+	pub count: u32,
+	/// Read proof of the entries.
+	pub proof: StorageProof,
+}
+
 /// Generic types.
 pub mod generic {
 	use codec::{Encode, Decode, Input, Output};
@@ -143,7 +155,7 @@ pub mod generic {
 	use super::{
 		RemoteReadResponse, Transactions, Direction,
 		RequestId, BlockAttributes, RemoteCallResponse, ConsensusEngineId,
-		BlockState, StorageProof,
+		BlockState, StorageProof, StateResponse,
 	};
 	/// Consensus is mostly opaque to us
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
@@ -219,6 +231,10 @@ pub mod generic {
 		FinalityProofResponse(FinalityProofResponse<Hash>),
 		/// Batch of consensus protocol messages.
 		ConsensusBatch(Vec<ConsensusMessage>),
+		/// State request.
+		StateRequest(StateRequest<Hash>),
+		/// State response.
+		StateResponse(StateResponse),
 		/// Chain-specific message.
 		#[codec(index = "255")]
 		ChainSpecific(Vec<u8>),
@@ -246,6 +262,8 @@ pub mod generic {
 				Message::FinalityProofRequest(_) => "FinalityProofRequest",
 				Message::FinalityProofResponse(_) => "FinalityProofResponse",
 				Message::ConsensusBatch(_) => "ConsensusBatch",
+				Message::StateRequest(_) => "StateRequest",
+				Message::StateResponse(_) => "StateResponse",
 				Message::ChainSpecific(_) => "ChainSpecific",
 			}
 		}
@@ -377,6 +395,17 @@ pub mod generic {
 		pub keys: Vec<Vec<u8>>,
 	}
 
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	/// Request for a range of the storage entries of a block.
+	pub struct StateRequest<H> {
+		/// Unique request id.
+		pub id: RequestId,
+		/// Block whose state is requested.
+		pub block: H,
+		/// First storage key (inclusive) of the requested range.
+		pub start: Vec<u8>,
+	}
+
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
 	/// Remote header request.
 	pub struct RemoteHeaderRequest<N> {
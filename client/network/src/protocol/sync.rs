@@ -34,12 +34,13 @@ use sp_consensus::{BlockOrigin, BlockStatus,
 	import_queue::{IncomingBlock, BlockImportResult, BlockImportError}
 };
 use crate::{
-	config::{Roles, BoxFinalityProofRequestBuilder},
+	config::{Roles, BoxFinalityProofRequestBuilder, SyncMode},
 	message::{self, generic::FinalityProofRequest, BlockAnnounce, BlockAttributes, BlockRequest, BlockResponse,
-	FinalityProofResponse},
+	FinalityProofResponse, StateRequest, StateResponse},
 };
 use either::Either;
 use extra_requests::ExtraRequests;
+use state::{StateSync, OnStateResponse};
 use libp2p::PeerId;
 use log::{debug, trace, warn, info, error};
 use sp_runtime::{
@@ -51,6 +52,7 @@ use std::{fmt, ops::Range, collections::{HashMap, HashSet, VecDeque}, sync::Arc}
 
 mod blocks;
 mod extra_requests;
+mod state;
 
 /// Maximum blocks to request in a single packet.
 const MAX_BLOCKS_TO_REQUEST: usize = 128;
@@ -98,6 +100,9 @@ mod rep {
 
 	/// Reputation change when a peer sent us invlid ancestry result.
 	pub const UNKNOWN_ANCESTOR:Rep = Rep::new(-(1 << 16), "DB Error");
+
+	/// Reputation change for peers which send us state with a bad proof.
+	pub const BAD_STATE_PROOF: Rep = Rep::new(-(1 << 20), "Bad state proof");
 }
 
 /// The main data structure which contains all the state for a chains
@@ -138,6 +143,23 @@ pub struct ChainSync<B: BlockT> {
 	block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>,
 	/// Maximum number of peers to ask the same blocks in parallel.
 	max_parallel_downloads: u32,
+	/// Progress of the fast sync, if any.
+	fast_sync: Option<FastSync<B>>,
+}
+
+/// The phases of a fast sync.
+enum FastSync<B: BlockT> {
+	/// Downloading and importing headers only.
+	Headers,
+	/// Downloading the state of a finalized block.
+	State(StateSync<B>),
+	/// Importing the blocks after the block with the downloaded state again, this time with their
+	/// bodies. `base` is the last block with state, `until` the best block when the state was
+	/// imported.
+	Reimport {
+		base: NumberFor<B>,
+		until: NumberFor<B>,
+	},
 }
 
 /// All the data we have about a Peer that we are trying to sync with
@@ -192,7 +214,9 @@ pub enum PeerSyncState<B: BlockT> {
 	/// Downloading justification for given block hash.
 	DownloadingJustification(B::Hash),
 	/// Downloading finality proof for given block hash.
-	DownloadingFinalityProof(B::Hash)
+	DownloadingFinalityProof(B::Hash),
+	/// Downloading state of a block.
+	DownloadingState,
 }
 
 impl<B: BlockT> PeerSyncState<B> {
@@ -248,6 +272,21 @@ pub enum OnBlockData<B: BlockT> {
 	Request(PeerId, BlockRequest<B>)
 }
 
+/// Result of [`ChainSync::on_state_data`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OnStateData<B: BlockT> {
+	/// The response needs no further handling.
+	Nothing,
+	/// A chunk of the state of the given block with the given index, which should be stored.
+	Store(B::Hash, u32, Vec<(Vec<u8>, Vec<u8>)>),
+	/// The last chunk of the state of the given block with the given index. Once it has been
+	/// stored, the state should be imported from all chunks.
+	Import(B::Hash, u32, Vec<(Vec<u8>, Vec<u8>)>),
+	/// The fast sync has been given up, the given number of stored chunks of the state of the
+	/// given block should be removed.
+	Discard(B::Hash, u32),
+}
+
 /// Result of [`ChainSync::on_block_announce`].
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum OnBlockAnnounce {
@@ -294,10 +333,19 @@ impl<B: BlockT> ChainSync<B> {
 		request_builder: Option<BoxFinalityProofRequestBuilder<B>>,
 		block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>,
 		max_parallel_downloads: u32,
+		sync_mode: SyncMode,
 	) -> Self {
 		let mut required_block_attributes = BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION;
 
-		if role.is_full() {
+		// fast sync only makes sense for a node that has nothing but the genesis block.
+		let fast_sync = if role.is_full() && sync_mode == SyncMode::Fast && info.best_number.is_zero() {
+			info!("Starting fast sync");
+			Some(FastSync::Headers)
+		} else {
+			None
+		};
+
+		if role.is_full() && fast_sync.is_none() {
 			required_block_attributes |= BlockAttributes::BODY
 		}
 
@@ -318,6 +366,7 @@ impl<B: BlockT> ChainSync<B> {
 			is_idle: false,
 			block_announce_validator,
 			max_parallel_downloads,
+			fast_sync,
 		}
 	}
 
@@ -332,17 +381,13 @@ impl<B: BlockT> ChainSync<B> {
 	pub fn status(&self) -> Status<B> {
 		let best_seen = self.peers.values().max_by_key(|p| p.best_number).map(|p| p.best_number);
 		let sync_state =
-			if let Some(n) = best_seen {
-				// A chain is classified as downloading if the provided best block is
-				// more than `MAJOR_SYNC_BLOCKS` behind the best queued block.
-				if n > self.best_queued_number && n - self.best_queued_number > MAJOR_SYNC_BLOCKS.into() {
-					SyncState::Downloading
-				} else {
-					SyncState::Idle
-				}
+			if self.is_far_behind(best_seen) {
+				SyncState::Downloading
 			} else {
 				SyncState::Idle
 			};
+		// the chain can not be used before the fast sync is done.
+		let sync_state = if self.fast_sync.is_some() { SyncState::Downloading } else { sync_state };
 
 		Status {
 			state: sync_state,
@@ -352,6 +397,14 @@ impl<B: BlockT> ChainSync<B> {
 		}
 	}
 
+	/// A chain is classified as downloading if the provided best block is
+	/// more than `MAJOR_SYNC_BLOCKS` behind the best queued block.
+	fn is_far_behind(&self, best_seen: Option<NumberFor<B>>) -> bool {
+		best_seen.map_or(false, |n| {
+			n > self.best_queued_number && n - self.best_queued_number > MAJOR_SYNC_BLOCKS.into()
+		})
+	}
+
 	/// Number of active sync requests.
 	pub fn num_sync_requests(&self) -> usize {
 		self.fork_targets.len()
@@ -436,8 +489,13 @@ impl<B: BlockT> ChainSync<B> {
 			}
 			Ok(BlockStatus::Queued) | Ok(BlockStatus::InChainWithState) | Ok(BlockStatus::InChainPruned) => {
 				debug!(target:"sync", "New peer with known best hash {} ({}).", best_hash, best_number);
+				// blocks imported without bodies during a fast sync still need to be downloaded.
+				let common_number = match self.fast_sync {
+					Some(FastSync::Reimport { .. }) => std::cmp::min(best_number, self.best_queued_number),
+					_ => best_number,
+				};
 				self.peers.insert(who.clone(), PeerSync {
-					common_number: best_number,
+					common_number,
 					best_hash,
 					best_number,
 					state: PeerSyncState::Available,
@@ -574,6 +632,10 @@ impl<B: BlockT> ChainSync<B> {
 		if self.is_idle {
 			return Either::Left(std::iter::empty())
 		}
+		if let Some(FastSync::State(_)) = self.fast_sync {
+			trace!(target: "sync", "Downloading state, no block requests.");
+			return Either::Left(std::iter::empty())
+		}
 		if self.queue_blocks.len() > MAX_IMPORTING_BLOCKS {
 			trace!(target: "sync", "Too many blocks in the queue.");
 			return Either::Left(std::iter::empty())
@@ -583,7 +645,11 @@ impl<B: BlockT> ChainSync<B> {
 		let attrs = &self.required_block_attributes;
 		let fork_targets = &mut self.fork_targets;
 		let mut have_requests = false;
-		let last_finalized = self.client.info().finalized_number;
+		let last_finalized = match self.fast_sync {
+			// finalized blocks without state are downloaded again.
+			Some(FastSync::Reimport { base, .. }) => base,
+			_ => self.client.info().finalized_number,
+		};
 		let best_queued = self.best_queued_number;
 		let client = &self.client;
 		let queue = &self.queue_blocks;
@@ -639,6 +705,157 @@ impl<B: BlockT> ChainSync<B> {
 		Either::Right(iter)
 	}
 
+	/// Get the next state request, if the state of a block is being downloaded.
+	///
+	/// Only peers for which `can_serve` returns `true` are asked.
+	pub fn state_request(&mut self, can_serve: impl Fn(&PeerId) -> bool) -> Option<(PeerId, StateRequest<B::Hash>)> {
+		self.update_fast_sync();
+		let state = match &mut self.fast_sync {
+			Some(FastSync::State(state)) => state,
+			_ => return None,
+		};
+		if let Some(who) = state.timed_out_peer() {
+			if let Some(peer) = self.peers.get_mut(&who) {
+				peer.state = PeerSyncState::Available;
+			}
+		}
+		for (id, peer) in self.peers.iter_mut() {
+			if !peer.state.is_available() || !can_serve(id) {
+				continue
+			}
+			if let Some(request) = state.request(id, peer.best_number) {
+				trace!(target: "sync", "New state request for {} to {}", request.block, id);
+				peer.state = PeerSyncState::DownloadingState;
+				return Some((id.clone(), request))
+			}
+		}
+		None
+	}
+
+	/// Moves a fast sync that downloaded all headers on to download the state of the
+	/// last finalized block.
+	fn update_fast_sync(&mut self) {
+		match self.fast_sync {
+			Some(FastSync::Headers) => {},
+			_ => return,
+		}
+		let best_seen = self.peers.values().map(|p| p.best_number).max();
+		if best_seen.is_none() || self.is_far_behind(best_seen) || !self.queue_blocks.is_empty() {
+			return
+		}
+
+		let info = self.client.info();
+		if info.finalized_number.is_zero() {
+			// nothing is finalized, all blocks are imported again on top of genesis.
+			self.start_reimport(info.finalized_hash, info.finalized_number);
+			return
+		}
+		match self.client.header(&BlockId::Hash(info.finalized_hash)) {
+			Ok(Some(header)) => {
+				info!(
+					"Headers downloaded, downloading state of #{} ({})",
+					info.finalized_number,
+					info.finalized_hash,
+				);
+				self.fast_sync = Some(FastSync::State(StateSync::new(&header)));
+			},
+			Ok(None) => warn!(target: "sync", "Missing header of finalized block {}", info.finalized_hash),
+			Err(e) => warn!(target: "sync", "Error reading header of finalized block: {:?}", e),
+		}
+	}
+
+	/// Start downloading the bodies of all blocks after the given one, which has state.
+	fn start_reimport(&mut self, hash: B::Hash, number: NumberFor<B>) {
+		let until = self.client.info().best_number;
+		debug!(target: "sync", "Importing blocks #{}..#{} with state", number, until);
+		self.required_block_attributes |= BlockAttributes::BODY;
+		self.blocks.clear();
+		self.best_queued_hash = hash;
+		self.best_queued_number = number;
+		for peer in self.peers.values_mut() {
+			peer.common_number = std::cmp::min(peer.common_number, number);
+		}
+		self.is_idle = false;
+		self.fast_sync = if number < until {
+			Some(FastSync::Reimport { base: number, until })
+		} else {
+			info!("Fast sync complete at #{}", number);
+			None
+		};
+	}
+
+	/// Notes that a block has been imported, ending the fast sync once all blocks
+	/// have been imported with state.
+	fn note_reimported(&mut self, number: NumberFor<B>) {
+		let complete = match &mut self.fast_sync {
+			Some(FastSync::Reimport { base, until }) => {
+				if number > *base {
+					*base = number;
+				}
+				*base >= *until
+			},
+			_ => false,
+		};
+		if complete {
+			info!("Fast sync complete at #{}", number);
+			self.fast_sync = None;
+		}
+	}
+
+	/// Handle a response from the remote to a state request that we made.
+	pub fn on_state_data(&mut self, who: PeerId, response: StateResponse) -> Result<OnStateData<B>, BadPeer> {
+		if let Some(peer) = self.peers.get_mut(&who) {
+			if let PeerSyncState::DownloadingState = peer.state {
+				peer.state = PeerSyncState::Available;
+			}
+		}
+		let state = match &mut self.fast_sync {
+			Some(FastSync::State(state)) => state,
+			_ => {
+				trace!(target: "sync", "Unexpected state response from {}", who);
+				return Ok(OnStateData::Nothing)
+			},
+		};
+		match state.on_response(&who, response) {
+			None | Some(OnStateResponse::Continue) => Ok(OnStateData::Nothing),
+			Some(OnStateResponse::Chunk(hash, index, entries)) => Ok(OnStateData::Store(hash, index, entries)),
+			Some(OnStateResponse::Import(hash, index, entries)) => Ok(OnStateData::Import(hash, index, entries)),
+			Some(OnStateResponse::ChildTries(hash, chunks)) => {
+				error!(
+					target: "sync",
+					"Fast sync is not supported for chains with child tries, found in the state of #{}",
+					state.target().1,
+				);
+				self.fall_back_to_full_sync();
+				Ok(OnStateData::Discard(hash, chunks))
+			},
+			Some(OnStateResponse::BadProof) => Err(BadPeer(who, rep::BAD_STATE_PROOF)),
+		}
+	}
+
+	/// Call when the downloaded state of a block has been imported, with or without errors.
+	pub fn on_state_imported(&mut self, hash: B::Hash, success: bool) {
+		let number = match &self.fast_sync {
+			Some(FastSync::State(state)) if state.target().0 == hash => state.target().1,
+			_ => return,
+		};
+		if success {
+			info!("Imported state of #{} ({})", number, hash);
+			self.start_reimport(hash, number);
+		} else {
+			// the state has been verified against the header, downloading it again won't help.
+			error!(target: "sync", "Failed to import the state of #{} ({})", number, hash);
+			self.fall_back_to_full_sync();
+		}
+	}
+
+	/// Gives up the fast sync and imports all blocks with their state, starting at genesis.
+	fn fall_back_to_full_sync(&mut self) {
+		warn!(target: "sync", "Fast sync failed, importing all blocks instead");
+		let genesis = self.client.info().genesis_hash;
+		self.start_reimport(genesis, Zero::zero());
+	}
+
 	/// Handle a response from the remote to a block request that we made.
 	///
 	/// `request` must be the original request that triggered `response`.
@@ -663,10 +880,16 @@ impl<B: BlockT> ChainSync<B> {
 							self.blocks.clear_peer_download(&who);
 							self.blocks.insert(*start_block, blocks, who);
 							peer.state = PeerSyncState::Available;
+							let reimport = if let Some(FastSync::Reimport { .. }) = self.fast_sync { true } else { false };
+							let client = &self.client;
 							self.blocks
 								.drain(self.best_queued_number + One::one())
 								.into_iter()
 								.map(|block_data| {
+									// headers imported during a fast sync are imported again with their state.
+									let import_existing = reimport && client
+										.block_status(&BlockId::Hash(block_data.block.hash))
+										.map_or(false, |status| status == BlockStatus::InChainPruned);
 									IncomingBlock {
 										hash: block_data.block.hash,
 										header: block_data.block.header,
@@ -674,7 +897,7 @@ impl<B: BlockT> ChainSync<B> {
 										justification: block_data.block.justification,
 										origin: block_data.origin,
 										allow_missing_state: true,
-										import_existing: false,
+										import_existing,
 									}
 								}).collect()
 						}
@@ -748,7 +971,8 @@ impl<B: BlockT> ChainSync<B> {
 
 						| PeerSyncState::Available
 						| PeerSyncState::DownloadingJustification(..)
-						| PeerSyncState::DownloadingFinalityProof(..) => Vec::new()
+						| PeerSyncState::DownloadingFinalityProof(..)
+						| PeerSyncState::DownloadingState => Vec::new()
 					}
 				} else {
 					// When request.is_none() just acccept blocks
@@ -899,8 +1123,10 @@ impl<B: BlockT> ChainSync<B> {
 			}
 
 			match result {
-				Ok(BlockImportResult::ImportedKnown(_number)) => {}
+				Ok(BlockImportResult::ImportedKnown(number)) => self.note_reimported(number),
 				Ok(BlockImportResult::ImportedUnknown(number, aux, who)) => {
+					self.note_reimported(number);
+
 					if aux.clear_justification_requests {
 						trace!(
 							target: "sync",
@@ -1093,6 +1319,10 @@ impl<B: BlockT> ChainSync<B> {
 			} else if header.parent_hash() == &self.best_queued_hash || known_parent {
 				peer.common_number = number - One::one();
 			}
+			// known blocks may still lack their state during a fast sync.
+			if let Some(FastSync::Reimport { .. }) = self.fast_sync {
+				peer.common_number = std::cmp::min(peer.common_number, self.best_queued_number);
+			}
 		}
 		self.is_idle = false;
 
@@ -1150,6 +1380,9 @@ impl<B: BlockT> ChainSync<B> {
 
 	/// Call when a peer has disconnected.
 	pub fn peer_disconnected(&mut self, who: PeerId) {
+		if let Some(FastSync::State(state)) = &mut self.fast_sync {
+			state.peer_disconnected(&who);
+		}
 		self.blocks.clear_peer_download(&who);
 		self.peers.remove(&who);
 		self.extra_justifications.peer_disconnected(&who);
@@ -1165,6 +1398,13 @@ impl<B: BlockT> ChainSync<B> {
 		let info = self.client.info();
 		self.best_queued_hash = info.best_hash;
 		self.best_queued_number = std::cmp::max(info.best_number, self.best_imported_number);
+		if let Some(FastSync::Reimport { base, .. }) = self.fast_sync {
+			// the blocks after `base` are known, but don't have state yet.
+			if let Ok(Some(hash)) = self.client.block_hash(base) {
+				self.best_queued_hash = hash;
+				self.best_queued_number = base;
+			}
+		}
 		self.is_idle = false;
 		debug!(target:"sync", "Restarted with {} ({})", self.best_queued_number, self.best_queued_hash);
 		let old_peers = std::mem::replace(&mut self.peers, HashMap::new());
@@ -0,0 +1,317 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Download of the full state of a block, in chunks of consecutive storage entries. Every
+//! chunk comes with a read proof that is checked against the state root of the block header.
+//! Verified chunks are handed out right away, to be stored until the whole state can be
+//! imported.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use log::{debug, trace};
+use libp2p::PeerId;
+use sp_core::storage::well_known_keys;
+use sp_runtime::traits::{Block as BlockT, Header, NumberFor, HasherFor};
+use sp_state_machine::read_range_proof_check;
+use crate::message::{self, StateResponse};
+
+/// Time after which a peer that hasn't answered a state request is no longer asked.
+const STATE_REQUEST_TIMEOUT: Duration = Duration::from_secs(40);
+
+/// Result of [`StateSync::on_response`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OnStateResponse<B: BlockT> {
+	/// More state needs to be downloaded.
+	Continue,
+	/// A verified chunk of the state with the given index, which should be stored. More chunks
+	/// follow.
+	Chunk(B::Hash, u32, Vec<(Vec<u8>, Vec<u8>)>),
+	/// The last chunk of the state with the given index. Once stored, the whole state should be
+	/// imported.
+	Import(B::Hash, u32, Vec<(Vec<u8>, Vec<u8>)>),
+	/// The state contains child tries, which can't be downloaded. The given number of chunks
+	/// have been handed out before.
+	ChildTries(B::Hash, u32),
+	/// The response has an invalid proof.
+	BadProof,
+}
+
+/// State download of a single block.
+pub struct StateSync<B: BlockT> {
+	/// Hash of the block whose state is downloaded.
+	target_hash: B::Hash,
+	/// Number of the block whose state is downloaded.
+	target_number: NumberFor<B>,
+	/// State root of the block whose state is downloaded.
+	target_root: B::Hash,
+	/// First key of the next chunk.
+	next_key: Vec<u8>,
+	/// Number of chunks downloaded so far.
+	chunks: u32,
+	/// Number of entries downloaded so far.
+	entries: usize,
+	/// Size of the entries downloaded so far.
+	state_size: usize,
+	/// Peer the pending request has been sent to and when.
+	pending: Option<(PeerId, Instant)>,
+	/// Peers which are not able to provide the state.
+	unavailable: HashSet<PeerId>,
+}
+
+impl<B: BlockT> StateSync<B> {
+	/// Start downloading the state of the given block.
+	pub fn new(target: &B::Header) -> Self {
+		StateSync {
+			target_hash: target.hash(),
+			target_number: *target.number(),
+			target_root: *target.state_root(),
+			next_key: Vec::new(),
+			chunks: 0,
+			entries: 0,
+			state_size: 0,
+			pending: None,
+			unavailable: HashSet::new(),
+		}
+	}
+
+	/// Hash and number of the block whose state is downloaded.
+	pub fn target(&self) -> (B::Hash, NumberFor<B>) {
+		(self.target_hash, self.target_number)
+	}
+
+	/// Number of entries and total size of the state downloaded so far.
+	pub fn progress(&self) -> (usize, usize) {
+		(self.entries, self.state_size)
+	}
+
+	/// Returns the peer with a request that has not been answered in time, if any.
+	/// The peer will not be asked again.
+	pub fn timed_out_peer(&mut self) -> Option<PeerId> {
+		match self.pending.take() {
+			Some((who, sent)) if sent.elapsed() > STATE_REQUEST_TIMEOUT => {
+				debug!(target: "sync", "State request to {} timed out", who);
+				self.unavailable.insert(who.clone());
+				Some(who)
+			},
+			pending => {
+				self.pending = pending;
+				None
+			},
+		}
+	}
+
+	/// Create the next request if the given peer should be asked.
+	pub fn request(&mut self, who: &PeerId, best_number: NumberFor<B>) -> Option<message::StateRequest<B::Hash>> {
+		if self.pending.is_some() || best_number < self.target_number || self.unavailable.contains(who) {
+			return None
+		}
+		self.pending = Some((who.clone(), Instant::now()));
+		Some(message::StateRequest {
+			id: 0,
+			block: self.target_hash,
+			start: self.next_key.clone(),
+		})
+	}
+
+	/// Handle a response to a state request.
+	///
+	/// Returns `None` if the response was not expected.
+	pub fn on_response(&mut self, who: &PeerId, response: StateResponse) -> Option<OnStateResponse<B>> {
+		match self.pending {
+			Some((ref pending, _)) if pending == who => {},
+			_ => return None,
+		}
+		self.pending = None;
+
+		if response.count == 0 {
+			debug!(target: "sync", "Peer {} can not provide the state of {}", who, self.target_hash);
+			self.unavailable.insert(who.clone());
+			return Some(OnStateResponse::Continue)
+		}
+
+		let (entries, complete) = match read_range_proof_check::<HasherFor<B>>(
+			self.target_root,
+			response.proof,
+			&self.next_key,
+			response.count as usize,
+		) {
+			Ok(result) => result,
+			Err(e) => {
+				debug!(target: "sync", "Bad state proof from {}: {:?}", who, e);
+				return Some(OnStateResponse::BadProof)
+			},
+		};
+
+		trace!(
+			target: "sync",
+			"Received {} state entries of {} from {}, complete: {}",
+			entries.len(),
+			self.target_hash,
+			who,
+			complete,
+		);
+
+		// the roots of child tries are part of the top trie, but not where their nodes are
+		// stored, so they can't be served.
+		if entries.iter().any(|(key, _)| well_known_keys::is_child_storage_key(key)) {
+			debug!(target: "sync", "State of {} contains child tries", self.target_hash);
+			return Some(OnStateResponse::ChildTries(self.target_hash, self.chunks))
+		}
+
+		if let Some((last_key, _)) = entries.last() {
+			// the smallest key that is bigger than the last key.
+			self.next_key = last_key.clone();
+			self.next_key.push(0);
+		}
+		let index = self.chunks;
+		self.chunks += 1;
+		self.entries += entries.len();
+		self.state_size += entries.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>();
+
+		if complete {
+			debug!(
+				target: "sync",
+				"Downloaded state of #{} ({}): {} entries, {} bytes",
+				self.target_number,
+				self.target_hash,
+				self.entries,
+				self.state_size,
+			);
+			Some(OnStateResponse::Import(self.target_hash, index, entries))
+		} else {
+			Some(OnStateResponse::Chunk(self.target_hash, index, entries))
+		}
+	}
+
+	/// Call when a peer has disconnected.
+	pub fn peer_disconnected(&mut self, who: &PeerId) {
+		if self.pending.as_ref().map_or(false, |(pending, _)| pending == who) {
+			self.pending = None;
+		}
+		self.unavailable.remove(who);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::BTreeMap;
+	use sp_core::{Blake2Hasher, H256};
+	use sp_state_machine::{Backend, InMemoryBackend, prove_range_read_with_size};
+	use sp_test_primitives::Block;
+
+	fn test_backend() -> (InMemoryBackend<Blake2Hasher>, Vec<(Vec<u8>, Vec<u8>)>) {
+		let storage: Vec<_> = (0u8..100).map(|i| (vec![i; 3], vec![i; 20])).collect();
+		let backend = InMemoryBackend::<Blake2Hasher>::from(storage.iter().cloned().collect::<BTreeMap<_, _>>());
+		(backend, storage)
+	}
+
+	fn test_header(state_root: H256) -> <Block as BlockT>::Header {
+		sp_test_primitives::Header {
+			parent_hash: Default::default(),
+			number: 10,
+			state_root,
+			extrinsics_root: Default::default(),
+			digest: Default::default(),
+		}
+	}
+
+	#[test]
+	fn downloads_state_in_verified_chunks() {
+		let (backend, storage) = test_backend();
+		let header = test_header(backend.storage_root(std::iter::empty()).0);
+		let mut sync = StateSync::<Block>::new(&header);
+		let peer = PeerId::random();
+
+		assert!(sync.request(&peer, 9).is_none());
+		let mut state = Vec::new();
+		let mut chunks = 0;
+		loop {
+			let request = sync.request(&peer, 10).unwrap();
+			assert!(sync.request(&PeerId::random(), 10).is_none());
+			let (entries, proof, _) = prove_range_read_with_size(backend.clone(), &request.start, 200).unwrap();
+			let response = StateResponse { id: 0, count: entries.len() as u32, proof };
+			match sync.on_response(&peer, response) {
+				Some(OnStateResponse::Chunk(hash, index, entries)) => {
+					assert_eq!((hash, index), (header.hash(), chunks));
+					chunks += 1;
+					state.extend(entries);
+				},
+				Some(OnStateResponse::Import(hash, index, entries)) => {
+					assert_eq!((hash, index), (header.hash(), chunks));
+					state.extend(entries);
+					break
+				},
+				r => panic!("Unexpected response result: {:?}", r),
+			}
+		}
+
+		assert!(chunks > 1);
+		assert_eq!(state, storage);
+		assert_eq!(sync.progress().0, storage.len());
+	}
+
+	#[test]
+	fn child_tries_are_detected() {
+		let mut child_root_key = well_known_keys::CHILD_STORAGE_KEY_PREFIX.to_vec();
+		child_root_key.extend_from_slice(b"default:child");
+		let storage = vec![(vec![1, 2, 3], vec![9; 20]), (child_root_key, vec![0; 32])];
+		let backend = InMemoryBackend::<Blake2Hasher>::from(storage.into_iter().collect::<BTreeMap<_, _>>());
+		let header = test_header(backend.storage_root(std::iter::empty()).0);
+		let mut sync = StateSync::<Block>::new(&header);
+		let peer = PeerId::random();
+
+		sync.request(&peer, 10).unwrap();
+		let (entries, proof, _) = prove_range_read_with_size(backend.clone(), &[], 1000).unwrap();
+		let response = StateResponse { id: 0, count: entries.len() as u32, proof };
+		assert_eq!(sync.on_response(&peer, response), Some(OnStateResponse::ChildTries(header.hash(), 0)));
+	}
+
+	#[test]
+	fn rejects_unproven_entries() {
+		let (backend, _) = test_backend();
+		let header = test_header(backend.storage_root(std::iter::empty()).0);
+		let mut sync = StateSync::<Block>::new(&header);
+		let peer = PeerId::random();
+
+		sync.request(&peer, 10).unwrap();
+		let (entries, proof, _) = prove_range_read_with_size(backend.clone(), &[], 200).unwrap();
+		let response = StateResponse { id: 0, count: entries.len() as u32 + 1, proof };
+		assert_eq!(sync.on_response(&peer, response), Some(OnStateResponse::BadProof));
+	}
+
+	#[test]
+	fn peers_without_state_are_not_asked_again() {
+		let (backend, _) = test_backend();
+		let header = test_header(backend.storage_root(std::iter::empty()).0);
+		let mut sync = StateSync::<Block>::new(&header);
+		let peer = PeerId::random();
+
+		sync.request(&peer, 10).unwrap();
+		assert_eq!(sync.on_response(&PeerId::random(), StateResponse {
+			id: 0,
+			count: 0,
+			proof: sp_state_machine::StorageProof::empty(),
+		}), None);
+		assert_eq!(sync.on_response(&peer, StateResponse {
+			id: 0,
+			count: 0,
+			proof: sp_state_machine::StorageProof::empty(),
+		}), Some(OnStateResponse::Continue));
+		assert!(sync.request(&peer, 10).is_none());
+		assert!(sync.request(&PeerId::random(), 10).is_some());
+	}
+}
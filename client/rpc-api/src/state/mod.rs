@@ -17,6 +17,7 @@
 //! Substrate state API.
 
 pub mod error;
+pub mod helpers;
 
 use jsonrpc_core::Result as RpcResult;
 use jsonrpc_core::futures::Future;
@@ -28,6 +29,7 @@ use sp_version::RuntimeVersion;
 use self::error::FutureResult;
 
 pub use self::gen_client::Client as StateClient;
+pub use self::helpers::ReadProof;
 
 /// Substrate state API
 #[rpc]
@@ -99,6 +101,21 @@ pub trait StateApi<Hash> {
 		hash: Option<Hash>
 	) -> FutureResult<Option<u64>>;
 
+	/// Returns proof of storage entries at a specific block's state.
+	#[rpc(name = "state_getReadProof")]
+	fn read_proof(&self, keys: Vec<StorageKey>, hash: Option<Hash>) -> FutureResult<ReadProof<Hash>>;
+
+	/// Returns proof of child storage entries at a specific block's state.
+	#[rpc(name = "state_getChildReadProof")]
+	fn read_child_proof(
+		&self,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		keys: Vec<StorageKey>,
+		hash: Option<Hash>
+	) -> FutureResult<ReadProof<Hash>>;
+
 	/// Returns the runtime metadata as an opaque blob.
 	#[rpc(name = "state_getMetadata")]
 	fn metadata(&self, hash: Option<Hash>) -> FutureResult<Bytes>;
@@ -119,7 +119,7 @@ where
 		};
 		let best_block_hash = self.client.chain_info().best_hash;
 		Box::new(self.pool
-			.submit_one(&generic::BlockId::hash(best_block_hash), xt)
+			.submit_local(&generic::BlockId::hash(best_block_hash), xt)
 			.compat()
 			.map_err(|e| e.into_pool_error()
 				.map(Into::into)
@@ -144,6 +144,23 @@ fn should_watch_extrinsic() {
 	);
 }
 
+#[test]
+fn transaction_status_wire_names_should_be_backwards_compatible() {
+	use sp_transaction_pool::TransactionStatus;
+
+	let h = H256::from_low_u64_be(5);
+	let to_json = |status: TransactionStatus<H256, H256>| serde_json::to_string(&status).unwrap();
+
+	// inclusion in a block is still reported as `finalized`, see #4438.
+	assert_eq!(to_json(TransactionStatus::InBlock(h)), format!(r#"{{"finalized":"{:?}"}}"#, h));
+	assert_eq!(to_json(TransactionStatus::Finalized(h)), format!(r#"{{"blockFinalized":"{:?}"}}"#, h));
+	assert_eq!(to_json(TransactionStatus::Retracted(h)), format!(r#"{{"retracted":"{:?}"}}"#, h));
+	assert_eq!(
+		to_json(TransactionStatus::FinalityTimeout(h)),
+		format!(r#"{{"finalityTimeout":"{:?}"}}"#, h),
+	);
+}
+
 #[test]
 fn should_return_watch_validation_error() {
 	//given
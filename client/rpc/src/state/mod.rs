@@ -128,6 +128,23 @@ pub trait StateBackend<B, E, Block: BlockT, RA>: Send + Sync + 'static
 			.map(|x| x.map(|x| x.0.len() as u64)))
 	}
 
+	/// Returns proof of storage entries at a specific block's state.
+	fn read_proof(
+		&self,
+		block: Option<Block::Hash>,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<ReadProof<Block::Hash>>;
+
+	/// Returns proof of child storage entries at a specific block's state.
+	fn read_child_proof(
+		&self,
+		block: Option<Block::Hash>,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<ReadProof<Block::Hash>>;
+
 	/// Returns the runtime metadata as an opaque blob.
 	fn metadata(&self, block: Option<Block::Hash>) -> FutureResult<Bytes>;
 
@@ -300,6 +317,21 @@ impl<B, E, Block, RA> StateApi<Block::Hash> for State<B, E, Block, RA>
 		self.backend.child_storage_size(block, child_storage_key, child_info, child_type, key)
 	}
 
+	fn read_proof(&self, keys: Vec<StorageKey>, block: Option<Block::Hash>) -> FutureResult<ReadProof<Block::Hash>> {
+		self.backend.read_proof(block, keys)
+	}
+
+	fn read_child_proof(
+		&self,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		keys: Vec<StorageKey>,
+		block: Option<Block::Hash>
+	) -> FutureResult<ReadProof<Block::Hash>> {
+		self.backend.read_child_proof(block, child_storage_key, child_info, child_type, keys)
+	}
+
 	fn metadata(&self, block: Option<Block::Hash>) -> FutureResult<Bytes> {
 		self.backend.metadata(block)
 	}
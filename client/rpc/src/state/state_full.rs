@@ -43,7 +43,9 @@ use sp_runtime::{
 
 use sp_api::{Metadata, ProvideRuntimeApi};
 
-use super::{StateBackend, error::{FutureResult, Error, Result}, client_err, child_resolution_error};
+use super::{
+	StateBackend, ReadProof, error::{FutureResult, Error, Result}, client_err, child_resolution_error,
+};
 
 /// Ranges to query in state_queryStorage.
 struct QueryStorageRange<Block: BlockT> {
@@ -339,6 +341,51 @@ impl<B, E, Block, RA> StateBackend<B, E, Block, RA> for FullState<B, E, Block, R
 				.map_err(client_err)))
 	}
 
+	fn read_proof(
+		&self,
+		block: Option<Block::Hash>,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<ReadProof<Block::Hash>> {
+		Box::new(result(
+			self.block_or_best(block)
+				.and_then(|block| {
+					self.client
+						.read_proof(&BlockId::Hash(block), keys.iter().map(|key| &key.0[..]))
+						.map(|proof| ReadProof {
+							at: block,
+							proof: proof.iter_nodes().map(Into::into).collect(),
+						})
+				})
+				.map_err(client_err)))
+	}
+
+	fn read_child_proof(
+		&self,
+		block: Option<Block::Hash>,
+		child_storage_key: StorageKey,
+		child_info: StorageKey,
+		child_type: u32,
+		keys: Vec<StorageKey>,
+	) -> FutureResult<ReadProof<Block::Hash>> {
+		Box::new(result(
+			self.block_or_best(block)
+				.and_then(|block| {
+					self.client
+						.read_child_proof(
+							&BlockId::Hash(block),
+							&child_storage_key.0,
+							ChildInfo::resolve_child_info(child_type, &child_info.0[..])
+								.ok_or_else(child_resolution_error)?,
+							keys.iter().map(|key| &key.0[..]),
+						)
+						.map(|proof| ReadProof {
+							at: block,
+							proof: proof.iter_nodes().map(Into::into).collect(),
+						})
+				})
+				.map_err(client_err)))
+	}
+
 	fn metadata(&self, block: Option<Block::Hash>) -> FutureResult<Bytes> {
 		Box::new(result(
 			self.block_or_best(block)
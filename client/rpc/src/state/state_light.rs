@@ -54,7 +54,7 @@ use sp_core::{
 use sp_version::RuntimeVersion;
 use sp_runtime::{generic::BlockId, traits::{Block as BlockT, HasherFor}};
 
-use super::{StateBackend, error::{FutureResult, Error}, client_err};
+use super::{StateBackend, ReadProof, error::{FutureResult, Error}, client_err};
 
 /// Storage data map of storage keys => (optional) storage value.
 type StorageMap = HashMap<StorageKey, Option<StorageData>>;
@@ -289,6 +289,25 @@ impl<Block, F, B, E, RA> StateBackend<B, E, Block, RA> for LightState<Block, F,
 		)
 	}
 
+	fn read_proof(
+		&self,
+		_block: Option<Block::Hash>,
+		_keys: Vec<StorageKey>,
+	) -> FutureResult<ReadProof<Block::Hash>> {
+		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
+	}
+
+	fn read_child_proof(
+		&self,
+		_block: Option<Block::Hash>,
+		_child_storage_key: StorageKey,
+		_child_info: StorageKey,
+		_child_type: u32,
+		_keys: Vec<StorageKey>,
+	) -> FutureResult<ReadProof<Block::Hash>> {
+		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
+	}
+
 	fn metadata(&self, block: Option<Block::Hash>) -> FutureResult<Bytes> {
 		let metadata = self.call(block, "Metadata_metadata".into(), Bytes(Vec::new()))
 			.and_then(|metadata| OpaqueMetadata::decode(&mut &metadata.0[..])
@@ -123,6 +123,36 @@ fn should_return_child_storage() {
 	);
 }
 
+#[test]
+fn should_return_read_proof() {
+	use sp_runtime::{generic::BlockId, traits::{BlakeTwo256, Header as _}};
+
+	const KEY: &[u8] = b":mock";
+	const VALUE: &[u8] = b"hello world";
+
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(TestClientBuilder::new()
+		.add_extra_storage(KEY.to_vec(), VALUE.to_vec())
+		.build());
+	let genesis_hash = client.genesis_hash();
+	let state_root = *client.header(&BlockId::Hash(genesis_hash)).unwrap().unwrap().state_root();
+	let client = new_full(client, Subscriptions::new(Arc::new(core.executor())));
+
+	let read_proof = client.read_proof(vec![StorageKey(KEY.to_vec())], None).wait().unwrap();
+	assert_eq!(read_proof.at, genesis_hash);
+
+	// the proof must be enough to check the value against the state root.
+	let proof = sp_state_machine::StorageProof::new(
+		read_proof.proof.into_iter().map(|node| node.0).collect(),
+	);
+	let values = sp_state_machine::read_proof_check::<BlakeTwo256, _>(
+		state_root,
+		proof,
+		&[KEY],
+	).unwrap();
+	assert_eq!(values.get(KEY), Some(&Some(VALUE.to_vec())));
+}
+
 #[test]
 fn should_call_contract() {
 	let core = tokio::runtime::Runtime::new().unwrap();
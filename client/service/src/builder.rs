@@ -17,7 +17,7 @@
 use crate::{Service, NetworkStatus, NetworkState, error::Error, DEFAULT_PROTOCOL_ID};
 use crate::{SpawnTaskHandle, start_rpc_servers, build_network_future, TransactionPoolAdapter};
 use crate::status_sinks;
-use crate::config::{Configuration, DatabaseConfig, KeystoreConfig};
+use crate::config::{Configuration, DatabaseConfig, KeystoreConfig, PrometheusConfig};
 use sc_client_api::{
 	self,
 	BlockchainEvents,
@@ -38,6 +38,7 @@ use sc_network::{FinalityProofProvider, OnDemand, NetworkService, NetworkStateIn
 use sc_network::{config::BoxFinalityProofRequestBuilder, specialization::NetworkSpecialization};
 use parking_lot::{Mutex, RwLock};
 use sp_runtime::generic::BlockId;
+use sp_core::storage::Storage;
 use sp_runtime::traits::{
 	Block as BlockT, NumberFor, SaturatedConversion, HasherFor,
 };
@@ -52,6 +53,68 @@ use sc_telemetry::{telemetry, SUBSTRATE_INFO};
 use sp_transaction_pool::{TransactionPool, TransactionPoolMaintainer};
 use sp_blockchain;
 use grafana_data_source::{self, record_metrics};
+use prometheus_endpoint::{
+	register, Gauge, GaugeVec, IntGauge, IntGaugeVec, Opts, PrometheusError, Registry,
+};
+
+/// Metrics of the service, updated by the periodic telemetry task.
+struct ServiceMetrics {
+	block_height_number: IntGaugeVec,
+	ready_transactions_number: IntGauge,
+	future_transactions_number: IntGauge,
+	peers_count: IntGauge,
+	network_per_sec_bytes: GaugeVec,
+	cpu_usage_percentage: Gauge,
+	memory_usage_bytes: IntGauge,
+	state_cache_bytes: IntGauge,
+	database_cache_bytes: IntGauge,
+	state_reads: IntGauge,
+	state_cache_reads: IntGauge,
+}
+
+impl ServiceMetrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			block_height_number: register(IntGaugeVec::new(
+				Opts::new("block_height_number", "Height of the chain"),
+				&["status"]
+			)?, registry)?,
+			ready_transactions_number: register(IntGauge::new(
+				"ready_transactions_number", "Number of transactions in the ready queue",
+			)?, registry)?,
+			future_transactions_number: register(IntGauge::new(
+				"future_transactions_number", "Number of transactions in the future queue",
+			)?, registry)?,
+			peers_count: register(IntGauge::new(
+				"peers_count", "Number of network peers connected",
+			)?, registry)?,
+			network_per_sec_bytes: register(GaugeVec::new(
+				Opts::new("network_per_sec_bytes", "Average network bandwidth in bytes per second"),
+				&["direction"]
+			)?, registry)?,
+			cpu_usage_percentage: register(Gauge::new(
+				"cpu_usage_percentage", "CPU usage of the node process in percent",
+			)?, registry)?,
+			memory_usage_bytes: register(IntGauge::new(
+				"memory_usage_bytes", "Memory usage of the node process in bytes",
+			)?, registry)?,
+			state_cache_bytes: register(IntGauge::new(
+				"state_cache_bytes", "Memory used by the state cache in bytes",
+			)?, registry)?,
+			database_cache_bytes: register(IntGauge::new(
+				"database_cache_bytes", "Memory used by the database cache in bytes",
+			)?, registry)?,
+			state_reads: register(IntGauge::new(
+				"state_reads", "Number of state reads, including those served from the cache, \
+					in the last usage info period",
+			)?, registry)?,
+			state_cache_reads: register(IntGauge::new(
+				"state_cache_reads", "Number of state reads served from the state cache \
+					in the last usage info period",
+			)?, registry)?,
+		})
+	}
+}
 
 /// Aggregator for the components required to build a service.
 ///
@@ -74,7 +137,7 @@ use grafana_data_source::{self, record_metrics};
 pub struct ServiceBuilder<TBl, TRtApi, TCfg, TGen, TCSExt, TCl, TFchr, TSc, TImpQu, TFprb, TFpp,
 	TNetP, TExPool, TRpc, Backend>
 {
-	config: Configuration<TCfg, TGen, TCSExt>,
+	pub (crate) config: Configuration<TCfg, TGen, TCSExt>,
 	pub (crate) client: Arc<TCl>,
 	backend: Arc<Backend>,
 	keystore: Arc<RwLock<Keystore>>,
@@ -174,6 +237,7 @@ fn new_full_parts<TBl, TRtApi, TExecDisp, TCfg, TGen, TCSExt>(
 	let executor = NativeExecutor::<TExecDisp>::new(
 		config.wasm_method,
 		config.default_heap_pages,
+		config.max_runtime_instances,
 	);
 
 	let fork_blocks = config.chain_spec
@@ -217,6 +281,7 @@ fn new_full_parts<TBl, TRtApi, TExecDisp, TCfg, TGen, TCSExt>(
 			fork_blocks,
 			bad_blocks,
 			extensions,
+			config.prometheus_registry().cloned(),
 		)?
 	};
 
@@ -299,6 +364,7 @@ where TGen: RuntimeGenesis, TCSExt: Extension {
 		let executor = NativeExecutor::<TExecDisp>::new(
 			config.wasm_method,
 			config.default_heap_pages,
+			config.max_runtime_instances,
 		);
 
 		let db_storage = {
@@ -702,6 +768,25 @@ pub trait ServiceBuilderCommand {
 		self,
 		block: BlockId<Self::Block>
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
+
+	/// Export the raw state at the given `block`. If `block` is `None`, the
+	/// best block will be used.
+	fn export_raw_state(
+		&self,
+		block: Option<BlockId<Self::Block>>,
+	) -> Result<Storage, Error>;
+
+	/// Dry-run an upgrade to the runtime `code` on top of the state of `block`.
+	///
+	/// The upgrade is enacted by initializing the child of `block` with the new
+	/// runtime, after which up to `blocks` descendants are re-executed on top of it.
+	/// The result is only reported, nothing is written to the database.
+	fn dry_run_upgrade(
+		&self,
+		code: Vec<u8>,
+		block: BlockId<Self::Block>,
+		blocks: u32,
+	) -> Result<(), Error>;
 }
 
 impl<TBl, TRtApi, TCfg, TGen, TCSExt, TBackend, TExec, TSc, TImpQu, TNetP, TExPool, TRpc>
@@ -801,6 +886,19 @@ ServiceBuilder<
 		let import_queue = Box::new(import_queue);
 		let chain_info = client.chain_info();
 
+		// Prometheus endpoint and metrics.
+		let metrics = if let Some(PrometheusConfig { port, registry }) = config.prometheus_config.clone() {
+			let metrics = ServiceMetrics::register(&registry)?;
+			let future = prometheus_endpoint::init_prometheus(port, registry)
+				.map(|result| if let Err(err) = result {
+					error!("Prometheus metrics endpoint failed: {}", err);
+				});
+			let _ = to_spawn_tx.unbounded_send(Box::pin(select(future.boxed(), exit.clone()).map(drop)));
+			Some(metrics)
+		} else {
+			None
+		};
+
 		let version = config.full_version();
 		info!("Highest known block at #{}", chain_info.best_number);
 		telemetry!(
@@ -868,6 +966,13 @@ ServiceBuilder<
 			_ => None,
 		};
 
+		{
+			// maintain the pool at the best block right away, so that the transactions journaled
+			// before restart are restored without waiting for the next block
+			let future = transaction_pool.maintain(&BlockId::hash(chain_info.best_hash), &[]);
+			let _ = to_spawn_tx.unbounded_send(Box::pin(future));
+		}
+
 		{
 			// block notifications
 			let txpool = Arc::downgrade(&transaction_pool);
@@ -903,6 +1008,23 @@ ServiceBuilder<
 			let _ = to_spawn_tx.unbounded_send(Box::pin(select(events, exit.clone()).map(drop)));
 		}
 
+		{
+			// finality notifications
+			let txpool = Arc::downgrade(&transaction_pool);
+			let to_spawn_tx_ = to_spawn_tx.clone();
+
+			let events = client.finality_notification_stream()
+				.for_each(move |notification| {
+					if let Some(txpool) = txpool.upgrade() {
+						let future = txpool.on_block_finalized(&BlockId::hash(notification.hash));
+						let _ = to_spawn_tx_.unbounded_send(Box::pin(future));
+					}
+
+					ready(())
+				});
+			let _ = to_spawn_tx.unbounded_send(Box::pin(select(events, exit.clone()).map(drop)));
+		}
+
 		{
 			// extrinsic notifications
 			let network = Arc::downgrade(&network);
@@ -982,6 +1104,26 @@ ServiceBuilder<
 				"disk_write_per_sec" => info.usage.as_ref().map(|usage| usage.io.bytes_written).unwrap_or(0),
 			);
 
+			if let Some(metrics) = metrics.as_ref() {
+				metrics.block_height_number.with_label_values(&["best"]).set(best_number as i64);
+				metrics.block_height_number.with_label_values(&["finalized"]).set(finalized_number as i64);
+				metrics.ready_transactions_number.set(txpool_status.ready as i64);
+				metrics.future_transactions_number.set(txpool_status.future as i64);
+				metrics.peers_count.set(num_peers as i64);
+				metrics.network_per_sec_bytes.with_label_values(&["download"]).set(bandwidth_download as f64);
+				metrics.network_per_sec_bytes.with_label_values(&["upload"]).set(bandwidth_upload as f64);
+				metrics.cpu_usage_percentage.set(cpu_usage as f64);
+				metrics.memory_usage_bytes.set(memory as i64 * 1024);
+				if let Some(usage) = info.usage.as_ref() {
+					metrics.state_cache_bytes.set(usage.memory.state_cache as i64);
+					metrics.database_cache_bytes.set(usage.memory.database_cache as i64);
+					// The usage info only holds the reads of the last period, which other consumers
+					// of the usage info may have taken as well, so they can't be summed up.
+					metrics.state_reads.set(usage.io.state_reads as i64);
+					metrics.state_cache_reads.set(usage.io.state_reads_cache as i64);
+				}
+			}
+
 			ready(())
 		});
 		let _ = to_spawn_tx.unbounded_send(Box::pin(select(tel_task, exit.clone()).map(drop)));
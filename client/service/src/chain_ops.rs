@@ -23,18 +23,33 @@ use sc_chain_spec::{ChainSpec, RuntimeGenesis, Extension};
 use log::{warn, info};
 use futures::{future, prelude::*};
 use sp_runtime::traits::{
-	Block as BlockT, NumberFor, One, Zero, Header, SaturatedConversion
+	Block as BlockT, NumberFor, One, Zero, Header, SaturatedConversion, HasherFor
 };
+use sp_runtime::ApplyExtrinsicResult;
 use sp_runtime::generic::{BlockId, SignedBlock};
 use codec::{Decode, Encode, IoReader};
 use sc_client::Client;
+use sc_client_api::StateBackend;
+use sc_executor::{
+	WasmExecutionMethod, WasmRuntime, create_wasm_runtime_with_code, with_externalities_safe,
+	sp_wasm_interface::HostFunctions,
+};
+use sp_state_machine::{Ext, OverlayedChanges, StorageTransactionCache};
 use sp_consensus::import_queue::{IncomingBlock, Link, BlockImportError, BlockImportResult, ImportQueue};
 use sp_consensus::BlockOrigin;
 
-use std::{io::{Read, Write, Seek}, pin::Pin};
+use sp_core::storage::{well_known_keys, ChildInfo, Storage, StorageChild, StorageMap};
+use sp_core::{hexdisplay::HexDisplay, traits::Externalities};
+
+use std::{io::{Read, Write, Seek}, pin::Pin, collections::{HashMap, BTreeSet}};
+use std::panic::AssertUnwindSafe;
 
 use sc_network::message;
 
+/// Number of 64KB pages to allocate for Wasm execution if neither the configuration
+/// nor the state specify it.
+const DEFAULT_HEAP_PAGES: u64 = 1024;
+
 /// Build a chain spec json
 pub fn build_spec<G, E>(spec: ChainSpec<G, E>, raw: bool) -> error::Result<String> where
 	G: RuntimeGenesis,
@@ -303,4 +318,369 @@ impl<
 			Err(e) => Box::pin(future::err(format!("Error reading block: {:?}", e).into())),
 		}
 	}
+
+	fn export_raw_state(
+		&self,
+		block: Option<BlockId<TBl>>,
+	) -> Result<Storage, Error> {
+		let block = block.unwrap_or_else(
+			|| BlockId::Hash(self.client.chain_info().best_hash)
+		);
+
+		let state = self.client.state_at(&block)?;
+		let default_child_prefix = [well_known_keys::CHILD_STORAGE_KEY_PREFIX, b"default:"].concat();
+		let mut top = StorageMap::new();
+		let mut children = HashMap::new();
+
+		// Walk the keys one by one instead of collecting all of them first, the state can be
+		// too large to hold its keys in memory.
+		let mut key = Vec::new();
+		while let Some(next) = state.next_storage_key(&key).map_err(state_error)? {
+			key = next;
+			let value = match state.storage(&key).map_err(state_error)? {
+				Some(value) => value,
+				None => continue,
+			};
+
+			if !well_known_keys::is_child_storage_key(&key) {
+				top.insert(key.clone(), value);
+				continue;
+			}
+
+			// The top trie only holds the root of a child trie. Default child tries
+			// use the remainder of their storage key as unique id, so their content
+			// can be collected and the root is recomputed when the spec is loaded.
+			if !key.starts_with(&default_child_prefix) {
+				return Err(format!(
+					"Unsupported child trie at {}", HexDisplay::from(&key),
+				).into());
+			}
+
+			let child_info = ChildInfo::new_default(&key[default_child_prefix.len()..]);
+			let mut data = StorageMap::new();
+			let mut child_key = Vec::new();
+			while let Some(next) = state.next_child_storage_key(&key, child_info, &child_key)
+				.map_err(state_error)?
+			{
+				child_key = next;
+				if let Some(value) = state.child_storage(&key, child_info, &child_key)
+					.map_err(state_error)?
+				{
+					data.insert(child_key.clone(), value);
+				}
+			}
+
+			let child_info = child_info.to_owned();
+			children.insert(key.clone(), StorageChild { data, child_info });
+		}
+
+		Ok(Storage { top, children })
+	}
+
+	fn dry_run_upgrade(
+		&self,
+		code: Vec<u8>,
+		block: BlockId<TBl>,
+		blocks: u32,
+	) -> Result<(), Error> {
+		let client = &self.client;
+		let base = client.header(&block)?.ok_or("Unknown block")?;
+		let base_hash = base.hash();
+
+		let mut descendants = Vec::new();
+		let mut parent_hash = base_hash;
+		for n in 1..=blocks {
+			let id = BlockId::Number(*base.number() + n.into());
+			let header = match client.header(&id)? {
+				Some(header) if *header.parent_hash() == parent_hash => header,
+				_ => break,
+			};
+			let body = client.body(&id)?
+				.ok_or_else(|| format!("Missing body of block #{}", header.number()))?;
+			parent_hash = header.hash();
+			descendants.push((header, body));
+		}
+
+		if descendants.is_empty() {
+			return Err(format!(
+				"Block #{} ({}) has no known descendant in the best chain to enact the upgrade in",
+				base.number(),
+				base_hash,
+			).into());
+		}
+
+		let state = client.state_at(&BlockId::Hash(base_hash))?;
+		let read = |overlay: &OverlayedChanges, key: &[u8]|
+			read_storage::<HasherFor<TBl>, _>(&state, overlay, key);
+
+		let current_code = read(&OverlayedChanges::default(), well_known_keys::CODE)?
+			.ok_or("`:code` not found in state")?;
+		let heap_pages = read(&OverlayedChanges::default(), well_known_keys::HEAP_PAGES)?
+			.and_then(|pages| u64::decode(&mut &pages[..]).ok())
+			.or(self.config.default_heap_pages)
+			.unwrap_or(DEFAULT_HEAP_PAGES);
+
+		let wasm_method = self.config.wasm_method;
+		let mut current_runtime = instantiate(wasm_method, heap_pages, &current_code)?;
+		let mut runtime = instantiate(wasm_method, heap_pages, &code)?;
+
+		let mut current_overlay = OverlayedChanges::default();
+		let mut overlay = OverlayedChanges::default();
+		{
+			let mut cache = StorageTransactionCache::<_, HasherFor<TBl>, NumberFor<TBl>>::default();
+			let mut ext = Ext::new(&mut overlay, &mut cache, &state, None, None);
+			ext.place_storage(well_known_keys::CODE.to_vec(), Some(code));
+		}
+		overlay.commit_prospective();
+
+		// Initialize the first descendant with both runtimes. Anything it does differently
+		// with the new runtime is attributed to the upgrade.
+		let (first, _) = &descendants[0];
+		call_runtime::<TBl, _>(
+			&mut *current_runtime,
+			&state,
+			&mut current_overlay,
+			"Core_initialize_block",
+			&first.encode(),
+		).map_err(|e| format!("Current runtime failed to initialize block #{}: {}", first.number(), e))?;
+		call_runtime::<TBl, _>(
+			&mut *runtime,
+			&state,
+			&mut overlay,
+			"Core_initialize_block",
+			&first.encode(),
+		).map_err(|e| format!("Upgrade failed while initializing block #{}: {}", first.number(), e))?;
+
+		let mut changed = BTreeSet::new();
+		changed.extend(changed_keys(&current_overlay));
+		changed.extend(changed_keys(&overlay));
+		changed.remove(well_known_keys::CODE);
+
+		let mut migrated = 0;
+		for key in changed {
+			let value = read(&overlay, &key)?;
+			if value == read(&current_overlay, &key)? {
+				continue;
+			}
+			migrated += 1;
+			match value {
+				Some(value) => info!("  {}: {} bytes", HexDisplay::from(&key), value.len()),
+				None => info!("  {}: removed", HexDisplay::from(&key)),
+			}
+		}
+
+		let mut changed_children = BTreeSet::new();
+		changed_children.extend(changed_child_keys(&current_overlay));
+		changed_children.extend(changed_child_keys(&overlay));
+
+		for (storage_key, key) in changed_children {
+			// Both overlays are on top of the same state, so the child trie is the same in both.
+			let child_info = overlay.child_info(&storage_key)
+				.or_else(|| current_overlay.child_info(&storage_key))
+				.expect("the key was changed in one of the overlays; qed")
+				.as_ref();
+			let read_child = |overlay: &OverlayedChanges|
+				read_child_storage::<HasherFor<TBl>, _>(&state, overlay, &storage_key, child_info, &key);
+
+			let value = read_child(&overlay)?;
+			if value == read_child(&current_overlay)? {
+				continue;
+			}
+			migrated += 1;
+			match value {
+				Some(value) => info!(
+					"  {} {}: {} bytes",
+					HexDisplay::from(&storage_key),
+					HexDisplay::from(&key),
+					value.len(),
+				),
+				None => info!("  {} {}: removed", HexDisplay::from(&storage_key), HexDisplay::from(&key)),
+			}
+		}
+		info!("Upgrade changed {} storage keys", migrated);
+
+		// The weight is queried through the `BlockWeightApi` of the system pallet, runtimes
+		// that don't implement it yet are reported as such.
+		let block_weight = |runtime: &mut dyn WasmRuntime, overlay: &mut OverlayedChanges| {
+			call_runtime::<TBl, _>(runtime, &state, overlay, "BlockWeightApi_block_weight", &[])
+				.ok()
+				.and_then(|weight| u64::decode(&mut &weight[..]).ok())
+				.map_or_else(|| "unknown".to_string(), |weight| weight.to_string())
+		};
+		info!(
+			"Weight of initializing block #{}: {} before the upgrade, {} with it",
+			first.number(),
+			block_weight(&mut *current_runtime, &mut current_overlay),
+			block_weight(&mut *runtime, &mut overlay),
+		);
+		drop(current_runtime);
+		drop(current_overlay);
+
+		for (index, (header, body)) in descendants.iter().enumerate() {
+			if index > 0 {
+				call_runtime::<TBl, _>(
+					&mut *runtime,
+					&state,
+					&mut overlay,
+					"Core_initialize_block",
+					&header.encode(),
+				).map_err(|e| format!("Failed to initialize block #{}: {}", header.number(), e))?;
+			}
+
+			let mut failed = 0;
+			for (xt_index, xt) in body.iter().enumerate() {
+				let result = call_runtime::<TBl, _>(
+					&mut *runtime,
+					&state,
+					&mut overlay,
+					"BlockBuilder_apply_extrinsic",
+					&xt.encode(),
+				).map_err(|e| format!(
+					"Failed to apply extrinsic #{} of block #{}: {}", xt_index, header.number(), e,
+				))?;
+
+				match ApplyExtrinsicResult::decode(&mut &result[..]) {
+					Ok(Ok(Ok(()))) => {},
+					Ok(Ok(Err(e))) => {
+						failed += 1;
+						warn!(
+							"Dispatch of extrinsic #{} of block #{} failed: {:?}",
+							xt_index,
+							header.number(),
+							e,
+						);
+					},
+					Ok(Err(e)) => return Err(format!(
+						"Extrinsic #{} of block #{} is invalid after the upgrade: {:?}",
+						xt_index,
+						header.number(),
+						e,
+					).into()),
+					Err(e) => return Err(format!(
+						"Failed to decode result of extrinsic #{} of block #{}: {:?}",
+						xt_index,
+						header.number(),
+						e,
+					).into()),
+				}
+			}
+
+			call_runtime::<TBl, _>(
+				&mut *runtime,
+				&state,
+				&mut overlay,
+				"BlockBuilder_finalize_block",
+				&[],
+			).map_err(|e| format!("Failed to finalize block #{}: {}", header.number(), e))?;
+
+			info!(
+				"Executed block #{} ({}) with {} extrinsics, {} failed to dispatch",
+				header.number(),
+				header.hash(),
+				body.len(),
+				failed,
+			);
+		}
+
+		info!(
+			"Upgrade on top of block #{} succeeded, {} blocks executed with the new runtime",
+			base.number(),
+			descendants.len(),
+		);
+		Ok(())
+	}
+}
+
+/// Instantiate the given wasm runtime `code` with the default host functions.
+fn instantiate(
+	wasm_method: WasmExecutionMethod,
+	heap_pages: u64,
+	code: &[u8],
+) -> Result<Box<dyn WasmRuntime>, Error> {
+	create_wasm_runtime_with_code(
+		wasm_method,
+		heap_pages,
+		code,
+		sp_io::SubstrateHostFunctions::host_functions(),
+		true,
+	).map_err(|e| format!("Failed to instantiate runtime: {}", e).into())
+}
+
+/// Call `method` of `runtime` on top of `state` and the changes in `overlay`.
+///
+/// The changes made by the call are committed to `overlay`.
+fn call_runtime<TBl: BlockT, S: StateBackend<HasherFor<TBl>>>(
+	runtime: &mut dyn WasmRuntime,
+	state: &S,
+	overlay: &mut OverlayedChanges,
+	method: &str,
+	data: &[u8],
+) -> Result<Vec<u8>, String> {
+	let result = {
+		let mut cache = StorageTransactionCache::<_, HasherFor<TBl>, NumberFor<TBl>>::default();
+		let mut ext = Ext::new(overlay, &mut cache, state, None, None);
+		let mut runtime = AssertUnwindSafe(runtime);
+		with_externalities_safe(&mut ext, move || runtime.call(method, data))
+			.and_then(|r| r)
+			.map_err(|e| e.to_string())?
+	};
+	overlay.commit_prospective();
+	Ok(result)
+}
+
+/// Turn an error of the state backend into an `Error`.
+fn state_error(e: impl std::fmt::Display) -> Error {
+	format!("Error reading state: {}", e).into()
+}
+
+/// Read the value of `key` from `overlay`, falling back to `state`.
+fn read_storage<H: sp_core::Hasher, S: StateBackend<H>>(
+	state: &S,
+	overlay: &OverlayedChanges,
+	key: &[u8],
+) -> Result<Option<Vec<u8>>, Error> {
+	match overlay.storage(key) {
+		Some(value) => Ok(value.map(|v| v.to_vec())),
+		None => state.storage(key).map_err(state_error),
+	}
+}
+
+/// Read the value of `key` in the child trie at `storage_key` from `overlay`, falling back to
+/// `state`.
+fn read_child_storage<H: sp_core::Hasher, S: StateBackend<H>>(
+	state: &S,
+	overlay: &OverlayedChanges,
+	storage_key: &[u8],
+	child_info: ChildInfo,
+	key: &[u8],
+) -> Result<Option<Vec<u8>>, Error> {
+	match overlay.child_storage(storage_key, key) {
+		Some(value) => Ok(value.map(|v| v.to_vec())),
+		None => state.child_storage(storage_key, child_info, key).map_err(state_error),
+	}
+}
+
+/// Collect the keys of all top trie changes in `overlay`.
+fn changed_keys(overlay: &OverlayedChanges) -> Vec<Vec<u8>> {
+	let mut keys = Vec::new();
+	let mut key = Vec::new();
+	while let Some((next, _)) = overlay.next_storage_key_change(&key) {
+		key = next.to_vec();
+		keys.push(key.clone());
+	}
+	keys
+}
+
+/// Collect the keys of all child trie changes in `overlay`, along with the storage key of the
+/// child trie they belong to.
+fn changed_child_keys(overlay: &OverlayedChanges) -> Vec<(Vec<u8>, Vec<u8>)> {
+	let mut keys = Vec::new();
+	for storage_key in overlay.child_storage_keys() {
+		let mut key = Vec::new();
+		while let Some((next, _)) = overlay.next_child_storage_key_change(storage_key, &key) {
+			key = next.to_vec();
+			keys.push((storage_key.to_vec(), key.clone()));
+		}
+	}
+	keys
 }
@@ -23,10 +23,12 @@ pub use sc_executor::WasmExecutionMethod;
 
 use std::{path::{PathBuf, Path}, net::SocketAddr, sync::Arc};
 pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
+pub use sc_transaction_pool::txpool::base_pool::SenderLimit as TransactionPoolSenderLimit;
 use sc_chain_spec::{ChainSpec, RuntimeGenesis, Extension, NoExtension};
 use sp_core::crypto::Protected;
 use target_info::Target;
 use sc_telemetry::TelemetryEndpoints;
+use prometheus_endpoint::Registry;
 
 /// Service configuration.
 #[derive(Clone)]
@@ -75,6 +77,8 @@ pub struct Configuration<C, G, E = NoExtension> {
 	pub rpc_cors: Option<Vec<String>>,
 	/// Grafana data source http port. `None` if disabled.
 	pub grafana_port: Option<SocketAddr>,
+	/// Prometheus endpoint configuration. `None` if disabled.
+	pub prometheus_config: Option<PrometheusConfig>,
 	/// Telemetry service URL. `None` if disabled.
 	pub telemetry_endpoints: Option<TelemetryEndpoints>,
 	/// External WASM transport for the telemetry. If `Some`, when connection to a telemetry
@@ -82,6 +86,8 @@ pub struct Configuration<C, G, E = NoExtension> {
 	pub telemetry_external_transport: Option<ExtTransport>,
 	/// The default number of 64KB pages to allocate for Wasm execution
 	pub default_heap_pages: Option<u64>,
+	/// The maximal number of idle Wasm runtime instances kept for reuse.
+	pub max_runtime_instances: usize,
 	/// Should offchain workers be executed.
 	pub offchain_worker: bool,
 	/// Sentry mode is enabled, the node's role is AUTHORITY but it should not
@@ -130,6 +136,28 @@ impl KeystoreConfig {
 	}
 }
 
+/// Configuration of the Prometheus endpoint.
+#[derive(Clone)]
+pub struct PrometheusConfig {
+	/// Address to serve the metrics on.
+	pub port: SocketAddr,
+	/// The registry all metrics of the node are registered with.
+	pub registry: Registry,
+}
+
+impl PrometheusConfig {
+	/// Create a new config using the default registry.
+	///
+	/// The default registry prefixes all metrics with `substrate`.
+	pub fn new_with_default_registry(port: SocketAddr) -> Self {
+		Self {
+			port,
+			registry: Registry::new_custom(Some("substrate".into()), None)
+				.expect("this can only fail if the prefix is empty"),
+		}
+	}
+}
+
 /// Configuration of the database of the client.
 #[derive(Clone)]
 pub enum DatabaseConfig {
@@ -178,9 +206,11 @@ impl<C, G, E> Configuration<C, G, E> where
 			rpc_ws_max_connections: None,
 			rpc_cors: Some(vec![]),
 			grafana_port: None,
+			prometheus_config: None,
 			telemetry_endpoints: None,
 			telemetry_external_transport: None,
 			default_heap_pages: None,
+			max_runtime_instances: 8,
 			offchain_worker: Default::default(),
 			sentry_mode: false,
 			force_authoring: false,
@@ -209,6 +239,11 @@ impl<C, G, E> Configuration<C, G, E> {
 		format!("{}/v{}", self.impl_name, self.full_version())
 	}
 
+	/// Returns the Prometheus metrics registry, if Prometheus is enabled.
+	pub fn prometheus_registry(&self) -> Option<&Registry> {
+		self.prometheus_config.as_ref().map(|config| &config.registry)
+	}
+
 	/// Generate a PathBuf to sub in the chain configuration directory
 	/// if given
 	pub fn in_chain_config_dir(&self, sub: &str) -> Option<PathBuf> {
@@ -165,6 +165,7 @@ fn node_config<G, E: Clone> (
 			wasm_external_transport: None,
 		},
 		max_parallel_downloads: NetworkConfiguration::default().max_parallel_downloads,
+		sync_mode: NetworkConfiguration::default().sync_mode,
 	};
 
 	Configuration {
@@ -196,9 +197,11 @@ fn node_config<G, E: Clone> (
 		rpc_ws_max_connections: None,
 		rpc_cors: None,
 		grafana_port: None,
+		prometheus_config: None,
 		telemetry_endpoints: None,
 		telemetry_external_transport: None,
 		default_heap_pages: None,
+		max_runtime_instances: 8,
 		offchain_worker: false,
 		sentry_mode: false,
 		force_authoring: false,
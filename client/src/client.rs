@@ -30,6 +30,7 @@ use sp_core::{
 	NativeOrEncoded, storage::{StorageKey, StorageData, well_known_keys, ChildInfo},
 };
 use sc_telemetry::{telemetry, SUBSTRATE_INFO};
+use prometheus_endpoint::{register, Histogram, HistogramOpts, PrometheusError, Registry};
 use sp_runtime::{
 	Justification, BuildStorage,
 	generic::{BlockId, SignedBlock, DigestItem},
@@ -40,7 +41,7 @@ use sp_runtime::{
 };
 use sp_state_machine::{
 	DBValue, Backend as StateBackend, ChangesTrieAnchorBlockId,
-	prove_read, prove_child_read, ChangesTrieRootsStorage, ChangesTrieStorage,
+	prove_read, prove_child_read, prove_range_read_with_size, ChangesTrieRootsStorage, ChangesTrieStorage,
 	ChangesTrieConfigurationRange, key_changes, key_changes_proof, StorageProof,
 	merge_storage_proofs,
 };
@@ -97,9 +98,25 @@ pub struct Client<B, E, Block, RA> where Block: BlockT {
 	fork_blocks: ForkBlocks<Block>,
 	bad_blocks: BadBlocks<Block>,
 	execution_extensions: ExecutionExtensions<Block>,
+	metrics: Option<ClientMetrics>,
 	_phantom: PhantomData<RA>,
 }
 
+/// Prometheus metrics of the client.
+struct ClientMetrics {
+	block_import_time: Histogram,
+}
+
+impl ClientMetrics {
+	fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			block_import_time: register(Histogram::with_opts(
+				HistogramOpts::new("block_import_time", "Time taken to import a block in seconds"),
+			)?, registry)?,
+		})
+	}
+}
+
 // used in importing a block, where additional changes are made after the runtime
 // executed.
 enum PrePostHeader<H> {
@@ -168,6 +185,7 @@ pub fn new_with_backend<B, E, Block, S, RA>(
 		Default::default(),
 		Default::default(),
 		extensions,
+		None,
 	)
 }
 
@@ -192,6 +210,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		fork_blocks: ForkBlocks<Block>,
 		bad_blocks: BadBlocks<Block>,
 		execution_extensions: ExecutionExtensions<Block>,
+		prometheus_registry: Option<Registry>,
 	) -> sp_blockchain::Result<Self> {
 		if backend.blockchain().header(BlockId::Number(Zero::zero()))?.is_none() {
 			let genesis_storage = build_genesis_storage.build_storage()?;
@@ -212,6 +231,12 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			backend.commit_operation(op)?;
 		}
 
+		let metrics = match prometheus_registry {
+			Some(registry) => Some(ClientMetrics::register(&registry)
+				.map_err(|e| Error::Msg(format!("Failed to register client metrics: {}", e)))?),
+			None => None,
+		};
+
 		Ok(Client {
 			backend,
 			executor,
@@ -222,6 +247,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			fork_blocks,
 			bad_blocks,
 			execution_extensions,
+			metrics,
 			_phantom: Default::default(),
 		})
 	}
@@ -346,6 +372,21 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 				.map_err(Into::into))
 	}
 
+	/// Reads the storage entries starting at `start` (inclusive) at a given block, until
+	/// at least `max_size` bytes have been read, returning the number of entries read and
+	/// a read proof.
+	pub fn read_state_range_proof(
+		&self,
+		id: &BlockId<Block>,
+		start: &[u8],
+		max_size: usize,
+	) -> sp_blockchain::Result<(usize, StorageProof)> {
+		self.state_at(id)
+			.and_then(|state| prove_range_read_with_size(state, start, max_size)
+				.map(|(entries, proof, _)| (entries.len(), proof))
+				.map_err(Into::into))
+	}
+
 	/// Execute a call to a contract on top of state in a block of given hash
 	/// AND returning execution proof.
 	///
@@ -796,6 +837,81 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		result
 	}
 
+	/// Store a chunk of the state of a block that is being downloaded, until the whole state is
+	/// imported with [`Client::import_state`]. Chunks are numbered from zero.
+	pub fn store_state_chunk(
+		&self,
+		hash: Block::Hash,
+		index: u32,
+		entries: Vec<(Vec<u8>, Vec<u8>)>,
+	) -> sp_blockchain::Result<()> {
+		let key = state_chunk_key(&hash, index);
+		let value = entries.encode();
+		backend::AuxStore::insert_aux(&*self.backend, &[(&key[..], &value[..])], &[])
+	}
+
+	/// Remove the first `chunks` stored chunks of the state of a block.
+	pub fn discard_state(&self, hash: Block::Hash, chunks: u32) -> sp_blockchain::Result<()> {
+		let keys = (0..chunks).map(|index| state_chunk_key(&hash, index)).collect::<Vec<_>>();
+		let keys = keys.iter().map(|key| &key[..]).collect::<Vec<_>>();
+		backend::AuxStore::insert_aux(&*self.backend, &[], &keys)
+	}
+
+	/// Import the full state of a finalized block that has been imported without state,
+	/// from the first `chunks` chunks stored with [`Client::store_state_chunk`]. The chunks
+	/// are read one at a time and removed afterwards, whether the import succeeds or not.
+	/// The state is checked against the state root of the block header.
+	pub fn import_state(&self, hash: Block::Hash, chunks: u32) -> sp_blockchain::Result<()> {
+		let result = self.import_state_chunks(hash, chunks);
+		if let Err(e) = self.discard_state(hash, chunks) {
+			warn!("Failed to remove the stored state of {}: {:?}", hash, e);
+		}
+		result
+	}
+
+	fn import_state_chunks(&self, hash: Block::Hash, chunks: u32) -> sp_blockchain::Result<()> {
+		let header = self.backend.blockchain().expect_header(BlockId::Hash(hash))?;
+		let info = self.backend.blockchain().info();
+		if *header.number() > info.finalized_number ||
+			self.backend.blockchain().hash(*header.number())? != Some(hash)
+		{
+			return Err(sp_blockchain::Error::NotInFinalizedChain);
+		}
+
+		self.lock_import_and_run(|operation| {
+			let mut failure = None;
+			let root = {
+				let backend = &self.backend;
+				let mut top = (0..chunks).flat_map(|index| {
+					let entries = backend::AuxStore::get_aux(&**backend, &state_chunk_key(&hash, index))
+						.and_then(|chunk| chunk.ok_or_else(|| sp_blockchain::Error::Backend(
+							format!("Missing chunk {} of the state of {}", index, hash)
+						)))
+						.and_then(|chunk| Vec::<(Vec<u8>, Vec<u8>)>::decode(&mut &chunk[..])
+							.map_err(|e| sp_blockchain::Error::Backend(
+								format!("Invalid chunk {} of the state of {}: {:?}", index, hash, e)
+							))
+						);
+					match entries {
+						Ok(entries) => entries,
+						Err(e) => {
+							failure = Some(e);
+							Vec::new()
+						},
+					}
+				});
+				operation.op.import_state(hash, &mut top)?
+			};
+			if let Some(e) = failure {
+				return Err(e);
+			}
+			if root != *header.state_root() {
+				return Err(sp_blockchain::Error::InvalidStateRoot);
+			}
+			Ok(())
+		})
+	}
+
 	/// Apply a checked and validated block to an operation. If a justification is provided
 	/// then `finalized` *must* be true.
 	fn apply_block(
@@ -899,9 +1015,13 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		let info = self.backend.blockchain().info();
 
 		// the block is lower than our last finalized block so it must revert
-		// finality, refusing import.
+		// finality, refusing import. Blocks of the finalized chain that are imported
+		// again, e.g. to add their state, are fine.
 		if *import_headers.post().number() <= info.finalized_number {
-			return Err(sp_blockchain::Error::NotInFinalizedChain);
+			let canonical = self.backend.blockchain().hash(*import_headers.post().number())?;
+			if !import_existing || canonical != Some(hash) {
+				return Err(sp_blockchain::Error::NotInFinalizedChain);
+			}
 		}
 
 		// this is a fairly arbitrary choice of where to draw the line on making notifications,
@@ -930,10 +1050,11 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 
 				operation.op.update_cache(new_cache);
 
-				let (main_sc, child_sc, tx, _, changes_trie_tx) = storage_changes.into_inner();
+				let (main_sc, child_sc, offchain_sc, tx, _, changes_trie_tx) = storage_changes.into_inner();
 
 				operation.op.update_db_storage(tx)?;
 				operation.op.update_storage(main_sc.clone(), child_sc.clone())?;
+				operation.op.update_offchain_storage(offchain_sc)?;
 
 				if let Some(changes_trie_transaction) = changes_trie_tx {
 					operation.op.update_changes_trie(changes_trie_transaction)?;
@@ -944,10 +1065,11 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			None => None,
 		};
 
-		let is_new_best = finalized || match fork_choice {
+		// blocks that are imported again are already part of the chain.
+		let is_new_best = !import_existing && (finalized || match fork_choice {
 			ForkChoiceStrategy::LongestChain => import_headers.post().number() > &info.best_number,
 			ForkChoiceStrategy::Custom(v) => v,
-		};
+		});
 
 		let leaf_state = if finalized {
 			NewBlockState::Final
@@ -1527,6 +1649,8 @@ impl<B, E, Block, RA> sp_consensus::BlockImport<Block> for &Client<B, E, Block,
 			return Ok(res)
 		}
 
+		let _timer = self.metrics.as_ref().map(|metrics| metrics.block_import_time.start_timer());
+
 		self.lock_import_and_run(|operation| {
 			self.apply_block(operation, import_block, new_cache)
 		}).map_err(|e| {
@@ -1579,7 +1703,8 @@ impl<B, E, Block, RA> sp_consensus::BlockImport<Block> for &Client<B, E, Block,
 		{
 			BlockStatus::InChainWithState | BlockStatus::Queued if !import_existing  => return Ok(ImportResult::AlreadyInChain),
 			BlockStatus::InChainWithState | BlockStatus::Queued => {},
-			BlockStatus::InChainPruned => return Ok(ImportResult::AlreadyInChain),
+			BlockStatus::InChainPruned if !import_existing => return Ok(ImportResult::AlreadyInChain),
+			BlockStatus::InChainPruned => {},
 			BlockStatus::Unknown => {},
 			BlockStatus::KnownBad => return Ok(ImportResult::KnownBad),
 		}
@@ -1856,6 +1981,10 @@ impl<B, E, Block, RA> backend::AuxStore for &Client<B, E, Block, RA>
 	}
 }
 
+/// Key under which a chunk of a downloaded state is stored in the auxiliary database.
+fn state_chunk_key<Hash: Encode>(hash: &Hash, index: u32) -> Vec<u8> {
+	(b"state_chunk", hash, index).encode()
+}
 
 /// Helper function to apply auxiliary data insertion into an operation.
 pub fn apply_aux<'a, 'b: 'a, 'c: 'a, B, Block, D, I>(
@@ -84,6 +84,7 @@ pub fn new_light<B, S, GS, RA, E>(
 		Default::default(),
 		Default::default(),
 		Default::default(),
+		None,
 	)
 }
 
@@ -280,6 +280,23 @@ impl<BlockHash: Hash, Key: Hash> StateDbSync<BlockHash, Key> {
 		Ok(commit)
 	}
 
+	pub fn import_canonical_state<E: fmt::Debug>(&mut self, hash: &BlockHash, number: u64, mut changeset: ChangeSet<Key>) -> Result<CommitSet<Key>, Error<E>> {
+		changeset.deleted.clear();
+		let mut commit = CommitSet {
+			data: changeset,
+			meta: ChangeSet::default(),
+		};
+		if self.mode == PruningMode::ArchiveAll {
+			return Ok(commit)
+		}
+		self.non_canonical.set_last_canonicalized(hash, number, &mut commit)?;
+		if let Some(ref mut pruning) = self.pruning {
+			pruning.note_canonical(&hash, &mut commit);
+		}
+		self.prune(&mut commit);
+		Ok(commit)
+	}
+
 	pub fn best_canonical(&self) -> Option<u64> {
 		return self.non_canonical.last_canonicalized_block_number()
 	}
@@ -423,6 +440,13 @@ impl<BlockHash: Hash, Key: Hash> StateDb<BlockHash, Key> {
 		self.db.write().canonicalize_block(hash)
 	}
 
+	/// Make the full state of a block that was imported without state the last canonical state.
+	/// This is only possible while there are no non-canonical blocks, e.g. after the state of a
+	/// finalized block has been downloaded from the network.
+	pub fn import_canonical_state<E: fmt::Debug>(&self, hash: &BlockHash, number: u64, changeset: ChangeSet<Key>) -> Result<CommitSet<Key>, Error<E>> {
+		self.db.write().import_canonical_state(hash, number, changeset)
+	}
+
 	/// Prevents pruning of specified block and its descendants.
 	pub fn pin(&self, hash: &BlockHash) -> Result<(), PinError> {
 		self.db.write().pin(hash)
@@ -589,6 +613,45 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
 
+	#[test]
+	fn imported_canonical_state_is_not_pruned() {
+		let (mut db, sdb) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: Some(2),
+			max_mem: None,
+		}));
+		// there are non-canonical blocks left.
+		assert!(sdb.import_canonical_state::<io::Error>(
+			&H256::from_low_u64_be(10),
+			10,
+			make_changeset(&[10], &[]),
+		).is_err());
+
+		db.commit(&sdb.canonicalize_block::<io::Error>(&H256::from_low_u64_be(4)).unwrap());
+		sdb.apply_pending();
+		db.commit(&sdb.import_canonical_state::<io::Error>(
+			&H256::from_low_u64_be(10),
+			10,
+			make_changeset(&[10], &[]),
+		).unwrap());
+		sdb.apply_pending();
+		assert_eq!(sdb.best_canonical(), Some(10));
+		assert!(!sdb.is_pruned(&H256::from_low_u64_be(10), 10));
+
+		db.commit(
+			&sdb.insert_block::<io::Error>(
+				&H256::from_low_u64_be(11),
+				11,
+				&H256::from_low_u64_be(10),
+				make_changeset(&[11], &[]),
+			)
+			.unwrap(),
+		);
+		sdb.apply_pending();
+		db.commit(&sdb.canonicalize_block::<io::Error>(&H256::from_low_u64_be(11)).unwrap());
+		sdb.apply_pending();
+		assert!(db.data_eq(&make_db(&[3, 4, 10, 11, 21, 922])));
+	}
+
 	#[test]
 	fn detects_incompatible_mode() {
 		let mut db = make_db(&[]);
@@ -262,6 +262,26 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 		}
 	}
 
+	/// Mark a block that has never been inserted into the overlay as the last canonicalized one.
+	/// Fails if there are any non-canonical blocks left.
+	pub fn set_last_canonicalized<E: fmt::Debug>(
+		&mut self,
+		hash: &BlockHash,
+		number: u64,
+		commit: &mut CommitSet<Key>,
+	) -> Result<(), Error<E>> {
+		if !self.levels.is_empty() || !self.pending_canonicalizations.is_empty() {
+			return Err(Error::InvalidBlockNumber);
+		}
+		if self.last_canonicalized.as_ref().map_or(false, |&(_, n)| number <= n) {
+			return Err(Error::InvalidBlockNumber);
+		}
+		let last_canonicalized = (hash.clone(), number);
+		commit.meta.inserted.push((to_meta_key(LAST_CANONICAL, &()), last_canonicalized.encode()));
+		self.last_canonicalized = Some(last_canonicalized);
+		Ok(())
+	}
+
 	fn front_block_number(&self) -> u64 {
 		self.last_canonicalized.as_ref().map(|&(_, n)| n + 1).unwrap_or(0)
 	}
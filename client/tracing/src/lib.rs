@@ -35,6 +35,11 @@
 //! let _guard = span.enter();
 //! ```
 //! Currently we provide `Log` (default), `Telemetry` and `Grafana` variants for `Receiver`
+//!
+//! Spans entered by the runtime (see `sp_tracing`) are reported with the target and name given by
+//! the runtime, and are filtered by the same targets as the native ones.
+//! When executing in WASM, the runtime only enters spans if it is built with the `with-tracing`
+//! feature of `frame-support`.
 
 use std::collections::HashMap;
 use std::fmt;
@@ -55,6 +60,9 @@ use tracing_core::{
 
 use grafana_data_source::{self, record_metrics};
 use sc_telemetry::{telemetry, SUBSTRATE_INFO};
+use sp_tracing::{
+	WASM_FIELD_KEY, WASM_FIELD_SEPARATOR, WASM_NAME_KEY, WASM_TARGET_KEY, WASM_TRACE_IDENTIFIER,
+};
 
 /// Used to configure how to receive the metrics
 #[derive(Debug, Clone)]
@@ -76,8 +84,8 @@ impl Default for TracingReceiver {
 #[derive(Debug)]
 struct SpanDatum {
 	id: u64,
-	name: &'static str,
-	target: &'static str,
+	name: String,
+	target: String,
 	level: Level,
 	line: u32,
 	start_time: Instant,
@@ -101,6 +109,10 @@ impl Visit for Visitor {
 		self.record_debug(field, &value)
 	}
 
+	fn record_str(&mut self, field: &Field, value: &str) {
+		self.0.push((field.name().to_string(), value.to_string()));
+	}
+
 	fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
 		self.0.push((field.name().to_string(), format!("{:?}",value)));
 	}
@@ -160,6 +172,7 @@ impl ProfilingSubscriber {
 	/// or without: "pallet".
 	pub fn new(receiver: TracingReceiver, targets: &str) -> Self {
 		let targets: Vec<_> = targets.split(',').map(|s| parse_target(s)).collect();
+		sp_tracing::set_wasm_tracing(true);
 		ProfilingSubscriber {
 			next_id: AtomicU64::new(1),
 			targets,
@@ -186,18 +199,26 @@ fn parse_target(s: &str) -> (String, Level) {
 	}
 }
 
-impl Subscriber for ProfilingSubscriber {
-	fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+impl ProfilingSubscriber {
+	fn check_target(&self, target: &str, level: &Level) -> bool {
 		for t in &self.targets {
-			if metadata.target().starts_with(t.0.as_str()) && metadata.level() <= &t.1 {
-				log::debug!("Enabled target: {}, level: {}", metadata.target(), metadata.level());
+			if target.starts_with(t.0.as_str()) && level <= &t.1 {
+				log::debug!("Enabled target: {}, level: {}", target, level);
 				return true;
 			} else {
-				log::debug!("Disabled target: {}, level: {}", metadata.target(), metadata.level());
+				log::debug!("Disabled target: {}, level: {}", target, level);
 			}
 		}
 		false
 	}
+}
+
+impl Subscriber for ProfilingSubscriber {
+	fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+		// The actual target of spans entered by the runtime is only known once they are closed.
+		metadata.target() == WASM_TRACE_IDENTIFIER
+			|| self.check_target(metadata.target(), metadata.level())
+	}
 
 	fn new_span(&self, attrs: &Attributes<'_>) -> Id {
 		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
@@ -205,8 +226,8 @@ impl Subscriber for ProfilingSubscriber {
 		attrs.record(&mut values);
 		let span_datum = SpanDatum {
 			id,
-			name: attrs.metadata().name(),
-			target: attrs.metadata().target(),
+			name: attrs.metadata().name().to_owned(),
+			target: attrs.metadata().target().to_owned(),
 			level: attrs.metadata().level().clone(),
 			line: attrs.metadata().line().unwrap_or(0),
 			start_time: Instant::now(),
@@ -217,7 +238,12 @@ impl Subscriber for ProfilingSubscriber {
 		Id::from_u64(id)
 	}
 
-	fn record(&self, _span: &Id, _values: &Record<'_>) {}
+	fn record(&self, span: &Id, values: &Record<'_>) {
+		let mut span_data = self.span_data.lock();
+		if let Some(s) = span_data.get_mut(&span.into_u64()) {
+			values.record(&mut s.values);
+		}
+	}
 
 	fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
 
@@ -251,7 +277,13 @@ impl Subscriber for ProfilingSubscriber {
 }
 
 impl ProfilingSubscriber {
-	fn send_span(&self, span_datum: SpanDatum) {
+	fn send_span(&self, mut span_datum: SpanDatum) {
+		if span_datum.target == WASM_TRACE_IDENTIFIER {
+			proxied_wasm_span(&mut span_datum);
+			if !self.check_target(&span_datum.target, &span_datum.level) {
+				return;
+			}
+		}
 		match self.receiver {
 			TracingReceiver::Log => print_log(span_datum),
 			TracingReceiver::Telemetry => send_telemetry(span_datum),
@@ -260,6 +292,24 @@ impl ProfilingSubscriber {
 	}
 }
 
+/// Replaces the metadata of a span entered by the runtime with the values set by the runtime.
+fn proxied_wasm_span(span_datum: &mut SpanDatum) {
+	let values = std::mem::replace(&mut span_datum.values.0, Vec::new());
+	for (key, value) in values {
+		match key.as_str() {
+			WASM_TARGET_KEY => span_datum.target = value,
+			WASM_NAME_KEY => span_datum.name = value,
+			WASM_FIELD_KEY => for field in value.split(WASM_FIELD_SEPARATOR) {
+				match field.find('=') {
+					Some(i) => span_datum.values.0.push((field[..i].to_owned(), field[i + 1..].to_owned())),
+					None => span_datum.values.0.push((field.to_owned(), String::new())),
+				}
+			},
+			_ => span_datum.values.0.push((key, value)),
+		}
+	}
+}
+
 fn print_log(span_datum: SpanDatum) {
 	if span_datum.values.0.is_empty() {
 		log::info!("TRACING: {} {}: {}, line: {}, time: {}",
@@ -19,7 +19,7 @@
 //! For a more full-featured pool, have a look at the `pool` module.
 
 use std::{
-	collections::HashSet,
+	collections::{HashMap, HashSet},
 	fmt,
 	hash,
 	sync::Arc,
@@ -57,6 +57,8 @@ pub enum Imported<Hash, Ex> {
 	Future {
 		/// Hash of transaction that was successfully imported.
 		hash: Hash,
+		/// Transactions removed from the Future pool (replaced).
+		removed: Vec<Arc<Transaction<Hash, Ex>>>,
 	}
 }
 
@@ -220,6 +222,12 @@ pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	/// transactions to future in case they were just stuck in verification.
 	recently_pruned: [HashSet<Tag>; RECENTLY_PRUNED_TAGS],
 	recently_pruned_index: usize,
+	/// Maximal number of transactions in the pool coming from a single sender.
+	sender_limit: Option<SenderLimit>,
+	/// Hashes of the transactions in both queues per sender.
+	///
+	/// Only tracked when the `sender_limit` is set.
+	senders: HashMap<Vec<u8>, HashSet<Hash>>,
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> Default for BasePool<Hash, Ex> {
@@ -237,6 +245,25 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			ready: Default::default(),
 			recently_pruned: Default::default(),
 			recently_pruned_index: 0,
+			sender_limit: None,
+			senders: Default::default(),
+		}
+	}
+
+	/// Limit the number of transactions in the pool coming from a single sender.
+	///
+	/// `None` disables the limit.
+	pub fn set_sender_limit(&mut self, sender_limit: Option<SenderLimit>) {
+		self.sender_limit = sender_limit;
+		self.senders.clear();
+
+		if let Some(ref limit) = self.sender_limit {
+			let senders = &mut self.senders;
+			let mut note = |tx: &Transaction<Hash, Ex>| if let Some(sender) = limit.sender(&tx.provides) {
+				senders.entry(sender.to_vec()).or_default().insert(tx.hash.clone());
+			};
+			self.ready.get().for_each(|tx| note(&tx));
+			self.future.all().for_each(|tx| note(tx));
 		}
 	}
 
@@ -268,6 +295,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			return Err(error::Error::AlreadyImported(Box::new(tx.hash.clone())))
 		}
 
+		self.ensure_sender_limit(&tx)?;
+
 		let tx = WaitingTransaction::new(
 			tx,
 			self.ready.provided_tags(),
@@ -288,13 +317,74 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			}
 
 			let hash = tx.transaction.hash.clone();
+			let removed = self.future.replace_previous(&tx.transaction)?;
+			self.note_removed(&removed);
+			self.note_imported(&tx.transaction);
 			self.future.import(tx);
-			return Ok(Imported::Future { hash });
+			return Ok(Imported::Future { hash, removed });
 		}
 
 		self.import_to_ready(tx)
 	}
 
+	/// Makes sure that the sender of given transaction doesn't exceed the `sender_limit`.
+	///
+	/// Transactions that would be replaced by the new one (i.e. providing the same tags)
+	/// are not counted towards the limit.
+	fn ensure_sender_limit(&self, tx: &Transaction<Hash, Ex>) -> error::Result<()> {
+		let limit = match self.sender_limit {
+			Some(ref limit) => limit,
+			None => return Ok(()),
+		};
+		let hashes = match limit.sender(&tx.provides).and_then(|sender| self.senders.get(sender)) {
+			Some(hashes) if hashes.len() >= limit.count => hashes.iter().cloned().collect::<Vec<_>>(),
+			_ => return Ok(()),
+		};
+
+		// the sender is at the limit, so there is at most `limit.count` transactions to look up.
+		let replaced = self.by_hash(&hashes)
+			.into_iter()
+			.flatten()
+			.filter(|other| other.provides.iter().any(|tag| tx.provides.contains(tag)))
+			.count();
+		let count = hashes.len() - replaced;
+
+		if count >= limit.count {
+			debug!(
+				target: "txpool",
+				"[{:?}] Sender has {} transactions in the pool, rejecting.",
+				tx.hash,
+				count,
+			);
+			return Err(error::Error::TooManyFromSender);
+		}
+
+		Ok(())
+	}
+
+	/// Notes that given transaction is now in one of the queues.
+	fn note_imported(&mut self, tx: &Transaction<Hash, Ex>) {
+		if let Some(sender) = self.sender_limit.as_ref().and_then(|limit| limit.sender(&tx.provides)) {
+			self.senders.entry(sender.to_vec()).or_default().insert(tx.hash.clone());
+		}
+	}
+
+	/// Notes that given transactions are not in any of the queues anymore.
+	fn note_removed(&mut self, removed: &[Arc<Transaction<Hash, Ex>>]) {
+		for tx in removed {
+			let sender = match self.sender_limit.as_ref().and_then(|limit| limit.sender(&tx.provides)) {
+				Some(sender) => sender,
+				None => continue,
+			};
+			if let Some(hashes) = self.senders.get_mut(sender) {
+				hashes.remove(&tx.hash);
+				if hashes.is_empty() {
+					self.senders.remove(sender);
+				}
+			}
+		}
+	}
+
 	/// Imports transaction to ready queue.
 	///
 	/// NOTE the transaction has to have all requirements satisfied.
@@ -319,20 +409,27 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 
 			// import this transaction
 			let current_hash = tx.transaction.hash.clone();
+			let transaction = tx.transaction.clone();
 			match self.ready.import(tx) {
 				Ok(mut replaced) => {
 					if !first {
 						promoted.push(current_hash);
 					}
+					self.note_imported(&transaction);
+					self.note_removed(&replaced);
 					// The transactions were removed from the ready pool. We might attempt to re-import them.
 					removed.append(&mut replaced);
 				},
 				// transaction failed to be imported.
-				Err(e) => if first {
-					debug!(target: "txpool", "[{:?}] Error importing: {:?}", current_hash, e);
-					return Err(e)
-				} else {
-					failed.push(current_hash);
+				Err(e) => {
+					// promoted transactions were already taken out of the future queue.
+					self.note_removed(&[transaction]);
+					if first {
+						debug!(target: "txpool", "[{:?}] Error importing: {:?}", current_hash, e);
+						return Err(e)
+					} else {
+						failed.push(current_hash);
+					}
 				},
 			}
 			first = false;
@@ -346,7 +443,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		if removed.iter().any(|tx| tx.hash == hash) {
 			// We still need to remove all transactions that we promoted
 			// since they depend on each other and will never get to the best iterator.
-			self.ready.remove_subtree(&promoted);
+			let cycle = self.ready.remove_subtree(&promoted);
+			self.note_removed(&cycle);
 
 			debug!(target: "txpool", "[{:?}] Cycle detected, bailing.", hash);
 			return Err(error::Error::CycleDetected)
@@ -389,7 +487,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	///
 	/// Removes and returns worst transactions from the queues and all transactions that depend on them.
 	/// Technically the worst transaction should be evaluated by computing the entire pending set.
-	/// We use a simplified approach to remove the transaction that occupies the pool for the longest time.
+	/// We use a simplified approach to remove the transaction with the lowest priority,
+	/// and in case of a tie the one that occupies the pool for the longest time.
 	pub fn enforce_limits(&mut self, ready: &Limit, future: &Limit) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = vec![];
 
@@ -400,7 +499,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 					let transaction = &current.transaction;
 					match minimal {
 						None => Some(transaction.clone()),
-						Some(ref tx) if tx.insertion_id > transaction.insertion_id => {
+						Some(ref tx) if (tx.transaction.priority, tx.insertion_id)
+							> (transaction.transaction.priority, transaction.insertion_id) => {
 							Some(transaction.clone())
 						},
 						other => other,
@@ -420,7 +520,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 				.fold(|minimal, current| {
 					match minimal {
 						None => Some(current.clone()),
-						Some(ref tx) if tx.imported_at > current.imported_at => {
+						Some(ref tx) if (tx.transaction.priority, tx.imported_at)
+							> (current.transaction.priority, current.imported_at) => {
 							Some(current.clone())
 						},
 						other => other,
@@ -448,12 +549,15 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	pub fn remove_subtree(&mut self, hashes: &[Hash]) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = self.ready.remove_subtree(hashes);
 		removed.extend(self.future.remove(hashes));
+		self.note_removed(&removed);
 		removed
 	}
 
 	/// Removes and returns all transactions from the future queue.
 	pub fn clear_future(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
-		self.future.clear()
+		let removed = self.future.clear();
+		self.note_removed(&removed);
+		removed
 	}
 
 	/// Prunes transactions that provide given list of tags.
@@ -477,6 +581,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			// store the tags for next submission
 			recently_pruned.insert(tag);
 		}
+		self.note_removed(&pruned);
 
 		let mut promoted = vec![];
 		let mut failed = vec![];
@@ -525,6 +630,29 @@ impl Limit {
 	}
 }
 
+/// Limit of transactions coming from a single sender.
+///
+/// The sender is identified by a prefix of the first tag provided by the transaction.
+/// For FRAME-based runtimes the tag is `(AccountId, Index).encode()`, so the prefix
+/// length should be the size of the encoded account id.
+#[derive(Debug, Clone)]
+pub struct SenderLimit {
+	/// Maximal number of transactions from a single sender in both queues.
+	pub count: usize,
+	/// Number of leading bytes of the first provided tag that identify the sender.
+	pub tag_prefix_len: usize,
+}
+
+impl SenderLimit {
+	/// Returns the sender of a transaction with given `provides` tags, if it can be identified.
+	fn sender<'a>(&self, provides: &'a [Tag]) -> Option<&'a [u8]> {
+		provides
+			.first()
+			.filter(|tag| tag.len() >= self.tag_prefix_len)
+			.map(|tag| &tag[..self.tag_prefix_len])
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -1119,4 +1247,154 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 		assert_eq!(pool.reject_future_transactions, true);
 		assert_eq!(pool.future.len(), 1);
 	}
+
+	#[test]
+	fn should_evict_lowest_priority_transactions_first() {
+		// given
+		let mut pool = pool();
+		for (hash, priority) in vec![(1, 1u64), (2, 5), (3, 1), (4, 3)] {
+			pool.import(Transaction {
+				data: vec![hash as u8],
+				bytes: 1,
+				hash,
+				priority,
+				valid_till: 64u64,
+				requires: vec![],
+				provides: vec![vec![hash as u8]],
+				propagate: true,
+			}).unwrap();
+		}
+		let limit = Limit { count: 2, total_bytes: 1024 };
+
+		// when
+		let removed = pool.enforce_limits(&limit, &limit);
+
+		// then
+		assert_eq!(removed.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1, 3]);
+		let mut ready = pool.ready().map(|tx| tx.hash).collect::<Vec<_>>();
+		ready.sort();
+		assert_eq!(ready, vec![2, 4]);
+	}
+
+	#[test]
+	fn should_evict_lowest_priority_future_transactions_first() {
+		// given
+		let mut pool = pool();
+		for (hash, priority) in vec![(1, 5u64), (2, 1), (3, 3)] {
+			pool.import(Transaction {
+				data: vec![hash as u8],
+				bytes: 1,
+				hash,
+				priority,
+				valid_till: 64u64,
+				requires: vec![vec![0]],
+				provides: vec![vec![hash as u8]],
+				propagate: true,
+			}).unwrap();
+		}
+		let ready = Limit { count: 10, total_bytes: 1024 };
+		let future = Limit { count: 1, total_bytes: 1024 };
+
+		// when
+		let removed = pool.enforce_limits(&ready, &future);
+
+		// then
+		assert_eq!(removed.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2, 3]);
+		assert_eq!(pool.futures().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+	}
+
+	#[test]
+	fn should_replace_future_transaction_with_higher_priority() {
+		// given
+		let mut pool = pool();
+		pool.import(Transaction {
+			data: vec![1u8],
+			bytes: 1,
+			hash: 1,
+			priority: 5u64,
+			valid_till: 64u64,
+			requires: vec![vec![0]],
+			provides: vec![vec![1]],
+			propagate: true,
+		}).unwrap();
+
+		// when
+		let err = pool.import(Transaction {
+			data: vec![2u8],
+			bytes: 1,
+			hash: 2,
+			priority: 5u64,
+			valid_till: 64u64,
+			requires: vec![vec![0]],
+			provides: vec![vec![1]],
+			propagate: true,
+		}).unwrap_err();
+		let imported = pool.import(Transaction {
+			data: vec![3u8],
+			bytes: 1,
+			hash: 3,
+			priority: 10u64,
+			valid_till: 64u64,
+			requires: vec![vec![0]],
+			provides: vec![vec![1]],
+			propagate: true,
+		}).unwrap();
+
+		// then
+		if let error::Error::TooLowPriority { old: 5, new: 5 } = err {
+		} else {
+			assert!(false, "Invalid error kind: {:?}", err);
+		}
+		if let Imported::Future { hash: 3, ref removed } = imported {
+			assert_eq!(removed.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+		} else {
+			assert!(false, "Invalid import result: {:?}", imported);
+		}
+		assert_eq!(pool.futures().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3]);
+	}
+
+	#[test]
+	fn should_limit_transactions_per_sender() {
+		// given
+		let mut pool = pool();
+		pool.set_sender_limit(Some(SenderLimit { count: 2, tag_prefix_len: 1 }));
+		let tx = |hash: u64, priority: u64, provides: Vec<u8>| Transaction {
+			data: vec![hash as u8],
+			bytes: 1,
+			hash,
+			priority,
+			valid_till: 64u64,
+			requires: vec![],
+			provides: vec![provides],
+			propagate: true,
+		};
+		pool.import(tx(1, 5, vec![1, 0])).unwrap();
+		pool.import(tx(2, 5, vec![1, 1])).unwrap();
+
+		// when
+		let err = pool.import(tx(3, 5, vec![1, 2])).unwrap_err();
+
+		// then
+		if let error::Error::TooManyFromSender = err {
+		} else {
+			assert!(false, "Invalid error kind: {:?}", err);
+		}
+
+		// other senders and replacements are still accepted
+		pool.import(tx(4, 5, vec![2, 0])).unwrap();
+		pool.import(tx(5, 10, vec![1, 1])).unwrap();
+		let mut ready = pool.ready().map(|tx| tx.hash).collect::<Vec<_>>();
+		ready.sort();
+		assert_eq!(ready, vec![1, 4, 5]);
+
+		// and removed or pruned transactions are not counted anymore
+		pool.remove_subtree(&[1]);
+		pool.import(tx(6, 5, vec![1, 2])).unwrap();
+		assert!(pool.import(tx(7, 5, vec![1, 3])).is_err());
+		pool.prune_tags(vec![vec![1, 1]]);
+		pool.import(tx(7, 5, vec![1, 3])).unwrap();
+		let mut ready = pool.ready().map(|tx| tx.hash).collect::<Vec<_>>();
+		ready.sort();
+		assert_eq!(ready, vec![4, 6, 7]);
+	}
 }
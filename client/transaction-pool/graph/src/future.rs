@@ -26,6 +26,7 @@ use sp_core::hexdisplay::HexDisplay;
 use sp_runtime::transaction_validity::{
 	TransactionTag as Tag,
 };
+use sp_transaction_pool::error;
 
 use crate::base_pool::Transaction;
 
@@ -154,6 +155,42 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 		self.waiting.insert(tx.transaction.hash.clone(), tx);
 	}
 
+	/// Removes transactions providing the same tags as given transaction.
+	///
+	/// Old transactions are only replaced if their collective priority is lower
+	/// than the priority of the replacement transaction.
+	/// In case replacement is successful returns a list of removed transactions.
+	pub fn replace_previous(
+		&mut self,
+		tx: &Transaction<Hash, Ex>,
+	) -> error::Result<Vec<Arc<Transaction<Hash, Ex>>>> {
+		let replace_hashes = self.waiting
+			.values()
+			.filter(|waiting| waiting.transaction.provides.iter().any(|tag| tx.provides.contains(tag)))
+			.map(|waiting| waiting.transaction.hash.clone())
+			.collect::<Vec<_>>();
+
+		// early exit if we are not replacing anything.
+		if replace_hashes.is_empty() {
+			return Ok(vec![]);
+		}
+
+		// now check if collective priority is lower than the replacement transaction.
+		let old_priority = replace_hashes
+			.iter()
+			.filter_map(|hash| self.waiting.get(hash))
+			.fold(0u64, |total, waiting|
+				total.saturating_add(waiting.transaction.priority)
+			);
+
+		// bail - the transaction has too low priority to replace the old ones
+		if old_priority >= tx.priority {
+			return Err(error::Error::TooLowPriority { old: old_priority, new: tx.priority })
+		}
+
+		Ok(self.remove(&replace_hashes))
+	}
+
 	/// Returns true if given hash is part of the queue.
 	pub fn contains(&self, hash: &Hash) -> bool {
 		self.waiting.contains_key(hash)
@@ -0,0 +1,346 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! On-disk journal of submitted extrinsics.
+//!
+//! The journal is an append-only file of SCALE-encoded extrinsics. Every extrinsic that
+//! is submitted locally is appended to it, so that it can be re-validated and restored
+//! after the node restarts. Extrinsics that left the pool are only removed when the journal
+//! is rewritten with the current pool content.
+//!
+//! The file is written by a background thread, see `JournalWriter`, so that the pool never
+//! waits for the disk when transactions are imported.
+
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{self, Read, Write},
+	path::{Path, PathBuf},
+	sync::atomic::{AtomicUsize, Ordering},
+	thread,
+};
+
+use codec::{Encode, Decode};
+use futures::{channel::mpsc, executor::block_on, StreamExt};
+use log::warn;
+use parking_lot::Mutex;
+
+/// Journal of extrinsics submitted to the pool.
+pub struct Journal<Ex> {
+	path: PathBuf,
+	file: File,
+	/// Number of entries in the file.
+	entries: usize,
+	/// Extrinsics loaded from the file that have not been restored yet.
+	pending: Vec<Ex>,
+}
+
+impl<Ex: Encode + Decode> Journal<Ex> {
+	/// Open the journal at given path, creating it if it doesn't exist.
+	///
+	/// Extrinsics recorded so far are loaded and can be retrieved with `take_pending`.
+	pub fn open(path: &Path) -> io::Result<Self> {
+		if let Some(dir) = path.parent() {
+			fs::create_dir_all(dir)?;
+		}
+
+		let (pending, is_complete) = match File::open(path) {
+			Ok(mut file) => {
+				let mut data = Vec::new();
+				file.read_to_end(&mut data)?;
+				decode_entries(&data)
+			},
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => (Vec::new(), true),
+			Err(e) => return Err(e),
+		};
+
+		let mut journal = Journal {
+			path: path.to_owned(),
+			file: OpenOptions::new().create(true).append(true).open(path)?,
+			entries: pending.len(),
+			pending: Vec::new(),
+		};
+		// get rid of the malformed tail, so that new entries can be appended
+		if !is_complete {
+			journal.rewrite(&pending)?;
+		}
+		journal.pending = pending;
+
+		Ok(journal)
+	}
+
+	/// Returns extrinsics loaded from the journal when it was opened.
+	///
+	/// Subsequent calls return an empty list.
+	pub fn take_pending(&mut self) -> Vec<Ex> {
+		std::mem::replace(&mut self.pending, Vec::new())
+	}
+
+	/// Number of entries currently stored in the journal.
+	///
+	/// Includes extrinsics that might have already left the pool.
+	pub fn entries(&self) -> usize {
+		self.entries
+	}
+
+	/// Append an extrinsic to the journal.
+	///
+	/// The entry is not guaranteed to be on disk until `sync` is called.
+	pub fn append(&mut self, xt: &Ex) -> io::Result<()> {
+		self.file.write_all(&xt.encode().encode())?;
+		self.entries += 1;
+		Ok(())
+	}
+
+	/// Make sure that all appended entries are stored on disk.
+	pub fn sync(&mut self) -> io::Result<()> {
+		self.file.sync_data()
+	}
+
+	/// Replace the content of the journal with given extrinsics.
+	///
+	/// The new content is written to a temporary file first, which then atomically
+	/// replaces the journal.
+	pub fn rewrite<'a>(&mut self, xts: impl IntoIterator<Item=&'a Ex>) -> io::Result<()> where
+		Ex: 'a,
+	{
+		let tmp_path = self.path.with_extension("tmp");
+		let mut entries = 0;
+		{
+			let mut tmp = File::create(&tmp_path)?;
+			for xt in xts {
+				tmp.write_all(&xt.encode().encode())?;
+				entries += 1;
+			}
+			tmp.sync_all()?;
+		}
+		fs::rename(&tmp_path, &self.path)?;
+
+		self.file = OpenOptions::new().append(true).open(&self.path)?;
+		self.entries = entries;
+		Ok(())
+	}
+}
+
+/// An operation performed by the journal writer thread.
+enum Command<Ex> {
+	Append(Ex),
+	Rewrite(Vec<Ex>),
+}
+
+/// Handle to a journal that is written by a background thread.
+///
+/// Appending and rewriting only queue the operation, so they never block on I/O. Operations
+/// queued in a row are written together and synced to disk once. Dropping the handle waits
+/// until all queued operations are written.
+pub struct JournalWriter<Ex> {
+	sender: Option<mpsc::UnboundedSender<Command<Ex>>>,
+	thread: Option<thread::JoinHandle<()>>,
+	/// Number of entries in the journal, including the queued ones.
+	entries: AtomicUsize,
+	/// Extrinsics loaded from the file that have not been restored yet.
+	pending: Mutex<Vec<Ex>>,
+}
+
+impl<Ex: Encode + Decode + Send + 'static> JournalWriter<Ex> {
+	/// Open the journal at given path, creating it if it doesn't exist, and start the writer
+	/// thread.
+	///
+	/// Extrinsics recorded so far are loaded and can be retrieved with `take_pending`.
+	pub fn open(path: &Path) -> io::Result<Self> {
+		let mut journal = Journal::open(path)?;
+		let pending = journal.take_pending();
+		let entries = journal.entries();
+		let (sender, receiver) = mpsc::unbounded();
+		let thread = thread::Builder::new()
+			.name("txpool-journal".into())
+			.spawn(move || write_journal(journal, receiver))?;
+
+		Ok(JournalWriter {
+			sender: Some(sender),
+			thread: Some(thread),
+			entries: AtomicUsize::new(entries),
+			pending: Mutex::new(pending),
+		})
+	}
+
+	/// Returns extrinsics loaded from the journal when it was opened.
+	///
+	/// Subsequent calls return an empty list.
+	pub fn take_pending(&self) -> Vec<Ex> {
+		std::mem::replace(&mut *self.pending.lock(), Vec::new())
+	}
+
+	/// Number of entries stored in the journal, including the ones that are not written yet.
+	///
+	/// Includes extrinsics that might have already left the pool.
+	pub fn entries(&self) -> usize {
+		self.entries.load(Ordering::Relaxed)
+	}
+
+	/// Queue appending an extrinsic to the journal.
+	pub fn append(&self, xt: Ex) {
+		self.entries.fetch_add(1, Ordering::Relaxed);
+		self.send(Command::Append(xt));
+	}
+
+	/// Queue replacing the content of the journal with given extrinsics.
+	pub fn rewrite(&self, xts: Vec<Ex>) {
+		self.entries.store(xts.len(), Ordering::Relaxed);
+		self.send(Command::Rewrite(xts));
+	}
+
+	fn send(&self, command: Command<Ex>) {
+		let sent = self.sender.as_ref().map_or(false, |sender| sender.unbounded_send(command).is_ok());
+		if !sent {
+			warn!(target: "txpool", "Transaction pool journal writer is not running");
+		}
+	}
+}
+
+impl<Ex> Drop for JournalWriter<Ex> {
+	fn drop(&mut self) {
+		// closing the channel stops the writer once all queued operations are written
+		self.sender.take();
+		if let Some(thread) = self.thread.take() {
+			let _ = thread.join();
+		}
+	}
+}
+
+/// Body of the journal writer thread.
+fn write_journal<Ex: Encode + Decode>(
+	mut journal: Journal<Ex>,
+	mut receiver: mpsc::UnboundedReceiver<Command<Ex>>,
+) {
+	while let Some(command) = block_on(receiver.next()) {
+		let mut command = Some(command);
+		while let Some(next) = command {
+			let result = match next {
+				Command::Append(xt) => journal.append(&xt),
+				Command::Rewrite(xts) => journal.rewrite(&xts),
+			};
+			if let Err(e) = result {
+				warn!(target: "txpool", "Failed to write to transaction pool journal: {}", e);
+			}
+			// write everything that is already queued before syncing
+			command = receiver.try_next().ok().and_then(|command| command);
+		}
+		if let Err(e) = journal.sync() {
+			warn!(target: "txpool", "Failed to sync transaction pool journal: {}", e);
+		}
+	}
+}
+
+/// Decode all entries of the journal.
+///
+/// Decoding stops at the first malformed entry, which might be a result of the node
+/// being killed in the middle of a write. Returns decoded extrinsics and whether
+/// the entire journal has been decoded.
+fn decode_entries<Ex: Decode>(mut data: &[u8]) -> (Vec<Ex>, bool) {
+	let mut entries = Vec::new();
+	let mut is_complete = true;
+	while !data.is_empty() {
+		let entry = match Vec::<u8>::decode(&mut data) {
+			Ok(entry) => entry,
+			Err(e) => {
+				warn!(target: "txpool", "Truncated transaction pool journal: {:?}", e.what());
+				is_complete = false;
+				break;
+			},
+		};
+		match Ex::decode(&mut &entry[..]) {
+			Ok(xt) => entries.push(xt),
+			Err(e) => warn!(target: "txpool", "Skipping invalid journal entry: {:?}", e.what()),
+		}
+	}
+	(entries, is_complete)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_restore_appended_extrinsics() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("txpool").join("journal");
+
+		let mut journal = Journal::<Vec<u8>>::open(&path).unwrap();
+		assert!(journal.take_pending().is_empty());
+		journal.append(&vec![1, 2, 3]).unwrap();
+		journal.append(&vec![4]).unwrap();
+		drop(journal);
+
+		let mut journal = Journal::<Vec<u8>>::open(&path).unwrap();
+		assert_eq!(journal.entries(), 2);
+		assert_eq!(journal.take_pending(), vec![vec![1, 2, 3], vec![4]]);
+		assert!(journal.take_pending().is_empty());
+	}
+
+	#[test]
+	fn should_rewrite_journal() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("journal");
+
+		let mut journal = Journal::<Vec<u8>>::open(&path).unwrap();
+		journal.append(&vec![1]).unwrap();
+		journal.append(&vec![2]).unwrap();
+		journal.rewrite(vec![vec![3]].iter()).unwrap();
+		journal.append(&vec![4]).unwrap();
+		assert_eq!(journal.entries(), 2);
+		drop(journal);
+
+		let mut journal = Journal::<Vec<u8>>::open(&path).unwrap();
+		assert_eq!(journal.take_pending(), vec![vec![3], vec![4]]);
+	}
+
+	#[test]
+	fn should_ignore_truncated_entry() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("journal");
+
+		let mut data = vec![1u8, 2].encode().encode();
+		data.extend(&vec![3u8, 4, 5].encode().encode()[..3]);
+		fs::write(&path, data).unwrap();
+
+		let mut journal = Journal::<Vec<u8>>::open(&path).unwrap();
+		assert_eq!(journal.take_pending(), vec![vec![1, 2]]);
+		journal.append(&vec![6]).unwrap();
+		drop(journal);
+
+		let mut journal = Journal::<Vec<u8>>::open(&path).unwrap();
+		assert_eq!(journal.take_pending(), vec![vec![1, 2], vec![6]]);
+	}
+
+	#[test]
+	fn writer_should_write_queued_operations_before_drop() {
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("journal");
+
+		let writer = JournalWriter::<Vec<u8>>::open(&path).unwrap();
+		writer.append(vec![1]);
+		writer.append(vec![2]);
+		writer.rewrite(vec![vec![3]]);
+		writer.append(vec![4]);
+		assert_eq!(writer.entries(), 2);
+		drop(writer);
+
+		let writer = JournalWriter::<Vec<u8>>::open(&path).unwrap();
+		assert_eq!(writer.entries(), 2);
+		assert_eq!(writer.take_pending(), vec![vec![3], vec![4]]);
+		assert!(writer.take_pending().is_empty());
+	}
+}
@@ -25,6 +25,7 @@
 #![warn(unused_extern_crates)]
 
 mod future;
+mod journal;
 mod listener;
 mod pool;
 mod ready;
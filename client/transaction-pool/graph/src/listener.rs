@@ -16,7 +16,7 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{
-	collections::HashMap,
+	collections::{HashMap, VecDeque},
 	fmt,
 	hash,
 };
@@ -25,20 +25,32 @@ use crate::watcher;
 use sp_runtime::traits;
 use log::{debug, trace, warn};
 
+/// Maximal number of blocks which inclusion is tracked until finality.
+const MAX_FINALITY_WATCHERS: usize = 512;
+
 /// Extrinsic pool default listener.
-pub struct Listener<H: hash::Hash + Eq, H2> {
-	watchers: HashMap<H, watcher::Sender<H, H2>>
+pub struct Listener<H: hash::Hash + Eq, H2: hash::Hash + Eq> {
+	watchers: HashMap<H, watcher::Sender<H, H2>>,
+	/// Watched extrinsics included in blocks that are not yet finalized.
+	finality_watchers: HashMap<H2, Vec<H>>,
+	/// Blocks tracked in `finality_watchers` in the order of their inclusion.
+	finality_watchers_order: VecDeque<H2>,
 }
 
-impl<H: hash::Hash + Eq, H2> Default for Listener<H, H2> {
+impl<H: hash::Hash + Eq, H2: hash::Hash + Eq> Default for Listener<H, H2> {
 	fn default() -> Self {
 		Listener {
 			watchers: Default::default(),
+			finality_watchers: Default::default(),
+			finality_watchers_order: Default::default(),
 		}
 	}
 }
 
-impl<H: hash::Hash + traits::Member + Serialize, H2: Clone + fmt::Debug> Listener<H, H2> {
+impl<H, H2> Listener<H, H2> where
+	H: hash::Hash + traits::Member + Serialize,
+	H2: hash::Hash + Eq + Clone + fmt::Debug,
+{
 	fn fire<F>(&mut self, hash: &H, fun: F) where F: FnOnce(&mut watcher::Sender<H, H2>) {
 		let clean = if let Some(h) = self.watchers.get_mut(hash) {
 			fun(h);
@@ -103,6 +115,51 @@ impl<H: hash::Hash + traits::Member + Serialize, H2: Clone + fmt::Debug> Listene
 	/// Transaction was pruned from the pool.
 	pub fn pruned(&mut self, header_hash: H2, tx: &H) {
 		debug!(target: "txpool", "[{:?}] Pruned at {:?}", tx, header_hash);
-		self.fire(tx, |watcher| watcher.in_block(header_hash))
+		self.fire(tx, |watcher| watcher.in_block(header_hash.clone()));
+
+		// keep watching the transaction until the block gets finalized
+		if !self.watchers.contains_key(tx) {
+			return;
+		}
+		if !self.finality_watchers.contains_key(&header_hash) {
+			self.finality_watchers_order.push_back(header_hash.clone());
+		}
+		self.finality_watchers.entry(header_hash).or_insert_with(Vec::new).push(tx.clone());
+
+		while self.finality_watchers_order.len() > MAX_FINALITY_WATCHERS {
+			let block = match self.finality_watchers_order.pop_front() {
+				Some(block) => block,
+				None => break,
+			};
+			for tx in self.finality_watchers.remove(&block).unwrap_or_default() {
+				self.fire(&tx, |watcher| watcher.finality_timeout(block.clone()));
+			}
+		}
+	}
+
+	/// The block with given hash has been retracted.
+	pub fn retracted(&mut self, block_hash: H2) {
+		if let Some(hashes) = self.remove_finality_watchers(&block_hash) {
+			for tx in hashes {
+				debug!(target: "txpool", "[{:?}] Retracted from {:?}", tx, block_hash);
+				self.fire(&tx, |watcher| watcher.retracted(block_hash.clone()));
+			}
+		}
+	}
+
+	/// The block with given hash has been finalized.
+	pub fn finalized(&mut self, block_hash: H2) {
+		if let Some(hashes) = self.remove_finality_watchers(&block_hash) {
+			for tx in hashes {
+				debug!(target: "txpool", "[{:?}] Finalized in {:?}", tx, block_hash);
+				self.fire(&tx, |watcher| watcher.finalized(block_hash.clone()));
+			}
+		}
+	}
+
+	fn remove_finality_watchers(&mut self, block_hash: &H2) -> Option<Vec<H>> {
+		let hashes = self.finality_watchers.remove(block_hash)?;
+		self.finality_watchers_order.retain(|hash| hash != block_hash);
+		Some(hashes)
 	}
 }
@@ -17,6 +17,7 @@
 use std::{
 	hash,
 	collections::HashMap,
+	path::PathBuf,
 	sync::Arc,
 };
 
@@ -36,7 +37,7 @@ use sp_runtime::{
 };
 use sp_transaction_pool::{error, PoolStatus};
 
-use crate::validated_pool::{ValidatedPool, ValidatedTransaction};
+use crate::validated_pool::{ValidatedPool, ValidatedTransaction, Source};
 
 /// Modification notification event stream type;
 pub type EventStream = mpsc::UnboundedReceiver<()>;
@@ -95,6 +96,13 @@ pub struct Options {
 	pub future: base::Limit,
 	/// Reject future transactions.
 	pub reject_future_transactions: bool,
+	/// Limit of transactions from a single sender.
+	pub sender_limit: Option<base::SenderLimit>,
+	/// Path of the journal recording locally submitted transactions.
+	///
+	/// Transactions stored in the journal are re-validated and restored with
+	/// `Pool::restore_journal` after restart. `None` disables the journal.
+	pub journal_path: Option<PathBuf>,
 }
 
 impl Default for Options {
@@ -109,6 +117,8 @@ impl Default for Options {
 				total_bytes: 1 * 1024 * 1024,
 			},
 			reject_future_transactions: false,
+			sender_limit: None,
+			journal_path: None,
 		}
 	}
 }
@@ -132,12 +142,7 @@ impl<B: ChainApi> Pool<B> {
 	where
 		T: IntoIterator<Item=ExtrinsicFor<B>>
 	{
-		let validated_pool = self.validated_pool.clone();
-		self.verify(at, xts, force)
-			.map(move |validated_transactions| validated_transactions
-				.map(|validated_transactions| validated_pool.submit(validated_transactions
-					.into_iter()
-					.map(|(_, tx)| tx))))
+		self.submit_from(at, xts, force, Source::External)
 	}
 
 	/// Imports one unverified extrinsic to the pool
@@ -153,7 +158,24 @@ impl<B: ChainApi> Pool<B> {
 			))
 	}
 
+	/// Imports one unverified extrinsic submitted locally, e.g. through RPC.
+	///
+	/// Unlike extrinsics received from the network, it is recorded in the pool journal.
+	pub fn submit_local(
+		&self,
+		at: &BlockId<B::Block>,
+		xt: ExtrinsicFor<B>,
+	) -> impl Future<Output=Result<ExHash<B>, B::Error>> {
+		self.submit_from(at, std::iter::once(xt), false, Source::Local)
+			.map(|import_result| import_result.and_then(|mut import_result| import_result
+				.pop()
+				.expect("One extrinsic passed; one result returned; qed")
+			))
+	}
+
 	/// Import a single extrinsic and starts to watch their progress in the pool.
+	///
+	/// The extrinsic is considered to be submitted locally, so it is recorded in the journal.
 	pub fn submit_and_watch(
 		&self,
 		at: &BlockId<B::Block>,
@@ -171,6 +193,55 @@ impl<B: ChainApi> Pool<B> {
 		)
 	}
 
+	/// Re-submits transactions recorded in the journal before the node was restarted.
+	///
+	/// Transactions are re-validated at given block, so only the ones that are still valid
+	/// end up in the pool. The journal is restored only once, subsequent calls do nothing.
+	/// Returns the number of restored transactions.
+	pub fn restore_journal(
+		&self,
+		at: &BlockId<B::Block>,
+	) -> impl Future<Output=Result<usize, B::Error>> {
+		let journaled = self.validated_pool.take_journaled();
+		if journaled.is_empty() {
+			return Either::Left(ready(Ok(0)));
+		}
+
+		log::debug!(target: "txpool", "Restoring {} transactions from the journal", journaled.len());
+		Either::Right(self.submit_from(at, journaled, false, Source::Restored)
+			.map(|results| results.map(|results| results.iter().filter(|res| res.is_ok()).count())))
+	}
+
+	fn submit_from<T>(&self, at: &BlockId<B::Block>, xts: T, force: bool, source: Source)
+		-> impl Future<Output=Result<Vec<Result<ExHash<B>, B::Error>>, B::Error>>
+	where
+		T: IntoIterator<Item=ExtrinsicFor<B>>
+	{
+		let validated_pool = self.validated_pool.clone();
+		self.verify(at, xts, force)
+			.map(move |validated_transactions| validated_transactions
+				.map(|validated_transactions| validated_pool.submit(validated_transactions
+					.into_iter()
+					.map(|(_, tx)| tx), source)))
+	}
+
+	/// Notify watchers that the block with given id has been finalized.
+	///
+	/// Transactions included in the block receive the `Finalized` status.
+	pub fn on_block_finalized(&self, at: &BlockId<B::Block>) -> Result<(), B::Error> {
+		let block_hash = self.validated_pool.api().block_id_to_hash(at)?
+			.ok_or_else(|| error::Error::InvalidBlockId(format!("{:?}", at)).into())?;
+		self.validated_pool.on_block_finalized(block_hash);
+		Ok(())
+	}
+
+	/// Notify watchers that the block with given hash has been retracted.
+	///
+	/// Transactions included in the block receive the `Retracted` status.
+	pub fn on_block_retracted(&self, block_hash: BlockHash<B>) {
+		self.validated_pool.on_block_retracted(block_hash)
+	}
+
 	/// Revalidate all ready transactions.
 	///
 	/// Returns future that performs validation of all ready transactions and
@@ -802,11 +873,41 @@ mod tests {
 			block_on(pool.prune_tags(&BlockId::Number(2), vec![vec![0u8]], vec![])).unwrap();
 			assert_eq!(pool.status().ready, 0);
 			assert_eq!(pool.status().future, 0);
+			pool.on_block_finalized(&BlockId::Number(2)).unwrap();
+
+			// then
+			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+			assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+			assert_eq!(stream.next(), Some(TransactionStatus::InBlock(H256::from_low_u64_be(2).into())));
+			assert_eq!(stream.next(), Some(TransactionStatus::Finalized(H256::from_low_u64_be(2).into())));
+			assert_eq!(stream.next(), None);
+		}
+
+		#[test]
+		fn should_trigger_retracted_and_keep_watching() {
+			// given
+			let pool = pool();
+			let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			}))).unwrap();
+			block_on(pool.prune_tags(&BlockId::Number(2), vec![vec![0u8]], vec![])).unwrap();
+
+			// when
+			pool.on_block_retracted(H256::from_low_u64_be(2).into());
+			pool.on_block_finalized(&BlockId::Number(2)).unwrap();
+			block_on(pool.prune_tags(&BlockId::Number(3), vec![vec![0u8]], vec![watcher.hash().clone()])).unwrap();
+			pool.on_block_finalized(&BlockId::Number(3)).unwrap();
 
 			// then
 			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
 			assert_eq!(stream.next(), Some(TransactionStatus::Ready));
 			assert_eq!(stream.next(), Some(TransactionStatus::InBlock(H256::from_low_u64_be(2).into())));
+			assert_eq!(stream.next(), Some(TransactionStatus::Retracted(H256::from_low_u64_be(2).into())));
+			assert_eq!(stream.next(), Some(TransactionStatus::InBlock(H256::from_low_u64_be(3).into())));
+			assert_eq!(stream.next(), Some(TransactionStatus::Finalized(H256::from_low_u64_be(3).into())));
 			assert_eq!(stream.next(), None);
 		}
 
@@ -827,11 +928,13 @@ mod tests {
 			block_on(pool.prune_tags(&BlockId::Number(2), vec![vec![0u8]], vec![2u64])).unwrap();
 			assert_eq!(pool.status().ready, 0);
 			assert_eq!(pool.status().future, 0);
+			pool.on_block_finalized(&BlockId::Number(2)).unwrap();
 
 			// then
 			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
 			assert_eq!(stream.next(), Some(TransactionStatus::Ready));
 			assert_eq!(stream.next(), Some(TransactionStatus::InBlock(H256::from_low_u64_be(2).into())));
+			assert_eq!(stream.next(), Some(TransactionStatus::Finalized(H256::from_low_u64_be(2).into())));
 			assert_eq!(stream.next(), None);
 		}
 
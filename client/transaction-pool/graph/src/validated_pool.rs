@@ -23,6 +23,7 @@ use std::{
 };
 
 use crate::base_pool as base;
+use crate::journal::JournalWriter;
 use crate::listener::Listener;
 use crate::rotator::PoolRotator;
 use crate::watcher::Watcher;
@@ -61,6 +62,25 @@ pub type ValidatedTransactionFor<B> = ValidatedTransaction<
 	<B as ChainApi>::Error,
 >;
 
+/// Where a submitted transaction comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Source {
+	/// Received from the network or re-submitted by the pool itself.
+	External,
+	/// Submitted locally, e.g. through RPC. Such transactions are recorded in the journal.
+	Local,
+	/// Restored from the journal after restart, so already recorded in it.
+	Restored,
+}
+
+/// Journal of locally submitted transactions.
+struct LocalJournal<Hash, Ex> {
+	writer: JournalWriter<Ex>,
+	/// Hashes of the journaled transactions, used to find them in the pool when the journal
+	/// is rewritten.
+	hashes: Mutex<HashSet<Hash>>,
+}
+
 /// Pool that deals with validated transactions.
 pub(crate) struct ValidatedPool<B: ChainApi> {
 	api: B,
@@ -72,12 +92,26 @@ pub(crate) struct ValidatedPool<B: ChainApi> {
 	>>,
 	import_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<()>>>,
 	rotator: PoolRotator<ExHash<B>>,
+	journal: Option<LocalJournal<ExHash<B>, ExtrinsicFor<B>>>,
 }
 
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, api: B) -> Self {
-		let base_pool = base::BasePool::new(options.reject_future_transactions);
+		let mut base_pool = base::BasePool::new(options.reject_future_transactions);
+		base_pool.set_sender_limit(options.sender_limit.clone());
+		let journal = options.journal_path.as_ref().and_then(|path| match JournalWriter::open(path) {
+			Ok(writer) => Some(LocalJournal { writer, hashes: Default::default() }),
+			Err(e) => {
+				warn!(
+					target: "txpool",
+					"Unable to open transaction pool journal at {}: {}",
+					path.display(),
+					e,
+				);
+				None
+			},
+		});
 		ValidatedPool {
 			api,
 			options,
@@ -85,6 +119,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 			pool: RwLock::new(base_pool),
 			import_notification_sinks: Default::default(),
 			rotator: Default::default(),
+			journal,
 		}
 	}
 
@@ -99,15 +134,16 @@ impl<B: ChainApi> ValidatedPool<B> {
 	}
 
 	/// Imports a bunch of pre-validated transactions to the pool.
-	pub fn submit<T>(&self, txs: T) -> Vec<Result<ExHash<B>, B::Error>> where
+	pub fn submit<T>(&self, txs: T, source: Source) -> Vec<Result<ExHash<B>, B::Error>> where
 		T: IntoIterator<Item=ValidatedTransactionFor<B>>
 	{
 		let results = txs.into_iter()
-			.map(|validated_tx| self.submit_one(validated_tx))
+			.map(|validated_tx| self.submit_one(validated_tx, source))
 			.collect::<Vec<_>>();
 
 		// only enforce limits if there is at least one imported transaction
 		let removed = if results.iter().any(|res| res.is_ok()) {
+			self.compact_journal();
 			self.enforce_limits()
 		} else {
 			Default::default()
@@ -120,11 +156,19 @@ impl<B: ChainApi> ValidatedPool<B> {
 	}
 
 	/// Submit single pre-validated transaction to the pool.
-	fn submit_one(&self, tx: ValidatedTransactionFor<B>) -> Result<ExHash<B>, B::Error> {
+	fn submit_one(&self, tx: ValidatedTransactionFor<B>, source: Source) -> Result<ExHash<B>, B::Error> {
 		match tx {
 			ValidatedTransaction::Valid(tx) => {
+				let journaled = match (&self.journal, source) {
+					(Some(_), Source::Local) => Some(tx.data.clone()),
+					_ => None,
+				};
 				let imported = self.pool.write().import(tx)?;
 
+				if source != Source::External {
+					self.record_in_journal(imported.hash(), journaled);
+				}
+
 				if let base::Imported::Ready { .. } = imported {
 					self.import_notification_sinks.lock().retain(|sink| sink.unbounded_send(()).is_ok());
 				}
@@ -144,6 +188,60 @@ impl<B: ChainApi> ValidatedPool<B> {
 		}
 	}
 
+	/// Records an imported local transaction in the journal.
+	///
+	/// `xt` is `None` if the transaction is already stored in the journal file.
+	fn record_in_journal(&self, hash: &ExHash<B>, xt: Option<ExtrinsicFor<B>>) {
+		if let Some(ref journal) = self.journal {
+			// keep the lock while appending, so that it's not lost by a concurrent rewrite
+			let mut hashes = journal.hashes.lock();
+			hashes.insert(hash.clone());
+			if let Some(xt) = xt {
+				journal.writer.append(xt);
+			}
+		}
+	}
+
+	/// Rewrites the journal with the local transactions that are still in the pool if it holds
+	/// too many stale entries.
+	fn compact_journal(&self) {
+		let journal = match self.journal {
+			Some(ref journal) => journal,
+			None => return,
+		};
+		let max_entries = 2 * (self.options.ready.count + self.options.future.count);
+		if journal.writer.entries() <= max_entries {
+			return;
+		}
+
+		let mut hashes = journal.hashes.lock();
+		let mut in_pool = HashSet::new();
+		let mut xts = Vec::new();
+		{
+			let pool = self.pool.read();
+			let txs = pool.ready()
+				.map(|tx| (tx.hash.clone(), tx.data.clone()))
+				.chain(pool.futures().map(|tx| (tx.hash.clone(), tx.data.clone())));
+			for (hash, xt) in txs {
+				if hashes.contains(&hash) {
+					in_pool.insert(hash);
+					xts.push(xt);
+				}
+			}
+		}
+		*hashes = in_pool;
+		journal.writer.rewrite(xts);
+	}
+
+	/// Returns extrinsics recorded in the journal before the pool was created.
+	///
+	/// The journaled extrinsics are only returned once.
+	pub fn take_journaled(&self) -> Vec<ExtrinsicFor<B>> {
+		self.journal.as_ref()
+			.map(|journal| journal.writer.take_pending())
+			.unwrap_or_default()
+	}
+
 	fn enforce_limits(&self) -> HashSet<ExHash<B>> {
 		let status = self.pool.read().status();
 		let ready_limit = &self.options.ready;
@@ -183,7 +281,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 			ValidatedTransaction::Valid(tx) => {
 				let hash = self.api.hash_and_length(&tx.data).0;
 				let watcher = self.listener.write().create_watcher(hash);
-				self.submit(std::iter::once(ValidatedTransaction::Valid(tx)))
+				self.submit(std::iter::once(ValidatedTransaction::Valid(tx)), Source::Local)
 					.pop()
 					.expect("One extrinsic passed; one result returned; qed")
 					.map(|_| watcher)
@@ -268,8 +366,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 										final_statuses.insert(tx.hash.clone(), Status::Dropped);
 									}
 								},
-								base::Imported::Future { .. } => {
+								base::Imported::Future { removed, .. } => {
 									final_statuses.insert(hash, Status::Future);
+									for tx in removed {
+										final_statuses.insert(tx.hash.clone(), Status::Dropped);
+									}
 								},
 							},
 							Err(err) => {
@@ -361,7 +462,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 		debug_assert_eq!(pruned_hashes.len(), pruned_xts.len());
 
 		// Resubmit pruned transactions
-		let results = self.submit(pruned_xts);
+		let results = self.submit(pruned_xts, Source::External);
 
 		// Collect the hashes of transactions that now became invalid (meaning that they are successfully pruned).
 		let hashes = results
@@ -397,6 +498,17 @@ impl<B: ChainApi> ValidatedPool<B> {
 		Ok(())
 	}
 
+	/// Notify all watchers that transactions in the block with hash have been finalized.
+	pub fn on_block_finalized(&self, block_hash: BlockHash<B>) {
+		debug!(target: "txpool", "Attempting to notify watchers of finalization for {:?}", block_hash);
+		self.listener.write().finalized(block_hash);
+	}
+
+	/// Notify all watchers that transactions in the block with hash have been retracted.
+	pub fn on_block_retracted(&self, block_hash: BlockHash<B>) {
+		self.listener.write().retracted(block_hash);
+	}
+
 	/// Removes stale transactions from the pool.
 	///
 	/// Stale transactions are transaction beyond their longevity period.
@@ -503,7 +615,7 @@ fn fire_events<H, H2, Ex>(
 	imported: &base::Imported<H, Ex>,
 ) where
 	H: hash::Hash + Eq + traits::Member + Serialize,
-	H2: Clone + fmt::Debug,
+	H2: hash::Hash + Eq + Clone + fmt::Debug,
 {
 	match *imported {
 		base::Imported::Ready { ref promoted, ref failed, ref removed, ref hash } => {
@@ -518,8 +630,11 @@ fn fire_events<H, H2, Ex>(
 				listener.ready(p, None);
 			}
 		},
-		base::Imported::Future { ref hash } => {
-			listener.future(hash)
+		base::Imported::Future { ref hash, ref removed } => {
+			listener.future(hash);
+			for r in removed {
+				listener.dropped(&r.hash, Some(hash));
+			}
 		},
 	}
 }
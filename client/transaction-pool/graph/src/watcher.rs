@@ -91,6 +91,23 @@ impl<H: Clone, H2: Clone> Sender<H, H2> {
 	/// Extrinsic has been included in block with given hash.
 	pub fn in_block(&mut self, hash: H2) {
 		self.send(TransactionStatus::InBlock(hash));
+	}
+
+	/// Extrinsic has been finalized by a finality gadget.
+	pub fn finalized(&mut self, hash: H2) {
+		self.send(TransactionStatus::Finalized(hash));
+		self.finalized = true;
+	}
+
+	/// The block this extrinsic was included in has been retracted.
+	pub fn retracted(&mut self, hash: H2) {
+		self.send(TransactionStatus::Retracted(hash));
+	}
+
+	/// The block this extrinsic was included in has not been finalized in time
+	/// and it's no longer tracked.
+	pub fn finality_timeout(&mut self, hash: H2) {
+		self.send(TransactionStatus::FinalityTimeout(hash));
 		self.finalized = true;
 	}
 
@@ -95,6 +95,14 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		Box::new(self.pool.submit_one(at, xt))
 	}
 
+	fn submit_local(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: TransactionFor<Self>,
+	) -> Box<dyn Future<Output=Result<TxHash<Self>, Self::Error>> + Send + Unpin> {
+		Box::new(self.pool.submit_local(at, xt))
+	}
+
 	fn submit_and_watch(
 		&self,
 		at: &BlockId<Self::Block>,
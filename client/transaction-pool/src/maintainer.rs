@@ -21,7 +21,7 @@ use std::{
 };
 use futures::{
 	Future, FutureExt,
-	future::{Either, join, ready},
+	future::{Either, ready},
 };
 use log::{warn, debug, trace};
 use parking_lot::Mutex;
@@ -78,6 +78,13 @@ where
 
 		let id = *id;
 		trace!(target: "txpool", "[{:?}] Starting pool maintainance", id);
+		// Notify watchers of transactions that were included in retracted blocks.
+		for hash in retracted {
+			self.pool.on_block_retracted(hash.clone());
+		}
+		// Put transactions recorded in the journal before restart back into the pool. This does
+		// nothing if the journal was already restored by an earlier maintenance.
+		let restore_future = restore_journal(&self.pool, &id);
 		// Put transactions from retracted blocks back into the pool.
 		let client_copy = self.client.clone();
 		let retracted_transactions = retracted.to_vec().into_iter()
@@ -95,6 +102,7 @@ where
 					"[{:?}] Error re-submitting transactions: {:?}", id, e
 				),
 			}));
+		let resubmit_future = restore_future.then(|_| resubmit_future);
 
 		// Avoid calling into runtime if there is nothing to prune from the pool anyway.
 		if self.pool.status().is_empty() {
@@ -138,6 +146,14 @@ where
 
 		Box::new(prune_future.then(|_| revalidate_future))
 	}
+
+	fn on_block_finalized(
+		&self,
+		id: &BlockId<Block>,
+	) -> Box<dyn Future<Output=()> + Send + Unpin> {
+		notify_finalized(&self.pool, id);
+		Box::new(ready(()))
+	}
 }
 
 /// Basic transaction pool maintainer for light clients.
@@ -272,38 +288,80 @@ where
 	fn maintain(
 		&self,
 		id: &BlockId<Block>,
-		_retracted: &[Block::Hash],
+		retracted: &[Block::Hash],
 	) -> Box<dyn Future<Output=()> + Send + Unpin> {
-		// Do nothing if transaction pool is empty.
+		// Notify watchers of transactions that were included in retracted blocks.
+		for hash in retracted {
+			self.pool.on_block_retracted(hash.clone());
+		}
+
+		// Put transactions recorded in the journal before restart back into the pool.
+		let restore_future = restore_journal(&self.pool, id);
+
+		// Do nothing else if transaction pool is empty.
 		if self.pool.status().is_empty() {
 			self.revalidation_status.lock().clear();
-			return Box::new(ready(()));
+			return Box::new(restore_future);
 		}
 		let header = self.client.header(*id)
 			.and_then(|h| h.ok_or(sp_blockchain::Error::UnknownBlock(format!("{}", id))));
 		let header = match header {
 			Ok(header) => header,
 			Err(err) => {
-				println!("Failed to maintain light tx pool: {:?}", err);
-				return Box::new(ready(()));
+				warn!(target: "txpool", "Failed to maintain light tx pool: {:?}", err);
+				return Box::new(restore_future);
 			}
 		};
 
-		// else prune block transactions from the pool
+		// else prune block transactions from the pool, notifying watchers about inclusion
 		let prune_future = self.prune(id, &header);
 
-		// and then (optionally) revalidate in-pool transactions
+		// and then (optionally) revalidate in-pool transactions. Revalidation has to happen
+		// after pruning, otherwise included transactions would be reported as invalid.
 		let revalidate_future = self.revalidate(id, &header);
 
-		let maintain_future = join(
-			prune_future,
-			revalidate_future,
-		).map(|_| ());
+		let maintain_future = prune_future.then(|_| revalidate_future);
+
+		Box::new(restore_future.then(|_| maintain_future))
+	}
 
-		Box::new(maintain_future)
+	fn on_block_finalized(
+		&self,
+		id: &BlockId<Block>,
+	) -> Box<dyn Future<Output=()> + Send + Unpin> {
+		notify_finalized(&self.pool, id);
+		Box::new(ready(()))
 	}
 }
 
+/// Notifies the pool watchers about finalization of given block.
+fn notify_finalized<PoolApi: ChainApi>(
+	pool: &sc_transaction_graph::Pool<PoolApi>,
+	id: &BlockId<PoolApi::Block>,
+) {
+	if let Err(e) = pool.on_block_finalized(id) {
+		warn!(target: "txpool", "[{:?}] Error notifying watchers about finality: {:?}", id, e);
+	}
+}
+
+/// Returns future that restores transactions recorded in the pool journal.
+fn restore_journal<PoolApi: ChainApi>(
+	pool: &sc_transaction_graph::Pool<PoolApi>,
+	id: &BlockId<PoolApi::Block>,
+) -> impl Future<Output=()> + Send + Unpin {
+	let id = *id;
+	pool.restore_journal(&id)
+		.then(move |restore_result| ready(match restore_result {
+			Ok(0) => (),
+			Ok(restored) => debug!(target: "txpool",
+				"[{:?}] Restored {} transactions from the journal", id, restored
+			),
+			Err(e) => warn!(target: "txpool",
+				"[{:?}] Error restoring transactions from the journal: {:?}", id, e
+			),
+		}))
+}
+
 /// The status of transactions revalidation at light tx pool.
 #[cfg_attr(test, derive(Debug))]
 enum TxPoolRevalidationStatus<N> {
@@ -355,10 +413,10 @@ mod tests {
 	use futures::executor::block_on;
 	use codec::Encode;
 	use substrate_test_runtime_client::{
-		prelude::*, Client, runtime::{Block, Transfer}, sp_consensus::{BlockOrigin, SelectChain},
+		prelude::*, Client, runtime::{Block, Extrinsic, Transfer}, sp_consensus::{BlockOrigin, SelectChain},
 		LongestChain,
 	};
-	use sp_transaction_pool::PoolStatus;
+	use sp_transaction_pool::{PoolStatus, TransactionStatus};
 	use crate::api::{FullChainApi, LightChainApi};
 
 	struct TestSetup<Api: ChainApi> {
@@ -482,6 +540,81 @@ mod tests {
 		assert_eq!(setup.pool.status().future, 0);
 	}
 
+	fn light_fetcher_including(
+		transaction: Extrinsic,
+	) -> Arc<substrate_test_runtime_client::LightFetcher> {
+		Arc::new(substrate_test_runtime_client::new_light_fetcher()
+			.with_remote_body(Some(Box::new(move |_| Ok(vec![transaction.clone()]))))
+			.with_remote_call(Some(Box::new(move |_| {
+				let validity: sp_runtime::transaction_validity::TransactionValidity =
+					Ok(sp_runtime::transaction_validity::ValidTransaction {
+						priority: 0,
+						requires: Vec::new(),
+						provides: vec![vec![42]],
+						longevity: 0,
+						propagate: true,
+					});
+				Ok(validity.encode())
+			}))))
+	}
+
+	#[test]
+	fn should_notify_finality_of_watched_transactions_at_light_pool() {
+		let transaction = Transfer {
+			amount: 5,
+			nonce: 0,
+			from: AccountKeyring::Alice.into(),
+			to: Default::default(),
+		}.into_signed_tx();
+		let fetcher = light_fetcher_including(transaction.clone());
+		let setup = setup_light(fetcher.clone());
+		let best = setup.longest_chain.best_chain().unwrap();
+		let maintainer = LightBasicPoolMaintainer::with_defaults(
+			setup.pool.clone(),
+			setup.client.clone(),
+			fetcher,
+		);
+
+		let watcher = block_on(setup.pool.submit_and_watch(&BlockId::hash(best.hash()), transaction))
+			.unwrap();
+		block_on(maintainer.maintain(&BlockId::hash(best.hash()), &[]));
+		block_on(maintainer.on_block_finalized(&BlockId::hash(best.hash())));
+
+		let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+		assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+		assert_eq!(stream.next(), Some(TransactionStatus::InBlock(best.hash())));
+		assert_eq!(stream.next(), Some(TransactionStatus::Finalized(best.hash())));
+		assert_eq!(stream.next(), None);
+	}
+
+	#[test]
+	fn should_notify_retraction_of_watched_transactions_at_light_pool() {
+		let transaction = Transfer {
+			amount: 5,
+			nonce: 0,
+			from: AccountKeyring::Alice.into(),
+			to: Default::default(),
+		}.into_signed_tx();
+		let fetcher = light_fetcher_including(transaction.clone());
+		let setup = setup_light(fetcher.clone());
+		let best = setup.longest_chain.best_chain().unwrap();
+		let maintainer = LightBasicPoolMaintainer::with_defaults(
+			setup.pool.clone(),
+			setup.client.clone(),
+			fetcher,
+		);
+
+		let watcher = block_on(setup.pool.submit_and_watch(&BlockId::hash(best.hash()), transaction))
+			.unwrap();
+		block_on(maintainer.maintain(&BlockId::hash(best.hash()), &[]));
+		block_on(maintainer.maintain(&BlockId::hash(best.hash()), &[best.hash()]));
+
+		let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+		assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+		assert_eq!(stream.next(), Some(TransactionStatus::InBlock(best.hash())));
+		assert_eq!(stream.next(), Some(TransactionStatus::Retracted(best.hash())));
+	}
+
 	#[test]
 	fn should_schedule_transactions_revalidation_at_light_pool() {
 		// when revalidation is not scheduled, it became scheduled
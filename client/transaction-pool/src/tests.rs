@@ -220,3 +220,34 @@ fn should_correctly_prune_transactions_providing_more_than_one_tag() {
 	assert_eq!(pool.status().ready, 0);
 	assert_eq!(pool.status().future, 2);
 }
+
+#[test]
+fn only_local_transactions_should_be_journaled() {
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("txpool_journal");
+	let options = || sc_transaction_graph::Options {
+		journal_path: Some(path.clone()),
+		..Default::default()
+	};
+	let journal_len = || std::fs::metadata(&path).unwrap().len() as usize;
+	let entry_len = uxt(Alice, 209).encode().encode().len();
+
+	{
+		let pool = BasicPool::new(options(), TestApi::default());
+		block_on(pool.submit_local(&BlockId::number(0), uxt(Alice, 209))).unwrap();
+		block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 210))).unwrap();
+		assert_eq!(pool.status().ready, 2);
+	}
+	assert_eq!(journal_len(), entry_len);
+
+	let pool = BasicPool::new(options(), TestApi::default());
+	assert_eq!(pool.status().ready, 0);
+	assert_eq!(block_on(pool.pool().restore_journal(&BlockId::number(0))).unwrap(), 1);
+	let pending: Vec<_> = pool.ready().map(|a| a.data.transfer().nonce).collect();
+	assert_eq!(pending, vec![209]);
+	assert_eq!(block_on(pool.pool().restore_journal(&BlockId::number(0))).unwrap(), 0);
+	drop(pool);
+
+	// restored transactions are not journaled again
+	assert_eq!(journal_len(), entry_len);
+}
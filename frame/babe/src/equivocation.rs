@@ -0,0 +1,213 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An opt-in utility module for reporting equivocations.
+//!
+//! This module defines an offence type for BABE equivocations
+//! and some utility traits to wire together:
+//! - a key ownership proof system (e.g. to prove that a given authority was
+//! part of a session);
+//! - a system for reporting offences;
+//! - a system for signing and submitting transactions;
+//!
+//! These can be used in an offchain context in order to submit equivocation
+//! reporting extrinsics (from the client that's importing BABE blocks).
+//! And in a runtime context, so that the BABE module can validate the
+//! equivocation proofs in the extrinsic and report the offences.
+
+use sp_std::prelude::*;
+
+use codec::Encode;
+use frame_support::debug;
+use frame_system::offchain::SubmitUnsignedTransaction;
+use sp_consensus_babe::{EquivocationProof, SlotNumber};
+use sp_runtime::{
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionValidity, ValidTransaction,
+	},
+	DispatchResult, Perbill,
+};
+use sp_staking::{
+	offence::{Kind, Offence, ReportOffence},
+	SessionIndex,
+};
+
+use super::{Call, Module, Trait};
+
+/// A trait with utility methods for handling equivocation reports in BABE.
+/// The key owner proof and offence types are generic, and the trait provides
+/// methods to report an offence (after it has been validated) and also submit
+/// an equivocation report extrinsic (useful only in offchain context).
+pub trait HandleEquivocation<T: Trait> {
+	/// Report an offence proved by the given reporters.
+	fn report_offence(
+		reporters: Vec<T::AccountId>,
+		offence: BabeEquivocationOffence<T::KeyOwnerIdentification>,
+	);
+
+	/// Create and dispatch an equivocation report extrinsic.
+	fn submit_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Header>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> DispatchResult;
+}
+
+impl<T: Trait> HandleEquivocation<T> for () {
+	fn report_offence(
+		_reporters: Vec<T::AccountId>,
+		_offence: BabeEquivocationOffence<T::KeyOwnerIdentification>,
+	) {}
+
+	fn submit_equivocation_report(
+		_equivocation_proof: EquivocationProof<T::Header>,
+		_key_owner_proof: T::KeyOwnerProof,
+	) -> DispatchResult {
+		Ok(())
+	}
+}
+
+/// Generic equivocation handler. This type implements `HandleEquivocation`
+/// using existing subsystems that are part of frame (type bounds described
+/// below) and will dispatch to them directly, it's only purpose is to wire all
+/// subsystems together.
+pub struct EquivocationHandler<I, R, S> {
+	_phantom: sp_std::marker::PhantomData<(I, R, S)>,
+}
+
+impl<I, R, S> Default for EquivocationHandler<I, R, S> {
+	fn default() -> Self {
+		Self {
+			_phantom: Default::default(),
+		}
+	}
+}
+
+impl<T, R, S> HandleEquivocation<T> for EquivocationHandler<T::KeyOwnerIdentification, R, S>
+where
+	T: Trait,
+	// A system for reporting offences after valid equivocation reports are
+	// processed.
+	R: ReportOffence<
+		T::AccountId,
+		T::KeyOwnerIdentification,
+		BabeEquivocationOffence<T::KeyOwnerIdentification>,
+	>,
+	// A transaction submitter. Used for submitting equivocation reports.
+	S: SubmitUnsignedTransaction<T, <T as Trait>::Call>,
+{
+	fn report_offence(
+		reporters: Vec<T::AccountId>,
+		offence: BabeEquivocationOffence<T::KeyOwnerIdentification>,
+	) {
+		R::report_offence(reporters, offence);
+	}
+
+	fn submit_equivocation_report(
+		equivocation_proof: EquivocationProof<T::Header>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> DispatchResult {
+		let call = Call::report_equivocation_unsigned(equivocation_proof, key_owner_proof);
+
+		S::submit_unsigned(call)
+			.map_err(|_| "Error submitting equivocation report.")?;
+
+		Ok(())
+	}
+}
+
+/// A `ValidateUnsigned` implementation that restricts calls to
+/// `report_equivocation_unsigned` to the ones containing a valid equivocation
+/// proof. The key ownership proof is only checked when the call is dispatched.
+#[allow(deprecated)]
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		if let Call::report_equivocation_unsigned(equivocation_proof, _) = call {
+			// validate the equivocation proof itself, this is cheap compared to
+			// checking the key ownership proof which is done on dispatch.
+			if !sp_consensus_babe::check_equivocation_proof(equivocation_proof.clone()) {
+				debug::warn!(
+					target: "babe",
+					"rejecting invalid equivocation report for slot {}",
+					equivocation_proof.slot_number,
+				);
+
+				return InvalidTransaction::BadProof.into();
+			}
+
+			Ok(ValidTransaction {
+				priority: TransactionPriority::max_value(),
+				requires: vec![],
+				// only one report for the same offender at the same slot.
+				provides: vec![(
+					equivocation_proof.offender.clone(),
+					equivocation_proof.slot_number,
+				).encode()],
+				// equivocation reports should be included as soon as possible.
+				longevity: 64_u64,
+				// only submitted by the local node.
+				propagate: false,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+}
+
+/// A BABE equivocation offence report.
+///
+/// When a validator released two or more blocks at the same slot.
+pub struct BabeEquivocationOffence<FullIdentification> {
+	/// A babe slot number in which this incident happened.
+	pub slot: SlotNumber,
+	/// The session index in which the incident happened.
+	pub session_index: SessionIndex,
+	/// The size of the validator set at the time of the offence.
+	pub validator_set_count: u32,
+	/// The authority that produced the equivocation.
+	pub offender: FullIdentification,
+}
+
+impl<FullIdentification: Clone> Offence<FullIdentification>
+	for BabeEquivocationOffence<FullIdentification>
+{
+	const ID: Kind = *b"babe:equivocatio";
+	type TimeSlot = SlotNumber;
+
+	fn offenders(&self) -> Vec<FullIdentification> {
+		vec![self.offender.clone()]
+	}
+
+	fn session_index(&self) -> SessionIndex {
+		self.session_index
+	}
+
+	fn validator_set_count(&self) -> u32 {
+		self.validator_set_count
+	}
+
+	fn time_slot(&self) -> Self::TimeSlot {
+		self.slot
+	}
+
+	fn slash_fraction(offenders_count: u32, validator_set_count: u32) -> Perbill {
+		// the formula is min((3k / n)^2, 1)
+		let x = Perbill::from_rational_approximation(3 * offenders_count, validator_set_count);
+		// _ ^ 2
+		x.square()
+	}
+}
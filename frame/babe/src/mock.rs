@@ -17,13 +17,18 @@
 //! Test utilities
 #![allow(dead_code, unused_imports)]
 
-use super::{Trait, Module, GenesisConfig};
-use sp_consensus_babe::AuthorityId;
+use std::cell::RefCell;
+use super::{Trait, Module, GenesisConfig, EquivocationHandler, BabeEquivocationOffence};
+use sp_consensus_babe::{AuthorityId, AuthorityPair, KEY_TYPE};
 use sp_runtime::{
-	traits::IdentityLookup, Perbill, testing::{Header, UintAuthorityId}, impl_opaque_keys,
+	traits::{IdentityLookup, ConvertInto, OnInitialize, OnFinalize}, Perbill, KeyTypeId,
+	testing::{Header, UintAuthorityId, TestXt}, impl_opaque_keys,
 };
+use sp_staking::{SessionIndex, offence::ReportOffence};
 use sp_version::RuntimeVersion;
-use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
+use frame_support::{
+	impl_outer_origin, parameter_types, weights::Weight, traits::KeyOwnerProofSystem,
+};
 use sp_io;
 use sp_core::{H256, Blake2Hasher};
 
@@ -70,7 +75,7 @@ impl frame_system::Trait for Test {
 
 impl_opaque_keys! {
 	pub struct MockSessionKeys {
-		pub dummy: UintAuthorityId,
+		pub babe_authority: Babe,
 	}
 }
 
@@ -78,14 +83,20 @@ impl pallet_session::Trait for Test {
 	type Event = ();
 	type ValidatorId = <Self as frame_system::Trait>::AccountId;
 	type ShouldEndSession = Babe;
-	type SessionHandler = (Babe,Babe,);
-	type OnSessionEnding = ();
-	type ValidatorIdOf = ();
+	type SessionHandler = (Babe,);
+	// the validator set never changes in these tests, the sessions only rotate.
+	type OnSessionEnding = pallet_session::historical::NoteHistoricalRoot<Test, ()>;
+	type ValidatorIdOf = ConvertInto;
 	type SelectInitialValidators = ();
 	type Keys = MockSessionKeys;
 	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
 }
 
+impl pallet_session::historical::Trait for Test {
+	type FullIdentification = u64;
+	type FullIdentificationOf = ConvertInto;
+}
+
 impl pallet_timestamp::Trait for Test {
 	type Moment = u64;
 	type OnTimestampSet = Babe;
@@ -96,6 +107,35 @@ impl Trait for Test {
 	type EpochDuration = EpochDuration;
 	type ExpectedBlockTime = ExpectedBlockTime;
 	type EpochChangeTrigger = crate::ExternalTrigger;
+	type Call = crate::Call<Test>;
+	type KeyOwnerProofSystem = Historical;
+	type KeyOwnerProof =
+		<Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(KeyTypeId, AuthorityId)>>::Proof;
+	type KeyOwnerIdentification =
+		<Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(KeyTypeId, AuthorityId)>>::IdentificationTuple;
+	type HandleEquivocation = EquivocationHandler<
+		Self::KeyOwnerIdentification,
+		OffenceHandler,
+		SubmitTransaction,
+	>;
+}
+
+/// An extrinsic type used for tests.
+pub type Extrinsic = TestXt<crate::Call<Test>, ()>;
+type SubmitTransaction = frame_system::offchain::TransactionSubmitter<(), crate::Call<Test>, Extrinsic>;
+type IdentificationTuple = (u64, u64);
+type Offence = BabeEquivocationOffence<IdentificationTuple>;
+
+thread_local! {
+	pub static OFFENCES: RefCell<Vec<(Vec<u64>, Offence)>> = RefCell::new(vec![]);
+}
+
+/// A mock offence report handler.
+pub struct OffenceHandler;
+impl ReportOffence<u64, IdentificationTuple, Offence> for OffenceHandler {
+	fn report_offence(reporters: Vec<u64>, offence: Offence) {
+		OFFENCES.with(|l| l.borrow_mut().push((reporters, offence)));
+	}
 }
 
 pub fn new_test_ext(authorities: Vec<DummyValidatorId>) -> sp_io::TestExternalities {
@@ -106,5 +146,39 @@ pub fn new_test_ext(authorities: Vec<DummyValidatorId>) -> sp_io::TestExternalit
 	t.into()
 }
 
+/// Build externalities where the BABE authorities are set up by the session module, the
+/// validator with id `i + 1` owning the key of `authorities[i]`.
+pub fn new_test_ext_with_session(authorities: &[AuthorityPair]) -> sp_io::TestExternalities {
+	use sp_core::Pair;
+
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	pallet_session::GenesisConfig::<Test> {
+		keys: authorities.iter().enumerate()
+			.map(|(i, pair)| (i as u64 + 1, MockSessionKeys { babe_authority: pair.public() }))
+			.collect(),
+	}.assimilate_storage(&mut t).unwrap();
+	t.into()
+}
+
+/// Rotate sessions, one per block, until `session_index` is the current session.
+pub fn start_session(session_index: SessionIndex) {
+	for i in Session::current_index()..session_index {
+		let now = i as u64 + 1;
+		System::set_block_number(now);
+		Babe::on_initialize(now);
+		Session::rotate_session();
+		Babe::on_finalize(now);
+	}
+
+	assert_eq!(Session::current_index(), session_index);
+}
+
+/// Prove that `authority` is part of the current validator set.
+pub fn key_owner_proof(authority: &AuthorityId) -> pallet_session::historical::Proof {
+	Historical::prove((KEY_TYPE, authority)).unwrap()
+}
+
 pub type System = frame_system::Module<Test>;
+pub type Session = pallet_session::Module<Test>;
+pub type Historical = pallet_session::historical::Module<Test>;
 pub type Babe = Module<Test>;
@@ -17,8 +17,12 @@
 //! Consensus extension module tests for BABE consensus.
 
 use super::*;
-use mock::{new_test_ext, Babe, Test};
-use sp_runtime::{traits::OnFinalize, testing::{Digest, DigestItem}};
+use mock::{
+	new_test_ext, new_test_ext_with_session, start_session, key_owner_proof, Babe, Origin, Test,
+	OFFENCES,
+};
+use frame_support::{assert_ok, assert_noop};
+use sp_runtime::{traits::{Header as _, OnFinalize}, testing::{Digest, DigestItem, Header}};
 use pallet_session::ShouldEndSession;
 
 const EMPTY_RANDOMNESS: [u8; 32] = [
@@ -130,3 +134,197 @@ fn authority_index() {
 			"Trivially invalid authorities are ignored")
 	})
 }
+
+fn make_sealed_header(
+	pair: &sp_consensus_babe::AuthorityPair,
+	number: u64,
+	slot_number: sp_consensus_babe::SlotNumber,
+) -> Header {
+	use sp_core::Pair;
+
+	let pre_digest = make_pre_digest(0, slot_number, [1; 32], [0xff; 64]);
+	let mut header = Header::new(
+		number,
+		Default::default(),
+		Default::default(),
+		Default::default(),
+		pre_digest,
+	);
+
+	let seal = pair.sign(header.hash().as_ref());
+	header.digest_mut().push(DigestItem::Seal(BABE_ENGINE_ID, seal.encode()));
+
+	header
+}
+
+#[test]
+fn check_equivocation_proof_works() {
+	use sp_core::Pair;
+	use sp_consensus_babe::{check_equivocation_proof, AuthorityPair};
+
+	let pair = AuthorityPair::from_seed(&[1; 32]);
+	let other_pair = AuthorityPair::from_seed(&[2; 32]);
+
+	let proof = |first_header, second_header| EquivocationProof {
+		offender: pair.public(),
+		slot_number: 42,
+		first_header,
+		second_header,
+	};
+
+	// two different headers at the same slot signed by the same authority.
+	assert!(check_equivocation_proof(proof(
+		make_sealed_header(&pair, 1, 42),
+		make_sealed_header(&pair, 2, 42),
+	)));
+
+	// the same header twice is not an equivocation.
+	assert!(!check_equivocation_proof(proof(
+		make_sealed_header(&pair, 1, 42),
+		make_sealed_header(&pair, 1, 42),
+	)));
+
+	// headers at different slots.
+	assert!(!check_equivocation_proof(proof(
+		make_sealed_header(&pair, 1, 42),
+		make_sealed_header(&pair, 2, 43),
+	)));
+
+	// the slot in the proof doesn't match the slot of the headers.
+	assert!(!check_equivocation_proof(proof(
+		make_sealed_header(&pair, 1, 43),
+		make_sealed_header(&pair, 2, 43),
+	)));
+
+	// one of the headers is signed by a different authority.
+	assert!(!check_equivocation_proof(proof(
+		make_sealed_header(&pair, 1, 42),
+		make_sealed_header(&other_pair, 2, 42),
+	)));
+}
+
+fn authority_pairs() -> Vec<sp_consensus_babe::AuthorityPair> {
+	use sp_core::Pair;
+
+	(1..=3).map(|i| sp_consensus_babe::AuthorityPair::from_seed(&[i; 32])).collect()
+}
+
+fn equivocation_proof(
+	pair: &sp_consensus_babe::AuthorityPair,
+	slot_number: sp_consensus_babe::SlotNumber,
+) -> EquivocationProof<Header> {
+	use sp_core::Pair;
+
+	EquivocationProof {
+		offender: pair.public(),
+		slot_number,
+		first_header: make_sealed_header(pair, 1, slot_number),
+		second_header: make_sealed_header(pair, 2, slot_number),
+	}
+}
+
+#[test]
+fn report_equivocation_reports_offence() {
+	use sp_core::Pair;
+
+	let authorities = authority_pairs();
+	new_test_ext_with_session(&authorities).execute_with(|| {
+		let offender = &authorities[0];
+		let key_owner_proof = key_owner_proof(&offender.public());
+
+		// the proof is checked against the historical root of a past session.
+		start_session(1);
+
+		// slot 1 is in the first epoch, which is coupled to session 0.
+		let proof = equivocation_proof(offender, 1);
+		assert_ok!(Babe::report_equivocation(Origin::signed(2), proof, key_owner_proof));
+
+		OFFENCES.with(|l| {
+			let offences = l.borrow();
+			assert_eq!(offences.len(), 1);
+
+			let (reporters, offence) = &offences[0];
+			assert_eq!(reporters, &vec![2]);
+			assert_eq!(offence.offender, (1, 1));
+			assert_eq!(offence.slot, 1);
+			assert_eq!(offence.session_index, 0);
+			assert_eq!(offence.validator_set_count, 3);
+		});
+	});
+}
+
+#[test]
+fn report_equivocation_rejects_invalid_key_owner_proof() {
+	use sp_core::Pair;
+
+	let authorities = authority_pairs();
+	new_test_ext_with_session(&authorities).execute_with(|| {
+		let offender = &authorities[0];
+		// a valid proof, but for the key of another authority.
+		let other_key_owner_proof = key_owner_proof(&authorities[1].public());
+		let key_owner_proof = key_owner_proof(&offender.public());
+
+		start_session(1);
+
+		assert_noop!(
+			Babe::report_equivocation(
+				Origin::signed(2),
+				equivocation_proof(offender, 1),
+				other_key_owner_proof,
+			),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+
+		// the proof is for session 0, but slot 4 is in the second epoch.
+		assert_noop!(
+			Babe::report_equivocation(
+				Origin::signed(2),
+				equivocation_proof(offender, 4),
+				key_owner_proof,
+			),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+
+		assert!(OFFENCES.with(|l| l.borrow().is_empty()));
+	});
+}
+
+#[test]
+fn unsigned_equivocation_report_is_validated() {
+	use sp_core::Pair;
+	use sp_runtime::transaction_validity::InvalidTransaction;
+
+	let authorities = authority_pairs();
+	new_test_ext_with_session(&authorities).execute_with(|| {
+		let offender = &authorities[0];
+		let key_owner_proof = key_owner_proof(&offender.public());
+		#[allow(deprecated)]
+		let validate = |call: Call<Test>| <Babe as frame_support::unsigned::ValidateUnsigned>::validate_unsigned(&call);
+
+		let validity = validate(Call::report_equivocation_unsigned(
+			equivocation_proof(offender, 1),
+			key_owner_proof.clone(),
+		)).unwrap();
+		assert_eq!(validity.provides, vec![(offender.public(), 1u64).encode()]);
+		assert!(!validity.propagate);
+
+		// the same header twice is not an equivocation.
+		let header = make_sealed_header(offender, 1, 1);
+		let invalid_proof = EquivocationProof {
+			offender: offender.public(),
+			slot_number: 1,
+			first_header: header.clone(),
+			second_header: header,
+		};
+		assert_eq!(
+			validate(Call::report_equivocation_unsigned(invalid_proof, key_owner_proof.clone())),
+			InvalidTransaction::BadProof.into(),
+		);
+
+		// only unsigned reports are accepted.
+		assert_eq!(
+			validate(Call::report_equivocation(equivocation_proof(offender, 1), key_owner_proof)),
+			InvalidTransaction::Call.into(),
+		);
+	});
+}
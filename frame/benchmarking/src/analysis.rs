@@ -0,0 +1,168 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tools for analysing the benchmark results.
+
+use std::collections::BTreeMap;
+use crate::BenchmarkResults;
+
+/// A linear model of the extrinsic execution time, built from benchmark results.
+///
+/// The model is `base + sum(slope_i * component_i)`, all values in nanoseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+	/// Execution time that is independent of all components.
+	pub base: u128,
+	/// Execution time added per unit of each component.
+	pub slopes: Vec<u128>,
+	/// Names of the components, in the same order as `slopes`.
+	pub names: Vec<String>,
+}
+
+impl Analysis {
+	/// Fit a linear regression per component on the median execution time of each of its values.
+	///
+	/// Only samples where all other components are at their highest value are taken into account
+	/// for a component, which is how the `benchmarks!` macro varies the components.
+	pub fn median_slopes(r: &[BenchmarkResults]) -> Option<Self> {
+		let first = r.first()?;
+		let names: Vec<String> = first.0.iter().map(|(p, _)| p.to_string()).collect();
+
+		if names.is_empty() {
+			let mut times: Vec<u128> = r.iter().map(|result| result.1).collect();
+			return Some(Self { base: median(&mut times), slopes: Vec::new(), names })
+		}
+
+		let maxima: Vec<u32> = (0..names.len())
+			.map(|i| r.iter().map(|result| result.0[i].1).max().unwrap_or_default())
+			.collect();
+
+		let fits = (0..names.len()).map(|i| {
+			let mut samples = BTreeMap::<u32, Vec<u128>>::new();
+			for (components, time, _) in r {
+				let others_at_max = components.iter()
+					.enumerate()
+					.all(|(j, (_, value))| j == i || *value == maxima[j]);
+				if others_at_max {
+					samples.entry(components[i].1).or_default().push(*time);
+				}
+			}
+
+			let points: Vec<(f64, f64)> = samples.into_iter()
+				.map(|(x, mut times)| (x as f64, median(&mut times) as f64))
+				.collect();
+
+			linear_regression(&points)
+		}).collect::<Option<Vec<(f64, f64)>>>()?;
+
+		let slopes: Vec<f64> = fits.iter().map(|(_, slope)| slope.max(0.0)).collect();
+
+		// Every intercept contains the contribution of the other components at their maximum.
+		let base = fits.iter()
+			.enumerate()
+			.map(|(i, (intercept, _))| {
+				let others: f64 = slopes.iter()
+					.enumerate()
+					.filter(|(j, _)| *j != i)
+					.map(|(j, slope)| slope * maxima[j] as f64)
+					.sum();
+				intercept - others
+			})
+			.sum::<f64>() / fits.len() as f64;
+
+		Some(Self {
+			base: base.max(0.0).round() as u128,
+			slopes: slopes.into_iter().map(|slope| slope.round() as u128).collect(),
+			names,
+		})
+	}
+}
+
+impl std::fmt::Display for Analysis {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "Model:")?;
+		writeln!(f, "Time ~= {:>8}", self.base)?;
+		for (name, slope) in self.names.iter().zip(self.slopes.iter()) {
+			writeln!(f, "    + {} {:>8}", name, slope)?;
+		}
+		write!(f, "              ns")
+	}
+}
+
+fn median(values: &mut Vec<u128>) -> u128 {
+	values.sort();
+	values.get(values.len() / 2).cloned().unwrap_or_default()
+}
+
+/// Least squares fit of `y = intercept + slope * x`, returns `(intercept, slope)`.
+fn linear_regression(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+	if points.is_empty() {
+		return None
+	}
+
+	let n = points.len() as f64;
+	let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+	let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+	let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+	if variance == 0.0 {
+		return Some((mean_y, 0.0))
+	}
+
+	let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+	let slope = covariance / variance;
+
+	Some((mean_y - slope * mean_x, slope))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::BenchmarkParameter;
+
+	fn result(a: u32, b: u32, time: u128) -> BenchmarkResults {
+		(vec![(BenchmarkParameter::a, a), (BenchmarkParameter::b, b)], time, 0)
+	}
+
+	#[test]
+	fn median_slopes_should_work() {
+		// time = 10 + 3a + 7b
+		let time = |a: u128, b: u128| 10 + 3 * a + 7 * b;
+		let mut results = Vec::new();
+		for a in 1..=5 {
+			results.push(result(a, 5, time(a as u128, 5)));
+		}
+		for b in 1..=5 {
+			results.push(result(5, b, time(5, b as u128)));
+		}
+
+		let analysis = Analysis::median_slopes(&results).unwrap();
+
+		assert_eq!(analysis.base, 10);
+		assert_eq!(analysis.slopes, vec![3, 7]);
+		assert_eq!(analysis.names, vec!["a".to_string(), "b".to_string()]);
+	}
+
+	#[test]
+	fn median_slopes_without_components_should_work() {
+		let results = vec![(vec![], 5, 0), (vec![], 100, 0), (vec![], 7, 0)];
+
+		let analysis = Analysis::median_slopes(&results).unwrap();
+
+		assert_eq!(analysis.base, 7);
+		assert!(analysis.slopes.is_empty());
+	}
+}
@@ -0,0 +1,197 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Macro for benchmarking a FRAME runtime.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod utils;
+#[cfg(feature = "std")]
+mod analysis;
+
+pub use utils::*;
+#[cfg(feature = "std")]
+pub use analysis::Analysis;
+#[doc(hidden)]
+pub use sp_io::storage::root as storage_root;
+#[doc(hidden)]
+pub use sp_runtime::traits::Dispatchable;
+#[doc(hidden)]
+pub use sp_std;
+
+/// Construct pallet benchmarks for weighing dispatchables.
+///
+/// Works around the idea of complexity parameters, named by a single lowercase letter, that
+/// are used to vary the state in which a dispatchable is called. Each parameter has a range
+/// of values that will be iterated over when benchmarking.
+///
+/// Each benchmark is declared as the name of the benchmark followed by its parameters and
+/// their ranges, a setup block and the call to dispatch with its origin as the first argument.
+/// Bindings introduced in the setup block, as well as the parameters themselves, can be used
+/// in the call.
+///
+/// The macro must be used inside of the pallet (usually in a `benchmarking` module), since it
+/// relies on `Trait`, `Call` and `Module` being in scope. It implements `Benchmarking` for the
+/// pallet's `Module`.
+///
+/// Example:
+/// ```ignore
+/// benchmarks! {
+/// 	set_identity(r in 1 .. MAX_REGISTRARS, x in 1 .. MAX_EXTRA_FIELDS) {
+/// 		add_registrars::<T>(r)?;
+/// 		let caller = account::<T::AccountId>("caller", 0, SEED);
+/// 		let info = create_identity_info::<T>(x);
+/// 	}: set_identity(RawOrigin::Signed(caller), info)
+///
+/// 	kill_identity(r in 1 .. MAX_REGISTRARS) {
+/// 		let caller = account::<T::AccountId>("caller", 0, SEED);
+/// 		// ...
+/// 	}: kill_identity(RawOrigin::Root, caller_lookup)
+/// }
+/// ```
+///
+/// Every parameter is iterated over its range in `steps` steps, while the other parameters
+/// are kept at their highest value. Each resulting set of parameters is executed `repeat`
+/// times and the state is reset to genesis between executions.
+#[macro_export]
+macro_rules! benchmarks {
+	(
+		$(
+			$name:ident ( $( $param:ident in $low:tt .. $high:tt ),* )
+			{ $( $setup:tt )* }
+			: $dispatch:ident ( $origin:expr $( , $arg:expr )* )
+		)*
+	) => {
+		#[allow(non_camel_case_types)]
+		enum SelectedBenchmark {
+			$( $name, )*
+		}
+
+		impl<T: Trait> $crate::BenchmarkingSetup<T, Call<T>, frame_system::RawOrigin<T::AccountId>>
+			for SelectedBenchmark
+		{
+			fn components(&self) -> $crate::sp_std::vec::Vec<($crate::BenchmarkParameter, u32, u32)> {
+				match self {
+					$(
+						Self::$name => $crate::sp_std::vec![
+							$( ($crate::BenchmarkParameter::$param, $low, $high), )*
+						],
+					)*
+				}
+			}
+
+			#[allow(unused_variables)]
+			fn instance(&self, components: &[($crate::BenchmarkParameter, u32)])
+				-> Result<(Call<T>, frame_system::RawOrigin<T::AccountId>), &'static str>
+			{
+				match self {
+					$(
+						Self::$name => {
+							$(
+								let $param = components.iter()
+									.find(|&c| c.0 == $crate::BenchmarkParameter::$param)
+									.ok_or("Could not find component during benchmark preparation.")?
+									.1;
+							)*
+							$( $setup )*
+							Ok((Call::<T>::$dispatch($( $arg ),*), $origin))
+						}
+					)*
+				}
+			}
+		}
+
+		impl<T: Trait> $crate::Benchmarking<$crate::BenchmarkResults> for Module<T> {
+			fn run_benchmark(extrinsic: $crate::sp_std::vec::Vec<u8>, steps: u32, repeat: u32)
+				-> Result<$crate::sp_std::vec::Vec<$crate::BenchmarkResults>, &'static str>
+			{
+				let extrinsic = $crate::sp_std::str::from_utf8(extrinsic.as_slice())
+					.map_err(|_| "Invalid benchmark name.")?;
+				let selected_benchmark = match extrinsic {
+					$( stringify!($name) => SelectedBenchmark::$name, )*
+					_ => return Err("Could not find extrinsic."),
+				};
+
+				let components = <SelectedBenchmark as $crate::BenchmarkingSetup<
+					T,
+					Call<T>,
+					frame_system::RawOrigin<T::AccountId>,
+				>>::components(&selected_benchmark);
+
+				// Every component is varied on its own, while the others stay at their maximum.
+				let mut component_sets = $crate::sp_std::vec::Vec::new();
+				if components.is_empty() {
+					component_sets.push($crate::sp_std::vec::Vec::new());
+				}
+				for (idx, (_, low, high)) in components.iter().enumerate() {
+					let diff = high.saturating_sub(*low);
+					let step_size = (diff / steps.max(1)).max(1);
+					let num_of_steps = diff / step_size + 1;
+
+					for s in 0..num_of_steps {
+						let component_value = low + step_size * s;
+						let c: $crate::sp_std::vec::Vec<($crate::BenchmarkParameter, u32)> = components.iter()
+							.enumerate()
+							.map(|(j, (n, _, h))| if j == idx { (*n, component_value) } else { (*n, *h) })
+							.collect();
+						component_sets.push(c);
+					}
+				}
+
+				// Commit the externalities to the database, flushing the DB cache.
+				// This will enable worst case scenario for reading from the database.
+				$crate::benchmarking::commit_db().map_err(|_| "Failed to commit the state.")?;
+
+				let mut results: $crate::sp_std::vec::Vec<$crate::BenchmarkResults> =
+					$crate::sp_std::vec::Vec::new();
+
+				for c in component_sets {
+					for _ in 0..repeat {
+						// Set up the externalities environment for the setup we want to benchmark.
+						let (call, caller) = <SelectedBenchmark as $crate::BenchmarkingSetup<
+							T,
+							Call<T>,
+							frame_system::RawOrigin<T::AccountId>,
+						>>::instance(&selected_benchmark, &c)?;
+
+						// Make sure the setup itself is not part of the measurement.
+						$crate::benchmarking::commit_db().map_err(|_| "Failed to commit the state.")?;
+
+						let start_extrinsic = $crate::benchmarking::current_time();
+						<Call<T> as $crate::Dispatchable>::dispatch(call, caller.into())
+							.map_err(Into::<&'static str>::into)?;
+						let finish_extrinsic = $crate::benchmarking::current_time();
+
+						let start_storage_root = $crate::benchmarking::current_time();
+						$crate::storage_root();
+						let finish_storage_root = $crate::benchmarking::current_time();
+
+						results.push((
+							c.clone(),
+							finish_extrinsic.saturating_sub(start_extrinsic) as u128,
+							finish_storage_root.saturating_sub(start_storage_root) as u128,
+						));
+
+						// Wipe the DB back to the genesis state.
+						$crate::benchmarking::wipe_db().map_err(|_| "Failed to wipe the state.")?;
+					}
+				}
+
+				Ok(results)
+			}
+		}
+	}
+}
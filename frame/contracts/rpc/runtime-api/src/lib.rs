@@ -24,7 +24,7 @@
 
 use sp_std::vec::Vec;
 use codec::{Encode, Decode, Codec};
-use sp_runtime::RuntimeDebug;
+use sp_runtime::{RuntimeDebug, traits::{Block as BlockT, NumberFor}};
 
 /// A result of execution of a contract.
 #[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
@@ -44,22 +44,134 @@ pub enum ContractExecResult {
 	Error,
 }
 
+/// A result of a dry-run call of a contract along with its execution trace.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct ContractCallResult<AccountId, Balance, Hash> {
+	/// The result of the call.
+	pub result: ContractExecResult,
+	/// The execution trace of the call.
+	///
+	/// `None` if the call couldn't be started, e.g. because the gas couldn't be bought.
+	pub trace: Option<CallTrace<AccountId, Balance, Hash>>,
+}
+
+/// The kind of a frame of an execution trace.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum CallKind {
+	/// A call to an existing account.
+	Call,
+	/// An instantiation of a new contract.
+	Instantiate,
+}
+
+/// An event deposited by a contract.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct TracedEvent<Hash> {
+	/// The topics of the event.
+	pub topics: Vec<Hash>,
+	/// The data of the event.
+	pub data: Vec<u8>,
+}
+
+/// The outcome of a frame of an execution trace.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum FrameOutcome {
+	/// The execution ran to completion.
+	Returned {
+		/// Status code returned by the contract.
+		status: u8,
+		/// Output data returned by the contract.
+		data: Vec<u8>,
+	},
+	/// The execution trapped or failed otherwise.
+	Trapped {
+		/// The reason of the failure.
+		reason: Vec<u8>,
+	},
+}
+
+/// A frame of an execution trace: a single call or instantiation.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct CallTrace<AccountId, Balance, Hash> {
+	/// Whether this frame is a call or an instantiation.
+	pub kind: CallKind,
+	/// The account which initiated the frame.
+	pub caller: AccountId,
+	/// The account which is called or instantiated.
+	pub dest: AccountId,
+	/// The value transferred to `dest`.
+	pub value: Balance,
+	/// The gas used by the frame, including the gas used by the nested frames.
+	pub gas_used: u64,
+	/// The events deposited by the contract.
+	pub events: Vec<TracedEvent<Hash>>,
+	/// The debug messages printed by the contract.
+	pub debug_messages: Vec<Vec<u8>>,
+	/// The outcome of the execution.
+	pub outcome: FrameOutcome,
+	/// The calls and instantiations performed by the contract, in order of execution.
+	pub nested: Vec<CallTrace<AccountId, Balance, Hash>>,
+}
+
+/// A result of a dry-run instantiation of a contract.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum ContractInstantiateResult<AccountId> {
+	/// The constructor of the contract returned successfully.
+	///
+	/// There is a status code and, optionally, some data returned by the constructor.
+	Success {
+		/// The address the contract would be instantiated at.
+		address: AccountId,
+		/// Status code returned by the constructor.
+		status: u8,
+		/// Output data returned by the constructor.
+		///
+		/// Can be empty.
+		data: Vec<u8>,
+		/// The amount of gas that was consumed by the instantiation.
+		gas_consumed: u64,
+	},
+	/// The instantiation either trapped or returned an error.
+	Error {
+		/// The amount of gas that was consumed until the instantiation failed.
+		gas_consumed: u64,
+	},
+}
+
 /// A result type of the get storage call.
 ///
 /// See [`ContractsApi::get_storage`] for more info.
 pub type GetStorageResult = Result<Option<Vec<u8>>, GetStorageError>;
 
-/// The possible errors that can happen querying the storage of a contract.
+/// A result type of the rent projection call.
+///
+/// See [`ContractsApi::rent_projection`] for more info.
+pub type RentProjectionResult<BlockNumber> = Result<RentProjection<BlockNumber>, GetStorageError>;
+
+/// The possible errors that can happen when accessing a contract by its address, i.e. when
+/// querying its storage or its rent projection.
 #[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
 pub enum GetStorageError {
 	/// The given address doesn't point on a contract.
 	ContractDoesntExist,
-	/// The specified contract is a tombstone and thus cannot have any storage.
+	/// The specified contract is a tombstone and thus cannot be accessed.
 	IsTombstone,
 }
 
+/// The projected eviction of a contract.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum RentProjection<BlockNumber> {
+	/// Eviction is projected to happen at the specified block number.
+	EvictionAt(BlockNumber),
+	/// No eviction is scheduled.
+	///
+	/// E.g. the contract has enough balance to offset the rent it would have to pay.
+	NoEviction,
+}
+
 sp_api::decl_runtime_apis! {
 	/// The API to interact with contracts without using executive.
+	#[api_version(3)]
 	pub trait ContractsApi<AccountId, Balance> where
 		AccountId: Codec,
 		Balance: Codec,
@@ -75,6 +187,34 @@ sp_api::decl_runtime_apis! {
 			input_data: Vec<u8>,
 		) -> ContractExecResult;
 
+		/// Perform a call from a specified account to a given contract and record its execution
+		/// trace.
+		///
+		/// Along with the result, the execution trace of the call is returned. It contains the
+		/// nested calls and instantiations, the gas used by each of them, and the events and
+		/// debug messages of the contracts. Available since version 3 of the API.
+		///
+		/// See the contracts' `call` dispatchable function for more details.
+		fn call_with_trace(
+			origin: AccountId,
+			dest: AccountId,
+			value: Balance,
+			gas_limit: u64,
+			input_data: Vec<u8>,
+		) -> ContractCallResult<AccountId, Balance, <Block as BlockT>::Hash>;
+
+		/// Instantiate a contract from the code with the given hash without committing any
+		/// changes.
+		///
+		/// See the contracts' `instantiate` dispatchable function for more details.
+		fn instantiate(
+			origin: AccountId,
+			endowment: Balance,
+			gas_limit: u64,
+			code_hash: <Block as BlockT>::Hash,
+			data: Vec<u8>,
+		) -> ContractInstantiateResult<AccountId>;
+
 		/// Query a given storage key in a given contract.
 		///
 		/// Returns `Ok(Some(Vec<u8>))` if the storage value exists under the given key in the
@@ -85,5 +225,13 @@ sp_api::decl_runtime_apis! {
 			address: AccountId,
 			key: [u8; 32],
 		) -> GetStorageResult;
+
+		/// Returns the projected time a given contract will be able to sustain paying its rent.
+		///
+		/// The returned projection is relevant for the current block, i.e. it is as if the contract
+		/// was accessed at the current block.
+		///
+		/// Returns `Err` if the contract is in a tombstone state or doesn't exist.
+		fn rent_projection(address: AccountId) -> RentProjectionResult<NumberFor<Block>>;
 	}
 }
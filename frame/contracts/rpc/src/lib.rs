@@ -25,12 +25,14 @@ use jsonrpc_derive::rpc;
 use sp_core::{H256, Bytes};
 use sp_rpc::number;
 use serde::{Deserialize, Serialize};
-use sp_runtime::{generic::BlockId, traits::Block as BlockT};
-use sp_api::ProvideRuntimeApi;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, Header as HeaderT}};
+use sp_api::{ApiExt, ApiErrorFor, ProvideRuntimeApi};
 
 pub use self::gen_client::Client as ContractsClient;
 pub use pallet_contracts_rpc_runtime_api::{
-	self as runtime_api, ContractExecResult, ContractsApi as ContractsRuntimeApi, GetStorageResult,
+	self as runtime_api, CallKind, CallTrace, ContractCallResult, ContractExecResult,
+	ContractInstantiateResult, ContractsApi as ContractsRuntimeApi, FrameOutcome,
+	GetStorageResult, RentProjection,
 };
 
 const RUNTIME_ERROR: i64 = 1;
@@ -59,7 +61,7 @@ impl From<GetStorageError> for Error {
 			},
 			IsTombstone => Error {
 				code: ErrorCode::ServerError(CONTRACT_IS_A_TOMBSTONE),
-				message: "The contract is a tombstone and doesn't have any storage.".into(),
+				message: "The contract is a tombstone and can't be accessed.".into(),
 				data: None,
 			}
 		}
@@ -78,6 +80,18 @@ pub struct CallRequest<AccountId, Balance> {
 	input_data: Bytes,
 }
 
+/// A struct that encodes RPC parameters required to instantiate a new smart-contract.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct InstantiateRequest<AccountId, Balance, Hash> {
+	origin: AccountId,
+	endowment: Balance,
+	gas_limit: number::NumberOrHex<u64>,
+	code_hash: Hash,
+	data: Bytes,
+}
+
 /// An RPC serializable result of contract execution
 #[derive(Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -107,9 +121,176 @@ impl From<ContractExecResult> for RpcContractExecResult {
 	}
 }
 
+/// An RPC serializable result of a contract call along with its execution trace.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcContractCallResult<AccountId, Balance, Hash> {
+	/// Result of the call
+	pub result: RpcContractExecResult,
+	/// Execution trace of the call
+	///
+	/// `None` if the call couldn't be started or the runtime doesn't support tracing.
+	pub trace: Option<RpcCallTrace<AccountId, Balance, Hash>>,
+}
+
+impl<AccountId, Balance, Hash> From<ContractCallResult<AccountId, Balance, Hash>>
+	for RpcContractCallResult<AccountId, Balance, Hash>
+{
+	fn from(r: ContractCallResult<AccountId, Balance, Hash>) -> Self {
+		RpcContractCallResult {
+			result: r.result.into(),
+			trace: r.trace.map(Into::into),
+		}
+	}
+}
+
+/// An RPC serializable kind of a frame of an execution trace.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcCallKind {
+	/// Call to an existing account
+	Call,
+	/// Instantiation of a new contract
+	Instantiate,
+}
+
+/// An RPC serializable event deposited by a contract.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RpcTracedEvent<Hash> {
+	/// Topics of the event
+	pub topics: Vec<Hash>,
+	/// Data of the event
+	pub data: Bytes,
+}
+
+/// An RPC serializable outcome of a frame of an execution trace.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcFrameOutcome {
+	/// Execution ran to completion
+	Returned {
+		/// Status code
+		status: u8,
+		/// Output data
+		data: Bytes,
+	},
+	/// Execution trapped
+	Trapped {
+		/// Reason of the failure
+		reason: String,
+	},
+}
+
+/// An RPC serializable frame of an execution trace.
+///
+/// Debug messages and trap reasons are converted into strings, replacing invalid UTF-8.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcCallTrace<AccountId, Balance, Hash> {
+	/// Whether the frame is a call or an instantiation
+	pub kind: RpcCallKind,
+	/// Account which initiated the frame
+	pub caller: AccountId,
+	/// Account which is called or instantiated
+	pub dest: AccountId,
+	/// Value transferred to `dest`
+	pub value: Balance,
+	/// Amount of gas used, including the nested frames
+	pub gas_used: u64,
+	/// Events deposited by the contract
+	pub events: Vec<RpcTracedEvent<Hash>>,
+	/// Debug messages printed by the contract
+	pub debug_messages: Vec<String>,
+	/// Outcome of the execution
+	pub outcome: RpcFrameOutcome,
+	/// Nested calls and instantiations in order of execution
+	pub nested: Vec<RpcCallTrace<AccountId, Balance, Hash>>,
+}
+
+impl<AccountId, Balance, Hash> From<CallTrace<AccountId, Balance, Hash>>
+	for RpcCallTrace<AccountId, Balance, Hash>
+{
+	fn from(t: CallTrace<AccountId, Balance, Hash>) -> Self {
+		RpcCallTrace {
+			kind: match t.kind {
+				CallKind::Call => RpcCallKind::Call,
+				CallKind::Instantiate => RpcCallKind::Instantiate,
+			},
+			caller: t.caller,
+			dest: t.dest,
+			value: t.value,
+			gas_used: t.gas_used,
+			events: t.events
+				.into_iter()
+				.map(|event| RpcTracedEvent { topics: event.topics, data: event.data.into() })
+				.collect(),
+			debug_messages: t.debug_messages
+				.into_iter()
+				.map(|message| String::from_utf8_lossy(&message).into_owned())
+				.collect(),
+			outcome: match t.outcome {
+				FrameOutcome::Returned { status, data } => {
+					RpcFrameOutcome::Returned { status, data: data.into() }
+				},
+				FrameOutcome::Trapped { reason } => RpcFrameOutcome::Trapped {
+					reason: String::from_utf8_lossy(&reason).into_owned(),
+				},
+			},
+			nested: t.nested.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+/// An RPC serializable result of a contract instantiation.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcContractInstantiateResult<AccountId> {
+	/// Successful instantiation
+	#[serde(rename_all = "camelCase")]
+	Success {
+		/// Address of the instantiated contract
+		address: AccountId,
+		/// Status code
+		status: u8,
+		/// Output data
+		data: Bytes,
+		/// Amount of gas consumed
+		gas_consumed: u64,
+	},
+	/// Error instantiation
+	#[serde(rename_all = "camelCase")]
+	Error {
+		/// Amount of gas consumed
+		gas_consumed: u64,
+	},
+}
+
+impl<AccountId> From<ContractInstantiateResult<AccountId>> for RpcContractInstantiateResult<AccountId> {
+	fn from(r: ContractInstantiateResult<AccountId>) -> Self {
+		match r {
+			ContractInstantiateResult::Success { address, status, data, gas_consumed } => {
+				RpcContractInstantiateResult::Success {
+					address,
+					status,
+					data: data.into(),
+					gas_consumed,
+				}
+			},
+			ContractInstantiateResult::Error { gas_consumed } => {
+				RpcContractInstantiateResult::Error { gas_consumed }
+			},
+		}
+	}
+}
+
 /// Contracts RPC methods.
 #[rpc]
-pub trait ContractsApi<BlockHash, AccountId, Balance> {
+pub trait ContractsApi<BlockHash, BlockNumber, AccountId, Balance> {
 	/// Executes a call to a contract.
 	///
 	/// This call is performed locally without submitting any transactions. Thus executing this
@@ -123,6 +304,35 @@ pub trait ContractsApi<BlockHash, AccountId, Balance> {
 		at: Option<BlockHash>,
 	) -> Result<RpcContractExecResult>;
 
+	/// Executes a call to a contract and records its execution trace.
+	///
+	/// Like `contracts_call`, this call is performed locally without changing any state. Along
+	/// with the result, the execution trace of the call is returned. It contains the nested calls
+	/// and instantiations, the gas used by each of them, the events deposited and the debug
+	/// messages printed by the contracts, and the reason of a trap, if any.
+	///
+	/// This method is useful for debugging contracts.
+	#[rpc(name = "contracts_callWithTrace")]
+	fn call_with_trace(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		at: Option<BlockHash>,
+	) -> Result<RpcContractCallResult<AccountId, Balance, BlockHash>>;
+
+	/// Instantiates a new contract from the code with the given hash.
+	///
+	/// This call is performed locally without submitting any transactions. Thus executing this
+	/// won't change any state.
+	///
+	/// This method is useful for estimating the gas required for an instantiation and for
+	/// finding out the address the contract would be instantiated at.
+	#[rpc(name = "contracts_instantiate")]
+	fn instantiate(
+		&self,
+		instantiate_request: InstantiateRequest<AccountId, Balance, BlockHash>,
+		at: Option<BlockHash>,
+	) -> Result<RpcContractInstantiateResult<AccountId>>;
+
 	/// Returns the value under a specified storage `key` in a contract given by `address` param,
 	/// or `None` if it is not set.
 	#[rpc(name = "contracts_getStorage")]
@@ -132,6 +342,19 @@ pub trait ContractsApi<BlockHash, AccountId, Balance> {
 		key: H256,
 		at: Option<BlockHash>,
 	) -> Result<Option<Bytes>>;
+
+	/// Returns the projected time a given contract will be able to sustain paying its rent.
+	///
+	/// The returned projection is relevant for the given block, i.e. it is as if the contract was
+	/// accessed at the beginning of that block.
+	///
+	/// Returns `None` if the contract is exempted from rent.
+	#[rpc(name = "contracts_rentProjection")]
+	fn rent_projection(
+		&self,
+		address: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Option<BlockNumber>>;
 }
 
 /// An implementation of contract specific RPC methods.
@@ -150,8 +373,13 @@ impl<C, B> Contracts<C, B> {
 	}
 }
 
-impl<C, Block, AccountId, Balance> ContractsApi<<Block as BlockT>::Hash, AccountId, Balance>
-	for Contracts<C, Block>
+impl<C, Block, AccountId, Balance>
+	ContractsApi<
+		<Block as BlockT>::Hash,
+		<<Block as BlockT>::Header as HeaderT>::Number,
+		AccountId,
+		Balance,
+	> for Contracts<C, Block>
 where
 	Block: BlockT,
 	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
@@ -176,24 +404,7 @@ where
 			gas_limit,
 			input_data,
 		} = call_request;
-		let gas_limit = gas_limit.to_number().map_err(|e| Error {
-			code: ErrorCode::InvalidParams,
-			message: e,
-			data: None,
-		})?;
-
-		let max_gas_limit = 5 * GAS_PER_SECOND;
-		if gas_limit > max_gas_limit {
-			return Err(Error {
-				code: ErrorCode::InvalidParams,
-				message: format!(
-					"Requested gas limit is greater than maximum allowed: {} > {}",
-					gas_limit,
-					max_gas_limit
-				),
-				data: None,
-			});
-		}
+		let gas_limit = limit_gas(gas_limit)?;
 
 		let exec_result = api
 			.call(&at, origin, dest, value, gas_limit, input_data.to_vec())
@@ -206,6 +417,83 @@ where
 		Ok(exec_result.into())
 	}
 
+	fn call_with_trace(
+		&self,
+		call_request: CallRequest<AccountId, Balance>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<RpcContractCallResult<AccountId, Balance, <Block as BlockT>::Hash>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		let CallRequest {
+			origin,
+			dest,
+			value,
+			gas_limit,
+			input_data,
+		} = call_request;
+		let gas_limit = limit_gas(gas_limit)?;
+
+		let runtime_error = |e: ApiErrorFor<C, Block>| Error {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Runtime trapped while executing a contract.".into(),
+			data: Some(format!("{:?}", e).into()),
+		};
+
+		let has_trace = api
+			.has_api_with::<
+				dyn ContractsRuntimeApi<Block, AccountId, Balance, Error = ApiErrorFor<C, Block>>,
+				_,
+			>(&at, |version| version >= 3)
+			.map_err(runtime_error)?;
+
+		let call_result = if has_trace {
+			api
+				.call_with_trace(&at, origin, dest, value, gas_limit, input_data.to_vec())
+				.map_err(runtime_error)?
+		} else {
+			// Runtimes before version 3 of the API don't record a trace.
+			let exec_result = api
+				.call(&at, origin, dest, value, gas_limit, input_data.to_vec())
+				.map_err(runtime_error)?;
+			ContractCallResult { result: exec_result, trace: None }
+		};
+
+		Ok(call_result.into())
+	}
+
+	fn instantiate(
+		&self,
+		instantiate_request: InstantiateRequest<AccountId, Balance, <Block as BlockT>::Hash>,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<RpcContractInstantiateResult<AccountId>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		let InstantiateRequest {
+			origin,
+			endowment,
+			gas_limit,
+			code_hash,
+			data,
+		} = instantiate_request;
+		let gas_limit = limit_gas(gas_limit)?;
+
+		let instantiate_result = api
+			.instantiate(&at, origin, endowment, gas_limit, code_hash, data.to_vec())
+			.map_err(|e| Error {
+				code: ErrorCode::ServerError(RUNTIME_ERROR),
+				message: "Runtime trapped while instantiating a contract.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?;
+
+		Ok(instantiate_result.into())
+	}
+
 	fn get_storage(
 		&self,
 		address: AccountId,
@@ -231,6 +519,58 @@ where
 
 		Ok(get_storage_result)
 	}
+
+	fn rent_projection(
+		&self,
+		address: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<<<Block as BlockT>::Header as HeaderT>::Number>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash));
+
+		let result = api
+			.rent_projection(&at, address)
+			.map_err(|e|
+				// Handle general API calling errors.
+				Error {
+					code: ErrorCode::ServerError(RUNTIME_ERROR),
+					message: "Runtime trapped while computing rent projection.".into(),
+					data: Some(format!("{:?}", e).into()),
+				})?
+			.map_err(GetStorageError)?;
+
+		Ok(match result {
+			RentProjection::NoEviction => None,
+			RentProjection::EvictionAt(block_num) => Some(block_num),
+		})
+	}
+}
+
+/// Converts the requested gas limit into a number and checks that it is within the bounds
+/// allowed for a dry-run execution.
+fn limit_gas(gas_limit: number::NumberOrHex<u64>) -> Result<u64> {
+	let gas_limit = gas_limit.to_number().map_err(|e| Error {
+		code: ErrorCode::InvalidParams,
+		message: e,
+		data: None,
+	})?;
+
+	let max_gas_limit = 5 * GAS_PER_SECOND;
+	if gas_limit > max_gas_limit {
+		return Err(Error {
+			code: ErrorCode::InvalidParams,
+			message: format!(
+				"Requested gas limit is greater than maximum allowed: {} > {}",
+				gas_limit,
+				max_gas_limit
+			),
+			data: None,
+		});
+	}
+
+	Ok(gas_limit)
 }
 
 #[cfg(test)]
@@ -248,4 +588,53 @@ mod tests {
 		test(r#"{"success":{"status":5,"data":"0x1234"}}"#);
 		test(r#"{"error":null}"#);
 	}
+
+	#[test]
+	fn call_result_should_serialize_deserialize_properly() {
+		fn test(expected: &str) {
+			let res: RpcContractCallResult<u64, u64, u64> = serde_json::from_str(expected).unwrap();
+			let actual = serde_json::to_string(&res).unwrap();
+			assert_eq!(actual, expected);
+		}
+
+		test(r#"{"result":{"error":null},"trace":null}"#);
+		test(concat!(
+			r#"{"result":{"success":{"status":0,"data":"0x"}},"trace":{"#,
+			r#""kind":"call","caller":1,"dest":2,"value":0,"gasUsed":100,"#,
+			r#""events":[{"topics":[7],"data":"0x0102"}],"debugMessages":["outer"],"#,
+			r#""outcome":{"returned":{"status":0,"data":"0x"}},"nested":[{"#,
+			r#""kind":"instantiate","caller":2,"dest":3,"value":50,"gasUsed":40,"#,
+			r#""events":[],"debugMessages":[],"#,
+			r#""outcome":{"trapped":{"reason":"during execution"}},"nested":[]}]}}"#,
+		));
+	}
+
+	#[test]
+	fn call_trace_should_replace_invalid_utf8() {
+		let trace: RpcCallTrace<u64, u64, u64> = CallTrace {
+			kind: CallKind::Call,
+			caller: 1,
+			dest: 2,
+			value: 0,
+			gas_used: 10,
+			events: Vec::new(),
+			debug_messages: vec![b"valid".to_vec(), vec![0xff]],
+			outcome: FrameOutcome::Trapped { reason: b"during execution".to_vec() },
+			nested: Vec::new(),
+		}.into();
+
+		assert_eq!(trace.debug_messages, vec!["valid".to_string(), "\u{fffd}".to_string()]);
+	}
+
+	#[test]
+	fn instantiate_result_should_serialize_deserialize_properly() {
+		fn test(expected: &str) {
+			let res: RpcContractInstantiateResult<u64> = serde_json::from_str(expected).unwrap();
+			let actual = serde_json::to_string(&res).unwrap();
+			assert_eq!(actual, expected);
+		}
+
+		test(r#"{"success":{"address":5,"status":0,"data":"0x1234","gasConsumed":100}}"#);
+		test(r#"{"error":{"gasConsumed":50}}"#);
+	}
 }
@@ -0,0 +1,89 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate. If not, see <http://www.gnu.org/licenses/>.
+
+//! A mechanism for runtime authors to augment the functionality of contracts.
+//!
+//! Calls dispatched with `ext_dispatch_call` are only executed after the contract has finished
+//! and their outcome is not reported back to the contract. A chain extension on the other hand is
+//! invoked synchronously by a contract through `ext_call_chain_extension`: the contract passes a
+//! function id together with an input buffer and receives the status code and output of the
+//! extension right away.
+//!
+//! The runtime provides the extension through [`Trait::ChainExtension`](../trait.Trait.html).
+
+use crate::{Trait, GasMeter, ExecReturnValue, BalanceOf};
+use sp_std::prelude::*;
+use sp_runtime::DispatchError;
+
+/// The result of a chain extension call.
+///
+/// `Ok(_)` contains the status code and output data which are handed back to the calling
+/// contract. `Err(_)` aborts the execution of the calling contract with a trap.
+pub type ChainExtensionResult = Result<ExecReturnValue, DispatchError>;
+
+/// Information about the calling contract that is passed to a chain extension.
+pub struct Environment<T: Trait> {
+	/// The account of the contract that invokes the extension.
+	pub caller: T::AccountId,
+	/// The free balance of the calling contract.
+	///
+	/// Unlike the balance found in the runtime storage, this includes the changes made by the
+	/// current call stack which are not committed yet, e.g. the value transferred to the contract.
+	pub caller_balance: BalanceOf<T>,
+}
+
+/// A function set that is exposed to contracts by the runtime.
+///
+/// # Gas
+///
+/// The extension is responsible for charging the gas for the work it performs by using
+/// [`GasMeter::charge`](../struct.GasMeter.html#method.charge) on the passed gas meter. Only the
+/// cost of copying the input out of the sandbox memory is charged before the extension is called.
+/// An extension should fail once the gas meter reports that it ran out of gas.
+///
+/// # State changes
+///
+/// Every contract call is executed in a storage transaction. Changes that the extension performs
+/// on the runtime storage are therefore kept only if the calling contract and all of its callers
+/// succeed, and are reverted together with the changes of the contract otherwise.
+///
+/// The contracts module keeps the changes of the executing contracts, such as their storage and
+/// balances, in memory until the outermost call finishes. The extension doesn't see these pending
+/// changes when reading the runtime storage, apart from the balance of the caller passed in the
+/// [`Environment`](struct.Environment.html). For the same reason an extension must not modify
+/// the balances of contract accounts, since they are overwritten when the pending changes are
+/// written back.
+pub trait ChainExtension<T: Trait> {
+	/// Call the function identified by `func_id` with the given `input`.
+	fn call(
+		func_id: u32,
+		env: Environment<T>,
+		input: Vec<u8>,
+		gas_meter: &mut GasMeter<T>,
+	) -> ChainExtensionResult;
+}
+
+/// Implementation that indicates that no chain extension is available.
+impl<T: Trait> ChainExtension<T> for () {
+	fn call(
+		_func_id: u32,
+		_env: Environment<T>,
+		_input: Vec<u8>,
+		_gas_meter: &mut GasMeter<T>,
+	) -> ChainExtensionResult {
+		Err("No chain extension is available".into())
+	}
+}
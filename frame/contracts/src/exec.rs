@@ -17,13 +17,15 @@
 use super::{CodeHash, Config, ContractAddressFor, Event, RawEvent, Trait,
 	TrieId, BalanceOf, ContractInfo};
 use crate::account_db::{AccountDb, DirectAccountDb, OverlayAccountDb};
+use crate::chain_extension::{ChainExtension, ChainExtensionResult, Environment};
 use crate::gas::{Gas, GasMeter, Token, approx_gas_for_balance};
 use crate::rent;
+use crate::trace::{CallKind, TraceRecorder};
 
-use sp_std::prelude::*;
+use sp_std::{cell::RefCell, prelude::*};
 use sp_runtime::traits::{Bounded, CheckedAdd, CheckedSub, Zero};
 use frame_support::{
-	storage::unhashed, dispatch::DispatchError,
+	storage::{unhashed, with_transaction, TransactionOutcome}, dispatch::DispatchError,
 	traits::{WithdrawReason, Currency, Time, Randomness},
 };
 
@@ -129,6 +131,28 @@ pub trait Ext {
 		input_data: Vec<u8>,
 	) -> ExecResult;
 
+	/// Transfer all funds to `beneficiary` and delete the contract without leaving a tombstone.
+	///
+	/// Since this function removes the self contract eagerly, if succeeded, no further actions
+	/// should be performed on this `Ext` instance.
+	///
+	/// This function will fail if the same contract is present on the contract call stack.
+	fn terminate(
+		&mut self,
+		beneficiary: &AccountIdOf<Self::T>,
+		gas_meter: &mut GasMeter<Self::T>,
+	) -> Result<(), DispatchError>;
+
+	/// Call the chain extension provided by the runtime.
+	///
+	/// The extension is executed synchronously and is responsible for charging the gas it uses.
+	fn call_chain_extension(
+		&mut self,
+		func_id: u32,
+		input: Vec<u8>,
+		gas_meter: &mut GasMeter<Self::T>,
+	) -> ChainExtensionResult;
+
 	/// Notes a call dispatch.
 	fn note_dispatch_call(&mut self, call: CallOf<Self::T>);
 
@@ -169,6 +193,11 @@ pub trait Ext {
 	/// There should not be any duplicates in `topics`.
 	fn deposit_event(&mut self, topics: Vec<TopicOf<Self::T>>, data: Vec<u8>);
 
+	/// Record a debug message printed by the contract.
+	///
+	/// The message is only kept if the execution trace is recorded, otherwise it is discarded.
+	fn debug_message(&mut self, message: &[u8]);
+
 	/// Set rent allowance of the contract
 	fn set_rent_allowance(&mut self, rent_allowance: BalanceOf<Self::T>);
 
@@ -223,9 +252,9 @@ pub trait Vm<T: Trait> {
 #[cfg_attr(test, derive(Debug, PartialEq, Eq))]
 #[derive(Copy, Clone)]
 pub enum ExecFeeToken {
-	/// Base fee charged for a call.
+	/// Base fee charged for a call, including the storage transaction it is executed in.
 	Call,
-	/// Base fee charged for a instantiate.
+	/// Base fee charged for a instantiate, including the storage transaction it is executed in.
 	Instantiate,
 }
 
@@ -234,8 +263,10 @@ impl<T: Trait> Token<T> for ExecFeeToken {
 	#[inline]
 	fn calculate_amount(&self, metadata: &Config<T>) -> Gas {
 		match *self {
-			ExecFeeToken::Call => metadata.schedule.call_base_cost,
-			ExecFeeToken::Instantiate => metadata.schedule.instantiate_base_cost,
+			ExecFeeToken::Call => metadata.schedule.call_base_cost
+				.saturating_add(metadata.schedule.storage_transaction_cost),
+			ExecFeeToken::Instantiate => metadata.schedule.instantiate_base_cost
+				.saturating_add(metadata.schedule.storage_transaction_cost),
 		}
 	}
 }
@@ -282,6 +313,8 @@ pub struct ExecutionContext<'a, T: Trait + 'a, V, L> {
 	pub loader: &'a L,
 	pub timestamp: MomentOf<T>,
 	pub block_number: T::BlockNumber,
+	/// Records the execution trace if set. This is only the case for dry-runs.
+	pub tracer: Option<&'a RefCell<TraceRecorder<T>>>,
 }
 
 impl<'a, T, E, V, L> ExecutionContext<'a, T, V, L>
@@ -307,6 +340,7 @@ where
 			loader: &loader,
 			timestamp: T::Time::now(),
 			block_number: <frame_system::Module<T>>::block_number(),
+			tracer: None,
 		}
 	}
 
@@ -325,6 +359,7 @@ where
 			loader: self.loader,
 			timestamp: self.timestamp.clone(),
 			block_number: self.block_number.clone(),
+			tracer: self.tracer,
 		}
 	}
 
@@ -335,6 +370,30 @@ where
 		value: BalanceOf<T>,
 		gas_meter: &mut GasMeter<T>,
 		input_data: Vec<u8>,
+	) -> ExecResult {
+		let tracer = match self.tracer {
+			Some(tracer) => tracer,
+			None => return self.execute_call(dest, value, gas_meter, input_data),
+		};
+
+		tracer.borrow_mut().enter(
+			CallKind::Call,
+			self.self_account.clone(),
+			dest.clone(),
+			value,
+			gas_meter.gas_left(),
+		);
+		let result = self.execute_call(dest, value, gas_meter, input_data);
+		tracer.borrow_mut().exit(result.as_ref(), gas_meter.gas_left());
+		result
+	}
+
+	fn execute_call(
+		&mut self,
+		dest: T::AccountId,
+		value: BalanceOf<T>,
+		gas_meter: &mut GasMeter<T>,
+		input_data: Vec<u8>,
 	) -> ExecResult {
 		if self.depth == self.config.max_depth as usize {
 			return Err(ExecError {
@@ -427,6 +486,39 @@ where
 		gas_meter: &mut GasMeter<T>,
 		code_hash: &CodeHash<T>,
 		input_data: Vec<u8>,
+	) -> Result<(T::AccountId, ExecReturnValue), ExecError> {
+		let dest = T::DetermineContractAddress::contract_address_for(
+			code_hash,
+			&input_data,
+			&self.self_account,
+		);
+
+		let tracer = match self.tracer {
+			Some(tracer) => tracer,
+			None => {
+				return self.execute_instantiate(dest, endowment, gas_meter, code_hash, input_data)
+			},
+		};
+
+		tracer.borrow_mut().enter(
+			CallKind::Instantiate,
+			self.self_account.clone(),
+			dest.clone(),
+			endowment,
+			gas_meter.gas_left(),
+		);
+		let result = self.execute_instantiate(dest, endowment, gas_meter, code_hash, input_data);
+		tracer.borrow_mut().exit(result.as_ref().map(|(_, output)| output), gas_meter.gas_left());
+		result
+	}
+
+	fn execute_instantiate(
+		&mut self,
+		dest: T::AccountId,
+		endowment: BalanceOf<T>,
+		gas_meter: &mut GasMeter<T>,
+		code_hash: &CodeHash<T>,
+		input_data: Vec<u8>,
 	) -> Result<(T::AccountId, ExecReturnValue), ExecError> {
 		if self.depth == self.config.max_depth as usize {
 			return Err(ExecError {
@@ -446,11 +538,6 @@ where
 		}
 
 		let caller = self.self_account.clone();
-		let dest = T::DetermineContractAddress::contract_address_for(
-			code_hash,
-			&input_data,
-			&caller,
-		);
 
 		// TrieId has not been generated yet and storage is empty since contract is new.
 		let dest_trie_id = None;
@@ -527,7 +614,16 @@ where
 	{
 		let (output, change_set, deferred) = {
 			let mut nested = self.nested(dest, trie_id);
-			let output = func(&mut nested)?;
+			// Changes made directly to the runtime storage while executing the frame, e.g. by a
+			// chain extension, are kept only if the frame succeeds, just like its overlay.
+			let output = with_transaction(|| {
+				let output = func(&mut nested);
+				if output.as_ref().map(|output| output.is_success()).unwrap_or(false) {
+					TransactionOutcome::Commit(output)
+				} else {
+					TransactionOutcome::Rollback(output)
+				}
+			})?;
 			(output, nested.overlay.into_change_set(), nested.deferred)
 		};
 
@@ -580,6 +676,7 @@ impl<T: Trait> Token<T> for TransferFeeToken<BalanceOf<T>> {
 enum TransferCause {
 	Call,
 	Instantiate,
+	Terminate,
 }
 
 /// Transfer some funds from `transactor` to `dest`.
@@ -626,7 +723,7 @@ fn transfer<'a, T: Trait, V: Vm<T>, L: Loader<T>>(
 
 			// Otherwise the fee depends on whether we create a new account or transfer
 			// to an existing one.
-			Call => if would_create {
+			Call | Terminate => if would_create {
 				TransferFeeKind::AccountCreate
 			} else {
 				TransferFeeKind::Transfer
@@ -728,6 +825,43 @@ where
 		self.ctx.call(to.clone(), value, gas_meter, input_data)
 	}
 
+	fn terminate(
+		&mut self,
+		beneficiary: &AccountIdOf<Self::T>,
+		gas_meter: &mut GasMeter<Self::T>,
+	) -> Result<(), DispatchError> {
+		let self_id = self.ctx.self_account.clone();
+		let value = self.ctx.overlay.get_balance(&self_id);
+		if let Some(parent) = self.ctx.parent {
+			if parent.is_live(&self_id) {
+				return Err("Cannot terminate a contract that is present on the call stack".into());
+			}
+		}
+		transfer(
+			gas_meter,
+			TransferCause::Terminate,
+			&self_id,
+			beneficiary,
+			value,
+			self.ctx,
+		)?;
+		self.ctx.overlay.destroy_contract(&self_id);
+		Ok(())
+	}
+
+	fn call_chain_extension(
+		&mut self,
+		func_id: u32,
+		input: Vec<u8>,
+		gas_meter: &mut GasMeter<Self::T>,
+	) -> ChainExtensionResult {
+		let env = Environment {
+			caller: self.ctx.self_account.clone(),
+			caller_balance: self.ctx.overlay.get_balance(&self.ctx.self_account),
+		};
+		<T::ChainExtension as ChainExtension<T>>::call(func_id, env, input, gas_meter)
+	}
+
 	fn note_dispatch_call(&mut self, call: CallOf<Self::T>) {
 		self.ctx.deferred.push(DeferredAction::DispatchRuntimeCall {
 			origin: self.ctx.self_account.clone(),
@@ -780,12 +914,21 @@ where
 	}
 
 	fn deposit_event(&mut self, topics: Vec<T::Hash>, data: Vec<u8>) {
+		if let Some(tracer) = self.ctx.tracer {
+			tracer.borrow_mut().event(topics.clone(), data.clone());
+		}
 		self.ctx.deferred.push(DeferredAction::DepositEvent {
 			topics,
 			event: RawEvent::Contract(self.ctx.self_account.clone(), data),
 		});
 	}
 
+	fn debug_message(&mut self, message: &[u8]) {
+		if let Some(tracer) = self.ctx.tracer {
+			tracer.borrow_mut().debug_message(message.to_vec());
+		}
+	}
+
 	fn set_rent_allowance(&mut self, rent_allowance: BalanceOf<T>) {
 		self.ctx.overlay.set_rent_allowance(&self.ctx.self_account, rent_allowance)
 	}
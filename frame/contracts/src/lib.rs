@@ -95,6 +95,10 @@ mod account_db;
 mod exec;
 mod wasm;
 mod rent;
+mod migration;
+
+pub mod chain_extension;
+pub mod trace;
 
 #[cfg(test)]
 mod tests;
@@ -102,14 +106,16 @@ mod tests;
 use crate::exec::ExecutionContext;
 use crate::account_db::{AccountDb, DirectAccountDb};
 use crate::wasm::{WasmLoader, WasmVm};
+use crate::chain_extension::ChainExtension;
+use crate::trace::{CallTraceOf, TraceRecorder};
 
-pub use crate::gas::{Gas, GasMeter};
+pub use crate::gas::{Gas, GasMeter, GasMeterResult, Token};
 pub use crate::exec::{ExecResult, ExecReturnValue, ExecError, StatusCode};
 
 #[cfg(feature = "std")]
 use serde::{Serialize, Deserialize};
 use sp_core::crypto::UncheckedFrom;
-use sp_std::{prelude::*, marker::PhantomData, fmt::Debug};
+use sp_std::{prelude::*, marker::PhantomData, fmt::Debug, cell::RefCell};
 use codec::{Codec, Encode, Decode};
 use sp_io::hashing::blake2_256;
 use sp_runtime::{
@@ -429,6 +435,11 @@ pub trait Trait: frame_system::Trait {
 
 	/// The maximum amount of gas that could be expended per block.
 	type BlockGasLimit: Get<Gas>;
+
+	/// Functions that the runtime exposes to contracts through `ext_call_chain_extension`.
+	///
+	/// Use `()` if no chain extension is provided.
+	type ChainExtension: ChainExtension<Self>;
 }
 
 /// Simple contract address determiner.
@@ -634,12 +645,10 @@ decl_module! {
 		) -> DispatchResult {
 			let origin = ensure_signed(origin)?;
 
-			Self::execute_wasm(origin, gas_limit, |ctx, gas_meter| {
-				ctx.instantiate(endowment, gas_meter, &code_hash, data)
-					.map(|(_address, output)| output)
-			})
-			.map(|_| ())
-			.map_err(|e| e.reason.into())
+			Self::bare_instantiate(origin, endowment, gas_limit, code_hash, data)
+				.0
+				.map(|_| ())
+				.map_err(|e| e.reason.into())
 		}
 
 		/// Allows block producers to claim a small reward for evicting a contract. If a block producer
@@ -674,20 +683,37 @@ decl_module! {
 			}
 		}
 
+		fn on_runtime_upgrade() {
+			migration::perform_migrations::<T>();
+		}
+
 		fn on_finalize() {
 			GasSpent::kill();
 		}
 	}
 }
 
-/// The possible errors that can happen querying the storage of a contract.
+/// The possible errors that can happen when accessing a contract by its address, i.e. when
+/// querying its storage or its rent projection.
+#[derive(PartialEq, Eq, RuntimeDebug)]
 pub enum GetStorageError {
 	/// The given address doesn't point on a contract.
 	ContractDoesntExist,
-	/// The specified contract is a tombstone and thus cannot have any storage.
+	/// The specified contract is a tombstone and thus cannot be accessed.
 	IsTombstone,
 }
 
+/// The projected eviction of a contract.
+#[derive(PartialEq, Eq, RuntimeDebug)]
+pub enum RentProjection<BlockNumber> {
+	/// Eviction is projected to happen at the specified block number.
+	EvictionAt(BlockNumber),
+	/// No eviction is scheduled.
+	///
+	/// E.g. the contract has enough balance to offset the rent it would have to pay.
+	NoEviction,
+}
+
 /// Public APIs provided by the contracts module.
 impl<T: Trait> Module<T> {
 	/// Perform a call to a specified contract.
@@ -701,9 +727,54 @@ impl<T: Trait> Module<T> {
 		gas_limit: Gas,
 		input_data: Vec<u8>,
 	) -> ExecResult {
-		Self::execute_wasm(origin, gas_limit, |ctx, gas_meter| {
+		Self::execute_wasm(origin, gas_limit, None, |ctx, gas_meter| {
+			ctx.call(dest, value, gas_meter, input_data)
+		}).0
+	}
+
+	/// Perform a call to a specified contract and record its execution trace.
+	///
+	/// This function is similar to `Self::bare_call`, but also returns the execution trace of the
+	/// call, see the [`trace`](trace/index.html) module. It is meant for dry-runs. The trace is
+	/// `None` if the call couldn't be started, e.g. because the gas couldn't be bought.
+	pub fn bare_call_with_trace(
+		origin: T::AccountId,
+		dest: T::AccountId,
+		value: BalanceOf<T>,
+		gas_limit: Gas,
+		input_data: Vec<u8>,
+	) -> (ExecResult, Option<CallTraceOf<T>>) {
+		let tracer = RefCell::new(TraceRecorder::default());
+		let (result, _) = Self::execute_wasm(origin, gas_limit, Some(&tracer), |ctx, gas_meter| {
 			ctx.call(dest, value, gas_meter, input_data)
-		})
+		});
+		(result, tracer.into_inner().into_trace())
+	}
+
+	/// Instantiate a new contract.
+	///
+	/// This function is similar to `Self::instantiate`, but is better suitable for calling
+	/// directly from Rust. Along with the address of the new contract and the output of its
+	/// constructor, it returns the amount of gas that was consumed, even if the instantiation
+	/// failed.
+	pub fn bare_instantiate(
+		origin: T::AccountId,
+		endowment: BalanceOf<T>,
+		gas_limit: Gas,
+		code_hash: CodeHash<T>,
+		data: Vec<u8>,
+	) -> (sp_std::result::Result<(T::AccountId, ExecReturnValue), ExecError>, Gas) {
+		let mut address = None;
+		let (result, gas_consumed) = Self::execute_wasm(origin, gas_limit, None, |ctx, gas_meter| {
+			let (new_address, output) = ctx.instantiate(endowment, gas_meter, &code_hash, data)?;
+			address = Some(new_address);
+			Ok(output)
+		});
+		let result = result.map(|output| (
+			address.expect("the address is set whenever the instantiation succeeds; qed"),
+			output,
+		));
+		(result, gas_consumed)
 	}
 
 	/// Query storage of a specified contract under a specified key.
@@ -724,29 +795,43 @@ impl<T: Trait> Module<T> {
 		);
 		Ok(maybe_value)
 	}
+
+	/// Compute the block number at which the specified contract is projected to be evicted.
+	///
+	/// See `rent::compute_rent_projection` for the details.
+	pub fn rent_projection(
+		address: T::AccountId,
+	) -> sp_std::result::Result<RentProjection<T::BlockNumber>, GetStorageError> {
+		rent::compute_rent_projection::<T>(&address)
+	}
 }
 
 impl<T: Trait> Module<T> {
+	/// Execute `func` in a new top-level execution context.
+	///
+	/// Returns the result of `func` along with the amount of gas spent. The execution trace is
+	/// recorded into `tracer` if it is given.
 	fn execute_wasm(
 		origin: T::AccountId,
 		gas_limit: Gas,
+		tracer: Option<&RefCell<TraceRecorder<T>>>,
 		func: impl FnOnce(&mut ExecutionContext<T, WasmVm, WasmLoader>, &mut GasMeter<T>) -> ExecResult
-	) -> ExecResult {
+	) -> (ExecResult, Gas) {
 		// Pay for the gas upfront.
 		//
 		// NOTE: it is very important to avoid any state changes before
 		// paying for the gas.
-		let (mut gas_meter, imbalance) =
-			try_or_exec_error!(
-				gas::buy_gas::<T>(&origin, gas_limit),
-				// We don't have a spare buffer here in the first place, so create a new empty one.
-				Vec::new()
-			);
+		let (mut gas_meter, imbalance) = match gas::buy_gas::<T>(&origin, gas_limit) {
+			Ok(bought) => bought,
+			// We don't have a spare buffer here in the first place, so create a new empty one.
+			Err(reason) => return (Err(ExecError { reason, buffer: Vec::new() }), 0),
+		};
 
 		let cfg = Config::preload();
 		let vm = WasmVm::new(&cfg.schedule);
 		let loader = WasmLoader::new(&cfg.schedule);
 		let mut ctx = ExecutionContext::top_level(origin.clone(), &cfg, &vm, &loader);
+		ctx.tracer = tracer;
 
 		let result = func(&mut ctx, &mut gas_meter);
 
@@ -759,6 +844,7 @@ impl<T: Trait> Module<T> {
 		//
 		// NOTE: This should go after the commit to the storage, since the storage changes
 		// can alter the balance of the caller.
+		let gas_spent = gas_meter.spent();
 		gas::refund_unused_gas::<T>(&origin, gas_meter, imbalance);
 
 		// Execute deferred actions.
@@ -791,7 +877,7 @@ impl<T: Trait> Module<T> {
 			}
 		});
 
-		result
+		(result, gas_spent)
 	}
 
 	fn restore_to(
@@ -912,7 +998,7 @@ decl_event! {
 }
 
 decl_storage! {
-	trait Store for Module<T: Trait> as Contract {
+	trait Store for Module<T: Trait> as Contract storage_version(1) {
 		/// Gas spent so far in this block.
 		GasSpent get(fn gas_spent): Gas;
 		/// Current cost schedule for contracts.
@@ -1000,12 +1086,21 @@ pub struct Schedule {
 	/// Base gas cost to instantiate a contract.
 	pub instantiate_base_cost: Gas,
 
+	/// Gas cost of the storage transaction every call and instantiation is executed in.
+	pub storage_transaction_cost: Gas,
+
 	/// Gas cost per one byte read from the sandbox memory.
 	pub sandbox_data_read_cost: Gas,
 
 	/// Gas cost per one byte written to the sandbox memory.
 	pub sandbox_data_write_cost: Gas,
 
+	/// Base gas cost to compute a hash with one of the `ext_hash_*` functions.
+	pub hash_base_cost: Gas,
+
+	/// Gas cost per one byte of input hashed with one of the `ext_hash_*` functions.
+	pub hash_per_byte_cost: Gas,
+
 	/// The maximum number of topics supported by an event.
 	pub max_event_topics: u32,
 
@@ -1032,7 +1127,7 @@ pub struct Schedule {
 impl Default for Schedule {
 	fn default() -> Schedule {
 		Schedule {
-			version: 0,
+			version: 1,
 			put_code_per_byte_cost: 1,
 			grow_mem_cost: 1,
 			regular_op_cost: 1,
@@ -1042,8 +1137,11 @@ impl Default for Schedule {
 			event_base_cost: 1,
 			call_base_cost: 135,
 			instantiate_base_cost: 175,
+			storage_transaction_cost: 15,
 			sandbox_data_read_cost: 1,
 			sandbox_data_write_cost: 1,
+			hash_base_cost: 10,
+			hash_per_byte_cost: 1,
 			max_event_topics: 4,
 			max_stack_height: 64 * 1024,
 			max_memory_pages: 16,
@@ -0,0 +1,103 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage migrations for the contracts module.
+//!
+//! Migrations are executed in `on_runtime_upgrade`. The storage version of the module is tracked
+//! with `GetStorageVersion`, its current version is declared in `decl_storage!`.
+
+use crate::{Gas, Module, Schedule, Store, Trait};
+use codec::{Encode, Decode};
+use frame_support::{StorageValue, traits::{GetStorageVersion, StorageVersion}};
+
+/// The layout of `Schedule` in storage version 0, before the costs of storage transactions and
+/// of the hashing functions were added.
+#[derive(Encode, Decode)]
+pub(crate) struct ScheduleV0 {
+	pub version: u32,
+	pub put_code_per_byte_cost: Gas,
+	pub grow_mem_cost: Gas,
+	pub regular_op_cost: Gas,
+	pub return_data_per_byte_cost: Gas,
+	pub event_data_per_byte_cost: Gas,
+	pub event_per_topic_cost: Gas,
+	pub event_base_cost: Gas,
+	pub call_base_cost: Gas,
+	pub instantiate_base_cost: Gas,
+	pub sandbox_data_read_cost: Gas,
+	pub sandbox_data_write_cost: Gas,
+	pub max_event_topics: u32,
+	pub max_stack_height: u32,
+	pub max_memory_pages: u32,
+	pub max_table_size: u32,
+	pub enable_println: bool,
+	pub max_subject_len: u32,
+}
+
+// migrate storage from v0 to v1.
+//
+// this upgrades `CurrentSchedule` to the current layout of `Schedule`. the costs added since are
+// set to their default values and the version of the schedule is bumped, the other values are
+// kept.
+fn to_v1<T: Trait>(version: &mut StorageVersion) {
+	if *version != 0 { return }
+	*version = StorageVersion::new(1);
+
+	let default = Schedule::default();
+	let res = <Module<T> as Store>::CurrentSchedule::translate::<ScheduleV0, _>(|old| old.map(|old|
+		Schedule {
+			version: old.version.saturating_add(1).max(default.version),
+			put_code_per_byte_cost: old.put_code_per_byte_cost,
+			grow_mem_cost: old.grow_mem_cost,
+			regular_op_cost: old.regular_op_cost,
+			return_data_per_byte_cost: old.return_data_per_byte_cost,
+			event_data_per_byte_cost: old.event_data_per_byte_cost,
+			event_per_topic_cost: old.event_per_topic_cost,
+			event_base_cost: old.event_base_cost,
+			call_base_cost: old.call_base_cost,
+			instantiate_base_cost: old.instantiate_base_cost,
+			storage_transaction_cost: default.storage_transaction_cost,
+			sandbox_data_read_cost: old.sandbox_data_read_cost,
+			sandbox_data_write_cost: old.sandbox_data_write_cost,
+			hash_base_cost: default.hash_base_cost,
+			hash_per_byte_cost: default.hash_per_byte_cost,
+			max_event_topics: old.max_event_topics,
+			max_stack_height: old.max_stack_height,
+			max_memory_pages: old.max_memory_pages,
+			max_table_size: old.max_table_size,
+			enable_println: old.enable_println,
+			max_subject_len: old.max_subject_len,
+		}
+	));
+
+	if res.is_err() {
+		frame_support::print("Encountered error in migration of Contracts::CurrentSchedule.");
+	}
+
+	frame_support::print("Finished migrating Contracts storage to v1.");
+}
+
+/// Perform all necessary storage migrations to get storage into the expected state for current
+/// logic. No-op if fully upgraded.
+pub(crate) fn perform_migrations<T: Trait>() {
+	let mut version = <Module<T>>::on_chain_storage_version();
+
+	if version != <Module<T>>::current_storage_version() {
+		to_v1::<T>(&mut version);
+	}
+
+	<Module<T>>::put_on_chain_storage_version(version);
+}
@@ -14,8 +14,9 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate. If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{BalanceOf, ContractInfo, ContractInfoOf, TombstoneContractInfo, Trait, AliveContractInfo};
-use sp_runtime::traits::{Bounded, CheckedDiv, CheckedMul, Saturating, Zero,
+use crate::{BalanceOf, ContractInfo, ContractInfoOf, TombstoneContractInfo, Trait, AliveContractInfo,
+	GetStorageError, RentProjection};
+use sp_runtime::traits::{Bounded, CheckedDiv, CheckedMul, One, Saturating, Zero,
 	SaturatedConversion};
 use frame_support::traits::{Currency, ExistenceRequirement, Get, WithdrawReason, OnUnbalanced};
 use frame_support::StorageMap;
@@ -39,6 +40,24 @@ pub enum RentOutcome {
 	Ok,
 }
 
+/// Returns the amount of funds a contract is charged per block for the storage it takes up,
+/// given its current `balance`.
+fn compute_fee_per_block<T: Trait>(
+	balance: &BalanceOf<T>,
+	contract: &AliveContractInfo<T>,
+) -> BalanceOf<T> {
+	let free_storage = balance
+		.checked_div(&T::RentDepositOffset::get())
+		.unwrap_or_else(Zero::zero);
+
+	let effective_storage_size =
+		<BalanceOf<T>>::from(contract.storage_size).saturating_sub(free_storage);
+
+	effective_storage_size
+		.checked_mul(&T::RentByteFee::get())
+		.unwrap_or(<BalanceOf<T>>::max_value())
+}
+
 /// Evict and optionally pay dues (or check account can pay them otherwise) at the current
 /// block number (modulo `handicap`, read on).
 ///
@@ -75,18 +94,7 @@ fn try_evict_or_and_pay_rent<T: Trait>(
 	let balance = T::Currency::free_balance(account);
 
 	// An amount of funds to charge per block for storage taken up by the contract.
-	let fee_per_block = {
-		let free_storage = balance
-			.checked_div(&T::RentDepositOffset::get())
-			.unwrap_or_else(Zero::zero);
-
-		let effective_storage_size =
-			<BalanceOf<T>>::from(contract.storage_size).saturating_sub(free_storage);
-
-		effective_storage_size
-			.checked_mul(&T::RentByteFee::get())
-			.unwrap_or(<BalanceOf<T>>::max_value())
-	};
+	let fee_per_block = compute_fee_per_block::<T>(&balance, &contract);
 
 	if fee_per_block.is_zero() {
 		// The rent deposit offset reduced the fee to 0. This means that the contract
@@ -195,3 +203,46 @@ pub fn pay_rent<T: Trait>(account: &T::AccountId) -> Option<ContractInfo<T>> {
 pub fn try_evict<T: Trait>(account: &T::AccountId, handicap: T::BlockNumber) -> RentOutcome {
 	try_evict_or_and_pay_rent::<T>(account, handicap, false).0
 }
+
+/// Compute the block number at which the contract at `account` is projected to be evicted.
+///
+/// The projection takes the dues that are outstanding since the last rent payment into account
+/// and assumes that the fee per block stays the same, i.e. that neither the balance nor the
+/// storage size of the contract change. A contract that can already be evicted is projected to
+/// be evicted at the current block.
+///
+/// NOTE: This function doesn't modify the storage.
+pub fn compute_rent_projection<T: Trait>(
+	account: &T::AccountId,
+) -> Result<RentProjection<T::BlockNumber>, GetStorageError> {
+	let contract = match <ContractInfoOf<T>>::get(account) {
+		None => return Err(GetStorageError::ContractDoesntExist),
+		Some(ContractInfo::Tombstone(_)) => return Err(GetStorageError::IsTombstone),
+		Some(ContractInfo::Alive(contract)) => contract,
+	};
+
+	let current_block_number = <frame_system::Module<T>>::block_number();
+	let balance = T::Currency::free_balance(account);
+
+	let fee_per_block = compute_fee_per_block::<T>(&balance, &contract);
+	if fee_per_block.is_zero() {
+		return Ok(RentProjection::NoEviction);
+	}
+
+	let subsistence_threshold = T::Currency::minimum_balance() + T::TombstoneDeposit::get();
+	if balance < subsistence_threshold {
+		return Ok(RentProjection::EvictionAt(current_block_number));
+	}
+
+	// The rent is paid for the blocks passed since `deduct_block`. The contract can be evicted at
+	// the first block for which the dues exceed its rent budget.
+	let rent_budget = contract.rent_allowance.min(balance - subsistence_threshold);
+	let blocks_paid: T::BlockNumber = (rent_budget / fee_per_block)
+		.saturated_into::<u32>()
+		.into();
+	let eviction_at = contract.deduct_block
+		.saturating_add(blocks_paid)
+		.saturating_add(One::one());
+
+	Ok(RentProjection::EvictionAt(eviction_at.max(current_block_number)))
+}
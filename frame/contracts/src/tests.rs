@@ -22,7 +22,12 @@
 use crate::{
 	BalanceOf, ComputeDispatchFee, ContractAddressFor, ContractInfo, ContractInfoOf, GenesisConfig,
 	Module, RawAliveContractInfo, RawEvent, Trait, TrieId, TrieIdFromParentCounter, Schedule,
-	TrieIdGenerator, CheckBlockGasLimit, account_db::{AccountDb, DirectAccountDb, OverlayAccountDb},
+	TrieIdGenerator, CheckBlockGasLimit, Gas, GasMeter, Token, ExecReturnValue, RentProjection,
+	GetStorageError,
+	account_db::{AccountDb, DirectAccountDb, OverlayAccountDb},
+	chain_extension::{ChainExtension, ChainExtensionResult, Environment},
+	exec::STATUS_SUCCESS,
+	trace::{CallKind, FrameOutcome, TracedEvent},
 };
 use assert_matches::assert_matches;
 use hex_literal::*;
@@ -34,7 +39,7 @@ use sp_runtime::{
 };
 use frame_support::{
 	assert_ok, assert_err, impl_outer_dispatch, impl_outer_event, impl_outer_origin, parameter_types,
-	storage::child, StorageMap, StorageValue, traits::{Currency, Get},
+	storage::{child, unhashed}, StorageMap, StorageValue, traits::{Currency, Get},
 	weights::{DispatchInfo, DispatchClass, Weight},
 };
 use std::{cell::RefCell, sync::atomic::{AtomicUsize, Ordering}};
@@ -181,6 +186,7 @@ impl Trait for Test {
 	type MaxDepth = MaxDepth;
 	type MaxValueSize = MaxValueSize;
 	type BlockGasLimit = BlockGasLimit;
+	type ChainExtension = TestChainExtension;
 }
 
 type Balances = pallet_balances::Module<Test>;
@@ -216,6 +222,58 @@ impl TrieIdGenerator<u64> for DummyTrieIdGenerator {
 	}
 }
 
+/// The amount of gas charged by `TestChainExtension` for each call.
+const CHAIN_EXTENSION_GAS: Gas = 10;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct ChainExtensionToken;
+impl Token<Test> for ChainExtensionToken {
+	type Metadata = ();
+	fn calculate_amount(&self, _metadata: &()) -> Gas {
+		CHAIN_EXTENSION_GAS
+	}
+}
+
+/// The storage key the chain extension function with the id `1` writes its input to.
+const CHAIN_EXTENSION_KEY: &[u8] = b"chain_extension";
+
+/// A chain extension exposing the following functions:
+///
+/// - `0`: returns the free balance of the account passed as input.
+/// - `1`: writes the input to [`CHAIN_EXTENSION_KEY`].
+/// - `2`: returns the balance of the calling contract.
+pub struct TestChainExtension;
+impl ChainExtension<Test> for TestChainExtension {
+	fn call(
+		func_id: u32,
+		env: Environment<Test>,
+		input: Vec<u8>,
+		gas_meter: &mut GasMeter<Test>,
+	) -> ChainExtensionResult {
+		if gas_meter.charge(&(), ChainExtensionToken).is_out_of_gas() {
+			return Err("Out of gas".into());
+		}
+		match func_id {
+			0 => {
+				let who = u64::decode(&mut &input[..]).map_err(|_| "Invalid account id")?;
+				Ok(ExecReturnValue {
+					status: STATUS_SUCCESS,
+					data: Balances::free_balance(&who).encode(),
+				})
+			}
+			1 => {
+				unhashed::put_raw(CHAIN_EXTENSION_KEY, &input);
+				Ok(ExecReturnValue { status: STATUS_SUCCESS, data: Vec::new() })
+			}
+			2 => Ok(ExecReturnValue {
+				status: STATUS_SUCCESS,
+				data: env.caller_balance.encode(),
+			}),
+			_ => Err("Unknown function id".into()),
+		}
+	}
+}
+
 pub struct DummyComputeDispatchFee;
 impl ComputeDispatchFee<Call, u64> for DummyComputeDispatchFee {
 	fn compute_dispatch_fee(call: &Call) -> u64 {
@@ -278,7 +336,6 @@ impl ExtBuilder {
 		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
 		pallet_balances::GenesisConfig::<Test> {
 			balances: vec![],
-			vesting: vec![],
 		}.assimilate_storage(&mut t).unwrap();
 		GenesisConfig::<Test> {
 			current_schedule: Schedule {
@@ -310,8 +367,9 @@ fn refunds_unused_gas() {
 
 		assert_ok!(Contract::call(Origin::signed(ALICE), BOB, 0, 100_000, Vec::new()));
 
-		// 2 * 135 - gas price multiplied by the call base fee.
-		assert_eq!(Balances::free_balance(&ALICE), 100_000_000 - (2 * 135));
+		// 2 * (135 + 15) - gas price multiplied by the call base fee and the fee of the
+		// storage transaction the call is executed in.
+		assert_eq!(Balances::free_balance(&ALICE), 100_000_000 - (2 * (135 + 15)));
 	});
 }
 
@@ -930,6 +988,85 @@ fn deduct_blocks() {
 	});
 }
 
+#[test]
+fn bare_instantiate_returns_address_and_gas_consumed() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_SET_RENT).unwrap();
+
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+
+		let (result, gas_consumed) = Contract::bare_instantiate(
+			ALICE,
+			30_000,
+			100_000,
+			code_hash.into(),
+			<Test as pallet_balances::Trait>::Balance::from(1_000u32).encode() // rent allowance
+		);
+		let (address, output) = result.unwrap();
+		assert_eq!(address, BOB);
+		assert!(output.is_success());
+		assert!(gas_consumed > 0 && gas_consumed < 100_000);
+
+		// Not enough gas for the instantiation still reports the consumed gas.
+		let (result, gas_consumed) = Contract::bare_instantiate(
+			ALICE,
+			30_000,
+			10,
+			code_hash.into(),
+			<Test as pallet_balances::Trait>::Balance::from(1_000u32).encode() // rent allowance
+		);
+		assert!(result.is_err());
+		assert_eq!(gas_consumed, 10);
+	});
+}
+
+#[test]
+fn rent_projection() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_SET_RENT).unwrap();
+
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		assert_matches!(Contract::rent_projection(BOB), Err(GetStorageError::ContractDoesntExist));
+
+		// Create
+		Balances::deposit_creating(&ALICE, 1_000_000);
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+		assert_ok!(Contract::instantiate(
+			Origin::signed(ALICE),
+			30_000,
+			100_000, code_hash.into(),
+			<Test as pallet_balances::Trait>::Balance::from(1_000u32).encode() // rent allowance
+		));
+
+		let fee_per_block = (8 + 4 - 3) // storage size = size_offset + deploy_set_storage - deposit_offset
+			* 4; // rent byte price
+		// The rent allowance of 1_000 lasts for 27 blocks after the instantiation at block 1.
+		let eviction_at = 1 + 1_000 / fee_per_block + 1;
+		assert_matches!(
+			Contract::rent_projection(BOB),
+			Ok(RentProjection::EvictionAt(block)) if block == eviction_at
+		);
+
+		// The projection doesn't change as time passes.
+		initialize_block(10);
+		assert_matches!(
+			Contract::rent_projection(BOB),
+			Ok(RentProjection::EvictionAt(block)) if block == eviction_at
+		);
+
+		// A contract which can already be evicted is projected to be evicted right away.
+		initialize_block(eviction_at + 5);
+		assert_matches!(
+			Contract::rent_projection(BOB),
+			Ok(RentProjection::EvictionAt(block)) if block == eviction_at + 5
+		);
+
+		// Check that the contract is indeed evicted at that block.
+		assert_ok!(Contract::claim_surcharge(Origin::NONE, BOB, Some(ALICE)));
+		assert_matches!(Contract::rent_projection(BOB), Err(GetStorageError::IsTombstone));
+	});
+}
+
 #[test]
 fn call_contract_removals() {
 	removals(|| {
@@ -2067,6 +2204,460 @@ fn cannot_self_destruct_while_live() {
 	});
 }
 
+const CODE_TERMINATE: &str = r#"
+(module
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_address" (func $ext_address))
+	(import "env" "ext_call" (func $ext_call (param i32 i32 i64 i32 i32 i32 i32) (result i32)))
+	(import "env" "ext_terminate" (func $ext_terminate (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func $assert (param i32)
+		(block $ok
+			(br_if $ok
+				(get_local 0)
+			)
+			(unreachable)
+		)
+	)
+
+	(func (export "deploy"))
+
+	(func (export "call")
+		;; If the input data is not empty, then recursively call self with empty input data.
+		;; The termination in the recursive call must fail since the contract is present on the
+		;; call stack. If the recursive call traps, then trap here as well.
+		(if (call $ext_scratch_size)
+			(then
+				(call $ext_address)
+
+				;; Expect address to be 8 bytes.
+				(call $assert
+					(i32.eq
+						(call $ext_scratch_size)
+						(i32.const 8)
+					)
+				)
+
+				;; Read own address into memory.
+				(call $ext_scratch_read
+					(i32.const 16)	;; Pointer to write address to
+					(i32.const 0)	;; Offset into scrach buffer
+					(i32.const 8)	;; Length of encoded address
+				)
+
+				;; Recursively call self with empty input data.
+				(call $assert
+					(i32.eq
+						(call $ext_call
+							(i32.const 16)	;; Pointer to own address
+							(i32.const 8)	;; Length of own address
+							(i64.const 0)	;; How much gas to devote for the execution. 0 = all.
+							(i32.const 24)	;; Pointer to the buffer with value to transfer
+							(i32.const 8)	;; Length of the buffer with value to transfer
+							(i32.const 0)	;; Pointer to input data buffer address
+							(i32.const 0)	;; Length of input data buffer
+						)
+						(i32.const 0)
+					)
+				)
+			)
+			(else
+				;; Terminate and send the entire remaining balance to DJANGO.
+				(call $ext_terminate
+					(i32.const 0)	;; Pointer to beneficiary address
+					(i32.const 8)	;; Length of beneficiary address
+				)
+			)
+		)
+	)
+
+	;; Address of DJANGO.
+	(data (i32.const 0) "\04\00\00\00\00\00\00\00")
+)
+"#;
+
+#[test]
+fn terminate_removes_contract_and_transfers_balance() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_TERMINATE).unwrap();
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+
+		// Instantiate the BOB contract.
+		assert_ok!(Contract::instantiate(
+			Origin::signed(ALICE),
+			100_000,
+			100_000,
+			code_hash.into(),
+			vec![],
+		));
+
+		// Check that the BOB contract has been instantiated.
+		assert_matches!(
+			ContractInfoOf::<Test>::get(BOB),
+			Some(ContractInfo::Alive(_))
+		);
+		let bob_balance = Balances::free_balance(BOB);
+		assert_eq!(Balances::free_balance(DJANGO), 0);
+
+		// Call BOB with no input data, forcing it to terminate.
+		assert_ok!(Contract::call(
+			Origin::signed(ALICE),
+			BOB,
+			0,
+			100_000,
+			vec![],
+		));
+
+		// Check that BOB is now dead without leaving a tombstone and that its whole balance
+		// was transferred to DJANGO.
+		assert!(ContractInfoOf::<Test>::get(BOB).is_none());
+		assert_eq!(Balances::free_balance(BOB), 0);
+		assert_eq!(Balances::free_balance(DJANGO), bob_balance);
+	});
+}
+
+#[test]
+fn cannot_terminate_while_live() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_TERMINATE).unwrap();
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+
+		// Instantiate the BOB contract.
+		assert_ok!(Contract::instantiate(
+			Origin::signed(ALICE),
+			100_000,
+			100_000,
+			code_hash.into(),
+			vec![],
+		));
+
+		// Call BOB with input data, forcing it make a recursive call to itself to
+		// terminate, resulting in a trap.
+		assert_err!(
+			Contract::call(
+				Origin::signed(ALICE),
+				BOB,
+				0,
+				100_000,
+				vec![0],
+			),
+			"during execution"
+		);
+
+		// Check that BOB is still alive.
+		assert_matches!(
+			ContractInfoOf::<Test>::get(BOB),
+			Some(ContractInfo::Alive(_))
+		);
+		assert_eq!(Balances::free_balance(DJANGO), 0);
+	});
+}
+
+const CODE_CHAIN_EXTENSION: &str = r#"
+(module
+	(import "env" "ext_call_chain_extension"
+		(func $ext_call_chain_extension (param i32 i32 i32) (result i32)))
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_return" (func $ext_return (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func $assert (param i32)
+		(block $ok
+			(br_if $ok
+				(get_local 0)
+			)
+			(unreachable)
+		)
+	)
+
+	(func (export "deploy"))
+
+	;; Calls the function of the chain extension with the id passed as input and the address
+	;; of DJANGO as argument, then returns the output of the extension.
+	(func (export "call")
+		;; Expect the function id to be 4 bytes.
+		(call $assert
+			(i32.eq
+				(call $ext_scratch_size)
+				(i32.const 4)
+			)
+		)
+
+		;; Read the function id into memory.
+		(call $ext_scratch_read
+			(i32.const 8)	;; Pointer to write the function id to
+			(i32.const 0)	;; Offset into scratch buffer
+			(i32.const 4)	;; Length of the function id
+		)
+
+		(call $assert
+			(i32.eq
+				(call $ext_call_chain_extension
+					(i32.load (i32.const 8))	;; The function id
+					(i32.const 0)	;; Pointer to the input buffer
+					(i32.const 8)	;; Length of the input buffer
+				)
+				(i32.const 0)
+			)
+		)
+
+		;; Read the output of the extension into memory and return it.
+		(call $ext_scratch_read
+			(i32.const 16)	;; Pointer to write the output to
+			(i32.const 0)	;; Offset into scratch buffer
+			(call $ext_scratch_size)	;; Length of the output
+		)
+		(call $ext_return
+			(i32.const 16)
+			(call $ext_scratch_size)
+		)
+	)
+
+	;; Address of DJANGO.
+	(data (i32.const 0) "\04\00\00\00\00\00\00\00")
+)
+"#;
+
+#[test]
+fn chain_extension_works() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_CHAIN_EXTENSION).unwrap();
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+		Balances::deposit_creating(&DJANGO, 1_234);
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+
+		// Instantiate the BOB contract.
+		assert_ok!(Contract::instantiate(
+			Origin::signed(ALICE),
+			100_000,
+			100_000,
+			code_hash.into(),
+			vec![],
+		));
+
+		// The output of the extension is available to the contract within the same call.
+		let output = Contract::bare_call(ALICE, BOB, 0, 100_000, 0u32.encode()).unwrap();
+		assert_eq!(
+			output,
+			ExecReturnValue { status: STATUS_SUCCESS, data: 1_234u64.encode() },
+		);
+
+		// Changes made by the extension are kept if the contract succeeds.
+		assert_ok!(Contract::call(Origin::signed(ALICE), BOB, 0, 100_000, 1u32.encode()));
+		assert_eq!(unhashed::get_raw(CHAIN_EXTENSION_KEY), Some(4u64.encode()));
+
+		// The extension sees the balance of the contract including the pending value transfer.
+		let output = Contract::bare_call(ALICE, BOB, 100, 100_000, 2u32.encode()).unwrap();
+		assert_eq!(
+			output,
+			ExecReturnValue { status: STATUS_SUCCESS, data: Balances::free_balance(&BOB).encode() },
+		);
+
+		// A failing extension traps the calling contract with the error of the extension.
+		assert_err!(
+			Contract::call(
+				Origin::signed(ALICE),
+				BOB,
+				0,
+				100_000,
+				7u32.encode(),
+			),
+			"Unknown function id"
+		);
+	});
+}
+
+const CODE_CHAIN_EXTENSION_THEN_TRAP: &str = r#"
+(module
+	(import "env" "ext_call_chain_extension"
+		(func $ext_call_chain_extension (param i32 i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "deploy"))
+
+	;; Writes to storage through the chain extension and then traps.
+	(func (export "call")
+		(drop
+			(call $ext_call_chain_extension
+				(i32.const 1)	;; The function id
+				(i32.const 0)	;; Pointer to the input buffer
+				(i32.const 4)	;; Length of the input buffer
+			)
+		)
+		(unreachable)
+	)
+
+	(data (i32.const 0) "\01\02\03\04")
+)
+"#;
+
+#[test]
+fn chain_extension_changes_are_reverted_on_trap() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_CHAIN_EXTENSION_THEN_TRAP).unwrap();
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+		assert_ok!(Contract::instantiate(
+			Origin::signed(ALICE),
+			100_000,
+			100_000,
+			code_hash.into(),
+			vec![],
+		));
+
+		assert_err!(
+			Contract::call(Origin::signed(ALICE), BOB, 0, 100_000, vec![]),
+			"during execution"
+		);
+		assert_eq!(unhashed::get_raw(CHAIN_EXTENSION_KEY), None);
+	});
+}
+
+const CODE_TRACE: &str = r#"
+(module
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_address" (func $ext_address))
+	(import "env" "ext_call" (func $ext_call (param i32 i32 i64 i32 i32 i32 i32) (result i32)))
+	(import "env" "ext_deposit_event" (func $ext_deposit_event (param i32 i32 i32 i32)))
+	(import "env" "ext_debug_message" (func $ext_debug_message (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func $assert (param i32)
+		(block $ok
+			(br_if $ok
+				(get_local 0)
+			)
+			(unreachable)
+		)
+	)
+
+	(func (export "deploy"))
+
+	(func (export "call")
+		;; If the input data is not empty, then print a message, deposit an event and recursively
+		;; call self with empty input data. The recursive call prints a message and traps.
+		(if (call $ext_scratch_size)
+			(then
+				(call $ext_debug_message
+					(i32.const 0)	;; Pointer to the message
+					(i32.const 5)	;; Length of the message
+				)
+				(call $ext_deposit_event
+					(i32.const 32)	;; Pointer to the encoded topics
+					(i32.const 33)	;; Length of the encoded topics
+					(i32.const 80)	;; Pointer to the event data
+					(i32.const 4)	;; Length of the event data
+				)
+
+				;; Read own address into memory.
+				(call $ext_address)
+				(call $ext_scratch_read
+					(i32.const 96)	;; Pointer to write address to
+					(i32.const 0)	;; Offset into scrach buffer
+					(i32.const 8)	;; Length of encoded address
+				)
+
+				;; Recursively call self with empty input data and expect it to trap.
+				(call $assert
+					(i32.eq
+						(call $ext_call
+							(i32.const 96)	;; Pointer to own address
+							(i32.const 8)	;; Length of own address
+							(i64.const 0)	;; How much gas to devote for the execution. 0 = all.
+							(i32.const 104)	;; Pointer to the buffer with value to transfer
+							(i32.const 8)	;; Length of the buffer with value to transfer
+							(i32.const 0)	;; Pointer to input data buffer address
+							(i32.const 0)	;; Length of input data buffer
+						)
+						(i32.const 0x0100)
+					)
+				)
+			)
+			(else
+				(call $ext_debug_message
+					(i32.const 8)	;; Pointer to the message
+					(i32.const 5)	;; Length of the message
+				)
+				(unreachable)
+			)
+		)
+	)
+
+	(data (i32.const 0) "outer")
+	(data (i32.const 8) "inner")
+
+	;; A single topic consisting of 0x01 bytes.
+	(data (i32.const 32)
+		"\04"
+		"\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01"
+		"\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01\01"
+	)
+
+	;; Event data.
+	(data (i32.const 80) "\01\02\03\04")
+)
+"#;
+
+#[test]
+fn bare_call_with_trace_records_trace() {
+	let (wasm, code_hash) = compile_module::<Test>(CODE_TRACE).unwrap();
+	ExtBuilder::default().existential_deposit(50).build().execute_with(|| {
+		Balances::deposit_creating(&ALICE, 1_000_000);
+		assert_ok!(Contract::put_code(Origin::signed(ALICE), 100_000, wasm));
+
+		// Instantiate the BOB contract.
+		assert_ok!(Contract::instantiate(
+			Origin::signed(ALICE),
+			100_000,
+			100_000,
+			code_hash.into(),
+			vec![],
+		));
+
+		let (result, trace) = Contract::bare_call_with_trace(ALICE, BOB, 0, 100_000, vec![1]);
+		assert_eq!(result.unwrap(), ExecReturnValue { status: STATUS_SUCCESS, data: Vec::new() });
+
+		let trace = trace.unwrap();
+		assert_eq!(trace.kind, CallKind::Call);
+		assert_eq!(trace.caller, ALICE);
+		assert_eq!(trace.dest, BOB);
+		assert_eq!(trace.value, 0);
+		assert_eq!(
+			trace.events,
+			vec![TracedEvent { topics: vec![H256::repeat_byte(1)], data: vec![1, 2, 3, 4] }],
+		);
+		assert_eq!(trace.debug_messages, vec![b"outer".to_vec()]);
+		assert_eq!(
+			trace.outcome,
+			FrameOutcome::Returned { status: STATUS_SUCCESS, data: Vec::new() },
+		);
+
+		// The recursive call is recorded as a nested frame even though it trapped.
+		assert_eq!(trace.nested.len(), 1);
+		let nested = &trace.nested[0];
+		assert_eq!(nested.kind, CallKind::Call);
+		assert_eq!(nested.caller, BOB);
+		assert_eq!(nested.dest, BOB);
+		assert!(nested.events.is_empty());
+		assert_eq!(nested.debug_messages, vec![b"inner".to_vec()]);
+		assert_eq!(nested.outcome, FrameOutcome::Trapped { reason: b"during execution".to_vec() });
+		assert!(nested.nested.is_empty());
+
+		// The gas used by a frame includes the gas used by its nested frames.
+		assert!(nested.gas_used > 0);
+		assert!(trace.gas_used > nested.gas_used);
+
+		// The same call dispatched as a transaction succeeds as well, without recording a trace.
+		assert_ok!(Contract::call(Origin::signed(ALICE), BOB, 0, 100_000, vec![1]));
+	});
+}
+
 const CODE_DESTROY_AND_TRANSFER: &str = r#"
 (module
 	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
@@ -2462,3 +3053,64 @@ fn get_runtime_storage() {
 		));
 	});
 }
+
+#[test]
+fn schedule_is_migrated_on_runtime_upgrade() {
+	use frame_support::traits::{GetStorageVersion, StorageVersion};
+	use sp_runtime::traits::OnRuntimeUpgrade;
+	use crate::{Store, migration::ScheduleV0};
+
+	ExtBuilder::default().build().execute_with(|| {
+		let current = Contract::current_schedule();
+		let old = ScheduleV0 {
+			version: 3,
+			put_code_per_byte_cost: 2,
+			grow_mem_cost: 3,
+			regular_op_cost: 4,
+			return_data_per_byte_cost: 5,
+			event_data_per_byte_cost: 6,
+			event_per_topic_cost: 7,
+			event_base_cost: 8,
+			call_base_cost: 9,
+			instantiate_base_cost: 10,
+			sandbox_data_read_cost: 11,
+			sandbox_data_write_cost: 12,
+			max_event_topics: 13,
+			max_stack_height: 14,
+			max_memory_pages: 15,
+			max_table_size: 16,
+			enable_println: current.enable_println,
+			max_subject_len: 17,
+		};
+		unhashed::put(&<Contract as Store>::CurrentSchedule::hashed_key(), &old);
+		Contract::put_on_chain_storage_version(StorageVersion::new(0));
+
+		Contract::on_runtime_upgrade();
+
+		assert_eq!(Contract::on_chain_storage_version(), 1);
+		assert_eq!(Contract::current_schedule(), Schedule {
+			version: 4,
+			put_code_per_byte_cost: 2,
+			grow_mem_cost: 3,
+			regular_op_cost: 4,
+			return_data_per_byte_cost: 5,
+			event_data_per_byte_cost: 6,
+			event_per_topic_cost: 7,
+			event_base_cost: 8,
+			call_base_cost: 9,
+			instantiate_base_cost: 10,
+			sandbox_data_read_cost: 11,
+			sandbox_data_write_cost: 12,
+			max_event_topics: 13,
+			max_stack_height: 14,
+			max_memory_pages: 15,
+			max_table_size: 16,
+			max_subject_len: 17,
+			..current
+		});
+
+		// the schedule is only migrated once.
+		Contract::on_runtime_upgrade();
+		assert_eq!(Contract::current_schedule().version, 4);
+	});
+}
@@ -0,0 +1,202 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate. If not, see <http://www.gnu.org/licenses/>.
+
+//! Execution traces of contract calls.
+//!
+//! A trace is only recorded for dry-runs, see `Module::bare_call_with_trace`. It contains a frame
+//! for every call and instantiation performed while executing the call, along with the gas each
+//! of them used, the events deposited and the debug messages printed by the contracts, and the
+//! outcome of the execution.
+
+use crate::{BalanceOf, Trait};
+use crate::exec::{AccountIdOf, ExecError, ExecReturnValue, StatusCode, TopicOf};
+use crate::gas::Gas;
+use sp_std::prelude::*;
+use sp_runtime::RuntimeDebug;
+
+/// The kind of an execution frame.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum CallKind {
+	/// A call to an existing account.
+	Call,
+	/// An instantiation of a new contract.
+	Instantiate,
+}
+
+/// An event deposited by a contract.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct TracedEvent<Hash> {
+	/// The topics of the event.
+	pub topics: Vec<Hash>,
+	/// The data of the event.
+	pub data: Vec<u8>,
+}
+
+/// The outcome of an execution frame.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum FrameOutcome {
+	/// The execution ran to completion.
+	///
+	/// Changes made by the frame are reverted unless `status` indicates success.
+	Returned {
+		/// The status code returned by the contract.
+		status: StatusCode,
+		/// The output data returned by the contract.
+		data: Vec<u8>,
+	},
+	/// The execution trapped or failed otherwise.
+	Trapped {
+		/// The reason of the failure.
+		reason: Vec<u8>,
+	},
+}
+
+impl FrameOutcome {
+	fn from_result(result: Result<&ExecReturnValue, &ExecError>) -> Self {
+		match result {
+			Ok(output) => FrameOutcome::Returned {
+				status: output.status,
+				data: output.data.clone(),
+			},
+			Err(error) => {
+				let reason: &'static str = error.reason.into();
+				FrameOutcome::Trapped { reason: reason.as_bytes().to_vec() }
+			},
+		}
+	}
+}
+
+/// A frame of the execution trace: a single call or instantiation.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct CallTrace<AccountId, Balance, Hash> {
+	/// Whether this frame is a call or an instantiation.
+	pub kind: CallKind,
+	/// The account which initiated the frame.
+	pub caller: AccountId,
+	/// The account which is called or instantiated.
+	pub dest: AccountId,
+	/// The value transferred to `dest`.
+	pub value: Balance,
+	/// The gas used by the frame, including the gas used by the nested frames.
+	pub gas_used: Gas,
+	/// The events deposited by the contract.
+	pub events: Vec<TracedEvent<Hash>>,
+	/// The debug messages printed by the contract.
+	pub debug_messages: Vec<Vec<u8>>,
+	/// The outcome of the execution.
+	pub outcome: FrameOutcome,
+	/// The calls and instantiations performed by the contract, in order of execution.
+	pub nested: Vec<CallTrace<AccountId, Balance, Hash>>,
+}
+
+/// A `CallTrace` with the types of the given runtime.
+pub type CallTraceOf<T> = CallTrace<AccountIdOf<T>, BalanceOf<T>, TopicOf<T>>;
+
+/// A frame which has been entered but not exited yet.
+struct OpenFrame<T: Trait> {
+	kind: CallKind,
+	caller: AccountIdOf<T>,
+	dest: AccountIdOf<T>,
+	value: BalanceOf<T>,
+	gas_left: Gas,
+	events: Vec<TracedEvent<TopicOf<T>>>,
+	debug_messages: Vec<Vec<u8>>,
+	nested: Vec<CallTraceOf<T>>,
+}
+
+/// Records the execution trace while the execution is going on.
+pub struct TraceRecorder<T: Trait> {
+	/// The frames which are currently executing. The last one is the innermost.
+	stack: Vec<OpenFrame<T>>,
+	/// The outermost frame once it has been exited.
+	root: Option<CallTraceOf<T>>,
+}
+
+impl<T: Trait> Default for TraceRecorder<T> {
+	fn default() -> Self {
+		TraceRecorder {
+			stack: Vec::new(),
+			root: None,
+		}
+	}
+}
+
+impl<T: Trait> TraceRecorder<T> {
+	/// Enter a new frame nested into the current one.
+	pub(crate) fn enter(
+		&mut self,
+		kind: CallKind,
+		caller: AccountIdOf<T>,
+		dest: AccountIdOf<T>,
+		value: BalanceOf<T>,
+		gas_left: Gas,
+	) {
+		self.stack.push(OpenFrame {
+			kind,
+			caller,
+			dest,
+			value,
+			gas_left,
+			events: Vec::new(),
+			debug_messages: Vec::new(),
+			nested: Vec::new(),
+		});
+	}
+
+	/// Exit the current frame with the given result.
+	pub(crate) fn exit(&mut self, result: Result<&ExecReturnValue, &ExecError>, gas_left: Gas) {
+		let frame = match self.stack.pop() {
+			Some(frame) => frame,
+			None => return,
+		};
+		let trace = CallTrace {
+			kind: frame.kind,
+			caller: frame.caller,
+			dest: frame.dest,
+			value: frame.value,
+			gas_used: frame.gas_left.saturating_sub(gas_left),
+			events: frame.events,
+			debug_messages: frame.debug_messages,
+			outcome: FrameOutcome::from_result(result),
+			nested: frame.nested,
+		};
+		match self.stack.last_mut() {
+			Some(parent) => parent.nested.push(trace),
+			None => self.root = Some(trace),
+		}
+	}
+
+	/// Record an event deposited in the current frame.
+	pub(crate) fn event(&mut self, topics: Vec<TopicOf<T>>, data: Vec<u8>) {
+		if let Some(frame) = self.stack.last_mut() {
+			frame.events.push(TracedEvent { topics, data });
+		}
+	}
+
+	/// Record a debug message printed in the current frame.
+	pub(crate) fn debug_message(&mut self, message: Vec<u8>) {
+		if let Some(frame) = self.stack.last_mut() {
+			frame.debug_messages.push(message);
+		}
+	}
+
+	/// Returns the recorded trace of the outermost frame.
+	///
+	/// Returns `None` if no frame was entered, e.g. if the gas couldn't be bought.
+	pub fn into_trace(self) -> Option<CallTraceOf<T>> {
+		self.root
+	}
+}
@@ -154,6 +154,7 @@ mod tests {
 	use std::cell::RefCell;
 	use sp_core::H256;
 	use crate::exec::{Ext, StorageKey, ExecError, ExecReturnValue, STATUS_SUCCESS};
+	use crate::chain_extension::ChainExtensionResult;
 	use crate::gas::{Gas, GasMeter};
 	use crate::tests::{Test, Call};
 	use crate::wasm::prepare::prepare_contract;
@@ -190,16 +191,32 @@ mod tests {
 		gas_left: u64,
 	}
 
+	#[derive(Debug, PartialEq, Eq)]
+	struct ChainExtensionEntry {
+		func_id: u32,
+		input: Vec<u8>,
+		gas_left: u64,
+	}
+
+	#[derive(Debug, PartialEq, Eq)]
+	struct TerminationEntry {
+		beneficiary: u64,
+		gas_left: u64,
+	}
+
 	#[derive(Default)]
 	pub struct MockExt {
 		storage: HashMap<StorageKey, Vec<u8>>,
 		rent_allowance: u64,
 		instantiates: Vec<InstantiateEntry>,
 		transfers: Vec<TransferEntry>,
+		terminations: Vec<TerminationEntry>,
+		chain_extension_calls: Vec<ChainExtensionEntry>,
 		dispatches: Vec<DispatchEntry>,
 		restores: Vec<RestoreEntry>,
 		// (topics, data)
 		events: Vec<(Vec<H256>, Vec<u8>)>,
+		debug_messages: Vec<Vec<u8>>,
 		next_account_id: u64,
 
 		/// Runtime storage keys works the following way.
@@ -261,6 +278,34 @@ mod tests {
 			// TODO: Add tests for different call outcomes.
 			Ok(ExecReturnValue { status: STATUS_SUCCESS, data: Vec::new() })
 		}
+		fn terminate(
+			&mut self,
+			beneficiary: &u64,
+			gas_meter: &mut GasMeter<Test>,
+		) -> Result<(), DispatchError> {
+			self.terminations.push(TerminationEntry {
+				beneficiary: *beneficiary,
+				gas_left: gas_meter.gas_left(),
+			});
+			Ok(())
+		}
+		fn call_chain_extension(
+			&mut self,
+			func_id: u32,
+			input: Vec<u8>,
+			gas_meter: &mut GasMeter<Test>,
+		) -> ChainExtensionResult {
+			self.chain_extension_calls.push(ChainExtensionEntry {
+				func_id,
+				input: input.clone(),
+				gas_left: gas_meter.gas_left(),
+			});
+			if func_id == 0 {
+				return Err(DispatchError::Other("Chain extension failed"));
+			}
+			// Echo the input back with a non-zero status code.
+			Ok(ExecReturnValue { status: 7, data: input })
+		}
 		fn note_dispatch_call(&mut self, call: Call) {
 			self.dispatches.push(DispatchEntry(call));
 		}
@@ -307,6 +352,10 @@ mod tests {
 			self.events.push((topics, data))
 		}
 
+		fn debug_message(&mut self, message: &[u8]) {
+			self.debug_messages.push(message.to_vec())
+		}
+
 		fn set_rent_allowance(&mut self, rent_allowance: u64) {
 			self.rent_allowance = rent_allowance;
 		}
@@ -362,6 +411,21 @@ mod tests {
 		) -> ExecResult {
 			(**self).call(to, value, gas_meter, input_data)
 		}
+		fn terminate(
+			&mut self,
+			beneficiary: &u64,
+			gas_meter: &mut GasMeter<Test>,
+		) -> Result<(), DispatchError> {
+			(**self).terminate(beneficiary, gas_meter)
+		}
+		fn call_chain_extension(
+			&mut self,
+			func_id: u32,
+			input: Vec<u8>,
+			gas_meter: &mut GasMeter<Test>,
+		) -> ChainExtensionResult {
+			(**self).call_chain_extension(func_id, input, gas_meter)
+		}
 		fn note_dispatch_call(&mut self, call: Call) {
 			(**self).note_dispatch_call(call)
 		}
@@ -403,6 +467,9 @@ mod tests {
 		fn deposit_event(&mut self, topics: Vec<H256>, data: Vec<u8>) {
 			(**self).deposit_event(topics, data)
 		}
+		fn debug_message(&mut self, message: &[u8]) {
+			(**self).debug_message(message)
+		}
 		fn set_rent_allowance(&mut self, rent_allowance: u64) {
 			(**self).set_rent_allowance(rent_allowance)
 		}
@@ -567,6 +634,161 @@ mod tests {
 		);
 	}
 
+	const CODE_TERMINATE: &str = r#"
+(module
+	;; ext_terminate(
+	;;     beneficiary_ptr: u32,
+	;;     beneficiary_len: u32,
+	;; )
+	(import "env" "ext_terminate" (func $ext_terminate (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+	(func (export "call")
+		(call $ext_terminate
+			(i32.const 4)  ;; Pointer to "beneficiary" address.
+			(i32.const 8)  ;; Length of "beneficiary" address.
+		)
+	)
+	(func (export "deploy"))
+
+	;; Beneficiary AccountId to transfer the funds.
+	;; Represented by u64 (8 bytes long) in little endian.
+	(data (i32.const 4) "\09\00\00\00\00\00\00\00")
+)
+"#;
+
+	#[test]
+	fn contract_terminate() {
+		let mut mock_ext = MockExt::default();
+		execute(
+			CODE_TERMINATE,
+			vec![],
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+
+		assert_eq!(
+			&mock_ext.terminations,
+			&[TerminationEntry {
+				beneficiary: 9,
+				gas_left: 49989,
+			}]
+		);
+	}
+
+	const CODE_CHAIN_EXTENSION: &str = r#"
+(module
+	;; ext_call_chain_extension(
+	;;     func_id: u32,
+	;;     input_ptr: u32,
+	;;     input_len: u32,
+	;; ) -> u32
+	(import "env" "ext_call_chain_extension"
+		(func $ext_call_chain_extension (param i32 i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; Return the status code of the extension as the exit status. The output of the
+	;; extension is left in the scratch buffer and thus returned as the output data.
+	(func (export "call") (result i32)
+		(call $ext_call_chain_extension
+			(i32.const 3)  ;; The function id.
+			(i32.const 4)  ;; Pointer to the input buffer.
+			(i32.const 4)  ;; Length of the input buffer.
+		)
+	)
+	(func (export "deploy"))
+
+	(data (i32.const 4) "\01\02\03\04")
+)
+"#;
+
+	#[test]
+	fn contract_call_chain_extension() {
+		let mut mock_ext = MockExt::default();
+		let output = execute(
+			CODE_CHAIN_EXTENSION,
+			vec![],
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+
+		assert_eq!(output, ExecReturnValue { status: 7, data: vec![1, 2, 3, 4] });
+		assert_eq!(
+			&mock_ext.chain_extension_calls,
+			&[ChainExtensionEntry {
+				func_id: 3,
+				input: vec![1, 2, 3, 4],
+				gas_left: 49992,
+			}]
+		);
+	}
+
+	const CODE_CHAIN_EXTENSION_FAILING: &str = r#"
+(module
+	(import "env" "ext_call_chain_extension"
+		(func $ext_call_chain_extension (param i32 i32 i32) (result i32)))
+	(import "env" "memory" (memory 1 1))
+
+	;; Call the function id the mock extension fails for.
+	(func (export "call")
+		(drop
+			(call $ext_call_chain_extension
+				(i32.const 0)  ;; The function id.
+				(i32.const 0)  ;; Pointer to the input buffer.
+				(i32.const 0)  ;; Length of the input buffer.
+			)
+		)
+	)
+	(func (export "deploy"))
+)
+"#;
+
+	#[test]
+	fn contract_call_chain_extension_error_is_preserved() {
+		assert_matches!(
+			execute(
+				CODE_CHAIN_EXTENSION_FAILING,
+				vec![],
+				MockExt::default(),
+				&mut GasMeter::with_limit(50_000, 1),
+			),
+			Err(ExecError { reason: DispatchError::Other("Chain extension failed"), buffer: _ })
+		);
+	}
+
+	const CODE_DEBUG_MESSAGE: &str = r#"
+(module
+	;; ext_debug_message(str_ptr: u32, str_len: u32)
+	(import "env" "ext_debug_message" (func $ext_debug_message (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "call")
+		(call $ext_debug_message
+			(i32.const 8)  ;; Pointer to the message.
+			(i32.const 5)  ;; Length of the message.
+		)
+	)
+	(func (export "deploy"))
+
+	(data (i32.const 8) "Hello")
+)
+"#;
+
+	#[test]
+	fn contract_debug_message() {
+		let mut mock_ext = MockExt::default();
+		let mut gas_meter = GasMeter::with_limit(50_000, 1);
+		execute(
+			CODE_DEBUG_MESSAGE,
+			vec![],
+			&mut mock_ext,
+			&mut gas_meter,
+		).unwrap();
+
+		assert_eq!(mock_ext.debug_messages, vec![b"Hello".to_vec()]);
+		// 3 instructions and 5 bytes of the message read from the sandbox memory.
+		assert_eq!(gas_meter.gas_left(), 50_000 - 3 - 5);
+	}
+
 	const CODE_TRANSFER_LIMITED_GAS: &str = r#"
 (module
 	;; ext_call(
@@ -1538,6 +1760,71 @@ mod tests {
 		).unwrap();
 	}
 
+	/// calls the hash function `HASH_FN` on the input data and returns the resulting hash of
+	/// `HASH_LEN` bytes.
+	const CODE_HASH: &str = r#"
+(module
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_return" (func $ext_return (param i32 i32)))
+	(import "env" "HASH_FN" (func $hash_fn (param i32 i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "call")
+		(local $input_len i32)
+		(set_local $input_len
+			(call $ext_scratch_size)
+		)
+
+		;; copy the input data into the contract's memory.
+		(call $ext_scratch_read
+			(i32.const 64)		;; Pointer in memory to the place where to copy.
+			(i32.const 0)		;; Offset from the start of the scratch buffer.
+			(get_local $input_len)	;; Count of bytes to copy.
+		)
+
+		;; hash the input data into the first bytes of the contract's memory.
+		(call $hash_fn
+			(i32.const 64)		;; Pointer to the input data.
+			(get_local $input_len)	;; Length of the input data.
+			(i32.const 0)		;; Pointer to the output buffer.
+		)
+
+		(call $ext_return
+			(i32.const 0)
+			(i32.const HASH_LEN)
+		)
+	)
+
+	(func (export "deploy"))
+)
+"#;
+
+	#[test]
+	fn hash_functions() {
+		let input = b"_DEAD_BEEF".to_vec();
+		let cases = vec![
+			("ext_hash_keccak_256", sp_io::hashing::keccak_256(&input).to_vec()),
+			("ext_hash_blake2_256", sp_io::hashing::blake2_256(&input).to_vec()),
+			("ext_hash_blake2_128", sp_io::hashing::blake2_128(&input).to_vec()),
+			("ext_hash_sha2_256", sp_io::hashing::sha2_256(&input).to_vec()),
+		];
+
+		for (hash_fn, expected) in cases {
+			let code = CODE_HASH
+				.replace("HASH_FN", hash_fn)
+				.replace("HASH_LEN", &expected.len().to_string());
+			let output = execute(
+				&code,
+				input.clone(),
+				MockExt::default(),
+				&mut GasMeter::with_limit(50_000, 1),
+			).unwrap();
+
+			assert_eq!(output, ExecReturnValue { status: STATUS_SUCCESS, data: expected });
+		}
+	}
+
 	// asserts that the size of the input data is 4.
 	const CODE_SIMPLE_ASSERT: &str = r#"
 (module
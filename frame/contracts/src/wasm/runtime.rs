@@ -27,7 +27,8 @@ use sp_std::prelude::*;
 use sp_std::convert::TryInto;
 use sp_std::mem;
 use codec::{Decode, Encode};
-use sp_runtime::traits::{Bounded, SaturatedConversion};
+use sp_runtime::{DispatchError, traits::{Bounded, SaturatedConversion}};
+use sp_io::hashing::{keccak_256, blake2_256, blake2_128, sha2_256};
 
 /// The value returned from ext_call and ext_instantiate contract external functions if the call or
 /// instantiation traps. This value is chosen as if the execution does not trap, the return value
@@ -41,6 +42,12 @@ const TRAP_RETURN_CODE: u32 = 0x0100;
 enum SpecialTrap {
 	/// Signals that trap was generated in response to call `ext_return` host function.
 	Return(Vec<u8>),
+	/// Signals that a trap was generated in response to a successful call to the
+	/// `ext_terminate` host function.
+	Termination,
+	/// Signals that a trap was generated because the chain extension called through
+	/// `ext_call_chain_extension` failed with the given error.
+	ChainExtension(DispatchError),
 }
 
 /// Can only be used for one call.
@@ -76,9 +83,20 @@ pub(crate) fn to_execution_result<E: Ext>(
 	runtime: Runtime<E>,
 	sandbox_result: Result<sp_sandbox::ReturnValue, sp_sandbox::Error>,
 ) -> ExecResult {
-	// Special case. The trap was the result of the execution `return` host function.
-	if let Some(SpecialTrap::Return(data)) = runtime.special_trap {
-		return Ok(ExecReturnValue { status: STATUS_SUCCESS, data });
+	match runtime.special_trap {
+		// Special case. The trap was the result of the execution `return` host function.
+		Some(SpecialTrap::Return(data)) => {
+			return Ok(ExecReturnValue { status: STATUS_SUCCESS, data });
+		},
+		// Special case. The trap was the result of a successful `terminate` host function.
+		Some(SpecialTrap::Termination) => {
+			return Ok(ExecReturnValue { status: STATUS_SUCCESS, data: Vec::new() });
+		},
+		// Special case. The trap was the result of a failed chain extension call.
+		Some(SpecialTrap::ChainExtension(reason)) => {
+			return Err(ExecError { reason, buffer: runtime.scratch_buf });
+		},
+		None => (),
 	}
 
 	// Check the exact type of the error.
@@ -131,6 +149,8 @@ pub enum RuntimeToken {
 	/// (topic_count, data_bytes): A buffer of the given size is posted as an event indexed with the
 	/// given number of topics.
 	DepositEvent(u32, u32),
+	/// The given number of bytes is hashed with one of the `ext_hash_*` functions.
+	Hash(u32),
 }
 
 impl<T: Trait> Token<T> for RuntimeToken {
@@ -169,6 +189,10 @@ impl<T: Trait> Token<T> for RuntimeToken {
 					)
 			},
 			ComputedDispatchFee(gas) => Some(gas),
+			Hash(byte_count) => metadata
+				.hash_per_byte_cost
+				.checked_mul(byte_count.into())
+				.and_then(|cost| cost.checked_add(metadata.hash_base_cost)),
 		};
 
 		value.unwrap_or_else(|| Bounded::max_value())
@@ -287,6 +311,36 @@ fn write_sandbox_memory<T: Trait>(
 	Ok(())
 }
 
+/// Hash the input buffer designated in the sandbox memory with the given hash function and write
+/// the result to the designated output location in the sandbox memory, consuming an appropriate
+/// amount of gas.
+///
+/// The output buffer must be large enough to hold the result of the hash function.
+///
+/// Returns `Err` if one of the following conditions occurs:
+///
+/// - calculating the gas cost resulted in overflow.
+/// - out of gas
+/// - the input or output buffer is not within the bounds of the sandbox memory.
+fn compute_hash_on_intermediate_buffer<E, F, R>(
+	ctx: &mut Runtime<E>,
+	hash_fn: F,
+	input_ptr: u32,
+	input_len: u32,
+	output_ptr: u32,
+) -> Result<(), sp_sandbox::HostError>
+where
+	E: Ext,
+	F: FnOnce(&[u8]) -> R,
+	R: AsRef<[u8]>,
+{
+	charge_gas(ctx.gas_meter, ctx.schedule, RuntimeToken::Hash(input_len))?;
+
+	let input = read_sandbox_memory(ctx, input_ptr, input_len)?;
+	let hash = hash_fn(&input);
+	write_sandbox_memory(ctx.schedule, ctx.gas_meter, &ctx.memory, output_ptr, hash.as_ref())
+}
+
 // ***********************************************************
 // * AFTER MAKING A CHANGE MAKE SURE TO UPDATE COMPLEXITY.MD *
 // ***********************************************************
@@ -517,6 +571,30 @@ define_env!(Env, <E: Ext>,
 		}
 	},
 
+	// Remove the calling account and transfer remaining balance.
+	//
+	// This function never returns. Either the termination was successful and the
+	// execution of the destroyed contract is halted. Or it failed during the termination
+	// which is considered fatal and results in a trap + rollback.
+	//
+	// - beneficiary_ptr: a pointer to the address of the beneficiary account where all
+	//   remaining funds of the caller are transferred.
+	//   Should be decodable as an `T::AccountId`. Traps otherwise.
+	// - beneficiary_len: length of the address buffer.
+	ext_terminate(
+		ctx,
+		beneficiary_ptr: u32,
+		beneficiary_len: u32
+	) => {
+		let beneficiary: <<E as Ext>::T as frame_system::Trait>::AccountId =
+			read_sandbox_memory_as(ctx, beneficiary_ptr, beneficiary_len)?;
+
+		if let Ok(_) = ctx.ext.terminate(&beneficiary, ctx.gas_meter) {
+			ctx.special_trap = Some(SpecialTrap::Termination);
+		}
+		Err(sp_sandbox::HostError)
+	},
+
 	// Save a data buffer as a result of the execution, terminate the execution and return a
 	// successful result to the caller.
 	//
@@ -644,6 +722,35 @@ define_env!(Env, <E: Ext>,
 		Ok(())
 	},
 
+	// Call into the chain extension provided by the runtime.
+	//
+	// Unlike `ext_dispatch_call`, the extension is executed synchronously and its output is
+	// available to the contract right away. The extension charges the gas it uses from the
+	// gas meter of the calling contract.
+	//
+	// - func_id: identifies the function of the extension to call.
+	// - input_ptr: a pointer to a buffer to be passed to the extension as input.
+	// - input_len: length of the input buffer.
+	//
+	// The output of the extension is placed into the scratch buffer and the status code that
+	// it reported is returned. Traps if the extension fails or the runtime doesn't provide one.
+	ext_call_chain_extension(ctx, func_id: u32, input_ptr: u32, input_len: u32) -> u32 => {
+		// Read input data into the scratch buffer, then take ownership of it.
+		read_sandbox_memory_into_scratch(ctx, input_ptr, input_len)?;
+		let input = mem::replace(&mut ctx.scratch_buf, Vec::new());
+
+		match ctx.ext.call_chain_extension(func_id, input, ctx.gas_meter) {
+			Ok(output) => {
+				ctx.scratch_buf = output.data;
+				Ok(output.status.into())
+			},
+			Err(reason) => {
+				ctx.special_trap = Some(SpecialTrap::ChainExtension(reason));
+				Err(sp_sandbox::HostError)
+			},
+		}
+	},
+
 	// Record a request to restore the caller contract to the specified contract.
 	//
 	// At the finalization stage, i.e. when all changes from the extrinsic that invoked this
@@ -826,6 +933,21 @@ define_env!(Env, <E: Ext>,
 		if let Ok(utf8) = core::str::from_utf8(&data) {
 			sp_runtime::print(utf8);
 		}
+		ctx.ext.debug_message(&data);
+		Ok(())
+	},
+
+	// Records the given message in the execution trace of the current call.
+	//
+	// The trace is only recorded for dry-runs performed through the `contracts_callWithTrace` RPC.
+	// In all other cases this function does nothing apart from charging gas for reading the
+	// message, so it is available on all chains.
+	//
+	// - str_ptr: the pointer into the linear memory where the message is placed.
+	// - str_len: the length of the message in bytes.
+	ext_debug_message(ctx, str_ptr: u32, str_len: u32) => {
+		let data = read_sandbox_memory(ctx, str_ptr, str_len)?;
+		ctx.ext.debug_message(&data);
 		Ok(())
 	},
 
@@ -861,6 +983,46 @@ define_env!(Env, <E: Ext>,
 			}
 		}
 	},
+
+	// Computes the KECCAK 256-bit hash of the given input buffer and writes the 32 byte result
+	// into the output buffer. The input and output buffers may overlap.
+	//
+	// - input_ptr: the pointer into the linear memory where the input data is placed.
+	// - input_len: the length of the input data in bytes.
+	// - output_ptr: the pointer into the linear memory where the hash is written.
+	ext_hash_keccak_256(ctx, input_ptr: u32, input_len: u32, output_ptr: u32) => {
+		compute_hash_on_intermediate_buffer(ctx, keccak_256, input_ptr, input_len, output_ptr)
+	},
+
+	// Computes the BLAKE2 256-bit hash of the given input buffer and writes the 32 byte result
+	// into the output buffer. The input and output buffers may overlap.
+	//
+	// - input_ptr: the pointer into the linear memory where the input data is placed.
+	// - input_len: the length of the input data in bytes.
+	// - output_ptr: the pointer into the linear memory where the hash is written.
+	ext_hash_blake2_256(ctx, input_ptr: u32, input_len: u32, output_ptr: u32) => {
+		compute_hash_on_intermediate_buffer(ctx, blake2_256, input_ptr, input_len, output_ptr)
+	},
+
+	// Computes the BLAKE2 128-bit hash of the given input buffer and writes the 16 byte result
+	// into the output buffer. The input and output buffers may overlap.
+	//
+	// - input_ptr: the pointer into the linear memory where the input data is placed.
+	// - input_len: the length of the input data in bytes.
+	// - output_ptr: the pointer into the linear memory where the hash is written.
+	ext_hash_blake2_128(ctx, input_ptr: u32, input_len: u32, output_ptr: u32) => {
+		compute_hash_on_intermediate_buffer(ctx, blake2_128, input_ptr, input_len, output_ptr)
+	},
+
+	// Computes the SHA2 256-bit hash of the given input buffer and writes the 32 byte result
+	// into the output buffer. The input and output buffers may overlap.
+	//
+	// - input_ptr: the pointer into the linear memory where the input data is placed.
+	// - input_len: the length of the input data in bytes.
+	// - output_ptr: the pointer into the linear memory where the hash is written.
+	ext_hash_sha2_256(ctx, input_ptr: u32, input_len: u32, output_ptr: u32) => {
+		compute_hash_on_intermediate_buffer(ctx, sha2_256, input_ptr, input_len, output_ptr)
+	},
 );
 
 /// Finds duplicates in a given vector.
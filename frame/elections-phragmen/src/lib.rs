@@ -989,7 +989,6 @@ mod tests {
 						(5, 50 * self.balance_factor),
 						(6, 60 * self.balance_factor)
 					],
-					vesting: vec![],
 				}),
 			}.build_storage().unwrap().into()
 		}
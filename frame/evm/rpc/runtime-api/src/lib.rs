@@ -0,0 +1,91 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Runtime API definition required by the EVM RPC extensions.
+//!
+//! This API should be imported and implemented by the runtime,
+//! of a node that wants to use the custom RPC extension
+//! adding Ethereum-compatible access methods.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::vec::Vec;
+use codec::{Encode, Decode};
+use sp_core::{H160, H256, U256};
+use sp_runtime::RuntimeDebug;
+
+/// The result of a dry-run execution in the EVM.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum ExecutionResult<T> {
+	/// The execution succeeded.
+	Succeed {
+		/// The returned data of a call, or the address of a created contract.
+		value: T,
+		/// The gas used by the execution.
+		used_gas: U256,
+	},
+	/// The execution was reverted.
+	Revert {
+		/// The gas used by the execution.
+		used_gas: U256,
+	},
+	/// The execution failed, or could not be started.
+	Error,
+}
+
+sp_api::decl_runtime_apis! {
+	/// The API to query the EVM state and dry-run transactions without using executive.
+	pub trait EvmApi {
+		/// The chain id Ethereum transactions must be signed for.
+		fn chain_id() -> u64;
+
+		/// The balance of an EVM account.
+		fn balance(address: H160) -> U256;
+
+		/// The nonce of an EVM account.
+		fn nonce(address: H160) -> U256;
+
+		/// The code of an EVM account.
+		fn code(address: H160) -> Vec<u8>;
+
+		/// The value under `index` in the storage of an EVM account.
+		fn storage(address: H160, index: H256) -> H256;
+
+		/// Dry-run a call from `from` to `to`.
+		///
+		/// See the EVM module's `call` dispatchable function for more details.
+		fn call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: u32,
+			gas_price: U256,
+		) -> ExecutionResult<Vec<u8>>;
+
+		/// Dry-run the creation of a contract from `from`.
+		///
+		/// See the EVM module's `create` dispatchable function for more details.
+		fn create(
+			from: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: u32,
+			gas_price: U256,
+		) -> ExecutionResult<H160>;
+	}
+}
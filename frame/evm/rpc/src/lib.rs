@@ -0,0 +1,380 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Ethereum-compatible RPC methods for the EVM module.
+//!
+//! Implements the core of the `eth_` namespace, so that Ethereum tooling can query EVM accounts,
+//! dry-run calls and submit signed Ethereum transactions. Submitted transactions are RLP-decoded
+//! into a [`pallet_evm::Transaction`] and turned into an extrinsic by a [`ConvertTransaction`]
+//! implementation provided by the node, typically wrapping the EVM module's unsigned `transact`
+//! call.
+
+use std::sync::Arc;
+
+use jsonrpc_core::{Error, ErrorCode, Result, futures::future::{self as rpc_future, Future}};
+use jsonrpc_derive::rpc;
+use futures::future::TryFutureExt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error as _};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{H160, H256, U256, Bytes};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Zero, UniqueSaturatedFrom, UniqueSaturatedInto},
+};
+use sp_transaction_pool::TransactionPool;
+use pallet_evm::Transaction;
+
+pub use self::gen_client::Client as EthClient;
+pub use pallet_evm_rpc_runtime_api::{self as runtime_api, EvmApi as EvmRuntimeApi, ExecutionResult};
+
+const RUNTIME_ERROR: i64 = 1;
+const EXECUTION_ERROR: i64 = 2;
+const EXECUTION_REVERTED: i64 = 3;
+
+/// The gas limit of dry-run calls which don't specify one.
+const DEFAULT_GAS_LIMIT: u32 = 10_000_000;
+
+/// Future that resolves to the hash of a submitted transaction.
+pub type FutureResult<T> = Box<dyn Future<Item = T, Error = Error> + Send>;
+
+/// Something that can turn an Ethereum transaction into an extrinsic of the runtime.
+pub trait ConvertTransaction<Extrinsic> {
+	/// Wrap `transaction` into an extrinsic.
+	fn convert_transaction(&self, transaction: Transaction) -> Extrinsic;
+}
+
+/// A block parameter, as accepted by the `eth_` methods.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BlockNumber {
+	/// The block with the given number.
+	Num(u64),
+	/// The best block.
+	Latest,
+	/// The genesis block.
+	Earliest,
+	/// The pending block, which is treated like the best block.
+	Pending,
+}
+
+impl Serialize for BlockNumber {
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		match self {
+			BlockNumber::Num(n) => serializer.serialize_str(&format!("0x{:x}", n)),
+			BlockNumber::Latest => serializer.serialize_str("latest"),
+			BlockNumber::Earliest => serializer.serialize_str("earliest"),
+			BlockNumber::Pending => serializer.serialize_str("pending"),
+		}
+	}
+}
+
+impl<'de> Deserialize<'de> for BlockNumber {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+		let s = String::deserialize(deserializer)?;
+		match s.as_str() {
+			"latest" => Ok(BlockNumber::Latest),
+			"earliest" => Ok(BlockNumber::Earliest),
+			"pending" => Ok(BlockNumber::Pending),
+			_ if s.starts_with("0x") => u64::from_str_radix(&s[2..], 16)
+				.map(BlockNumber::Num)
+				.map_err(|e| D::Error::custom(format!("Invalid block number: {}", e))),
+			_ => Err(D::Error::custom("Invalid block number: missing 0x prefix")),
+		}
+	}
+}
+
+/// The parameters of a dry-run call, as accepted by `eth_call` and `eth_estimateGas`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallRequest {
+	/// Sender of the call.
+	pub from: Option<H160>,
+	/// Target of the call, or `None` for a contract creation.
+	pub to: Option<H160>,
+	/// Gas price, zero if not given.
+	pub gas_price: Option<U256>,
+	/// Gas limit.
+	pub gas: Option<U256>,
+	/// Transferred value.
+	pub value: Option<U256>,
+	/// Call data, or the init code of the contract to create.
+	pub data: Option<Bytes>,
+}
+
+/// Ethereum-compatible RPC methods.
+#[rpc]
+pub trait EthApi {
+	/// Returns the chain id used for signing transactions.
+	#[rpc(name = "eth_chainId")]
+	fn chain_id(&self) -> Result<U256>;
+
+	/// Returns the number of the best block.
+	#[rpc(name = "eth_blockNumber")]
+	fn block_number(&self) -> Result<U256>;
+
+	/// Returns the balance of the given address.
+	#[rpc(name = "eth_getBalance")]
+	fn balance(&self, address: H160, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Returns the nonce of the given address.
+	#[rpc(name = "eth_getTransactionCount")]
+	fn transaction_count(&self, address: H160, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Returns the code of the given address.
+	#[rpc(name = "eth_getCode")]
+	fn code_at(&self, address: H160, number: Option<BlockNumber>) -> Result<Bytes>;
+
+	/// Returns the value in the storage of the given address at the given position.
+	#[rpc(name = "eth_getStorageAt")]
+	fn storage_at(&self, address: H160, index: U256, number: Option<BlockNumber>) -> Result<H256>;
+
+	/// Executes a call locally, without submitting a transaction, and returns its output.
+	#[rpc(name = "eth_call")]
+	fn call(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<Bytes>;
+
+	/// Executes a call or contract creation locally and returns the gas it used.
+	#[rpc(name = "eth_estimateGas")]
+	fn estimate_gas(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<U256>;
+
+	/// Submits an RLP-encoded signed transaction to the transaction pool and returns its hash.
+	#[rpc(name = "eth_sendRawTransaction")]
+	fn send_raw_transaction(&self, bytes: Bytes) -> FutureResult<H256>;
+}
+
+/// An implementation of the Ethereum-compatible RPC methods.
+pub struct Eth<C, P, CT, B> {
+	client: Arc<C>,
+	pool: Arc<P>,
+	convert_transaction: CT,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, P, CT, B> Eth<C, P, CT, B> {
+	/// Create new `Eth` with the given reference to the client and the transaction pool.
+	pub fn new(client: Arc<C>, pool: Arc<P>, convert_transaction: CT) -> Self {
+		Eth {
+			client,
+			pool,
+			convert_transaction,
+			_marker: Default::default(),
+		}
+	}
+}
+
+impl<C, P, CT, Block> Eth<C, P, CT, Block> where
+	Block: BlockT,
+	C: HeaderBackend<Block>,
+{
+	fn block_id(&self, number: Option<BlockNumber>) -> BlockId<Block> {
+		match number.unwrap_or(BlockNumber::Latest) {
+			BlockNumber::Num(n) => BlockId::number(UniqueSaturatedFrom::unique_saturated_from(n)),
+			BlockNumber::Earliest => BlockId::number(Zero::zero()),
+			BlockNumber::Latest | BlockNumber::Pending => BlockId::hash(self.client.info().best_hash),
+		}
+	}
+}
+
+fn runtime_error(e: impl std::fmt::Debug) -> Error {
+	Error {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Runtime trapped while querying the EVM.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+fn execution_error<T>(result: ExecutionResult<T>) -> Result<(T, U256)> {
+	match result {
+		ExecutionResult::Succeed { value, used_gas } => Ok((value, used_gas)),
+		ExecutionResult::Revert { .. } => Err(Error {
+			code: ErrorCode::ServerError(EXECUTION_REVERTED),
+			message: "Execution reverted.".into(),
+			data: None,
+		}),
+		ExecutionResult::Error => Err(Error {
+			code: ErrorCode::ServerError(EXECUTION_ERROR),
+			message: "Execution failed.".into(),
+			data: None,
+		}),
+	}
+}
+
+fn gas_limit(request: &CallRequest) -> Result<u32> {
+	match request.gas {
+		None => Ok(DEFAULT_GAS_LIMIT),
+		Some(gas) if gas <= U256::from(u32::max_value()) => Ok(gas.low_u32()),
+		Some(gas) => Err(Error {
+			code: ErrorCode::InvalidParams,
+			message: format!(
+				"Requested gas limit is greater than maximum allowed: {} > {}",
+				gas,
+				u32::max_value(),
+			),
+			data: None,
+		}),
+	}
+}
+
+impl<C, P, CT, Block> EthApi for Eth<C, P, CT, Block> where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: EvmRuntimeApi<Block>,
+	P: TransactionPool<Block = Block> + 'static,
+	CT: ConvertTransaction<<Block as BlockT>::Extrinsic> + Send + Sync + 'static,
+{
+	fn chain_id(&self) -> Result<U256> {
+		let at = self.block_id(None);
+		self.client.runtime_api().chain_id(&at)
+			.map(U256::from)
+			.map_err(runtime_error)
+	}
+
+	fn block_number(&self) -> Result<U256> {
+		let number: u128 = self.client.info().best_number.unique_saturated_into();
+		Ok(U256::from(number))
+	}
+
+	fn balance(&self, address: H160, number: Option<BlockNumber>) -> Result<U256> {
+		let at = self.block_id(number);
+		self.client.runtime_api().balance(&at, address).map_err(runtime_error)
+	}
+
+	fn transaction_count(&self, address: H160, number: Option<BlockNumber>) -> Result<U256> {
+		let at = self.block_id(number);
+		self.client.runtime_api().nonce(&at, address).map_err(runtime_error)
+	}
+
+	fn code_at(&self, address: H160, number: Option<BlockNumber>) -> Result<Bytes> {
+		let at = self.block_id(number);
+		self.client.runtime_api().code(&at, address)
+			.map(Bytes)
+			.map_err(runtime_error)
+	}
+
+	fn storage_at(&self, address: H160, index: U256, number: Option<BlockNumber>) -> Result<H256> {
+		let at = self.block_id(number);
+		let mut key = [0u8; 32];
+		index.to_big_endian(&mut key);
+		self.client.runtime_api().storage(&at, address, H256::from(key)).map_err(runtime_error)
+	}
+
+	fn call(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<Bytes> {
+		let at = self.block_id(number);
+		let gas_limit = gas_limit(&request)?;
+		let to = request.to.ok_or_else(|| Error {
+			code: ErrorCode::InvalidParams,
+			message: "The `to` address of a call is required.".into(),
+			data: None,
+		})?;
+
+		let result = self.client.runtime_api()
+			.call(
+				&at,
+				request.from.unwrap_or_default(),
+				to,
+				request.data.map(|d| d.0).unwrap_or_default(),
+				request.value.unwrap_or_default(),
+				gas_limit,
+				request.gas_price.unwrap_or_default(),
+			)
+			.map_err(runtime_error)?;
+
+		execution_error(result).map(|(data, _)| Bytes(data))
+	}
+
+	fn estimate_gas(&self, request: CallRequest, number: Option<BlockNumber>) -> Result<U256> {
+		let at = self.block_id(number);
+		let gas_limit = gas_limit(&request)?;
+		let api = self.client.runtime_api();
+
+		let from = request.from.unwrap_or_default();
+		let data = request.data.map(|d| d.0).unwrap_or_default();
+		let value = request.value.unwrap_or_default();
+		let gas_price = request.gas_price.unwrap_or_default();
+
+		let used_gas = match request.to {
+			Some(to) => execution_error(
+				api.call(&at, from, to, data, value, gas_limit, gas_price).map_err(runtime_error)?
+			)?.1,
+			None => execution_error(
+				api.create(&at, from, data, value, gas_limit, gas_price).map_err(runtime_error)?
+			)?.1,
+		};
+
+		Ok(used_gas)
+	}
+
+	fn send_raw_transaction(&self, bytes: Bytes) -> FutureResult<H256> {
+		let transaction = match rlp::decode::<Transaction>(&bytes.0) {
+			Ok(transaction) => transaction,
+			Err(e) => return Box::new(rpc_future::err(Error {
+				code: ErrorCode::InvalidParams,
+				message: "Invalid RLP-encoded transaction.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})),
+		};
+		let transaction_hash = transaction.hash();
+
+		let best_block_hash = self.client.info().best_hash;
+		let extrinsic = self.convert_transaction.convert_transaction(transaction);
+		Box::new(self.pool
+			.submit_one(&BlockId::hash(best_block_hash), extrinsic)
+			.compat()
+			.map(move |_| transaction_hash)
+			.map_err(|e| Error {
+				code: ErrorCode::ServerError(RUNTIME_ERROR),
+				message: "Transaction was rejected by the pool.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_serialize_deserialize_block_numbers() {
+		fn test(expected: &str, number: BlockNumber) {
+			let res: BlockNumber = serde_json::from_str(expected).unwrap();
+			assert_eq!(res, number);
+			assert_eq!(serde_json::to_string(&res).unwrap(), expected);
+		}
+
+		test(r#""latest""#, BlockNumber::Latest);
+		test(r#""earliest""#, BlockNumber::Earliest);
+		test(r#""pending""#, BlockNumber::Pending);
+		test(r#""0x1f""#, BlockNumber::Num(31));
+		assert!(serde_json::from_str::<BlockNumber>(r#""31""#).is_err());
+	}
+
+	#[test]
+	fn should_deserialize_call_request() {
+		let request: CallRequest = serde_json::from_str(r#"{
+			"from": "0x0000000000000000000000000000000000000001",
+			"to": "0x0000000000000000000000000000000000000002",
+			"gasPrice": "0x1",
+			"data": "0x1234"
+		}"#).unwrap();
+
+		assert_eq!(request.from, Some(H160::from_low_u64_be(1)));
+		assert_eq!(request.to, Some(H160::from_low_u64_be(2)));
+		assert_eq!(request.gas_price, Some(U256::one()));
+		assert_eq!(request.gas, None);
+		assert_eq!(request.data, Some(Bytes(vec![0x12, 0x34])));
+	}
+}
@@ -6,6 +6,7 @@ use codec::{Encode, Decode};
 use sp_core::{U256, H256, H160};
 use sp_runtime::traits::UniqueSaturatedInto;
 use frame_support::storage::{StorageMap, StorageDoubleMap};
+use frame_support::traits::Get;
 use sha3::{Keccak256, Digest};
 use evm::Config;
 use evm::backend::{Backend as BackendT, ApplyBackend, Apply};
@@ -96,7 +97,7 @@ impl<'vicinity, T: Trait> BackendT for Backend<'vicinity, T> {
 	}
 
 	fn chain_id(&self) -> U256 {
-		U256::from(sp_io::misc::chain_id())
+		U256::from(T::ChainId::get())
 	}
 
 	fn exists(&self, _address: H160) -> bool {
@@ -20,26 +20,38 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod backend;
+mod transaction;
 
 pub use crate::backend::{Account, Log, Vicinity, Backend};
+pub use crate::transaction::{Transaction, TransactionAction, TransactionSignature};
 
-use sp_std::{vec::Vec, marker::PhantomData};
+use sp_std::{prelude::*, marker::PhantomData};
+use codec::Encode;
 use frame_support::{ensure, decl_module, decl_storage, decl_event, decl_error};
 use frame_support::weights::{Weight, WeighData, ClassifyDispatch, DispatchClass, PaysFee};
-use frame_support::traits::{Currency, WithdrawReason, ExistenceRequirement};
-use frame_system::{self as system, ensure_signed};
+use frame_support::traits::{Currency, WithdrawReason, ExistenceRequirement, Get};
+use frame_system::{self as system, ensure_signed, ensure_none};
 use sp_runtime::ModuleId;
 use frame_support::weights::SimpleDispatchInfo;
 use sp_core::{U256, H256, H160, Hasher};
 use sp_runtime::{
 	DispatchResult, traits::{UniqueSaturatedInto, AccountIdConversion, SaturatedConversion},
+	transaction_validity::{
+		TransactionValidity, ValidTransaction, InvalidTransaction, TransactionPriority,
+		TransactionLongevity,
+	},
 };
-use evm::{ExitReason, ExitSucceed, ExitError};
+use evm::{ExitReason, ExitSucceed, ExitError, CreateScheme};
+use pallet_evm_rpc_runtime_api::ExecutionResult;
 use evm::executor::StackExecutor;
 use evm::backend::ApplyBackend;
 
 const MODULE_ID: ModuleId = ModuleId(*b"py/ethvm");
 
+/// The number of blocks an Ethereum transaction stays in the pool, after which it is dropped and
+/// has to be resubmitted.
+const TRANSACTION_LONGEVITY: TransactionLongevity = 64;
+
 /// Type alias for currency balance.
 pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
 
@@ -49,8 +61,9 @@ pub trait FeeCalculator {
 	fn min_gas_price() -> U256;
 }
 
+/// The lowest possible gas price, so that EVM executions are never free.
 impl FeeCalculator for () {
-	fn min_gas_price() -> U256 { U256::zero() }
+	fn min_gas_price() -> U256 { U256::one() }
 }
 
 /// Trait for converting account ids of `balances` module into
@@ -115,23 +128,31 @@ impl Precompiles for () {
 	}
 }
 
+/// Weighs EVM executions by the gas they may use. The gas price only affects the fee paid to
+/// the EVM, not how much of the block the execution may fill.
 struct WeightForCallCreate;
 
 impl WeighData<(&H160, &Vec<u8>, &U256, &u32, &U256)> for WeightForCallCreate {
 	fn weigh_data(
 		&self,
-		(_, _, _, gas_provided, gas_price): (&H160, &Vec<u8>, &U256, &u32, &U256)
+		(_, _, _, gas_provided, _): (&H160, &Vec<u8>, &U256, &u32, &U256)
 	) -> Weight {
-		(*gas_price).saturated_into::<Weight>().saturating_mul(*gas_provided)
+		*gas_provided
 	}
 }
 
 impl WeighData<(&Vec<u8>, &U256, &u32, &U256)> for WeightForCallCreate {
 	fn weigh_data(
 		&self,
-		(_, _, gas_provided, gas_price): (&Vec<u8>, &U256, &u32, &U256)
+		(_, _, gas_provided, _): (&Vec<u8>, &U256, &u32, &U256)
 	) -> Weight {
-		(*gas_price).saturated_into::<Weight>().saturating_mul(*gas_provided)
+		*gas_provided
+	}
+}
+
+impl WeighData<(&Transaction,)> for WeightForCallCreate {
+	fn weigh_data(&self, (transaction,): (&Transaction,)) -> Weight {
+		transaction.gas_limit.saturated_into::<Weight>()
 	}
 }
 
@@ -159,13 +180,15 @@ pub trait Trait: frame_system::Trait + pallet_timestamp::Trait {
 	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
 	/// Precompiles associated with this EVM engine.
 	type Precompiles: Precompiles;
+	/// The chain id Ethereum transactions must be signed for, as specified by EIP-155.
+	type ChainId: Get<u64>;
 }
 
 decl_storage! {
 	trait Store for Module<T: Trait> as Example {
 		Accounts get(fn accounts) config(): map H160 => Account;
-		AccountCodes: map H160 => Vec<u8>;
-		AccountStorages: double_map H160, H256 => H256;
+		AccountCodes get(fn account_codes): map H160 => Vec<u8>;
+		AccountStorages get(fn account_storages): double_map H160, H256 => H256;
 	}
 }
 
@@ -195,6 +218,14 @@ decl_error! {
 		ExitReasonRevert,
 		/// Call returned VM fatal error
 		ExitReasonFatal,
+		/// The signature of an Ethereum transaction is invalid
+		InvalidSignature,
+		/// An Ethereum transaction was signed for another chain
+		InvalidChainId,
+		/// The nonce of an Ethereum transaction doesn't match the sender's
+		InvalidNonce,
+		/// The gas limit of an Ethereum transaction is too high
+		GasLimitTooHigh,
 	}
 }
 
@@ -261,49 +292,8 @@ decl_module! {
 			ensure!(gas_price >= T::FeeCalculator::min_gas_price(), Error::<T>::GasPriceTooLow);
 			let source = T::ConvertAccountId::convert_account_id(&sender);
 
-			let vicinity = Vicinity {
-				gas_price,
-				origin: source,
-			};
-
-			let mut backend = Backend::<T>::new(&vicinity);
-			let mut executor = StackExecutor::new_with_precompile(
-				&backend,
-				gas_limit as usize,
-				&backend::GASOMETER_CONFIG,
-				T::Precompiles::execute,
-			);
-
-			let total_fee = gas_price.checked_mul(U256::from(gas_limit))
-				.ok_or(Error::<T>::FeeOverflow)?;
-			if Accounts::get(&source).balance <
-				value.checked_add(total_fee).ok_or(Error::<T>::PaymentOverflow)?
-			{
-				Err(Error::<T>::BalanceLow)?
-			}
-			executor.withdraw(source, total_fee).map_err(|_| Error::<T>::WithdrawFailed)?;
-
-			let reason = executor.transact_call(
-				source,
-				target,
-				value,
-				input,
-				gas_limit as usize,
-			);
-
-			let ret = match reason {
-				ExitReason::Succeed(_) => Ok(()),
-				ExitReason::Error(_) => Err(Error::<T>::ExitReasonFailed),
-				ExitReason::Revert(_) => Err(Error::<T>::ExitReasonRevert),
-				ExitReason::Fatal(_) => Err(Error::<T>::ExitReasonFatal),
-			};
-			let actual_fee = executor.fee(gas_price);
-			executor.deposit(source, total_fee.saturating_sub(actual_fee));
-
-			let (values, logs) = executor.deconstruct();
-			backend.apply(values, logs, true);
-
-			ret.map_err(Into::into)
+			let (reason, _, _) = Self::execute_call(source, target, input, value, gas_limit, gas_price)?;
+			Self::exit_reason_result(reason).map_err(Into::into)
 		}
 
 		/// Issue an EVM create operation. This is similar to a contract creation transaction in
@@ -318,51 +308,46 @@ decl_module! {
 		) -> DispatchResult {
 			let sender = ensure_signed(origin)?;
 			ensure!(gas_price >= T::FeeCalculator::min_gas_price(), Error::<T>::GasPriceTooLow);
-
 			let source = T::ConvertAccountId::convert_account_id(&sender);
 
-			let vicinity = Vicinity {
-				gas_price,
-				origin: source,
-			};
-
-			let mut backend = Backend::<T>::new(&vicinity);
-			let mut executor = StackExecutor::new_with_precompile(
-				&backend,
-				gas_limit as usize,
-				&backend::GASOMETER_CONFIG,
-				T::Precompiles::execute,
-			);
+			let (reason, _, _) = Self::execute_create(source, init, value, gas_limit, gas_price)?;
+			Self::exit_reason_result(reason).map_err(Into::into)
+		}
 
-			let total_fee = gas_price.checked_mul(U256::from(gas_limit))
-				.ok_or(Error::<T>::FeeOverflow)?;
-			if Accounts::get(&source).balance <
-				value.checked_add(total_fee).ok_or(Error::<T>::PaymentOverflow)?
-			{
-				Err(Error::<T>::BalanceLow)?
-			}
-			executor.withdraw(source, total_fee).map_err(|_| Error::<T>::WithdrawFailed)?;
+		/// Execute a signed Ethereum transaction, on behalf of the address that signed it.
+		///
+		/// This is unsigned from Substrate's point of view; the Ethereum signature is checked by
+		/// `ValidateUnsigned` and once more on dispatch.
+		#[weight = WeightForCallCreate]
+		fn transact(origin, transaction: Transaction) -> DispatchResult {
+			ensure_none(origin)?;
 
-			let reason = executor.transact_create(
-				source,
-				value,
-				init,
-				gas_limit as usize,
+			let source = Self::check_transaction(&transaction)?;
+			ensure!(
+				transaction.nonce == Accounts::get(&source).nonce,
+				Error::<T>::InvalidNonce,
 			);
-
-			let ret = match reason {
-				ExitReason::Succeed(_) => Ok(()),
-				ExitReason::Error(_) => Err(Error::<T>::ExitReasonFailed),
-				ExitReason::Revert(_) => Err(Error::<T>::ExitReasonRevert),
-				ExitReason::Fatal(_) => Err(Error::<T>::ExitReasonFatal),
+			let gas_limit = Self::gas_limit_of(&transaction)?;
+
+			let reason = match transaction.action {
+				TransactionAction::Call(target) => Self::execute_call(
+					source,
+					target,
+					transaction.input,
+					transaction.value,
+					gas_limit,
+					transaction.gas_price,
+				)?.0,
+				TransactionAction::Create => Self::execute_create(
+					source,
+					transaction.input,
+					transaction.value,
+					gas_limit,
+					transaction.gas_price,
+				)?.0,
 			};
-			let actual_fee = executor.fee(gas_price);
-			executor.deposit(source, total_fee.saturating_sub(actual_fee));
 
-			let (values, logs) = executor.deconstruct();
-			backend.apply(values, logs, true);
-
-			ret.map_err(Into::into)
+			Self::exit_reason_result(reason).map_err(Into::into)
 		}
 	}
 }
@@ -399,4 +384,391 @@ impl<T: Trait> Module<T> {
 		AccountCodes::remove(address);
 		AccountStorages::remove_prefix(address);
 	}
+
+	/// Execute an EVM call from `source`, applying the resulting state changes.
+	///
+	/// Returns the exit reason, the returned data and the gas used. The gas price is not checked
+	/// against the minimum, which is left to the caller.
+	pub fn execute_call(
+		source: H160,
+		target: H160,
+		input: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+	) -> Result<(ExitReason, Vec<u8>, U256), Error<T>> {
+		Self::execute_evm(source, value, gas_limit, gas_price, |executor| executor.transact_call(
+			source,
+			target,
+			value,
+			input,
+			gas_limit as usize,
+		))
+	}
+
+	/// Execute an EVM create from `source`, applying the resulting state changes.
+	///
+	/// Returns the exit reason, the address of the new contract and the gas used. The gas price
+	/// is not checked against the minimum, which is left to the caller.
+	pub fn execute_create(
+		source: H160,
+		init: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+	) -> Result<(ExitReason, H160, U256), Error<T>> {
+		Self::execute_evm(source, value, gas_limit, gas_price, |executor| {
+			let address = executor.create_address(CreateScheme::Legacy { caller: source });
+			(executor.transact_create(source, value, init, gas_limit as usize), address)
+		})
+	}
+
+	/// Dry-run a call, for the `eth_call` and `eth_estimateGas` RPCs.
+	pub fn call_info(
+		from: H160,
+		to: H160,
+		data: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+	) -> ExecutionResult<Vec<u8>> {
+		Self::execution_result(Self::execute_call(from, to, data, value, gas_limit, gas_price))
+	}
+
+	/// Dry-run the creation of a contract, for the `eth_call` and `eth_estimateGas` RPCs.
+	pub fn create_info(
+		from: H160,
+		data: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+	) -> ExecutionResult<H160> {
+		Self::execution_result(Self::execute_create(from, data, value, gas_limit, gas_price))
+	}
+
+	fn execution_result<R>(
+		result: Result<(ExitReason, R, U256), Error<T>>,
+	) -> ExecutionResult<R> {
+		match result {
+			Ok((ExitReason::Succeed(_), value, used_gas)) => ExecutionResult::Succeed { value, used_gas },
+			Ok((ExitReason::Revert(_), _, used_gas)) => ExecutionResult::Revert { used_gas },
+			_ => ExecutionResult::Error,
+		}
+	}
+
+	/// Run `f` with an executor, after withdrawing the maximum fee from `source`. The unused part
+	/// of the fee is refunded afterwards.
+	fn execute_evm<F, R>(
+		source: H160,
+		value: U256,
+		gas_limit: u32,
+		gas_price: U256,
+		f: F,
+	) -> Result<(ExitReason, R, U256), Error<T>> where
+		F: FnOnce(&mut StackExecutor<Backend<T>>) -> (ExitReason, R),
+	{
+		let vicinity = Vicinity {
+			gas_price,
+			origin: source,
+		};
+
+		let mut backend = Backend::<T>::new(&vicinity);
+		let mut executor = StackExecutor::new_with_precompile(
+			&backend,
+			gas_limit as usize,
+			&backend::GASOMETER_CONFIG,
+			T::Precompiles::execute,
+		);
+
+		let total_fee = gas_price.checked_mul(U256::from(gas_limit))
+			.ok_or(Error::<T>::FeeOverflow)?;
+		if Accounts::get(&source).balance <
+			value.checked_add(total_fee).ok_or(Error::<T>::PaymentOverflow)?
+		{
+			Err(Error::<T>::BalanceLow)?
+		}
+		executor.withdraw(source, total_fee).map_err(|_| Error::<T>::WithdrawFailed)?;
+
+		let (reason, retv) = f(&mut executor);
+
+		let used_gas = U256::from(executor.used_gas());
+		let actual_fee = executor.fee(gas_price);
+		executor.deposit(source, total_fee.saturating_sub(actual_fee));
+
+		let (values, logs) = executor.deconstruct();
+		backend.apply(values, logs, true);
+
+		Ok((reason, retv, used_gas))
+	}
+
+	/// Turn the exit reason of an execution into a result.
+	fn exit_reason_result(reason: ExitReason) -> Result<(), Error<T>> {
+		match reason {
+			ExitReason::Succeed(_) => Ok(()),
+			ExitReason::Error(_) => Err(Error::<T>::ExitReasonFailed),
+			ExitReason::Revert(_) => Err(Error::<T>::ExitReasonRevert),
+			ExitReason::Fatal(_) => Err(Error::<T>::ExitReasonFatal),
+		}
+	}
+
+	/// Check the signature, chain id and gas price of an Ethereum transaction.
+	///
+	/// Returns the address of the sender.
+	fn check_transaction(transaction: &Transaction) -> Result<H160, Error<T>> {
+		if let Some(chain_id) = transaction.signature.chain_id() {
+			ensure!(chain_id == T::ChainId::get(), Error::<T>::InvalidChainId);
+		}
+		// a zero gas price is rejected even if the fee calculator allows it, the transaction
+		// would otherwise execute without paying any fee.
+		ensure!(
+			!transaction.gas_price.is_zero() &&
+				transaction.gas_price >= T::FeeCalculator::min_gas_price(),
+			Error::<T>::GasPriceTooLow,
+		);
+
+		transaction.sender().ok_or(Error::<T>::InvalidSignature)
+	}
+
+	/// The gas limit of an Ethereum transaction, if it fits the executor.
+	fn gas_limit_of(transaction: &Transaction) -> Result<u32, Error<T>> {
+		if transaction.gas_limit > U256::from(u32::max_value()) {
+			Err(Error::<T>::GasLimitTooHigh)
+		} else {
+			Ok(transaction.gas_limit.low_u32())
+		}
+	}
+}
+
+#[allow(deprecated)]
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		if let Call::transact(transaction) = call {
+			let source = match Self::check_transaction(transaction) {
+				Ok(source) => source,
+				Err(Error::<T>::GasPriceTooLow) => return InvalidTransaction::Payment.into(),
+				Err(_) => return InvalidTransaction::BadProof.into(),
+			};
+			if Self::gas_limit_of(transaction).is_err() {
+				return InvalidTransaction::ExhaustsResources.into();
+			}
+
+			let account = Accounts::get(&source);
+			if transaction.nonce < account.nonce {
+				return InvalidTransaction::Stale.into();
+			}
+
+			let fee = transaction.gas_price.saturating_mul(transaction.gas_limit);
+			if account.balance < transaction.value.saturating_add(fee) {
+				return InvalidTransaction::Payment.into();
+			}
+
+			// transactions of the same sender are ordered by nonce, like in Ethereum.
+			let requires = if transaction.nonce > account.nonce {
+				vec![(source, transaction.nonce - 1).encode()]
+			} else {
+				vec![]
+			};
+
+			Ok(ValidTransaction {
+				priority: transaction.gas_price.saturated_into::<TransactionPriority>(),
+				requires,
+				provides: vec![(source, transaction.nonce).encode()],
+				longevity: TRANSACTION_LONGEVITY,
+				propagate: true,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types,
+		weights::{Weight, GetDispatchInfo}, unsigned::ValidateUnsigned,
+	};
+	use sp_runtime::{Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup}};
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnReapAccount = System;
+		type OnNewAccount = ();
+		type Event = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const MinimumPeriod: u64 = 1;
+	}
+	impl pallet_timestamp::Trait for Test {
+		type Moment = u64;
+		type OnTimestampSet = ();
+		type MinimumPeriod = MinimumPeriod;
+	}
+
+	pub struct TestConvertAccountId;
+	impl ConvertAccountId<u64> for TestConvertAccountId {
+		fn convert_account_id(account_id: &u64) -> H160 {
+			H160::from_low_u64_be(*account_id)
+		}
+	}
+
+	parameter_types! {
+		// the chain id of the EIP-155 example transaction.
+		pub const ChainId: u64 = 1;
+	}
+	impl Trait for Test {
+		type FeeCalculator = ();
+		type ConvertAccountId = TestConvertAccountId;
+		type Currency = pallet_balances::Module<Test>;
+		type Event = ();
+		type Precompiles = ();
+		type ChainId = ChainId;
+	}
+	type System = frame_system::Module<Test>;
+	type EVM = Module<Test>;
+
+	// The example of EIP-155, a transfer of 1 ether from `sender()` to `0x3535...35`.
+	const SIGNED_TRANSACTION: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+	fn transaction() -> Transaction {
+		let bytes = (0..SIGNED_TRANSACTION.len()).step_by(2)
+			.map(|i| u8::from_str_radix(&SIGNED_TRANSACTION[i..i + 2], 16).unwrap())
+			.collect::<Vec<_>>();
+		rlp::decode(&bytes).unwrap()
+	}
+
+	fn sender() -> H160 {
+		transaction().sender().unwrap()
+	}
+
+	fn ether(value: u64) -> U256 {
+		U256::from(value) * U256::from(1_000_000_000_000_000_000u64)
+	}
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		let mut ext = sp_io::TestExternalities::new(t);
+		ext.execute_with(|| Accounts::insert(sender(), Account { nonce: 9.into(), balance: ether(2) }));
+		ext
+	}
+
+	fn validate(transaction: Transaction) -> TransactionValidity {
+		<EVM as ValidateUnsigned>::validate_unsigned(&Call::transact(transaction))
+	}
+
+	#[test]
+	fn transact_works() {
+		new_test_ext().execute_with(|| {
+			let valid = validate(transaction()).unwrap();
+			assert_eq!(valid.provides, vec![(sender(), U256::from(9)).encode()]);
+			assert_eq!(valid.longevity, TRANSACTION_LONGEVITY);
+
+			assert_ok!(EVM::transact(Origin::NONE, transaction()));
+
+			let fee = U256::from(21_000) * U256::from(20_000_000_000u64);
+			assert_eq!(Accounts::get(H160::from([0x35; 20])).balance, ether(1));
+			assert_eq!(Accounts::get(sender()).balance, ether(1) - fee);
+
+			// the transaction can't be replayed.
+			assert_eq!(validate(transaction()), InvalidTransaction::Stale.into());
+			assert_noop!(EVM::transact(Origin::NONE, transaction()), Error::<Test>::InvalidNonce);
+		});
+	}
+
+	#[test]
+	fn transact_is_weighed_by_gas_limit() {
+		let call = Call::<Test>::transact(transaction());
+		assert_eq!(call.get_dispatch_info().weight, 21_000);
+	}
+
+	#[test]
+	fn transact_rejects_zero_gas_price() {
+		new_test_ext().execute_with(|| {
+			let mut transaction = transaction();
+			transaction.gas_price = U256::zero();
+
+			assert_eq!(validate(transaction.clone()), InvalidTransaction::Payment.into());
+			assert_noop!(EVM::transact(Origin::NONE, transaction), Error::<Test>::GasPriceTooLow);
+		});
+	}
+
+	#[test]
+	fn transact_rejects_other_chain_id() {
+		new_test_ext().execute_with(|| {
+			// the same recovery id, for chain id 2.
+			let mut transaction = transaction();
+			transaction.signature.v = 39;
+
+			assert_eq!(validate(transaction.clone()), InvalidTransaction::BadProof.into());
+			assert_noop!(EVM::transact(Origin::NONE, transaction), Error::<Test>::InvalidChainId);
+		});
+	}
+
+	#[test]
+	fn transact_rejects_malleable_signature() {
+		new_test_ext().execute_with(|| {
+			let mut transaction = transaction();
+			// `s` above half the curve order.
+			transaction.signature.s = H256::repeat_byte(0xff);
+
+			assert_eq!(validate(transaction.clone()), InvalidTransaction::BadProof.into());
+			assert_noop!(EVM::transact(Origin::NONE, transaction), Error::<Test>::InvalidSignature);
+		});
+	}
+
+	#[test]
+	fn transact_must_be_unsigned() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				EVM::transact(Origin::signed(1), transaction()),
+				sp_runtime::traits::BadOrigin,
+			);
+			assert!(validate(transaction()).is_ok());
+		});
+	}
 }
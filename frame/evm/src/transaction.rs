@@ -0,0 +1,258 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Ethereum transactions, as submitted through `eth_sendRawTransaction`.
+
+use sp_std::vec::Vec;
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use codec::{Encode, Decode};
+use sp_core::{U256, H256, H160};
+use sp_runtime::RuntimeDebug;
+use rlp::{Rlp, RlpStream, DecoderError};
+
+/// Half the order of the secp256k1 curve. As specified by EIP-2, signatures with a higher `s`
+/// are rejected, since `(r, n - s)` would be a second valid signature of the same transaction.
+const SECP256K1N_HALF: H256 = H256([
+	0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+	0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+	0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d,
+	0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+]);
+
+/// What a transaction does: call an existing account or create a new contract.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum TransactionAction {
+	/// Call the given address.
+	Call(H160),
+	/// Create a new contract.
+	Create,
+}
+
+impl rlp::Encodable for TransactionAction {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		match self {
+			TransactionAction::Call(address) => { s.append(address); },
+			TransactionAction::Create => { s.append_empty_data(); },
+		}
+	}
+}
+
+impl rlp::Decodable for TransactionAction {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.is_empty() {
+			Ok(TransactionAction::Create)
+		} else {
+			Ok(TransactionAction::Call(rlp.as_val()?))
+		}
+	}
+}
+
+/// The ECDSA signature of a transaction, with the recovery id encoded in `v` as specified by
+/// EIP-155.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct TransactionSignature {
+	/// Recovery id, possibly combined with the chain id.
+	pub v: u64,
+	/// The `r` value of the signature.
+	pub r: H256,
+	/// The `s` value of the signature.
+	pub s: H256,
+}
+
+impl TransactionSignature {
+	/// The chain id the transaction was signed for, or `None` if it is replayable on any chain.
+	pub fn chain_id(&self) -> Option<u64> {
+		if self.v >= 35 {
+			Some((self.v - 35) / 2)
+		} else {
+			None
+		}
+	}
+
+	/// The recovery id of the signature, if `v` is well-formed.
+	pub fn recovery_id(&self) -> Option<u8> {
+		match self.v {
+			27 | 28 => Some((self.v - 27) as u8),
+			v if v >= 35 => Some(((v - 35) % 2) as u8),
+			_ => None,
+		}
+	}
+}
+
+/// A signed legacy Ethereum transaction.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct Transaction {
+	/// Nonce of the sender.
+	pub nonce: U256,
+	/// Gas price the sender is willing to pay.
+	pub gas_price: U256,
+	/// Maximum gas this transaction may consume.
+	pub gas_limit: U256,
+	/// Whether this is a call or a contract creation.
+	pub action: TransactionAction,
+	/// Value transferred to the target.
+	pub value: U256,
+	/// Call data, or the init code of the contract to create.
+	pub input: Vec<u8>,
+	/// Signature of the sender.
+	pub signature: TransactionSignature,
+}
+
+impl Transaction {
+	/// The hash of the RLP-encoded transaction, as known to Ethereum tooling.
+	pub fn hash(&self) -> H256 {
+		H256::from(sp_io::hashing::keccak_256(&rlp::encode(self)))
+	}
+
+	/// The hash the sender signed.
+	pub fn message_hash(&self) -> H256 {
+		let chain_id = self.signature.chain_id();
+		let mut s = RlpStream::new_list(if chain_id.is_some() { 9 } else { 6 });
+		self.rlp_append_unsigned(&mut s);
+		if let Some(chain_id) = chain_id {
+			s.append(&chain_id);
+			s.append(&0u8);
+			s.append(&0u8);
+		}
+		H256::from(sp_io::hashing::keccak_256(&s.out()))
+	}
+
+	/// Recover the address of the sender from the signature.
+	///
+	/// Returns `None` if the signature is invalid or malleable.
+	pub fn sender(&self) -> Option<H160> {
+		if self.signature.s > SECP256K1N_HALF {
+			return None;
+		}
+
+		let mut sig = [0u8; 65];
+		sig[0..32].copy_from_slice(self.signature.r.as_bytes());
+		sig[32..64].copy_from_slice(self.signature.s.as_bytes());
+		sig[64] = self.signature.recovery_id()?;
+
+		let pubkey = sp_io::crypto::secp256k1_ecdsa_recover(&sig, self.message_hash().as_fixed_bytes())
+			.ok()?;
+		Some(H160::from(H256::from(sp_io::hashing::keccak_256(&pubkey))))
+	}
+
+	fn rlp_append_unsigned(&self, s: &mut RlpStream) {
+		s.append(&self.nonce);
+		s.append(&self.gas_price);
+		s.append(&self.gas_limit);
+		s.append(&self.action);
+		s.append(&self.value);
+		s.append(&self.input);
+	}
+}
+
+impl rlp::Encodable for Transaction {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(9);
+		self.rlp_append_unsigned(s);
+		s.append(&self.signature.v);
+		s.append(&U256::from_big_endian(self.signature.r.as_bytes()));
+		s.append(&U256::from_big_endian(self.signature.s.as_bytes()));
+	}
+}
+
+impl rlp::Decodable for Transaction {
+	fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+		if rlp.item_count()? != 9 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		let to_h256 = |value: U256| {
+			let mut bytes = [0u8; 32];
+			value.to_big_endian(&mut bytes);
+			H256::from(bytes)
+		};
+
+		Ok(Transaction {
+			nonce: rlp.val_at(0)?,
+			gas_price: rlp.val_at(1)?,
+			gas_limit: rlp.val_at(2)?,
+			action: rlp.val_at(3)?,
+			value: rlp.val_at(4)?,
+			input: rlp.val_at(5)?,
+			signature: TransactionSignature {
+				v: rlp.val_at(6)?,
+				r: to_h256(rlp.val_at(7)?),
+				s: to_h256(rlp.val_at(8)?),
+			},
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::hexdisplay::HexDisplay;
+
+	// The example of EIP-155, signed with the private key `0x4646...46`.
+	const SIGNED_TRANSACTION: &str = "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+
+	fn from_hex(s: &str) -> Vec<u8> {
+		(0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+	}
+
+	#[test]
+	fn decodes_and_recovers_eip155_transaction() {
+		let bytes = from_hex(SIGNED_TRANSACTION);
+		let transaction: Transaction = rlp::decode(&bytes).unwrap();
+
+		assert_eq!(transaction.nonce, U256::from(9));
+		assert_eq!(transaction.gas_price, U256::from(20_000_000_000u64));
+		assert_eq!(transaction.gas_limit, U256::from(21_000));
+		assert_eq!(transaction.action, TransactionAction::Call(H160::from([0x35; 20])));
+		assert_eq!(transaction.value, U256::from(1_000_000_000_000_000_000u64));
+		assert!(transaction.input.is_empty());
+		assert_eq!(transaction.signature.chain_id(), Some(1));
+
+		assert_eq!(
+			format!("{}", HexDisplay::from(transaction.message_hash().as_bytes())),
+			"daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53",
+		);
+		assert_eq!(
+			transaction.sender().map(|a| format!("{}", HexDisplay::from(a.as_bytes()))),
+			Some("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f".to_string()),
+		);
+
+		// encoding it again gives back the original bytes.
+		assert_eq!(rlp::encode(&transaction), bytes);
+	}
+
+	#[test]
+	fn rejects_high_s_signatures() {
+		let mut transaction: Transaction = rlp::decode(&from_hex(SIGNED_TRANSACTION)).unwrap();
+
+		// `(r, n - s)` with the other recovery id is a valid signature of the same message.
+		let n = U256::from_big_endian(&from_hex(
+			"fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+		));
+		let s = n - U256::from_big_endian(transaction.signature.s.as_bytes());
+		let mut bytes = [0u8; 32];
+		s.to_big_endian(&mut bytes);
+		transaction.signature.s = H256::from(bytes);
+		transaction.signature.v = 38;
+
+		assert_eq!(transaction.sender(), None);
+	}
+}
@@ -19,8 +19,9 @@
 //! This manages the GRANDPA authority set ready for the native code.
 //! These authorities are only for GRANDPA finality, not for consensus overall.
 //!
-//! In the future, it will also handle misbehavior reports, and on-chain
-//! finality notifications.
+//! It also handles equivocation reports: validators that vote for two different
+//! blocks in the same round can be reported, with the report being checked
+//! on-chain and the resulting offence passed on to e.g. `pallet-offences`.
 //!
 //! For full integration with GRANDPA, the `GrandpaApi` should be implemented.
 //! The necessary items are re-exported via the `fg_primitives` crate.
@@ -32,26 +33,60 @@ pub use sp_finality_grandpa as fg_primitives;
 
 use sp_std::prelude::*;
 use codec::{self as codec, Encode, Decode};
-use frame_support::{decl_event, decl_storage, decl_module, decl_error, storage};
-use sp_runtime::{
-	DispatchResult, generic::{DigestItem, OpaqueDigestItemId}, traits::Zero, Perbill,
+use frame_support::{
+	decl_event, decl_storage, decl_module, decl_error, storage, Parameter,
+	traits::KeyOwnerProofSystem,
 };
-use sp_staking::{
-	SessionIndex,
-	offence::{Offence, Kind},
+use sp_runtime::{
+	DispatchResult, KeyTypeId, generic::{DigestItem, OpaqueDigestItemId}, traits::Zero,
 };
+use sp_session::{GetSessionNumber, GetValidatorCount};
+use sp_staking::SessionIndex;
 use fg_primitives::{
-	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID, ScheduledChange, ConsensusLog, SetId, RoundNumber,
+	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID, ScheduledChange, ConsensusLog, SetId,
+	EquivocationProof,
 };
 pub use fg_primitives::{AuthorityId, AuthorityList, AuthorityWeight, VersionedAuthorityList};
-use frame_system::{self as system, ensure_signed, DigestOf};
+use frame_system::{self as system, ensure_signed, ensure_none, DigestOf};
 
+mod equivocation;
 mod mock;
 mod tests;
 
+pub use equivocation::{
+	EquivocationHandler, GrandpaEquivocationOffence, GrandpaTimeSlot, HandleEquivocation,
+};
+
 pub trait Trait: frame_system::Trait {
 	/// The event type of this module.
 	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The function call.
+	type Call: From<Call<Self>>;
+
+	/// The proof of key ownership, used for validating equivocation reports.
+	/// The proof must include the session index and validator count of the
+	/// session at which the equivocation occurred.
+	type KeyOwnerProof: Parameter + GetSessionNumber + GetValidatorCount;
+
+	/// The identification of a key owner, used when reporting equivocations.
+	type KeyOwnerIdentification: Parameter;
+
+	/// A system for proving ownership of keys, i.e. that a given key was part
+	/// of a validator set, needed for validating equivocation reports.
+	type KeyOwnerProofSystem: KeyOwnerProofSystem<
+		(KeyTypeId, AuthorityId),
+		Proof = Self::KeyOwnerProof,
+		IdentificationTuple = Self::KeyOwnerIdentification,
+	>;
+
+	/// The equivocation handling subsystem, defines methods to report an
+	/// offence (after the equivocation has been validated) and for submitting a
+	/// transaction to report an equivocation (from an offchain context).
+	/// NOTE: when enabling equivocation handling (i.e. this type isn't set to
+	/// `()`) you must include the `ValidateUnsigned` part of this module in the
+	/// runtime's `construct_runtime!` declaration.
+	type HandleEquivocation: HandleEquivocation<Self>;
 }
 
 /// A stored pending change, old format.
@@ -146,6 +181,10 @@ decl_error! {
 		ChangePending,
 		/// Cannot signal forced change so soon after last.
 		TooSoon,
+		/// A key ownership proof provided as part of an equivocation report is invalid.
+		InvalidKeyOwnershipProof,
+		/// An equivocation proof provided as part of an equivocation report is invalid.
+		InvalidEquivocationProof,
 	}
 }
 
@@ -189,13 +228,40 @@ decl_module! {
 
 		fn deposit_event() = default;
 
-		/// Report some misbehavior.
-		fn report_misbehavior(origin, _report: Vec<u8>) {
-			ensure_signed(origin)?;
-			// FIXME: https://github.com/paritytech/substrate/issues/1112
+		/// Report voter equivocation/misbehavior. This method will verify the
+		/// equivocation proof and validate the given key ownership proof
+		/// against the extracted offender. If both are valid, the offence
+		/// will be reported.
+		fn report_equivocation(
+			origin,
+			equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+			key_owner_proof: T::KeyOwnerProof,
+		) {
+			let reporter = ensure_signed(origin)?;
+
+			Self::do_report_equivocation(Some(reporter), equivocation_proof, key_owner_proof)?;
 		}
 
-		fn on_initialize() {
+		/// Report voter equivocation/misbehavior. This method will verify the
+		/// equivocation proof and validate the given key ownership proof
+		/// against the extracted offender. If both are valid, the offence
+		/// will be reported.
+		///
+		/// This extrinsic must be called unsigned. `ValidateUnsigned` only
+		/// accepts it into the transaction pool if the equivocation proof is
+		/// valid, the key ownership proof is checked when it is dispatched. The
+		/// offence is reported without a reporter.
+		fn report_equivocation_unsigned(
+			origin,
+			equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+			key_owner_proof: T::KeyOwnerProof,
+		) {
+			ensure_none(origin)?;
+
+			Self::do_report_equivocation(None, equivocation_proof, key_owner_proof)?;
+		}
+
+		fn on_runtime_upgrade() {
 			#[cfg(feature = "migrate-authorities")]
 			Self::migrate_authorities();
 		}
@@ -355,6 +421,81 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Submits an extrinsic to report an equivocation. This method will sign an
+	/// extrinsic with a call to `report_equivocation_unsigned` and submit it
+	/// to the transaction pool. Only useful in an offchain context.
+	pub fn submit_report_equivocation_extrinsic(
+		equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> Option<()> {
+		T::HandleEquivocation::submit_equivocation_report(equivocation_proof, key_owner_proof)
+			.ok()
+	}
+
+	fn do_report_equivocation(
+		reporter: Option<T::AccountId>,
+		equivocation_proof: EquivocationProof<T::Hash, T::BlockNumber>,
+		key_owner_proof: T::KeyOwnerProof,
+	) -> DispatchResult {
+		// we check the equivocation within the context of its set id (and
+		// associated session) and round. we also need to know the validator
+		// set count at the time of the offence since it is required to calculate
+		// the slash amount.
+		let set_id = equivocation_proof.set_id;
+		let round = equivocation_proof.round();
+		let session_index = key_owner_proof.session();
+		let validator_set_count = key_owner_proof.validator_count();
+
+		// validate the key ownership proof extracting the id of the offender.
+		let offender = T::KeyOwnerProofSystem::check_proof(
+			(fg_primitives::KEY_TYPE, equivocation_proof.offender().clone()),
+			key_owner_proof,
+		).ok_or(Error::<T>::InvalidKeyOwnershipProof)?;
+
+		// validate equivocation proof (check votes are different and
+		// signatures are valid).
+		if !fg_primitives::check_equivocation_proof(&equivocation_proof) {
+			Err(Error::<T>::InvalidEquivocationProof)?
+		}
+
+		// fetch the current and previous sets last session index. on the
+		// genesis set there's no previous set.
+		let previous_set_id_session_index = if set_id == 0 {
+			None
+		} else {
+			let session_index = Self::session_for_set(set_id - 1)
+				.ok_or(Error::<T>::InvalidEquivocationProof)?;
+
+			Some(session_index)
+		};
+
+		let set_id_session_index = Self::session_for_set(set_id)
+			.ok_or(Error::<T>::InvalidEquivocationProof)?;
+
+		// check that the session id for the membership proof is within the
+		// bounds of the set id reported in the equivocation.
+		if session_index > set_id_session_index ||
+			previous_set_id_session_index
+				.map(|previous_index| session_index <= previous_index)
+				.unwrap_or(false)
+		{
+			Err(Error::<T>::InvalidEquivocationProof)?
+		}
+
+		// report to the offences module rewarding the sender.
+		T::HandleEquivocation::report_offence(
+			reporter.into_iter().collect(),
+			GrandpaEquivocationOffence {
+				time_slot: GrandpaTimeSlot { set_id, round },
+				session_index,
+				validator_set_count,
+				offender,
+			},
+		);
+
+		Ok(())
+	}
+
 	/// Deposit one of this module's logs.
 	fn deposit_log(log: ConsensusLog<T::BlockNumber>) {
 		let log: DigestItem<T::Hash> = DigestItem::Consensus(GRANDPA_ENGINE_ID, log.encode());
@@ -369,6 +510,12 @@ impl<T: Trait> Module<T> {
 			);
 			Self::set_grandpa_authorities(authorities);
 		}
+
+		// NOTE: initialize first session of first set. this is necessary for
+		// the genesis set and session since we only update the set -> session
+		// mapping whenever a new session starts, i.e. the session index for the
+		// first session of the genesis set is never tracked.
+		SetIdSession::insert(0 as SetId, 0 as SessionIndex);
 	}
 
 	#[cfg(feature = "migrate-authorities")]
@@ -470,56 +617,3 @@ impl<T: Trait> pallet_finality_tracker::OnFinalizationStalled<T::BlockNumber> fo
 		<Stalled<T>>::put((further_wait, median));
 	}
 }
-
-/// A round number and set id which point on the time of an offence.
-#[derive(Copy, Clone, PartialOrd, Ord, Eq, PartialEq, Encode, Decode)]
-struct GrandpaTimeSlot {
-	// The order of these matters for `derive(Ord)`.
-	set_id: SetId,
-	round: RoundNumber,
-}
-
-// TODO [slashing]: Integrate this.
-/// A grandpa equivocation offence report.
-#[allow(dead_code)]
-struct GrandpaEquivocationOffence<FullIdentification> {
-	/// Time slot at which this incident happened.
-	time_slot: GrandpaTimeSlot,
-	/// The session index in which the incident happened.
-	session_index: SessionIndex,
-	/// The size of the validator set at the time of the offence.
-	validator_set_count: u32,
-	/// The authority which produced this equivocation.
-	offender: FullIdentification,
-}
-
-impl<FullIdentification: Clone> Offence<FullIdentification> for GrandpaEquivocationOffence<FullIdentification> {
-	const ID: Kind = *b"grandpa:equivoca";
-	type TimeSlot = GrandpaTimeSlot;
-
-	fn offenders(&self) -> Vec<FullIdentification> {
-		vec![self.offender.clone()]
-	}
-
-	fn session_index(&self) -> SessionIndex {
-		self.session_index
-	}
-
-	fn validator_set_count(&self) -> u32 {
-		self.validator_set_count
-	}
-
-	fn time_slot(&self) -> Self::TimeSlot {
-		self.time_slot
-	}
-
-	fn slash_fraction(
-		offenders_count: u32,
-		validator_set_count: u32,
-	) -> Perbill {
-		// the formula is min((3k / n)^2, 1)
-		let x = Perbill::from_rational_approximation(3 * offenders_count, validator_set_count);
-		// _ ^ 2
-		x.square()
-	}
-}
@@ -18,13 +18,24 @@
 
 #![cfg(test)]
 
-use sp_runtime::{Perbill, DigestItem, traits::IdentityLookup, testing::{Header, UintAuthorityId}};
+use std::cell::RefCell;
+use sp_runtime::{
+	Perbill, DigestItem, KeyTypeId, impl_opaque_keys,
+	traits::{IdentityLookup, ConvertInto, OnInitialize},
+	testing::{Header, UintAuthorityId, TestXt},
+};
 use sp_io;
-use frame_support::{impl_outer_origin, impl_outer_event, parameter_types, weights::Weight};
-use sp_core::H256;
+use frame_support::{
+	impl_outer_origin, impl_outer_event, parameter_types, weights::Weight, traits::KeyOwnerProofSystem,
+};
+use sp_core::{H256, ed25519};
+use sp_staking::{SessionIndex, offence::ReportOffence};
 use codec::{Encode, Decode};
-use crate::{AuthorityId, AuthorityList, GenesisConfig, Trait, Module, ConsensusLog};
-use sp_finality_grandpa::GRANDPA_ENGINE_ID;
+use crate::{
+	AuthorityId, AuthorityList, GenesisConfig, Trait, Module, ConsensusLog, EquivocationHandler,
+	GrandpaEquivocationOffence,
+};
+use sp_finality_grandpa::{GRANDPA_ENGINE_ID, KEY_TYPE};
 
 use frame_system as system;
 impl_outer_origin!{
@@ -39,8 +50,43 @@ pub fn grandpa_log(log: ConsensusLog<u64>) -> DigestItem<H256> {
 #[derive(Clone, PartialEq, Eq, Debug, Decode, Encode)]
 pub struct Test;
 
+impl_opaque_keys! {
+	pub struct TestSessionKeys {
+		pub grandpa_authority: Grandpa,
+	}
+}
+
+/// An extrinsic type used for tests.
+pub type Extrinsic = TestXt<crate::Call<Test>, ()>;
+type SubmitTransaction = frame_system::offchain::TransactionSubmitter<(), crate::Call<Test>, Extrinsic>;
+type IdentificationTuple = (u64, u64);
+type Offence = GrandpaEquivocationOffence<IdentificationTuple>;
+
+thread_local! {
+	pub static OFFENCES: RefCell<Vec<(Vec<u64>, Offence)>> = RefCell::new(vec![]);
+}
+
+/// A mock offence report handler.
+pub struct OffenceHandler;
+impl ReportOffence<u64, IdentificationTuple, Offence> for OffenceHandler {
+	fn report_offence(reporters: Vec<u64>, offence: Offence) {
+		OFFENCES.with(|l| l.borrow_mut().push((reporters, offence)));
+	}
+}
+
 impl Trait for Test {
 	type Event = TestEvent;
+	type Call = crate::Call<Test>;
+	type KeyOwnerProofSystem = Historical;
+	type KeyOwnerProof =
+		<Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(KeyTypeId, AuthorityId)>>::Proof;
+	type KeyOwnerIdentification =
+		<Self::KeyOwnerProofSystem as KeyOwnerProofSystem<(KeyTypeId, AuthorityId)>>::IdentificationTuple;
+	type HandleEquivocation = EquivocationHandler<
+		Self::KeyOwnerIdentification,
+		OffenceHandler,
+		SubmitTransaction,
+	>;
 }
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
@@ -67,13 +113,42 @@ impl frame_system::Trait for Test {
 	type ModuleToIndex = ();
 }
 
+parameter_types! {
+	pub const Period: u64 = 1;
+	pub const Offset: u64 = 0;
+	pub const DisabledValidatorsThreshold: Perbill = Perbill::from_percent(33);
+}
+
+impl pallet_session::Trait for Test {
+	type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
+	// the validator set never changes in these tests, the sessions only rotate.
+	type OnSessionEnding = pallet_session::historical::NoteHistoricalRoot<Test, ()>;
+	type SessionHandler = (Grandpa, );
+	type ValidatorId = u64;
+	type ValidatorIdOf = ConvertInto;
+	type Keys = TestSessionKeys;
+	type Event = TestEvent;
+	type SelectInitialValidators = ();
+	type DisabledValidatorsThreshold = DisabledValidatorsThreshold;
+}
+
+impl pallet_session::historical::Trait for Test {
+	type FullIdentification = u64;
+	type FullIdentificationOf = ConvertInto;
+}
+
 mod grandpa {
 	pub use crate::Event;
 }
 
+mod session {
+	pub use pallet_session::Event;
+}
+
 impl_outer_event!{
 	pub enum TestEvent for Test {
 		grandpa,
+		session,
 	}
 }
 
@@ -91,5 +166,39 @@ pub fn new_test_ext(authorities: Vec<(u64, u64)>) -> sp_io::TestExternalities {
 	t.into()
 }
 
+/// Build externalities where the GRANDPA authorities are set up by the session module, the
+/// validator with id `i + 1` owning the key of `authorities[i]`.
+pub fn new_test_ext_with_session(authorities: &[ed25519::Pair]) -> sp_io::TestExternalities {
+	use sp_core::Pair;
+
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	GenesisConfig {
+		authorities: vec![],
+	}.assimilate_storage::<Test>(&mut t).unwrap();
+	pallet_session::GenesisConfig::<Test> {
+		keys: authorities.iter().enumerate()
+			.map(|(i, pair)| (i as u64 + 1, TestSessionKeys { grandpa_authority: pair.public().into() }))
+			.collect(),
+	}.assimilate_storage(&mut t).unwrap();
+	t.into()
+}
+
+/// Rotate sessions, one per block, until `session_index` is the current session.
+pub fn start_session(session_index: SessionIndex) {
+	for i in Session::current_index()..session_index {
+		System::set_block_number(i as u64 + 1);
+		Session::on_initialize(System::block_number());
+	}
+
+	assert_eq!(Session::current_index(), session_index);
+}
+
+/// Prove that `authority` is part of the current validator set.
+pub fn key_owner_proof(authority: &AuthorityId) -> pallet_session::historical::Proof {
+	Historical::prove((KEY_TYPE, authority)).unwrap()
+}
+
 pub type System = frame_system::Module<Test>;
+pub type Session = pallet_session::Module<Test>;
+pub type Historical = pallet_session::historical::Module<Test>;
 pub type Grandpa = Module<Test>;
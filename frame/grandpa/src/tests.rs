@@ -21,6 +21,7 @@
 use sp_runtime::{testing::{H256, Digest}, traits::{Header, OnFinalize}};
 use crate::mock::*;
 use frame_system::{EventRecord, Phase};
+use frame_support::{assert_ok, assert_noop};
 use codec::{Decode, Encode};
 use fg_primitives::ScheduledChange;
 use super::*;
@@ -319,6 +320,212 @@ fn time_slot_have_sane_ord() {
 	assert!(FIXTURE.windows(2).all(|f| f[0] < f[1]));
 }
 
+fn equivocation_proof(
+	pair: &sp_core::ed25519::Pair,
+	set_id: SetId,
+	round: fg_primitives::RoundNumber,
+	first: (H256, u64),
+	second: (H256, u64),
+) -> EquivocationProof<H256, u64> {
+	use sp_core::Pair;
+
+	let signed = |(target_hash, target_number)| {
+		let vote = fg_primitives::Vote { target_hash, target_number };
+		// prevotes are encoded with message index 0.
+		let payload = (0u8, &vote, round, set_id).encode();
+		(vote, pair.sign(&payload).into())
+	};
+
+	EquivocationProof::new(
+		set_id,
+		fg_primitives::Equivocation::Prevote(fg_primitives::VoteEquivocation {
+			round_number: round,
+			identity: pair.public().into(),
+			first: signed(first),
+			second: signed(second),
+		}),
+	)
+}
+
+#[test]
+fn check_equivocation_proof_works() {
+	use sp_core::Pair;
+
+	new_test_ext(vec![]).execute_with(|| {
+		let pair = sp_core::ed25519::Pair::from_seed(&[1; 32]);
+		let other = sp_core::ed25519::Pair::from_seed(&[2; 32]);
+
+		// votes for different blocks in the same round are an equivocation.
+		let proof = equivocation_proof(&pair, 1, 2, (H256::repeat_byte(1), 1), (H256::repeat_byte(2), 1));
+		assert!(fg_primitives::check_equivocation_proof(&proof));
+
+		// voting twice for the same block isn't.
+		let proof = equivocation_proof(&pair, 1, 2, (H256::repeat_byte(1), 1), (H256::repeat_byte(1), 1));
+		assert!(!fg_primitives::check_equivocation_proof(&proof));
+
+		// the signatures must be from the reported offender.
+		let mut proof = equivocation_proof(&pair, 1, 2, (H256::repeat_byte(1), 1), (H256::repeat_byte(2), 1));
+		if let fg_primitives::Equivocation::Prevote(ref mut equivocation) = proof.equivocation {
+			equivocation.identity = other.public().into();
+		}
+		assert!(!fg_primitives::check_equivocation_proof(&proof));
+
+		// the signatures must be for the reported set id.
+		let mut proof = equivocation_proof(&pair, 1, 2, (H256::repeat_byte(1), 1), (H256::repeat_byte(2), 1));
+		proof.set_id = 2;
+		assert!(!fg_primitives::check_equivocation_proof(&proof));
+	});
+}
+
+fn authority_pairs() -> Vec<sp_core::ed25519::Pair> {
+	use sp_core::Pair;
+
+	(1..=3).map(|i| sp_core::ed25519::Pair::from_seed(&[i; 32])).collect()
+}
+
+#[test]
+fn report_equivocation_reports_offence() {
+	use sp_core::Pair;
+
+	let authorities = authority_pairs();
+	new_test_ext_with_session(&authorities).execute_with(|| {
+		let offender = &authorities[0];
+		let key_owner_proof = key_owner_proof(&offender.public().into());
+
+		// the proof is checked against the historical root of a past session.
+		start_session(1);
+
+		let proof = equivocation_proof(offender, 0, 1, (H256::repeat_byte(1), 1), (H256::repeat_byte(2), 1));
+		assert_ok!(Grandpa::report_equivocation(Origin::signed(2), proof, key_owner_proof));
+
+		OFFENCES.with(|l| {
+			let offences = l.borrow();
+			assert_eq!(offences.len(), 1);
+
+			let (reporters, offence) = &offences[0];
+			assert_eq!(reporters, &vec![2]);
+			assert_eq!(offence.offender, (1, 1));
+			assert_eq!(offence.session_index, 0);
+			assert_eq!(offence.validator_set_count, 3);
+			assert!(offence.time_slot == GrandpaTimeSlot { set_id: 0, round: 1 });
+		});
+	});
+}
+
+#[test]
+fn report_equivocation_rejects_invalid_key_owner_proof() {
+	use sp_core::Pair;
+
+	let authorities = authority_pairs();
+	new_test_ext_with_session(&authorities).execute_with(|| {
+		let offender = &authorities[0];
+		// a valid proof, but for the key of another authority.
+		let key_owner_proof = key_owner_proof(&authorities[1].public().into());
+
+		start_session(1);
+
+		let proof = equivocation_proof(offender, 0, 1, (H256::repeat_byte(1), 1), (H256::repeat_byte(2), 1));
+		assert_noop!(
+			Grandpa::report_equivocation(Origin::signed(2), proof, key_owner_proof),
+			Error::<Test>::InvalidKeyOwnershipProof,
+		);
+
+		assert!(OFFENCES.with(|l| l.borrow().is_empty()));
+	});
+}
+
+#[test]
+fn report_equivocation_rejects_key_owner_proof_outside_of_set_sessions() {
+	use sp_core::Pair;
+
+	let authorities = authority_pairs();
+	new_test_ext_with_session(&authorities).execute_with(|| {
+		let offender = &authorities[0];
+		let first_session_proof = key_owner_proof(&offender.public().into());
+
+		// set 0 spans sessions 0 and 1, set 1 starts at session 2.
+		start_session(1);
+		CurrentSetId::put(1);
+		start_session(2);
+		let third_session_proof = key_owner_proof(&offender.public().into());
+		start_session(3);
+		assert_eq!(Grandpa::session_for_set(0), Some(1));
+		assert_eq!(Grandpa::session_for_set(1), Some(3));
+
+		let proof = |set_id| equivocation_proof(
+			offender, set_id, 1, (H256::repeat_byte(1), 1), (H256::repeat_byte(2), 1),
+		);
+
+		// the session of the proof is before the set.
+		assert_noop!(
+			Grandpa::report_equivocation(Origin::signed(2), proof(1), first_session_proof.clone()),
+			Error::<Test>::InvalidEquivocationProof,
+		);
+		// the session of the proof is after the set.
+		assert_noop!(
+			Grandpa::report_equivocation(Origin::signed(2), proof(0), third_session_proof.clone()),
+			Error::<Test>::InvalidEquivocationProof,
+		);
+		// the set is unknown.
+		assert_noop!(
+			Grandpa::report_equivocation(Origin::signed(2), proof(2), third_session_proof.clone()),
+			Error::<Test>::InvalidEquivocationProof,
+		);
+		assert!(OFFENCES.with(|l| l.borrow().is_empty()));
+
+		assert_ok!(Grandpa::report_equivocation(Origin::signed(2), proof(0), first_session_proof));
+		assert_ok!(Grandpa::report_equivocation(Origin::signed(2), proof(1), third_session_proof));
+		assert_eq!(OFFENCES.with(|l| l.borrow().len()), 2);
+	});
+}
+
+#[test]
+fn unsigned_equivocation_report_is_validated() {
+	use sp_core::Pair;
+	use sp_runtime::transaction_validity::InvalidTransaction;
+
+	let authorities = authority_pairs();
+	new_test_ext_with_session(&authorities).execute_with(|| {
+		let offender = &authorities[0];
+		let key_owner_proof = key_owner_proof(&offender.public().into());
+		#[allow(deprecated)]
+		let validate = |call: Call<Test>| <Grandpa as frame_support::unsigned::ValidateUnsigned>::validate_unsigned(&call);
+
+		let valid_proof = equivocation_proof(
+			offender, 0, 1, (H256::repeat_byte(1), 1), (H256::repeat_byte(2), 1),
+		);
+		let validity = validate(Call::report_equivocation_unsigned(
+			valid_proof.clone(),
+			key_owner_proof.clone(),
+		)).unwrap();
+		assert_eq!(validity.provides, vec![(offender.public(), 0u64, 1u64).encode()]);
+		assert!(!validity.propagate);
+
+		// voting twice for the same block is not an equivocation.
+		let invalid_proof = equivocation_proof(
+			offender, 0, 1, (H256::repeat_byte(1), 1), (H256::repeat_byte(1), 1),
+		);
+		assert_eq!(
+			validate(Call::report_equivocation_unsigned(invalid_proof, key_owner_proof.clone())),
+			InvalidTransaction::BadProof.into(),
+		);
+
+		// only unsigned reports are accepted.
+		assert_eq!(
+			validate(Call::report_equivocation(valid_proof, key_owner_proof)),
+			InvalidTransaction::Call.into(),
+		);
+	});
+}
+
+#[test]
+fn genesis_set_is_tracked_in_first_session() {
+	new_test_ext(vec![(1, 1), (2, 1), (3, 1)]).execute_with(|| {
+		assert_eq!(Grandpa::session_for_set(0), Some(0));
+		assert_eq!(Grandpa::session_for_set(1), None);
+	});
+}
+
 #[test]
 #[cfg(feature = "migrate-authorities")]
 fn authorities_migration() {
@@ -0,0 +1,156 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Identity pallet benchmarking.
+
+use super::*;
+
+use frame_system::RawOrigin;
+use sp_runtime::traits::Bounded;
+use frame_benchmarking::{benchmarks, account};
+
+use crate::Module as Identity;
+
+const SEED: u32 = 0;
+const MAX_REGISTRARS: u32 = 50;
+const MAX_EXTRA_FIELDS: u32 = 100;
+
+fn dispatch_err(e: sp_runtime::DispatchError) -> &'static str {
+	e.into()
+}
+
+// Create an account with enough free balance to pay for any deposit.
+fn funded_account<T: Trait>(name: &'static str, index: u32) -> T::AccountId {
+	let who: T::AccountId = account(name, index, SEED);
+	let _ = T::Currency::make_free_balance_be(&who, BalanceOf::<T>::max_value() / 2u32.into());
+	who
+}
+
+// Adds `r` registrars to the Identity Pallet. These registrars will have set fees and fields.
+fn add_registrars<T: Trait>(r: u32) -> Result<(), &'static str> {
+	for i in 0..r {
+		let registrar = funded_account::<T>("registrar", i);
+		Identity::<T>::add_registrar(RawOrigin::Root.into(), registrar.clone()).map_err(dispatch_err)?;
+		Identity::<T>::set_fee(RawOrigin::Signed(registrar.clone()).into(), i, 10u32.into())
+			.map_err(dispatch_err)?;
+		let fields = IdentityFields(
+			IdentityField::Display | IdentityField::Legal | IdentityField::Web | IdentityField::Riot
+			| IdentityField::Email | IdentityField::PgpFingerprint | IdentityField::Image | IdentityField::Twitter
+		);
+		Identity::<T>::set_fields(RawOrigin::Signed(registrar).into(), i, fields)
+			.map_err(dispatch_err)?;
+	}
+
+	assert_eq!(Registrars::<T>::get().len(), r as usize);
+	Ok(())
+}
+
+// Adds `s` sub-accounts to the identity of `who`.
+fn add_sub_accounts<T: Trait>(who: &T::AccountId, s: u32) -> Result<(), &'static str> {
+	let subs = (0..s)
+		.map(|i| (account("sub", i, SEED), Data::Raw(vec![0; 32])))
+		.collect::<Vec<_>>();
+	Identity::<T>::set_subs(RawOrigin::Signed(who.clone()).into(), subs).map_err(dispatch_err)
+}
+
+// Sets an identity with `x` additional fields for `who` and gives it a judgement from all
+// of the first `r` registrars.
+fn add_judged_identity<T: Trait>(who: &T::AccountId, r: u32, x: u32) -> Result<(), &'static str> {
+	Identity::<T>::set_identity(RawOrigin::Signed(who.clone()).into(), create_identity_info::<T>(x))
+		.map_err(dispatch_err)?;
+
+	for i in 0..r {
+		let registrar: T::AccountId = account("registrar", i, SEED);
+		Identity::<T>::provide_judgement(
+			RawOrigin::Signed(registrar).into(),
+			i,
+			T::Lookup::unlookup(who.clone()),
+			Judgement::Reasonable,
+		).map_err(dispatch_err)?;
+	}
+
+	Ok(())
+}
+
+// This creates an `IdentityInfo` object with `num_fields` extra fields.
+// All data is pre-populated with some arbitrary bytes.
+fn create_identity_info<T: Trait>(num_fields: u32) -> IdentityInfo {
+	let data = Data::Raw(vec![0; 32]);
+
+	IdentityInfo {
+		additional: vec![(data.clone(), data.clone()); num_fields as usize],
+		display: data.clone(),
+		legal: data.clone(),
+		web: data.clone(),
+		riot: data.clone(),
+		email: data.clone(),
+		pgp_fingerprint: Some([0; 20]),
+		image: data.clone(),
+		twitter: data,
+	}
+}
+
+benchmarks! {
+	add_registrar(r in 1 .. MAX_REGISTRARS) {
+		add_registrars::<T>(r)?;
+		let registrar: T::AccountId = account("registrar", r + 1, SEED);
+	}: add_registrar(RawOrigin::Root, registrar)
+
+	set_identity(r in 1 .. MAX_REGISTRARS, x in 1 .. MAX_EXTRA_FIELDS) {
+		add_registrars::<T>(r)?;
+		let caller = funded_account::<T>("caller", 0);
+		add_judged_identity::<T>(&caller, r, x)?;
+		let info = create_identity_info::<T>(x);
+	}: set_identity(RawOrigin::Signed(caller), info)
+
+	set_subs(s in 1 .. { T::MaximumSubAccounts::get() }) {
+		let caller = funded_account::<T>("caller", 0);
+		add_judged_identity::<T>(&caller, 0, MAX_EXTRA_FIELDS)?;
+		add_sub_accounts::<T>(&caller, s)?;
+		let subs = (0..s)
+			.map(|i| (account("new_sub", i, SEED), Data::Raw(vec![1; 32])))
+			.collect::<Vec<_>>();
+	}: set_subs(RawOrigin::Signed(caller), subs)
+
+	clear_identity(
+		r in 1 .. MAX_REGISTRARS,
+		s in 1 .. { T::MaximumSubAccounts::get() },
+		x in 1 .. MAX_EXTRA_FIELDS
+	) {
+		add_registrars::<T>(r)?;
+		let caller = funded_account::<T>("caller", 0);
+		add_judged_identity::<T>(&caller, r, x)?;
+		add_sub_accounts::<T>(&caller, s)?;
+	}: clear_identity(RawOrigin::Signed(caller))
+
+	request_judgement(r in 1 .. MAX_REGISTRARS, x in 1 .. MAX_EXTRA_FIELDS) {
+		add_registrars::<T>(r)?;
+		let caller = funded_account::<T>("caller", 0);
+		add_judged_identity::<T>(&caller, r - 1, x)?;
+	}: request_judgement(RawOrigin::Signed(caller), r - 1, 10u32.into())
+
+	kill_identity(
+		r in 1 .. MAX_REGISTRARS,
+		s in 1 .. { T::MaximumSubAccounts::get() },
+		x in 1 .. MAX_EXTRA_FIELDS
+	) {
+		add_registrars::<T>(r)?;
+		let caller = funded_account::<T>("caller", 0);
+		add_judged_identity::<T>(&caller, r, x)?;
+		add_sub_accounts::<T>(&caller, s)?;
+		let caller_lookup = T::Lookup::unlookup(caller);
+	}: kill_identity(RawOrigin::Root, caller_lookup)
+}
@@ -0,0 +1,951 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Proxy Module
+//! A module allowing accounts to give permission to other accounts to dispatch types of calls from
+//! their signed origin.
+//!
+//! - [`proxy::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! An account (the "real" account) can register any number of delegates, up to `MaxProxies`,
+//! each with a `ProxyType`. A delegate can then dispatch calls from the signed origin of the real
+//! account, as long as the call passes the `InstanceFilter` of its proxy type. A deposit is
+//! reserved from the real account for the storage of its proxies.
+//!
+//! Delegates can be registered with a delay. Such a delegate must first `announce` the hash of
+//! the call it intends to make and can only execute it once the delay has passed, giving the
+//! real account the chance to `reject_announcement` in the meantime. This is useful for cold
+//! storage accounts, which can delegate without giving up control over their funds. Each pending
+//! announcement holds a deposit of the delegate.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `proxy` - Dispatch a call from the origin of an account for which the sender is a proxy
+//!   without delay.
+//! * `add_proxy` - Register a delegate for the sender.
+//! * `remove_proxy` - Unregister a delegate of the sender.
+//! * `remove_proxies` - Unregister all delegates of the sender.
+//! * `announce` - Announce a call that will later be made through `proxy_announced`.
+//! * `remove_announcement` - Remove an announcement made by the sender.
+//! * `reject_announcement` - Remove an announcement made by a delegate of the sender.
+//! * `proxy_announced` - Dispatch a previously announced call once its delay has passed.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::{
+	DispatchResult, DispatchError, RuntimeDebug,
+	traits::{Dispatchable, Zero, Hash, Member, Saturating},
+};
+use frame_support::{
+	decl_module, decl_event, decl_error, decl_storage, Parameter, ensure,
+	traits::{Get, ReservableCurrency, Currency, InstanceFilter},
+	weights::{GetDispatchInfo, SimpleDispatchInfo, FunctionOf, DispatchClass},
+	dispatch::IsSubType,
+};
+use frame_system::{self as system, ensure_signed};
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+/// Configuration trait.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The overarching call type.
+	type Call: Parameter + Dispatchable<Origin=Self::Origin> + GetDispatchInfo
+		+ IsSubType<Module<Self>, Self>;
+
+	/// The currency mechanism.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// A kind of proxy; specified with the proxy and passed in to the `InstanceFilter` to decide
+	/// whether a given call may be proxied under this type.
+	///
+	/// The default value is expected to allow any call.
+	type ProxyType: Parameter + Member + Ord + PartialOrd + InstanceFilter<<Self as Trait>::Call>
+		+ Default;
+
+	/// The base amount of currency needed to reserve for creating a proxy.
+	///
+	/// This is held for an additional storage item whose value size is
+	/// `sizeof(Balance)` bytes and whose key size is `sizeof(AccountId)` bytes.
+	type ProxyDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount of currency needed per proxy added.
+	///
+	/// This is held for adding 32 bytes plus an instance of `ProxyType` more into a pre-existing
+	/// storage value.
+	type ProxyDepositFactor: Get<BalanceOf<Self>>;
+
+	/// The maximum amount of proxies allowed for a single account.
+	type MaxProxies: Get<u16>;
+
+	/// The maximum amount of time-delayed announcements that are allowed to be pending.
+	type MaxPending: Get<u32>;
+
+	/// The base amount of currency needed to reserve for creating an announcement.
+	///
+	/// This is held when a new storage item holding a `Balance` is created (typically 16 bytes).
+	type AnnouncementDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount of currency needed per announcement made.
+	///
+	/// This is held for adding an `AccountId`, `Hash` and `BlockNumber` (typically 68 bytes)
+	/// into a pre-existing storage value.
+	type AnnouncementDepositFactor: Get<BalanceOf<Self>>;
+}
+
+/// The parameters under which a particular account has a proxy relationship with some other
+/// account.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug)]
+pub struct ProxyDefinition<AccountId, ProxyType, BlockNumber> {
+	/// The account which may act on behalf of another.
+	pub delegate: AccountId,
+	/// A value defining the subset of calls that it is allowed to make.
+	pub proxy_type: ProxyType,
+	/// The number of blocks that an announcement must be in place for before the corresponding
+	/// call may be dispatched. If zero, then no announcement is needed.
+	pub delay: BlockNumber,
+}
+
+/// Details surrounding a specific instance of an announcement to make a call.
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
+pub struct Announcement<AccountId, Hash, BlockNumber> {
+	/// The account which the proxy is acting on behalf of.
+	pub real: AccountId,
+	/// The hash of the call to be made.
+	pub call_hash: Hash,
+	/// The height at which the announcement was made.
+	pub height: BlockNumber,
+}
+
+type ProxyDefinitionOf<T> = ProxyDefinition<
+	<T as frame_system::Trait>::AccountId,
+	<T as Trait>::ProxyType,
+	<T as frame_system::Trait>::BlockNumber,
+>;
+
+type AnnouncementOf<T> = Announcement<
+	<T as frame_system::Trait>::AccountId,
+	<T as frame_system::Trait>::Hash,
+	<T as frame_system::Trait>::BlockNumber,
+>;
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Proxy {
+		/// The set of account proxies. Maps the account which has delegated to the accounts
+		/// which are being delegated to, together with the amount held on deposit.
+		pub Proxies get(fn proxies): map hasher(twox_64_concat) T::AccountId
+			=> (Vec<ProxyDefinitionOf<T>>, BalanceOf<T>);
+
+		/// The announcements made by the proxy (key), together with the amount held on deposit.
+		pub Announcements get(fn announcements): map hasher(twox_64_concat) T::AccountId
+			=> (Vec<AnnouncementOf<T>>, BalanceOf<T>);
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// There are too many proxies registered or too many announcements pending.
+		TooMany,
+		/// Proxy registration or announcement not found.
+		NotFound,
+		/// Sender is not a proxy of the account to be proxied.
+		NotProxy,
+		/// A call which is incompatible with the proxy type's filter was attempted.
+		Unproxyable,
+		/// Account is already a proxy.
+		Duplicate,
+		/// Cannot add self as proxy.
+		NoSelfProxy,
+		/// Announcement, if made at all, was made too recently.
+		Unannounced,
+	}
+}
+
+decl_event! {
+	/// Events type.
+	pub enum Event<T> where
+		AccountId = <T as frame_system::Trait>::AccountId,
+		Hash = <T as frame_system::Trait>::Hash,
+	{
+		/// A proxy was executed correctly, with the given result.
+		ProxyExecuted(DispatchResult),
+		/// An announcement was placed to make a call in the future. First param is the real
+		/// account, second is the proxy and third is the hash of the call.
+		Announced(AccountId, AccountId, Hash),
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// Deposit one of this module's events by using the default implementation.
+		fn deposit_event() = default;
+
+		/// The base amount of currency needed to reserve for creating a proxy.
+		const ProxyDepositBase: BalanceOf<T> = T::ProxyDepositBase::get();
+
+		/// The amount of currency needed per proxy added.
+		const ProxyDepositFactor: BalanceOf<T> = T::ProxyDepositFactor::get();
+
+		/// The maximum amount of proxies allowed for a single account.
+		const MaxProxies: u16 = T::MaxProxies::get();
+
+		/// Dispatch the given `call` from an account that the sender is authorised for through
+		/// `add_proxy`.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Parameters:
+		/// - `real`: The account that the proxy will make a call on behalf of.
+		/// - `force_proxy_type`: Specify the exact proxy type to be used and checked for this call.
+		/// - `call`: The call to be made by the `real` account.
+		///
+		/// Fails if the sender is only a time-delayed proxy of `real`, such proxies must use
+		/// `announce` and `proxy_announced` instead.
+		///
+		/// # <weight>
+		/// - One storage read `O(P)` where `P` is the number of proxies of `real`.
+		/// - The weight of the `call`.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&T::AccountId, &Option<T::ProxyType>, &Box<<T as Trait>::Call>)| {
+				args.2.get_dispatch_info().weight.saturating_add(10_000)
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		fn proxy(origin,
+			real: T::AccountId,
+			force_proxy_type: Option<T::ProxyType>,
+			call: Box<<T as Trait>::Call>,
+		) {
+			let who = ensure_signed(origin)?;
+			let def = Self::find_proxy(&real, &who, force_proxy_type, &call)?;
+			ensure!(def.delay.is_zero(), Error::<T>::Unannounced);
+
+			Self::do_proxy(real, *call)?;
+		}
+
+		/// Register a proxy account for the sender that is able to make calls on its behalf.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Parameters:
+		/// - `delegate`: The account that the `caller` would like to make a proxy.
+		/// - `proxy_type`: The permissions allowed for this proxy account.
+		/// - `delay`: The announcement period required of the initial proxy. Will generally be
+		/// zero.
+		///
+		/// # <weight>
+		/// - One balance-reserve operation.
+		/// - One storage mutation (codec `O(P)`).
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn add_proxy(origin, delegate: T::AccountId, proxy_type: T::ProxyType, delay: T::BlockNumber) {
+			let who = ensure_signed(origin)?;
+			ensure!(who != delegate, Error::<T>::NoSelfProxy);
+
+			let (mut proxies, old_deposit) = Proxies::<T>::get(&who);
+			ensure!(proxies.len() < T::MaxProxies::get() as usize, Error::<T>::TooMany);
+
+			let proxy_def = ProxyDefinition { delegate, proxy_type, delay };
+			let i = proxies.binary_search(&proxy_def).err().ok_or(Error::<T>::Duplicate)?;
+			proxies.insert(i, proxy_def);
+
+			Self::store_proxies(&who, proxies, old_deposit)?;
+		}
+
+		/// Unregister a proxy account for the sender.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Parameters:
+		/// - `delegate`: The account that the `caller` would like to remove as a proxy.
+		/// - `proxy_type`: The permissions currently enabled for the removed proxy account.
+		/// - `delay`: The announcement period of the removed proxy account.
+		///
+		/// # <weight>
+		/// - One balance-unreserve operation.
+		/// - One storage mutation (codec `O(P)`).
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn remove_proxy(origin, delegate: T::AccountId, proxy_type: T::ProxyType, delay: T::BlockNumber) {
+			let who = ensure_signed(origin)?;
+
+			let (mut proxies, old_deposit) = Proxies::<T>::get(&who);
+			let proxy_def = ProxyDefinition { delegate, proxy_type, delay };
+			let i = proxies.binary_search(&proxy_def).ok().ok_or(Error::<T>::NotFound)?;
+			proxies.remove(i);
+
+			Self::store_proxies(&who, proxies, old_deposit)?;
+		}
+
+		/// Unregister all proxy accounts for the sender.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// WARNING: This may be called on accounts created by `utility::as_sub`, however if done,
+		/// then the unreserved fees will be inaccessible. **All access to this account will be
+		/// lost.**
+		///
+		/// # <weight>
+		/// - One balance-unreserve operation.
+		/// - One storage removal.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn remove_proxies(origin) {
+			let who = ensure_signed(origin)?;
+			let (_, old_deposit) = Proxies::<T>::take(&who);
+			T::Currency::unreserve(&who, old_deposit);
+		}
+
+		/// Publish the hash of a proxy-call that will be made in the future.
+		///
+		/// This must be called some number of blocks before the corresponding `proxy_announced`
+		/// is attempted if the delay associated with the proxy relationship is greater than zero.
+		///
+		/// No more than `MaxPending` announcements may be made at any one time.
+		///
+		/// This will take a deposit of `AnnouncementDepositFactor` as well as
+		/// `AnnouncementDepositBase` if there are no other pending announcements.
+		///
+		/// The dispatch origin for this call must be _Signed_ and a proxy of `real`.
+		///
+		/// Parameters:
+		/// - `real`: The account that the proxy will make a call on behalf of.
+		/// - `call_hash`: The hash of the call to be made by the `real` account.
+		///
+		/// # <weight>
+		/// - One storage read `O(P)`.
+		/// - One balance-reserve operation.
+		/// - One storage mutation (codec `O(A)`) where `A` is the number of pending
+		///   announcements of the sender.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn announce(origin, real: T::AccountId, call_hash: T::Hash) {
+			let who = ensure_signed(origin)?;
+			Proxies::<T>::get(&real).0.into_iter()
+				.find(|x| &x.delegate == &who)
+				.ok_or(Error::<T>::NotProxy)?;
+
+			let (mut pending, old_deposit) = Announcements::<T>::get(&who);
+			ensure!(pending.len() < T::MaxPending::get() as usize, Error::<T>::TooMany);
+			pending.push(Announcement {
+				real: real.clone(),
+				call_hash: call_hash.clone(),
+				height: system::Module::<T>::block_number(),
+			});
+
+			Self::store_announcements(&who, pending, old_deposit)?;
+			Self::deposit_event(RawEvent::Announced(real, who, call_hash));
+		}
+
+		/// Remove a given announcement.
+		///
+		/// May be called by a proxy account to remove a call they previously announced and return
+		/// the deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Parameters:
+		/// - `real`: The account that the proxy will make a call on behalf of.
+		/// - `call_hash`: The hash of the call to be made by the `real` account.
+		///
+		/// # <weight>
+		/// - One balance-unreserve operation.
+		/// - One storage mutation (codec `O(A)`).
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn remove_announcement(origin, real: T::AccountId, call_hash: T::Hash) {
+			let who = ensure_signed(origin)?;
+			Self::edit_announcements(&who, |ann| ann.real != real || ann.call_hash != call_hash)?;
+		}
+
+		/// Remove the given announcement of a delegate.
+		///
+		/// May be called by a target (proxied) account to remove a call that one of their delegates
+		/// (`delegate`) has announced they want to execute. The deposit is returned.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Parameters:
+		/// - `delegate`: The account that previously announced the call.
+		/// - `call_hash`: The hash of the call to be made.
+		///
+		/// # <weight>
+		/// - One balance-unreserve operation.
+		/// - One storage mutation (codec `O(A)`).
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn reject_announcement(origin, delegate: T::AccountId, call_hash: T::Hash) {
+			let who = ensure_signed(origin)?;
+			Self::edit_announcements(&delegate, |ann| ann.real != who || ann.call_hash != call_hash)?;
+		}
+
+		/// Dispatch the given `call` from an account that the sender is authorised for through
+		/// `add_proxy`, once the call has been announced for at least the delay of the proxy.
+		///
+		/// Removes any corresponding announcement(s).
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// Parameters:
+		/// - `delegate`: The account that previously announced the call.
+		/// - `real`: The account that the proxy will make a call on behalf of.
+		/// - `force_proxy_type`: Specify the exact proxy type to be used and checked for this call.
+		/// - `call`: The call to be made by the `real` account.
+		///
+		/// # <weight>
+		/// - One storage read `O(P)`.
+		/// - One balance-unreserve operation.
+		/// - One storage mutation (codec `O(A)`).
+		/// - The weight of the `call`.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&T::AccountId, &T::AccountId, &Option<T::ProxyType>, &Box<<T as Trait>::Call>)| {
+				args.3.get_dispatch_info().weight.saturating_add(50_000)
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		fn proxy_announced(origin,
+			delegate: T::AccountId,
+			real: T::AccountId,
+			force_proxy_type: Option<T::ProxyType>,
+			call: Box<<T as Trait>::Call>,
+		) {
+			ensure_signed(origin)?;
+			let def = Self::find_proxy(&real, &delegate, force_proxy_type, &call)?;
+
+			let call_hash = T::Hashing::hash_of(&call);
+			let now = system::Module::<T>::block_number();
+			Self::edit_announcements(&delegate, |ann| {
+				ann.real != real || ann.call_hash != call_hash || now.saturating_sub(ann.height) < def.delay
+			}).map_err(|_| Error::<T>::Unannounced)?;
+
+			Self::do_proxy(real, *call)?;
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Find the proxy definition of `delegate` for `real` that may dispatch `call`, optionally of
+	/// a specific type.
+	///
+	/// If `delegate` has several definitions that may dispatch `call`, one without a delay is
+	/// preferred.
+	fn find_proxy(
+		real: &T::AccountId,
+		delegate: &T::AccountId,
+		force_proxy_type: Option<T::ProxyType>,
+		call: &<T as Trait>::Call,
+	) -> Result<ProxyDefinitionOf<T>, DispatchError> {
+		let f = |x: &ProxyDefinitionOf<T>| -> bool {
+			&x.delegate == delegate && force_proxy_type.as_ref().map_or(true, |y| &x.proxy_type == y)
+		};
+
+		let matches = Proxies::<T>::get(real).0.into_iter().filter(f).collect::<Vec<_>>();
+		ensure!(!matches.is_empty(), Error::<T>::NotProxy);
+
+		let allowed = matches.into_iter()
+			.filter(|x| Self::allowed(&x.proxy_type, call))
+			.collect::<Vec<_>>();
+		let def = allowed.iter()
+			.find(|x| x.delay.is_zero())
+			.or_else(|| allowed.first())
+			.cloned()
+			.ok_or(Error::<T>::Unproxyable)?;
+		Ok(def)
+	}
+
+	/// Whether a proxy of the given type may dispatch `call`.
+	///
+	/// Calls to this module are restricted further, so that a proxy can never grant or revoke
+	/// more permissions than it has itself.
+	fn allowed(proxy_type: &T::ProxyType, call: &<T as Trait>::Call) -> bool {
+		match call.is_sub_type() {
+			Some(Call::add_proxy(_, pt, _)) | Some(Call::remove_proxy(_, pt, _))
+				if proxy_type != pt && !proxy_type.is_superset(pt) => false,
+			Some(Call::remove_proxies()) if proxy_type != &T::ProxyType::default() => false,
+			_ => proxy_type.filter(call),
+		}
+	}
+
+	fn do_proxy(real: T::AccountId, call: <T as Trait>::Call) -> DispatchResult {
+		let result = call.dispatch(frame_system::RawOrigin::Signed(real).into());
+		Self::deposit_event(RawEvent::ProxyExecuted(result));
+
+		Ok(())
+	}
+
+	/// Store the proxies of `who`, adjusting the reserved deposit accordingly.
+	fn store_proxies(
+		who: &T::AccountId,
+		proxies: Vec<ProxyDefinitionOf<T>>,
+		old_deposit: BalanceOf<T>,
+	) -> DispatchResult {
+		let new_deposit = if proxies.is_empty() {
+			Zero::zero()
+		} else {
+			T::ProxyDepositBase::get() + T::ProxyDepositFactor::get() * (proxies.len() as u32).into()
+		};
+		Self::rebalance_deposit(who, old_deposit, new_deposit)?;
+
+		if proxies.is_empty() {
+			Proxies::<T>::remove(who);
+		} else {
+			Proxies::<T>::insert(who, (proxies, new_deposit));
+		}
+
+		Ok(())
+	}
+
+	/// Store the pending announcements of `who`, adjusting the reserved deposit accordingly.
+	fn store_announcements(
+		who: &T::AccountId,
+		pending: Vec<AnnouncementOf<T>>,
+		old_deposit: BalanceOf<T>,
+	) -> DispatchResult {
+		let new_deposit = if pending.is_empty() {
+			Zero::zero()
+		} else {
+			T::AnnouncementDepositBase::get()
+				+ T::AnnouncementDepositFactor::get() * (pending.len() as u32).into()
+		};
+		Self::rebalance_deposit(who, old_deposit, new_deposit)?;
+
+		if pending.is_empty() {
+			Announcements::<T>::remove(who);
+		} else {
+			Announcements::<T>::insert(who, (pending, new_deposit));
+		}
+
+		Ok(())
+	}
+
+	/// Keep only the announcements of `delegate` for which `f` returns `true`.
+	///
+	/// Fails if no announcement was removed.
+	fn edit_announcements<F: FnMut(&AnnouncementOf<T>) -> bool>(
+		delegate: &T::AccountId,
+		f: F,
+	) -> DispatchResult {
+		let (mut pending, old_deposit) = Announcements::<T>::get(delegate);
+		let orig_pending_len = pending.len();
+		pending.retain(f);
+		ensure!(orig_pending_len > pending.len(), Error::<T>::NotFound);
+
+		Self::store_announcements(delegate, pending, old_deposit)
+	}
+
+	fn rebalance_deposit(
+		who: &T::AccountId,
+		old_deposit: BalanceOf<T>,
+		new_deposit: BalanceOf<T>,
+	) -> DispatchResult {
+		if new_deposit > old_deposit {
+			T::Currency::reserve(who, new_deposit - old_deposit)?;
+		} else if new_deposit < old_deposit {
+			T::Currency::unreserve(who, old_deposit - new_deposit);
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types, impl_outer_dispatch,
+		weights::Weight, impl_outer_event, RuntimeDebug,
+	};
+	use codec::{Encode, Decode};
+	use sp_core::H256;
+	use sp_runtime::{Perbill, traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+	use crate as proxy;
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			pallet_balances<T>,
+			proxy<T>,
+		}
+	}
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			frame_system::System,
+			pallet_balances::Balances,
+			proxy::Proxy,
+		}
+	}
+
+	// For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = Call;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 0;
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnReapAccount = System;
+		type OnNewAccount = ();
+		type Event = TestEvent;
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const ProxyDepositBase: u64 = 1;
+		pub const ProxyDepositFactor: u64 = 1;
+		pub const MaxProxies: u16 = 4;
+		pub const MaxPending: u32 = 2;
+		pub const AnnouncementDepositBase: u64 = 1;
+		pub const AnnouncementDepositFactor: u64 = 1;
+	}
+	#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Encode, Decode, RuntimeDebug)]
+	pub enum ProxyType {
+		Any,
+		JustTransfer,
+	}
+	impl Default for ProxyType { fn default() -> Self { Self::Any } }
+	impl InstanceFilter<Call> for ProxyType {
+		fn filter(&self, c: &Call) -> bool {
+			match self {
+				ProxyType::Any => true,
+				ProxyType::JustTransfer => match c {
+					Call::Balances(pallet_balances::Call::transfer(..)) => true,
+					_ => false,
+				},
+			}
+		}
+		fn is_superset(&self, o: &Self) -> bool {
+			self == &ProxyType::Any || self == o
+		}
+	}
+	impl Trait for Test {
+		type Event = TestEvent;
+		type Call = Call;
+		type Currency = Balances;
+		type ProxyType = ProxyType;
+		type ProxyDepositBase = ProxyDepositBase;
+		type ProxyDepositFactor = ProxyDepositFactor;
+		type MaxProxies = MaxProxies;
+		type MaxPending = MaxPending;
+		type AnnouncementDepositBase = AnnouncementDepositBase;
+		type AnnouncementDepositFactor = AnnouncementDepositFactor;
+	}
+
+	type System = frame_system::Module<Test>;
+	type Balances = pallet_balances::Module<Test>;
+	type Proxy = Module<Test>;
+
+	use frame_system::Call as SystemCall;
+	use pallet_balances::Call as BalancesCall;
+	use pallet_balances::Error as BalancesError;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: vec![(1, 10), (2, 10), (3, 10), (4, 10), (5, 2)],
+		}.assimilate_storage(&mut t).unwrap();
+		let mut ext = sp_io::TestExternalities::new(t);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	fn last_event() -> TestEvent {
+		system::Module::<Test>::events().pop().map(|e| e.event).expect("Event expected")
+	}
+
+	fn expect_event<E: Into<TestEvent>>(e: E) {
+		assert_eq!(last_event(), e.into());
+	}
+
+	fn announcements(who: u64) -> Vec<(u64, H256, u64)> {
+		Announcements::<Test>::get(who).0.into_iter()
+			.map(|a| (a.real, a.call_hash, a.height))
+			.collect()
+	}
+
+	#[test]
+	fn add_remove_proxies_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 0));
+			assert_noop!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 0), Error::<Test>::Duplicate);
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::JustTransfer, 0));
+			assert_eq!(Balances::reserved_balance(1), 3);
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::Any, 0));
+			assert_eq!(Balances::reserved_balance(1), 4);
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 4, ProxyType::Any, 0));
+			assert_eq!(Balances::reserved_balance(1), 5);
+			assert_noop!(Proxy::add_proxy(Origin::signed(1), 4, ProxyType::JustTransfer, 0), Error::<Test>::TooMany);
+			assert_noop!(Proxy::remove_proxy(Origin::signed(1), 3, ProxyType::JustTransfer, 0), Error::<Test>::NotFound);
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 4, ProxyType::Any, 0));
+			assert_eq!(Balances::reserved_balance(1), 4);
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 3, ProxyType::Any, 0));
+			assert_eq!(Balances::reserved_balance(1), 3);
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 2, ProxyType::Any, 0));
+			assert_eq!(Balances::reserved_balance(1), 2);
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 2, ProxyType::JustTransfer, 0));
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert!(!Proxies::<Test>::exists(1));
+		});
+	}
+
+	#[test]
+	fn cannot_add_self_as_proxy() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(Proxy::add_proxy(Origin::signed(1), 1, ProxyType::Any, 0), Error::<Test>::NoSelfProxy);
+		});
+	}
+
+	#[test]
+	fn cannot_add_proxy_without_balance() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(5), 3, ProxyType::Any, 0));
+			assert_eq!(Balances::reserved_balance(5), 2);
+			assert_noop!(
+				Proxy::add_proxy(Origin::signed(5), 4, ProxyType::Any, 0),
+				BalancesError::<Test, _>::InsufficientBalance
+			);
+		});
+	}
+
+	#[test]
+	fn remove_proxies_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 0));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::JustTransfer, 0));
+			assert_eq!(Balances::reserved_balance(1), 3);
+			assert_ok!(Proxy::remove_proxies(Origin::signed(1)));
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert!(!Proxies::<Test>::exists(1));
+		});
+	}
+
+	#[test]
+	fn filtering_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 0));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::JustTransfer, 0));
+
+			let call = Box::new(Call::Balances(BalancesCall::transfer(6, 1)));
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, None, call.clone()));
+			expect_event(RawEvent::ProxyExecuted(Ok(())));
+			assert_ok!(Proxy::proxy(Origin::signed(3), 1, None, call.clone()));
+			expect_event(RawEvent::ProxyExecuted(Ok(())));
+			assert_eq!(Balances::free_balance(6), 2);
+
+			let call = Box::new(Call::System(SystemCall::remark(vec![])));
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, None, call.clone()));
+			expect_event(RawEvent::ProxyExecuted(Ok(())));
+			assert_noop!(Proxy::proxy(Origin::signed(3), 1, None, call.clone()), Error::<Test>::Unproxyable);
+
+			assert_noop!(Proxy::proxy(Origin::signed(4), 1, None, call.clone()), Error::<Test>::NotProxy);
+			assert_noop!(
+				Proxy::proxy(Origin::signed(2), 1, Some(ProxyType::JustTransfer), call.clone()),
+				Error::<Test>::NotProxy
+			);
+		});
+	}
+
+	#[test]
+	fn proxies_cannot_escalate_permissions() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 0));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::JustTransfer, 0));
+
+			// a restricted proxy can neither grant nor revoke stronger permissions.
+			let call = Box::new(Call::Proxy(proxy::Call::add_proxy(4, ProxyType::Any, 0)));
+			assert_noop!(Proxy::proxy(Origin::signed(3), 1, None, call.clone()), Error::<Test>::Unproxyable);
+			let call = Box::new(Call::Proxy(proxy::Call::remove_proxy(2, ProxyType::Any, 0)));
+			assert_noop!(Proxy::proxy(Origin::signed(3), 1, None, call.clone()), Error::<Test>::Unproxyable);
+			let call = Box::new(Call::Proxy(proxy::Call::remove_proxies()));
+			assert_noop!(Proxy::proxy(Origin::signed(3), 1, None, call.clone()), Error::<Test>::Unproxyable);
+
+			// while an unrestricted one can.
+			let call = Box::new(Call::Proxy(proxy::Call::add_proxy(4, ProxyType::JustTransfer, 0)));
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, None, call.clone()));
+			expect_event(RawEvent::ProxyExecuted(Ok(())));
+			assert_eq!(Proxies::<Test>::get(1).0.len(), 3);
+			let call = Box::new(Call::Proxy(proxy::Call::remove_proxies()));
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, None, call.clone()));
+			expect_event(RawEvent::ProxyExecuted(Ok(())));
+			assert!(!Proxies::<Test>::exists(1));
+		});
+	}
+
+	#[test]
+	fn delayed_proxy_requires_announcement() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 1));
+			let call = Box::new(Call::Balances(BalancesCall::transfer(6, 1)));
+			let call_hash = BlakeTwo256::hash_of(&call);
+
+			assert_noop!(Proxy::proxy(Origin::signed(2), 1, None, call.clone()), Error::<Test>::Unannounced);
+			assert_noop!(
+				Proxy::proxy_announced(Origin::signed(0), 2, 1, None, call.clone()),
+				Error::<Test>::Unannounced
+			);
+
+			assert_noop!(Proxy::announce(Origin::signed(3), 1, call_hash), Error::<Test>::NotProxy);
+			assert_ok!(Proxy::announce(Origin::signed(2), 1, call_hash));
+			expect_event(RawEvent::Announced(1, 2, call_hash));
+			assert_eq!(announcements(2), vec![(1, call_hash, 1)]);
+			assert_eq!(Balances::reserved_balance(2), 2);
+
+			// the delay has not passed yet.
+			assert_noop!(
+				Proxy::proxy_announced(Origin::signed(0), 2, 1, None, call.clone()),
+				Error::<Test>::Unannounced
+			);
+
+			System::set_block_number(2);
+			assert_ok!(Proxy::proxy_announced(Origin::signed(0), 2, 1, None, call.clone()));
+			expect_event(RawEvent::ProxyExecuted(Ok(())));
+			assert_eq!(Balances::free_balance(6), 1);
+			assert_eq!(announcements(2), vec![]);
+			assert_eq!(Balances::reserved_balance(2), 0);
+		});
+	}
+
+	#[test]
+	fn non_delayed_proxy_is_preferred() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 1));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::JustTransfer, 0));
+
+			// the delayed `Any` proxy comes first, but the call goes through the non-delayed one.
+			let call = Box::new(Call::Balances(BalancesCall::transfer(6, 1)));
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, None, call.clone()));
+			expect_event(RawEvent::ProxyExecuted(Ok(())));
+			assert_eq!(Balances::free_balance(6), 1);
+
+			// forcing the delayed type still requires an announcement.
+			assert_noop!(
+				Proxy::proxy(Origin::signed(2), 1, Some(ProxyType::Any), call.clone()),
+				Error::<Test>::Unannounced
+			);
+
+			// only the delayed proxy may dispatch this call.
+			let call = Box::new(Call::System(SystemCall::remark(vec![])));
+			assert_noop!(Proxy::proxy(Origin::signed(2), 1, None, call.clone()), Error::<Test>::Unannounced);
+		});
+	}
+
+	#[test]
+	fn proxy_allowing_the_call_is_used_when_announced() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::Any, 1));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2, ProxyType::JustTransfer, 0));
+
+			// the non-delayed `JustTransfer` proxy may not dispatch the call, the delayed `Any` one may.
+			let call = Box::new(Call::System(SystemCall::remark(vec![])));
+			assert_ok!(Proxy::announce(Origin::signed(2), 1, BlakeTwo256::hash_of(&call)));
+
+			System::set_block_number(2);
+			assert_ok!(Proxy::proxy_announced(Origin::signed(0), 2, 1, None, call.clone()));
+			expect_event(RawEvent::ProxyExecuted(Ok(())));
+
+			assert_noop!(
+				Proxy::proxy_announced(Origin::signed(0), 2, 1, Some(ProxyType::JustTransfer), call.clone()),
+				Error::<Test>::Unproxyable
+			);
+		});
+	}
+
+	#[test]
+	fn announcements_are_bounded_and_can_be_removed() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3, ProxyType::Any, 1));
+			assert_ok!(Proxy::add_proxy(Origin::signed(2), 3, ProxyType::Any, 1));
+
+			assert_ok!(Proxy::announce(Origin::signed(3), 1, [1; 32].into()));
+			assert_ok!(Proxy::announce(Origin::signed(3), 2, [2; 32].into()));
+			assert_eq!(Balances::reserved_balance(3), 3);
+			assert_noop!(Proxy::announce(Origin::signed(3), 1, [3; 32].into()), Error::<Test>::TooMany);
+
+			assert_noop!(
+				Proxy::remove_announcement(Origin::signed(3), 1, [2; 32].into()),
+				Error::<Test>::NotFound
+			);
+			assert_ok!(Proxy::remove_announcement(Origin::signed(3), 1, [1; 32].into()));
+			assert_eq!(announcements(3), vec![(2, H256::from([2; 32]), 1)]);
+			assert_eq!(Balances::reserved_balance(3), 2);
+
+			// only the real account of an announcement can reject it.
+			assert_noop!(
+				Proxy::reject_announcement(Origin::signed(1), 3, [2; 32].into()),
+				Error::<Test>::NotFound
+			);
+			assert_ok!(Proxy::reject_announcement(Origin::signed(2), 3, [2; 32].into()));
+			assert_eq!(announcements(3), vec![]);
+			assert_eq!(Balances::reserved_balance(3), 0);
+		});
+	}
+}
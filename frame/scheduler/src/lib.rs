@@ -0,0 +1,739 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Scheduler
+//! A module for scheduling dispatches.
+//!
+//! - [`scheduler::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//! - [`Module`](./struct.Module.html)
+//!
+//! ## Overview
+//!
+//! This module exposes capabilities for scheduling dispatches to occur at a specified block
+//! number or at a specified period. These scheduled dispatches may be named or anonymous and may
+//! be canceled.
+//!
+//! Scheduled dispatches are executed in `on_initialize` of the block they are scheduled for, in
+//! order of their priority. Dispatches are only executed while their accumulated weight stays
+//! within `MaximumWeight`; the others are postponed to the next block. Dispatches with a priority
+//! of `schedule::HARD_DEADLINE` or higher are always executed in the block they ask for.
+//!
+//! Dispatches are made with the origin they were scheduled with. Periodic dispatches are repeated
+//! relative to the block they were due at, even if an execution was postponed.
+//!
+//! Other modules can schedule dispatches through the `schedule::Anon` and `schedule::Named`
+//! traits, which the module implements.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `schedule` - schedule a dispatch, which may be periodic, to occur at a specified block and
+//!   with a specified priority.
+//! * `cancel` - cancel a scheduled dispatch, specified by block number and index.
+//! * `schedule_named` - augments the `schedule` interface with an additional `Vec<u8>` parameter
+//!   that can be used for identification.
+//! * `cancel_named` - the named complement to the cancel function.
+
+// Ensure we're `no_std` when compiling for Wasm.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::{prelude::*, convert::TryFrom};
+use codec::{Encode, Decode};
+use sp_runtime::{RuntimeDebug, DispatchResult, traits::{Zero, One, Dispatchable, BadOrigin}};
+use frame_support::{
+	decl_module, decl_storage, decl_event, decl_error, ensure, Parameter,
+	traits::{Get, EnsureOrigin, schedule},
+	weights::{GetDispatchInfo, SimpleDispatchInfo, Weight},
+};
+use frame_system::{self as system, ensure_root};
+
+/// Our pallet's configuration trait. All our types and constants go in here. If the
+/// pallet is dependent on specific other pallets, then their configuration traits
+/// should be added to our implied traits list.
+///
+/// `system::Trait` should always be included in our implied traits.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The encodable caller origin, i.e. the origins of the pallets that calls can be scheduled
+	/// by. Scheduled calls are dispatched with the origin they were scheduled by, origins that
+	/// can't be converted into this type can't schedule calls.
+	type PalletsOrigin: Parameter
+		+ From<system::RawOrigin<Self::AccountId>>
+		+ TryFrom<<Self as system::Trait>::Origin>
+		+ Into<<Self as system::Trait>::Origin>;
+
+	/// The aggregated call type.
+	type Call: Parameter + Dispatchable<Origin=<Self as system::Trait>::Origin> + GetDispatchInfo;
+
+	/// The maximum weight that may be scheduled per block for any dispatchables of less priority
+	/// than `schedule::HARD_DEADLINE`.
+	type MaximumWeight: Get<Weight>;
+
+	/// Required origin to schedule or cancel calls through the module's dispatchables.
+	type ScheduleOrigin: EnsureOrigin<<Self as system::Trait>::Origin>;
+}
+
+/// The location of a scheduled task that can be used to remove it.
+pub type TaskAddress<BlockNumber> = (BlockNumber, u32);
+
+/// Information regarding an item to be executed in the future.
+#[derive(Clone, RuntimeDebug, Encode, Decode)]
+pub struct Scheduled<Call, BlockNumber, PalletsOrigin> {
+	/// The unique identity for this task, if there is one.
+	maybe_id: Option<Vec<u8>>,
+	/// This task's priority.
+	priority: schedule::Priority,
+	/// The call to be dispatched.
+	call: Call,
+	/// If the call is periodic, then this points to the information concerning that.
+	maybe_periodic: Option<schedule::Period<BlockNumber>>,
+	/// The block the task is due at. This is behind the block of its agenda if the task was
+	/// postponed, and is what the next execution of a periodic task is counted from.
+	due: BlockNumber,
+	/// The origin the call is dispatched with.
+	origin: PalletsOrigin,
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Scheduler {
+		/// Items to be executed, indexed by the block number that they should be executed on.
+		pub Agenda: map hasher(twox_64_concat) T::BlockNumber
+			=> Vec<Option<Scheduled<<T as Trait>::Call, T::BlockNumber, T::PalletsOrigin>>>;
+
+		/// Lookup from identity to the block number and index of the task.
+		Lookup: map hasher(twox_64_concat) Vec<u8> => Option<TaskAddress<T::BlockNumber>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where <T as system::Trait>::BlockNumber {
+		/// A task was scheduled at the given block and index.
+		Scheduled(BlockNumber, u32),
+		/// The task at the given block and index was canceled.
+		Canceled(BlockNumber, u32),
+		/// A task was dispatched, together with its identity, if any, and the dispatch result.
+		Dispatched(TaskAddress<BlockNumber>, Option<Vec<u8>>, DispatchResult),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// Failed to schedule a call, because a task with the same identity already exists.
+		FailedToSchedule,
+		/// The task to cancel could not be found.
+		NotFound,
+	}
+}
+
+decl_module! {
+	/// Scheduler module declaration.
+	pub struct Module<T: Trait> for enum Call where origin: <T as system::Trait>::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Anonymously schedule a task.
+		///
+		/// The call is dispatched with the origin of this call.
+		///
+		/// # <weight>
+		/// - One storage mutation (codec `O(R)`), where `R` is the number of tasks already
+		///   scheduled in the same block.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn schedule(origin,
+			when: T::BlockNumber,
+			maybe_periodic: Option<schedule::Period<T::BlockNumber>>,
+			priority: schedule::Priority,
+			call: Box<<T as Trait>::Call>,
+		) {
+			T::ScheduleOrigin::try_origin(origin.clone())
+				.map(|_| ())
+				.or_else(ensure_root)?;
+
+			let origin = T::PalletsOrigin::try_from(origin).map_err(|_| BadOrigin)?;
+			Self::do_schedule(when, maybe_periodic, priority, origin, *call);
+		}
+
+		/// Cancel an anonymously scheduled task.
+		///
+		/// # <weight>
+		/// - One storage mutation (codec `O(R)`).
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn cancel(origin, when: T::BlockNumber, index: u32) {
+			T::ScheduleOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)?;
+
+			Self::do_cancel((when, index))?;
+		}
+
+		/// Schedule a named task.
+		///
+		/// The call is dispatched with the origin of this call.
+		///
+		/// # <weight>
+		/// - One storage mutation (codec `O(R)`).
+		/// - One lookup insertion.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn schedule_named(origin,
+			id: Vec<u8>,
+			when: T::BlockNumber,
+			maybe_periodic: Option<schedule::Period<T::BlockNumber>>,
+			priority: schedule::Priority,
+			call: Box<<T as Trait>::Call>,
+		) {
+			T::ScheduleOrigin::try_origin(origin.clone())
+				.map(|_| ())
+				.or_else(ensure_root)?;
+
+			let origin = T::PalletsOrigin::try_from(origin).map_err(|_| BadOrigin)?;
+			Self::do_schedule_named(id, when, maybe_periodic, priority, origin, *call)?;
+		}
+
+		/// Cancel a named scheduled task.
+		///
+		/// # <weight>
+		/// - One storage mutation (codec `O(R)`).
+		/// - One lookup removal.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn cancel_named(origin, id: Vec<u8>) {
+			T::ScheduleOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)?;
+
+			Self::do_cancel_named(id)?;
+		}
+
+		fn on_initialize(now: T::BlockNumber) {
+			let limit = T::MaximumWeight::get();
+			let mut queued = Agenda::<T>::take(now).into_iter()
+				.enumerate()
+				.filter_map(|(index, s)| s.map(|inner| (index as u32, inner)))
+				.collect::<Vec<_>>();
+			// stable sort, so tasks of equal priority keep their scheduling order.
+			queued.sort_by_key(|(_, s)| s.priority);
+
+			let mut cumulative_weight: Weight = Zero::zero();
+			let mut postponed = Vec::new();
+
+			for (order, (index, mut s)) in queued.into_iter().enumerate() {
+				let weight = s.call.get_dispatch_info().weight;
+				let next_weight = cumulative_weight.saturating_add(weight);
+
+				// The first task and any task with a hard deadline is always executed; the others
+				// only as long as they fit into the block's scheduler budget.
+				if order > 0 && s.priority > schedule::HARD_DEADLINE && next_weight > limit {
+					postponed.push(s);
+					continue
+				}
+				cumulative_weight = next_weight;
+
+				let origin: <T as system::Trait>::Origin = s.origin.clone().into();
+				let result = s.call.clone().dispatch(origin);
+				let maybe_id = s.maybe_id.clone();
+
+				match s.maybe_periodic {
+					Some((period, count)) if count > 1 => {
+						s.maybe_periodic = if count > 2 { Some((period, count - 1)) } else { None };
+						// Count from when the task was due, so that postponing it doesn't shift
+						// the following executions.
+						s.due = s.due + period;
+						Self::place(s.due.max(now + One::one()), s);
+					},
+					_ => if let Some(ref id) = s.maybe_id {
+						Lookup::<T>::remove(id);
+					},
+				}
+
+				Self::deposit_event(RawEvent::Dispatched((now, index), maybe_id, result));
+			}
+
+			// Tasks that did not fit are tried again in the next block.
+			let next = now + One::one();
+			for s in postponed {
+				Self::place(next, s);
+			}
+
+			system::Module::<T>::register_extra_weight_unchecked(cumulative_weight);
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Append the task to the agenda of block `when`, updating its lookup entry if named.
+	fn place(
+		when: T::BlockNumber,
+		s: Scheduled<<T as Trait>::Call, T::BlockNumber, T::PalletsOrigin>,
+	) -> TaskAddress<T::BlockNumber> {
+		let index = Agenda::<T>::decode_len(when).unwrap_or(0) as u32;
+		if let Some(ref id) = s.maybe_id {
+			Lookup::<T>::insert(id, (when, index));
+		}
+		Agenda::<T>::append_or_insert(when, &[Some(s)][..]);
+
+		(when, index)
+	}
+
+	/// Tasks can't be scheduled for the current or a past block, such tasks are scheduled for
+	/// the next block instead. Periodic tasks with a zero period or a single execution are
+	/// treated as one-off tasks.
+	fn normalize(
+		when: T::BlockNumber,
+		maybe_periodic: Option<schedule::Period<T::BlockNumber>>,
+	) -> (T::BlockNumber, Option<schedule::Period<T::BlockNumber>>) {
+		let earliest = system::Module::<T>::block_number() + One::one();
+		let maybe_periodic = maybe_periodic.filter(|(period, count)| !period.is_zero() && *count > 1);
+
+		(when.max(earliest), maybe_periodic)
+	}
+
+	fn do_schedule(
+		when: T::BlockNumber,
+		maybe_periodic: Option<schedule::Period<T::BlockNumber>>,
+		priority: schedule::Priority,
+		origin: T::PalletsOrigin,
+		call: <T as Trait>::Call,
+	) -> TaskAddress<T::BlockNumber> {
+		let (when, maybe_periodic) = Self::normalize(when, maybe_periodic);
+		let s = Scheduled { maybe_id: None, priority, call, maybe_periodic, due: when, origin };
+		let (when, index) = Self::place(when, s);
+
+		Self::deposit_event(RawEvent::Scheduled(when, index));
+		(when, index)
+	}
+
+	fn do_cancel((when, index): TaskAddress<T::BlockNumber>) -> DispatchResult {
+		let mut agenda = Agenda::<T>::get(when);
+		let s = agenda.get_mut(index as usize)
+			.and_then(Option::take)
+			.ok_or(Error::<T>::NotFound)?;
+		// Don't leave an agenda of canceled tasks behind.
+		if agenda.iter().all(Option::is_none) {
+			Agenda::<T>::remove(when);
+		} else {
+			Agenda::<T>::insert(when, agenda);
+		}
+
+		if let Some(id) = s.maybe_id {
+			Lookup::<T>::remove(id);
+		}
+		Self::deposit_event(RawEvent::Canceled(when, index));
+
+		Ok(())
+	}
+
+	fn do_schedule_named(
+		id: Vec<u8>,
+		when: T::BlockNumber,
+		maybe_periodic: Option<schedule::Period<T::BlockNumber>>,
+		priority: schedule::Priority,
+		origin: T::PalletsOrigin,
+		call: <T as Trait>::Call,
+	) -> Result<TaskAddress<T::BlockNumber>, Error<T>> {
+		// ensure id it is unique
+		ensure!(!Lookup::<T>::exists(&id), Error::<T>::FailedToSchedule);
+
+		let (when, maybe_periodic) = Self::normalize(when, maybe_periodic);
+		let s = Scheduled { maybe_id: Some(id), priority, call, maybe_periodic, due: when, origin };
+		let (when, index) = Self::place(when, s);
+
+		Self::deposit_event(RawEvent::Scheduled(when, index));
+		Ok((when, index))
+	}
+
+	fn do_cancel_named(id: Vec<u8>) -> DispatchResult {
+		let (when, index) = Lookup::<T>::get(&id).ok_or(Error::<T>::NotFound)?;
+		Self::do_cancel((when, index))
+	}
+}
+
+impl<T: Trait> schedule::Anon<T::BlockNumber, <T as Trait>::Call, system::RawOrigin<T::AccountId>>
+	for Module<T>
+{
+	type Address = TaskAddress<T::BlockNumber>;
+
+	fn schedule(
+		when: T::BlockNumber,
+		maybe_periodic: Option<schedule::Period<T::BlockNumber>>,
+		priority: schedule::Priority,
+		origin: system::RawOrigin<T::AccountId>,
+		call: <T as Trait>::Call,
+	) -> Self::Address {
+		Self::do_schedule(when, maybe_periodic, priority, origin.into(), call)
+	}
+
+	fn cancel(address: Self::Address) -> Result<(), ()> {
+		Self::do_cancel(address).map_err(|_| ())
+	}
+}
+
+impl<T: Trait> schedule::Named<T::BlockNumber, <T as Trait>::Call, system::RawOrigin<T::AccountId>>
+	for Module<T>
+{
+	type Address = TaskAddress<T::BlockNumber>;
+
+	fn schedule_named(
+		id: Vec<u8>,
+		when: T::BlockNumber,
+		maybe_periodic: Option<schedule::Period<T::BlockNumber>>,
+		priority: schedule::Priority,
+		origin: system::RawOrigin<T::AccountId>,
+		call: <T as Trait>::Call,
+	) -> Result<Self::Address, ()> {
+		Self::do_schedule_named(id, when, maybe_periodic, priority, origin.into(), call).map_err(|_| ())
+	}
+
+	fn cancel_named(id: Vec<u8>) -> Result<(), ()> {
+		Self::do_cancel_named(id).map_err(|_| ())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		impl_outer_event, impl_outer_origin, impl_outer_dispatch, parameter_types, assert_ok,
+		assert_noop, traits::Contains, weights::{DispatchClass, FunctionOf},
+	};
+	use sp_core::H256;
+	use sp_runtime::{
+		Perbill,
+		testing::Header,
+		traits::{BlakeTwo256, IdentityLookup, OnInitialize, OnFinalize},
+	};
+	use crate as scheduler;
+
+	mod logger {
+		use super::*;
+		use std::cell::RefCell;
+
+		thread_local! {
+			static LOG: RefCell<Vec<u32>> = RefCell::new(Vec::new());
+		}
+		pub fn log() -> Vec<u32> {
+			LOG.with(|log| log.borrow().clone())
+		}
+
+		pub trait Trait: system::Trait {}
+
+		decl_module! {
+			pub struct Module<T: Trait> for enum Call where origin: <T as system::Trait>::Origin {
+				#[weight = FunctionOf(|args: (&u32, &Weight)| *args.1, DispatchClass::Normal, true)]
+				fn log(origin, i: u32, weight: Weight) {
+					ensure_root(origin)?;
+					let _ = weight;
+					LOG.with(|log| log.borrow_mut().push(i));
+				}
+			}
+		}
+	}
+
+	impl_outer_origin! {
+		#[derive(Encode, Decode)]
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			logger::Logger,
+		}
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			scheduler<T>,
+		}
+	}
+
+	// For testing the pallet, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of pallets we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 2_000_000;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Call = ();
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+	impl logger::Trait for Test {}
+	parameter_types! {
+		pub const MaximumSchedulerWeight: Weight = 10_000;
+	}
+	pub struct One;
+	impl Contains<u64> for One {
+		fn sorted_members() -> Vec<u64> {
+			vec![1]
+		}
+	}
+	impl Trait for Test {
+		type Event = TestEvent;
+		type PalletsOrigin = Origin;
+		type Call = Call;
+		type MaximumWeight = MaximumSchedulerWeight;
+		type ScheduleOrigin = frame_system::EnsureSignedBy<One, u64>;
+	}
+	type System = system::Module<Test>;
+	type Logger = logger::Module<Test>;
+	type Scheduler = Module<Test>;
+
+	use schedule::{Anon, Named};
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		t.into()
+	}
+
+	fn run_to_block(n: u64) {
+		while System::block_number() < n {
+			Scheduler::on_finalize(System::block_number());
+			System::set_block_number(System::block_number() + 1);
+			Scheduler::on_initialize(System::block_number());
+		}
+	}
+
+	fn log_call(i: u32, weight: Weight) -> Call {
+		Call::Logger(logger::Call::log(i, weight))
+	}
+
+	fn schedule(
+		when: u64,
+		maybe_periodic: Option<schedule::Period<u64>>,
+		priority: schedule::Priority,
+		call: Call,
+	) -> TaskAddress<u64> {
+		<Scheduler as Anon<_, _, _>>::schedule(when, maybe_periodic, priority, system::RawOrigin::Root, call)
+	}
+
+	fn schedule_named(
+		id: u32,
+		when: u64,
+		maybe_periodic: Option<schedule::Period<u64>>,
+		priority: schedule::Priority,
+		call: Call,
+	) -> Result<TaskAddress<u64>, ()> {
+		<Scheduler as Named<_, _, _>>::schedule_named(
+			id.encode(), when, maybe_periodic, priority, system::RawOrigin::Root, call
+		)
+	}
+
+	#[test]
+	fn basic_scheduling_works() {
+		new_test_ext().execute_with(|| {
+			schedule(4, None, 127, log_call(42, 1000));
+			run_to_block(3);
+			assert!(logger::log().is_empty());
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42u32]);
+			run_to_block(100);
+			assert_eq!(logger::log(), vec![42u32]);
+		});
+	}
+
+	#[test]
+	fn scheduling_through_dispatchable_requires_schedule_origin() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				Scheduler::schedule(Origin::signed(2), 4, None, 127, Box::new(log_call(42, 1000))),
+				sp_runtime::DispatchError::BadOrigin,
+			);
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, None, 127, Box::new(log_call(42, 1000))));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42u32]);
+		});
+	}
+
+	#[test]
+	fn calls_are_dispatched_with_the_scheduling_origin() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::signed(1), 4, None, 127, Box::new(log_call(42, 1000))));
+			assert_ok!(Scheduler::schedule_named(
+				Origin::signed(1), 1u32.encode(), 4, None, 127, Box::new(log_call(69, 1000)),
+			));
+			run_to_block(4);
+			// `log` requires `Root`, so both dispatches fail with the signed origin.
+			assert!(logger::log().is_empty());
+			let results = System::events().into_iter().filter_map(|r| match r.event {
+				TestEvent::scheduler(RawEvent::Dispatched(_, _, result)) => Some(result),
+				_ => None,
+			}).collect::<Vec<_>>();
+			assert_eq!(results, vec![Err(sp_runtime::DispatchError::BadOrigin); 2]);
+		});
+	}
+
+	#[test]
+	fn tasks_in_the_past_are_scheduled_for_the_next_block() {
+		new_test_ext().execute_with(|| {
+			run_to_block(3);
+			assert_eq!(schedule(1, None, 127, log_call(42, 1000)), (4, 0));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42u32]);
+		});
+	}
+
+	#[test]
+	fn periodic_scheduling_works() {
+		new_test_ext().execute_with(|| {
+			// at #4, every 3 blocks, 3 times.
+			schedule(4, Some((3, 3)), 127, log_call(42, 1000));
+			run_to_block(3);
+			assert!(logger::log().is_empty());
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42u32]);
+			run_to_block(6);
+			assert_eq!(logger::log(), vec![42u32]);
+			run_to_block(7);
+			assert_eq!(logger::log(), vec![42u32, 42u32]);
+			run_to_block(9);
+			assert_eq!(logger::log(), vec![42u32, 42u32]);
+			run_to_block(10);
+			assert_eq!(logger::log(), vec![42u32, 42u32, 42u32]);
+			run_to_block(100);
+			assert_eq!(logger::log(), vec![42u32, 42u32, 42u32]);
+		});
+	}
+
+	#[test]
+	fn cancel_named_scheduling_works_with_normal_cancel() {
+		new_test_ext().execute_with(|| {
+			// at #4.
+			schedule_named(1, 4, None, 127, log_call(69, 1000)).unwrap();
+			let i = schedule(4, None, 127, log_call(42, 1000));
+			run_to_block(3);
+			assert!(logger::log().is_empty());
+			assert_ok!(Scheduler::cancel_named(Origin::ROOT, 1u32.encode()));
+			assert_ok!(Scheduler::cancel(Origin::ROOT, i.0, i.1));
+			run_to_block(100);
+			assert!(logger::log().is_empty());
+		});
+	}
+
+	#[test]
+	fn cancel_named_periodic_scheduling_works() {
+		new_test_ext().execute_with(|| {
+			// at #4, every 3 blocks, 3 times.
+			schedule_named(1, 4, Some((3, 3)), 127, log_call(42, 1000)).unwrap();
+			// same id results in error.
+			assert!(schedule_named(1, 4, None, 127, log_call(69, 1000)).is_err());
+			// different id is ok.
+			schedule_named(2, 8, None, 127, log_call(69, 1000)).unwrap();
+			run_to_block(3);
+			assert!(logger::log().is_empty());
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42u32]);
+			run_to_block(6);
+			assert_ok!(Scheduler::cancel_named(Origin::ROOT, 1u32.encode()));
+			run_to_block(100);
+			assert_eq!(logger::log(), vec![42u32, 69u32]);
+			assert_noop!(Scheduler::cancel_named(Origin::ROOT, 1u32.encode()), Error::<Test>::NotFound);
+		});
+	}
+
+	#[test]
+	fn scheduler_respects_weight_limits() {
+		new_test_ext().execute_with(|| {
+			schedule(4, None, 127, log_call(42, 6000));
+			schedule(4, None, 127, log_call(69, 6000));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42u32]);
+			run_to_block(5);
+			assert_eq!(logger::log(), vec![42u32, 69u32]);
+		});
+	}
+
+	#[test]
+	fn postponed_periodic_tasks_keep_their_period() {
+		new_test_ext().execute_with(|| {
+			// at #4, every 3 blocks, 3 times; postponed at #4 by the heavier task before it.
+			schedule(4, None, 127, log_call(69, 6000));
+			schedule(4, Some((3, 3)), 127, log_call(42, 6000));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![69u32]);
+			run_to_block(5);
+			assert_eq!(logger::log(), vec![69u32, 42u32]);
+			run_to_block(6);
+			assert_eq!(logger::log(), vec![69u32, 42u32]);
+			run_to_block(7);
+			assert_eq!(logger::log(), vec![69u32, 42u32, 42u32]);
+			run_to_block(10);
+			assert_eq!(logger::log(), vec![69u32, 42u32, 42u32, 42u32]);
+		});
+	}
+
+	#[test]
+	fn canceling_the_last_task_removes_the_agenda() {
+		new_test_ext().execute_with(|| {
+			let first = schedule(4, None, 127, log_call(42, 1000));
+			let second = schedule_named(1, 4, None, 127, log_call(69, 1000)).unwrap();
+			assert_ok!(Scheduler::cancel(Origin::ROOT, first.0, first.1));
+			assert!(Agenda::<Test>::exists(4));
+			assert_ok!(Scheduler::cancel(Origin::ROOT, second.0, second.1));
+			assert!(!Agenda::<Test>::exists(4));
+			assert!(!Lookup::<Test>::exists(1u32.encode()));
+		});
+	}
+
+	#[test]
+	fn scheduler_respects_hard_deadlines_more() {
+		new_test_ext().execute_with(|| {
+			schedule(4, None, 0, log_call(42, 6000));
+			schedule(4, None, 0, log_call(69, 6000));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![42u32, 69u32]);
+		});
+	}
+
+	#[test]
+	fn scheduler_respects_priority_ordering() {
+		new_test_ext().execute_with(|| {
+			schedule(4, None, 1, log_call(42, 6000));
+			schedule(4, None, 0, log_call(69, 6000));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![69u32, 42u32]);
+		});
+	}
+}
@@ -27,16 +27,15 @@
 
 use sp_std::prelude::*;
 use codec::{Encode, Decode};
-use sp_runtime::KeyTypeId;
+use sp_runtime::{KeyTypeId, RuntimeDebug};
 use sp_runtime::traits::{Convert, OpaqueKeys, Hash as HashT};
 use frame_support::{decl_module, decl_storage};
 use frame_support::{Parameter, print};
 use sp_trie::{MemoryDB, Trie, TrieMut, Recorder, EMPTY_PREFIX};
 use sp_trie::trie_types::{TrieDBMut, TrieDB};
+use sp_session::{GetSessionNumber, GetValidatorCount, ValidatorCount};
 use super::{SessionIndex, Module as SessionModule};
 
-type ValidatorCount = u32;
-
 /// Trait necessary for the historical module.
 pub trait Trait: super::Trait {
 	/// Full identification of the validator.
@@ -106,6 +105,10 @@ pub trait OnSessionEnding<ValidatorId, FullIdentification>: crate::OnSessionEndi
 		-> Option<(Vec<ValidatorId>, Vec<(ValidatorId, FullIdentification)>)>;
 }
 
+impl<A, B> OnSessionEnding<A, B> for () {
+	fn on_session_ending(_: SessionIndex, _: SessionIndex) -> Option<(Vec<A>, Vec<(A, B)>)> { None }
+}
+
 /// An `OnSessionEnding` implementation that wraps an inner `I` and also
 /// sets the historical trie root of the ending session.
 pub struct NoteHistoricalRoot<T, I>(sp_std::marker::PhantomData<(T, I)>);
@@ -267,10 +270,23 @@ impl<T: Trait> ProvingTrie<T> {
 }
 
 /// Proof of ownership of a specific key.
-#[derive(Encode, Decode, Clone)]
+#[derive(Encode, Decode, Clone, Eq, PartialEq, RuntimeDebug)]
 pub struct Proof {
 	session: SessionIndex,
 	trie_nodes: Vec<Vec<u8>>,
+	validator_count: ValidatorCount,
+}
+
+impl GetSessionNumber for Proof {
+	fn session(&self) -> SessionIndex {
+		self.session
+	}
+}
+
+impl GetValidatorCount for Proof {
+	fn validator_count(&self) -> ValidatorCount {
+		self.validator_count
+	}
 }
 
 impl<T: Trait, D: AsRef<[u8]>> frame_support::traits::KeyOwnerProofSystem<(KeyTypeId, D)>
@@ -281,6 +297,8 @@ impl<T: Trait, D: AsRef<[u8]>> frame_support::traits::KeyOwnerProofSystem<(KeyTy
 
 	fn prove(key: (KeyTypeId, D)) -> Option<Self::Proof> {
 		let session = <SessionModule<T>>::current_index();
+		let validators = <SessionModule<T>>::validators();
+		let validator_count = validators.len() as ValidatorCount;
 		let trie = ProvingTrie::<T>::generate_for(session).ok()?;
 
 		let (id, data) = key;
@@ -288,6 +306,7 @@ impl<T: Trait, D: AsRef<[u8]>> frame_support::traits::KeyOwnerProofSystem<(KeyTy
 		trie.prove(id, data.as_ref()).map(|trie_nodes| Proof {
 			session,
 			trie_nodes,
+			validator_count,
 		})
 	}
 
@@ -295,11 +314,20 @@ impl<T: Trait, D: AsRef<[u8]>> frame_support::traits::KeyOwnerProofSystem<(KeyTy
 		let (id, data) = key;
 
 		if proof.session == <SessionModule<T>>::current_index() {
+			let count = <SessionModule<T>>::validators().len() as ValidatorCount;
+			if count != proof.validator_count {
+				return None;
+			}
+
 			<SessionModule<T>>::key_owner(id, data.as_ref()).and_then(|owner|
 				T::FullIdentificationOf::convert(owner.clone()).map(move |id| (owner, id))
 			)
 		} else {
-			let (root, _) = <HistoricalSessions<T>>::get(&proof.session)?;
+			let (root, count) = <HistoricalSessions<T>>::get(&proof.session)?;
+			if count != proof.validator_count {
+				return None;
+			}
+
 			let trie = ProvingTrie::<T>::from_nodes(root, &proof.trie_nodes);
 
 			trie.query(id, data.as_ref())
@@ -233,6 +233,19 @@
 //! values until the total difference among votes of a particular nominator are less than a
 //! threshold.
 //!
+//! ### Off-chain Election
+//!
+//! Running the election on-chain at the end of an era is unbounded work in a single block. To
+//! avoid this, an election window is opened during the last session of each era: a snapshot of
+//! the validator candidates and nominators is taken and the offchain workers of validators
+//! compute the election result, including the equalize post-processing, off-chain. The result is
+//! submitted as an unsigned transaction in a compact encoding (see
+//! [`submit_election_solution_unsigned`](./enum.Call.html#variant.submit_election_solution_unsigned))
+//! along with its [`PhragmenScore`](../sp_phragmen/type.PhragmenScore.html). The runtime checks
+//! that the solution is feasible and that its score is correct, and only keeps it if it is better
+//! than any solution already queued. If no valid solution has been queued by the end of the era,
+//! the election falls back to being computed on-chain.
+//!
 //! ## GenesisConfig
 //!
 //! The Staking module depends on the [`GenesisConfig`](./struct.GenesisConfig.html).
@@ -252,13 +265,14 @@ mod mock;
 mod tests;
 mod migration;
 mod slashing;
+mod offchain_election;
 
 pub mod inflation;
 
-use sp_std::{prelude::*, result};
+use sp_std::{prelude::*, result, collections::btree_set::BTreeSet};
 use codec::{HasCompact, Encode, Decode};
 use frame_support::{
-	decl_module, decl_event, decl_storage, ensure, decl_error,
+	decl_module, decl_event, decl_storage, ensure, decl_error, debug,
 	weights::SimpleDispatchInfo,
 	traits::{
 		Currency, OnFreeBalanceZero, LockIdentifier, LockableCurrency,
@@ -273,7 +287,11 @@ use sp_runtime::{
 	traits::{
 		Convert, Zero, One, StaticLookup, CheckedSub, Saturating, Bounded, SaturatedConversion,
 		SimpleArithmetic, EnsureOrigin,
-	}
+	},
+	transaction_validity::{
+		TransactionValidity, ValidTransaction, InvalidTransaction, TransactionPriority,
+		TransactionLongevity,
+	},
 };
 use sp_staking::{
 	SessionIndex,
@@ -281,9 +299,10 @@ use sp_staking::{
 };
 #[cfg(feature = "std")]
 use sp_runtime::{Serialize, Deserialize};
-use frame_system::{self as system, ensure_signed, ensure_root};
+use frame_system::{self as system, ensure_signed, ensure_root, ensure_none};
+use frame_system::offchain::SubmitUnsignedTransaction;
 
-use sp_phragmen::ExtendedBalance;
+use sp_phragmen::{ExtendedBalance, PhragmenScore, CompactAssignments, SupportMap};
 
 const DEFAULT_MINIMUM_VALIDATOR_COUNT: u32 = 4;
 const MAX_NOMINATIONS: usize = 16;
@@ -296,6 +315,15 @@ pub type EraIndex = u32;
 /// Counter for the number of "reward" points earned by a given validator.
 pub type Points = u32;
 
+/// Index of a voter (a nominator, or a validator voting for itself) in the election snapshot.
+pub type NominatorIndex = u32;
+
+/// Index of a validator candidate in the election snapshot.
+pub type ValidatorIndex = u16;
+
+/// The compact encoding of an election solution, referring to the election snapshot.
+pub type Compact = CompactAssignments<NominatorIndex, ValidatorIndex>;
+
 /// Reward points of an era. Used to split era total payout between validators.
 #[derive(Encode, Decode, Default)]
 pub struct EraPoints {
@@ -544,6 +572,56 @@ pub struct UnappliedSlash<AccountId, Balance: HasCompact> {
 	payout: Balance,
 }
 
+/// Status of the offchain election window.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+pub enum ElectionStatus<BlockNumber> {
+	/// No election is happening; solutions are not accepted.
+	Closed,
+	/// The election window was opened at the given block and solutions are accepted.
+	Open(BlockNumber),
+}
+
+impl<BlockNumber> ElectionStatus<BlockNumber> {
+	/// Returns `true` if the election window is open.
+	pub fn is_open(&self) -> bool {
+		match self {
+			ElectionStatus::Open(_) => true,
+			ElectionStatus::Closed => false,
+		}
+	}
+
+	/// Returns `true` if the election window is closed.
+	pub fn is_closed(&self) -> bool {
+		!self.is_open()
+	}
+}
+
+impl<BlockNumber> Default for ElectionStatus<BlockNumber> {
+	fn default() -> Self {
+		ElectionStatus::Closed
+	}
+}
+
+/// Where an election result was computed.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+pub enum ElectionCompute {
+	/// The result was computed on-chain, at the end of the era.
+	OnChain,
+	/// The result was computed off-chain and submitted as an unsigned transaction.
+	Unsigned,
+}
+
+/// The result of an election, queued until it is enacted at the end of the era.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct ElectionResult<AccountId, Balance: HasCompact> {
+	/// The elected validator stashes.
+	pub elected_stashes: Vec<AccountId>,
+	/// The exposure of each elected validator.
+	pub exposures: Vec<(AccountId, Exposure<AccountId, Balance>)>,
+	/// Where this result was computed.
+	pub compute: ElectionCompute,
+}
+
 pub type BalanceOf<T> =
 	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
 type PositiveImbalanceOf<T> =
@@ -637,6 +715,26 @@ pub trait Trait: frame_system::Trait {
 
 	/// The NPoS reward curve to use.
 	type RewardCurve: Get<&'static PiecewiseLinear<'static>>;
+
+	/// The overarching call type.
+	type Call: From<Call<Self>>;
+
+	/// A transaction submitter, used by the offchain worker to submit election solutions.
+	type SubmitTransaction: SubmitUnsignedTransaction<Self, <Self as Trait>::Call>;
+
+	/// Maximum number of equalize iterations run by the offchain worker on its election result.
+	/// Set to zero to skip the post-processing entirely.
+	type MaxIterations: Get<u32>;
+
+	/// Number of blocks in a session. The election window is open for the last session of an
+	/// era, so this bounds how long an election solution stays valid.
+	type SessionLength: Get<Self::BlockNumber>;
+
+	/// Maximum number of election solutions that can be queued in a single election window.
+	///
+	/// Every queued solution has gone through the full feasibility check on-chain, so this
+	/// bounds the work spent on them per era.
+	type MaxSubmissions: Get<u32>;
 }
 
 /// Mode of era-forcing.
@@ -658,7 +756,7 @@ impl Default for Forcing {
 }
 
 decl_storage! {
-	trait Store for Module<T: Trait> as Staking {
+	trait Store for Module<T: Trait> as Staking storage_version(1) {
 
 		/// The ideal number of staking participants.
 		pub ValidatorCount get(fn validator_count) config(): u32;
@@ -756,7 +854,32 @@ decl_storage! {
 		EarliestUnappliedSlash: Option<EraIndex>;
 
 		/// The version of storage for upgrade.
-		StorageVersion: u32;
+		///
+		/// Deprecated: the storage version is tracked by `GetStorageVersion`. This is only read and
+		/// removed by the migration of chains that were running before.
+		StorageVersion: Option<u32>;
+
+		/// Whether the offchain election window is open, and since which block.
+		pub EraElectionStatus get(fn era_election_status): ElectionStatus<T::BlockNumber>;
+
+		/// The validator candidates at the time the election window was opened. Election solutions
+		/// refer to them by their index in this list.
+		pub SnapshotValidators get(fn snapshot_validators): Option<Vec<T::AccountId>>;
+
+		/// The nominators at the time the election window was opened. Election solutions refer to
+		/// them by their index in this list, offset by the number of snapshot validators.
+		pub SnapshotNominators get(fn snapshot_nominators): Option<Vec<T::AccountId>>;
+
+		/// The best election result submitted during the current window, to be enacted at the end
+		/// of the era.
+		pub QueuedElected get(fn queued_elected):
+			Option<ElectionResult<T::AccountId, BalanceOf<T>>>;
+
+		/// The score of `QueuedElected`.
+		pub QueuedScore get(fn queued_score): Option<PhragmenScore>;
+
+		/// The number of solutions queued during the current election window.
+		pub QueuedSubmissions get(fn queued_submissions): u32;
 	}
 	add_extra_genesis {
 		config(stakers):
@@ -788,8 +911,6 @@ decl_storage! {
 					}, _ => Ok(())
 				};
 			}
-
-			StorageVersion::put(migration::CURRENT_VERSION);
 		});
 	}
 }
@@ -804,6 +925,10 @@ decl_event!(
 		/// An old slashing report from a prior era was discarded because it could
 		/// not be processed.
 		OldSlashingReportDiscarded(SessionIndex),
+		/// A new set of validators was elected, with the result computed as given.
+		StakingElection(ElectionCompute),
+		/// A new election solution was validated and queued, replacing any previous one.
+		SolutionStored(ElectionCompute),
 	}
 );
 
@@ -830,6 +955,29 @@ decl_error! {
 		NoMoreChunks,
 		/// Can not rebond without unlocking chunks.
 		NoUnlockChunk,
+		/// The election window is not open.
+		PhragmenEarlySubmission,
+		/// The submitted solution is not better than the queued one.
+		PhragmenWeakSubmission,
+		/// The maximum number of solutions has already been queued in this election window.
+		PhragmenTooManySubmissions,
+		/// The election snapshot is not available.
+		SnapshotUnavailable,
+		/// The submitted solution has the wrong number of winners.
+		PhragmenBogusWinnerCount,
+		/// One of the submitted winners is not a snapshot validator, or appears twice.
+		PhragmenBogusWinner,
+		/// The compact assignments refer to unknown accounts, have ratios adding up to more than
+		/// one, or contain duplicate voters or targets.
+		PhragmenBogusCompact,
+		/// A voter backs a target it did not nominate.
+		PhragmenBogusNomination,
+		/// A voter backs a target that is not among the winners.
+		PhragmenBogusEdge,
+		/// The claimed score does not match the score of the solution.
+		PhragmenBogusScore,
+		/// The call is not allowed while the election window is open.
+		CallNotAllowed,
 	}
 }
 
@@ -845,8 +993,10 @@ decl_module! {
 
 		fn deposit_event() = default;
 
-		fn on_initialize() {
-			Self::ensure_storage_upgraded();
+		/// The migrated `Nominators` entries are weighed by the migration itself.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn on_runtime_upgrade() {
+			migration::perform_migrations::<T>();
 		}
 
 		fn on_finalize() {
@@ -856,6 +1006,22 @@ decl_module! {
 			}
 		}
 
+		fn offchain_worker(now: T::BlockNumber) {
+			debug::RuntimeLogger::init();
+
+			// Only compute an election result if we are a potential validator.
+			if Self::era_election_status().is_open() && sp_io::offchain::is_validator() {
+				if let Err(e) = offchain_election::compute_and_submit::<T>(now) {
+					debug::native::warn!(
+						target: "staking",
+						"Failed to submit an election solution at {:?}: {:?}",
+						now,
+						e,
+					);
+				}
+			}
+		}
+
 		/// Take the origin account as a stash and lock up `value` of its balance. `controller` will
 		/// be the account that controls it.
 		///
@@ -914,6 +1080,8 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the stash, not the controller.
 		///
+		/// Not allowed while the election window is open.
+		///
 		/// # <weight>
 		/// - Independent of the arguments. Insignificant complexity.
 		/// - O(1).
@@ -922,6 +1090,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
 		fn bond_extra(origin, #[compact] max_additional: BalanceOf<T>) {
 			let stash = ensure_signed(origin)?;
+			ensure!(Self::era_election_status().is_closed(), Error::<T>::CallNotAllowed);
 
 			let controller = Self::bonded(&stash).ok_or(Error::<T>::NotStash)?;
 			let mut ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
@@ -951,6 +1120,8 @@ decl_module! {
 		///
 		/// See also [`Call::withdraw_unbonded`].
 		///
+		/// Not allowed while the election window is open.
+		///
 		/// # <weight>
 		/// - Independent of the arguments. Limited but potentially exploitable complexity.
 		/// - Contains a limited number of reads.
@@ -962,6 +1133,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(400_000)]
 		fn unbond(origin, #[compact] value: BalanceOf<T>) {
 			let controller = ensure_signed(origin)?;
+			ensure!(Self::era_election_status().is_closed(), Error::<T>::CallNotAllowed);
 			let mut ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
 			ensure!(
 				ledger.unlocking.len() < MAX_UNLOCKING_CHUNKS,
@@ -994,6 +1166,8 @@ decl_module! {
 		///
 		/// See also [`Call::unbond`].
 		///
+		/// Not allowed while the election window is open.
+		///
 		/// # <weight>
 		/// - Could be dependent on the `origin` argument and how much `unlocking` chunks exist.
 		///  It implies `consolidate_unlocked` which loops over `Ledger.unlocking`, which is
@@ -1004,6 +1178,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(400_000)]
 		fn withdraw_unbonded(origin) {
 			let controller = ensure_signed(origin)?;
+			ensure!(Self::era_election_status().is_closed(), Error::<T>::CallNotAllowed);
 			let ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
 			let ledger = ledger.consolidate_unlocked(Self::current_era());
 
@@ -1028,6 +1203,8 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
 		///
+		/// Not allowed while the election window is open.
+		///
 		/// # <weight>
 		/// - Independent of the arguments. Insignificant complexity.
 		/// - Contains a limited number of reads.
@@ -1035,9 +1212,8 @@ decl_module! {
 		/// # </weight>
 		#[weight = SimpleDispatchInfo::FixedNormal(750_000)]
 		fn validate(origin, prefs: ValidatorPrefs) {
-			Self::ensure_storage_upgraded();
-
 			let controller = ensure_signed(origin)?;
+			ensure!(Self::era_election_status().is_closed(), Error::<T>::CallNotAllowed);
 			let ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
 			let stash = &ledger.stash;
 			<Nominators<T>>::remove(stash);
@@ -1050,6 +1226,8 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
 		///
+		/// Not allowed while the election window is open.
+		///
 		/// # <weight>
 		/// - The transaction's complexity is proportional to the size of `targets`,
 		/// which is capped at `MAX_NOMINATIONS`.
@@ -1057,9 +1235,8 @@ decl_module! {
 		/// # </weight>
 		#[weight = SimpleDispatchInfo::FixedNormal(750_000)]
 		fn nominate(origin, targets: Vec<<T::Lookup as StaticLookup>::Source>) {
-			Self::ensure_storage_upgraded();
-
 			let controller = ensure_signed(origin)?;
+			ensure!(Self::era_election_status().is_closed(), Error::<T>::CallNotAllowed);
 			let ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
 			let stash = &ledger.stash;
 			ensure!(!targets.is_empty(), Error::<T>::EmptyTargets);
@@ -1084,6 +1261,8 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
 		///
+		/// Not allowed while the election window is open.
+		///
 		/// # <weight>
 		/// - Independent of the arguments. Insignificant complexity.
 		/// - Contains one read.
@@ -1092,6 +1271,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
 		fn chill(origin) {
 			let controller = ensure_signed(origin)?;
+			ensure!(Self::era_election_status().is_closed(), Error::<T>::CallNotAllowed);
 			let ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
 			Self::chill_stash(&ledger.stash);
 		}
@@ -1121,6 +1301,8 @@ decl_module! {
 		///
 		/// The dispatch origin for this call must be _Signed_ by the stash, not the controller.
 		///
+		/// Not allowed while the election window is open.
+		///
 		/// # <weight>
 		/// - Independent of the arguments. Insignificant complexity.
 		/// - Contains a limited number of reads.
@@ -1129,6 +1311,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(750_000)]
 		fn set_controller(origin, controller: <T::Lookup as StaticLookup>::Source) {
 			let stash = ensure_signed(origin)?;
+			ensure!(Self::era_election_status().is_closed(), Error::<T>::CallNotAllowed);
 			let old_controller = Self::bonded(&stash).ok_or(Error::<T>::NotStash)?;
 			let controller = T::Lookup::lookup(controller)?;
 			if <Ledger<T>>::exists(&controller) {
@@ -1239,6 +1422,8 @@ decl_module! {
 
 		/// Rebond a portion of the stash scheduled to be unlocked.
 		///
+		/// Not allowed while the election window is open.
+		///
 		/// # <weight>
 		/// - Time complexity: O(1). Bounded by `MAX_UNLOCKING_CHUNKS`.
 		/// - Storage changes: Can't increase storage, only decrease it.
@@ -1246,6 +1431,7 @@ decl_module! {
 		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
 		fn rebond(origin, #[compact] value: BalanceOf<T>) {
 			let controller = ensure_signed(origin)?;
+			ensure!(Self::era_election_status().is_closed(), Error::<T>::CallNotAllowed);
 			let ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
 			ensure!(
 				ledger.unlocking.len() > 0,
@@ -1256,6 +1442,36 @@ decl_module! {
 
 			Self::update_ledger(&controller, &ledger);
 		}
+
+		/// Submit an election solution computed off-chain, while the election window is open.
+		///
+		/// `winners` and `compact` refer to the accounts of the election snapshot by index, and
+		/// `score` is the score of the solution as computed by `sp_phragmen::evaluate_support`.
+		/// The solution is queued if it is feasible, its score is correct and it is better than the
+		/// currently queued solution, if any. At most `MaxSubmissions` solutions are queued per
+		/// election window.
+		///
+		/// The dispatch origin for this call must be _None_.
+		///
+		/// # <weight>
+		/// - Linear in the number of voters and in the size of their nominations.
+		/// - Reads the snapshot and the nominations of every voter.
+		/// - Two storage writes.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000_000)]
+		fn submit_election_solution_unsigned(
+			origin,
+			winners: Vec<ValidatorIndex>,
+			compact: Compact,
+			score: PhragmenScore,
+		) {
+			ensure_none(origin)?;
+			let result = Self::check_election_solution(winners, compact, score)?;
+			<QueuedElected<T>>::put(result);
+			QueuedScore::put(score);
+			QueuedSubmissions::mutate(|n| *n += 1);
+			Self::deposit_event(RawEvent::SolutionStored(ElectionCompute::Unsigned));
+		}
 	}
 }
 
@@ -1291,11 +1507,6 @@ impl<T: Trait> Module<T> {
 		<Nominators<T>>::remove(stash);
 	}
 
-	/// Ensures storage is upgraded to most recent necessary state.
-	fn ensure_storage_upgraded() {
-		migration::perform_migrations::<T>();
-	}
-
 	/// Actually make a payment to a staker. This uses the currency's reward function
 	/// to pay the right payee for the given staker account.
 	fn make_payout(stash: &T::AccountId, amount: BalanceOf<T>) -> Option<PositiveImbalanceOf<T>> {
@@ -1356,6 +1567,14 @@ impl<T: Trait> Module<T> {
 			Forcing::ForceNew => ForceEra::kill(),
 			Forcing::ForceAlways => (),
 			Forcing::NotForcing if era_length >= T::SessionsPerEra::get() => (),
+			Forcing::NotForcing if era_length + 1 >= T::SessionsPerEra::get() => {
+				// The era ends with the next session; give the offchain workers that session to
+				// submit an election result.
+				if !Self::era_election_status().is_open() {
+					Self::open_election_window();
+				}
+				return None
+			},
 			_ => return None,
 		}
 		let validators = T::SessionInterface::validators();
@@ -1441,8 +1660,16 @@ impl<T: Trait> Module<T> {
 			}
 		});
 
-		// Reassign all Stakers.
-		let (_slot_stake, maybe_new_validators) = Self::select_validators();
+		// Reassign all Stakers, preferring a result submitted during the election window over
+		// computing one on-chain.
+		let maybe_new_validators = Self::close_election_window()
+			.or_else(Self::elect_on_chain)
+			.map(|result| {
+				let compute = result.compute;
+				let (_slot_stake, elected_stashes) = Self::enact_election(result);
+				Self::deposit_event(RawEvent::StakingElection(compute));
+				elected_stashes
+			});
 		Self::apply_unapplied_slashes(current_era);
 
 		maybe_new_validators
@@ -1464,12 +1691,34 @@ impl<T: Trait> Module<T> {
 		})
 	}
 
-	/// Select a new validator set from the assembled stakers and their role preferences.
+	/// Select a new validator set from the assembled stakers and their role preferences, and
+	/// enact it.
 	///
 	/// Returns the new `SlotStake` value and a set of newly selected _stash_ IDs.
 	///
 	/// Assumes storage is coherent with the declaration.
 	fn select_validators() -> (BalanceOf<T>, Option<Vec<T::AccountId>>) {
+		match Self::elect_on_chain() {
+			Some(result) => {
+				let (slot_stake, elected_stashes) = Self::enact_election(result);
+				(slot_stake, Some(elected_stashes))
+			}
+			None => {
+				// There were not enough candidates for even our minimal level of functionality.
+				// This is bad.
+				// We should probably disable all functionality except for block production
+				// and let the chain keep producing blocks until we can decide on a sufficiently
+				// substantial set.
+				// TODO: #2494
+				(Self::slot_stake(), None)
+			}
+		}
+	}
+
+	/// Run the election on-chain, over the current validator candidates and nominators.
+	///
+	/// Returns `None` if there are not enough candidates.
+	fn elect_on_chain() -> Option<ElectionResult<T::AccountId, BalanceOf<T>>> {
 		let mut all_nominators: Vec<(T::AccountId, Vec<T::AccountId>)> = Vec::new();
 		let all_validator_candidates_iter = <Validators<T>>::enumerate();
 		let all_validators = all_validator_candidates_iter.map(|(who, _pref)| {
@@ -1479,103 +1728,261 @@ impl<T: Trait> Module<T> {
 		}).collect::<Vec<T::AccountId>>();
 
 		let nominator_votes = <Nominators<T>>::enumerate().map(|(nominator, nominations)| {
-			let Nominations { submitted_in, mut targets, suppressed: _ } = nominations;
-
-			// Filter out nomination targets which were nominated before the most recent
-			// slashing span.
-			targets.retain(|stash| {
-				<Self as Store>::SlashingSpans::get(&stash).map_or(
-					true,
-					|spans| submitted_in >= spans.last_start(),
-				)
-			});
-
-			(nominator, targets)
+			(nominator, Self::filter_nominations(nominations))
 		});
 		all_nominators.extend(nominator_votes);
 
-		let maybe_phragmen_result = sp_phragmen::elect::<_, _, _, T::CurrencyToVote>(
+		let phragmen_result = sp_phragmen::elect::<_, _, _, T::CurrencyToVote>(
 			Self::validator_count() as usize,
 			Self::minimum_validator_count().max(1) as usize,
 			all_validators,
 			all_nominators,
 			Self::slashable_balance_of,
+		)?;
+
+		let elected_stashes = phragmen_result.winners.iter()
+			.map(|(s, _)| s.clone())
+			.collect::<Vec<T::AccountId>>();
+
+		let supports = sp_phragmen::build_support_map::<_, _, _, T::CurrencyToVote>(
+			&elected_stashes,
+			&phragmen_result.assignments,
+			Self::slashable_balance_of,
 		);
 
-		if let Some(phragmen_result) = maybe_phragmen_result {
-			let elected_stashes = phragmen_result.winners.iter()
-				.map(|(s, _)| s.clone())
-				.collect::<Vec<T::AccountId>>();
-			let assignments = phragmen_result.assignments;
+		Some(Self::build_election_result(elected_stashes, supports, ElectionCompute::OnChain))
+	}
 
-			let to_balance = |e: ExtendedBalance|
-				<T::CurrencyToVote as Convert<ExtendedBalance, BalanceOf<T>>>::convert(e);
+	/// The targets of some nominations, without those that were nominated before their most
+	/// recent slashing span.
+	fn filter_nominations(nominations: Nominations<T::AccountId>) -> Vec<T::AccountId> {
+		let Nominations { submitted_in, mut targets, suppressed: _ } = nominations;
 
-			let supports = sp_phragmen::build_support_map::<_, _, _, T::CurrencyToVote>(
-				&elected_stashes,
-				&assignments,
-				Self::slashable_balance_of,
-			);
+		targets.retain(|stash| {
+			<Self as Store>::SlashingSpans::get(&stash).map_or(
+				true,
+				|spans| submitted_in >= spans.last_start(),
+			)
+		});
 
-			// Clear Stakers.
-			for v in Self::current_elected().iter() {
-				<Stakers<T>>::remove(v);
-			}
+		targets
+	}
 
-			// Populate Stakers and figure out the minimum stake behind a slot.
-			let mut slot_stake = BalanceOf::<T>::max_value();
-			for (c, s) in supports.into_iter() {
-				// build `struct exposure` from `support`
-				let mut others = Vec::new();
-				let mut own: BalanceOf<T> = Zero::zero();
-				let mut total: BalanceOf<T> = Zero::zero();
-				s.voters
-					.into_iter()
-					.map(|(who, value)| (who, to_balance(value)))
-					.for_each(|(who, value)| {
-						if who == c {
-							own = own.saturating_add(value);
-						} else {
-							others.push(IndividualExposure { who, value });
-						}
-						total = total.saturating_add(value);
-					});
-				let exposure = Exposure {
-					own,
-					others,
-					// This might reasonably saturate and we cannot do much about it. The sum of
-					// someone's stake might exceed the balance type if they have the maximum amount
-					// of balance and receive some support. This is super unlikely to happen, yet
-					// we simulate it in some tests.
-					total,
-				};
+	/// Build an election result out of the elected stashes and their support.
+	fn build_election_result(
+		elected_stashes: Vec<T::AccountId>,
+		supports: SupportMap<T::AccountId>,
+		compute: ElectionCompute,
+	) -> ElectionResult<T::AccountId, BalanceOf<T>> {
+		let to_balance = |e: ExtendedBalance|
+			<T::CurrencyToVote as Convert<ExtendedBalance, BalanceOf<T>>>::convert(e);
+
+		let exposures = supports.into_iter().map(|(c, s)| {
+			// build `struct exposure` from `support`
+			let mut others = Vec::new();
+			let mut own: BalanceOf<T> = Zero::zero();
+			let mut total: BalanceOf<T> = Zero::zero();
+			s.voters
+				.into_iter()
+				.map(|(who, value)| (who, to_balance(value)))
+				.for_each(|(who, value)| {
+					if who == c {
+						own = own.saturating_add(value);
+					} else {
+						others.push(IndividualExposure { who, value });
+					}
+					total = total.saturating_add(value);
+				});
+			let exposure = Exposure {
+				own,
+				others,
+				// This might reasonably saturate and we cannot do much about it. The sum of
+				// someone's stake might exceed the balance type if they have the maximum amount
+				// of balance and receive some support. This is super unlikely to happen, yet
+				// we simulate it in some tests.
+				total,
+			};
+			(c, exposure)
+		}).collect();
 
-				if exposure.total < slot_stake {
-					slot_stake = exposure.total;
-				}
-				<Stakers<T>>::insert(&c, exposure.clone());
+		ElectionResult { elected_stashes, exposures, compute }
+	}
+
+	/// Enact an election result: populate `Stakers`, `SlotStake` and `CurrentElected`.
+	///
+	/// Returns the new `SlotStake` value and the elected stashes.
+	fn enact_election(
+		result: ElectionResult<T::AccountId, BalanceOf<T>>,
+	) -> (BalanceOf<T>, Vec<T::AccountId>) {
+		let ElectionResult { elected_stashes, exposures, compute: _ } = result;
+
+		// Clear Stakers.
+		for v in Self::current_elected().iter() {
+			<Stakers<T>>::remove(v);
+		}
+
+		// Populate Stakers and figure out the minimum stake behind a slot.
+		let mut slot_stake = BalanceOf::<T>::max_value();
+		for (c, exposure) in exposures.into_iter() {
+			if exposure.total < slot_stake {
+				slot_stake = exposure.total;
 			}
+			<Stakers<T>>::insert(&c, exposure);
+		}
 
-			// Update slot stake.
-			<SlotStake<T>>::put(&slot_stake);
+		// Update slot stake.
+		<SlotStake<T>>::put(&slot_stake);
 
-			// Set the new validator set in sessions.
-			<CurrentElected<T>>::put(&elected_stashes);
+		// Set the new validator set in sessions.
+		<CurrentElected<T>>::put(&elected_stashes);
 
-			// In order to keep the property required by `n_session_ending`
-			// that we must return the new validator set even if it's the same as the old,
-			// as long as any underlying economic conditions have changed, we don't attempt
-			// to do any optimization where we compare against the prior set.
-			(slot_stake, Some(elected_stashes))
-		} else {
-			// There were not enough candidates for even our minimal level of functionality.
-			// This is bad.
-			// We should probably disable all functionality except for block production
-			// and let the chain keep producing blocks until we can decide on a sufficiently
-			// substantial set.
-			// TODO: #2494
-			(Self::slot_stake(), None)
+		// In order to keep the property required by `n_session_ending`
+		// that we must return the new validator set even if it's the same as the old,
+		// as long as any underlying economic conditions have changed, we don't attempt
+		// to do any optimization where we compare against the prior set.
+		(slot_stake, elected_stashes)
+	}
+
+	/// Open the election window, taking a snapshot of the current validator candidates and
+	/// nominators.
+	///
+	/// Does nothing if the snapshot would be too large to be indexed by the compact encoding, in
+	/// which case the election will be computed on-chain.
+	fn open_election_window() {
+		let validators = <Validators<T>>::enumerate().map(|(who, _)| who).collect::<Vec<_>>();
+		let nominators = <Nominators<T>>::enumerate().map(|(who, _)| who).collect::<Vec<_>>();
+
+		let num_voters = validators.len().saturating_add(nominators.len());
+		if validators.len() > ValidatorIndex::max_value() as usize
+			|| num_voters > NominatorIndex::max_value() as usize
+		{
+			return;
+		}
+
+		<SnapshotValidators<T>>::put(validators);
+		<SnapshotNominators<T>>::put(nominators);
+		<EraElectionStatus<T>>::put(ElectionStatus::Open(<frame_system::Module<T>>::block_number()));
+	}
+
+	/// Number of blocks until the election window closes, zero if it is closed.
+	fn election_window_remaining() -> T::BlockNumber {
+		match Self::era_election_status() {
+			ElectionStatus::Open(opened) => opened
+				.saturating_add(T::SessionLength::get())
+				.saturating_sub(<frame_system::Module<T>>::block_number()),
+			ElectionStatus::Closed => Zero::zero(),
+		}
+	}
+
+	/// Close the election window and drop the snapshot. Returns the queued election result, if
+	/// any.
+	fn close_election_window() -> Option<ElectionResult<T::AccountId, BalanceOf<T>>> {
+		<EraElectionStatus<T>>::kill();
+		<SnapshotValidators<T>>::kill();
+		<SnapshotNominators<T>>::kill();
+		QueuedScore::kill();
+		QueuedSubmissions::kill();
+		<QueuedElected<T>>::take()
+	}
+
+	/// Check that an election solution with `claimed_score` may be submitted at all, i.e. that the
+	/// election window is open, that the maximum number of submissions is not reached yet and
+	/// that the score is better than the one of the queued solution, if any.
+	///
+	/// This is cheap and done before the solution itself is checked.
+	pub fn pre_check_election_solution(claimed_score: PhragmenScore) -> result::Result<(), Error<T>> {
+		ensure!(Self::era_election_status().is_open(), Error::<T>::PhragmenEarlySubmission);
+		ensure!(
+			Self::queued_submissions() < T::MaxSubmissions::get(),
+			Error::<T>::PhragmenTooManySubmissions,
+		);
+		if let Some(queued_score) = Self::queued_score() {
+			ensure!(
+				sp_phragmen::is_score_better(claimed_score, queued_score),
+				Error::<T>::PhragmenWeakSubmission,
+			);
+		}
+
+		Ok(())
+	}
+
+	/// Check that an election solution passes `pre_check_election_solution`, that it is feasible
+	/// and that `claimed_score` is its actual score.
+	///
+	/// Returns the election result built out of the solution.
+	fn check_election_solution(
+		winners: Vec<ValidatorIndex>,
+		compact: Compact,
+		claimed_score: PhragmenScore,
+	) -> result::Result<ElectionResult<T::AccountId, BalanceOf<T>>, Error<T>> {
+		Self::pre_check_election_solution(claimed_score)?;
+
+		let snapshot_validators = Self::snapshot_validators()
+			.ok_or(Error::<T>::SnapshotUnavailable)?;
+		let snapshot_nominators = Self::snapshot_nominators()
+			.ok_or(Error::<T>::SnapshotUnavailable)?;
+
+		// exactly as many winners as the on-chain election would elect.
+		let desired_winners = (Self::validator_count() as usize).min(snapshot_validators.len());
+		ensure!(
+			winners.len() == desired_winners
+				&& desired_winners >= Self::minimum_validator_count().max(1) as usize,
+			Error::<T>::PhragmenBogusWinnerCount,
+		);
+
+		let winners = winners.into_iter()
+			.map(|index| snapshot_validators.get(index as usize).cloned())
+			.collect::<Option<Vec<T::AccountId>>>()
+			.ok_or(Error::<T>::PhragmenBogusWinner)?;
+		let winner_set = winners.iter().collect::<BTreeSet<_>>();
+		ensure!(winner_set.len() == winners.len(), Error::<T>::PhragmenBogusWinner);
+
+		let num_validators = snapshot_validators.len();
+		let assignments = compact.into_assignment(
+			|index: NominatorIndex| {
+				let index = index as usize;
+				snapshot_validators.get(index)
+					.or_else(|| index.checked_sub(num_validators)
+						.and_then(|index| snapshot_nominators.get(index))
+					)
+					.cloned()
+			},
+			|index: ValidatorIndex| snapshot_validators.get(index as usize).cloned(),
+		).ok_or(Error::<T>::PhragmenBogusCompact)?;
+
+		// every voter may only back the winners it actually voted for, once.
+		let validator_set = snapshot_validators.iter().collect::<BTreeSet<_>>();
+		let mut voters = BTreeSet::new();
+		for (voter, distribution) in assignments.iter() {
+			ensure!(voters.insert(voter), Error::<T>::PhragmenBogusCompact);
+
+			let nominated = if validator_set.contains(voter) {
+				vec![voter.clone()]
+			} else {
+				Self::nominators(voter)
+					.map(Self::filter_nominations)
+					.ok_or(Error::<T>::PhragmenBogusNomination)?
+			};
+
+			let mut targets = BTreeSet::new();
+			for (target, _) in distribution.iter() {
+				ensure!(targets.insert(target), Error::<T>::PhragmenBogusCompact);
+				ensure!(nominated.contains(target), Error::<T>::PhragmenBogusNomination);
+				ensure!(winner_set.contains(target), Error::<T>::PhragmenBogusEdge);
+			}
 		}
+
+		let supports = sp_phragmen::build_support_map::<_, _, _, T::CurrencyToVote>(
+			&winners,
+			&assignments,
+			Self::slashable_balance_of,
+		);
+		ensure!(
+			sp_phragmen::evaluate_support(&supports) == claimed_score,
+			Error::<T>::PhragmenBogusScore,
+		);
+
+		Ok(Self::build_election_result(winners, supports, ElectionCompute::Unsigned))
 	}
 
 	/// Remove all associated data of a stash account from the staking system.
@@ -1648,7 +2055,6 @@ impl<T: Trait> Module<T> {
 
 impl<T: Trait> pallet_session::OnSessionEnding<T::AccountId> for Module<T> {
 	fn on_session_ending(_ending: SessionIndex, start_session: SessionIndex) -> Option<Vec<T::AccountId>> {
-		Self::ensure_storage_upgraded();
 		Self::new_session(start_session - 1).map(|(new, _old)| new)
 	}
 }
@@ -1657,14 +2063,12 @@ impl<T: Trait> OnSessionEnding<T::AccountId, Exposure<T::AccountId, BalanceOf<T>
 	fn on_session_ending(_ending: SessionIndex, start_session: SessionIndex)
 		-> Option<(Vec<T::AccountId>, Vec<(T::AccountId, Exposure<T::AccountId, BalanceOf<T>>)>)>
 	{
-		Self::ensure_storage_upgraded();
 		Self::new_session(start_session - 1)
 	}
 }
 
 impl<T: Trait> OnFreeBalanceZero<T::AccountId> for Module<T> {
 	fn on_free_balance_zero(stash: &T::AccountId) {
-		Self::ensure_storage_upgraded();
 		Self::kill_stash(stash);
 	}
 }
@@ -1730,8 +2134,6 @@ impl <T: Trait> OnOffenceHandler<T::AccountId, pallet_session::historical::Ident
 		slash_fraction: &[Perbill],
 		slash_session: SessionIndex,
 	) {
-		<Module<T>>::ensure_storage_upgraded();
-
 		let reward_proportion = SlashRewardFraction::get();
 
 		let era_now = Self::current_era();
@@ -1807,8 +2209,6 @@ impl<T, Reporter, Offender, R, O> ReportOffence<Reporter, Offender, O>
 	O: Offence<Offender>,
 {
 	fn report_offence(reporters: Vec<Reporter>, offence: O) {
-		<Module<T>>::ensure_storage_upgraded();
-
 		// disallow any slashing from before the current bonding period.
 		let offence_session = offence.session_index();
 		let bonded_eras = BondedEras::get();
@@ -1822,3 +2222,37 @@ impl<T, Reporter, Offender, R, O> ReportOffence<Reporter, Offender, O>
 		}
 	}
 }
+
+#[allow(deprecated)]
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(call: &Self::Call) -> TransactionValidity {
+		if let Call::submit_election_solution_unsigned(winners, compact, score) = call {
+			// run the full check here already, so that bogus solutions never make it into a block.
+			// Weak or late solutions are rejected by the cheap checks before the solution is
+			// decoded against the snapshot.
+			if let Err(e) = Self::check_election_solution(winners.clone(), compact.clone(), *score) {
+				debug::native::debug!(
+					target: "staking",
+					"rejecting unsigned election solution: {:?}",
+					e,
+				);
+				return InvalidTransaction::Custom(e.as_u8()).into();
+			}
+
+			Ok(ValidTransaction {
+				// better solutions replace worse ones in the pool.
+				priority: score[0].saturated_into::<TransactionPriority>(),
+				requires: vec![],
+				// only one solution per era.
+				provides: vec![(STAKING_ID, Self::current_era()).encode()],
+				// the solution becomes stale once the election window closes.
+				longevity: Self::election_window_remaining().saturated_into::<TransactionLongevity>(),
+				propagate: true,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+}
@@ -15,41 +15,51 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Storage migrations for srml-staking.
+//!
+//! Migrations are executed in `on_runtime_upgrade`. The storage version of the module is tracked
+//! with `GetStorageVersion`, its current version is declared in `decl_storage!`.
 
-/// Indicator of a version of a storage layout.
-pub type VersionNumber = u32;
-
-// the current expected version of the storage
-pub const CURRENT_VERSION: VersionNumber = 1;
+/// The weight of migrating a single `Nominators` entry to v1.
+#[cfg(any(test, feature = "migrate"))]
+pub(crate) const MIGRATE_NOMINATOR_WEIGHT: frame_support::weights::Weight = 50_000;
 
 #[cfg(any(test, feature = "migrate"))]
 mod inner {
 	use crate::{Store, Module, Trait};
-	use frame_support::{StorageLinkedMap, StorageValue};
-	use sp_std::vec::Vec;
-	use super::{CURRENT_VERSION, VersionNumber};
+	use frame_support::{StorageLinkedMap, StorageValue, traits::{GetStorageVersion, StorageVersion}};
+	use sp_std::{vec::Vec, cell::Cell};
+	use super::MIGRATE_NOMINATOR_WEIGHT;
 
 	// the minimum supported version of the migration logic.
-	const MIN_SUPPORTED_VERSION: VersionNumber = 0;
+	const MIN_SUPPORTED_VERSION: StorageVersion = StorageVersion::new(0);
 
 	// migrate storage from v0 to v1.
 	//
 	// this upgrades the `Nominators` linked_map value type from `Vec<T::AccountId>` to
-	// `Option<Nominations<T::AccountId>>`
-	pub fn to_v1<T: Trait>(version: &mut VersionNumber) {
+	// `Option<Nominations<T::AccountId>>`. the weight of every migrated entry is registered in
+	// the block.
+	pub fn to_v1<T: Trait>(version: &mut StorageVersion) {
 		if *version != 0 { return }
-		*version += 1;
+		*version = StorageVersion::new(1);
 
 		let now = <Module<T>>::current_era();
+		let migrated = Cell::new(0);
 		let res = <Module<T> as Store>::Nominators::translate::<T::AccountId, Vec<T::AccountId>, _, _>(
 			|key| key,
-			|targets| crate::Nominations {
-				targets,
-				submitted_in: now,
-				suppressed: false,
+			|targets| {
+				migrated.set(migrated.get() + 1);
+				crate::Nominations {
+					targets,
+					submitted_in: now,
+					suppressed: false,
+				}
 			},
 		);
 
+		<frame_system::Module<T>>::register_extra_weight_unchecked(
+			MIGRATE_NOMINATOR_WEIGHT.saturating_mul(migrated.get()),
+		);
+
 		if let Err(e) = res {
 			frame_support::print("Encountered error in migration of Staking::Nominators map.");
 			if e.is_none() {
@@ -61,18 +71,23 @@ mod inner {
 	}
 
 	pub(super) fn perform_migrations<T: Trait>() {
-		<Module<T> as Store>::StorageVersion::mutate(|version| {
-			if *version < MIN_SUPPORTED_VERSION {
-				frame_support::print("Cannot migrate staking storage because version is less than\
-					minimum.");
-				frame_support::print(*version);
-				return
-			}
+		// chains running before the storage version was tracked by frame-support have it stored
+		// in the legacy `StorageVersion` item.
+		let mut version = <Module<T> as Store>::StorageVersion::take()
+			.map(|v| StorageVersion::new(v as u16))
+			.unwrap_or_else(<Module<T>>::on_chain_storage_version);
+
+		if version < MIN_SUPPORTED_VERSION {
+			frame_support::print("Cannot migrate staking storage because version is less than\
+				minimum.");
+			return
+		}
 
-			if *version == CURRENT_VERSION { return }
+		if version != <Module<T>>::current_storage_version() {
+			to_v1::<T>(&mut version);
+		}
 
-			to_v1::<T>(version);
-		});
+		<Module<T>>::put_on_chain_storage_version(version);
 	}
 }
 
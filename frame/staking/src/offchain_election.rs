@@ -0,0 +1,169 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Helpers for computing the staking election off-chain and submitting it back to the chain.
+
+use sp_std::{prelude::*, collections::btree_map::BTreeMap};
+use codec::Encode;
+use sp_core::offchain::StorageKind;
+use sp_runtime::RuntimeDebug;
+use sp_phragmen::{PhragmenResult, PhragmenScore};
+use frame_support::traits::Get;
+use frame_system::offchain::SubmitUnsignedTransaction;
+use crate::{
+	Call, Module, Trait, Compact, NominatorIndex, ValidatorIndex, BalanceOf,
+};
+
+/// Key of the offchain local storage entry recording the last era an election was submitted for.
+const OFFCHAIN_ELECTION_DB_KEY: &[u8] = b"parity/staking-election/";
+
+/// The tolerance of the equalize post-processing, in units of `ExtendedBalance`.
+const EQUALIZE_TOLERANCE: u128 = 0;
+
+/// Error of the off-chain election.
+#[derive(RuntimeDebug)]
+pub enum OffchainElectionError {
+	/// The snapshot of the election window is not available.
+	SnapshotUnavailable,
+	/// Phragmen could not elect enough validators.
+	ElectionFailed,
+	/// The election result could not be compacted using the snapshot indices.
+	CompactFailed,
+	/// An election solution was already submitted for this era.
+	AlreadyRun,
+	/// The computed solution would be rejected by the chain without being checked, e.g. because
+	/// it is not better than the queued one.
+	PreCheckFailed,
+	/// Submitting the transaction failed.
+	SubmitTransaction,
+}
+
+/// Compute the election result over the snapshot of the current election window.
+///
+/// Returns the winners and assignments in compact form, along with the score of the result.
+pub fn compute_offchain_election<T: Trait>()
+	-> Result<(Vec<ValidatorIndex>, Compact, PhragmenScore), OffchainElectionError>
+{
+	let snapshot_validators = <Module<T>>::snapshot_validators()
+		.ok_or(OffchainElectionError::SnapshotUnavailable)?;
+	let snapshot_nominators = <Module<T>>::snapshot_nominators()
+		.ok_or(OffchainElectionError::SnapshotUnavailable)?;
+
+	// validators vote for themselves, followed by the nominators who still have nominations.
+	let mut voters = snapshot_validators.iter()
+		.map(|who| (who.clone(), vec![who.clone()]))
+		.collect::<Vec<_>>();
+	voters.extend(snapshot_nominators.iter().filter_map(|who| {
+		<Module<T>>::nominators(who)
+			.map(|nominations| (who.clone(), <Module<T>>::filter_nominations(nominations)))
+	}));
+
+	let PhragmenResult { winners, assignments } = sp_phragmen::elect::<_, _, _, T::CurrencyToVote>(
+		<Module<T>>::validator_count() as usize,
+		<Module<T>>::minimum_validator_count().max(1) as usize,
+		snapshot_validators.clone(),
+		voters,
+		<Module<T>>::slashable_balance_of,
+	).ok_or(OffchainElectionError::ElectionFailed)?;
+
+	let winners = winners.into_iter().map(|(who, _)| who).collect::<Vec<T::AccountId>>();
+
+	let iterations = T::MaxIterations::get() as usize;
+	let assignments = if iterations > 0 {
+		let mut supports = sp_phragmen::build_support_map::<_, _, _, T::CurrencyToVote>(
+			&winners,
+			&assignments,
+			<Module<T>>::slashable_balance_of,
+		);
+		let mut staked = sp_phragmen::assignment_ratio_to_staked::<_, _, _, T::CurrencyToVote>(
+			assignments,
+			<Module<T>>::slashable_balance_of,
+		);
+		sp_phragmen::equalize::<BalanceOf<T>, _, T::CurrencyToVote, _>(
+			&mut staked,
+			&mut supports,
+			EQUALIZE_TOLERANCE,
+			iterations,
+			<Module<T>>::slashable_balance_of,
+		);
+		sp_phragmen::assignment_staked_to_ratio(staked)
+	} else {
+		assignments
+	};
+
+	// the score must be computed exactly like the chain will, i.e. from the ratios.
+	let supports = sp_phragmen::build_support_map::<_, _, _, T::CurrencyToVote>(
+		&winners,
+		&assignments,
+		<Module<T>>::slashable_balance_of,
+	);
+	let score = sp_phragmen::evaluate_support(&supports);
+
+	let num_validators = snapshot_validators.len();
+	let validator_index = snapshot_validators.iter()
+		.enumerate()
+		.map(|(index, who)| (who.clone(), index))
+		.collect::<BTreeMap<_, _>>();
+	let nominator_index = snapshot_nominators.iter()
+		.enumerate()
+		.map(|(index, who)| (who.clone(), index))
+		.collect::<BTreeMap<_, _>>();
+
+	let target_index = |who: &T::AccountId| -> Option<ValidatorIndex> {
+		validator_index.get(who).map(|index| *index as ValidatorIndex)
+	};
+	let voter_index = |who: &T::AccountId| -> Option<NominatorIndex> {
+		validator_index.get(who).cloned()
+			.or_else(|| nominator_index.get(who).map(|index| index + num_validators))
+			.map(|index| index as NominatorIndex)
+	};
+
+	let winners = winners.iter()
+		.map(target_index)
+		.collect::<Option<Vec<_>>>()
+		.ok_or(OffchainElectionError::CompactFailed)?;
+	let compact = Compact::from_assignment(assignments, voter_index, target_index)
+		.ok_or(OffchainElectionError::CompactFailed)?;
+
+	Ok((winners, compact, score))
+}
+
+/// Compute the election result and submit it as an unsigned transaction, at most once per era.
+pub fn compute_and_submit<T: Trait>(_now: T::BlockNumber) -> Result<(), OffchainElectionError> {
+	let current_era = <Module<T>>::current_era().encode();
+	let last_era = sp_io::offchain::local_storage_get(StorageKind::PERSISTENT, OFFCHAIN_ELECTION_DB_KEY);
+	if last_era.as_ref() == Some(&current_era) {
+		return Err(OffchainElectionError::AlreadyRun);
+	}
+
+	// take the lock before doing the heavy work, so that concurrent workers don't repeat it.
+	if !sp_io::offchain::local_storage_compare_and_set(
+		StorageKind::PERSISTENT,
+		OFFCHAIN_ELECTION_DB_KEY,
+		last_era,
+		&current_era,
+	) {
+		return Err(OffchainElectionError::AlreadyRun);
+	}
+
+	let (winners, compact, score) = compute_offchain_election::<T>()?;
+	<Module<T>>::pre_check_election_solution(score)
+		.map_err(|_| OffchainElectionError::PreCheckFailed)?;
+	let call = Call::submit_election_solution_unsigned(winners, compact, score);
+	T::SubmitTransaction::submit_unsigned(call)
+		.map_err(|_| OffchainElectionError::SubmitTransaction)
+}
@@ -18,11 +18,11 @@
 
 use super::*;
 use mock::*;
-use sp_runtime::{assert_eq_error_rate, traits::{OnInitialize, BadOrigin}};
+use sp_runtime::{assert_eq_error_rate, traits::{OnInitialize, OnRuntimeUpgrade, BadOrigin}};
 use sp_staking::offence::OffenceDetails;
 use frame_support::{
 	assert_ok, assert_noop,
-	traits::{Currency, ReservableCurrency},
+	traits::{Currency, ReservableCurrency, GetStorageVersion, StorageVersion},
 	dispatch::DispatchError,
 };
 use substrate_test_utils::assert_eq_uvec;
@@ -38,7 +38,7 @@ fn force_unstake_works() {
 			Balances::transfer(Origin::signed(11), 1, 10),
 			DispatchError::Module {
 				index: 0,
-				error: 1,
+				error: 0,
 				message: Some("LiquidityRestrictions"),
 			}
 		);
@@ -279,12 +279,14 @@ fn staking_should_work() {
 			// put some money in account that we'll use.
 			for i in 1..5 { let _ = Balances::make_free_balance_be(&i, 2000); }
 
-			// --- Block 1:
-			start_session(1);
-			// add a new candidate for being a validator. account 3 controlled by 4.
+			// add a new candidate for being a validator. account 3 controlled by 4. This can't
+			// be done in block 1, while the election window is open.
 			assert_ok!(Staking::bond(Origin::signed(3), 4, 1500, RewardDestination::Controller));
 			assert_ok!(Staking::validate(Origin::signed(4), ValidatorPrefs::default()));
 
+			// --- Block 1:
+			start_session(1);
+
 			// No effects will be seen so far.
 			assert_eq_uvec!(validator_controllers(), vec![20, 10]);
 
@@ -299,13 +301,13 @@ fn staking_should_work() {
 			start_session(3);
 			assert_eq!(Staking::current_era(), 1);
 
+			// --- Block 3: Unstake 4 as a validator, freeing up the balance stashed in 3
+			// 4 will chill, before the election window opens in block 4.
+			Staking::chill(Origin::signed(4)).unwrap();
+
 			// --- Block 4: the validators will now be changed.
 			start_session(4);
-
 			assert_eq_uvec!(validator_controllers(), vec![20, 4]);
-			// --- Block 4: Unstake 4 as a validator, freeing up the balance stashed in 3
-			// 4 will chill
-			Staking::chill(Origin::signed(4)).unwrap();
 
 			// --- Block 5: nothing. 4 is still there.
 			start_session(5);
@@ -325,7 +327,7 @@ fn staking_should_work() {
 				Balances::reserve(&3, 501),
 				DispatchError::Module {
 					index: 0,
-					error: 1,
+					error: 0,
 					message: Some("LiquidityRestrictions"),
 				}
 			);
@@ -766,7 +768,7 @@ fn cannot_transfer_staked_balance() {
 			Balances::transfer(Origin::signed(11), 20, 1),
 			DispatchError::Module {
 				index: 0,
-				error: 1,
+				error: 0,
 				message: Some("LiquidityRestrictions"),
 			}
 		);
@@ -795,7 +797,7 @@ fn cannot_transfer_staked_balance_2() {
 			Balances::transfer(Origin::signed(21), 20, 1001),
 			DispatchError::Module {
 				index: 0,
-				error: 1,
+				error: 0,
 				message: Some("LiquidityRestrictions"),
 			}
 		);
@@ -818,7 +820,7 @@ fn cannot_reserve_staked_balance() {
 			Balances::reserve(&11, 1),
 			DispatchError::Module {
 				index: 0,
-				error: 1,
+				error: 0,
 				message: Some("LiquidityRestrictions"),
 			}
 		);
@@ -2672,7 +2674,45 @@ fn remove_multi_deferred() {
 #[test]
 fn version_initialized() {
 	ExtBuilder::default().build().execute_with(|| {
-		assert_eq!(<Staking as Store>::StorageVersion::get(), crate::migration::CURRENT_VERSION);
+		assert_eq!(Staking::on_chain_storage_version(), Staking::current_storage_version());
+		assert_eq!(Staking::on_chain_storage_version(), 1);
+		assert!(<Staking as Store>::StorageVersion::get().is_none());
+	});
+}
+
+#[test]
+fn legacy_version_is_migrated_on_runtime_upgrade() {
+	ExtBuilder::default().build().execute_with(|| {
+		// a chain which tracked its version in the legacy storage item.
+		Staking::put_on_chain_storage_version(StorageVersion::new(0));
+		<Staking as Store>::StorageVersion::put(1);
+
+		Staking::on_runtime_upgrade();
+
+		assert_eq!(Staking::on_chain_storage_version(), 1);
+		assert!(<Staking as Store>::StorageVersion::get().is_none());
+	});
+}
+
+#[test]
+fn nominators_migration_is_weighed_per_entry() {
+	ExtBuilder::default().build().execute_with(|| {
+		use frame_support::storage::{unhashed, generator::{StorageLinkedMap, Linkage}};
+
+		// store the nomination of 101 in the v0 format.
+		let key = <<Staking as Store>::Nominators as StorageLinkedMap<
+			AccountId, Nominations<AccountId>,
+		>>::storage_linked_map_final_key(&101);
+		let (_, linkage): (Nominations<AccountId>, Linkage<AccountId>) =
+			unhashed::get(&key).unwrap();
+		unhashed::put(&key, &(vec![11u64, 21], linkage));
+		Staking::put_on_chain_storage_version(StorageVersion::new(0));
+
+		Staking::on_runtime_upgrade();
+
+		assert_eq!(Staking::on_chain_storage_version(), 1);
+		assert_eq!(Staking::nominators(101).unwrap().targets, vec![11, 21]);
+		assert_eq!(System::all_extrinsics_weight(), migration::MIGRATE_NOMINATOR_WEIGHT);
 	});
 }
 
@@ -2714,3 +2754,235 @@ fn slash_kicks_validators_not_nominators() {
 		assert!(nominations.submitted_in < last_slash);
 	});
 }
+
+#[test]
+fn election_window_opens_one_session_before_era_end() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(Staking::era_election_status(), ElectionStatus::Closed);
+		assert!(Staking::snapshot_validators().is_none());
+
+		// the era ends with session 3, so the window opens with session 2.
+		start_session(1);
+		assert_eq!(Staking::era_election_status(), ElectionStatus::Open(2));
+		assert_eq_uvec!(Staking::snapshot_validators().unwrap(), vec![11, 21, 31]);
+		assert_eq!(Staking::snapshot_nominators().unwrap(), vec![101]);
+
+		// nobody submitted anything, the election falls back to on-chain.
+		start_session(2);
+		assert_eq!(Staking::current_era(), 1);
+		assert_eq!(Staking::era_election_status(), ElectionStatus::Closed);
+		assert!(Staking::snapshot_validators().is_none());
+		assert!(Staking::snapshot_nominators().is_none());
+		assert_eq_uvec!(Staking::current_elected(), vec![11, 21]);
+	});
+}
+
+#[test]
+fn staking_calls_are_not_allowed_while_election_window_is_open() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_session(1);
+		assert!(Staking::era_election_status().is_open());
+
+		assert_noop!(Staking::bond_extra(Origin::signed(11), 10), Error::<Test>::CallNotAllowed);
+		assert_noop!(Staking::unbond(Origin::signed(10), 10), Error::<Test>::CallNotAllowed);
+		assert_noop!(Staking::withdraw_unbonded(Origin::signed(10)), Error::<Test>::CallNotAllowed);
+		assert_noop!(Staking::rebond(Origin::signed(10), 10), Error::<Test>::CallNotAllowed);
+		assert_noop!(
+			Staking::validate(Origin::signed(10), ValidatorPrefs::default()),
+			Error::<Test>::CallNotAllowed,
+		);
+		assert_noop!(
+			Staking::nominate(Origin::signed(100), vec![11]),
+			Error::<Test>::CallNotAllowed,
+		);
+		assert_noop!(Staking::chill(Origin::signed(100)), Error::<Test>::CallNotAllowed);
+		assert_noop!(
+			Staking::set_controller(Origin::signed(11), 5),
+			Error::<Test>::CallNotAllowed,
+		);
+
+		// once the window is closed again, all of them go through.
+		start_session(2);
+		assert!(Staking::era_election_status().is_closed());
+
+		assert_ok!(Staking::bond_extra(Origin::signed(11), 10));
+		assert_ok!(Staking::unbond(Origin::signed(10), 10));
+		assert_ok!(Staking::rebond(Origin::signed(10), 10));
+		assert_ok!(Staking::withdraw_unbonded(Origin::signed(10)));
+		assert_ok!(Staking::validate(Origin::signed(10), ValidatorPrefs::default()));
+		assert_ok!(Staking::nominate(Origin::signed(100), vec![11]));
+		assert_ok!(Staking::chill(Origin::signed(100)));
+		assert_ok!(Staking::set_controller(Origin::signed(11), 5));
+	});
+}
+
+#[test]
+fn election_solution_longevity_is_bounded_by_the_window() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_session(1);
+		assert_eq!(Staking::era_election_status(), ElectionStatus::Open(2));
+
+		let (winners, compact, score) =
+			offchain_election::compute_offchain_election::<Test>().unwrap();
+		let call = super::Call::submit_election_solution_unsigned(winners, compact, score);
+		#[allow(deprecated)]
+		let validity = <Staking as frame_support::unsigned::ValidateUnsigned>::validate_unsigned(&call);
+
+		// the window opened in block 2 and closes with the session, one block later.
+		assert_eq!(validity.unwrap().longevity, 1);
+	});
+}
+
+#[test]
+fn offchain_election_solution_is_enacted() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_session(1);
+		assert!(Staking::era_election_status().is_open());
+
+		let (winners, compact, score) =
+			offchain_election::compute_offchain_election::<Test>().unwrap();
+		assert_ok!(Staking::submit_election_solution_unsigned(
+			Origin::NONE,
+			winners,
+			compact,
+			score,
+		));
+		assert_eq!(Staking::queued_score(), Some(score));
+		assert_eq!(
+			Staking::queued_elected().unwrap().compute,
+			ElectionCompute::Unsigned,
+		);
+
+		start_session(2);
+		assert_eq!(Staking::current_era(), 1);
+		assert!(Staking::queued_elected().is_none());
+		assert!(Staking::queued_score().is_none());
+		assert_eq_uvec!(Staking::current_elected(), vec![11, 21]);
+		assert_eq!(Staking::stakers(11).total + Staking::stakers(21).total, score[1] as Balance);
+		check_exposure_all();
+		check_nominator_all();
+	});
+}
+
+#[test]
+fn bogus_or_weak_election_solutions_are_rejected() {
+	ExtBuilder::default().build().execute_with(|| {
+		// the window is not open yet.
+		assert_noop!(
+			Staking::submit_election_solution_unsigned(
+				Origin::NONE,
+				vec![],
+				Default::default(),
+				Default::default(),
+			),
+			Error::<Test>::PhragmenEarlySubmission,
+		);
+
+		start_session(1);
+		let (winners, compact, score) =
+			offchain_election::compute_offchain_election::<Test>().unwrap();
+
+		// signed submissions are not allowed.
+		assert_noop!(
+			Staking::submit_election_solution_unsigned(
+				Origin::signed(10),
+				winners.clone(),
+				compact.clone(),
+				score,
+			),
+			BadOrigin,
+		);
+
+		// wrong number of winners.
+		assert_noop!(
+			Staking::submit_election_solution_unsigned(
+				Origin::NONE,
+				winners[..1].to_vec(),
+				compact.clone(),
+				score,
+			),
+			Error::<Test>::PhragmenBogusWinnerCount,
+		);
+
+		// duplicate winners.
+		assert_noop!(
+			Staking::submit_election_solution_unsigned(
+				Origin::NONE,
+				vec![winners[0], winners[0]],
+				compact.clone(),
+				score,
+			),
+			Error::<Test>::PhragmenBogusWinner,
+		);
+
+		// a score that does not match the solution.
+		let mut bogus_score = score;
+		bogus_score[0] += 1;
+		assert_noop!(
+			Staking::submit_election_solution_unsigned(
+				Origin::NONE,
+				winners.clone(),
+				compact.clone(),
+				bogus_score,
+			),
+			Error::<Test>::PhragmenBogusScore,
+		);
+
+		// the real solution is accepted by the pool and on dispatch.
+		let call = super::Call::submit_election_solution_unsigned(winners.clone(), compact.clone(), score);
+		#[allow(deprecated)]
+		let validity = <Staking as frame_support::unsigned::ValidateUnsigned>::validate_unsigned(&call);
+		assert_eq!(validity.unwrap().priority, score[0] as TransactionPriority);
+		assert_ok!(Staking::submit_election_solution_unsigned(
+			Origin::NONE,
+			winners.clone(),
+			compact.clone(),
+			score,
+		));
+
+		// but not a second time, since the score is not better.
+		assert_noop!(
+			Staking::submit_election_solution_unsigned(Origin::NONE, winners, compact, score),
+			Error::<Test>::PhragmenWeakSubmission,
+		);
+	});
+}
+
+#[test]
+fn election_solutions_are_pre_checked_and_limited_per_window() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_session(1);
+		let (winners, compact, score) =
+			offchain_election::compute_offchain_election::<Test>().unwrap();
+		assert_ok!(Staking::submit_election_solution_unsigned(
+			Origin::NONE,
+			winners.clone(),
+			compact.clone(),
+			score,
+		));
+		assert_eq!(Staking::queued_submissions(), 1);
+
+		// a weak solution is rejected before it is checked at all.
+		assert_noop!(
+			Staking::submit_election_solution_unsigned(Origin::NONE, vec![], Default::default(), score),
+			Error::<Test>::PhragmenWeakSubmission,
+		);
+
+		// once the maximum number of solutions is queued, no more are accepted.
+		QueuedSubmissions::put(<Test as Trait>::MaxSubmissions::get());
+		let mut better_score = score;
+		better_score[0] += 1;
+		assert_noop!(
+			Staking::submit_election_solution_unsigned(Origin::NONE, winners, compact, better_score),
+			Error::<Test>::PhragmenTooManySubmissions,
+		);
+		assert_eq!(
+			Staking::pre_check_election_solution(better_score).map_err(DispatchError::from),
+			Err(Error::<Test>::PhragmenTooManySubmissions.into()),
+		);
+
+		// the count starts over in the next window.
+		start_session(2);
+		assert_eq!(Staking::queued_submissions(), 0);
+	});
+}
@@ -230,6 +230,20 @@ use proc_macro::TokenStream;
 /// trait Store for Module<T: Trait> as Example where T::AccountId: std::fmt::Display {}
 /// ```
 ///
+/// ## Storage version
+///
+/// The current storage version of the module can be declared after the prefix:
+///
+/// ```nocompile
+/// trait Store for Module<T: Trait> as Example storage_version(2) {}
+/// ```
+///
+/// The macro implements `frame_support::traits::GetStorageVersion` for the module, which gives
+/// access to the declared version and to the version stored on chain under
+/// `twox_128(prefix) ++ twox_128(":__STORAGE_VERSION__:")`. The declared version is written at
+/// genesis, so declaring a storage version always generates a `GenesisConfig` for the module.
+/// Modules without a storage version declaration use the default version `0`.
+///
 /// ## Limitations
 ///
 /// # Instancing and generic `GenesisConfig`
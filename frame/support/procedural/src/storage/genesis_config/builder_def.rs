@@ -29,6 +29,7 @@ pub struct BuilderDef {
 	/// * build block for storage with build attribute.
 	/// * build block for storage with config attribute and no build attribute.
 	/// * build block for extra genesis build expression.
+	/// * build block for the storage version, if declared.
 	pub blocks: Vec<TokenStream>,
 	/// The build blocks requires generic traits.
 	pub is_generic: bool,
@@ -126,6 +127,18 @@ impl BuilderDef {
 			});
 		}
 
+		// The declared storage version is always written at genesis, even if the module has no
+		// other genesis storage, so that the first migration sees the right on chain version.
+		if def.storage_version.is_some() {
+			is_generic = true;
+
+			let module_struct = &def.module_struct;
+			blocks.push(quote!{{
+				<#module_struct as #scrate::traits::GetStorageVersion>::put_on_chain_storage_version(
+					<#module_struct as #scrate::traits::GetStorageVersion>::current_storage_version(),
+				);
+			}});
+		}
 
 		Self {
 			blocks,
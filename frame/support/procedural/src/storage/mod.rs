@@ -23,6 +23,7 @@ mod getters;
 mod metadata;
 mod instance_trait;
 mod genesis_config;
+mod storage_version;
 
 use quote::quote;
 use frame_support_procedural_tools::{
@@ -55,6 +56,8 @@ pub struct DeclStorageDef {
 	storage_lines: Vec<StorageLineDef>,
 	/// Name of the crate, used for storage prefixes.
 	crate_name: syn::Ident,
+	/// The storage version declared with `storage_version(N)`, if any.
+	storage_version: Option<syn::LitInt>,
 }
 
 impl syn::parse::Parse for DeclStorageDef {
@@ -90,6 +93,8 @@ pub struct DeclStorageDefExt {
 	storage_lines: Vec<StorageLineDefExt>,
 	/// Name of the crate, used for storage prefixes.
 	crate_name: syn::Ident,
+	/// The storage version declared with `storage_version(N)`, if any.
+	storage_version: Option<syn::LitInt>,
 	/// Full struct expansion: `Module<T, I>`.
 	module_struct: proc_macro2::TokenStream,
 	/// Impl block for module: `<T: Trait, I: Instance>`.
@@ -151,6 +156,7 @@ impl From<DeclStorageDef> for DeclStorageDefExt {
 			extra_genesis_build: def.extra_genesis_build,
 			extra_genesis_config_lines: def.extra_genesis_config_lines,
 			crate_name: def.crate_name,
+			storage_version: def.storage_version,
 			storage_lines,
 			module_struct,
 			module_impl,
@@ -415,6 +421,7 @@ pub fn decl_storage_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStr
 	let instance_trait = instance_trait::decl_and_impl(&scrate, &def_ext);
 	let genesis_config = genesis_config::genesis_config_and_build_storage(&scrate, &def_ext);
 	let storage_struct = storage_struct::decl_and_impl(&scrate, &def_ext);
+	let storage_version = storage_version::impl_get_storage_version(&scrate, &def_ext);
 
 	quote!(
 		use #scrate::{
@@ -432,5 +439,6 @@ pub fn decl_storage_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStr
 		#instance_trait
 		#genesis_config
 		#storage_struct
+		#storage_version
 	).into()
 }
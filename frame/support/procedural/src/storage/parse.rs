@@ -36,6 +36,7 @@ mod keyword {
 	syn::custom_keyword!(twox_128);
 	syn::custom_keyword!(twox_64_concat);
 	syn::custom_keyword!(hasher);
+	syn::custom_keyword!(storage_version);
 }
 
 /// Parsing usage only
@@ -60,6 +61,7 @@ struct StorageDefinition {
 	pub mod_gt_token: Token![>],
 	pub as_token: Token![as],
 	pub crate_ident: Ident,
+	pub storage_version: ext::Opt<DeclStorageVersion>,
 	pub where_clause: Option<syn::WhereClause>,
 	pub content: ext::Braces<ext::Punctuated<DeclStorageLine, Token![;]>>,
 	pub extra_genesis: ext::Opt<AddExtraGenesis>,
@@ -71,6 +73,12 @@ struct SpecificHiddenCrate {
 	pub ident: ext::Parens<Ident>,
 }
 
+#[derive(Parse, ToTokens, Debug)]
+struct DeclStorageVersion {
+	pub keyword: keyword::storage_version,
+	pub version: ext::Parens<syn::LitInt>,
+}
+
 #[derive(Parse, ToTokens, Debug)]
 struct AddExtraGenesis {
 	pub extragenesis_keyword: keyword::add_extra_genesis,
@@ -307,6 +315,15 @@ pub fn parse(input: syn::parse::ParseStream) -> syn::Result<super::DeclStorageDe
 
 	let storage_lines = parse_storage_line_defs(def.content.content.inner.into_iter())?;
 
+	let storage_version = match def.storage_version.inner {
+		Some(version) => {
+			let version = version.version.content;
+			version.base10_parse::<u16>()?;
+			Some(version)
+		},
+		None => None,
+	};
+
 	Ok(super::DeclStorageDef {
 		hidden_crate: def.hidden_crate.inner.map(|i| i.ident.content),
 		visibility: def.visibility,
@@ -316,6 +333,7 @@ pub fn parse(input: syn::parse::ParseStream) -> syn::Result<super::DeclStorageDe
 		module_runtime_trait: def.mod_param_bound,
 		where_clause: def.where_clause,
 		crate_name: def.crate_ident,
+		storage_version,
 		module_instance,
 		extra_genesis_build,
 		extra_genesis_config_lines,
@@ -85,6 +85,19 @@
 //!	// same output to stdout, no overhead on WASM.
 //!	native::print!("My struct: {:?}", x);
 //! ```
+//!
+//! To profile the runtime, spans can be entered with `enter_span!`. They are forwarded to the
+//! tracing subscriber of the node both when running natively and in WASM, so they are reported
+//! like any other span when the node is started with `--tracing-targets`. In WASM, spans are only
+//! entered if the runtime is built with the `with-tracing` feature of `frame-support`, otherwise
+//! they don't call into the host at all.
+//!
+//! ```rust,no_run
+//! fn transfer(amount: u64) {
+//!	// The span lasts until the end of the scope and records `amount`.
+//!	frame_support::enter_span!("transfer", amount = amount);
+//! }
+//! ```
 
 use sp_std::vec::Vec;
 use sp_std::fmt::{self, Debug};
@@ -159,6 +172,67 @@ impl Writer {
 	}
 }
 
+/// A tracing span entered by the runtime, which is exited when dropped.
+///
+/// Prefer using the `enter_span!` macro over using this directly.
+pub struct RuntimeSpan(u64);
+
+impl RuntimeSpan {
+	/// Enter a new span with the given `target` and `name`.
+	#[cfg(any(feature = "std", feature = "with-tracing"))]
+	pub fn enter(target: &str, name: &str) -> Self {
+		RuntimeSpan(sp_io::wasm_tracing::enter_span(target, name))
+	}
+
+	/// Enter a new span with the given `target` and `name`.
+	///
+	/// Tracing is not compiled in, so this is a no-op.
+	#[cfg(not(any(feature = "std", feature = "with-tracing")))]
+	pub fn enter(_target: &str, _name: &str) -> Self {
+		RuntimeSpan(0)
+	}
+
+	/// Record a field of this span.
+	///
+	/// The value is only formatted if tracing is enabled on the host.
+	pub fn record(&self, key: &str, value: &dyn Debug) {
+		if self.0 == 0 {
+			return;
+		}
+
+		use fmt::Write;
+		let mut w = Writer::default();
+		let _ = core::write!(&mut w, "{:?}", value);
+		sp_io::wasm_tracing::record(self.0, key, &w.0);
+	}
+}
+
+impl Drop for RuntimeSpan {
+	fn drop(&mut self) {
+		if self.0 != 0 {
+			sp_io::wasm_tracing::exit_span(self.0);
+		}
+	}
+}
+
+/// Enter a tracing span that lasts until the end of the current scope.
+///
+/// The target of the span is the current module path. Fields are recorded with their `Debug`
+/// representation, which is empty for types deriving `RuntimeDebug` when running in WASM.
+///
+/// # Example
+///
+/// ```
+/// frame_support::enter_span!("my_span", my_value = 3);
+/// ```
+#[macro_export]
+macro_rules! enter_span {
+	( $name:expr $(, $key:ident = $value:expr )* $(,)? ) => {
+		let __runtime_span__ = $crate::debug::RuntimeSpan::enter(module_path!(), $name);
+		$( __runtime_span__.record(stringify!($key), &$value); )*
+	}
+}
+
 /// Runtime logger implementation - `log` crate backend.
 ///
 /// The logger should be initialized if you want to display
@@ -202,6 +202,13 @@ impl<T> Parameter for T where T: Codec + EncodeLike + Clone + Eq + fmt::Debug {}
 /// * `offchain_worker`: Executes at the beginning of a block and produces extrinsics for a future block
 /// upon completion. Using this function will implement the
 /// [`OffchainWorker`](../sp_runtime/traits/trait.OffchainWorker.html) trait.
+///
+/// The following reserved function takes no input:
+///
+/// * `on_runtime_upgrade`: Executes exactly once, at the beginning of the first block after a
+/// runtime upgrade, before any `on_initialize`. This is the place for storage migrations. Using
+/// this function will implement the [`OnRuntimeUpgrade`](../sp_runtime/traits/trait.OnRuntimeUpgrade.html)
+/// trait, and its `#[weight]` is registered in the block like the one of `on_initialize`.
 #[macro_export]
 macro_rules! decl_module {
 	// Entry point #1.
@@ -228,6 +235,7 @@ macro_rules! decl_module {
 			{}
 			{}
 			{}
+			{}
 			[]
 			$($t)*
 		);
@@ -260,6 +268,7 @@ macro_rules! decl_module {
 			{}
 			{}
 			{}
+			{}
 			[]
 			$($t)*
 		);
@@ -274,6 +283,7 @@ macro_rules! decl_module {
 		{}
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -290,6 +300,7 @@ macro_rules! decl_module {
 			{ $vis fn deposit_event() = default; }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			{ $( $error_type )* }
@@ -305,6 +316,7 @@ macro_rules! decl_module {
 		{}
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -326,6 +338,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{}
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -345,6 +358,7 @@ macro_rules! decl_module {
 				#[weight = $crate::dispatch::SimpleDispatchInfo::zero()]
 				fn on_finalize( $( $param_name : $param ),* ) { $( $impl )* }
 			}
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			{ $( $error_type )* }
@@ -361,6 +375,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{}
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -381,6 +396,82 @@ macro_rules! decl_module {
 				#[weight = $weight]
 				fn on_finalize( $( $param_name : $param ),* ) { $( $impl )* }
 			}
+			{ $( $on_runtime_upgrade )* }
+			{ $( $offchain )* }
+			{ $( $constants )* }
+			{ $( $error_type )* }
+			[ $( $dispatchables )* ]
+			$($rest)*
+		);
+	};
+	// Add on_runtime_upgrade, without a given weight.
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<$trait_instance:ident: $trait_name:ident$(<I>, I: $instantiable:path $(= $module_default_instance:path)?)?>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $other_where_bounds:tt )* }
+		{ $( $deposit_event:tt )* }
+		{ $( $on_initialize:tt )* }
+		{ $( $on_finalize:tt )* }
+		{}
+		{ $( $offchain:tt )* }
+		{ $( $constants:tt )* }
+		{ $( $error_type:tt )* }
+		[ $( $dispatchables:tt )* ]
+		$(#[doc = $doc_attr:tt])*
+		fn on_runtime_upgrade() { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		$crate::decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name$(<I>, I: $instantiable $(= $module_default_instance)?)?>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $other_where_bounds )* }
+			{ $( $deposit_event )* }
+			{ $( $on_initialize )* }
+			{ $( $on_finalize )* }
+			{
+				#[weight = $crate::dispatch::SimpleDispatchInfo::zero()]
+				fn on_runtime_upgrade() { $( $impl )* }
+			}
+			{ $( $offchain )* }
+			{ $( $constants )* }
+			{ $( $error_type )* }
+			[ $( $dispatchables )* ]
+			$($rest)*
+		);
+	};
+	// Add on_runtime_upgrade, given weight.
+	(@normalize
+		$(#[$attr:meta])*
+		pub struct $mod_type:ident<$trait_instance:ident: $trait_name:ident$(<I>, I: $instantiable:path $(= $module_default_instance:path)?)?>
+		for enum $call_type:ident where origin: $origin_type:ty, system = $system:ident
+		{ $( $other_where_bounds:tt )* }
+		{ $( $deposit_event:tt )* }
+		{ $( $on_initialize:tt )* }
+		{ $( $on_finalize:tt )* }
+		{}
+		{ $( $offchain:tt )* }
+		{ $( $constants:tt )* }
+		{ $( $error_type:tt )* }
+		[ $( $dispatchables:tt )* ]
+		$(#[doc = $doc_attr:tt])*
+		#[weight = $weight:expr]
+		fn on_runtime_upgrade() { $( $impl:tt )* }
+		$($rest:tt)*
+	) => {
+		$crate::decl_module!(@normalize
+			$(#[$attr])*
+			pub struct $mod_type<$trait_instance: $trait_name$(<I>, I: $instantiable $(= $module_default_instance)?)?>
+			for enum $call_type where origin: $origin_type, system = $system
+			{ $( $other_where_bounds )* }
+			{ $( $deposit_event )* }
+			{ $( $on_initialize )* }
+			{ $( $on_finalize )* }
+			{
+				#[weight = $weight]
+				fn on_runtime_upgrade() { $( $impl )* }
+			}
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			{ $( $error_type )* }
@@ -399,6 +490,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{}
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -418,6 +510,7 @@ macro_rules! decl_module {
 				fn on_initialize( $( $param_name : $param ),* ) { $( $impl )* }
 			}
 			{ $( $on_finalize )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			{ $( $error_type )* }
@@ -436,6 +529,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{}
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -456,6 +550,7 @@ macro_rules! decl_module {
 				fn on_initialize( $( $param_name : $param ),* ) { $( $impl )* }
 			}
 			{ $( $on_finalize )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			{ $( $error_type )* }
@@ -474,6 +569,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -492,6 +588,7 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_runtime_upgrade )* }
 			{ fn offchain_worker( $( $param_name : $param ),* ) { $( $impl )* } }
 			{ $( $constants )* }
 			{ $( $error_type )* }
@@ -512,6 +609,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -531,6 +629,7 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{
 				$( $constants )*
@@ -555,6 +654,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ }
@@ -573,6 +673,7 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			{ $error_type }
@@ -592,6 +693,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ }
@@ -608,6 +710,7 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			{ &'static str }
@@ -628,6 +731,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $error_type:ty }
@@ -649,6 +753,7 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			{ $error_type }
@@ -676,6 +781,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -696,6 +802,7 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			{ $( $error_type )* }
@@ -717,6 +824,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -742,6 +850,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -767,6 +876,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -793,6 +903,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $( $error_type:tt )* }
@@ -808,6 +919,7 @@ macro_rules! decl_module {
 			{ $( $deposit_event )* }
 			{ $( $on_initialize )* }
 			{ $( $on_finalize )* }
+			{ $( $on_runtime_upgrade )* }
 			{ $( $offchain )* }
 			{ $( $constants )* }
 			{ $( $error_type )* }
@@ -859,12 +971,7 @@ macro_rules! decl_module {
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
 			fn on_initialize(_block_number_not_used: $trait_instance::BlockNumber) {
-				use $crate::sp_std::if_std;
-				if_std! {
-					use $crate::tracing;
-					let span = tracing::span!(tracing::Level::DEBUG, "on_initialize");
-					let _enter = span.enter();
-				}
+				$crate::enter_span!("on_initialize");
 				{ $( $impl )* }
 			}
 		}
@@ -881,12 +988,7 @@ macro_rules! decl_module {
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
 			fn on_initialize($param: $param_ty) {
-				use $crate::sp_std::if_std;
-				if_std! {
-					use $crate::tracing;
-					let span = tracing::span!(tracing::Level::DEBUG, "on_initialize");
-					let _enter = span.enter();
-				}
+				$crate::enter_span!("on_initialize");
 				{ $( $impl )* }
 			}
 		}
@@ -913,12 +1015,7 @@ macro_rules! decl_module {
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
 			fn on_finalize(_block_number_not_used: $trait_instance::BlockNumber) {
-				use $crate::sp_std::if_std;
-				if_std! {
-					use $crate::tracing;
-					let span = tracing::span!(tracing::Level::DEBUG, "on_finalize");
-					let _enter = span.enter();
-				}
+				$crate::enter_span!("on_finalize");
 				{ $( $impl )* }
 			}
 		}
@@ -935,12 +1032,7 @@ macro_rules! decl_module {
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
 			fn on_finalize($param: $param_ty) {
-				use $crate::sp_std::if_std;
-				if_std! {
-					use $crate::tracing;
-					let span = tracing::span!(tracing::Level::DEBUG, "on_finalize");
-					let _enter = span.enter();
-				}
+				$crate::enter_span!("on_finalize");
 				{ $( $impl )* }
 			}
 		}
@@ -957,6 +1049,33 @@ macro_rules! decl_module {
 		}
 	};
 
+	(@impl_on_runtime_upgrade
+		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
+		{ $( $other_where_bounds:tt )* }
+		#[weight = $weight:expr]
+		fn on_runtime_upgrade() { $( $impl:tt )* }
+	) => {
+		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
+			$crate::sp_runtime::traits::OnRuntimeUpgrade
+			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
+		{
+			fn on_runtime_upgrade() {
+				$crate::enter_span!("on_runtime_upgrade");
+				{ $( $impl )* }
+			}
+		}
+	};
+
+	(@impl_on_runtime_upgrade
+		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
+		{ $( $other_where_bounds:tt )* }
+	) => {
+		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
+			$crate::sp_runtime::traits::OnRuntimeUpgrade
+			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
+		{}
+	};
+
 	(@impl_block_hooks_weight
 		$module:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>;
 		{ $( $other_where_bounds:tt )* }
@@ -968,6 +1087,10 @@ macro_rules! decl_module {
 			#[weight = $weight_finalize:expr]
 			fn on_finalize($( $param_finalize:ident : $param_ty_finalize:ty )*) { $( $impl_finalize:tt )* }
 		)?
+		@upgrade $(
+			#[weight = $weight_runtime_upgrade:expr]
+			fn on_runtime_upgrade() { $( $impl_runtime_upgrade:tt )* }
+		)?
 	) => {
 		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
 		$crate::dispatch::WeighBlock<$trait_instance::BlockNumber> for $module<$trait_instance$(, $instance)?> where
@@ -983,6 +1106,11 @@ macro_rules! decl_module {
 					<dyn $crate::dispatch::WeighData<$trait_instance::BlockNumber>>::weigh_data(&$weight_finalize, n)
 				}
 			)?
+			$(
+				fn on_runtime_upgrade(n: $trait_instance::BlockNumber) -> $crate::dispatch::Weight {
+					<dyn $crate::dispatch::WeighData<$trait_instance::BlockNumber>>::weigh_data(&$weight_runtime_upgrade, n)
+				}
+			)?
 		}
 	};
 
@@ -1038,11 +1166,7 @@ macro_rules! decl_module {
 		$vis fn $name(
 			$origin: $origin_ty $(, $param: $param_ty )*
 		) -> $crate::dispatch::DispatchResult {
-			$crate::sp_std::if_std! {
-				use $crate::tracing;
-				let span = tracing::span!(tracing::Level::DEBUG, stringify!($name));
-				let _enter = span.enter();
-			}
+			$crate::enter_span!(stringify!($name));
 			{
 				{ $( $impl )* }
 				Ok(())
@@ -1063,12 +1187,7 @@ macro_rules! decl_module {
 	) => {
 		$(#[doc = $doc_attr])*
 		$vis fn $name($origin: $origin_ty $(, $param: $param_ty )* ) -> $result {
-			use $crate::sp_std::if_std;
-			if_std! {
-				use $crate::tracing;
-				let span = tracing::span!(tracing::Level::DEBUG, stringify!($name));
-				let _enter = span.enter();
-			}
+			$crate::enter_span!(stringify!($name));
 			{ $( $impl )* }
 		}
 	};
@@ -1208,6 +1327,7 @@ macro_rules! decl_module {
 		{ $( $deposit_event:tt )* }
 		{ $( $on_initialize:tt )* }
 		{ $( $on_finalize:tt )* }
+		{ $( $on_runtime_upgrade:tt )* }
 		{ $( $offchain:tt )* }
 		{ $( $constants:tt )* }
 		{ $error_type:ty }
@@ -1237,12 +1357,20 @@ macro_rules! decl_module {
 			$( $on_finalize )*
 		}
 
+		$crate::decl_module! {
+			@impl_on_runtime_upgrade
+			$mod_type<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?>;
+			{ $( $other_where_bounds )* }
+			$( $on_runtime_upgrade )*
+		}
+
 		$crate::decl_module! {
 			@impl_block_hooks_weight
 			$mod_type<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?>;
 			{ $( $other_where_bounds )* }
 			@init $( $on_initialize )*
 			@fin $( $on_finalize )*
+			@upgrade $( $on_runtime_upgrade )*
 		}
 
 		$crate::decl_module! {
@@ -1836,6 +1964,9 @@ macro_rules! __check_reserved_fn_name {
 	(offchain_worker $( $rest:ident )*) => {
 		$crate::__check_reserved_fn_name!(@compile_error offchain_worker);
 	};
+	(on_runtime_upgrade $( $rest:ident )*) => {
+		$crate::__check_reserved_fn_name!(@compile_error on_runtime_upgrade);
+	};
 	($t:ident $( $rest:ident )*) => {
 		$crate::__check_reserved_fn_name!($( $rest )*);
 	};
@@ -1869,7 +2000,7 @@ macro_rules! __check_reserved_fn_name {
 #[allow(dead_code)]
 mod tests {
 	use super::*;
-	use crate::sp_runtime::traits::{OnInitialize, OnFinalize};
+	use crate::sp_runtime::traits::{OnInitialize, OnFinalize, OnRuntimeUpgrade};
 	use crate::weights::{DispatchInfo, DispatchClass};
 
 	pub trait Trait: system::Trait + Sized where Self::AccountId: From<u32> {
@@ -1913,6 +2044,8 @@ mod tests {
 			fn on_initialize(n: T::BlockNumber,) { if n.into() == 42 { panic!("on_initialize") } }
 			#[weight = BLockWeight]
 			fn on_finalize(n: T::BlockNumber) { if n.into() == 42 { panic!("on_finalize") } }
+			#[weight = SimpleDispatchInfo::FixedOperational(11)]
+			fn on_runtime_upgrade() { panic!("on_runtime_upgrade") }
 			fn offchain_worker() {}
 
 			#[weight = SimpleDispatchInfo::FixedOperational(5)]
@@ -2054,6 +2187,12 @@ mod tests {
 		<Module<TraitImpl> as OnFinalize<u32>>::on_finalize(42);
 	}
 
+	#[test]
+	#[should_panic(expected = "on_runtime_upgrade")]
+	fn on_runtime_upgrade_should_work() {
+		<Module<TraitImpl> as OnRuntimeUpgrade>::on_runtime_upgrade();
+	}
+
 	#[test]
 	fn weight_should_attach_to_call_enum() {
 		// operational.
@@ -2083,5 +2222,7 @@ mod tests {
 		// dependent
 		assert_eq!(<Test as WeighBlock<u32>>::on_finalize(2), 10);
 		assert_eq!(<Test as WeighBlock<u32>>::on_finalize(3), 0);
+
+		assert_eq!(<Test as WeighBlock<u32>>::on_runtime_upgrade(0), 11);
 	}
 }
@@ -214,10 +214,7 @@ macro_rules! assert_ok {
 	}
 }
 
-/// The void type - it cannot exist.
-// Oh rust, you crack me up...
-#[derive(Clone, Eq, PartialEq, RuntimeDebug)]
-pub enum Void {}
+pub use sp_core::Void;
 
 #[cfg(feature = "std")]
 #[doc(hidden)]
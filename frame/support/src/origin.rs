@@ -19,6 +19,9 @@
 
 /// Constructs an Origin type for a runtime. This is usually called automatically by the
 /// construct_runtime macro. See also __create_decl_macro.
+///
+/// Attributes given to the enum are added to the Origin type, e.g. `#[derive(Encode, Decode)]`
+/// makes it encodable as long as the origins of all the modules are.
 #[macro_export]
 macro_rules! impl_outer_origin {
 
@@ -26,6 +26,32 @@ pub mod child;
 #[doc(hidden)]
 pub mod generator;
 
+/// Describes whether a storage transaction should be committed or rolled back.
+pub enum TransactionOutcome<R> {
+	/// Keep the changes made in the transaction.
+	Commit(R),
+	/// Discard the changes made in the transaction.
+	Rollback(R),
+}
+
+/// Execute the supplied function in a new storage transaction.
+///
+/// All changes to storage performed by the supplied function are discarded if the returned
+/// outcome is `TransactionOutcome::Rollback`, and kept otherwise. Transactions can be nested.
+pub fn with_transaction<R>(f: impl FnOnce() -> TransactionOutcome<R>) -> R {
+	sp_io::storage::start_transaction();
+	match f() {
+		TransactionOutcome::Commit(res) => {
+			sp_io::storage::commit_transaction();
+			res
+		},
+		TransactionOutcome::Rollback(res) => {
+			sp_io::storage::rollback_transaction();
+			res
+		},
+	}
+}
+
 /// A trait for working with macro-generated storage values under the substrate storage API.
 ///
 /// Details on implementation can be found at
@@ -477,7 +503,32 @@ pub trait StoragePrefixedMap<Value: FullCodec> {
 mod test {
 	use sp_core::hashing::twox_128;
 	use sp_io::TestExternalities;
-	use crate::storage::{unhashed, StoragePrefixedMap};
+	use crate::storage::{unhashed, StoragePrefixedMap, with_transaction, TransactionOutcome};
+
+	#[test]
+	fn transactions_are_committed_and_rolled_back() {
+		TestExternalities::default().execute_with(|| {
+			unhashed::put(b"key", &1u32);
+
+			let res = with_transaction(|| {
+				unhashed::put(b"key", &2u32);
+				with_transaction(|| {
+					unhashed::put(b"other", &3u32);
+					TransactionOutcome::Rollback(())
+				});
+				assert_eq!(unhashed::get::<u32>(b"other"), None);
+				TransactionOutcome::Commit(5)
+			});
+			assert_eq!(res, 5);
+			assert_eq!(unhashed::get::<u32>(b"key"), Some(2));
+
+			with_transaction(|| {
+				unhashed::put(b"key", &4u32);
+				TransactionOutcome::Rollback(())
+			});
+			assert_eq!(unhashed::get::<u32>(b"key"), Some(2));
+		});
+	}
 
 	#[test]
 	fn prefixed_map_works() {
@@ -131,6 +131,21 @@ pub trait KeyOwnerProofSystem<Key> {
 	fn check_proof(key: Key, proof: Self::Proof) -> Option<Self::IdentificationTuple>;
 }
 
+impl<Key> KeyOwnerProofSystem<Key> for () {
+	// The proof and identification tuples are any bottom type to guarantee that the methods of
+	// this implementation can never be called or return anything other than `None`.
+	type Proof = crate::Void;
+	type IdentificationTuple = crate::Void;
+
+	fn prove(_key: Key) -> Option<Self::Proof> {
+		None
+	}
+
+	fn check_proof(_key: Key, _proof: Self::Proof) -> Option<Self::IdentificationTuple> {
+		None
+	}
+}
+
 /// Handler for when some currency "account" decreased in balance for
 /// some reason.
 ///
@@ -609,27 +624,34 @@ pub trait LockableCurrency<AccountId>: Currency<AccountId> {
 	);
 }
 
-/// A currency whose accounts can have balances which vest over time.
-pub trait VestingCurrency<AccountId>: Currency<AccountId> {
+/// A vesting schedule over a currency. This allows a particular currency to have vesting limits
+/// applied to it.
+pub trait VestingSchedule<AccountId> {
 	/// The quantity used to denote time; usually just a `BlockNumber`.
 	type Moment;
 
+	/// The currency that this schedule applies to.
+	type Currency: Currency<AccountId>;
+
 	/// Get the amount that is currently being vested and cannot be transferred out of this account.
-	fn vesting_balance(who: &AccountId) -> Self::Balance;
+	/// Returns `None` if the account has no vesting schedule.
+	fn vesting_balance(who: &AccountId) -> Option<<Self::Currency as Currency<AccountId>>::Balance>;
 
 	/// Adds a vesting schedule to a given account.
 	///
-	/// If there already exists a vesting schedule for the given account, an `Err` is returned
+	/// If the account already has the maximum number of vesting schedules, an `Err` is returned
 	/// and nothing is updated.
+	///
+	/// Is a no-op if the amount to be vested is zero.
 	fn add_vesting_schedule(
 		who: &AccountId,
-		locked: Self::Balance,
-		per_block: Self::Balance,
+		locked: <Self::Currency as Currency<AccountId>>::Balance,
+		per_block: <Self::Currency as Currency<AccountId>>::Balance,
 		starting_block: Self::Moment,
 	) -> DispatchResult;
 
-	/// Remove a vesting schedule for a given account.
-	fn remove_vesting_schedule(who: &AccountId);
+	/// Remove the vesting schedule at index `schedule_index` for a given account.
+	fn remove_vesting_schedule(who: &AccountId, schedule_index: u32) -> DispatchResult;
 }
 
 bitmask! {
@@ -802,3 +824,158 @@ pub trait ModuleToIndex {
 impl ModuleToIndex for () {
 	fn module_to_index<M: 'static>() -> Option<usize> { Some(0) }
 }
+
+/// Determine whether or not a value of a given type is "within" another instance of a type,
+/// typically used to decide whether a call is allowed for some (proxy) permission.
+pub trait InstanceFilter<T>: Sized + Send + Sync {
+	/// Determine if a given value should be allowed with this filter.
+	fn filter(&self, _: &T) -> bool;
+
+	/// Determines whether `self` matches at least everything that `_o` does.
+	fn is_superset(&self, _o: &Self) -> bool { false }
+}
+
+impl<T> InstanceFilter<T> for () {
+	fn filter(&self, _: &T) -> bool { true }
+	fn is_superset(&self, _o: &Self) -> bool { true }
+}
+
+/// The storage version of a module.
+///
+/// Each module declares its current storage version in `decl_storage!` with
+/// `as Prefix storage_version(N)`. The version stored on chain is updated by the module, most
+/// likely in its `on_runtime_upgrade` hook after the storage was migrated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Encode, Decode)]
+pub struct StorageVersion(u16);
+
+impl StorageVersion {
+	/// The key suffix under the module prefix which holds the on chain storage version.
+	pub const STORAGE_VERSION_KEY: &'static [u8] = b":__STORAGE_VERSION__:";
+
+	/// Creates a new storage version.
+	pub const fn new(version: u16) -> Self {
+		Self(version)
+	}
+
+	/// Returns the storage key of the on chain storage version of the module with the given
+	/// storage prefix.
+	pub fn storage_key(module_prefix: &[u8]) -> [u8; 32] {
+		let mut key = [0u8; 32];
+		key[..16].copy_from_slice(&sp_io::hashing::twox_128(module_prefix));
+		key[16..].copy_from_slice(&sp_io::hashing::twox_128(Self::STORAGE_VERSION_KEY));
+		key
+	}
+}
+
+impl From<u16> for StorageVersion {
+	fn from(version: u16) -> Self {
+		Self(version)
+	}
+}
+
+impl PartialEq<u16> for StorageVersion {
+	fn eq(&self, other: &u16) -> bool {
+		self.0 == *other
+	}
+}
+
+impl PartialOrd<u16> for StorageVersion {
+	fn partial_cmp(&self, other: &u16) -> Option<sp_std::cmp::Ordering> {
+		Some(self.0.cmp(other))
+	}
+}
+
+/// Provides information about the storage version of a module.
+///
+/// Implemented by `decl_storage!` for every module.
+pub trait GetStorageVersion {
+	/// Returns the storage version declared in `decl_storage!`, or the default version if the
+	/// module doesn't declare one.
+	fn current_storage_version() -> StorageVersion;
+
+	/// Returns the storage version stored on chain, or the default version if nothing is stored.
+	fn on_chain_storage_version() -> StorageVersion;
+
+	/// Writes the given `version` as the on chain storage version.
+	fn put_on_chain_storage_version(version: StorageVersion);
+}
+
+/// Something that can schedule calls to be dispatched at a later block.
+pub mod schedule {
+	use super::*;
+
+	/// Information relating to the period of a scheduled task. First item is the length of the
+	/// period and the second is the number of times it should be executed in total before the task
+	/// is considered finished and removed.
+	pub type Period<BlockNumber> = (BlockNumber, u32);
+
+	/// Priority with which a call is scheduled. It's just a linear amount with lowest values meaning
+	/// higher priority.
+	pub type Priority = u8;
+
+	/// The highest priority. We invert the value so that normal sorting will place the highest
+	/// priority at the beginning of the list.
+	pub const HIGHEST_PRIORITY: Priority = 0;
+
+	/// Anything of this value or lower will definitely be scheduled on the block that they ask for,
+	/// even if it breaches the `MaximumWeight` limitation.
+	pub const HARD_DEADLINE: Priority = 63;
+
+	/// The lowest priority. Most stuff should be around here.
+	pub const LOWEST_PRIORITY: Priority = 255;
+
+	/// A type that can be used as a scheduler.
+	pub trait Anon<BlockNumber, Call, Origin> {
+		/// An address which can be used for removing a scheduled task.
+		type Address: Codec + Clone + Eq + Debug;
+
+		/// Schedule a one-off dispatch to happen at the beginning of some block in the future.
+		///
+		/// This is not named.
+		///
+		/// Infallible.
+		fn schedule(
+			when: BlockNumber,
+			maybe_periodic: Option<Period<BlockNumber>>,
+			priority: Priority,
+			origin: Origin,
+			call: Call,
+		) -> Self::Address;
+
+		/// Cancel a scheduled task. If periodic, then it will cancel all further instances of that,
+		/// also.
+		///
+		/// Will return an error if the `address` is invalid.
+		///
+		/// NOTE: This guaranteed to work only *before* the point that it is due to be executed.
+		/// If it ends up being delayed beyond the point of execution, then it cannot be cancelled.
+		fn cancel(address: Self::Address) -> Result<(), ()>;
+	}
+
+	/// A type that can be used as a scheduler.
+	pub trait Named<BlockNumber, Call, Origin> {
+		/// An address which can be used for removing a scheduled task.
+		type Address: Codec + Clone + Eq + Debug;
+
+		/// Schedule a one-off dispatch to happen at the beginning of some block in the future.
+		///
+		/// - `id`: The identity of the task. This must be unique and will return an error if not.
+		fn schedule_named(
+			id: Vec<u8>,
+			when: BlockNumber,
+			maybe_periodic: Option<Period<BlockNumber>>,
+			priority: Priority,
+			origin: Origin,
+			call: Call,
+		) -> Result<Self::Address, ()>;
+
+		/// Cancel a scheduled, named task. If periodic, then it will cancel all further instances
+		/// of that, also.
+		///
+		/// Will return an error if the `id` is invalid.
+		///
+		/// NOTE: This guaranteed to work only *before* the point that it is due to be executed.
+		/// If it ends up being delayed beyond the point of execution, then it cannot be cancelled.
+		fn cancel_named(id: Vec<u8>) -> Result<(), ()>;
+	}
+}
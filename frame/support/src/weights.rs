@@ -74,6 +74,8 @@ pub trait WeighBlock<BlockNumber> {
 	fn on_initialize(_: BlockNumber) -> Weight { Zero::zero() }
 	/// Return the weight of the block's on_finalize hook.
 	fn on_finalize(_: BlockNumber) -> Weight { Zero::zero() }
+	/// Return the weight of the module's on_runtime_upgrade hook.
+	fn on_runtime_upgrade(_: BlockNumber) -> Weight { Zero::zero() }
 }
 
 /// Indicates if dispatch function should pay fees or not.
@@ -102,6 +104,14 @@ impl<BlockNumber: Copy> WeighBlock<BlockNumber> for SingleModule {
 		);
 		accumulated_weight
 	}
+
+	fn on_runtime_upgrade(n: BlockNumber) -> Weight {
+		let mut accumulated_weight: Weight = Zero::zero();
+		for_tuples!(
+			#( accumulated_weight = accumulated_weight.saturating_add(SingleModule::on_runtime_upgrade(n)); )*
+		);
+		accumulated_weight
+	}
 }
 
 /// A generalized group of dispatch types. This is only distinguishing normal, user-triggered transactions
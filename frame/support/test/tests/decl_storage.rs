@@ -696,3 +696,75 @@ mod test_append_and_len {
 		});
 	}
 }
+
+#[cfg(test)]
+#[allow(dead_code)]
+mod test_storage_version {
+	use sp_io::TestExternalities;
+	use codec::Encode;
+	use frame_support::traits::{GetStorageVersion, StorageVersion};
+
+	pub trait Trait {
+		type Origin;
+		type BlockNumber;
+	}
+
+	frame_support::decl_module! {
+		pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+	}
+
+	frame_support::decl_storage! {
+		trait Store for Module<T: Trait> as TestVersion storage_version(3) {
+			Value config(value): u32;
+		}
+	}
+
+	struct TraitImpl {}
+
+	impl Trait for TraitImpl {
+		type Origin = u32;
+		type BlockNumber = u32;
+	}
+
+	#[test]
+	fn storage_version_is_written_at_genesis() {
+		let storage = GenesisConfig { value: 1 }.build_storage::<TraitImpl>().unwrap();
+		let mut ext: TestExternalities = storage.into();
+		ext.execute_with(|| {
+			assert_eq!(Module::<TraitImpl>::current_storage_version(), StorageVersion::new(3));
+			assert_eq!(Module::<TraitImpl>::on_chain_storage_version(), 3);
+
+			Module::<TraitImpl>::put_on_chain_storage_version(StorageVersion::new(4));
+			assert_eq!(Module::<TraitImpl>::on_chain_storage_version(), 4);
+			assert_eq!(
+				sp_io::storage::get(&StorageVersion::storage_key(b"TestVersion")),
+				Some(StorageVersion::new(4).encode()),
+			);
+		});
+	}
+
+	mod without_genesis_storage {
+		use super::Trait;
+
+		frame_support::decl_module! {
+			pub struct Module<T: Trait> for enum Call where origin: T::Origin {}
+		}
+
+		frame_support::decl_storage! {
+			trait Store for Module<T: Trait> as TestVersionNoGenesis storage_version(2) {
+				Value: u32;
+			}
+		}
+	}
+
+	#[test]
+	fn storage_version_is_written_at_genesis_without_genesis_storage() {
+		let storage = without_genesis_storage::GenesisConfig::default()
+			.build_storage::<TraitImpl>()
+			.unwrap();
+		let mut ext: TestExternalities = storage.into();
+		ext.execute_with(|| {
+			assert_eq!(without_genesis_storage::Module::<TraitImpl>::on_chain_storage_version(), 2);
+		});
+	}
+}
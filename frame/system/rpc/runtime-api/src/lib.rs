@@ -19,6 +19,9 @@
 //! This API should be imported and implemented by the runtime,
 //! of a node that wants to use the custom RPC extension
 //! adding System access methods.
+//!
+//! The `BlockWeightApi` is also used by the `dry-run-upgrade` command to
+//! compare the weight of blocks before and after a runtime upgrade.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -31,4 +34,10 @@ sp_api::decl_runtime_apis! {
 		/// Get current account nonce of given `AccountId`.
 		fn account_nonce(account: AccountId) -> Index;
 	}
+
+	/// The API to query the weight of the block being built.
+	pub trait BlockWeightApi {
+		/// Get the total weight of the extrinsics applied to the current block so far.
+		fn block_weight() -> u64;
+	}
 }
@@ -0,0 +1,929 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Vesting Module
+//!
+//! - [`vesting::Trait`](./trait.Trait.html)
+//! - [`Call`](./enum.Call.html)
+//!
+//! ## Overview
+//!
+//! A simple module providing a means of placing a linear curve on an account's locked balance. This
+//! module ensures that there is a lock in place preventing the balance to drop below the *unvested*
+//! amount for any reason other than transaction fee payment.
+//!
+//! As the amount vested increases over time, the amount unvested reduces. However, locks remain in
+//! place and explicit action is needed on behalf of the user to ensure that the amount locked is
+//! equivalent to the amount remaining to be vested. This is done through a dispatchable function,
+//! either `vest` (in typical case where the sender is calling on their own behalf) or `vest_other`
+//! in case the sender is calling on another account's behalf.
+//!
+//! An account may have several vesting schedules at once, up to `MaxVestingSchedules`. The amount
+//! locked is the sum of what remains unvested in each of them.
+//!
+//! Schedules that were created by pallet-balances before this module existed are migrated the
+//! first time their account is touched by this module, see `vest_other`. pallet-balances keeps
+//! enforcing them until then.
+//!
+//! ## Interface
+//!
+//! This module implements the `VestingSchedule` trait.
+//!
+//! ### Dispatchable Functions
+//!
+//! - `vest` - Update the lock, reducing it in line with the amount "vested" so far.
+//! - `vest_other` - Update the lock of another account, reducing it in line with the amount
+//!   "vested" so far.
+//! - `vested_transfer` - Transfer funds to another account under a new vesting schedule.
+//!
+//! [`Call`]: ./enum.Call.html
+//! [`Trait`]: ./trait.Trait.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::{
+	DispatchResult, RuntimeDebug,
+	traits::{StaticLookup, Zero, SimpleArithmetic, Saturating, Convert, Bounded},
+};
+use frame_support::{decl_module, decl_event, decl_storage, decl_error, ensure};
+use frame_support::traits::{
+	Currency, LockableCurrency, VestingSchedule, WithdrawReason, LockIdentifier, Get,
+	ExistenceRequirement,
+};
+use frame_support::weights::SimpleDispatchInfo;
+use frame_system::{self as system, ensure_signed};
+
+mod migration;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The currency trait.
+	type Currency: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber>;
+
+	/// Convert the block number into a balance.
+	type BlockNumberToBalance: Convert<Self::BlockNumber, BalanceOf<Self>>;
+
+	/// The minimum amount to be transferred to create a new vesting schedule.
+	type MinVestedTransfer: Get<BalanceOf<Self>>;
+
+	/// The maximum number of vesting schedules an account may have at a given moment.
+	type MaxVestingSchedules: Get<u32>;
+}
+
+const VESTING_ID: LockIdentifier = *b"vesting ";
+
+/// Struct to encode the vesting schedule of an individual account.
+#[derive(Encode, Decode, Copy, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct VestingInfo<Balance, BlockNumber> {
+	/// Locked amount at genesis.
+	pub locked: Balance,
+	/// Amount that gets unlocked every block after `starting_block`.
+	pub per_block: Balance,
+	/// Starting block for unlocking(vesting).
+	pub starting_block: BlockNumber,
+}
+
+impl<
+	Balance: SimpleArithmetic + Copy,
+	BlockNumber: SimpleArithmetic + Copy,
+> VestingInfo<Balance, BlockNumber> {
+	/// Amount locked at block `n`.
+	pub fn locked_at<
+		BlockNumberToBalance: Convert<BlockNumber, Balance>
+	>(&self, n: BlockNumber) -> Balance {
+		// Number of blocks that count toward vesting
+		// Saturating to 0 when n < starting_block
+		let vested_block_count = n.saturating_sub(self.starting_block);
+		let vested_block_count = BlockNumberToBalance::convert(vested_block_count);
+		// Return amount that is still locked in vesting
+		if let Some(x) = vested_block_count.checked_mul(&self.per_block) {
+			self.locked.max(x) - x
+		} else {
+			Zero::zero()
+		}
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Vesting {
+		/// Information regarding the vesting of a given account.
+		pub Vesting get(fn vesting):
+			map hasher(blake2_128_concat) T::AccountId
+			=> Vec<VestingInfo<BalanceOf<T>, T::BlockNumber>>;
+	}
+	add_extra_genesis {
+		config(vesting): Vec<(T::AccountId, T::BlockNumber, T::BlockNumber, BalanceOf<T>)>;
+		build(|config: &GenesisConfig<T>| {
+			// Generate initial vesting configuration
+			// * who - Account which we are generating vesting configuration for
+			// * begin - Block when the account will start to vest
+			// * length - Number of blocks from `begin` until fully vested
+			// * liquid - Number of units which can be spent before vesting begins
+			//
+			// Each entry adds a separate schedule, so an account may be listed several times. The
+			// entries split the balance between them: each one locks what is left of the balance
+			// after its `liquid` amount and the amount already locked by the previous entries.
+			for &(ref who, begin, length, liquid) in config.vesting.iter() {
+				let balance = T::Currency::free_balance(who);
+				assert!(!balance.is_zero(), "Currencies must be init'd before vesting");
+
+				let mut schedules = Vesting::<T>::get(who);
+				assert!(
+					(schedules.len() as u32) < T::MaxVestingSchedules::get(),
+					"Too many vesting schedules at genesis."
+				);
+				let already_locked = schedules.iter()
+					.fold(BalanceOf::<T>::zero(), |acc, s| acc.saturating_add(s.locked));
+				// Total genesis `balance` minus `liquid` and what is already vesting equals funds
+				// locked for vesting
+				let locked = balance.saturating_sub(already_locked).saturating_sub(liquid);
+				if locked.is_zero() { continue }
+				let length_as_balance = T::BlockNumberToBalance::convert(length);
+				let per_block = locked / length_as_balance.max(sp_runtime::traits::One::one());
+
+				schedules.push(VestingInfo {
+					locked: locked,
+					per_block: per_block,
+					starting_block: begin
+				});
+				Vesting::<T>::insert(who, schedules);
+				let total_locked = already_locked.saturating_add(locked);
+				let reasons = WithdrawReason::Transfer | WithdrawReason::Reserve;
+				T::Currency::set_lock(VESTING_ID, who, total_locked, T::BlockNumber::max_value(), reasons);
+			}
+		})
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId, Balance = BalanceOf<T> {
+		/// The amount vested has been updated. This could indicate more funds are available. The
+		/// balance given is the amount which is left unvested (and thus locked).
+		VestingUpdated(AccountId, Balance),
+		/// An account (given) has become fully vested. No further vesting can happen.
+		VestingCompleted(AccountId),
+	}
+);
+
+decl_error! {
+	/// Error for the vesting module.
+	pub enum Error for Module<T: Trait> {
+		/// The account given is not vesting.
+		NotVesting,
+		/// An existing vesting schedule already exists for this account that cannot be clobbered.
+		AtMaxVestingSchedules,
+		/// Amount being transferred is too low to create a vesting schedule.
+		AmountLow,
+		/// There is no vesting schedule at the given index.
+		ScheduleIndexOutOfBounds,
+		/// The schedule would never unlock anything, since it vests nothing per block.
+		InvalidScheduleParams,
+	}
+}
+
+decl_module! {
+	// Simple declaration of the `Module` type. Used for dispatching calls.
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The minimum amount to be transferred to create a new vesting schedule.
+		const MinVestedTransfer: BalanceOf<T> = T::MinVestedTransfer::get();
+
+		/// The maximum number of vesting schedules an account may have at a given moment.
+		const MaxVestingSchedules: u32 = T::MaxVestingSchedules::get();
+
+		fn deposit_event() = default;
+
+		/// Unlock any vested funds of the sender account.
+		///
+		/// The dispatch origin for this call must be _Signed_ and the sender must have funds still
+		/// locked under this module.
+		///
+		/// Emits either `VestingCompleted` or `VestingUpdated`.
+		///
+		/// # <weight>
+		/// - `O(S)` where `S` is the number of vesting schedules of the account, bounded by
+		///   `MaxVestingSchedules`.
+		/// - One storage read (codec `O(S)`) and up to one storage write or removal.
+		/// - One lock update.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn vest(origin) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::update_lock(who)
+		}
+
+		/// Unlock any vested funds of a `target` account.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `target`: The account whose vested funds should be unlocked. Must have funds still
+		/// locked under this module.
+		///
+		/// Emits either `VestingCompleted` or `VestingUpdated`.
+		///
+		/// # <weight>
+		/// - `O(S)` where `S` is the number of vesting schedules of the account, bounded by
+		///   `MaxVestingSchedules`.
+		/// - One storage read (codec `O(S)`) and up to one storage write or removal.
+		/// - One lock update.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn vest_other(origin, target: <T::Lookup as StaticLookup>::Source) -> DispatchResult {
+			ensure_signed(origin)?;
+			Self::update_lock(T::Lookup::lookup(target)?)
+		}
+
+		/// Create a vested transfer.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `target`: The account that should be transferred the vested funds.
+		/// - `schedule`: The vesting schedule attached to the transfer. The amount transferred is
+		/// `schedule.locked`, which must be at least `MinVestedTransfer`. `schedule.per_block` must
+		/// not be zero.
+		///
+		/// Fails if `target` already has `MaxVestingSchedules` vesting schedules.
+		///
+		/// Emits `VestingUpdated` (or `VestingCompleted` if the schedule has already fully vested).
+		///
+		/// # <weight>
+		/// - `O(S)` where `S` is the number of vesting schedules of the target, bounded by
+		///   `MaxVestingSchedules`.
+		/// - One balance transfer.
+		/// - One storage read (codec `O(S)`) and one storage write.
+		/// - One lock update.
+		/// - One event.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
+		pub fn vested_transfer(
+			origin,
+			target: <T::Lookup as StaticLookup>::Source,
+			schedule: VestingInfo<BalanceOf<T>, T::BlockNumber>,
+		) -> DispatchResult {
+			let transactor = ensure_signed(origin)?;
+			ensure!(schedule.locked >= T::MinVestedTransfer::get(), Error::<T>::AmountLow);
+			ensure!(!schedule.per_block.is_zero(), Error::<T>::InvalidScheduleParams);
+
+			let who = T::Lookup::lookup(target)?;
+			migration::migrate_account::<T>(&who);
+			ensure!(
+				(Vesting::<T>::decode_len(&who).unwrap_or_default() as u32) < T::MaxVestingSchedules::get(),
+				Error::<T>::AtMaxVestingSchedules
+			);
+
+			T::Currency::transfer(&transactor, &who, schedule.locked, ExistenceRequirement::AllowDeath)?;
+
+			Self::add_vesting_schedule(&who, schedule.locked, schedule.per_block, schedule.starting_block)
+				.expect("the number of schedules has been checked above; qed");
+
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// (Re)set or remove the module's currency lock on `who`'s account in accordance with their
+	/// current unvested amount, dropping any schedule that has fully vested.
+	fn update_lock(who: T::AccountId) -> DispatchResult {
+		migration::migrate_account::<T>(&who);
+		ensure!(Vesting::<T>::exists(&who), Error::<T>::NotVesting);
+		Self::write_lock(who, Vesting::<T>::get(&who));
+		Ok(())
+	}
+
+	/// Write the lock of `who` to match the unvested amount of the given `schedules`, and store
+	/// the schedules that have not yet fully vested.
+	fn write_lock(who: T::AccountId, mut schedules: Vec<VestingInfo<BalanceOf<T>, T::BlockNumber>>) {
+		let now = <frame_system::Module<T>>::block_number();
+		let mut total_locked = BalanceOf::<T>::zero();
+		schedules.retain(|s| {
+			let locked_now = s.locked_at::<T::BlockNumberToBalance>(now);
+			total_locked = total_locked.saturating_add(locked_now);
+			!locked_now.is_zero()
+		});
+
+		if total_locked.is_zero() {
+			T::Currency::remove_lock(VESTING_ID, &who);
+			Vesting::<T>::remove(&who);
+			Self::deposit_event(RawEvent::VestingCompleted(who));
+		} else {
+			let reasons = WithdrawReason::Transfer | WithdrawReason::Reserve;
+			T::Currency::set_lock(VESTING_ID, &who, total_locked, T::BlockNumber::max_value(), reasons);
+			Vesting::<T>::insert(&who, schedules);
+			Self::deposit_event(RawEvent::VestingUpdated(who, total_locked));
+		}
+	}
+}
+
+impl<T: Trait> VestingSchedule<T::AccountId> for Module<T> {
+	type Moment = T::BlockNumber;
+	type Currency = T::Currency;
+
+	/// Get the amount that is currently being vested and cannot be transferred out of this account.
+	fn vesting_balance(who: &T::AccountId) -> Option<BalanceOf<T>> {
+		let legacy = migration::legacy_schedule::<T>(who);
+		if !Vesting::<T>::exists(who) && legacy.is_none() {
+			return None
+		}
+
+		let now = <frame_system::Module<T>>::block_number();
+		let locked = Self::vesting(who).iter().chain(legacy.iter())
+			.fold(BalanceOf::<T>::zero(), |acc, s| {
+				acc.saturating_add(s.locked_at::<T::BlockNumberToBalance>(now))
+			});
+		Some(T::Currency::free_balance(who).min(locked))
+	}
+
+	/// Adds a vesting schedule to a given account.
+	///
+	/// If the account already has `MaxVestingSchedules` vesting schedules, an `Err` is returned
+	/// and nothing is updated.
+	///
+	/// Is a no-op if the amount to be vested is zero.
+	fn add_vesting_schedule(
+		who: &T::AccountId,
+		locked: BalanceOf<T>,
+		per_block: BalanceOf<T>,
+		starting_block: T::BlockNumber
+	) -> DispatchResult {
+		if locked.is_zero() { return Ok(()) }
+
+		migration::migrate_account::<T>(who);
+		let mut schedules = Self::vesting(who);
+		ensure!(
+			(schedules.len() as u32) < T::MaxVestingSchedules::get(),
+			Error::<T>::AtMaxVestingSchedules
+		);
+		schedules.push(VestingInfo {
+			locked,
+			per_block,
+			starting_block
+		});
+
+		Self::write_lock(who.clone(), schedules);
+		Ok(())
+	}
+
+	/// Remove the vesting schedule at index `schedule_index` for a given account.
+	fn remove_vesting_schedule(who: &T::AccountId, schedule_index: u32) -> DispatchResult {
+		migration::migrate_account::<T>(who);
+		let mut schedules = Self::vesting(who);
+		let schedule_index = schedule_index as usize;
+		ensure!(schedule_index < schedules.len(), Error::<T>::ScheduleIndexOutOfBounds);
+		schedules.remove(schedule_index);
+
+		Self::write_lock(who.clone(), schedules);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::cell::RefCell;
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types, weights::Weight,
+		traits::Get,
+	};
+	use sp_core::H256;
+	use sp_runtime::{
+		Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup, ConvertInto},
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	// For testing the module, we construct most of a mock runtime. This means
+	// first constructing a configuration type (`Test`) which `impl`s each of the
+	// configuration traits of modules we want to use.
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+	}
+	parameter_types! {
+		pub const TransferFee: u64 = 0;
+		pub const CreationFee: u64 = 0;
+	}
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type OnFreeBalanceZero = ();
+		type OnReapAccount = System;
+		type OnNewAccount = ();
+		type Event = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type TransferFee = TransferFee;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const MinVestedTransfer: u64 = 256 * 2;
+		pub const MaxVestingSchedules: u32 = 3;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Currency = Balances;
+		type BlockNumberToBalance = ConvertInto;
+		type MinVestedTransfer = MinVestedTransfer;
+		type MaxVestingSchedules = MaxVestingSchedules;
+	}
+	type System = frame_system::Module<Test>;
+	type Balances = pallet_balances::Module<Test>;
+	type Vesting = Module<Test>;
+
+	use pallet_balances::Error as BalancesError;
+
+	thread_local! {
+		static EXISTENTIAL_DEPOSIT: RefCell<u64> = RefCell::new(0);
+	}
+	pub struct ExistentialDeposit;
+	impl Get<u64> for ExistentialDeposit {
+		fn get() -> u64 { EXISTENTIAL_DEPOSIT.with(|v| *v.borrow()) }
+	}
+
+	pub struct ExtBuilder {
+		existential_deposit: u64,
+	}
+	impl Default for ExtBuilder {
+		fn default() -> Self {
+			Self {
+				existential_deposit: 1,
+			}
+		}
+	}
+	impl ExtBuilder {
+		pub fn existential_deposit(mut self, existential_deposit: u64) -> Self {
+			self.existential_deposit = existential_deposit;
+			self
+		}
+		pub fn build(self) -> sp_io::TestExternalities {
+			EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = self.existential_deposit);
+			let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+			pallet_balances::GenesisConfig::<Test> {
+				balances: vec![
+					(1, 10 * self.existential_deposit),
+					(2, 20 * self.existential_deposit),
+					(3, 30 * self.existential_deposit),
+					(4, 40 * self.existential_deposit),
+					(12, 10 * self.existential_deposit)
+				],
+			}.assimilate_storage(&mut t).unwrap();
+			GenesisConfig::<Test> {
+				vesting: vec![
+					(1, 0, 10, 5 * self.existential_deposit),
+					(2, 10, 20, 0),
+					(12, 10, 20, 5 * self.existential_deposit)
+				],
+			}.assimilate_storage(&mut t).unwrap();
+			let mut ext = sp_io::TestExternalities::new(t);
+			ext.execute_with(|| System::set_block_number(1));
+			ext
+		}
+	}
+
+	#[test]
+	fn check_vesting_status() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				let user1_free_balance = Balances::free_balance(&1);
+				let user2_free_balance = Balances::free_balance(&2);
+				let user12_free_balance = Balances::free_balance(&12);
+				assert_eq!(user1_free_balance, 256 * 10); // Account 1 has free balance
+				assert_eq!(user2_free_balance, 256 * 20); // Account 2 has free balance
+				assert_eq!(user12_free_balance, 256 * 10); // Account 12 has free balance
+				let user1_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 128, // Vesting over 10 blocks
+					starting_block: 0,
+				};
+				let user2_vesting_schedule = VestingInfo {
+					locked: 256 * 20,
+					per_block: 256, // Vesting over 20 blocks
+					starting_block: 10,
+				};
+				let user12_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 64, // Vesting over 20 blocks
+					starting_block: 10,
+				};
+				assert_eq!(Vesting::vesting(&1), vec![user1_vesting_schedule]); // Account 1 has a vesting schedule
+				assert_eq!(Vesting::vesting(&2), vec![user2_vesting_schedule]); // Account 2 has a vesting schedule
+				assert_eq!(Vesting::vesting(&12), vec![user12_vesting_schedule]); // Account 12 has a vesting schedule
+
+				// Account 1 has only 128 units vested from their illiquid 256 * 5 units at block 1
+				assert_eq!(Vesting::vesting_balance(&1), Some(128 * 9));
+				// Account 2 has their full balance locked
+				assert_eq!(Vesting::vesting_balance(&2), Some(user2_free_balance));
+				// Account 12 has only their illiquid funds locked
+				assert_eq!(Vesting::vesting_balance(&12), Some(user12_free_balance - 256 * 5));
+
+				System::set_block_number(10);
+				assert_eq!(System::block_number(), 10);
+
+				// Account 1 has fully vested by block 10
+				assert_eq!(Vesting::vesting_balance(&1), Some(0));
+				// Account 2 has started vesting by block 10
+				assert_eq!(Vesting::vesting_balance(&2), Some(user2_free_balance));
+				// Account 12 has started vesting by block 10
+				assert_eq!(Vesting::vesting_balance(&12), Some(user12_free_balance - 256 * 5));
+
+				System::set_block_number(30);
+				assert_eq!(System::block_number(), 30);
+
+				assert_eq!(Vesting::vesting_balance(&1), Some(0)); // Account 1 is still fully vested, and not negative
+				assert_eq!(Vesting::vesting_balance(&2), Some(0)); // Account 2 has fully vested by block 30
+				assert_eq!(Vesting::vesting_balance(&12), Some(0)); // Account 12 has fully vested by block 30
+
+				// Once vested, the schedules and locks are removed.
+				assert_ok!(Vesting::vest(Some(1).into()));
+				assert_eq!(Vesting::vesting_balance(&1), None);
+				assert!(Balances::locks(&1).is_empty());
+				assert_noop!(Vesting::vest(Some(1).into()), Error::<Test>::NotVesting);
+			});
+	}
+
+	#[test]
+	fn unvested_balance_should_not_transfer() {
+		ExtBuilder::default()
+			.existential_deposit(10)
+			.build()
+			.execute_with(|| {
+				let user1_free_balance = Balances::free_balance(&1);
+				assert_eq!(user1_free_balance, 100); // Account 1 has free balance
+				// Account 1 has only 5 units vested at block 1 (plus 50 unvested)
+				assert_eq!(Vesting::vesting_balance(&1), Some(45));
+				assert_noop!(
+					Balances::transfer(Some(1).into(), 2, 56),
+					BalancesError::<Test, _>::LiquidityRestrictions,
+				); // Account 1 cannot send more than vested amount
+			});
+	}
+
+	#[test]
+	fn vested_balance_should_transfer() {
+		ExtBuilder::default()
+			.existential_deposit(10)
+			.build()
+			.execute_with(|| {
+				let user1_free_balance = Balances::free_balance(&1);
+				assert_eq!(user1_free_balance, 100); // Account 1 has free balance
+				// Account 1 has only 5 units vested at block 1 (plus 50 unvested)
+				assert_eq!(Vesting::vesting_balance(&1), Some(45));
+				assert_ok!(Vesting::vest(Some(1).into()));
+				assert_ok!(Balances::transfer(Some(1).into(), 2, 55));
+			});
+	}
+
+	#[test]
+	fn vested_balance_should_transfer_using_vest_other() {
+		ExtBuilder::default()
+			.existential_deposit(10)
+			.build()
+			.execute_with(|| {
+				let user1_free_balance = Balances::free_balance(&1);
+				assert_eq!(user1_free_balance, 100); // Account 1 has free balance
+				// Account 1 has only 5 units vested at block 1 (plus 50 unvested)
+				assert_eq!(Vesting::vesting_balance(&1), Some(45));
+				assert_ok!(Vesting::vest_other(Some(2).into(), 1));
+				assert_ok!(Balances::transfer(Some(1).into(), 2, 55));
+				assert_noop!(Vesting::vest_other(Some(1).into(), 3), Error::<Test>::NotVesting);
+			});
+	}
+
+	#[test]
+	fn extra_balance_should_transfer() {
+		ExtBuilder::default()
+			.existential_deposit(10)
+			.build()
+			.execute_with(|| {
+				assert_ok!(Balances::transfer(Some(3).into(), 1, 100));
+				assert_ok!(Balances::transfer(Some(3).into(), 2, 100));
+
+				let user1_free_balance = Balances::free_balance(&1);
+				assert_eq!(user1_free_balance, 200); // Account 1 has 100 more free balance than normal
+
+				let user2_free_balance = Balances::free_balance(&2);
+				assert_eq!(user2_free_balance, 300); // Account 2 has 100 more free balance than normal
+
+				// Account 1 has only 5 units vested at block 1 (plus 150 unvested)
+				assert_eq!(Vesting::vesting_balance(&1), Some(45));
+				assert_ok!(Vesting::vest(Some(1).into()));
+				assert_ok!(Balances::transfer(Some(1).into(), 3, 155)); // Account 1 can send extra units gained
+
+				// Account 2 has no units vested at block 1, but gained 100
+				assert_eq!(Vesting::vesting_balance(&2), Some(200));
+				assert_ok!(Vesting::vest(Some(2).into()));
+				assert_ok!(Balances::transfer(Some(2).into(), 3, 100)); // Account 2 can send extra units gained
+			});
+	}
+
+	#[test]
+	fn liquid_funds_should_transfer_with_delayed_vesting() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				let user12_free_balance = Balances::free_balance(&12);
+
+				assert_eq!(user12_free_balance, 2560); // Account 12 has free balance
+				// Account 12 has liquid funds
+				assert_eq!(Vesting::vesting_balance(&12), Some(user12_free_balance - 256 * 5));
+
+				// Account 12 has delayed vesting
+				let user12_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 64, // Vesting over 20 blocks
+					starting_block: 10,
+				};
+				assert_eq!(Vesting::vesting(&12), vec![user12_vesting_schedule]);
+
+				// Account 12 can still send liquid funds
+				assert_ok!(Balances::transfer(Some(12).into(), 3, 256 * 5));
+			});
+	}
+
+	#[test]
+	fn vested_transfer_works() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				let user3_free_balance = Balances::free_balance(&3);
+				let user4_free_balance = Balances::free_balance(&4);
+				assert_eq!(user3_free_balance, 256 * 30);
+				assert_eq!(user4_free_balance, 256 * 40);
+				// Account 4 should not have any vesting yet.
+				assert_eq!(Vesting::vesting(&4), vec![]);
+				// Make the schedule for the new transfer.
+				let new_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 64, // Vesting over 20 blocks
+					starting_block: 10,
+				};
+				assert_ok!(Vesting::vested_transfer(Some(3).into(), 4, new_vesting_schedule));
+				// Now account 4 should have vesting.
+				assert_eq!(Vesting::vesting(&4), vec![new_vesting_schedule]);
+				// Ensure the transfer happened correctly.
+				let user3_free_balance_updated = Balances::free_balance(&3);
+				assert_eq!(user3_free_balance_updated, 256 * 25);
+				let user4_free_balance_updated = Balances::free_balance(&4);
+				assert_eq!(user4_free_balance_updated, 256 * 45);
+				// Account 4 has 5 * 256 locked.
+				assert_eq!(Vesting::vesting_balance(&4), Some(256 * 5));
+
+				System::set_block_number(20);
+				assert_eq!(System::block_number(), 20);
+
+				// Account 4 has only 10 * 64 units left locked by block 20.
+				assert_eq!(Vesting::vesting_balance(&4), Some(10 * 64));
+
+				System::set_block_number(30);
+				assert_eq!(System::block_number(), 30);
+
+				// Account 4 has fully vested.
+				assert_eq!(Vesting::vesting_balance(&4), Some(0));
+				assert_ok!(Vesting::vest(Some(4).into()));
+				assert_eq!(Vesting::vesting_balance(&4), None);
+			});
+	}
+
+	#[test]
+	fn vested_transfer_correctly_fails() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				let new_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 64, // Vesting over 20 blocks
+					starting_block: 10,
+				};
+				// Fails due to too low transfer amount.
+				let new_vesting_schedule_too_low = VestingInfo {
+					locked: 256 * 1,
+					per_block: 64,
+					starting_block: 10,
+				};
+				assert_noop!(
+					Vesting::vested_transfer(Some(3).into(), 4, new_vesting_schedule_too_low),
+					Error::<Test>::AmountLow,
+				);
+
+				// Fails due to a schedule that never vests.
+				assert_noop!(
+					Vesting::vested_transfer(Some(3).into(), 4, VestingInfo { per_block: 0, .. new_vesting_schedule }),
+					Error::<Test>::InvalidScheduleParams,
+				);
+
+				// Fails due to insufficient balance.
+				assert_noop!(
+					Vesting::vested_transfer(Some(4).into(), 3, VestingInfo { locked: 256 * 50, .. new_vesting_schedule }),
+					BalancesError::<Test, _>::InsufficientBalance,
+				);
+			});
+	}
+
+	#[test]
+	fn multiple_schedules_stack_up_to_the_limit() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				let user2_free_balance = Balances::free_balance(&2);
+				assert_eq!(user2_free_balance, 256 * 20);
+				assert_eq!(Vesting::vesting_balance(&2), Some(256 * 20));
+
+				let slow_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 64, // Vesting over 20 blocks
+					starting_block: 10,
+				};
+				let fast_vesting_schedule = VestingInfo {
+					locked: 256 * 5,
+					per_block: 128, // Vesting over 10 blocks
+					starting_block: 1,
+				};
+				assert_ok!(Vesting::vested_transfer(Some(3).into(), 2, slow_vesting_schedule));
+				assert_ok!(Vesting::vested_transfer(Some(3).into(), 2, fast_vesting_schedule));
+				assert_eq!(Vesting::vesting(&2).len(), 3);
+				// The locked amounts of all schedules add up.
+				assert_eq!(Vesting::vesting_balance(&2), Some(256 * 30));
+
+				// No more than `MaxVestingSchedules` schedules may be in place.
+				assert_noop!(
+					Vesting::vested_transfer(Some(3).into(), 2, slow_vesting_schedule),
+					Error::<Test>::AtMaxVestingSchedules,
+				);
+
+				System::set_block_number(11);
+				// One block of the genesis and slow schedules has vested, the fast one is done.
+				assert_eq!(Vesting::vesting_balance(&2), Some(256 * 25 - 256 - 64));
+				assert_ok!(Vesting::vest(Some(2).into()));
+				assert_eq!(Vesting::vesting(&2).len(), 2);
+				assert_ok!(Balances::transfer(Some(2).into(), 3, 256 * 5 + 256 + 64));
+				assert_noop!(
+					Balances::transfer(Some(2).into(), 3, 1),
+					BalancesError::<Test, _>::LiquidityRestrictions,
+				);
+
+				// The fully vested schedule made room for another one.
+				assert_ok!(Vesting::vested_transfer(Some(3).into(), 2, slow_vesting_schedule));
+				assert_eq!(Vesting::vesting(&2).len(), 3);
+			});
+	}
+
+	#[test]
+	fn remove_vesting_schedule_works() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				assert_noop!(
+					Vesting::remove_vesting_schedule(&2, 1),
+					Error::<Test>::ScheduleIndexOutOfBounds,
+				);
+				assert_ok!(Vesting::remove_vesting_schedule(&2, 0));
+				assert_eq!(Vesting::vesting_balance(&2), None);
+				assert!(Balances::locks(&2).is_empty());
+				assert_ok!(Balances::transfer(Some(2).into(), 3, 256 * 20));
+			});
+	}
+
+	fn genesis_with_vesting(vesting: Vec<(u64, u64, u64, u64)>) -> sp_io::TestExternalities {
+		EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = 256);
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: vec![(1, 256 * 10), (2, 256 * 20)],
+		}.assimilate_storage(&mut t).unwrap();
+		GenesisConfig::<Test> { vesting }.assimilate_storage(&mut t).unwrap();
+		let mut ext = sp_io::TestExternalities::new(t);
+		ext.execute_with(|| System::set_block_number(1));
+		ext
+	}
+
+	#[test]
+	fn multiple_genesis_schedules_split_the_balance() {
+		genesis_with_vesting(vec![
+			(1, 0, 10, 256 * 6),
+			(1, 10, 20, 256 * 2),
+		]).execute_with(|| {
+			// The first entry locks everything but its liquid amount, the second one locks what
+			// is left of the balance after that, but for its own liquid amount.
+			assert_eq!(Vesting::vesting(&1), vec![
+				VestingInfo { locked: 256 * 4, per_block: 256 * 4 / 10, starting_block: 0 },
+				VestingInfo { locked: 256 * 4, per_block: 256 * 4 / 20, starting_block: 10 },
+			]);
+			assert_eq!(Balances::locks(&1)[0].amount, 256 * 8);
+			assert_eq!(Vesting::vesting_balance(&1), Some(256 * 8 - 256 * 4 / 10));
+
+			// Nothing more than the balance is ever locked.
+			assert_ok!(Balances::transfer(Some(1).into(), 2, 256 * 2));
+			assert_noop!(
+				Balances::transfer(Some(1).into(), 2, 1),
+				BalancesError::<Test, _>::LiquidityRestrictions,
+			);
+
+			System::set_block_number(40);
+			assert_ok!(Vesting::vest(Some(1).into()));
+			assert_eq!(Vesting::vesting_balance(&1), None);
+			assert_ok!(Balances::transfer(Some(1).into(), 2, 256 * 7));
+		});
+	}
+
+	#[test]
+	#[should_panic(expected = "Too many vesting schedules at genesis.")]
+	fn too_many_genesis_schedules_are_rejected() {
+		genesis_with_vesting(vec![
+			(1, 0, 10, 256 * 9),
+			(1, 0, 10, 256 * 8),
+			(1, 0, 10, 256 * 7),
+			(1, 0, 10, 256 * 6),
+		]);
+	}
+
+	#[test]
+	fn legacy_balances_schedules_are_migrated() {
+		ExtBuilder::default()
+			.existential_deposit(256)
+			.build()
+			.execute_with(|| {
+				let schedule = VestingInfo {
+					locked: 256 * 10,
+					per_block: 256, // Vesting over 10 blocks
+					starting_block: 1,
+				};
+				let mut legacy_key = sp_io::hashing::twox_128(b"Balances").to_vec();
+				legacy_key.extend_from_slice(&sp_io::hashing::twox_128(b"Vesting"));
+				legacy_key.extend_from_slice(&4u64.using_encoded(sp_io::hashing::blake2_256));
+				frame_support::storage::unhashed::put(&legacy_key, &schedule);
+
+				// The legacy schedule counts and is enforced by balances before it is migrated.
+				assert_eq!(Vesting::vesting_balance(&4), Some(256 * 10));
+				assert!(Balances::locks(&4).is_empty());
+				assert_noop!(
+					Balances::transfer(Some(4).into(), 3, 256 * 30 + 1),
+					BalancesError::<Test, _>::VestingBalance,
+				);
+				assert_ok!(Balances::transfer(Some(4).into(), 3, 256 * 30));
+
+				System::set_block_number(3);
+				assert_ok!(Vesting::vest_other(Some(1).into(), 4));
+				assert!(frame_support::storage::unhashed::get_raw(&legacy_key).is_none());
+				assert_eq!(Vesting::vesting(&4), vec![schedule]);
+				assert_eq!(Vesting::vesting_balance(&4), Some(256 * 8));
+				assert_eq!(Balances::locks(&4)[0].amount, 256 * 8);
+				assert_noop!(
+					Balances::transfer(Some(4).into(), 3, 256 * 2 + 1),
+					BalancesError::<Test, _>::LiquidityRestrictions,
+				);
+			});
+	}
+}
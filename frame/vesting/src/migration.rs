@@ -0,0 +1,57 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage migration from the vesting schedules of pallet-balances.
+//!
+//! Before this module existed, pallet-balances kept a single schedule per account in its
+//! `Balances Vesting` map and checked it on every withdrawal. That map is keyed by the
+//! `blake2_256` hash of the account, so the accounts cannot be enumerated and a schedule is
+//! migrated the first time its account is touched by this module instead. Until then,
+//! pallet-balances keeps enforcing the legacy schedule, so the funds stay locked across the
+//! upgrade. Anyone can force the migration with `vest_other`.
+
+use sp_std::prelude::*;
+use codec::Encode;
+use frame_support::storage::unhashed;
+use crate::{Trait, Module, Vesting, VestingInfo, BalanceOf};
+
+/// The final key of `who` in the legacy `Balances Vesting` map.
+fn legacy_key<T: Trait>(who: &T::AccountId) -> Vec<u8> {
+	let mut key = sp_io::hashing::twox_128(b"Balances").to_vec();
+	key.extend_from_slice(&sp_io::hashing::twox_128(b"Vesting"));
+	key.extend_from_slice(&who.using_encoded(sp_io::hashing::blake2_256));
+	key
+}
+
+/// The legacy schedule of `who`, if it has not been migrated yet. The layout of the old
+/// `VestingSchedule` of pallet-balances is the same as `VestingInfo`.
+pub(crate) fn legacy_schedule<T: Trait>(
+	who: &T::AccountId,
+) -> Option<VestingInfo<BalanceOf<T>, T::BlockNumber>> {
+	unhashed::get(&legacy_key::<T>(who))
+}
+
+/// Move the legacy schedule of `who`, if any, into this module and lock the amount it has not
+/// vested yet.
+pub(crate) fn migrate_account<T: Trait>(who: &T::AccountId) {
+	if let Some(schedule) = unhashed::take(&legacy_key::<T>(who)) {
+		let mut schedules = Vesting::<T>::get(who);
+		// The legacy schedule is migrated even if it goes over `MaxVestingSchedules`, since
+		// dropping it would unlock the funds.
+		schedules.push(schedule);
+		<Module<T>>::write_lock(who.clone(), schedules);
+	}
+}
@@ -29,7 +29,7 @@ use quote::quote;
 use syn::{
 	spanned::Spanned, parse_macro_input, Ident, Type, ItemImpl, Path, Signature,
 	ImplItem, parse::{Parse, ParseStream, Result, Error}, PathArguments, GenericArgument, TypePath,
-	fold::{self, Fold}, parse_quote,
+	fold::{self, Fold}, parse_quote, Attribute,
 };
 
 use std::{collections::HashSet, iter};
@@ -137,15 +137,23 @@ fn extract_runtime_block_ident(trait_: &Path) -> Result<&TypePath> {
 	}
 }
 
+/// Extracts the `cfg` attributes of the given runtime api implementation.
+///
+/// These attributes need to be forwarded to everything that is generated for the implementation.
+fn extract_cfg_attrs(attrs: &[Attribute]) -> Vec<Attribute> {
+	attrs.iter().filter(|a| a.path.is_ident("cfg")).cloned().collect()
+}
+
 /// Generate all the implementation calls for the given functions.
 fn generate_impl_calls(
 	impls: &[ItemImpl],
 	input: &Ident
-) -> Result<Vec<(Ident, Ident, TokenStream)>> {
+) -> Result<Vec<(Ident, Ident, TokenStream, Vec<Attribute>)>> {
 	let mut impl_calls = Vec::new();
 
 	for impl_ in impls {
 		let impl_trait_path = extract_impl_trait(impl_)?;
+		let attrs = extract_cfg_attrs(&impl_.attrs);
 		let impl_trait = extend_with_runtime_decl_path(impl_trait_path.clone());
 		let impl_trait_ident = &impl_trait_path
 			.segments
@@ -163,7 +171,7 @@ fn generate_impl_calls(
 				)?;
 
 				impl_calls.push(
-					(impl_trait_ident.clone(), method.sig.ident.clone(), impl_call)
+					(impl_trait_ident.clone(), method.sig.ident.clone(), impl_call, attrs.clone())
 				);
 			}
 		}
@@ -178,9 +186,12 @@ fn generate_dispatch_function(impls: &[ItemImpl]) -> Result<TokenStream> {
 	let c = generate_crate_access(HIDDEN_INCLUDES_ID);
 	let impl_calls = generate_impl_calls(impls, &data)?
 		.into_iter()
-		.map(|(trait_, fn_name, impl_)| {
+		.map(|(trait_, fn_name, impl_, attrs)| {
 			let name = prefix_function_with_trait(&trait_, &fn_name);
-			quote!( #name => Some(#c::Encode::encode(&{ #impl_ })), )
+			quote!(
+				#( #attrs )*
+				#name => Some(#c::Encode::encode(&{ #impl_ })),
+			)
 		});
 
 	Ok(quote!(
@@ -200,13 +211,14 @@ fn generate_wasm_interface(impls: &[ItemImpl]) -> Result<TokenStream> {
 	let c = generate_crate_access(HIDDEN_INCLUDES_ID);
 	let impl_calls = generate_impl_calls(impls, &input)?
 		.into_iter()
-		.map(|(trait_, fn_name, impl_)| {
+		.map(|(trait_, fn_name, impl_, attrs)| {
 			let fn_name = Ident::new(
 				&prefix_function_with_trait(&trait_, &fn_name),
 				Span::call_site()
 			);
 
 			quote!(
+				#( #attrs )*
 				#[cfg(not(feature = "std"))]
 				#[no_mangle]
 				pub fn #fn_name(input_data: *mut u8, input_len: usize) -> u64 {
@@ -695,8 +707,12 @@ fn generate_runtime_api_versions(impls: &[ItemImpl]) -> Result<TokenStream> {
 
 		let id: Path = parse_quote!( #path ID );
 		let version: Path = parse_quote!( #path VERSION );
+		let attrs = extract_cfg_attrs(&impl_.attrs);
 
-		result.push(quote!( (#id, #version) ));
+		result.push(quote!(
+			#( #attrs )*
+			(#id, #version)
+		));
 	}
 
 	let c = generate_crate_access(HIDDEN_INCLUDES_ID);
@@ -157,6 +157,10 @@ pub use sp_api_proc_macro::decl_runtime_apis;
 /// `RUNTIME_API_VERSIONS` is generated. This constant should be used to instantiate the `apis`
 /// field of `RuntimeVersion`.
 ///
+/// `cfg` attributes on an api implementation are respected, so an implementation can be put
+/// behind a feature. If the implementation is disabled, the api is also not part of
+/// `RUNTIME_API_VERSIONS`.
+///
 /// # Example
 ///
 /// ```rust
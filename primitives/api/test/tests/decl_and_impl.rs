@@ -43,6 +43,10 @@ decl_runtime_apis! {
 		#[changed_in(2)]
 		fn same_name() -> String;
 	}
+
+	pub trait ApiWithCfgImpl {
+		fn disabled();
+	}
 }
 
 impl_runtime_apis! {
@@ -68,6 +72,11 @@ impl_runtime_apis! {
 		fn same_name() {}
 	}
 
+	#[cfg(feature = "never-enabled")]
+	impl self::ApiWithCfgImpl<Block> for Runtime {
+		fn disabled() {}
+	}
+
 	impl sp_api::Core<Block> for Runtime {
 		fn version() -> sp_version::RuntimeVersion {
 			unimplemented!()
@@ -129,3 +138,10 @@ fn check_runtime_api_versions() {
 	check_runtime_api_versions_contains::<dyn ApiWithCustomVersion<Block, Error = ()>>();
 	check_runtime_api_versions_contains::<dyn sp_api::Core<Block, Error = ()>>();
 }
+
+#[test]
+fn cfg_attributes_are_forwarded_to_runtime_api_versions() {
+	assert!(
+		!RUNTIME_API_VERSIONS.iter().any(|v| v.0 == <dyn ApiWithCfgImpl<Block, Error = ()>>::ID)
+	);
+}
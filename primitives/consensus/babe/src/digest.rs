@@ -124,6 +124,14 @@ pub enum RawBabePreDigest {
 }
 
 impl RawBabePreDigest {
+	/// Returns the authority index of the pre digest.
+	pub fn authority_index(&self) -> AuthorityIndex {
+		match self {
+			RawBabePreDigest::Primary { authority_index, .. } => *authority_index,
+			RawBabePreDigest::Secondary { authority_index, .. } => *authority_index,
+		}
+	}
+
 	/// Returns the slot number of the pre digest.
 	pub fn slot_number(&self) -> SlotNumber {
 		match self {
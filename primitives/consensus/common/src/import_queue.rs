@@ -257,6 +257,7 @@ pub fn import_single_block<B: BlockT, V: Verifier<B>, Transaction>(
 		cache.extend(keys.into_iter());
 	}
 	import_block.allow_missing_state = block.allow_missing_state;
+	import_block.import_existing = block.import_existing;
 
 	import_error(import_handle.import_block(import_block.convert_transaction(), cache))
 }
@@ -175,6 +175,12 @@ pub trait Externalities: ExtensionStore {
 		value: Option<Vec<u8>>,
 	);
 
+	/// Set or clear an entry of the offchain storage (effective once the block is imported).
+	///
+	/// Unlike the storage, the offchain storage is not part of the state. The entries are only
+	/// readable by offchain workers, through the persistent offchain storage.
+	fn set_offchain_storage(&mut self, key: &[u8], value: Option<&[u8]>);
+
 	/// Get the identity of the chain.
 	fn chain_id(&self) -> u64;
 
@@ -202,6 +208,40 @@ pub trait Externalities: ExtensionStore {
 	///
 	/// Returns the SCALE encoded hash.
 	fn storage_changes_root(&mut self, parent: &[u8]) -> Result<Option<Vec<u8>>, ()>;
+
+	/// Wipes all changes from caches and the database.
+	///
+	/// The state will be reset to genesis.
+	/// Benchmarking related functionality and shouldn't be used anywhere else!
+	/// Returns an error if the backend doesn't support wiping.
+	fn wipe(&mut self) -> Result<(), ()>;
+
+	/// Commits all changes to the database and clears all caches.
+	///
+	/// Benchmarking related functionality and shouldn't be used anywhere else!
+	/// Returns an error if the backend doesn't support committing.
+	fn commit(&mut self) -> Result<(), ()>;
+
+	/// Start a new nested storage transaction.
+	///
+	/// All changes to the storage made after this call are part of the transaction. They can be
+	/// discarded with [`storage_rollback_transaction`] or kept with [`storage_commit_transaction`].
+	/// Transactions can be nested.
+	///
+	/// [`storage_rollback_transaction`]: #tymethod.storage_rollback_transaction
+	/// [`storage_commit_transaction`]: #tymethod.storage_commit_transaction
+	fn storage_start_transaction(&mut self);
+
+	/// Discard all changes made since the innermost open transaction was started and close it.
+	///
+	/// Returns `Err(())` if there is no open transaction.
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()>;
+
+	/// Keep all changes made since the innermost open transaction was started and close it.
+	///
+	/// The changes become part of the enclosing transaction, if any. Returns `Err(())` if there is
+	/// no open transaction.
+	fn storage_commit_transaction(&mut self) -> Result<(), ()>;
 }
 
 /// Extension for the [`Externalities`] trait.
@@ -24,7 +24,7 @@ extern crate alloc;
 #[cfg(feature = "std")]
 use serde::Serialize;
 use codec::{Encode, Decode, Input, Codec};
-use sp_runtime::{ConsensusEngineId, RuntimeDebug};
+use sp_runtime::{ConsensusEngineId, RuntimeDebug, traits::NumberFor};
 use sp_std::borrow::Cow;
 use sp_std::vec::Vec;
 
@@ -33,6 +33,10 @@ mod app {
 	app_crypto!(ed25519, GRANDPA);
 }
 
+/// The key type used by GRANDPA authorities, e.g. when proving ownership of a
+/// GRANDPA key in the session.
+pub const KEY_TYPE: app_crypto::KeyTypeId = app_crypto::key_types::GRANDPA;
+
 /// The grandpa crypto scheme defined via the keypair type.
 #[cfg(feature = "std")]
 pub type AuthorityPair = app::Pair;
@@ -156,6 +160,150 @@ impl<N: Codec> ConsensusLog<N> {
 	}
 }
 
+/// A vote cast by a GRANDPA authority for a given target block.
+///
+/// This has the same encoding as both the `Prevote` and `Precommit` messages
+/// of the GRANDPA protocol.
+#[derive(Clone, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub struct Vote<H, N> {
+	/// The target block's hash.
+	pub target_hash: H,
+	/// The target block's number.
+	pub target_number: N,
+}
+
+/// An equivocation (double-vote) in a given round of a GRANDPA voter set.
+#[derive(Clone, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub struct VoteEquivocation<H, N> {
+	/// The round number in which the equivocation happened.
+	pub round_number: RoundNumber,
+	/// The authority that equivocated.
+	pub identity: AuthorityId,
+	/// The first vote in the equivocation and its signature.
+	pub first: (Vote<H, N>, AuthoritySignature),
+	/// The second vote in the equivocation and its signature.
+	pub second: (Vote<H, N>, AuthoritySignature),
+}
+
+/// Wrapper object for GRANDPA equivocation, either in the prevote or the
+/// precommit phase of a round.
+#[derive(Clone, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub enum Equivocation<H, N> {
+	/// Proof of equivocation at prevote stage.
+	Prevote(VoteEquivocation<H, N>),
+	/// Proof of equivocation at precommit stage.
+	Precommit(VoteEquivocation<H, N>),
+}
+
+impl<H, N> Equivocation<H, N> {
+	/// Returns the authority id of the equivocator.
+	pub fn offender(&self) -> &AuthorityId {
+		match self {
+			Equivocation::Prevote(ref equivocation) => &equivocation.identity,
+			Equivocation::Precommit(ref equivocation) => &equivocation.identity,
+		}
+	}
+
+	/// Returns the round number at which the equivocation occurred.
+	pub fn round_number(&self) -> RoundNumber {
+		match self {
+			Equivocation::Prevote(ref equivocation) => equivocation.round_number,
+			Equivocation::Precommit(ref equivocation) => equivocation.round_number,
+		}
+	}
+}
+
+/// Proof of voter misbehavior on a given set id. Misbehavior/equivocation in
+/// GRANDPA happens when a voter votes on the same round (either at prevote or
+/// precommit stage) for different blocks. Proving is achieved by collecting the
+/// signed messages of conflicting votes.
+#[derive(Clone, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub struct EquivocationProof<H, N> {
+	/// The set id of the voter set in which the equivocation happened.
+	pub set_id: SetId,
+	/// The equivocation itself.
+	pub equivocation: Equivocation<H, N>,
+}
+
+impl<H, N> EquivocationProof<H, N> {
+	/// Create a new `EquivocationProof` for the given set id and using the
+	/// given equivocation as proof.
+	pub fn new(set_id: SetId, equivocation: Equivocation<H, N>) -> Self {
+		EquivocationProof { set_id, equivocation }
+	}
+
+	/// Returns the authority id of the equivocator.
+	pub fn offender(&self) -> &AuthorityId {
+		self.equivocation.offender()
+	}
+
+	/// Returns the round number at which the equivocation occurred.
+	pub fn round(&self) -> RoundNumber {
+		self.equivocation.round_number()
+	}
+}
+
+/// Verifies the equivocation proof by making sure that both votes target
+/// different blocks and that their signatures are valid.
+pub fn check_equivocation_proof<H, N>(report: &EquivocationProof<H, N>) -> bool where
+	H: Encode + PartialEq,
+	N: Encode + PartialEq,
+{
+	// NOTE: the index of the message kind must match the one used by the
+	// `Message` enum of the GRANDPA protocol (0 for prevotes, 1 for precommits).
+	let (kind, equivocation) = match report.equivocation {
+		Equivocation::Prevote(ref equivocation) => (0u8, equivocation),
+		Equivocation::Precommit(ref equivocation) => (1u8, equivocation),
+	};
+
+	// if both votes have the same target the equivocation is invalid.
+	if equivocation.first.0 == equivocation.second.0 {
+		return false;
+	}
+
+	let valid_signature = |(vote, signature): &(Vote<H, N>, AuthoritySignature)| {
+		let payload = localized_payload(kind, vote, equivocation.round_number, report.set_id);
+		app_crypto::RuntimeAppPublic::verify(&equivocation.identity, &payload, signature)
+	};
+
+	// check signatures on both votes are valid.
+	valid_signature(&equivocation.first) && valid_signature(&equivocation.second)
+}
+
+/// Encode a GRANDPA vote of the given message kind, round and set id, i.e. the
+/// payload that is signed by the voter.
+fn localized_payload<H: Encode, N: Encode>(
+	kind: u8,
+	vote: &Vote<H, N>,
+	round: RoundNumber,
+	set_id: SetId,
+) -> Vec<u8> {
+	(kind, vote, round, set_id).encode()
+}
+
+/// An opaque type used to represent the key ownership proof at the runtime API
+/// boundary. The inner value is an encoded representation of the actual key
+/// ownership proof which will be parameterized when defining the runtime. At
+/// the runtime API boundary this type is unknown and as such we keep this
+/// opaque representation, implementors of the runtime API will have to make
+/// sure that all usages of `OpaqueKeyOwnershipProof` refer to the same type.
+#[derive(Decode, Encode, PartialEq, RuntimeDebug)]
+pub struct OpaqueKeyOwnershipProof(Vec<u8>);
+
+impl OpaqueKeyOwnershipProof {
+	/// Create a new `OpaqueKeyOwnershipProof` using the given encoded
+	/// representation.
+	pub fn new(inner: Vec<u8>) -> OpaqueKeyOwnershipProof {
+		OpaqueKeyOwnershipProof(inner)
+	}
+
+	/// Try to decode this `OpaqueKeyOwnershipProof` into the given concrete key
+	/// ownership proof type.
+	pub fn decode<T: Decode>(self) -> Option<T> {
+		Decode::decode(&mut &self.0[..]).ok()
+	}
+}
+
 /// WASM function call to check for pending changes.
 pub const PENDING_CHANGE_CALL: &str = "grandpa_pending_change";
 /// WASM function call to get current GRANDPA authorities.
@@ -220,7 +368,7 @@ sp_api::decl_runtime_apis! {
 	/// applied in the runtime after those N blocks have passed.
 	///
 	/// The consensus protocol will coordinate the handoff externally.
-	#[api_version(2)]
+	#[api_version(3)]
 	pub trait GrandpaApi {
 		/// Get the current GRANDPA authorities and weights. This should not change except
 		/// for when changes are scheduled and the corresponding delay has passed.
@@ -229,5 +377,34 @@ sp_api::decl_runtime_apis! {
 		/// used to finalize descendants of this block (B+1, B+2, ...). The block B itself
 		/// is finalized by the authorities from block B-1.
 		fn grandpa_authorities() -> AuthorityList;
+
+		/// Submits an unsigned extrinsic to report an equivocation. The caller
+		/// must provide the equivocation proof and a key ownership proof
+		/// (should be obtained using `generate_key_ownership_proof`). The
+		/// extrinsic will be unsigned and should only be accepted for local
+		/// authorship (not to be broadcast to the network). This method returns
+		/// `None` when creation of the extrinsic fails, e.g. if equivocation
+		/// reporting is disabled for the given runtime (i.e. this method is
+		/// hardcoded to return `None`). Only useful in an offchain context.
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: EquivocationProof<Block::Hash, NumberFor<Block>>,
+			key_owner_proof: OpaqueKeyOwnershipProof,
+		) -> Option<()>;
+
+		/// Generates a proof of key ownership for the given authority in the
+		/// given set. An example usage of this module is coupled with the
+		/// session historical module to prove that a given authority key is
+		/// tied to a given staking identity during a specific session. Proofs
+		/// of key ownership are necessary for submitting equivocation reports.
+		/// NOTE: even though the API takes a `set_id` as parameter the current
+		/// implementations ignore this parameter and instead rely on this
+		/// method being called at the correct block height, i.e. any point at
+		/// which the given set id is live on-chain. Future implementations will
+		/// instead use indexed data through an offchain worker, not requiring
+		/// older states to be available.
+		fn generate_key_ownership_proof(
+			set_id: SetId,
+			authority_id: AuthorityId,
+		) -> Option<OpaqueKeyOwnershipProof>;
 	}
 }
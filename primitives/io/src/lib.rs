@@ -311,6 +311,40 @@ pub trait Storage {
 			.expect("Invalid child definition");
 		self.next_child_storage_key(storage_key, child_info, key)
 	}
+
+	/// Start a new nested transaction.
+	///
+	/// This allows to either commit or roll back all changes made after this call. Transactions
+	/// can be nested, every started transaction must be closed with `rollback_transaction` or
+	/// `commit_transaction`.
+	fn start_transaction(&mut self) {
+		self.storage_start_transaction();
+	}
+
+	/// Roll back the innermost open transaction.
+	///
+	/// All changes made since the transaction was started are discarded.
+	///
+	/// # Panics
+	///
+	/// Panics if there is no open transaction.
+	fn rollback_transaction(&mut self) {
+		self.storage_rollback_transaction()
+			.expect("No open transaction that can be rolled back.");
+	}
+
+	/// Commit the innermost open transaction.
+	///
+	/// The changes made since the transaction was started become part of the enclosing
+	/// transaction, if any.
+	///
+	/// # Panics
+	///
+	/// Panics if there is no open transaction.
+	fn commit_transaction(&mut self) {
+		self.storage_commit_transaction()
+			.expect("No open transaction that can be committed.");
+	}
 }
 
 /// Interface that provides trie related functionality.
@@ -737,6 +771,23 @@ pub trait Offchain {
 	}
 }
 
+/// Interface that provides functions to index data for offchain workers during block execution.
+///
+/// The data is written to the persistent offchain storage when the block is imported, and is not
+/// part of the state. Data written by blocks on abandoned forks is not removed.
+#[runtime_interface]
+pub trait OffchainIndex {
+	/// Set `key` to `value` in the offchain storage.
+	fn set(&mut self, key: &[u8], value: &[u8]) {
+		self.set_offchain_storage(key, Some(value));
+	}
+
+	/// Clear the value of `key` in the offchain storage.
+	fn clear(&mut self, key: &[u8]) {
+		self.set_offchain_storage(key, None);
+	}
+}
+
 /// Wasm only interface that provides functions for calling into the allocator.
 #[runtime_interface(wasm_only)]
 trait Allocator {
@@ -772,6 +823,34 @@ pub trait Logging {
 	}
 }
 
+/// Interface that provides functions for entering and exiting tracing spans from within the
+/// runtime.
+///
+/// The spans are forwarded to the tracing subscriber of the node. Instead of using directly,
+/// prefer using the `frame_support::enter_span!` macro.
+#[runtime_interface]
+pub trait WasmTracing {
+	/// Enter a new span with the given `target` and `name`.
+	///
+	/// Returns the id of the span that should be passed to `exit_span`, or `0` if tracing is
+	/// disabled on the host.
+	fn enter_span(target: &str, name: &str) -> u64 {
+		sp_tracing::enter_span(target, name)
+	}
+
+	/// Record a field of the span with the given `id`.
+	fn record(id: u64, key: &str, value: &[u8]) {
+		if sp_tracing::wasm_tracing_enabled() {
+			sp_tracing::record(id, key, &String::from_utf8_lossy(value))
+		}
+	}
+
+	/// Exit the span with the given `id` and all spans entered after it.
+	fn exit_span(id: u64) {
+		sp_tracing::exit_span(id)
+	}
+}
+
 /// Wasm-only interface that provides functions for interacting with the sandbox.
 #[runtime_interface(wasm_only)]
 pub trait Sandbox {
@@ -910,10 +989,12 @@ pub type SubstrateHostFunctions = (
 	storage::HostFunctions,
 	misc::HostFunctions,
 	offchain::HostFunctions,
+	offchain_index::HostFunctions,
 	crypto::HostFunctions,
 	hashing::HostFunctions,
 	allocator::HostFunctions,
 	logging::HostFunctions,
+	wasm_tracing::HostFunctions,
 	sandbox::HostFunctions,
 	crate::trie::HostFunctions,
 );
@@ -145,7 +145,7 @@ fn do_phragmen(
 			let tolerance = 0_u128;
 			let iterations = 2_usize;
 			phragmen::equalize::<_, _, TestCurrencyToVote, _>(
-				staked_assignments,
+				&mut staked_assignments,
 				&mut supports,
 				tolerance,
 				iterations,
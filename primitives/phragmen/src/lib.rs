@@ -33,7 +33,8 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use sp_std::{prelude::*, collections::btree_map::BTreeMap};
+use sp_std::{prelude::*, collections::btree_map::BTreeMap, cmp::Ordering};
+use codec::{Encode, Decode};
 use sp_runtime::RuntimeDebug;
 use sp_runtime::{helpers_128bit::multiply_by_rational, Perbill, Rational128};
 use sp_runtime::traits::{Zero, Convert, Member, SimpleArithmetic, Saturating, Bounded};
@@ -129,6 +130,121 @@ pub struct Support<AccountId> {
 /// A linkage from a candidate and its [`Support`].
 pub type SupportMap<A> = BTreeMap<A, Support<A>>;
 
+/// The score of an election result: `[min_support, sum_support, sum_support_squared]`.
+///
+/// A higher minimum support, then a higher total support and finally a lower sum of squared
+/// supports (i.e. a more even distribution) makes for a better result. See [`is_score_better`].
+pub type PhragmenScore = [ExtendedBalance; 3];
+
+/// A compact representation of a set of phragmen assignments.
+///
+/// Voters and targets are referred to by their index `V` and `T` in some list known to both the
+/// producer and the consumer of this type, and the ratio of the last target of each voter is not
+/// stored since it is implied by the ratios of the others.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct CompactAssignments<V, T> {
+	/// Voters with a single target, backed by their entire stake.
+	pub votes1: Vec<(V, T)>,
+	/// Voters with two targets. The ratio of the first one is given and the second one gets the
+	/// rest.
+	pub votes2: Vec<(V, (T, Perbill), T)>,
+	/// Voters with more than two targets. The ratios of all but the last one are given and the
+	/// last one gets the rest.
+	pub votes_n: Vec<(V, Vec<(T, Perbill)>, T)>,
+}
+
+impl<V, T> Default for CompactAssignments<V, T> {
+	fn default() -> Self {
+		CompactAssignments { votes1: Vec::new(), votes2: Vec::new(), votes_n: Vec::new() }
+	}
+}
+
+impl<V: Copy, T: Copy> CompactAssignments<V, T> {
+	/// Number of voters in this compact representation.
+	pub fn len(&self) -> usize {
+		self.votes1.len() + self.votes2.len() + self.votes_n.len()
+	}
+
+	/// Returns `true` if this contains no voters.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Build the compact representation of `assignments`.
+	///
+	/// Voters without any target are skipped. Returns `None` if `voter_index` or `target_index`
+	/// fail to provide the index of any of the accounts.
+	pub fn from_assignment<AccountId, FV, FT>(
+		assignments: Vec<(AccountId, Vec<PhragmenAssignment<AccountId>>)>,
+		voter_index: FV,
+		target_index: FT,
+	) -> Option<Self> where
+		for<'r> FV: Fn(&'r AccountId) -> Option<V>,
+		for<'r> FT: Fn(&'r AccountId) -> Option<T>,
+	{
+		let mut compact = Self::default();
+		for (voter, distribution) in assignments {
+			let voter = voter_index(&voter)?;
+			let mut targets = distribution.into_iter()
+				.map(|(target, ratio)| target_index(&target).map(|index| (index, ratio)))
+				.collect::<Option<Vec<(T, Perbill)>>>()?;
+			let last = match targets.pop() {
+				Some((last, _)) => last,
+				None => continue,
+			};
+			match targets.len() {
+				0 => compact.votes1.push((voter, last)),
+				1 => compact.votes2.push((voter, targets[0], last)),
+				_ => compact.votes_n.push((voter, targets, last)),
+			}
+		}
+		Some(compact)
+	}
+
+	/// Expand this compact representation back into assignments.
+	///
+	/// Returns `None` if `voter_at` or `target_at` fail to provide the account of any of the
+	/// indices, or if the ratios given for a voter add up to more than one.
+	pub fn into_assignment<AccountId, FV, FT>(
+		self,
+		voter_at: FV,
+		target_at: FT,
+	) -> Option<Vec<(AccountId, Vec<PhragmenAssignment<AccountId>>)>> where
+		FV: Fn(V) -> Option<AccountId>,
+		FT: Fn(T) -> Option<AccountId>,
+	{
+		let mut assignments = Vec::with_capacity(self.len());
+		for (voter, last) in self.votes1 {
+			assignments.push((voter_at(voter)?, vec![(target_at(last)?, Perbill::one())]));
+		}
+		for (voter, first, last) in self.votes2 {
+			assignments.push((voter_at(voter)?, Self::expand(vec![first], last, &target_at)?));
+		}
+		for (voter, given, last) in self.votes_n {
+			assignments.push((voter_at(voter)?, Self::expand(given, last, &target_at)?));
+		}
+		Some(assignments)
+	}
+
+	/// Expand the distribution of a single voter, giving whatever is left to `last`.
+	fn expand<AccountId, FT>(
+		given: Vec<(T, Perbill)>,
+		last: T,
+		target_at: &FT,
+	) -> Option<Vec<PhragmenAssignment<AccountId>>> where
+		FT: Fn(T) -> Option<AccountId>,
+	{
+		let mut sum = 0u32;
+		let mut distribution = Vec::with_capacity(given.len() + 1);
+		for (target, ratio) in given {
+			sum = sum.checked_add(ratio.deconstruct()).filter(|s| *s <= Perbill::accuracy())?;
+			distribution.push((target_at(target)?, ratio));
+		}
+		distribution.push((target_at(last)?, Perbill::from_parts(Perbill::accuracy() - sum)));
+		Some(distribution)
+	}
+}
+
 /// Perform election based on Phragmén algorithm.
 ///
 /// Returns an `Option` the set of winners and their detailed support ratio from each voter if
@@ -374,19 +490,108 @@ pub fn build_support_map<Balance, AccountId, FS, C>(
 	supports
 }
 
+/// Convert ratio assignments into staked ones, using the stake of each voter.
+pub fn assignment_ratio_to_staked<AccountId, Balance, FS, C>(
+	assignments: Vec<(AccountId, Vec<PhragmenAssignment<AccountId>>)>,
+	stake_of: FS,
+) -> Vec<(AccountId, Vec<PhragmenStakedAssignment<AccountId>>)> where
+	C: Convert<Balance, u64>,
+	for<'r> FS: Fn(&'r AccountId) -> Balance,
+{
+	assignments.into_iter().map(|(voter, distribution)| {
+		let stake = <C as Convert<Balance, u64>>::convert(stake_of(&voter)) as ExtendedBalance;
+		let distribution = distribution.into_iter()
+			.map(|(target, ratio)| (target, ratio * stake))
+			.collect();
+		(voter, distribution)
+	}).collect()
+}
+
+/// Convert staked assignments back into ratio ones.
+///
+/// The ratios are relative to the total stake distributed by each voter, and always add up to
+/// exactly one since the last target gets whatever is left by rounding. Targets that received no
+/// stake are dropped, and so are voters that distributed no stake at all.
+pub fn assignment_staked_to_ratio<AccountId>(
+	assignments: Vec<(AccountId, Vec<PhragmenStakedAssignment<AccountId>>)>,
+) -> Vec<(AccountId, Vec<PhragmenAssignment<AccountId>>)> {
+	assignments.into_iter().filter_map(|(voter, distribution)| {
+		let distribution = distribution.into_iter()
+			.filter(|(_, stake)| !stake.is_zero())
+			.collect::<Vec<_>>();
+		let total = distribution.iter()
+			.fold(0 as ExtendedBalance, |total, (_, stake)| total.saturating_add(*stake));
+		if total.is_zero() {
+			return None;
+		}
+
+		let last_index = distribution.len() - 1;
+		let mut sum = 0u32;
+		let distribution = distribution.into_iter().enumerate().map(|(index, (target, stake))| {
+			let ratio = if index == last_index {
+				Perbill::from_parts(Perbill::accuracy().saturating_sub(sum))
+			} else {
+				let parts = multiply_by_rational(Perbill::accuracy().into(), stake, total)
+					.unwrap_or(0)
+					.min(Perbill::accuracy().saturating_sub(sum).into()) as u32;
+				sum += parts;
+				Perbill::from_parts(parts)
+			};
+			(target, ratio)
+		}).collect();
+
+		Some((voter, distribution))
+	}).collect()
+}
+
+/// Evaluate the score of an election result, given its support map.
+pub fn evaluate_support<AccountId>(support: &SupportMap<AccountId>) -> PhragmenScore {
+	let mut min_support = ExtendedBalance::max_value();
+	let mut sum: ExtendedBalance = Zero::zero();
+	let mut sum_squared: ExtendedBalance = Zero::zero();
+	for (_, support) in support.iter() {
+		sum = sum.saturating_add(support.total);
+		sum_squared = sum_squared.saturating_add(support.total.saturating_mul(support.total));
+		if support.total < min_support {
+			min_support = support.total;
+		}
+	}
+	if support.is_empty() {
+		min_support = Zero::zero();
+	}
+	[min_support, sum, sum_squared]
+}
+
+/// Returns `true` if `this` score is strictly better than `that` one.
+///
+/// Scores are compared lexicographically: the higher minimum support wins, then the higher sum of
+/// supports and finally the lower sum of squared supports.
+pub fn is_score_better(this: PhragmenScore, that: PhragmenScore) -> bool {
+	match this[0].cmp(&that[0]) {
+		Ordering::Greater => true,
+		Ordering::Less => false,
+		Ordering::Equal => match this[1].cmp(&that[1]) {
+			Ordering::Greater => true,
+			Ordering::Less => false,
+			Ordering::Equal => this[2] < that[2],
+		},
+	}
+}
+
 /// Performs equalize post-processing to the output of the election algorithm. This happens in
 /// rounds. The number of rounds and the maximum diff-per-round tolerance can be tuned through input
 /// parameters.
 ///
-/// No value is returned from the function and the `supports` parameter is updated.
+/// No value is returned from the function and the `assignments` and `supports` parameters are
+/// updated.
 ///
-/// * `assignments`: exactly the same is the output of phragmen.
+/// * `assignments`: the output of phragmen, in staked form. This parameter is updated.
 /// * `supports`: mutable reference to s `SupportMap`. This parameter is updated.
 /// * `tolerance`: maximum difference that can occur before an early quite happens.
 /// * `iterations`: maximum number of iterations that will be processed.
 /// * `stake_of`: something that can return the stake stake of a particular candidate or voter.
 pub fn equalize<Balance, AccountId, C, FS>(
-	mut assignments: Vec<(AccountId, Vec<PhragmenStakedAssignment<AccountId>>)>,
+	assignments: &mut Vec<(AccountId, Vec<PhragmenStakedAssignment<AccountId>>)>,
 	supports: &mut SupportMap<AccountId>,
 	tolerance: ExtendedBalance,
 	iterations: usize,
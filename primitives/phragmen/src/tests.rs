@@ -19,7 +19,11 @@
 #![cfg(test)]
 
 use crate::mock::*;
-use crate::{elect, PhragmenResult, PhragmenStakedAssignment, build_support_map, Support, equalize};
+use crate::{
+	elect, PhragmenResult, PhragmenStakedAssignment, build_support_map, Support, equalize,
+	evaluate_support, is_score_better, assignment_ratio_to_staked, assignment_staked_to_ratio,
+	CompactAssignments,
+};
 use substrate_test_utils::assert_eq_uvec;
 use sp_runtime::Perbill;
 
@@ -481,7 +485,13 @@ fn self_votes_should_be_kept() {
 		staked_assignments.push((n.clone(), staked_assignment));
 	}
 
-	equalize::<Balance, AccountId, TestCurrencyToVote, _>(staked_assignments, &mut supports, 0, 2usize, &stake_of);
+	equalize::<Balance, AccountId, TestCurrencyToVote, _>(
+		&mut staked_assignments,
+		&mut supports,
+		0,
+		2usize,
+		&stake_of,
+	);
 
 	assert_eq!(
 		supports.get(&10u64).unwrap(),
@@ -492,3 +502,113 @@ fn self_votes_should_be_kept() {
 		&Support { total: 20u128, voters: vec![(20u64, 20u128)] },
 	);
 }
+
+#[test]
+fn score_evaluation_and_comparison_works() {
+	let candidates = vec![1, 2, 3];
+	let voters = vec![
+		(10, vec![1, 2]),
+		(20, vec![1, 3]),
+		(30, vec![2, 3]),
+	];
+	let stake_of = create_stake_of(&[(10, 10), (20, 20), (30, 30)]);
+	let PhragmenResult { winners, assignments } = elect::<_, _, _, TestCurrencyToVote>(
+		2,
+		2,
+		candidates,
+		voters,
+		&stake_of,
+	).unwrap();
+	let winners = winners.into_iter().map(|(who, _)| who).collect::<Vec<AccountId>>();
+
+	let mut supports = build_support_map::<Balance, AccountId, _, TestCurrencyToVote>(
+		&winners,
+		&assignments,
+		&stake_of,
+	);
+	let score = evaluate_support(&supports);
+	assert_eq!(score, [25, 60, 25 * 25 + 35 * 35]);
+
+	let mut staked = assignment_ratio_to_staked::<_, Balance, _, TestCurrencyToVote>(
+		assignments,
+		&stake_of,
+	);
+	equalize::<Balance, AccountId, TestCurrencyToVote, _>(&mut staked, &mut supports, 0, 2, &stake_of);
+	let equalized_score = evaluate_support(&supports);
+	assert_eq!(equalized_score, [30, 60, 30 * 30 + 30 * 30]);
+
+	assert!(is_score_better(equalized_score, score));
+	assert!(!is_score_better(score, equalized_score));
+	assert!(!is_score_better(score, score));
+	assert!(is_score_better([10, 20, 30], [10, 20, 40]));
+	assert!(is_score_better([10, 30, 50], [10, 20, 40]));
+	assert!(!is_score_better([9, 30, 10], [10, 20, 40]));
+}
+
+#[test]
+fn staked_to_ratio_assignments_add_up() {
+	let staked = vec![
+		(10u64, vec![(1u64, 1u128), (2, 1), (3, 1)]),
+		(20, vec![(1, 0), (2, 20)]),
+		(30, vec![(1, 0)]),
+	];
+	let ratio = assignment_staked_to_ratio(staked);
+
+	assert_eq!(ratio.len(), 2);
+	assert_eq!(ratio[0].1.len(), 3);
+	assert_eq!(ratio[0].1.iter().map(|(_, p)| p.deconstruct()).sum::<u32>(), Perbill::accuracy());
+	assert_eq!(ratio[1], (20, vec![(2, Perbill::one())]));
+}
+
+#[test]
+fn compact_assignments_round_trip() {
+	let assignments = vec![
+		(10u64, vec![(1u64, Perbill::one())]),
+		(20, vec![(1, Perbill::from_percent(40)), (2, Perbill::from_percent(60))]),
+		(
+			30,
+			vec![
+				(1, Perbill::from_percent(20)),
+				(2, Perbill::from_percent(30)),
+				(3, Perbill::from_percent(50)),
+			],
+		),
+	];
+	let voters = vec![10u64, 20, 30];
+	let targets = vec![1u64, 2, 3];
+	let voter_index = |who: &u64| voters.iter().position(|v| v == who).map(|i| i as u32);
+	let target_index = |who: &u64| targets.iter().position(|t| t == who).map(|i| i as u16);
+
+	let compact = CompactAssignments::<u32, u16>::from_assignment(
+		assignments.clone(),
+		voter_index,
+		target_index,
+	).unwrap();
+	assert_eq!(compact.votes1, vec![(0, 0)]);
+	assert_eq!(compact.votes2, vec![(1, (0, Perbill::from_percent(40)), 1)]);
+	assert_eq!(compact.len(), 3);
+
+	let decoded = compact.into_assignment(
+		|i: u32| voters.get(i as usize).cloned(),
+		|i: u16| targets.get(i as usize).cloned(),
+	).unwrap();
+	assert_eq!(decoded, assignments);
+
+	// unknown accounts can't be compacted.
+	assert!(CompactAssignments::<u32, u16>::from_assignment(
+		vec![(40u64, vec![(1u64, Perbill::one())])],
+		voter_index,
+		target_index,
+	).is_none());
+
+	// ratios adding up to more than one are rejected.
+	let invalid = CompactAssignments::<u32, u16> {
+		votes1: vec![],
+		votes2: vec![(0, (0, Perbill::one()), 1)],
+		votes_n: vec![(1, vec![(0, Perbill::from_percent(60)), (1, Perbill::from_percent(60))], 2)],
+	};
+	assert!(invalid.into_assignment(
+		|i: u32| voters.get(i as usize).cloned(),
+		|i: u16| targets.get(i as usize).cloned(),
+	).is_none());
+}
@@ -25,6 +25,11 @@ use sp_runtime::{generic::BlockId, traits::Block as BlockT};
 #[cfg(feature = "std")]
 use sp_api::ProvideRuntimeApi;
 
+use sp_staking::SessionIndex;
+
+/// The number of validators in a session.
+pub type ValidatorCount = u32;
+
 sp_api::decl_runtime_apis! {
 	/// Session keys runtime api.
 	pub trait SessionKeys {
@@ -39,6 +44,33 @@ sp_api::decl_runtime_apis! {
 	}
 }
 
+/// Something that knows the index of the session it refers to.
+///
+/// This is e.g. implemented by key ownership proofs, which are only valid in
+/// the context of a given session.
+pub trait GetSessionNumber {
+	/// The session index this refers to.
+	fn session(&self) -> SessionIndex;
+}
+
+/// Something that knows the size of the validator set of the session it refers to.
+pub trait GetValidatorCount {
+	/// The number of validators in the session this refers to.
+	fn validator_count(&self) -> ValidatorCount;
+}
+
+impl GetSessionNumber for sp_core::Void {
+	fn session(&self) -> SessionIndex {
+		match *self {}
+	}
+}
+
+impl GetValidatorCount for sp_core::Void {
+	fn validator_count(&self) -> ValidatorCount {
+		match *self {}
+	}
+}
+
 /// Generate the initial session keys with the given seeds, at the given block and store them in
 /// the client's keystore.
 #[cfg(feature = "std")]
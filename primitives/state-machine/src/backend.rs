@@ -213,6 +213,22 @@ pub trait Backend<H: Hasher>: std::fmt::Debug {
 	fn usage_info(&self) -> UsageInfo {
 		UsageInfo::empty()
 	}
+
+	/// Wipe the state database.
+	///
+	/// Only supported by backends that are used for benchmarking, all other backends
+	/// return an error.
+	fn wipe(&self) -> Result<(), String> {
+		Err("Wiping the state is not supported by this backend".into())
+	}
+
+	/// Commit given transaction to storage.
+	///
+	/// Only supported by backends that are used for benchmarking, all other backends
+	/// return an error.
+	fn commit(&self, _storage_root: H::Out, _transaction: Self::Transaction) -> Result<(), String> {
+		Err("Committing the state is not supported by this backend".into())
+	}
 }
 
 impl<'a, T: Backend<H>, H: Hasher> Backend<H> for &'a T {
@@ -301,6 +317,14 @@ impl<'a, T: Backend<H>, H: Hasher> Backend<H> for &'a T {
 	fn usage_info(&self) -> UsageInfo {
 		(*self).usage_info()
 	}
+
+	fn wipe(&self) -> Result<(), String> {
+		(*self).wipe()
+	}
+
+	fn commit(&self, storage_root: H::Out, transaction: Self::Transaction) -> Result<(), String> {
+		(*self).commit(storage_root, transaction)
+	}
  }
 
 /// Trait that allows consolidate two transactions together.
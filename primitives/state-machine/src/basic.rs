@@ -19,14 +19,17 @@
 use std::{
 	collections::BTreeMap, any::{TypeId, Any}, iter::FromIterator, ops::Bound
 };
-use crate::{Backend, InMemoryBackend, StorageKey, StorageValue};
+use crate::{
+	Backend, InMemoryBackend, StorageKey, StorageValue,
+	transaction_journal::{TransactionJournal, note_change, revert_map},
+};
 use hash_db::Hasher;
 use sp_trie::{TrieConfiguration, default_child_trie_root};
 use sp_trie::trie_types::Layout;
 use sp_core::{
 	storage::{
 		well_known_keys::is_child_storage_key, ChildStorageKey, Storage,
-		ChildInfo, StorageChild,
+		ChildInfo, StorageChild, OwnedChildInfo,
 	},
 	traits::Externalities, Blake2Hasher,
 };
@@ -37,19 +40,41 @@ use codec::Encode;
 #[derive(Debug)]
 pub struct BasicExternalities {
 	inner: Storage,
+	/// The journals of the open storage transactions. The last one belongs to the innermost
+	/// transaction.
+	transactions: Vec<TransactionJournal<StorageValue, OwnedChildInfo>>,
 }
 
 impl BasicExternalities {
 	/// Create a new instance of `BasicExternalities`
 	pub fn new(inner: Storage) -> Self {
-		BasicExternalities { inner }
+		BasicExternalities { inner, transactions: Vec::new() }
 	}
 
 	/// Insert key/value
 	pub fn insert(&mut self, k: StorageKey, v: StorageValue) -> Option<StorageValue> {
+		self.note_top_change(&k);
 		self.inner.top.insert(k, v)
 	}
 
+	/// Record the current value of the given top level key in the journal of the innermost
+	/// open transaction.
+	fn note_top_change(&mut self, key: &[u8]) {
+		if let Some(journal) = self.transactions.last_mut() {
+			note_change(&mut journal.top, Some(&self.inner.top), key);
+		}
+	}
+
+	/// Record the current value of the given child key in the journal of the innermost
+	/// open transaction.
+	fn note_child_change(&mut self, storage_key: &[u8], key: &[u8]) {
+		if let Some(journal) = self.transactions.last_mut() {
+			let child = self.inner.children.get(storage_key);
+			let child_journal = journal.child(storage_key, child.map(|c| &c.child_info));
+			note_change(child_journal, child.map(|c| &c.data), key);
+		}
+	}
+
 	/// Consume self and returns inner storages
 	pub fn into_storages(self) -> Storage {
 		self.inner
@@ -62,10 +87,10 @@ impl BasicExternalities {
 		storage: &mut sp_core::storage::Storage,
 		f: impl FnOnce() -> R,
 	) -> R {
-		let mut ext = Self { inner: Storage {
+		let mut ext = Self::new(Storage {
 			top: std::mem::replace(&mut storage.top, Default::default()),
 			children: std::mem::replace(&mut storage.children, Default::default()),
-		}};
+		});
 
 		let r = ext.execute_with(f);
 
@@ -103,10 +128,10 @@ impl Default for BasicExternalities {
 
 impl From<BTreeMap<StorageKey, StorageValue>> for BasicExternalities {
 	fn from(hashmap: BTreeMap<StorageKey, StorageValue>) -> Self {
-		BasicExternalities { inner: Storage {
+		Self::new(Storage {
 			top: hashmap,
 			children: Default::default(),
-		}}
+		})
 	}
 }
 
@@ -185,6 +210,7 @@ impl Externalities for BasicExternalities {
 			return;
 		}
 
+		self.note_top_change(&key);
 		match maybe_value {
 			Some(value) => { self.inner.top.insert(key, value); }
 			None => { self.inner.top.remove(&key); }
@@ -198,6 +224,7 @@ impl Externalities for BasicExternalities {
 		key: StorageKey,
 		value: Option<StorageValue>,
 	) {
+		self.note_child_change(storage_key.as_ref(), &key);
 		let child_map = self.inner.children.entry(storage_key.into_owned())
 			.or_insert_with(|| StorageChild {
 				data: Default::default(),
@@ -215,7 +242,14 @@ impl Externalities for BasicExternalities {
 		storage_key: ChildStorageKey,
 		_child_info: ChildInfo,
 	) {
-		self.inner.children.remove(storage_key.as_ref());
+		if let Some(child) = self.inner.children.remove(storage_key.as_ref()) {
+			if let Some(journal) = self.transactions.last_mut() {
+				let child_journal = journal.child(storage_key.as_ref(), Some(&child.child_info));
+				for (key, value) in child.data {
+					child_journal.entry(key).or_insert(Some(value));
+				}
+			}
+		}
 	}
 
 	fn clear_prefix(&mut self, prefix: &[u8]) {
@@ -234,6 +268,7 @@ impl Externalities for BasicExternalities {
 			.collect::<Vec<_>>();
 
 		for key in to_remove {
+			self.note_top_change(&key);
 			self.inner.top.remove(&key);
 		}
 	}
@@ -244,19 +279,27 @@ impl Externalities for BasicExternalities {
 		_child_info: ChildInfo,
 		prefix: &[u8],
 	) {
-		if let Some(child) = self.inner.children.get_mut(storage_key.as_ref()) {
-			let to_remove = child.data.range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+		let to_remove = match self.inner.children.get(storage_key.as_ref()) {
+			Some(child) => child.data.range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
 				.map(|(k, _)| k)
 				.take_while(|k| k.starts_with(prefix))
 				.cloned()
-				.collect::<Vec<_>>();
+				.collect::<Vec<_>>(),
+			None => return,
+		};
 
-			for key in to_remove {
+		for key in to_remove {
+			self.note_child_change(storage_key.as_ref(), &key);
+			if let Some(child) = self.inner.children.get_mut(storage_key.as_ref()) {
 				child.data.remove(&key);
 			}
 		}
 	}
 
+	fn set_offchain_storage(&mut self, _key: &[u8], _value: Option<&[u8]>) {
+		// There is no block to import the offchain storage with, so the changes are dropped.
+	}
+
 	fn chain_id(&self) -> u64 { 42 }
 
 	fn storage_root(&mut self) -> Vec<u8> {
@@ -298,6 +341,49 @@ impl Externalities for BasicExternalities {
 	fn storage_changes_root(&mut self, _parent: &[u8]) -> Result<Option<Vec<u8>>, ()> {
 		Ok(None)
 	}
+
+	fn wipe(&mut self) -> Result<(), ()> {
+		Err(())
+	}
+
+	fn commit(&mut self) -> Result<(), ()> {
+		Err(())
+	}
+
+	fn storage_start_transaction(&mut self) {
+		self.transactions.push(Default::default());
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		let journal = self.transactions.pop().ok_or(())?;
+
+		revert_map(&mut self.inner.top, journal.top);
+		for (storage_key, (child_journal, child_info)) in journal.children {
+			match child_info {
+				Some(child_info) => {
+					let child = self.inner.children.entry(storage_key)
+						.or_insert_with(|| StorageChild {
+							data: Default::default(),
+							child_info: child_info.clone(),
+						});
+					child.child_info = child_info;
+					revert_map(&mut child.data, child_journal);
+				},
+				// The child trie was created in the transaction.
+				None => { self.inner.children.remove(&storage_key); },
+			}
+		}
+
+		Ok(())
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		let journal = self.transactions.pop().ok_or(())?;
+		if let Some(outer) = self.transactions.last_mut() {
+			outer.merge(journal);
+		}
+		Ok(())
+	}
 }
 
 impl sp_externalities::ExtensionStore for BasicExternalities {
@@ -366,6 +452,30 @@ mod tests {
 		assert_eq!(ext.child_storage(child(), CHILD_INFO_1, b"doe"), None);
 	}
 
+	#[test]
+	fn transactions_revert_top_and_child_changes() {
+		let child_storage = b":child_storage:default:test".to_vec();
+		let child = || ChildStorageKey::from_vec(child_storage.clone()).unwrap();
+
+		let mut ext = BasicExternalities::default();
+		ext.set_storage(b"doe".to_vec(), b"reindeer".to_vec());
+		ext.set_child_storage(child(), CHILD_INFO_1, b"dog".to_vec(), b"puppy".to_vec());
+
+		ext.storage_start_transaction();
+		ext.set_storage(b"doe".to_vec(), b"deer".to_vec());
+		ext.storage_start_transaction();
+		ext.kill_child_storage(child(), CHILD_INFO_1);
+		ext.clear_prefix(b"do");
+		assert_eq!(ext.storage_commit_transaction(), Ok(()));
+		assert_eq!(ext.storage(b"doe"), None);
+		assert_eq!(ext.child_storage(child(), CHILD_INFO_1, b"dog"), None);
+
+		assert_eq!(ext.storage_rollback_transaction(), Ok(()));
+		assert_eq!(ext.storage(b"doe"), Some(b"reindeer".to_vec()));
+		assert_eq!(ext.child_storage(child(), CHILD_INFO_1, b"dog"), Some(b"puppy".to_vec()));
+		assert_eq!(ext.storage_rollback_transaction(), Err(()));
+	}
+
 	#[test]
 	fn basic_externalities_is_empty() {
 		// Make sure no values are set by default in `BasicExternalities`.
@@ -393,6 +393,16 @@ where
 		self.overlay.set_storage(key, value);
 	}
 
+	fn set_offchain_storage(&mut self, key: &[u8], value: Option<&[u8]>) {
+		trace!(target: "state-trace", "{:04x}: PutOffchain {}={:?}",
+			self.id,
+			HexDisplay::from(&key),
+			value.map(HexDisplay::from)
+		);
+		let _guard = sp_panic_handler::AbortGuard::force_abort();
+		self.overlay.set_offchain_storage(key, value);
+	}
+
 	fn place_child_storage(
 		&mut self,
 		storage_key: ChildStorageKey,
@@ -582,6 +592,39 @@ where
 
 		root.map(|r| r.map(|o| o.encode()))
 	}
+
+	fn wipe(&mut self) -> Result<(), ()> {
+		self.backend.wipe().map_err(|e| warn!(target: "state-trace", "Failed to wipe state: {}", e))?;
+		self.overlay.clear_changes();
+		self.mark_dirty();
+		Ok(())
+	}
+
+	fn commit(&mut self) -> Result<(), ()> {
+		self.overlay.commit_prospective();
+		let root = self.overlay.storage_root(self.backend, self.storage_transaction_cache);
+		let transaction = self.storage_transaction_cache.transaction.take()
+			.expect("Transaction was generated as part of `storage_root`; qed");
+
+		self.backend.commit(root, transaction)
+			.map_err(|e| warn!(target: "state-trace", "Failed to commit state: {}", e))?;
+		self.overlay.clear_changes();
+		self.mark_dirty();
+		Ok(())
+	}
+
+	fn storage_start_transaction(&mut self) {
+		self.overlay.start_transaction();
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		self.mark_dirty();
+		self.overlay.rollback_transaction()
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		self.overlay.commit_transaction()
+	}
 }
 
 impl<'a, H, B, N> sp_externalities::ExtensionStore for Ext<'a, H, N, B>
@@ -627,6 +670,7 @@ mod tests {
 			].into_iter().collect(),
 			committed: Default::default(),
 			collect_extrinsics: true,
+			transactions: Vec::new(),
 		}
 	}
 
@@ -684,6 +728,19 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn wipe_and_commit_fail_when_not_supported_by_backend() {
+		let mut overlay = prepare_overlay_with_changes();
+		let mut cache = StorageTransactionCache::default();
+		let backend = TestBackend::default();
+		let mut ext = TestExt::new(&mut overlay, &mut cache, &backend, None, None);
+
+		assert_eq!(ext.wipe(), Err(()));
+		// the overlay is kept when the backend could not be wiped.
+		assert_eq!(ext.storage(&[1]), Some(vec![100]));
+		assert_eq!(ext.commit(), Err(()));
+	}
+
 	#[test]
 	fn next_storage_key_works() {
 		let mut cache = StorageTransactionCache::default();
@@ -41,6 +41,7 @@ mod proving_backend;
 mod trie_backend;
 mod trie_backend_essence;
 mod stats;
+mod transaction_journal;
 
 pub use sp_trie::{trie_types::{Layout, TrieDBMut}, TrieMut, DBValue, MemoryDB};
 pub use testing::TestExternalities;
@@ -610,6 +611,104 @@ where
 	Ok(proving_backend.extract_proof())
 }
 
+/// Generate a proof for a contiguous range of top-level storage entries.
+///
+/// Entries are read in key order, starting with `start` (inclusive), until at least
+/// `max_size` bytes of keys and values have been collected. Returns the entries, the proof
+/// and whether the last entry of the state has been reached.
+pub fn prove_range_read_with_size<B, H>(
+	mut backend: B,
+	start: &[u8],
+	max_size: usize,
+) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, StorageProof, bool), Box<dyn Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	H::Out: Ord + Codec,
+{
+	let trie_backend = backend.as_trie_backend()
+		.ok_or_else(|| Box::new(ExecutionError::UnableToGenerateProof) as Box<dyn Error>)?;
+	prove_range_read_with_size_on_trie_backend(trie_backend, start, max_size)
+}
+
+/// Generate a range read proof on pre-created trie backend.
+pub fn prove_range_read_with_size_on_trie_backend<S, H>(
+	trie_backend: &TrieBackend<S, H>,
+	start: &[u8],
+	max_size: usize,
+) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, StorageProof, bool), Box<dyn Error>>
+where
+	S: trie_backend_essence::TrieBackendStorage<H>,
+	H: Hasher,
+	H::Out: Ord + Codec,
+{
+	let proving_backend = proving_backend::ProvingBackend::<_, H>::new(trie_backend);
+	let (entries, complete) = read_range(&proving_backend, start, |_, size| size >= max_size)?;
+	Ok((entries, proving_backend.extract_proof(), complete))
+}
+
+/// Check a range read proof, generated by `prove_range_read_with_size` call.
+///
+/// `count` is the number of entries the proof is expected to contain. Returns these entries and
+/// whether they include the last entry of the state.
+pub fn read_range_proof_check<H>(
+	root: H::Out,
+	proof: StorageProof,
+	start: &[u8],
+	count: usize,
+) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool), Box<dyn Error>>
+where
+	H: Hasher,
+	H::Out: Ord + Codec,
+{
+	let proving_backend = create_proof_check_backend::<H>(root, proof)?;
+	read_range(&proving_backend, start, |entries, _| entries >= count)
+}
+
+/// Read entries in key order, starting with `start`, until `is_full` says to stop.
+///
+/// Both the prover and the checker go through the exact same sequence of trie accesses, so
+/// everything needed by the checker is recorded by the prover.
+fn read_range<B, H, F>(
+	backend: &B,
+	start: &[u8],
+	is_full: F,
+) -> Result<(Vec<(Vec<u8>, Vec<u8>)>, bool), Box<dyn Error>>
+where
+	B: Backend<H>,
+	H: Hasher,
+	F: Fn(usize, usize) -> bool,
+{
+	let to_error = |e| Box::new(e) as Box<dyn Error>;
+	let mut entries = Vec::new();
+	let mut size = 0;
+	let mut next = match backend.storage(start).map_err(to_error)? {
+		Some(value) => Some((start.to_vec(), value)),
+		None => None,
+	};
+	if next.is_none() {
+		next = match backend.next_storage_key(start).map_err(to_error)? {
+			Some(key) => backend.storage(&key).map_err(to_error)?.map(|value| (key, value)),
+			None => None,
+		};
+	}
+
+	while let Some((key, value)) = next {
+		if is_full(entries.len(), size) {
+			return Ok((entries, false));
+		}
+
+		size += key.len() + value.len();
+		next = match backend.next_storage_key(&key).map_err(to_error)? {
+			Some(key) => backend.storage(&key).map_err(to_error)?.map(|value| (key, value)),
+			None => None,
+		};
+		entries.push((key, value));
+	}
+
+	Ok((entries, true))
+}
+
 /// Check storage read proof, generated by `prove_read` call.
 pub fn read_proof_check<H, I>(
 	root: H::Out,
@@ -1026,6 +1125,54 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn prove_range_read_and_proof_check_works() {
+		let remote_backend = trie_backend::tests::test_trie();
+		let remote_root = remote_backend.storage_root(::std::iter::empty()).0;
+
+		// read the whole state in chunks of at least 100 bytes.
+		let mut start = Vec::new();
+		let mut state = Vec::new();
+		loop {
+			let remote_backend = trie_backend::tests::test_trie();
+			let (entries, proof, complete) = prove_range_read_with_size(
+				remote_backend,
+				&start,
+				100,
+			).unwrap();
+			assert!(!entries.is_empty());
+
+			let (checked, checked_complete) = read_range_proof_check::<Blake2Hasher>(
+				remote_root,
+				proof.clone(),
+				&start,
+				entries.len(),
+			).unwrap();
+			assert_eq!(checked, entries);
+			assert_eq!(checked_complete, complete);
+
+			// a proof can not be used to claim more entries than it contains.
+			if !complete {
+				assert!(read_range_proof_check::<Blake2Hasher>(
+					remote_root,
+					proof,
+					&start,
+					entries.len() + 1,
+				).is_err());
+			}
+
+			start = entries.last().unwrap().0.clone();
+			start.push(0);
+			state.extend(entries);
+			if complete {
+				break;
+			}
+		}
+
+		let remote_backend = trie_backend::tests::test_trie();
+		assert_eq!(state, remote_backend.pairs());
+	}
+
 	#[test]
 	fn child_storage_uuid() {
 		const CHILD_INFO_1: ChildInfo<'static> = ChildInfo::new_default(b"unique_id_1");
@@ -22,6 +22,7 @@ use crate::{
 		NO_EXTRINSIC_INDEX, BlockNumber, build_changes_trie,
 		State as ChangesTrieState,
 	},
+	transaction_journal::{TransactionJournal, note_change, revert_map},
 };
 
 #[cfg(test)]
@@ -57,6 +58,9 @@ pub struct OverlayedChanges {
 	pub(crate) committed: OverlayedChangeSet,
 	/// True if extrinsiscs stats must be collected.
 	pub(crate) collect_extrinsics: bool,
+	/// The journals of the open storage transactions, recording the prospective changes made
+	/// in them. The last one belongs to the innermost transaction.
+	pub(crate) transactions: Vec<TransactionJournal<OverlayedValue, OwnedChildInfo>>,
 }
 
 /// The storage value, used inside OverlayedChanges.
@@ -78,6 +82,8 @@ pub struct OverlayedChangeSet {
 	pub top: BTreeMap<StorageKey, OverlayedValue>,
 	/// Child storage changes.
 	pub children: HashMap<StorageKey, (BTreeMap<StorageKey, OverlayedValue>, OwnedChildInfo)>,
+	/// Offchain storage changes.
+	pub offchain: BTreeMap<StorageKey, Option<StorageValue>>,
 }
 
 /// A storage changes structure that can be generated by the data collected in [`OverlayedChanges`].
@@ -91,6 +97,10 @@ pub struct StorageChanges<Transaction, H: Hasher, N: BlockNumber> {
 	pub main_storage_changes: StorageCollection,
 	/// All changes to the child storages.
 	pub child_storage_changes: ChildStorageCollection,
+	/// All changes to the offchain storage.
+	///
+	/// A value of `None` means that it was deleted.
+	pub offchain_storage_changes: StorageCollection,
 	/// A transaction for the backend that contains all changes from
 	/// [`main_storage_changes`](Self::main_storage_changes) and from
 	/// [`child_storage_changes`](Self::child_storage_changes).
@@ -108,6 +118,7 @@ impl<Transaction, H: Hasher, N: BlockNumber> StorageChanges<Transaction, H, N> {
 	pub fn into_inner(self) -> (
 		StorageCollection,
 		ChildStorageCollection,
+		StorageCollection,
 		Transaction,
 		H::Out,
 		Option<ChangesTrieTransaction<H, N>>,
@@ -115,6 +126,7 @@ impl<Transaction, H: Hasher, N: BlockNumber> StorageChanges<Transaction, H, N> {
 		(
 			self.main_storage_changes,
 			self.child_storage_changes,
+			self.offchain_storage_changes,
 			self.transaction,
 			self.transaction_storage_root,
 			self.changes_trie_transaction,
@@ -172,6 +184,7 @@ impl FromIterator<(StorageKey, OverlayedValue)> for OverlayedChangeSet {
 		Self {
 			top: iter.into_iter().collect(),
 			children: Default::default(),
+			offchain: Default::default(),
 		}
 	}
 }
@@ -179,13 +192,14 @@ impl FromIterator<(StorageKey, OverlayedValue)> for OverlayedChangeSet {
 impl OverlayedChangeSet {
 	/// Whether the change set is empty.
 	pub fn is_empty(&self) -> bool {
-		self.top.is_empty() && self.children.is_empty()
+		self.top.is_empty() && self.children.is_empty() && self.offchain.is_empty()
 	}
 
 	/// Clear the change set.
 	pub fn clear(&mut self) {
 		self.top.clear();
 		self.children.clear();
+		self.offchain.clear();
 	}
 }
 
@@ -233,6 +247,9 @@ impl OverlayedChanges {
 	/// `None` can be used to delete a value specified by the given key.
 	pub(crate) fn set_storage(&mut self, key: StorageKey, val: Option<StorageValue>) {
 		let extrinsic_index = self.extrinsic_index();
+		if let Some(journal) = self.transactions.last_mut() {
+			note_change(&mut journal.top, Some(&self.prospective.top), &key);
+		}
 		let entry = self.prospective.top.entry(key).or_default();
 		entry.value = val;
 
@@ -242,6 +259,25 @@ impl OverlayedChanges {
 		}
 	}
 
+	/// Inserts the given key-value pair into the prospective offchain change set.
+	///
+	/// `None` can be used to delete a value specified by the given key.
+	pub(crate) fn set_offchain_storage(&mut self, key: &[u8], val: Option<&[u8]>) {
+		if let Some(journal) = self.transactions.last_mut() {
+			note_change(&mut journal.offchain, Some(&self.prospective.offchain), key);
+		}
+		self.prospective.offchain.insert(key.to_vec(), val.map(|v| v.to_vec()));
+	}
+
+	/// Returns the pending change of the given offchain storage key, if any.
+	///
+	/// `Some(None)` means the entry is about to be deleted.
+	pub fn offchain_storage(&self, key: &[u8]) -> Option<Option<&[u8]>> {
+		self.prospective.offchain.get(key)
+			.or_else(|| self.committed.offchain.get(key))
+			.map(|v| v.as_ref().map(AsRef::as_ref))
+	}
+
 	/// Inserts the given key-value pair into the prospective child change set.
 	///
 	/// `None` can be used to delete a value specified by the given key.
@@ -253,6 +289,11 @@ impl OverlayedChanges {
 		val: Option<StorageValue>,
 	) {
 		let extrinsic_index = self.extrinsic_index();
+		if let Some(journal) = self.transactions.last_mut() {
+			let child = self.prospective.children.get(&storage_key);
+			let child_journal = journal.child(&storage_key, child.map(|c| &c.1));
+			note_change(child_journal, child.map(|c| &c.0), &key);
+		}
 		let map_entry = self.prospective.children.entry(storage_key)
 			.or_insert_with(|| (Default::default(), child_info.to_owned()));
 		let updatable = map_entry.1.try_update(child_info);
@@ -279,6 +320,16 @@ impl OverlayedChanges {
 		child_info: ChildInfo,
 	) {
 		let extrinsic_index = self.extrinsic_index();
+		if let Some(journal) = self.transactions.last_mut() {
+			let child = self.prospective.children.get(storage_key);
+			let child_journal = journal.child(storage_key, child.map(|c| &c.1));
+			let committed_keys = self.committed.children.get(storage_key)
+				.into_iter()
+				.flat_map(|c| c.0.keys());
+			for key in child.into_iter().flat_map(|c| c.0.keys()).chain(committed_keys) {
+				note_change(child_journal, child.map(|c| &c.0), key);
+			}
+		}
 		let map_entry = self.prospective.children.entry(storage_key.to_vec())
 			.or_insert_with(|| (Default::default(), child_info.to_owned()));
 		let updatable = map_entry.1.try_update(child_info);
@@ -318,6 +369,13 @@ impl OverlayedChanges {
 	/// [`discard_prospective`]: #method.discard_prospective
 	pub(crate) fn clear_prefix(&mut self, prefix: &[u8]) {
 		let extrinsic_index = self.extrinsic_index();
+		if let Some(journal) = self.transactions.last_mut() {
+			let keys = self.prospective.top.keys().chain(self.committed.top.keys())
+				.filter(|key| key.starts_with(prefix));
+			for key in keys {
+				note_change(&mut journal.top, Some(&self.prospective.top), key);
+			}
+		}
 
 		// Iterate over all prospective and mark all keys that share
 		// the given prefix as removed (None).
@@ -354,6 +412,18 @@ impl OverlayedChanges {
 		prefix: &[u8],
 	) {
 		let extrinsic_index = self.extrinsic_index();
+		if let Some(journal) = self.transactions.last_mut() {
+			let child = self.prospective.children.get(storage_key);
+			let child_journal = journal.child(storage_key, child.map(|c| &c.1));
+			let committed_keys = self.committed.children.get(storage_key)
+				.into_iter()
+				.flat_map(|c| c.0.keys());
+			let keys = child.into_iter().flat_map(|c| c.0.keys()).chain(committed_keys)
+				.filter(|key| key.starts_with(prefix));
+			for key in keys {
+				note_change(child_journal, child.map(|c| &c.0), key);
+			}
+		}
 		let map_entry = self.prospective.children.entry(storage_key.to_vec())
 			.or_insert_with(|| (Default::default(), child_info.to_owned()));
 		let updatable = map_entry.1.try_update(child_info);
@@ -387,13 +457,73 @@ impl OverlayedChanges {
 		}
 	}
 
+	/// Drop all prospective and committed changes, e.g. after they have been
+	/// written to the backend.
+	pub(crate) fn clear_changes(&mut self) {
+		self.prospective.clear();
+		self.committed.clear();
+		self.transactions.clear();
+	}
+
+	/// Start a new nested storage transaction.
+	///
+	/// The prospective changes made afterwards can be reverted with `rollback_transaction`.
+	/// Only the changes made in the transaction are recorded, so this doesn't copy the
+	/// prospective changes.
+	pub fn start_transaction(&mut self) {
+		self.transactions.push(Default::default());
+	}
+
+	/// Revert the prospective changes to the state they had when the innermost open transaction
+	/// was started and close the transaction.
+	///
+	/// Returns `Err(())` if there is no open transaction.
+	pub fn rollback_transaction(&mut self) -> Result<(), ()> {
+		let journal = self.transactions.pop().ok_or(())?;
+
+		revert_map(&mut self.prospective.top, journal.top);
+		revert_map(&mut self.prospective.offchain, journal.offchain);
+		for (storage_key, (child_journal, child_info)) in journal.children {
+			match child_info {
+				Some(child_info) => {
+					let child = self.prospective.children.entry(storage_key)
+						.or_insert_with(|| (Default::default(), child_info.clone()));
+					child.1 = child_info;
+					revert_map(&mut child.0, child_journal);
+				},
+				// The child trie was created in the transaction.
+				None => { self.prospective.children.remove(&storage_key); },
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Keep the prospective changes made in the innermost open transaction and close it.
+	///
+	/// The changes become part of the enclosing transaction, if any. Returns `Err(())` if
+	/// there is no open transaction.
+	pub fn commit_transaction(&mut self) -> Result<(), ()> {
+		let journal = self.transactions.pop().ok_or(())?;
+		if let Some(outer) = self.transactions.last_mut() {
+			outer.merge(journal);
+		}
+		Ok(())
+	}
+
 	/// Discard prospective changes to state.
+	///
+	/// All open storage transactions are closed.
 	pub fn discard_prospective(&mut self) {
 		self.prospective.clear();
+		self.transactions.clear();
 	}
 
 	/// Commit prospective changes to state.
+	///
+	/// All open storage transactions are closed, the committed changes can't be rolled back.
 	pub fn commit_prospective(&mut self) {
+		self.transactions.clear();
 		if self.committed.is_empty() {
 			mem::swap(&mut self.prospective, &mut self.committed);
 		} else {
@@ -407,6 +537,7 @@ impl OverlayedChanges {
 						.extend(prospective_extrinsics);
 				}
 			}
+			self.committed.offchain.append(&mut self.prospective.offchain);
 			for (storage_key, (map, child_info)) in self.prospective.children.drain() {
 				let child_content = self.committed.children.entry(storage_key)
 					.or_insert_with(|| (Default::default(), child_info));
@@ -431,12 +562,14 @@ impl OverlayedChanges {
 	pub fn into_committed(self) -> (
 		impl Iterator<Item=(StorageKey, Option<StorageValue>)>,
 		impl Iterator<Item=(StorageKey, (impl Iterator<Item=(StorageKey, Option<StorageValue>)>, OwnedChildInfo))>,
+		impl Iterator<Item=(StorageKey, Option<StorageValue>)>,
 	){
 		assert!(self.prospective.is_empty());
 		(
 			self.committed.top.into_iter().map(|(k, v)| (k, v.value)),
 			self.committed.children.into_iter()
-				.map(|(sk, (v, ci))| (sk, (v.into_iter().map(|(k, v)| (k, v.value)), ci)))
+				.map(|(sk, (v, ci))| (sk, (v.into_iter().map(|(k, v)| (k, v.value)), ci))),
+			self.committed.offchain.into_iter(),
 		)
 	}
 
@@ -474,11 +607,16 @@ impl OverlayedChanges {
 			.take()
 			.expect("Changes trie transaction was generated by `changes_trie_root`; qed");
 
-		let (main_storage_changes, child_storage_changes) = self.into_committed();
+		let (
+			main_storage_changes,
+			child_storage_changes,
+			offchain_storage_changes,
+		) = self.into_committed();
 
 		Ok(StorageChanges {
 			main_storage_changes: main_storage_changes.collect(),
 			child_storage_changes: child_storage_changes.map(|(sk, it)| (sk, it.0.collect())).collect(),
+			offchain_storage_changes: offchain_storage_changes.collect(),
 			transaction,
 			transaction_storage_root,
 			changes_trie_transaction,
@@ -591,6 +729,14 @@ impl OverlayedChanges {
 		None
 	}
 
+	/// Returns the storage keys of all child tries that have overlayed changes.
+	pub fn child_storage_keys(&self) -> impl Iterator<Item = &[u8]> {
+		let committed = &self.committed.children;
+		committed.keys()
+			.chain(self.prospective.children.keys().filter(move |k| !committed.contains_key(*k)))
+			.map(|k| &k[..])
+	}
+
 	/// Returns the next (in lexicographic order) storage key in the overlayed alongside its value.
 	/// If no value is next then `None` is returned.
 	pub fn next_storage_key_change(&self, key: &[u8]) -> Option<(&[u8], &OverlayedValue)> {
@@ -695,6 +841,89 @@ mod tests {
 		assert!(overlayed.storage(&key).unwrap().is_none());
 	}
 
+	#[test]
+	fn transactions_can_be_nested_and_rolled_back() {
+		let mut overlayed = OverlayedChanges::default();
+
+		overlayed.set_storage(b"a".to_vec(), Some(b"1".to_vec()));
+		overlayed.start_transaction();
+		overlayed.set_storage(b"a".to_vec(), Some(b"2".to_vec()));
+		overlayed.set_storage(b"b".to_vec(), Some(b"1".to_vec()));
+
+		overlayed.start_transaction();
+		overlayed.set_storage(b"b".to_vec(), None);
+		assert_eq!(overlayed.storage(b"b").unwrap(), None);
+		assert_eq!(overlayed.rollback_transaction(), Ok(()));
+		assert_eq!(overlayed.storage(b"b").unwrap(), Some(&b"1"[..]));
+
+		overlayed.start_transaction();
+		overlayed.set_storage(b"c".to_vec(), Some(b"1".to_vec()));
+		assert_eq!(overlayed.commit_transaction(), Ok(()));
+		assert_eq!(overlayed.storage(b"c").unwrap(), Some(&b"1"[..]));
+
+		// Rolling back the outer transaction reverts the committed inner one as well.
+		assert_eq!(overlayed.rollback_transaction(), Ok(()));
+		assert_eq!(overlayed.storage(b"a").unwrap(), Some(&b"1"[..]));
+		assert!(overlayed.storage(b"b").is_none());
+		assert!(overlayed.storage(b"c").is_none());
+
+		assert_eq!(overlayed.rollback_transaction(), Err(()));
+		assert_eq!(overlayed.commit_transaction(), Err(()));
+	}
+
+	#[test]
+	fn transactions_revert_child_prefix_and_offchain_changes() {
+		let child_info = ChildInfo::new_default(b"uniqueid");
+		let child = b":child_storage:default:child".to_vec();
+		let mut overlayed = OverlayedChanges::default();
+
+		overlayed.set_storage(b"key1".to_vec(), Some(b"1".to_vec()));
+		overlayed.commit_prospective();
+		overlayed.set_storage(b"key2".to_vec(), Some(b"2".to_vec()));
+		overlayed.set_child_storage(child.clone(), child_info, b"a".to_vec(), Some(b"1".to_vec()));
+
+		overlayed.start_transaction();
+		overlayed.clear_prefix(b"key");
+		overlayed.clear_child_storage(&child, child_info);
+		overlayed.set_child_storage(
+			b":child_storage:default:new".to_vec(),
+			child_info,
+			b"b".to_vec(),
+			Some(b"1".to_vec()),
+		);
+		overlayed.set_offchain_storage(b"offchain", Some(b"1"));
+		assert_eq!(overlayed.storage(b"key1").unwrap(), None);
+		assert_eq!(overlayed.child_storage(&child, b"a").unwrap(), None);
+
+		assert_eq!(overlayed.rollback_transaction(), Ok(()));
+		assert_eq!(overlayed.storage(b"key1").unwrap(), Some(&b"1"[..]));
+		assert_eq!(overlayed.storage(b"key2").unwrap(), Some(&b"2"[..]));
+		assert_eq!(overlayed.child_storage(&child, b"a").unwrap(), Some(&b"1"[..]));
+		assert!(!overlayed.prospective.children.contains_key(&b":child_storage:default:new"[..]));
+		assert!(overlayed.offchain_storage(b"offchain").is_none());
+		// The committed key is not part of the prospective changes anymore.
+		assert!(!overlayed.prospective.top.contains_key(&b"key1"[..]));
+	}
+
+	#[test]
+	fn offchain_changes_follow_prospective_changes() {
+		let mut overlayed = OverlayedChanges::default();
+
+		overlayed.set_offchain_storage(b"committed", Some(b"value"));
+		overlayed.commit_prospective();
+		overlayed.set_offchain_storage(b"discarded", Some(b"value"));
+		assert_eq!(overlayed.offchain_storage(b"discarded"), Some(Some(&b"value"[..])));
+		overlayed.discard_prospective();
+		assert_eq!(overlayed.offchain_storage(b"discarded"), None);
+
+		overlayed.set_offchain_storage(b"committed", None);
+		overlayed.commit_prospective();
+		assert_eq!(overlayed.offchain_storage(b"committed"), Some(None));
+
+		let (_, _, offchain) = overlayed.into_committed();
+		assert_eq!(offchain.collect::<Vec<_>>(), vec![(b"committed".to_vec(), None)]);
+	}
+
 	#[test]
 	fn overlayed_storage_root_works() {
 		let initial: BTreeMap<_, _> = vec![
@@ -873,4 +1102,19 @@ mod tests {
 		assert_eq!(next_to_40.0.to_vec(), vec![50]);
 		assert_eq!(next_to_40.1.value, Some(vec![50]));
 	}
+
+	#[test]
+	fn child_storage_keys_works() {
+		let child_info = ChildInfo::new_default(b"uniqueid");
+		let mut overlay = OverlayedChanges::default();
+		overlay.set_child_storage(b"Child1".to_vec(), child_info, vec![10], Some(vec![10]));
+		overlay.set_child_storage(b"Child2".to_vec(), child_info, vec![10], Some(vec![10]));
+		overlay.commit_prospective();
+		overlay.set_child_storage(b"Child2".to_vec(), child_info, vec![20], Some(vec![20]));
+		overlay.set_child_storage(b"Child3".to_vec(), child_info, vec![10], None);
+
+		let mut keys = overlay.child_storage_keys().map(|k| k.to_vec()).collect::<Vec<_>>();
+		keys.sort();
+		assert_eq!(keys, vec![b"Child1".to_vec(), b"Child2".to_vec(), b"Child3".to_vec()]);
+	}
 }
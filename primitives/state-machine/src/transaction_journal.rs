@@ -0,0 +1,139 @@
+// Copyright 2017-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Journal of the changes made in an open storage transaction.
+
+use std::collections::{BTreeMap, HashMap};
+use crate::overlayed_changes::{StorageKey, StorageValue};
+
+/// The previous entries of the keys of a map that were changed in a storage transaction.
+///
+/// `None` means that the key wasn't part of the map before it was changed.
+pub(crate) type MapJournal<V> = BTreeMap<StorageKey, Option<V>>;
+
+/// The changes made to the storage in an open storage transaction.
+///
+/// Instead of taking a snapshot of the storage when a transaction is started, the previous
+/// entry of a key is recorded the first time it is changed in the transaction. Starting a
+/// transaction is therefore free, while committing or rolling it back costs time proportional
+/// to the number of keys changed in it.
+#[derive(Debug, Clone)]
+pub(crate) struct TransactionJournal<V, C> {
+	/// The previous entries of the changed top level keys.
+	pub(crate) top: MapJournal<V>,
+	/// The previous entries of the changed keys of every child trie, together with the child
+	/// info of the child trie if it already existed when it was first changed.
+	pub(crate) children: HashMap<StorageKey, (MapJournal<V>, Option<C>)>,
+	/// The previous entries of the changed offchain keys.
+	pub(crate) offchain: MapJournal<Option<StorageValue>>,
+}
+
+impl<V, C> Default for TransactionJournal<V, C> {
+	fn default() -> Self {
+		Self {
+			top: Default::default(),
+			children: Default::default(),
+			offchain: Default::default(),
+		}
+	}
+}
+
+impl<V, C: Clone> TransactionJournal<V, C> {
+	/// Returns the journal of the given child trie.
+	///
+	/// `child_info` is the child info of the child trie if it exists at the moment.
+	pub(crate) fn child(&mut self, storage_key: &[u8], child_info: Option<&C>) -> &mut MapJournal<V> {
+		&mut self.children.entry(storage_key.to_vec())
+			.or_insert_with(|| (Default::default(), child_info.cloned()))
+			.0
+	}
+
+	/// Merge the journal of a committed nested transaction into this one.
+	///
+	/// Entries recorded by this journal are older and therefore take precedence.
+	pub(crate) fn merge(&mut self, inner: Self) {
+		merge_map(&mut self.top, inner.top);
+		merge_map(&mut self.offchain, inner.offchain);
+		for (storage_key, (journal, child_info)) in inner.children {
+			let entry = self.children.entry(storage_key)
+				.or_insert_with(|| (Default::default(), child_info));
+			merge_map(&mut entry.0, journal);
+		}
+	}
+}
+
+/// Record the current entry of `key` in `map`, unless the key was already changed before.
+pub(crate) fn note_change<V: Clone>(
+	journal: &mut MapJournal<V>,
+	map: Option<&BTreeMap<StorageKey, V>>,
+	key: &[u8],
+) {
+	if !journal.contains_key(key) {
+		journal.insert(key.to_vec(), map.and_then(|map| map.get(key)).cloned());
+	}
+}
+
+/// Restore the entries of `map` recorded in the given journal.
+pub(crate) fn revert_map<V>(map: &mut BTreeMap<StorageKey, V>, journal: MapJournal<V>) {
+	for (key, previous) in journal {
+		match previous {
+			Some(value) => { map.insert(key, value); },
+			None => { map.remove(&key); },
+		}
+	}
+}
+
+fn merge_map<V>(outer: &mut MapJournal<V>, inner: MapJournal<V>) {
+	for (key, previous) in inner {
+		outer.entry(key).or_insert(previous);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn only_the_first_change_is_recorded() {
+		let mut map = BTreeMap::new();
+		map.insert(b"a".to_vec(), 1);
+		let mut journal = MapJournal::new();
+
+		note_change(&mut journal, Some(&map), b"a");
+		map.insert(b"a".to_vec(), 2);
+		note_change(&mut journal, Some(&map), b"a");
+		note_change(&mut journal, Some(&map), b"b");
+		map.insert(b"b".to_vec(), 3);
+
+		revert_map(&mut map, journal);
+		assert_eq!(map.into_iter().collect::<Vec<_>>(), vec![(b"a".to_vec(), 1)]);
+	}
+
+	#[test]
+	fn merge_keeps_older_entries() {
+		let mut outer = TransactionJournal::<u32, ()>::default();
+		outer.top.insert(b"a".to_vec(), Some(1));
+		let mut inner = TransactionJournal::<u32, ()>::default();
+		inner.top.insert(b"a".to_vec(), Some(2));
+		inner.top.insert(b"b".to_vec(), None);
+		inner.child(b"child", None).insert(b"c".to_vec(), Some(3));
+
+		outer.merge(inner);
+		assert_eq!(outer.top.get(&b"a"[..]), Some(&Some(1)));
+		assert_eq!(outer.top.get(&b"b"[..]), Some(&None));
+		assert_eq!(outer.children.get(&b"child"[..]).unwrap().0.get(&b"c"[..]), Some(&Some(3)));
+	}
+}
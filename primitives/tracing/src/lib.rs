@@ -0,0 +1,236 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Forwarding of tracing spans entered by the runtime.
+//!
+//! The runtime enters and exits spans through the `wasm_tracing` runtime interface. Since the
+//! metadata of a `tracing` span needs to be known at compile time, all spans entered by the
+//! runtime share the `WASM_TRACE_IDENTIFIER` target and name, and carry their actual target,
+//! name and recorded fields as values. Subscribers are expected to use these values in place of
+//! the span metadata.
+//!
+//! The fields recorded by the runtime are kept until the span is exited, and are then recorded
+//! all at once as the `WASM_FIELD_KEY` value, separated by `WASM_FIELD_SEPARATOR`.
+//!
+//! Forwarding is disabled by default, in which case entering a span is a no-op.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tracing::{field, span, Level, Span};
+
+/// Target and name of the spans entered by the runtime.
+pub const WASM_TRACE_IDENTIFIER: &str = "wasm_tracing";
+/// Key of the value holding the target of a span entered by the runtime.
+pub const WASM_TARGET_KEY: &str = "wasm_target";
+/// Key of the value holding the name of a span entered by the runtime.
+pub const WASM_NAME_KEY: &str = "wasm_name";
+/// Key of the value holding the fields recorded by the runtime, formatted as `key=value`.
+pub const WASM_FIELD_KEY: &str = "wasm_field";
+/// Separator of the fields in the `WASM_FIELD_KEY` value.
+pub const WASM_FIELD_SEPARATOR: &str = "\n";
+
+/// The maximal number of spans that can be entered at the same time.
+///
+/// The runtime doesn't exit its spans when the execution is aborted, the oldest spans are
+/// discarded once this limit is reached.
+const MAX_SPANS_LEN: usize = 1000;
+
+static WASM_TRACING_ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+	static PROXY: RefCell<TracingProxy> = RefCell::new(TracingProxy::default());
+}
+
+/// Enables or disables forwarding of the spans entered by the runtime.
+pub fn set_wasm_tracing(enabled: bool) {
+	WASM_TRACING_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether the spans entered by the runtime are forwarded.
+pub fn wasm_tracing_enabled() -> bool {
+	WASM_TRACING_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Enters a new span with the given `target` and `name`.
+///
+/// Returns the id of the span, or `0` if forwarding is disabled.
+pub fn enter_span(target: &str, name: &str) -> u64 {
+	if !wasm_tracing_enabled() {
+		return 0;
+	}
+	PROXY.with(|proxy| proxy.borrow_mut().enter_span(target, name))
+}
+
+/// Records a field of the span with the given `id`.
+pub fn record(id: u64, key: &str, value: &str) {
+	if id == 0 {
+		return;
+	}
+	PROXY.with(|proxy| proxy.borrow_mut().record(id, key, value))
+}
+
+/// Exits the span with the given `id` and all spans entered after it.
+pub fn exit_span(id: u64) {
+	if id == 0 {
+		return;
+	}
+	PROXY.with(|proxy| proxy.borrow_mut().exit_span(id))
+}
+
+/// A span entered by the runtime.
+struct ProxiedSpan {
+	id: u64,
+	span: Span,
+	/// The fields recorded so far, formatted as `key=value`.
+	fields: Vec<String>,
+}
+
+impl ProxiedSpan {
+	/// Records the fields and exits the span.
+	fn exit(self) {
+		if !self.fields.is_empty() {
+			self.span.record(WASM_FIELD_KEY, &field::display(self.fields.join(WASM_FIELD_SEPARATOR)));
+		}
+		self.span.with_subscriber(|(id, subscriber)| subscriber.exit(id));
+	}
+}
+
+/// Keeps the spans entered by the runtime on the current thread.
+#[derive(Default)]
+struct TracingProxy {
+	next_id: u64,
+	spans: Vec<ProxiedSpan>,
+}
+
+impl TracingProxy {
+	fn enter_span(&mut self, target: &str, name: &str) -> u64 {
+		self.next_id += 1;
+		// The field names must match `WASM_TARGET_KEY`, `WASM_NAME_KEY` and `WASM_FIELD_KEY`.
+		let span = span!(
+			target: WASM_TRACE_IDENTIFIER,
+			Level::DEBUG,
+			WASM_TRACE_IDENTIFIER,
+			wasm_target = target,
+			wasm_name = name,
+			wasm_field = field::Empty
+		);
+		span.with_subscriber(|(id, subscriber)| subscriber.enter(id));
+		self.spans.push(ProxiedSpan { id: self.next_id, span, fields: Vec::new() });
+
+		if self.spans.len() > MAX_SPANS_LEN {
+			log::warn!(
+				target: WASM_TRACE_IDENTIFIER,
+				"Too many spans entered by the runtime, discarding the oldest one",
+			);
+			self.spans.remove(0).exit();
+		}
+		self.next_id
+	}
+
+	fn record(&mut self, id: u64, key: &str, value: &str) {
+		match self.spans.iter_mut().rev().find(|span| span.id == id) {
+			Some(span) => span.fields.push(format!("{}={}", key, value)),
+			None => log::warn!(
+				target: WASM_TRACE_IDENTIFIER,
+				"Tried to record a field of span {} that has already been exited",
+				id,
+			),
+		}
+	}
+
+	fn exit_span(&mut self, id: u64) {
+		let position = match self.spans.iter().rposition(|span| span.id == id) {
+			Some(position) => position,
+			None => {
+				log::warn!(
+					target: WASM_TRACE_IDENTIFIER,
+					"Tried to exit span {} that has already been exited",
+					id,
+				);
+				return;
+			},
+		};
+
+		if position + 1 != self.spans.len() {
+			log::warn!(
+				target: WASM_TRACE_IDENTIFIER,
+				"Exiting span {} before {} spans entered after it",
+				id,
+				self.spans.len() - position - 1,
+			);
+		}
+		for span in self.spans.drain(position..).rev() {
+			span.exit();
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn span_ids(proxy: &TracingProxy) -> Vec<u64> {
+		proxy.spans.iter().map(|span| span.id).collect()
+	}
+
+	#[test]
+	fn exiting_span_exits_nested_spans() {
+		let mut proxy = TracingProxy::default();
+		let first = proxy.enter_span("pallet", "first");
+		let second = proxy.enter_span("pallet", "second");
+		let third = proxy.enter_span("pallet", "third");
+		assert_eq!(span_ids(&proxy), vec![first, second, third]);
+
+		proxy.exit_span(third);
+		assert_eq!(span_ids(&proxy), vec![first, second]);
+
+		proxy.exit_span(first);
+		assert!(span_ids(&proxy).is_empty());
+
+		// Exiting an unknown span is ignored.
+		proxy.exit_span(second);
+		assert!(span_ids(&proxy).is_empty());
+	}
+
+	#[test]
+	fn oldest_spans_are_discarded() {
+		let mut proxy = TracingProxy::default();
+		for _ in 0..MAX_SPANS_LEN + 2 {
+			proxy.enter_span("pallet", "span");
+		}
+		assert_eq!(proxy.spans.len(), MAX_SPANS_LEN);
+		assert_eq!(proxy.spans[0].id, 3);
+	}
+
+	#[test]
+	fn all_recorded_fields_are_kept() {
+		let mut proxy = TracingProxy::default();
+		let first = proxy.enter_span("pallet", "first");
+		let second = proxy.enter_span("pallet", "second");
+		proxy.record(first, "a", "1");
+		proxy.record(first, "b", "2");
+		proxy.record(second, "c", "3");
+
+		assert_eq!(proxy.spans[0].fields, vec!["a=1".to_string(), "b=2".to_string()]);
+		assert_eq!(proxy.spans[1].fields, vec!["c=3".to_string()]);
+	}
+
+	#[test]
+	fn entering_span_is_noop_when_disabled() {
+		assert_eq!(enter_span("pallet", "span"), 0);
+	}
+}
@@ -63,6 +63,9 @@ pub enum Error {
 	/// The pool is not accepting future transactions.
 	#[display(fmt="The pool is not accepting future transactions")]
 	RejectedFutureTransaction,
+	/// The sender of the transaction has too many transactions in the pool.
+	#[display(fmt="Sender has too many transactions in the pool.")]
+	TooManyFromSender,
 }
 
 impl std::error::Error for Error {}
@@ -70,12 +70,21 @@ impl PoolStatus {
 ///		- `Invalid`
 ///		- `Usurped`
 ///		- `Dropped`
+/// 4. Re-entering the pool:
+///		- `Retracted`
+/// 5. Block finalized:
+///		- `Finalized`
+///		- `FinalityTimeout`
 ///
 /// The events will always be received in the order described above, however
 /// there might be cases where transactions alternate between `Future` and `Ready`
 /// pool, and are `Broadcast` in the meantime.
 ///
 /// There is also only single event causing the transaction to leave the pool.
+/// After `InBlock` the transaction is still watched until the block is either
+/// `Finalized` or `Retracted` (in which case the transaction might re-enter the pool).
+/// `FinalityTimeout` is emitted if the block is not finalized for too long and
+/// the pool stops tracking it.
 ///
 /// Note that there are conditions that may cause transactions to reappear in the pool.
 /// 1. Due to possible forks, the transaction that ends up being in included
@@ -99,6 +108,16 @@ pub enum TransactionStatus<Hash, BlockHash> {
 	/// Transaction has been included in block with given hash.
 	#[serde(rename = "finalized")] // See #4438
 	InBlock(BlockHash),
+	/// The block this transaction was included in has been retracted.
+	Retracted(BlockHash),
+	/// Maximum number of finality watchers has been reached,
+	/// the pool stopped waiting for finality of the block.
+	FinalityTimeout(BlockHash),
+	/// Transaction has been finalized by a finality-gadget, e.g GRANDPA.
+	///
+	/// Serialized as `blockFinalized`, since `finalized` is taken by `InBlock`.
+	#[serde(rename = "blockFinalized")]
+	Finalized(BlockHash),
 	/// Transaction has been replaced in the pool, by another transaction
 	/// that provides the same tags. (e.g. same (sender, nonce)).
 	Usurped(Hash),
@@ -187,6 +206,19 @@ pub trait TransactionPool: Send + Sync {
 
 	// RPC
 
+	/// Returns a future that imports one transaction submitted locally.
+	///
+	/// Unlike transactions received from the network, local transactions are recorded in
+	/// the pool journal, if it is enabled.
+	fn submit_local(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: TransactionFor<Self>,
+	) -> Box<dyn Future<Output=Result<
+		TxHash<Self>,
+		Self::Error
+	>> + Send + Unpin>;
+
 	/// Returns a future that import a single transaction and starts to watch their progress in the pool.
 	fn submit_and_watch(
 		&self,
@@ -280,6 +312,13 @@ pub trait TransactionPoolMaintainer: Send + Sync {
 		id: &BlockId<Self::Block>,
 		retracted: &[Self::Hash],
 	) -> Box<dyn Future<Output=()> + Send + Unpin>;
+
+	/// Returns a future that notifies the pool about finalization of block
+	/// with given id.
+	fn on_block_finalized(
+		&self,
+		id: &BlockId<Self::Block>,
+	) -> Box<dyn Future<Output=()> + Send + Unpin>;
 }
 
 /// Maintainable pool implementation.
@@ -321,6 +360,14 @@ impl<Pool, Maintainer> TransactionPool for MaintainableTransactionPool<Pool, Mai
 		self.pool.submit_one(at, xt)
 	}
 
+	fn submit_local(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: TransactionFor<Self>,
+	) -> Box<dyn Future<Output=Result<TxHash<Self>, Self::Error>> + Send + Unpin> {
+		self.pool.submit_local(at, xt)
+	}
+
 	fn submit_and_watch(
 		&self,
 		at: &BlockId<Self::Block>,
@@ -369,4 +416,11 @@ impl<Pool, Maintainer> TransactionPoolMaintainer for MaintainableTransactionPool
 	) -> Box<dyn Future<Output=()> + Send + Unpin> {
 		self.maintainer.maintain(id, retracted)
 	}
+
+	fn on_block_finalized(
+		&self,
+		id: &BlockId<Self::Block>,
+	) -> Box<dyn Future<Output=()> + Send + Unpin> {
+		self.maintainer.on_block_finalized(id)
+	}
 }
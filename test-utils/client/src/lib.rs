@@ -196,7 +196,8 @@ impl<Executor, Backend, G: GenesisInit> TestClientBuilder<Executor, Backend, G>
 			ExecutionExtensions::new(
 				self.execution_strategies,
 				self.keystore.clone(),
-			)
+			),
+			None,
 		).expect("Creates new client");
 
 		let longest_chain = sc_client::LongestChain::new(self.backend);
@@ -229,7 +230,7 @@ impl<E, Backend, G: GenesisInit> TestClientBuilder<
 		Block: BlockT,
 	{
 		let executor = executor.into().unwrap_or_else(||
-			NativeExecutor::new(WasmExecutionMethod::Interpreted, None)
+			NativeExecutor::new(WasmExecutionMethod::Interpreted, None, 8)
 		);
 		let executor = LocalCallExecutor::new(self.backend.clone(), executor);
 
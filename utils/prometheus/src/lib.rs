@@ -0,0 +1,180 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! [Prometheus] metrics endpoint
+//!
+//! Components of the node register their metrics with a [`Registry`], and this crate exposes an
+//! `init_prometheus` function that starts up a HTTP server answering requests to `/metrics`
+//! with the current state of that registry, in the Prometheus text exposition format.
+//!
+//! [Prometheus]: https://prometheus.io/
+
+#![warn(missing_docs)]
+
+pub use prometheus::{
+	self, Registry, Error as PrometheusError, Opts, Encoder, TextEncoder,
+	Gauge, GaugeVec, IntGauge, IntGaugeVec, IntCounter, IntCounterVec,
+	Histogram, HistogramOpts, HistogramVec, core::Collector,
+};
+use hyper::{Body, Request, Response, StatusCode, header, service::{service_fn, make_service_fn}, Server};
+use std::net::SocketAddr;
+
+#[cfg(not(target_os = "unknown"))]
+mod networking;
+
+/// Register `metric` with `registry`, returning the metric so that it can be kept around and
+/// updated by the caller.
+pub fn register<T: Clone + Collector + 'static>(metric: T, registry: &Registry) -> Result<T, PrometheusError> {
+	registry.register(Box::new(metric.clone()))?;
+	Ok(metric)
+}
+
+/// Error type that can be returned by `init_prometheus`.
+#[derive(Debug, derive_more::Display, derive_more::From)]
+pub enum Error {
+	/// Hyper internal error.
+	Hyper(hyper::Error),
+	/// Http request error.
+	Http(hyper::http::Error),
+	/// Prometheus encoding error.
+	Prometheus(PrometheusError),
+	/// i/o error.
+	Io(std::io::Error)
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Hyper(error) => Some(error),
+			Error::Http(error) => Some(error),
+			Error::Prometheus(error) => Some(error),
+			Error::Io(error) => Some(error)
+		}
+	}
+}
+
+async fn request_metrics(req: Request<Body>, registry: Registry) -> Result<Response<Body>, Error> {
+	if req.uri().path() != "/metrics" {
+		return Response::builder()
+			.status(StatusCode::NOT_FOUND)
+			.body(Body::from("Not found."))
+			.map_err(Error::Http)
+	}
+
+	let metric_families = registry.gather();
+	let mut buffer = vec![];
+	let encoder = TextEncoder::new();
+	encoder.encode(&metric_families, &mut buffer)?;
+
+	Response::builder()
+		.status(StatusCode::OK)
+		.header(header::CONTENT_TYPE, encoder.format_type())
+		.body(Body::from(buffer))
+		.map_err(Error::Http)
+}
+
+/// Given that we're not using hyper's tokio feature, we need to define our own executor.
+#[derive(Clone)]
+pub struct Executor;
+
+#[cfg(not(target_os = "unknown"))]
+impl<T> hyper::rt::Executor<T> for Executor
+	where
+		T: futures_util::future::Future + Send + 'static,
+		T::Output: Send + 'static,
+{
+	fn execute(&self, future: T) {
+		async_std::task::spawn(future);
+	}
+}
+
+/// Start the metrics server, serving the metrics of `registry` on `/metrics`.
+#[cfg(not(target_os = "unknown"))]
+pub async fn init_prometheus(prometheus_addr: SocketAddr, registry: Registry) -> Result<(), Error> {
+	use networking::Incoming;
+
+	let listener = async_std::net::TcpListener::bind(&prometheus_addr).await?;
+	log::info!("Prometheus server started at {}", prometheus_addr);
+
+	let service = make_service_fn(move |_| {
+		let registry = registry.clone();
+
+		async move {
+			Ok::<_, hyper::Error>(service_fn(move |req: Request<Body>| {
+				request_metrics(req, registry.clone())
+			}))
+		}
+	});
+
+	Server::builder(Incoming(listener.incoming()))
+		.executor(Executor)
+		.serve(service)
+		.await
+		.map_err(Into::into)
+}
+
+/// Start the metrics server. This is a no-op on targets without networking support.
+#[cfg(target_os = "unknown")]
+pub async fn init_prometheus(_: SocketAddr, _: Registry) -> Result<(), Error> {
+	Ok(())
+}
+
+#[cfg(all(test, not(target_os = "unknown")))]
+mod tests {
+	use super::*;
+
+	fn get(registry: &Registry, path: &str) -> (StatusCode, String) {
+		let request = Request::get(path).body(Body::empty()).unwrap();
+		async_std::task::block_on(async {
+			let response = request_metrics(request, registry.clone()).await.unwrap();
+			let status = response.status();
+			let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+			(status, String::from_utf8(body.to_vec()).unwrap())
+		})
+	}
+
+	#[test]
+	fn registered_metrics_are_served() {
+		let registry = Registry::new();
+		let gauge = register(IntGauge::new("test_gauge", "A gauge for testing").unwrap(), &registry)
+			.unwrap();
+		gauge.set(42);
+
+		let (status, body) = get(&registry, "/metrics");
+		assert_eq!(status, StatusCode::OK);
+		assert!(body.contains("# HELP test_gauge A gauge for testing"));
+		assert!(body.contains("test_gauge 42"));
+
+		// The registry holds a clone of the metric, so later updates are served as well.
+		gauge.set(7);
+		assert!(get(&registry, "/metrics").1.contains("test_gauge 7"));
+	}
+
+	#[test]
+	fn registering_a_metric_twice_fails() {
+		let registry = Registry::new();
+		let gauge = IntGauge::new("test_gauge", "A gauge for testing").unwrap();
+		assert!(register(gauge.clone(), &registry).is_ok());
+		assert!(register(gauge, &registry).is_err());
+	}
+
+	#[test]
+	fn other_paths_are_not_found() {
+		let (status, body) = get(&Registry::new(), "/");
+		assert_eq!(status, StatusCode::NOT_FOUND);
+		assert_eq!(body, "Not found.");
+	}
+}